@@ -8,7 +8,8 @@
 // # Word Boundaries
 //
 // Word boundaries are defined consistently across the codebase using `is_word_char()`:
-// - **Word characters**: alphanumeric (a-z, A-Z, 0-9) and underscore (_)
+// - **Word characters**: alphanumeric (a-z, A-Z, 0-9) and underscore (_), plus the
+//   language-specific characters of a `WordChars`, like '-' in CSS or '$' in JavaScript
 // - **Non-word characters**: everything else (punctuation, emoji, etc.)
 // - **Whitespace**: spaces and tabs (treated as separate segments)
 // - **Newlines**: always treated as their own segment
@@ -32,6 +33,24 @@ pub enum CursorGoal {
   Column(usize),
 }
 
+/// Characters that are part of words on top of `Cursor::is_word_char`, set per language
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct WordChars {
+  extra: Vec<char>,
+}
+
+impl WordChars {
+  pub fn new(extra: impl IntoIterator<Item = char>) -> Self {
+    Self {
+      extra: extra.into_iter().collect(),
+    }
+  }
+
+  pub fn is_word_char(&self, ch: char) -> bool {
+    Cursor::is_word_char(ch) || self.extra.contains(&ch)
+  }
+}
+
 /// Tracks the desired horizontal position during vertical movement
 #[derive(Default, Copy, Clone, Debug, PartialEq)]
 pub struct Cursor {
@@ -47,6 +66,11 @@ impl Cursor {
     }
   }
 
+  pub fn move_to(&mut self, index: usize) {
+    self.index = index;
+    self.goal = CursorGoal::None;
+  }
+
   pub fn move_left(&mut self) {
     if self.index > 0 {
       self.index -= 1;
@@ -64,6 +88,16 @@ impl Cursor {
   }
 
   pub fn move_up(&mut self, buffer: &TextBuffer) {
+    self.move_up_by(buffer, 1);
+  }
+
+  pub fn move_down(&mut self, buffer: &TextBuffer) {
+    self.move_down_by(buffer, 1);
+  }
+
+  /// Moves up `lines` lines keeping the goal column, stops on the first line, or at its start
+  /// when the cursor already is on it
+  pub fn move_up_by(&mut self, buffer: &TextBuffer, lines: usize) {
     let (line, col) = buffer.char_to_line_col(self.index);
 
     let goal_col = match self.goal {
@@ -72,13 +106,7 @@ impl Cursor {
     };
 
     if line > 0 {
-      let new_line = line - 1;
-      let line_len = buffer
-        .line(new_line)
-        .map(|l| l.trim_end_matches('\n').chars().count())
-        .unwrap_or(0);
-      let new_col = goal_col.min(line_len);
-      self.index = buffer.line_col_to_char(new_line, new_col);
+      self.index = Self::index_at_column(buffer, line.saturating_sub(lines), goal_col);
     } else {
       self.index = 0;
     }
@@ -86,7 +114,9 @@ impl Cursor {
     self.goal = CursorGoal::Column(goal_col);
   }
 
-  pub fn move_down(&mut self, buffer: &TextBuffer) {
+  /// Moves down `lines` lines keeping the goal column, stops on the last line, or at its end
+  /// when the cursor already is on it
+  pub fn move_down_by(&mut self, buffer: &TextBuffer, lines: usize) {
     let (line, col) = buffer.char_to_line_col(self.index);
 
     let goal_col = match self.goal {
@@ -94,14 +124,9 @@ impl Cursor {
       CursorGoal::Column(c) => c,
     };
 
-    if line < buffer.line_count() - 1 {
-      let new_line = line + 1;
-      let line_len = buffer
-        .line(new_line)
-        .map(|l| l.trim_end_matches('\n').chars().count())
-        .unwrap_or(0);
-      let new_col = goal_col.min(line_len);
-      self.index = buffer.line_col_to_char(new_line, new_col);
+    let last_line = buffer.line_count() - 1;
+    if line < last_line {
+      self.index = Self::index_at_column(buffer, (line + lines).min(last_line), goal_col);
     } else {
       self.index = buffer.len();
     }
@@ -109,6 +134,14 @@ impl Cursor {
     self.goal = CursorGoal::Column(goal_col);
   }
 
+  fn index_at_column(buffer: &TextBuffer, line: usize, goal_col: usize) -> usize {
+    let line_len = buffer
+      .line(line)
+      .map(|l| l.trim_end_matches('\n').chars().count())
+      .unwrap_or(0);
+    buffer.line_col_to_char(line, goal_col.min(line_len))
+  }
+
   pub fn move_to_line_start(&mut self, buffer: &TextBuffer) {
     self.goal = CursorGoal::None;
     let (line, _col) = buffer.char_to_line_col(self.index);
@@ -138,6 +171,29 @@ impl Cursor {
   /// Move to previous word boundary (stop at each transition)
   /// Does not move across line boundaries unless at the start of a line
   pub fn move_word_left(&mut self, buffer: &TextBuffer) {
+    self.move_word_left_with(buffer, &WordChars::default());
+  }
+
+  /// Like `move_word_left`, with the word characters of the language
+  pub fn move_word_left_with(&mut self, buffer: &TextBuffer, word_chars: &WordChars) {
+    self.move_segment_left(buffer, |index| {
+      Self::find_word_boundaries_with(buffer, index, word_chars)
+    });
+  }
+
+  /// Like `move_word_left`, also stopping at camelCase humps and underscores inside identifiers
+  pub fn move_subword_left_with(&mut self, buffer: &TextBuffer, word_chars: &WordChars) {
+    self.move_segment_left(buffer, |index| {
+      Self::find_subword_boundaries_with(buffer, index, word_chars)
+    });
+  }
+
+  /// Moves to the start of the segment left of the cursor, `boundaries` giving the segment at an index
+  fn move_segment_left(
+    &mut self,
+    buffer: &TextBuffer,
+    boundaries: impl Fn(usize) -> (usize, usize),
+  ) {
     self.goal = CursorGoal::None;
     if self.index == 0 {
       return;
@@ -160,7 +216,7 @@ impl Cursor {
     let line_start = buffer.line_col_to_char(current_line, 0);
 
     // Find the word boundaries at the position to the left
-    let (start, _end) = Self::find_word_boundaries(buffer, self.index - 1);
+    let (start, _end) = boundaries(self.index - 1);
 
     // If we're not at the start of a line (col > 0), don't cross line boundaries
     let new_index = if current_col > 0 {
@@ -190,6 +246,15 @@ impl Cursor {
   /// // "hello 🌍 world" at position 6 returns (6, 7) for "🌍"
   /// // "hello 🌍 world" at position 5 returns (5, 6) for " " (space before emoji)
   pub fn find_word_boundaries(buffer: &TextBuffer, position: usize) -> (usize, usize) {
+    Self::find_word_boundaries_with(buffer, position, &WordChars::default())
+  }
+
+  /// Like `find_word_boundaries`, with the word characters of the language
+  pub fn find_word_boundaries_with(
+    buffer: &TextBuffer,
+    position: usize,
+    word_chars: &WordChars,
+  ) -> (usize, usize) {
     let text = buffer.as_str();
     let chars: Vec<char> = text.chars().collect();
     let clamped_pos = position.min(chars.len());
@@ -231,7 +296,7 @@ impl Cursor {
       return (start, end);
     }
 
-    let current_is_word = Self::is_word_char(current_char);
+    let current_is_word = word_chars.is_word_char(current_char);
 
     // Find start of word (scan backwards)
     let mut start = start_pos;
@@ -241,7 +306,7 @@ impl Cursor {
       if ch == '\n' || ch.is_whitespace() {
         break;
       }
-      let is_word = Self::is_word_char(ch);
+      let is_word = word_chars.is_word_char(ch);
       if is_word != current_is_word {
         break;
       }
@@ -256,7 +321,7 @@ impl Cursor {
       if ch == '\n' || ch.is_whitespace() {
         break;
       }
-      let is_word = Self::is_word_char(ch);
+      let is_word = word_chars.is_word_char(ch);
       if is_word != current_is_word {
         break;
       }
@@ -269,6 +334,29 @@ impl Cursor {
   /// Move to next word boundary (stop at each transition)
   /// Does not move across line boundaries
   pub fn move_word_right(&mut self, buffer: &TextBuffer) {
+    self.move_word_right_with(buffer, &WordChars::default());
+  }
+
+  /// Like `move_word_right`, with the word characters of the language
+  pub fn move_word_right_with(&mut self, buffer: &TextBuffer, word_chars: &WordChars) {
+    self.move_segment_right(buffer, |index| {
+      Self::find_word_boundaries_with(buffer, index, word_chars)
+    });
+  }
+
+  /// Like `move_word_right`, also stopping at camelCase humps and underscores inside identifiers
+  pub fn move_subword_right_with(&mut self, buffer: &TextBuffer, word_chars: &WordChars) {
+    self.move_segment_right(buffer, |index| {
+      Self::find_subword_boundaries_with(buffer, index, word_chars)
+    });
+  }
+
+  /// Moves to the end of the segment at the cursor, `boundaries` giving the segment at an index
+  fn move_segment_right(
+    &mut self,
+    buffer: &TextBuffer,
+    boundaries: impl Fn(usize) -> (usize, usize),
+  ) {
     self.goal = CursorGoal::None;
     let text = buffer.as_str();
     let chars: Vec<char> = text.chars().collect();
@@ -287,7 +375,7 @@ impl Cursor {
     };
 
     // Find the word boundaries at the current position
-    let (_start, end) = Self::find_word_boundaries(buffer, self.index);
+    let (_start, end) = boundaries(self.index);
 
     // Don't cross line boundaries
     let new_index = end.min(line_end_index);
@@ -295,6 +383,34 @@ impl Cursor {
     self.index = new_index;
   }
 
+  /// Like `find_word_boundaries_with`, words are split into their camelCase humps, digit runs
+  /// and parts between underscores
+  ///
+  /// // "parseHTTPRequest" at position 7 returns (5, 9) for "HTTP"
+  /// // "max_len" at position 5 returns (3, 7) for "_len", underscores start the part after them
+  pub fn find_subword_boundaries_with(
+    buffer: &TextBuffer,
+    position: usize,
+    word_chars: &WordChars,
+  ) -> (usize, usize) {
+    let (start, end) = Self::find_word_boundaries_with(buffer, position, word_chars);
+    let word: Vec<char> = buffer
+      .as_str()
+      .chars()
+      .skip(start)
+      .take(end - start)
+      .collect();
+    if !word.first().is_some_and(|c| word_chars.is_word_char(*c)) {
+      return (start, end);
+    }
+
+    let offset = position.clamp(start, end - 1) - start;
+    let starts = subword_starts(&word);
+    let part = starts.partition_point(|start| *start <= offset) - 1;
+    let part_end = starts.get(part + 1).copied().unwrap_or(word.len());
+    (start + starts[part], start + part_end)
+  }
+
   /// Determines if a character is a word character.
   ///
   /// Word characters: alphanumeric (a-z, A-Z, 0-9) and underscore (_)
@@ -306,6 +422,52 @@ impl Cursor {
   }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SubwordClass {
+  Upper,
+  Lower, // Letters without case too
+  Digit,
+  Separator, // Underscores and the other word characters of the language
+}
+
+impl SubwordClass {
+  fn of(ch: char) -> Self {
+    if ch.is_uppercase() {
+      Self::Upper
+    } else if ch.is_alphabetic() {
+      Self::Lower
+    } else if ch.is_numeric() {
+      Self::Digit
+    } else {
+      Self::Separator
+    }
+  }
+}
+
+/// Offsets in `word` where its subwords start, the first one included
+fn subword_starts(word: &[char]) -> Vec<usize> {
+  let class = |index: usize| SubwordClass::of(word[index]);
+  let mut starts = vec![0];
+  for index in 1..word.len() {
+    let (prev, current) = (class(index - 1), class(index));
+    let starts_part = match (prev, current) {
+      // Separators go with the part after them
+      (SubwordClass::Separator, _) => false,
+      (_, SubwordClass::Separator) => true,
+      (SubwordClass::Upper, SubwordClass::Lower) => false,
+      // The last capital of an acronym starts the next hump, like "S" in "HTTPServer"
+      (SubwordClass::Upper, SubwordClass::Upper) => {
+        index + 1 < word.len() && class(index + 1) == SubwordClass::Lower
+      }
+      (prev, current) => prev != current,
+    };
+    if starts_part {
+      starts.push(index);
+    }
+  }
+  starts
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -450,6 +612,31 @@ mod tests {
     assert_eq!(cursor.index, 2); // Back to column 2 in first line
   }
 
+  #[test]
+  fn test_move_by_lines() {
+    let mut buffer = TextBuffer::new();
+    buffer.insert(0, "AAAA\nBB\nCCCC\nDDDD\nEEEE");
+
+    let mut cursor = Cursor::new();
+    cursor.index = 3; // Column 3 in first line
+
+    cursor.move_down_by(&buffer, 3);
+    assert_eq!(cursor.index, 16); // Column 3 in fourth line
+
+    cursor.move_down_by(&buffer, 10);
+    assert_eq!(cursor.index, 21); // Clamped to the last line
+    cursor.move_down_by(&buffer, 10);
+    assert_eq!(cursor.index, buffer.len());
+
+    cursor.index = 16;
+    cursor.move_up_by(&buffer, 2);
+    assert_eq!(cursor.index, 7); // End of "BB", column 3 is kept as the goal
+    cursor.move_up_by(&buffer, 1);
+    assert_eq!(cursor.index, 3);
+    cursor.move_up_by(&buffer, 1);
+    assert_eq!(cursor.index, 0);
+  }
+
   #[test]
   fn test_move_to_line_start() {
     let mut buffer = TextBuffer::new();
@@ -918,6 +1105,20 @@ mod tests {
     assert_eq!(cursor.index, 5); // Start of line 2 (after newline)
   }
 
+  #[test]
+  fn test_move_to_resets_goal() {
+    let mut buffer = TextBuffer::new();
+    buffer.insert(0, "hello world\nhi\nhello world");
+    let mut cursor = Cursor::new();
+    cursor.index = 8;
+    cursor.move_down(&buffer);
+    assert_eq!(cursor.goal, CursorGoal::Column(8));
+
+    cursor.move_to(3);
+    assert_eq!(cursor.index, 3);
+    assert_eq!(cursor.goal, CursorGoal::None);
+  }
+
   #[test]
   fn test_cursor_goal_preserves_column() {
     let mut buffer = TextBuffer::new();
@@ -1167,6 +1368,69 @@ mod tests {
     assert!(!Cursor::is_word_char('🗿'));
   }
 
+  #[test]
+  fn test_word_chars() {
+    let mut buffer = TextBuffer::new();
+    buffer.insert(0, "margin-top: $gap");
+    let css = WordChars::new(['-']);
+    let js = WordChars::new(['$']);
+
+    assert_eq!(Cursor::find_word_boundaries(&buffer, 2), (0, 6));
+    assert_eq!(Cursor::find_word_boundaries_with(&buffer, 2, &css), (0, 10));
+    assert_eq!(Cursor::find_word_boundaries(&buffer, 12), (12, 13));
+    assert_eq!(
+      Cursor::find_word_boundaries_with(&buffer, 12, &js),
+      (12, 16)
+    );
+
+    let mut cursor = Cursor::new();
+    cursor.move_word_right_with(&buffer, &css);
+    assert_eq!(cursor.index, 10);
+    cursor.index = 16;
+    cursor.move_word_left_with(&buffer, &js);
+    assert_eq!(cursor.index, 12);
+  }
+
+  #[test]
+  fn test_subword_starts() {
+    let starts = |word: &str| subword_starts(&word.chars().collect::<Vec<_>>());
+    assert_eq!(starts("parseHTTPRequest"), [0, 5, 9]);
+    assert_eq!(starts("max_len"), [0, 3]);
+    assert_eq!(starts("_private"), [0]);
+    assert_eq!(starts("utf8Decoder"), [0, 3, 4]);
+    assert_eq!(starts("SCREAMING_CASE"), [0, 9]);
+  }
+
+  #[test]
+  fn test_subword_motion() {
+    let mut buffer = TextBuffer::new();
+    buffer.insert(0, "let parseHTTPRequest = max_len;");
+    let word_chars = WordChars::default();
+
+    assert_eq!(
+      Cursor::find_subword_boundaries_with(&buffer, 11, &word_chars),
+      (9, 13)
+    );
+    // Spaces and punctuation segment like whole words
+    assert_eq!(
+      Cursor::find_subword_boundaries_with(&buffer, 3, &word_chars),
+      (3, 4)
+    );
+
+    let mut cursor = Cursor::new();
+    cursor.index = 4;
+    cursor.move_subword_right_with(&buffer, &word_chars);
+    assert_eq!(cursor.index, 9);
+    cursor.move_subword_right_with(&buffer, &word_chars);
+    assert_eq!(cursor.index, 13);
+
+    cursor.index = 30; // After "max_len"
+    cursor.move_subword_left_with(&buffer, &word_chars);
+    assert_eq!(cursor.index, 26);
+    cursor.move_subword_left_with(&buffer, &word_chars);
+    assert_eq!(cursor.index, 23);
+  }
+
   #[test]
   fn test_move_to_line_end_with_emoji() {
     // Test case from bug report: emojis on line should move to end of line, not next line
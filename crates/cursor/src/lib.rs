@@ -2,3 +2,4 @@ mod cursor;
 
 pub use cursor::Cursor;
 pub use cursor::CursorGoal;
+pub use cursor::WordChars;
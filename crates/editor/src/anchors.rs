@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+/// Side an anchor sticks to when text is inserted right at its position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Bias {
+  #[default]
+  Left, // Stays before the inserted text
+  Right, // Moves past it, like a cursor
+}
+
+/// Handle to a char position that follows the text it was placed on through edits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Anchor(usize);
+
+/// Positions kept in sync with the edits of a buffer
+///
+/// A deletion around an anchor moves it to the start of the deleted text
+#[derive(Debug, Clone, Default)]
+pub struct AnchorSet {
+  anchors: HashMap<Anchor, (usize, Bias)>,
+  next_id: usize,
+}
+
+impl AnchorSet {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn create(&mut self, index: usize, bias: Bias) -> Anchor {
+    let anchor = Anchor(self.next_id);
+    self.next_id += 1;
+    self.anchors.insert(anchor, (index, bias));
+    anchor
+  }
+
+  /// Char index of an anchor, None once it's removed
+  pub fn position(&self, anchor: Anchor) -> Option<usize> {
+    self.anchors.get(&anchor).map(|(index, _)| *index)
+  }
+
+  pub fn remove(&mut self, anchor: Anchor) -> bool {
+    self.anchors.remove(&anchor).is_some()
+  }
+
+  pub fn clear(&mut self) {
+    self.anchors.clear();
+  }
+
+  pub fn len(&self) -> usize {
+    self.anchors.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.anchors.is_empty()
+  }
+
+  /// Shifts the anchors after `index` by `len` inserted chars
+  pub fn insert(&mut self, index: usize, len: usize) {
+    for (position, bias) in self.anchors.values_mut() {
+      if *position > index || (*position == index && *bias == Bias::Right) {
+        *position += len;
+      }
+    }
+  }
+
+  /// Moves the anchors back by the `len` chars deleted at `index`
+  pub fn delete(&mut self, index: usize, len: usize) {
+    for (position, _) in self.anchors.values_mut() {
+      if *position >= index + len {
+        *position -= len;
+      } else if *position > index {
+        *position = index;
+      }
+    }
+  }
+
+  /// Replaces every position, for changes that aren't a single insertion or deletion
+  pub fn remap(&mut self, mut map: impl FnMut(usize) -> usize) {
+    for (position, _) in self.anchors.values_mut() {
+      *position = map(*position);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_insert_shifts_by_bias() {
+    let mut anchors = AnchorSet::new();
+    let before = anchors.create(2, Bias::Left);
+    let after = anchors.create(2, Bias::Right);
+    let later = anchors.create(5, Bias::Left);
+
+    anchors.insert(2, 3);
+    assert_eq!(anchors.position(before), Some(2));
+    assert_eq!(anchors.position(after), Some(5));
+    assert_eq!(anchors.position(later), Some(8));
+
+    anchors.insert(9, 1);
+    assert_eq!(anchors.position(later), Some(8));
+  }
+
+  #[test]
+  fn test_delete_collapses_anchors_inside() {
+    let mut anchors = AnchorSet::new();
+    let start = anchors.create(2, Bias::Left);
+    let inside = anchors.create(4, Bias::Right);
+    let end = anchors.create(6, Bias::Left);
+    let later = anchors.create(10, Bias::Left);
+
+    anchors.delete(2, 4);
+    assert_eq!(anchors.position(start), Some(2));
+    assert_eq!(anchors.position(inside), Some(2));
+    assert_eq!(anchors.position(end), Some(2));
+    assert_eq!(anchors.position(later), Some(6));
+  }
+
+  #[test]
+  fn test_remove() {
+    let mut anchors = AnchorSet::new();
+    let anchor = anchors.create(1, Bias::Left);
+    assert!(anchors.remove(anchor));
+    assert!(!anchors.remove(anchor));
+    assert_eq!(anchors.position(anchor), None);
+    assert!(anchors.is_empty());
+  }
+}
@@ -0,0 +1,299 @@
+use std::ops::Range;
+
+/// What a row of the view shows of the buffer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowContent {
+  Line(usize),          // Buffer line (0-based) shown on the row
+  Folded(Range<usize>), // Buffer lines hidden behind the row, like a collapsed region
+  Virtual,              // Row without buffer text, like a removed line or a comment
+}
+
+/// Where a display row sits in the buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowPosition {
+  Line(usize),   // Buffer line shown on the row
+  Before(usize), // Row without buffer text, like a removed line, shown right before this buffer line
+}
+
+/// Mapping between display rows and buffer lines, both ways
+///
+/// Built from the rows the view draws once they're composed, so that hit-testing, scrolling
+/// and cursor motions all agree on which row shows which line
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DisplayMap {
+  rows: Vec<RowContent>,
+  line_rows: Vec<Option<usize>>, // Row showing each buffer line, None when it's hidden
+  positions: Vec<RowPosition>,
+}
+
+impl DisplayMap {
+  /// Map of `rows` over a buffer of `line_count` lines, rows after the last line sit before
+  /// `line_count`
+  pub fn new(rows: Vec<RowContent>, line_count: usize) -> Self {
+    let mut line_rows = vec![None; line_count];
+    for (row, content) in rows.iter().enumerate() {
+      if let RowContent::Line(line) = *content {
+        if line >= line_rows.len() {
+          line_rows.resize(line + 1, None);
+        }
+        // A line shown twice maps to its first row
+        line_rows[line].get_or_insert(row);
+      }
+    }
+
+    // Rows without a line sit before the next row that has one, found walking backwards
+    let mut next_line = line_rows.len();
+    let mut positions = vec![RowPosition::Before(next_line); rows.len()];
+    for (row, content) in rows.iter().enumerate().rev() {
+      positions[row] = match content {
+        RowContent::Line(line) => {
+          next_line = *line;
+          RowPosition::Line(*line)
+        }
+        RowContent::Folded(lines) => {
+          next_line = lines.start;
+          RowPosition::Before(lines.start)
+        }
+        RowContent::Virtual => RowPosition::Before(next_line),
+      };
+    }
+
+    Self {
+      rows,
+      line_rows,
+      positions,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.rows.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.rows.is_empty()
+  }
+
+  pub fn row(&self, row: usize) -> Option<&RowContent> {
+    self.rows.get(row)
+  }
+
+  /// Row showing buffer line `line`, None when it's hidden
+  pub fn line_row(&self, line: usize) -> Option<usize> {
+    self.line_rows.get(line).copied().flatten()
+  }
+
+  /// Row showing buffer line `line`, or the row hiding it
+  pub fn row_containing_line(&self, line: usize) -> Option<usize> {
+    self.line_row(line).or_else(|| {
+      self
+        .rows
+        .iter()
+        .position(|content| matches!(content, RowContent::Folded(lines) if lines.contains(&line)))
+    })
+  }
+
+  /// Buffer line shown on `row`, None for rows without buffer text
+  pub fn row_line(&self, row: usize) -> Option<usize> {
+    match self.rows.get(row)? {
+      RowContent::Line(line) => Some(*line),
+      RowContent::Folded(_) | RowContent::Virtual => None,
+    }
+  }
+
+  /// Position of `row` in the buffer, rows past the end sit before the end of the buffer
+  pub fn row_position(&self, row: usize) -> RowPosition {
+    self
+      .positions
+      .get(row)
+      .copied()
+      .unwrap_or(RowPosition::Before(self.line_rows.len()))
+  }
+
+  /// Buffer lines shown by `rows`, from the first to the last, None when they show none
+  pub fn buffer_lines(&self, rows: Range<usize>) -> Option<Range<usize>> {
+    let rows = &self.rows[rows.start.min(self.len())..rows.end.min(self.len())];
+    let mut lines = rows.iter().filter_map(|content| match content {
+      RowContent::Line(line) => Some(*line),
+      RowContent::Folded(_) | RowContent::Virtual => None,
+    });
+    let first = lines.next()?;
+    let last = lines.last().unwrap_or(first);
+    Some(first..last + 1)
+  }
+
+  /// First row in `rows` showing a buffer line, in the order they're given, with the line
+  pub fn first_line_in(&self, rows: impl IntoIterator<Item = usize>) -> Option<(usize, usize)> {
+    rows
+      .into_iter()
+      .find_map(|row| Some((row, self.row_line(row)?)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // "l" for a line, numbered in order, "v" for a virtual row and a digit for a region
+  // folding that many lines
+  fn map(rows: &str) -> DisplayMap {
+    let mut line = 0;
+    let rows: Vec<RowContent> = rows
+      .chars()
+      .map(|c| match c {
+        'l' => {
+          line += 1;
+          RowContent::Line(line - 1)
+        }
+        'v' => RowContent::Virtual,
+        c => {
+          let count = c.to_digit(10).unwrap() as usize;
+          line += count;
+          RowContent::Folded(line - count..line)
+        }
+      })
+      .collect();
+    DisplayMap::new(rows, line)
+  }
+
+  #[test]
+  fn test_empty() {
+    let map = DisplayMap::new(Vec::new(), 0);
+    assert!(map.is_empty());
+    assert_eq!(map.line_row(0), None);
+    assert_eq!(map.row_line(0), None);
+    assert_eq!(map.row_position(0), RowPosition::Before(0));
+    assert_eq!(map.buffer_lines(0..1), None);
+
+    // Lines hidden by every row
+    let map = DisplayMap::new(Vec::new(), 3);
+    assert_eq!(map.line_row(2), None);
+    assert_eq!(map.row_position(0), RowPosition::Before(3));
+  }
+
+  #[test]
+  fn test_line_rows_round_trip() {
+    let map = map("lvvlv3ll");
+    assert_eq!(map.len(), 8);
+    for line in 0..map.line_rows.len() {
+      if let Some(row) = map.line_row(line) {
+        assert_eq!(map.row_line(row), Some(line));
+      }
+    }
+    for row in 0..map.len() {
+      if let Some(line) = map.row_line(row) {
+        assert_eq!(map.line_row(line), Some(row));
+      }
+    }
+  }
+
+  #[test]
+  fn test_line_row() {
+    let map = map("lvvlv3ll");
+    assert_eq!(map.line_row(0), Some(0));
+    assert_eq!(map.line_row(1), Some(3));
+    assert_eq!(map.line_row(2), None); // Folded
+    assert_eq!(map.line_row(4), None);
+    assert_eq!(map.line_row(5), Some(6));
+    assert_eq!(map.line_row(6), Some(7));
+    assert_eq!(map.line_row(7), None); // Past the end
+  }
+
+  #[test]
+  fn test_row_containing_line() {
+    let map = map("lvvlv3ll");
+    assert_eq!(map.row_containing_line(1), Some(3));
+    assert_eq!(map.row_containing_line(2), Some(5));
+    assert_eq!(map.row_containing_line(4), Some(5));
+    assert_eq!(map.row_containing_line(7), None);
+  }
+
+  #[test]
+  fn test_row_line() {
+    let map = map("lvvlv3ll");
+    let lines: Vec<Option<usize>> = (0..=map.len()).map(|row| map.row_line(row)).collect();
+    assert_eq!(
+      lines,
+      [
+        Some(0),
+        None,
+        None,
+        Some(1),
+        None,
+        None,
+        Some(5),
+        Some(6),
+        None
+      ]
+    );
+  }
+
+  #[test]
+  fn test_row_position() {
+    let map = map("lvvlv3llv");
+    let positions: Vec<RowPosition> = (0..=map.len()).map(|row| map.row_position(row)).collect();
+    assert_eq!(
+      positions,
+      [
+        RowPosition::Line(0),
+        RowPosition::Before(1), // Removed rows sit before the next line
+        RowPosition::Before(1),
+        RowPosition::Line(1),
+        RowPosition::Before(2), // Or before the region after them
+        RowPosition::Before(2),
+        RowPosition::Line(5),
+        RowPosition::Line(6),
+        RowPosition::Before(7), // Rows after the last line sit before the end
+        RowPosition::Before(7),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_rows_before_first_line() {
+    let map = map("vvl");
+    assert_eq!(map.row_position(0), RowPosition::Before(0));
+    assert_eq!(map.row_position(1), RowPosition::Before(0));
+    assert_eq!(map.line_row(0), Some(2));
+  }
+
+  #[test]
+  fn test_line_shown_twice() {
+    let rows = vec![
+      RowContent::Line(0),
+      RowContent::Line(1),
+      RowContent::Line(0),
+    ];
+    let map = DisplayMap::new(rows, 2);
+    assert_eq!(map.line_row(0), Some(0));
+    assert_eq!(map.row_line(2), Some(0));
+  }
+
+  #[test]
+  fn test_line_past_line_count() {
+    let map = DisplayMap::new(vec![RowContent::Line(4)], 2);
+    assert_eq!(map.line_row(4), Some(0));
+    assert_eq!(map.row_position(1), RowPosition::Before(5));
+  }
+
+  #[test]
+  fn test_buffer_lines() {
+    let map = map("lvvlv3ll");
+    assert_eq!(map.buffer_lines(0..map.len()), Some(0..7));
+    assert_eq!(map.buffer_lines(1..3), None); // Virtual rows only
+    assert_eq!(map.buffer_lines(4..6), None); // And regions
+    assert_eq!(map.buffer_lines(1..4), Some(1..2));
+    assert_eq!(map.buffer_lines(3..7), Some(1..6));
+    assert_eq!(map.buffer_lines(6..20), Some(5..7)); // Clamped to the rows
+    assert_eq!(map.buffer_lines(20..30), None);
+  }
+
+  #[test]
+  fn test_first_line_in() {
+    let map = map("lvvlv3ll");
+    assert_eq!(map.first_line_in(1..3), None);
+    assert_eq!(map.first_line_in(1..5), Some((3, 1)));
+    assert_eq!(map.first_line_in((0..5).rev()), Some((3, 1)));
+    assert_eq!(map.first_line_in((4..=6).rev()), Some((6, 5)));
+    assert_eq!(map.first_line_in(8..10), None);
+  }
+}
@@ -0,0 +1,152 @@
+use std::ops::Range;
+use std::time::SystemTime;
+
+/// What made a logged change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditCause {
+  Edit,
+  Undo,
+  Redo,
+}
+
+/// A change of the buffer, chars of `range` replaced with `inserted`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditEvent {
+  pub time: SystemTime,
+  pub transaction: usize, // Changes made by the same command share it, like the undo step they make
+  pub cause: EditCause,
+  pub range: Range<usize>, // Char range in the buffer as it was before the change
+  pub removed: String,
+  pub inserted: String,
+}
+
+/// Every change made to the buffer while recording, in order, to replay a session or
+/// find out how the buffer got to a state
+///
+/// Changes are recorded by the same edit primitives and transactions the undo history uses,
+/// undo and redo are logged as the changes they make
+#[derive(Debug, Clone, Default)]
+pub struct EditLog {
+  events: Vec<EditEvent>,
+  transaction: usize,
+}
+
+impl EditLog {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn events(&self) -> &[EditEvent] {
+    &self.events
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.events.is_empty()
+  }
+
+  pub fn clear(&mut self) {
+    self.events.clear();
+  }
+
+  /// `base` with every logged change applied, the buffer text when `base` is the text the log
+  /// started from
+  pub fn replay(&self, base: &str) -> String {
+    let mut chars: Vec<char> = base.chars().collect();
+    for event in &self.events {
+      let end = event.range.end.min(chars.len());
+      let start = event.range.start.min(end);
+      chars.splice(start..end, event.inserted.chars());
+    }
+    chars.into_iter().collect()
+  }
+
+  /// Starts the transaction the next changes belong to
+  pub(crate) fn begin_transaction(&mut self) {
+    self.transaction += 1;
+  }
+
+  pub(crate) fn record(
+    &mut self,
+    cause: EditCause,
+    range: Range<usize>,
+    removed: String,
+    inserted: String,
+  ) {
+    // Text inserted char by char, like a paste, joins the change it follows
+    if let Some(last) = self.events.last_mut()
+      && last.transaction == self.transaction
+      && last.cause == cause
+      && range.is_empty()
+      && range.start == last.range.start + last.inserted.chars().count()
+    {
+      last.inserted.push_str(&inserted);
+      return;
+    }
+
+    self.events.push(EditEvent {
+      time: SystemTime::now(),
+      transaction: self.transaction,
+      cause,
+      range,
+      removed,
+      inserted,
+    });
+  }
+
+  /// Records the change from `old` to `new` as the replacement of the chars between their
+  /// common start and end
+  pub(crate) fn record_replacement(&mut self, cause: EditCause, old: &str, new: &str) {
+    let old: Vec<char> = old.chars().collect();
+    let new: Vec<char> = new.chars().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+      .iter()
+      .rev()
+      .zip(new[prefix..].iter().rev())
+      .take_while(|(a, b)| a == b)
+      .count();
+    if prefix == old.len() && prefix == new.len() {
+      return;
+    }
+
+    self.record(
+      cause,
+      prefix..old.len() - suffix,
+      old[prefix..old.len() - suffix].iter().collect(),
+      new[prefix..new.len() - suffix].iter().collect(),
+    );
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_record_replacement() {
+    let mut log = EditLog::new();
+    log.record_replacement(EditCause::Undo, "let a = 1;", "let abc = 1;");
+    log.record_replacement(EditCause::Redo, "same", "same");
+
+    let [event] = log.events() else {
+      panic!("expected a single event");
+    };
+    assert_eq!(event.cause, EditCause::Undo);
+    assert_eq!(event.range, 5..5);
+    assert_eq!(event.removed, "");
+    assert_eq!(event.inserted, "bc");
+  }
+
+  #[test]
+  fn test_replay() {
+    let mut log = EditLog::new();
+    log.record(EditCause::Edit, 0..0, String::new(), "hello ".to_string());
+    log.record(
+      EditCause::Edit,
+      6..11,
+      "world".to_string(),
+      "there".to_string(),
+    );
+    assert_eq!(log.replay("world"), "hello there");
+  }
+}
@@ -1,6 +1,12 @@
-use cursor::Cursor;
+use crate::anchors::{Anchor, AnchorSet, Bias};
+use crate::edit_log::{EditCause, EditLog};
+use crate::edits::{Edit, EditError, map_index, sort_edits};
+use crate::history::{History, Snapshot};
+use crate::occurrences::OccurrenceQuery;
+use crate::scopes::enclosing_scope;
+use cursor::{Cursor, WordChars};
 use std::ops::Range;
-use text::TextBuffer;
+use text::{LineEnding, TextBuffer};
 
 /// Represents a text selection with start and end positions
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -41,11 +47,43 @@ impl Selection {
   }
 }
 
+/// Unit a mouse drag extends the selection by, set by the click that started it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SelectionGranularity {
+  #[default]
+  Char,
+  Word, // After a double click
+  Line, // After a triple click
+}
+
+/// Text copied or cut from the editor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardText {
+  pub text: String,
+  pub whole_line: bool, // Copied without a selection, pasting it inserts a line above the cursor
+}
+
+/// Lines touched by a buffer edit, used to invalidate per-line caches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineEdit {
+  pub line_start: usize, // First line touched by the edit
+  pub line_end: usize,   // Last line touched, numbered as before the edit
+  pub line_delta: isize, // Number of lines added (positive) or removed (negative)
+}
+
 #[derive(Default)]
 pub struct Editor {
   pub buffer: TextBuffer,
   pub cursor: Cursor,
   pub selection: Option<Selection>,
+  pub word_chars: WordChars, // Characters of the language that join words, for word motions and selection
+  pub subwords: bool, // Word motions and deletions stop at camelCase humps and underscores inside identifiers
+  line_edits: Vec<LineEdit>,
+  history: History,
+  anchors: AnchorSet,
+  expansions: Vec<Selection>, // Selections before each expansion, the cursor as an empty one
+  expanded: Option<Selection>, // Last selection expanded to, shrinking only follows it
+  edit_log: Option<EditLog>,  // Changes recorded since `start_edit_log`
 }
 
 impl Editor {
@@ -54,9 +92,227 @@ impl Editor {
       buffer: TextBuffer::new(),
       cursor: Cursor::new(),
       selection: None,
+      word_chars: WordChars::default(),
+      subwords: false,
+      line_edits: Vec::new(),
+      history: History::default(),
+      anchors: AnchorSet::new(),
+      expansions: Vec::new(),
+      expanded: None,
+      edit_log: None,
+    }
+  }
+
+  pub fn from_buffer(buffer: TextBuffer) -> Self {
+    Self {
+      buffer,
+      ..Self::new()
+    }
+  }
+
+  /// Returns the line edits made since the last call, oldest first
+  pub fn take_line_edits(&mut self) -> Vec<LineEdit> {
+    std::mem::take(&mut self.line_edits)
+  }
+
+  /// Runs `edit` as a single undo step, transactions opened inside it join this one
+  pub fn transact<R>(&mut self, edit: impl FnOnce(&mut Self) -> R) -> R {
+    self.transact_with(false, edit)
+  }
+
+  /// Like `transact`, a `typed` edit joins the previous step when it continues the same run of typing
+  fn transact_with<R>(&mut self, typed: bool, edit: impl FnOnce(&mut Self) -> R) -> R {
+    let snapshot = (!self.history.is_open()).then(|| self.snapshot());
+    if snapshot.is_some()
+      && let Some(log) = &mut self.edit_log
+    {
+      log.begin_transaction();
+    }
+    self.history.begin(snapshot);
+    let result = edit(self);
+    self.history.end(typed, self.cursor.index);
+    result
+  }
+
+  /// Reverts the last undo step, returns false when there is nothing to undo
+  pub fn undo(&mut self) -> bool {
+    let current = self.snapshot();
+    match self.history.undo(current) {
+      Some(snapshot) => {
+        self.restore(snapshot, EditCause::Undo);
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Applies the last undone step again, returns false when there is nothing to redo
+  pub fn redo(&mut self) -> bool {
+    let current = self.snapshot();
+    match self.history.redo(current) {
+      Some(snapshot) => {
+        self.restore(snapshot, EditCause::Redo);
+        true
+      }
+      None => false,
+    }
+  }
+
+  pub fn can_undo(&self) -> bool {
+    self.history.can_undo()
+  }
+
+  pub fn can_redo(&self) -> bool {
+    self.history.can_redo()
+  }
+
+  /// Forgets every undo and redo step, like after loading another text in the buffer
+  pub fn clear_history(&mut self) {
+    self.history.clear();
+  }
+
+  /// Starts recording the changes made to the buffer, keeps the current log when already recording
+  pub fn start_edit_log(&mut self) {
+    self.edit_log.get_or_insert_with(EditLog::new);
+  }
+
+  /// Stops recording, returns the changes recorded
+  pub fn stop_edit_log(&mut self) -> Option<EditLog> {
+    self.edit_log.take()
+  }
+
+  pub fn edit_log(&self) -> Option<&EditLog> {
+    self.edit_log.as_ref()
+  }
+
+  /// Places an anchor at a char index, it follows the text around it through edits
+  pub fn create_anchor(&mut self, index: usize, bias: Bias) -> Anchor {
+    self.anchors.create(index.min(self.buffer.len()), bias)
+  }
+
+  pub fn anchor_position(&self, anchor: Anchor) -> Option<usize> {
+    self
+      .anchors
+      .position(anchor)
+      .map(|index| index.min(self.buffer.len()))
+  }
+
+  pub fn remove_anchor(&mut self, anchor: Anchor) -> bool {
+    self.anchors.remove(anchor)
+  }
+
+  /// For changes made to `buffer` directly, like swapping in the content read from disk
+  pub fn anchors_mut(&mut self) -> &mut AnchorSet {
+    &mut self.anchors
+  }
+
+  fn snapshot(&self) -> Snapshot {
+    Snapshot {
+      buffer: self.buffer.clone(),
+      cursor_index: self.cursor.index,
+      selection: self.selection,
     }
   }
 
+  /// Puts a snapshot back and records the lines that differ from the current buffer
+  fn restore(&mut self, snapshot: Snapshot, cause: EditCause) {
+    let old_count = self.buffer.line_count();
+    let new_count = snapshot.buffer.line_count();
+    let common = old_count.min(new_count);
+    let prefix = (0..common)
+      .take_while(|&line| self.buffer.line(line) == snapshot.buffer.line(line))
+      .count();
+    let suffix = (0..common - prefix)
+      .take_while(|&offset| {
+        self.buffer.line(old_count - 1 - offset) == snapshot.buffer.line(new_count - 1 - offset)
+      })
+      .count();
+
+    if prefix < common || old_count != new_count {
+      let old_end = old_count - suffix;
+      let new_end = new_count - suffix;
+      self.line_edits.push(LineEdit {
+        line_start: prefix,
+        line_end: old_end.max(prefix + 1) - 1,
+        line_delta: new_end as isize - old_end as isize,
+      });
+    }
+
+    self.remap_anchors_to(&snapshot.buffer);
+    // Undo and redo run outside of transactions, each is one of its own
+    if let Some(log) = &mut self.edit_log {
+      log.begin_transaction();
+      log.record_replacement(cause, &self.buffer.as_str(), &snapshot.buffer.as_str());
+    }
+    self.buffer = snapshot.buffer;
+    self.cursor.index = snapshot.cursor_index.min(self.buffer.len());
+    self.selection = snapshot.selection;
+  }
+
+  /// Moves the anchors as if the text between the common start and end of both buffers was replaced
+  fn remap_anchors_to(&mut self, buffer: &TextBuffer) {
+    if self.anchors.is_empty() {
+      return;
+    }
+    let (old, new) = (self.buffer.as_str(), buffer.as_str());
+    let (old_len, new_len) = (self.buffer.len(), buffer.len());
+    let prefix = old
+      .chars()
+      .zip(new.chars())
+      .take_while(|(a, b)| a == b)
+      .count();
+    let suffix = old
+      .chars()
+      .rev()
+      .zip(new.chars().rev())
+      .take_while(|(a, b)| a == b)
+      .count()
+      .min(old_len.min(new_len) - prefix);
+
+    self.anchors.delete(prefix, old_len - prefix - suffix);
+    self.anchors.insert(prefix, new_len - prefix - suffix);
+  }
+
+  /// Inserts text in the buffer and records the touched lines
+  fn insert_text(&mut self, index: usize, text: &str) {
+    let (line, _col) = self.buffer.char_to_line_col(index);
+    self.history.mark_changed();
+    if let Some(log) = &mut self.edit_log {
+      log.record(
+        EditCause::Edit,
+        index..index,
+        String::new(),
+        text.to_string(),
+      );
+    }
+    self.buffer.insert(index, text);
+    self.anchors.insert(index, text.chars().count());
+    self.line_edits.push(LineEdit {
+      line_start: line,
+      line_end: line,
+      line_delta: text.matches('\n').count() as isize,
+    });
+  }
+
+  /// Deletes text from the buffer and records the touched lines
+  fn delete_text(&mut self, index: usize, len: usize) {
+    let (line_start, _) = self.buffer.char_to_line_col(index);
+    let (line_end, _) = self.buffer.char_to_line_col(index + len);
+    self.history.mark_changed();
+    if let Some(log) = &mut self.edit_log {
+      let removed = self.buffer.slice(index..index + len);
+      let end = index + removed.chars().count();
+      log.record(EditCause::Edit, index..end, removed, String::new());
+    }
+    self.buffer.delete(index, len);
+    self.anchors.delete(index, len);
+    self.line_edits.push(LineEdit {
+      line_start,
+      line_end,
+      line_delta: -((line_end - line_start) as isize),
+    });
+  }
+
   /// Check if there's an active selection
   pub fn has_selection(&self) -> bool {
     self.selection.is_some()
@@ -84,16 +340,18 @@ impl Editor {
 
   /// Delete the selected text and return it
   pub fn delete_selection(&mut self) -> Option<String> {
-    if let Some(range) = self.selection_range() {
-      let text = self.get_selected_text();
-      let len = range.end - range.start;
-      self.buffer.delete(range.start, len);
-      self.cursor.index = range.start;
-      self.clear_selection();
-      text
-    } else {
-      None
-    }
+    self.transact(|editor| {
+      if let Some(range) = editor.selection_range() {
+        let text = editor.get_selected_text();
+        let len = range.end - range.start;
+        editor.delete_text(range.start, len);
+        editor.cursor.index = range.start;
+        editor.clear_selection();
+        text
+      } else {
+        None
+      }
+    })
   }
 
   /// Get the currently selected text
@@ -113,30 +371,132 @@ impl Editor {
 
   /// Replace the selected text with new content
   pub fn replace_selection(&mut self, replacement: &str) {
-    if self.selection_range().is_some() {
-      self.delete_selection();
-    }
-    for ch in replacement.chars() {
-      self.insert_char(ch);
-    }
+    self.transact(|editor| {
+      if editor.selection_range().is_some() {
+        editor.delete_selection();
+      }
+      for ch in replacement.chars() {
+        editor.insert_char(ch);
+      }
+    })
+  }
+
+  /// Occurrences of the selected text, or of the word under the cursor
+  pub fn occurrences(&self) -> Vec<Range<usize>> {
+    OccurrenceQuery::at(&self.buffer, self.cursor.index, self.selection_range())
+      .map(|query| query.find(&self.buffer))
+      .unwrap_or_default()
   }
 
   /// Select word at the given index
   pub fn select_word_at(&mut self, index: usize) {
-    let (start, end) = Cursor::find_word_boundaries(&self.buffer, index);
+    let (start, end) = Cursor::find_word_boundaries_with(&self.buffer, index, &self.word_chars);
     self.select_range(start, end);
   }
 
+  /// Grows the selection to the next enclosing scope: the word at the cursor, the content of
+  /// the enclosing brackets or quotes then the pair itself, the lines, the indentation block
+  /// and finally the whole buffer
+  pub fn expand_selection(&mut self) {
+    let current = self
+      .selection
+      .unwrap_or(Selection::new(self.cursor.index, self.cursor.index));
+    if self.selection != self.expanded {
+      self.expansions.clear();
+    }
+    let Some(scope) = enclosing_scope(&self.buffer.as_str(), current.range(), &self.word_chars)
+    else {
+      return;
+    };
+
+    self.expansions.push(current);
+    let selection = Selection::new(scope.start, scope.end);
+    self.selection = Some(selection);
+    self.cursor.index = selection.head();
+    self.expanded = self.selection;
+  }
+
+  /// Goes back to the selection before the last expansion, as long as it wasn't changed since
+  pub fn shrink_selection(&mut self) {
+    if self.selection != self.expanded {
+      self.expansions.clear();
+    }
+    let Some(previous) = self.expansions.pop() else {
+      return;
+    };
+
+    let len = self.buffer.len();
+    let previous = Selection {
+      start: previous.start.min(len),
+      end: previous.end.min(len),
+      ..previous
+    };
+    self.cursor.index = previous.head();
+    self.selection = (!previous.is_empty()).then_some(previous);
+    self.expanded = self.selection;
+  }
+
   /// Select entire line at the given index
   pub fn select_line_at(&mut self, index: usize) {
+    let range = self.line_range_at(index);
+    self.select_range(range.start, range.end);
+  }
+
+  /// Char range of the line containing `index`, newline included
+  fn line_range_at(&self, index: usize) -> Range<usize> {
     let (line, _col) = self.buffer.char_to_line_col(index);
-    let start = self.buffer.line_col_to_char(line, 0);
-    let end = if line + 1 < self.buffer.line_count() {
-      self.buffer.line_col_to_char(line + 1, 0)
+    self.line_char_range(line..line + 1)
+  }
+
+  /// Selects from the `anchor` range picked when the drag started to the unit at `index`,
+  /// the anchor always stays selected and the cursor goes to the side of `index`
+  pub fn select_to(
+    &mut self,
+    anchor: Range<usize>,
+    index: usize,
+    granularity: SelectionGranularity,
+  ) {
+    let head = match granularity {
+      SelectionGranularity::Char => index..index,
+      SelectionGranularity::Word => {
+        let (start, end) = Cursor::find_word_boundaries_with(&self.buffer, index, &self.word_chars);
+        start..end
+      }
+      SelectionGranularity::Line => self.line_range_at(index),
+    };
+
+    if head.start < anchor.start {
+      self.select_range(anchor.end, head.start);
+      self.cursor.move_to(head.start);
     } else {
-      self.buffer.len()
+      let end = head.end.max(anchor.end);
+      self.select_range(anchor.start, end);
+      self.cursor.move_to(end);
+    }
+  }
+
+  /// Select whole lines from `anchor_line` to `head_line` included, the cursor goes to the head side
+  pub fn select_lines(&mut self, anchor_line: usize, head_line: usize) {
+    let last_line = self.buffer.line_count().saturating_sub(1);
+    let anchor_line = anchor_line.min(last_line);
+    let head_line = head_line.min(last_line);
+
+    let line_start = |line: usize| self.buffer.line_col_to_char(line, 0);
+    let line_end = |line: usize| {
+      if line < last_line {
+        line_start(line + 1)
+      } else {
+        self.buffer.len()
+      }
     };
-    self.select_range(start, end);
+
+    let (anchor, head) = if head_line >= anchor_line {
+      (line_start(anchor_line), line_end(head_line))
+    } else {
+      (line_end(anchor_line), line_start(head_line))
+    };
+    self.select_range(anchor, head);
+    self.cursor.move_to(head);
   }
 
   /// Extend selection left by one character
@@ -163,21 +523,31 @@ impl Editor {
 
   /// Extend selection up by one line
   pub fn extend_selection_up(&mut self) {
+    self.extend_selection_up_by(1);
+  }
+
+  /// Extend selection down by one line
+  pub fn extend_selection_down(&mut self) {
+    self.extend_selection_down_by(1);
+  }
+
+  /// Extend selection up by `lines` lines
+  pub fn extend_selection_up_by(&mut self, lines: usize) {
     if self.selection.is_none() {
       self.selection = Some(Selection::new(self.cursor.index, self.cursor.index));
     }
-    self.cursor.move_up(&self.buffer);
+    self.cursor.move_up_by(&self.buffer, lines);
     if let Some(sel) = &mut self.selection {
       *sel = Selection::new(sel.tail(), self.cursor.index);
     }
   }
 
-  /// Extend selection down by one line
-  pub fn extend_selection_down(&mut self) {
+  /// Extend selection down by `lines` lines
+  pub fn extend_selection_down_by(&mut self, lines: usize) {
     if self.selection.is_none() {
       self.selection = Some(Selection::new(self.cursor.index, self.cursor.index));
     }
-    self.cursor.move_down(&self.buffer);
+    self.cursor.move_down_by(&self.buffer, lines);
     if let Some(sel) = &mut self.selection {
       *sel = Selection::new(sel.tail(), self.cursor.index);
     }
@@ -227,12 +597,34 @@ impl Editor {
     }
   }
 
+  /// Cursor moved to the previous or next word boundary, or subword one in subword mode
+  fn word_motion(&self, forward: bool) -> Cursor {
+    let mut cursor = self.cursor;
+    match (forward, self.subwords) {
+      (false, false) => cursor.move_word_left_with(&self.buffer, &self.word_chars),
+      (true, false) => cursor.move_word_right_with(&self.buffer, &self.word_chars),
+      (false, true) => cursor.move_subword_left_with(&self.buffer, &self.word_chars),
+      (true, true) => cursor.move_subword_right_with(&self.buffer, &self.word_chars),
+    }
+    cursor
+  }
+
+  /// Move to the previous word boundary
+  pub fn move_word_left(&mut self) {
+    self.cursor = self.word_motion(false);
+  }
+
+  /// Move to the next word boundary
+  pub fn move_word_right(&mut self) {
+    self.cursor = self.word_motion(true);
+  }
+
   /// Extend selection left by one word
   pub fn extend_selection_word_left(&mut self) {
     if self.selection.is_none() {
       self.selection = Some(Selection::new(self.cursor.index, self.cursor.index));
     }
-    self.cursor.move_word_left(&self.buffer);
+    self.cursor = self.word_motion(false);
     if let Some(sel) = &mut self.selection {
       *sel = Selection::new(sel.tail(), self.cursor.index);
     }
@@ -243,85 +635,643 @@ impl Editor {
     if self.selection.is_none() {
       self.selection = Some(Selection::new(self.cursor.index, self.cursor.index));
     }
-    self.cursor.move_word_right(&self.buffer);
+    self.cursor = self.word_motion(true);
     if let Some(sel) = &mut self.selection {
       *sel = Selection::new(sel.tail(), self.cursor.index);
     }
   }
 
-  /// Copy selected text (returns text for clipboard)
-  pub fn copy(&self) -> Option<String> {
-    self.get_selected_text()
-  }
-
-  /// Cut selected text (copy + delete, returns text for clipboard)
-  pub fn cut(&mut self) -> Option<String> {
-    self.delete_selection()
+  /// Copy selected text, or the cursor line with its newline without selection
+  pub fn copy(&self) -> Option<ClipboardText> {
+    if self.has_selection() {
+      return self.get_selected_text().map(|text| ClipboardText {
+        text,
+        whole_line: false,
+      });
+    }
+    self.cursor_line_text().map(|text| ClipboardText {
+      text,
+      whole_line: true,
+    })
+  }
+
+  /// Cut selected text, or the cursor line without selection
+  pub fn cut(&mut self) -> Option<ClipboardText> {
+    self.transact(|editor| {
+      if editor.has_selection() {
+        return editor.delete_selection().map(|text| ClipboardText {
+          text,
+          whole_line: false,
+        });
+      }
+      let text = editor.cursor_line_text()?;
+      editor.delete_line();
+      Some(ClipboardText {
+        text,
+        whole_line: true,
+      })
+    })
+  }
+
+  /// Text of the cursor line, always ending with a newline, None in an empty buffer
+  fn cursor_line_text(&self) -> Option<String> {
+    if self.buffer.is_empty() {
+      return None;
+    }
+    let range = self.line_range_at(self.cursor.index);
+    let mut text: String = self
+      .buffer
+      .as_str()
+      .chars()
+      .skip(range.start)
+      .take(range.len())
+      .collect();
+    if !text.ends_with('\n') {
+      text.push('\n');
+    }
+    Some(text)
+  }
+
+  /// Pastes copied text, a whole line copied without selection goes above the cursor line
+  /// and the cursor keeps its position in the text
+  pub fn paste_clipboard(&mut self, clipboard: &ClipboardText) {
+    self.transact(|editor| {
+      if !clipboard.whole_line || editor.has_selection() {
+        editor.paste(&clipboard.text);
+        return;
+      }
+
+      let text = LineEnding::normalize(&clipboard.text);
+      let line_start = editor.line_range_at(editor.cursor.index).start;
+      let cursor_index = editor.cursor.index;
+      editor.insert_text(line_start, &text);
+      editor.cursor.index = cursor_index + text.chars().count();
+    })
   }
 
   /// Paste text at cursor (or replace selection)
   pub fn paste(&mut self, text: &str) {
-    if self.has_selection() {
-      self.delete_selection();
-    }
-    for ch in text.chars() {
-      self.insert_char(ch);
-    }
+    self.transact(|editor| {
+      if editor.has_selection() {
+        editor.delete_selection();
+      }
+      // One edit for the whole text, the line caches rebuild once
+      let text = LineEnding::normalize(text);
+      editor.insert_text(editor.cursor.index, &text);
+      editor.cursor.index += text.chars().count();
+    })
   }
 
   pub fn insert_char(&mut self, ch: char) {
-    let mut buf = [0; 4];
-    let s = ch.encode_utf8(&mut buf);
-    self.buffer.insert(self.cursor.index, s);
-    self.cursor.index += 1; // Increment by 1 character, not bytes
+    self.transact_with(ch != '\n', |editor| {
+      let mut buf = [0; 4];
+      let s = ch.encode_utf8(&mut buf);
+      editor.insert_text(editor.cursor.index, s);
+      editor.cursor.index += 1; // Increment by 1 character, not bytes
+    })
   }
 
   pub fn backspace(&mut self) {
-    if self.cursor.index > 0 {
-      self.cursor.index -= 1;
-      self.buffer.delete(self.cursor.index, 1);
-    }
+    self.transact(|editor| {
+      if editor.cursor.index > 0 {
+        editor.cursor.index -= 1;
+        editor.delete_text(editor.cursor.index, 1);
+      }
+    })
   }
 
   pub fn delete_word(&mut self) {
-    if self.cursor.index == 0 {
-      return;
-    }
+    self.transact(|editor| {
+      if editor.cursor.index == 0 {
+        return;
+      }
+
+      let start_index = editor.cursor.index;
+      let (current_line, current_col) = editor.buffer.char_to_line_col(start_index);
+      let line_start = editor.buffer.line_col_to_char(current_line, 0);
+
+      editor.cursor = editor.word_motion(false);
+      let end_index = editor.cursor.index;
+
+      // If we're at the start of a line (col 0), allow deleting the newline
+      // Otherwise, don't delete across line boundaries
+      let delete_from = if current_col == 0 {
+        end_index
+      } else {
+        end_index.max(line_start)
+      };
+
+      let count = start_index - delete_from;
+
+      editor.delete_text(delete_from, count);
+      editor.cursor.index = delete_from;
+    })
+  }
+
+  /// Deletes the character after the cursor
+  pub fn delete(&mut self) {
+    self.transact(|editor| {
+      if editor.cursor.index < editor.buffer.len() {
+        editor.delete_text(editor.cursor.index, 1);
+      }
+    })
+  }
+
+  /// Deletes up to the end of the word after the cursor, without crossing to the next line
+  /// unless the cursor is at the end of its line
+  pub fn delete_word_forward(&mut self) {
+    self.transact(|editor| {
+      let start_index = editor.cursor.index;
+      let cursor = editor.word_motion(true);
+      if cursor.index > start_index {
+        editor.delete_text(start_index, cursor.index - start_index);
+      }
+    })
+  }
+
+  /// Deletes from the cursor to the end of its line, or the newline when it's at the end already
+  pub fn delete_to_line_end(&mut self) {
+    self.transact(|editor| {
+      let start_index = editor.cursor.index;
+      let (line, _col) = editor.buffer.char_to_line_col(start_index);
+      let line_len = editor
+        .buffer
+        .line(line)
+        .map(|l| l.trim_end_matches('\n').chars().count())
+        .unwrap_or(0);
+      let line_end = editor.buffer.line_col_to_char(line, line_len);
+
+      let count = if line_end > start_index {
+        line_end - start_index
+      } else {
+        1.min(editor.buffer.len() - start_index)
+      };
+      if count > 0 {
+        editor.delete_text(start_index, count);
+      }
+    })
+  }
 
-    let start_index = self.cursor.index;
-    let (current_line, current_col) = self.buffer.char_to_line_col(start_index);
-    let line_start = self.buffer.line_col_to_char(current_line, 0);
+  pub fn delete_line(&mut self) {
+    self.transact(|editor| {
+      let (line, _col) = editor.buffer.char_to_line_col(editor.cursor.index);
+      let line_start = editor.buffer.line_col_to_char(line, 0);
 
-    self.cursor.move_word_left(&self.buffer);
-    let end_index = self.cursor.index;
+      // Calculate line length including the newline if it exists
+      let line_content = editor.buffer.line(line).unwrap_or_default();
+      let line_len = line_content.chars().count();
+
+      // Delete the entire line including newline
+      editor.delete_text(line_start, line_len);
+
+      // Position cursor at the start of what's now at this line
+      editor.cursor.index = line_start;
+    })
+  }
 
-    // If we're at the start of a line (col 0), allow deleting the newline
-    // Otherwise, don't delete across line boundaries
-    let delete_from = if current_col == 0 {
-      end_index
+  /// Lines covered by the selection, or the cursor line without selection
+  /// A selection ending at the start of a line doesn't include that line
+  pub fn selected_line_range(&self) -> Range<usize> {
+    let Some(range) = self.selection_range() else {
+      let (line, _col) = self.buffer.char_to_line_col(self.cursor.index);
+      return line..line + 1;
+    };
+
+    let (start_line, _) = self.buffer.char_to_line_col(range.start);
+    let (end_line, end_col) = self.buffer.char_to_line_col(range.end);
+    let end_line = if end_col == 0 && end_line > start_line {
+      end_line - 1
     } else {
-      end_index.max(line_start)
+      end_line
+    };
+    start_line..end_line + 1
+  }
+
+  /// Check if the selection spans more than one line
+  pub fn has_multiline_selection(&self) -> bool {
+    self.has_selection() && self.selected_line_range().len() > 1
+  }
+
+  /// Insert `indent` at the start of every selected line
+  pub fn indent_lines(&mut self, indent: &str) {
+    self.transact(|editor| {
+      let width = indent.chars().count() as isize;
+      editor.edit_lines(|buffer, line| {
+        let line_start = buffer.line_col_to_char(line, 0);
+        buffer.insert(line_start, indent);
+        (0, width)
+      });
+    })
+  }
+
+  /// Remove one level of indentation (a tab or up to `tab_size` spaces) from every selected line
+  pub fn outdent_lines(&mut self, tab_size: usize) {
+    self.transact(|editor| {
+      editor.edit_lines(|buffer, line| {
+        let content = buffer.line(line).unwrap_or_default();
+        let count = if content.starts_with('\t') {
+          1
+        } else {
+          content
+            .chars()
+            .take(tab_size)
+            .take_while(|c| *c == ' ')
+            .count()
+        };
+
+        let line_start = buffer.line_col_to_char(line, 0);
+        buffer.delete(line_start, count);
+        (0, -(count as isize))
+      });
+    })
+  }
+
+  /// Comment out the selected lines with `token` at their smallest indentation, or uncomment
+  /// them when every non-blank line already starts with it. Blank lines are left alone
+  pub fn toggle_line_comments(&mut self, token: &str) {
+    let is_blank = |content: &str| content.trim().is_empty();
+    let indent_of = |content: &str| content.chars().take_while(|c| c.is_whitespace()).count();
+
+    let contents: Vec<String> = self
+      .selected_line_range()
+      .filter_map(|line| self.buffer.line(line))
+      .filter(|content| !is_blank(content))
+      .collect();
+    let Some(indent) = contents.iter().map(|content| indent_of(content)).min() else {
+      return;
+    };
+    let commented = contents
+      .iter()
+      .all(|content| content.trim_start().starts_with(token));
+    let token_len = token.chars().count();
+    let prefix = format!("{} ", token);
+
+    self.transact(|editor| {
+      editor.edit_lines(|buffer, line| {
+        let content = buffer.line(line).unwrap_or_default();
+        if is_blank(&content) {
+          return (0, 0);
+        }
+
+        let line_start = buffer.line_col_to_char(line, 0);
+        if commented {
+          let col = indent_of(&content);
+          let followed_by_space = content.chars().nth(col + token_len) == Some(' ');
+          let count = token_len + usize::from(followed_by_space);
+          buffer.delete(line_start + col, count);
+          (col, -(count as isize))
+        } else {
+          buffer.insert(line_start + indent, &prefix);
+          (indent, prefix.chars().count() as isize)
+        }
+      });
+    })
+  }
+
+  /// Wrap the selection in `open` and `close`, or unwrap it when it already is a block comment,
+  /// the comment stays selected so toggling again reverts it
+  /// Without selection an empty comment is inserted with the cursor inside it
+  pub fn toggle_block_comment(&mut self, open: &str, close: &str) {
+    let open_len = open.chars().count();
+    let close_len = close.chars().count();
+
+    let Some(range) = self.selection_range().filter(|range| !range.is_empty()) else {
+      self.transact(|editor| {
+        let index = editor.cursor.index;
+        editor.insert_text(index, &format!("{}  {}", open, close));
+        editor.cursor.move_to(index + open_len + 1);
+      });
+      return;
     };
 
-    let count = start_index - delete_from;
+    let chars: Vec<char> = self
+      .buffer
+      .as_str()
+      .chars()
+      .skip(range.start)
+      .take(range.len())
+      .collect();
+    let leading = chars.iter().take_while(|c| c.is_whitespace()).count();
+    let trailing = chars[leading..]
+      .iter()
+      .rev()
+      .take_while(|c| c.is_whitespace())
+      .count();
+    let content: String = chars[leading..chars.len() - trailing].iter().collect();
+    let start = range.start + leading;
+    let end = range.end - trailing;
+    let reversed = self.selection.is_some_and(|selection| selection.reversed);
+
+    let new_end = if content.starts_with(open)
+      && content.ends_with(close)
+      && content.chars().count() >= open_len + close_len
+    {
+      let inner: Vec<char> = content.chars().skip(open_len).collect();
+      let inner = &inner[..inner.len() - close_len];
+      let open_count = open_len + usize::from(inner.first() == Some(&' '));
+      let close_count = close_len + usize::from(inner.len() > 1 && inner.last() == Some(&' '));
+
+      self.transact(|editor| {
+        editor.delete_text(end - close_count, close_count);
+        editor.delete_text(start, open_count);
+      });
+      range.end - open_count - close_count
+    } else {
+      self.transact(|editor| {
+        editor.insert_text(end, &format!(" {}", close));
+        editor.insert_text(start, &format!("{} ", open));
+      });
+      range.end + open_len + close_len + 2
+    };
 
-    self.buffer.delete(delete_from, count);
-    self.cursor.index = delete_from;
+    let selection = if reversed {
+      Selection::new(new_end, range.start)
+    } else {
+      Selection::new(range.start, new_end)
+    };
+    self.cursor.move_to(selection.head());
+    self.selection = Some(selection);
+  }
+
+  /// Remove the spaces and tabs at the end of every line, returns whether anything was removed
+  pub fn trim_trailing_whitespace(&mut self) -> bool {
+    let trailing: Vec<Range<usize>> = (0..self.buffer.line_count())
+      .filter_map(|line| {
+        let content = self.buffer.line(line)?;
+        let content = content.trim_end_matches('\n');
+        let len = content.chars().count();
+        let trimmed_len = content.trim_end_matches([' ', '\t']).chars().count();
+        let line_start = self.buffer.line_col_to_char(line, 0);
+        (trimmed_len < len).then(|| line_start + trimmed_len..line_start + len)
+      })
+      .collect();
+    if trailing.is_empty() {
+      return false;
+    }
+
+    // Positions past a removed range move back by its length, the ones inside it go to its start
+    let remap = |index: usize| {
+      trailing
+        .iter()
+        .filter(|range| range.start < index)
+        .map(|range| range.end.min(index) - range.start)
+        .fold(index, |index, removed| index - removed)
+    };
+    let cursor_index = remap(self.cursor.index);
+    let selection = self
+      .selection
+      .map(|selection| Selection::new(remap(selection.tail()), remap(selection.head())));
+
+    self.transact(|editor| {
+      for range in trailing.iter().rev() {
+        editor.delete_text(range.start, range.len());
+      }
+      editor.cursor.move_to(cursor_index);
+      editor.selection = selection.filter(|selection| !selection.is_empty());
+    });
+    true
+  }
+
+  /// Copy the selected lines below them, the cursor and selection move to the copy
+  pub fn duplicate_lines_down(&mut self) {
+    self.duplicate_lines(true);
+  }
+
+  /// Copy the selected lines above them, the cursor and selection stay on the copy
+  pub fn duplicate_lines_up(&mut self) {
+    self.duplicate_lines(false);
+  }
+
+  fn duplicate_lines(&mut self, down: bool) {
+    let range = self.line_char_range(self.selected_line_range());
+    let mut text: String = self
+      .buffer
+      .as_str()
+      .chars()
+      .skip(range.start)
+      .take(range.len())
+      .collect();
+    if !text.ends_with('\n') {
+      text.push('\n');
+    }
+
+    // Inserting before the lines leaves the cursor on the upper copy
+    self.transact(|editor| {
+      editor.insert_text(range.start, &text);
+      if down {
+        editor.shift_cursor_and_selection(text.chars().count() as isize);
+      }
+    });
+  }
+
+  /// Swap the selected lines with the line above them
+  pub fn move_lines_up(&mut self) {
+    let lines = self.selected_line_range();
+    if lines.start == 0 {
+      return;
+    }
+    self.rotate_lines(lines.start - 1..lines.end, true);
   }
 
-  pub fn delete_line(&mut self) {
-    let (line, _col) = self.buffer.char_to_line_col(self.cursor.index);
-    let line_start = self.buffer.line_col_to_char(line, 0);
-
-    // Calculate line length including the newline if it exists
-    let line_content = self.buffer.line(line).unwrap_or_default();
-    let line_len = line_content.chars().count();
+  /// Swap the selected lines with the line below them
+  pub fn move_lines_down(&mut self) {
+    let lines = self.selected_line_range();
+    if lines.end >= self.buffer.line_count() {
+      return;
+    }
+    self.rotate_lines(lines.start..lines.end + 1, false);
+  }
+
+  /// Moves the first line of `lines` after the others, or the last one before them when `up`
+  /// is false, the cursor and selection follow the lines that didn't move
+  fn rotate_lines(&mut self, lines: Range<usize>, up: bool) {
+    let reaches_end = lines.end >= self.buffer.line_count();
+    let range = self.line_char_range(lines);
+    let text: String = self
+      .buffer
+      .as_str()
+      .chars()
+      .skip(range.start)
+      .take(range.len())
+      .collect();
+
+    // The last line of the buffer has no newline, the others keep their own
+    let mut parts: Vec<&str> = text.split('\n').collect();
+    if !reaches_end {
+      parts.pop();
+    }
+    let shift = if up {
+      let moved = parts.remove(0);
+      parts.push(moved);
+      -(moved.chars().count() as isize + 1)
+    } else {
+      let moved = parts.pop().unwrap_or_default();
+      parts.insert(0, moved);
+      moved.chars().count() as isize + 1
+    };
+    let mut rotated = parts.join("\n");
+    if !reaches_end {
+      rotated.push('\n');
+    }
+
+    self.transact(|editor| {
+      editor.delete_text(range.start, range.len());
+      editor.insert_text(range.start, &rotated);
+      editor.shift_cursor_and_selection(shift);
+    });
+  }
+
+  /// Replaces whole lines, newline of the last one included, as a single undo step
+  ///
+  /// The cursor goes to the start of the replacement and the selection is cleared
+  pub fn replace_lines(&mut self, lines: Range<usize>, text: &str) {
+    let range = self.line_char_range(lines);
+    self.transact(|editor| {
+      editor.delete_text(range.start, range.len());
+      editor.insert_text(range.start, &LineEnding::normalize(text));
+      editor.cursor.move_to(range.start);
+      editor.selection = None;
+    });
+  }
+
+  /// Applies a batch of edits as a single undo step, or none of them when one is invalid
+  ///
+  /// Ranges are chars of the buffer before the batch and can't overlap. The cursor, the
+  /// selection and the anchors follow the text around them, and the touched lines are
+  /// recorded as one line edit spanning all of them
+  pub fn apply_edits(&mut self, edits: Vec<Edit>) -> Result<(), EditError> {
+    let edits: Vec<Edit> = sort_edits(edits, self.buffer.len())?
+      .into_iter()
+      .map(|edit| Edit::new(edit.range, LineEnding::normalize(&edit.new_text)))
+      .filter(|edit| !edit.range.is_empty() || !edit.new_text.is_empty())
+      .collect();
+    let (Some(first), Some(last)) = (edits.first(), edits.last()) else {
+      return Ok(());
+    };
 
-    // Delete the entire line including newline
-    self.buffer.delete(line_start, line_len);
+    let (line_start, _) = self.buffer.char_to_line_col(first.range.start);
+    let (line_end, _) = self.buffer.char_to_line_col(last.range.end);
+    let mut line_delta = 0;
+    for edit in &edits {
+      let (removed_start, _) = self.buffer.char_to_line_col(edit.range.start);
+      let (removed_end, _) = self.buffer.char_to_line_col(edit.range.end);
+      line_delta += edit.new_text.matches('\n').count() as isize;
+      line_delta -= (removed_end - removed_start) as isize;
+    }
+
+    let cursor_index = map_index(self.cursor.index, &edits);
+    let selection = self
+      .selection
+      .map(|sel| Selection::new(map_index(sel.tail(), &edits), map_index(sel.head(), &edits)));
+    self.transact(|editor| {
+      editor.history.mark_changed();
+      // Last first, so the ranges of the edits before it still point at the same text
+      for edit in edits.iter().rev() {
+        if let Some(log) = &mut editor.edit_log {
+          let removed = editor.buffer.slice(edit.range.clone());
+          log.record(
+            EditCause::Edit,
+            edit.range.clone(),
+            removed,
+            edit.new_text.clone(),
+          );
+        }
+        editor.buffer.delete(edit.range.start, edit.range.len());
+        editor.anchors.delete(edit.range.start, edit.range.len());
+        editor.buffer.insert(edit.range.start, &edit.new_text);
+        editor
+          .anchors
+          .insert(edit.range.start, edit.new_text.chars().count());
+      }
+      editor.line_edits.push(LineEdit {
+        line_start,
+        line_end,
+        line_delta,
+      });
+      editor.cursor.move_to(cursor_index);
+      editor.selection = selection.filter(|sel| !sel.is_empty());
+    });
+    Ok(())
+  }
+
+  /// Char range of whole lines, newline of the last one included
+  fn line_char_range(&self, lines: Range<usize>) -> Range<usize> {
+    let start = self.buffer.line_col_to_char(lines.start, 0);
+    let end = if lines.end < self.buffer.line_count() {
+      self.buffer.line_col_to_char(lines.end, 0)
+    } else {
+      self.buffer.len()
+    };
+    start..end
+  }
+
+  fn shift_cursor_and_selection(&mut self, offset: isize) {
+    let shift = |index: usize| index.saturating_add_signed(offset);
+    self.cursor.move_to(shift(self.cursor.index));
+    if let Some(selection) = self.selection {
+      self.selection = Some(Selection::new(
+        shift(selection.tail()),
+        shift(selection.head()),
+      ));
+    }
+  }
+
+  /// Apply an edit to each selected line, `edit` returns the column it edited at and the
+  /// column shift it caused after it
+  /// Cursor and selection keep their position relative to the line content
+  fn edit_lines(&mut self, mut edit: impl FnMut(&mut TextBuffer, usize) -> (usize, isize)) {
+    let lines = self.selected_line_range();
+    let cursor = self.buffer.char_to_line_col(self.cursor.index);
+    let selection = self.selection.map(|sel| {
+      (
+        self.buffer.char_to_line_col(sel.tail()),
+        self.buffer.char_to_line_col(sel.head()),
+      )
+    });
+
+    let old_text = self.edit_log.is_some().then(|| self.buffer.as_str());
+    let shifts: Vec<(usize, isize)> = lines
+      .clone()
+      .map(|line| {
+        let (col, shift) = edit(&mut self.buffer, line);
+        let index = self.buffer.line_col_to_char(line, 0) + col;
+        match shift {
+          shift if shift > 0 => self.anchors.insert(index, shift as usize),
+          shift if shift < 0 => self.anchors.delete(index, shift.unsigned_abs()),
+          _ => {}
+        }
+        (col, shift)
+      })
+      .collect();
+    if shifts.iter().any(|(_, shift)| *shift != 0) {
+      self.history.mark_changed();
+    }
+    if let (Some(log), Some(old_text)) = (&mut self.edit_log, old_text) {
+      log.record_replacement(EditCause::Edit, &old_text, &self.buffer.as_str());
+    }
+    self.line_edits.push(LineEdit {
+      line_start: lines.start,
+      line_end: lines.end - 1,
+      line_delta: 0,
+    });
+
+    let remap = |buffer: &TextBuffer, (line, col): (usize, usize)| {
+      let col = match lines.contains(&line).then(|| shifts[line - lines.start]) {
+        Some((edit_col, shift)) if col >= edit_col => {
+          (col as isize + shift).max(edit_col as isize) as usize
+        }
+        _ => col,
+      };
+      buffer.line_col_to_char(line, col)
+    };
 
-    // Position cursor at the start of what's now at this line
-    self.cursor.index = line_start;
+    self.cursor.move_to(remap(&self.buffer, cursor));
+    if let Some((tail, head)) = selection {
+      self.selection = Some(Selection::new(
+        remap(&self.buffer, tail),
+        remap(&self.buffer, head),
+      ));
+    }
   }
 }
 
@@ -518,6 +1468,135 @@ mod tests {
     assert_eq!(editor.cursor.index, 0);
   }
 
+  #[test]
+  fn test_delete() {
+    let mut editor = Editor::new();
+    editor.paste("Hi\nyou");
+    editor.cursor.index = 1;
+
+    editor.delete();
+    assert_eq!(editor.buffer.as_str(), "H\nyou");
+    assert_eq!(editor.cursor.index, 1);
+
+    // Joins the next line
+    editor.delete();
+    assert_eq!(editor.buffer.as_str(), "Hyou");
+
+    editor.cursor.index = 4;
+    editor.delete();
+    assert_eq!(editor.buffer.as_str(), "Hyou");
+    assert_eq!(editor.cursor.index, 4);
+  }
+
+  #[test]
+  fn test_delete_word_forward() {
+    let mut editor = Editor::new();
+    editor.paste("hello world\nnext");
+    editor.cursor.index = 0;
+
+    editor.delete_word_forward();
+    assert_eq!(editor.buffer.as_str(), " world\nnext");
+    editor.delete_word_forward();
+    assert_eq!(editor.buffer.as_str(), "world\nnext");
+    editor.delete_word_forward();
+    assert_eq!(editor.buffer.as_str(), "\nnext");
+    assert_eq!(editor.cursor.index, 0);
+
+    // At the end of the line, only the newline goes
+    editor.delete_word_forward();
+    assert_eq!(editor.buffer.as_str(), "next");
+
+    editor.cursor.index = 4;
+    editor.delete_word_forward();
+    assert_eq!(editor.buffer.as_str(), "next");
+  }
+
+  #[test]
+  fn test_delete_to_line_end() {
+    let mut editor = Editor::new();
+    editor.paste("hello world\nnext");
+    editor.cursor.index = 5;
+
+    editor.delete_to_line_end();
+    assert_eq!(editor.buffer.as_str(), "hello\nnext");
+    assert_eq!(editor.cursor.index, 5);
+
+    // At the end of the line, joins the next one
+    editor.delete_to_line_end();
+    assert_eq!(editor.buffer.as_str(), "hellonext");
+
+    editor.cursor.index = 9;
+    editor.delete_to_line_end();
+    assert_eq!(editor.buffer.as_str(), "hellonext");
+  }
+
+  #[test]
+  fn test_word_chars() {
+    let mut editor = Editor::new();
+    editor.paste("a { margin-top: 0 }");
+    editor.word_chars = WordChars::new(['-']);
+
+    editor.select_word_at(6);
+    assert_eq!(editor.get_selected_text(), Some("margin-top".to_string()));
+
+    editor.clear_selection();
+    editor.cursor.index = 14;
+    editor.delete_word();
+    assert_eq!(editor.buffer.as_str(), "a { : 0 }");
+  }
+
+  #[test]
+  fn test_subwords() {
+    let mut editor = Editor::new();
+    editor.paste("readFileSync");
+    editor.subwords = true;
+
+    editor.delete_word();
+    assert_eq!(editor.buffer.as_str(), "readFile");
+    editor.move_word_left();
+    assert_eq!(editor.cursor.index, 4);
+    editor.extend_selection_word_left();
+    assert_eq!(editor.get_selected_text(), Some("read".to_string()));
+
+    editor.subwords = false;
+    editor.clear_selection();
+    editor.move_word_right();
+    assert_eq!(editor.cursor.index, 8);
+  }
+
+  #[test]
+  fn test_edit_log() {
+    let mut editor = Editor::new();
+    editor.paste("hello");
+    editor.start_edit_log();
+
+    editor.paste(" world");
+    editor.select_range(0, 5);
+    editor.paste("goodbye");
+    editor.undo();
+    editor.redo();
+
+    let log = editor.stop_edit_log().unwrap();
+    let causes: Vec<_> = log.events().iter().map(|event| event.cause).collect();
+    assert_eq!(
+      causes,
+      [
+        EditCause::Edit,
+        EditCause::Edit,
+        EditCause::Undo,
+        EditCause::Redo
+      ]
+    );
+    // Pasted over the selection
+    assert_eq!(log.events()[1].range, 0..5);
+    assert_eq!(log.events()[1].removed, "hello");
+    assert_eq!(log.events()[1].inserted, "goodbye");
+    assert_eq!(log.replay("hello"), editor.buffer.as_str());
+
+    editor.paste("!");
+    assert!(editor.edit_log().is_none());
+  }
+
   #[test]
   fn test_delete_line() {
     let mut editor = Editor::new();
@@ -1081,6 +2160,98 @@ mod tests {
     assert_eq!(selected, Some("Line 2".to_string()));
   }
 
+  #[test]
+  fn test_select_lines_down() {
+    let mut editor = Editor::new();
+    editor.paste("Line 1\nLine 2\nLine 3\nLine 4");
+
+    editor.select_lines(1, 2);
+    assert_eq!(
+      editor.get_selected_text(),
+      Some("Line 2\nLine 3\n".to_string())
+    );
+    assert_eq!(editor.cursor.index, 21);
+    assert!(!editor.selection.unwrap().reversed);
+  }
+
+  #[test]
+  fn test_select_lines_up() {
+    let mut editor = Editor::new();
+    editor.paste("Line 1\nLine 2\nLine 3\nLine 4");
+
+    editor.select_lines(2, 0);
+    assert_eq!(
+      editor.get_selected_text(),
+      Some("Line 1\nLine 2\nLine 3\n".to_string())
+    );
+    assert_eq!(editor.cursor.index, 0);
+    assert!(editor.selection.unwrap().reversed);
+  }
+
+  #[test]
+  fn test_extend_selection_by_lines() {
+    let mut editor = Editor::new();
+    editor.paste("Line 1\nLine 2\nLine 3\nLine 4");
+    editor.cursor.index = 2;
+
+    editor.extend_selection_down_by(2);
+    assert_eq!(
+      editor.get_selected_text(),
+      Some("ne 1\nLine 2\nLi".to_string())
+    );
+
+    editor.extend_selection_up_by(3);
+    assert_eq!(editor.get_selected_text(), Some("Li".to_string()));
+    assert!(editor.selection.unwrap().reversed);
+  }
+
+  #[test]
+  fn test_expand_and_shrink_selection() {
+    let mut editor = Editor::new();
+    editor.paste("f(a, b + c)");
+    editor.cursor.index = 6;
+
+    editor.expand_selection();
+    assert_eq!(editor.get_selected_text(), Some("b".to_string()));
+    editor.expand_selection();
+    assert_eq!(editor.get_selected_text(), Some("a, b + c".to_string()));
+    editor.expand_selection();
+    assert_eq!(editor.get_selected_text(), Some("(a, b + c)".to_string()));
+
+    editor.shrink_selection();
+    assert_eq!(editor.get_selected_text(), Some("a, b + c".to_string()));
+    editor.shrink_selection();
+    editor.shrink_selection();
+    assert_eq!(editor.selection, None);
+    assert_eq!(editor.cursor.index, 6);
+    editor.shrink_selection();
+    assert_eq!(editor.cursor.index, 6);
+  }
+
+  #[test]
+  fn test_shrink_selection_after_other_selection() {
+    let mut editor = Editor::new();
+    editor.paste("f(a, b)");
+    editor.cursor.index = 2;
+    editor.expand_selection();
+    editor.expand_selection();
+
+    // Selecting something else forgets the expansions
+    editor.select_range(0, 1);
+    editor.shrink_selection();
+    assert_eq!(editor.get_selected_text(), Some("f".to_string()));
+  }
+
+  #[test]
+  fn test_select_lines_clamps_to_last_line() {
+    let mut editor = Editor::new();
+    editor.paste("Line 1\nLine 2");
+
+    editor.select_lines(1, 10);
+    assert_eq!(editor.get_selected_text(), Some("Line 2".to_string()));
+    assert_eq!(editor.cursor.index, editor.buffer.len());
+  }
+
   #[test]
   fn test_copy() {
     let mut editor = Editor::new();
@@ -1089,8 +2260,9 @@ mod tests {
     }
 
     editor.select_range(0, 5); // Select "Hello"
-    let copied = editor.copy();
-    assert_eq!(copied, Some("Hello".to_string()));
+    let copied = editor.copy().unwrap();
+    assert_eq!(copied.text, "Hello");
+    assert!(!copied.whole_line);
     assert_eq!(editor.buffer.as_str(), "Hello World"); // Original unchanged
     assert!(editor.has_selection()); // Selection preserved
   }
@@ -1109,8 +2281,9 @@ mod tests {
     }
 
     editor.select_range(0, 5); // Select "Hello"
-    let cut = editor.cut();
-    assert_eq!(cut, Some("Hello".to_string()));
+    let cut = editor.cut().unwrap();
+    assert_eq!(cut.text, "Hello");
+    assert!(!cut.whole_line);
     assert_eq!(editor.buffer.as_str(), " World");
     assert!(!editor.has_selection());
   }
@@ -1121,6 +2294,307 @@ mod tests {
     assert_eq!(editor.cut(), None);
   }
 
+  #[test]
+  fn test_copy_and_cut_line_without_selection() {
+    let mut editor = Editor::new();
+    editor.paste("one\ntwo");
+
+    editor.cursor.index = 1;
+    let copied = editor.copy().unwrap();
+    assert_eq!(copied.text, "one\n");
+    assert!(copied.whole_line);
+
+    // The last line gets a newline so it pastes as a line of its own
+    editor.cursor.index = 5;
+    let cut = editor.cut().unwrap();
+    assert_eq!(cut.text, "two\n");
+    assert!(cut.whole_line);
+    assert_eq!(editor.buffer.as_str(), "one\n");
+  }
+
+  #[test]
+  fn test_paste_whole_line_above_cursor() {
+    let mut editor = Editor::new();
+    editor.paste("one\ntwo");
+    editor.cursor.index = 5; // "t|wo"
+
+    let clipboard = ClipboardText {
+      text: "new\n".to_string(),
+      whole_line: true,
+    };
+    editor.paste_clipboard(&clipboard);
+    assert_eq!(editor.buffer.as_str(), "one\nnew\ntwo");
+    assert_eq!(editor.cursor.index, 9); // Still "t|wo"
+
+    // Replaces a selection like any other text
+    editor.select_range(0, 3);
+    editor.paste_clipboard(&clipboard);
+    assert_eq!(editor.buffer.as_str(), "new\n\nnew\ntwo");
+  }
+
+  #[test]
+  fn test_undo_redo() {
+    let mut editor = Editor::new();
+    editor.paste("one\n");
+    for ch in "two".chars() {
+      editor.insert_char(ch);
+    }
+    editor.backspace();
+    assert_eq!(editor.buffer.as_str(), "one\ntw");
+
+    assert!(editor.undo());
+    assert_eq!(editor.buffer.as_str(), "one\ntwo");
+    // The typed word is a single step
+    assert!(editor.undo());
+    assert_eq!(editor.buffer.as_str(), "one\n");
+    assert_eq!(editor.cursor.index, 4);
+    assert!(editor.undo());
+    assert_eq!(editor.buffer.as_str(), "");
+    assert!(!editor.undo());
+
+    assert!(editor.redo());
+    assert_eq!(editor.buffer.as_str(), "one\n");
+    editor.insert_char('x');
+    assert!(!editor.can_redo());
+  }
+
+  #[test]
+  fn test_undo_records_changed_lines() {
+    let mut editor = Editor::new();
+    editor.paste("a\nb\nc\n");
+    editor.cursor.index = 2;
+    editor.delete_line();
+    editor.take_line_edits();
+
+    editor.undo();
+    assert_eq!(editor.buffer.as_str(), "a\nb\nc\n");
+    assert_eq!(
+      editor.take_line_edits(),
+      vec![LineEdit {
+        line_start: 1,
+        line_end: 1,
+        line_delta: 1,
+      }]
+    );
+  }
+
+  #[test]
+  fn test_replace_lines() {
+    let mut editor = Editor::new();
+    editor.paste("a\nb\nc\nd");
+    editor.select_range(2, 5);
+    editor.take_line_edits();
+
+    editor.replace_lines(1..3, "x\n");
+    assert_eq!(editor.buffer.as_str(), "a\nx\nd");
+    assert_eq!(editor.cursor.index, 2);
+    assert!(!editor.has_selection());
+
+    editor.replace_lines(2..3, "d\ne");
+    assert_eq!(editor.buffer.as_str(), "a\nx\nd\ne");
+    assert!(editor.undo());
+    assert!(editor.undo());
+    assert_eq!(editor.buffer.as_str(), "a\nb\nc\nd");
+  }
+
+  #[test]
+  fn test_apply_edits() {
+    let mut editor = Editor::new();
+    editor.paste("let a = 1;\nlet b = 2;\n");
+    editor.select_range(15, 19); // "b = "
+    let anchor = editor.create_anchor(11, Bias::Left); // Start of the second line
+    editor.take_line_edits();
+
+    editor
+      .apply_edits(vec![
+        Edit::new(15..16, "bb"),
+        Edit::new(4..5, "alpha"),
+        Edit::insert(0, "// x\n"),
+      ])
+      .unwrap();
+    assert_eq!(
+      editor.buffer.as_str(),
+      "// x\nlet alpha = 1;\nlet bb = 2;\n"
+    );
+    assert_eq!(editor.get_selected_text().as_deref(), Some("bb = "));
+    assert_eq!(editor.anchor_position(anchor), Some(20));
+    assert_eq!(
+      editor.take_line_edits(),
+      [LineEdit {
+        line_start: 0,
+        line_end: 1,
+        line_delta: 1,
+      }]
+    );
+
+    // One undo step
+    assert!(editor.undo());
+    assert_eq!(editor.buffer.as_str(), "let a = 1;\nlet b = 2;\n");
+  }
+
+  #[test]
+  fn test_apply_edits_rejects_overlaps() {
+    let mut editor = Editor::new();
+    editor.paste("hello");
+    let result = editor.apply_edits(vec![Edit::delete(0..3), Edit::new(2..4, "x")]);
+    assert_eq!(result, Err(EditError::Overlapping(0..3, 2..4)));
+    assert_eq!(editor.buffer.as_str(), "hello");
+    assert!(editor.apply_edits(vec![Edit::insert(6, "!")]).is_err());
+  }
+
+  #[test]
+  fn test_anchors_follow_edits() {
+    let mut editor = Editor::new();
+    editor.paste("fn a() {}\nfn b() {}");
+    let b = editor.create_anchor(13, Bias::Left); // "fn |b"
+
+    editor.cursor.index = 0;
+    editor.paste("// x\n");
+    assert_eq!(editor.anchor_position(b), Some(18));
+
+    editor.select_range(5, 18);
+    editor.indent_lines("  ");
+    assert_eq!(editor.buffer.as_str(), "// x\n  fn a() {}\n  fn b() {}");
+    assert_eq!(editor.anchor_position(b), Some(22));
+
+    assert!(editor.undo());
+    assert_eq!(editor.anchor_position(b), Some(18));
+    assert!(editor.undo());
+    assert_eq!(editor.anchor_position(b), Some(13));
+    assert!(editor.redo());
+    assert_eq!(editor.anchor_position(b), Some(18));
+
+    // Deleting the line it's on leaves it at the start of the deletion
+    editor.select_range(14, 24);
+    editor.delete_selection();
+    assert_eq!(editor.buffer.as_str(), "// x\nfn a() {}");
+    assert_eq!(editor.anchor_position(b), Some(14));
+    assert!(editor.remove_anchor(b));
+    assert_eq!(editor.anchor_position(b), None);
+  }
+
+  #[test]
+  fn test_duplicate_lines() {
+    let mut editor = Editor::new();
+    editor.paste("a\nb");
+    editor.cursor.index = 3; // "b|"
+
+    editor.duplicate_lines_down();
+    assert_eq!(editor.buffer.as_str(), "a\nb\nb");
+    assert_eq!(editor.cursor.index, 5);
+
+    editor.select_range(0, 3); // "a\nb"
+    editor.duplicate_lines_up();
+    assert_eq!(editor.buffer.as_str(), "a\nb\na\nb\nb");
+    assert_eq!(editor.selection_range(), Some(0..3));
+
+    assert!(editor.undo());
+    assert_eq!(editor.buffer.as_str(), "a\nb\nb");
+  }
+
+  #[test]
+  fn test_move_lines() {
+    let mut editor = Editor::new();
+    editor.paste("a\nb\nc");
+    editor.cursor.index = 5; // "c|"
+
+    editor.move_lines_up();
+    assert_eq!(editor.buffer.as_str(), "a\nc\nb");
+    assert_eq!(editor.cursor.index, 3);
+
+    editor.select_range(2, 5); // "c\nb"
+    editor.move_lines_up();
+    assert_eq!(editor.buffer.as_str(), "c\nb\na");
+    assert_eq!(editor.selection_range(), Some(0..3));
+
+    // Already at the top
+    editor.move_lines_up();
+    assert_eq!(editor.buffer.as_str(), "c\nb\na");
+
+    editor.clear_selection();
+    editor.cursor.index = 0;
+    editor.move_lines_down();
+    assert_eq!(editor.buffer.as_str(), "b\nc\na");
+    assert_eq!(editor.cursor.index, 2);
+
+    assert!(editor.undo());
+    assert_eq!(editor.buffer.as_str(), "c\nb\na");
+    assert_eq!(editor.cursor.index, 0);
+  }
+
+  #[test]
+  fn test_toggle_line_comments() {
+    let mut editor = Editor::new();
+    editor.paste("fn main() {\n    run();\n\n  stop();\n}");
+    editor.select_range(12, 33); // "run();" to "stop();"
+
+    editor.toggle_line_comments("//");
+    assert_eq!(
+      editor.buffer.as_str(),
+      "fn main() {\n  //   run();\n\n  // stop();\n}"
+    );
+    assert_eq!(editor.selection_range(), Some(12..39));
+
+    editor.toggle_line_comments("//");
+    assert_eq!(
+      editor.buffer.as_str(),
+      "fn main() {\n    run();\n\n  stop();\n}"
+    );
+    assert_eq!(editor.selection_range(), Some(12..33));
+  }
+
+  #[test]
+  fn test_toggle_line_comments_keeps_cursor_on_text() {
+    let mut editor = Editor::new();
+    editor.paste("  a = 1");
+    editor.cursor.index = 3; // "a| = 1"
+
+    editor.toggle_line_comments("#");
+    assert_eq!(editor.buffer.as_str(), "  # a = 1");
+    assert_eq!(editor.cursor.index, 5);
+
+    editor.toggle_line_comments("#");
+    assert_eq!(editor.buffer.as_str(), "  a = 1");
+    assert_eq!(editor.cursor.index, 3);
+  }
+
+  #[test]
+  fn test_toggle_block_comment() {
+    let mut editor = Editor::new();
+    editor.paste("a b c");
+    editor.select_range(2, 3); // "b"
+
+    editor.toggle_block_comment("/*", "*/");
+    assert_eq!(editor.buffer.as_str(), "a /* b */ c");
+    assert_eq!(editor.selection_range(), Some(2..9));
+
+    editor.toggle_block_comment("/*", "*/");
+    assert_eq!(editor.buffer.as_str(), "a b c");
+    assert_eq!(editor.selection_range(), Some(2..3));
+
+    // Without selection
+    editor.clear_selection();
+    editor.cursor.index = 5;
+    editor.toggle_block_comment("<!--", "-->");
+    assert_eq!(editor.buffer.as_str(), "a b c<!--  -->");
+    assert_eq!(editor.cursor.index, 10);
+  }
+
+  #[test]
+  fn test_trim_trailing_whitespace() {
+    let mut editor = Editor::new();
+    editor.paste("a  \n\t\n  b\t \nc");
+    editor.cursor.index = 13; // "|c"
+
+    assert!(editor.trim_trailing_whitespace());
+    assert_eq!(editor.buffer.as_str(), "a\n\n  b\nc");
+    assert_eq!(editor.cursor.index, 8);
+
+    assert!(!editor.trim_trailing_whitespace());
+    assert!(editor.undo());
+    assert_eq!(editor.buffer.as_str(), "a  \n\t\n  b\t \nc");
+  }
+
   #[test]
   fn test_paste() {
     let mut editor = Editor::new();
@@ -1133,6 +2607,34 @@ mod tests {
     assert_eq!(editor.buffer.as_str(), "Hello World");
   }
 
+  #[test]
+  fn test_paste_normalizes_line_endings() {
+    let mut editor = Editor::new();
+    editor.paste("a\r\nb\rc");
+    assert_eq!(editor.buffer.as_str(), "a\nb\nc");
+    assert_eq!(editor.cursor.index, 5);
+  }
+
+  #[test]
+  fn test_paste_records_one_edit() {
+    let mut editor = Editor::new();
+    editor.paste("a\nb");
+    editor.cursor.index = 1;
+    editor.take_line_edits();
+
+    editor.paste("1\n2\n3");
+    assert_eq!(editor.buffer.as_str(), "a1\n2\n3\nb");
+    assert_eq!(editor.cursor.index, 6);
+    assert_eq!(
+      editor.take_line_edits(),
+      vec![LineEdit {
+        line_start: 0,
+        line_end: 0,
+        line_delta: 2,
+      }]
+    );
+  }
+
   #[test]
   fn test_paste_replace_selection() {
     let mut editor = Editor::new();
@@ -1319,4 +2821,197 @@ mod tests {
     assert!(!editor.has_selection());
     assert_eq!(editor.selection_range(), None);
   }
+
+  #[test]
+  fn test_selected_line_range() {
+    let mut editor = Editor::new();
+    editor.paste("one\ntwo\nthree\n");
+
+    editor.cursor.index = 5;
+    assert_eq!(editor.selected_line_range(), 1..2);
+
+    editor.select_range(1, 9);
+    assert_eq!(editor.selected_line_range(), 0..3);
+
+    // Selection ending at the start of a line excludes it
+    editor.select_range(0, 8);
+    assert_eq!(editor.selected_line_range(), 0..2);
+    assert!(editor.has_multiline_selection());
+
+    editor.select_range(0, 2);
+    assert!(!editor.has_multiline_selection());
+  }
+
+  #[test]
+  fn test_indent_lines() {
+    let mut editor = Editor::new();
+    editor.paste("one\ntwo\nthree");
+    editor.cursor.index = 1;
+    editor.select_range(1, 6); // "ne\ntw"
+    editor.cursor.index = 6;
+
+    editor.indent_lines("  ");
+
+    assert_eq!(editor.buffer.as_str(), "  one\n  two\nthree");
+    assert_eq!(editor.selection_range(), Some(3..10));
+    assert_eq!(editor.cursor.index, 10);
+  }
+
+  #[test]
+  fn test_indent_line_without_selection() {
+    let mut editor = Editor::new();
+    editor.paste("one\ntwo");
+    editor.cursor.index = 5;
+
+    editor.indent_lines("\t");
+
+    assert_eq!(editor.buffer.as_str(), "one\n\ttwo");
+    assert_eq!(editor.cursor.index, 6);
+  }
+
+  #[test]
+  fn test_outdent_lines() {
+    let mut editor = Editor::new();
+    editor.paste("    one\n\ttwo\n three\nfour");
+    editor.select_range(0, editor.buffer.len());
+    editor.cursor.index = editor.buffer.len();
+
+    editor.outdent_lines(2);
+
+    assert_eq!(editor.buffer.as_str(), "  one\ntwo\nthree\nfour");
+    assert_eq!(editor.selection_range(), Some(0..editor.buffer.len()));
+  }
+
+  #[test]
+  fn test_outdent_keeps_cursor_in_content() {
+    let mut editor = Editor::new();
+    editor.paste("  abc");
+    editor.cursor.index = 1;
+
+    editor.outdent_lines(4);
+
+    assert_eq!(editor.buffer.as_str(), "abc");
+    assert_eq!(editor.cursor.index, 0);
+  }
+
+  #[test]
+  fn test_line_edits_insert() {
+    let mut editor = Editor::new();
+    editor.paste("ab\ncd");
+    editor.take_line_edits();
+
+    editor.cursor.index = 1;
+    editor.insert_char('x');
+    assert_eq!(
+      editor.take_line_edits(),
+      vec![LineEdit {
+        line_start: 0,
+        line_end: 0,
+        line_delta: 0
+      }]
+    );
+
+    editor.cursor.index = 5;
+    editor.paste("1\n2\n");
+    let edits = editor.take_line_edits();
+    assert_eq!(edits.iter().map(|e| e.line_delta).sum::<isize>(), 2);
+    assert!(edits.iter().all(|e| e.line_start >= 1));
+  }
+
+  #[test]
+  fn test_line_edits_delete() {
+    let mut editor = Editor::new();
+    editor.paste("one\ntwo\nthree");
+    editor.take_line_edits();
+
+    editor.select_range(2, 9); // "e\ntwo\nt"
+    editor.delete_selection();
+    assert_eq!(
+      editor.take_line_edits(),
+      vec![LineEdit {
+        line_start: 0,
+        line_end: 2,
+        line_delta: -2
+      }]
+    );
+
+    // Backspace at a line start joins it with the previous line
+    let mut editor = Editor::new();
+    editor.paste("a\nb");
+    editor.take_line_edits();
+    editor.backspace();
+    editor.backspace();
+    assert_eq!(
+      editor.take_line_edits(),
+      vec![
+        LineEdit {
+          line_start: 1,
+          line_end: 1,
+          line_delta: 0
+        },
+        LineEdit {
+          line_start: 0,
+          line_end: 1,
+          line_delta: -1
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_line_edits_indent() {
+    let mut editor = Editor::new();
+    editor.paste("a\nb\nc");
+    editor.take_line_edits();
+
+    editor.select_range(0, 3);
+    editor.indent_lines("  ");
+    assert_eq!(
+      editor.take_line_edits(),
+      vec![LineEdit {
+        line_start: 0,
+        line_end: 1,
+        line_delta: 0
+      }]
+    );
+    assert!(editor.take_line_edits().is_empty());
+  }
+
+  #[test]
+  fn test_select_to_by_char() {
+    let mut editor = Editor::new();
+    editor.paste("hello world");
+
+    editor.select_to(6..6, 2, SelectionGranularity::Char);
+    assert_eq!(editor.selection, Some(Selection::new(6, 2)));
+    assert_eq!(editor.cursor.index, 2);
+  }
+
+  #[test]
+  fn test_select_to_by_word() {
+    let mut editor = Editor::new();
+    editor.paste("one two three");
+
+    // Double click on "two", then drag into "three" and back into "one"
+    editor.select_to(4..7, 10, SelectionGranularity::Word);
+    assert_eq!(editor.selection_range(), Some(4..13));
+    assert_eq!(editor.cursor.index, 13);
+
+    editor.select_to(4..7, 1, SelectionGranularity::Word);
+    assert_eq!(editor.selection, Some(Selection::new(7, 0)));
+    assert_eq!(editor.cursor.index, 0);
+  }
+
+  #[test]
+  fn test_select_to_by_line() {
+    let mut editor = Editor::new();
+    editor.paste("a\nb\nc");
+
+    // Triple click on line 1, drag within it keeps the whole line
+    editor.select_to(2..4, 2, SelectionGranularity::Line);
+    assert_eq!(editor.selection_range(), Some(2..4));
+
+    editor.select_to(2..4, 5, SelectionGranularity::Line);
+    assert_eq!(editor.selection_range(), Some(2..5));
+  }
 }
@@ -0,0 +1,132 @@
+use std::fmt;
+use std::ops::Range;
+
+/// Replacement of a char range of the buffer, like a formatter change or an LSP text edit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+  pub range: Range<usize>, // Chars of the buffer before any edit of the batch
+  pub new_text: String,
+}
+
+impl Edit {
+  pub fn new(range: Range<usize>, new_text: impl Into<String>) -> Self {
+    Self {
+      range,
+      new_text: new_text.into(),
+    }
+  }
+
+  pub fn insert(index: usize, text: impl Into<String>) -> Self {
+    Self::new(index..index, text)
+  }
+
+  pub fn delete(range: Range<usize>) -> Self {
+    Self::new(range, "")
+  }
+}
+
+/// Why a batch of edits was rejected, the buffer is left untouched then
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+  InvalidRange(Range<usize>),              // Ends before it starts
+  OutOfBounds(Range<usize>),               // Past the end of the buffer
+  Overlapping(Range<usize>, Range<usize>), // Two edits replace some of the same chars
+}
+
+impl fmt::Display for EditError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::InvalidRange(range) => write!(f, "invalid edit range {:?}", range),
+      Self::OutOfBounds(range) => write!(f, "edit range {:?} is out of the buffer", range),
+      Self::Overlapping(a, b) => write!(f, "edit ranges {:?} and {:?} overlap", a, b),
+    }
+  }
+}
+
+impl std::error::Error for EditError {}
+
+/// Sorts the edits by position, insertions at the same index keep their order
+pub(crate) fn sort_edits(mut edits: Vec<Edit>, buffer_len: usize) -> Result<Vec<Edit>, EditError> {
+  for edit in &edits {
+    if edit.range.start > edit.range.end {
+      return Err(EditError::InvalidRange(edit.range.clone()));
+    }
+    if edit.range.end > buffer_len {
+      return Err(EditError::OutOfBounds(edit.range.clone()));
+    }
+  }
+  edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+  for pair in edits.windows(2) {
+    if pair[1].range.start < pair[0].range.end {
+      return Err(EditError::Overlapping(
+        pair[0].range.clone(),
+        pair[1].range.clone(),
+      ));
+    }
+  }
+  Ok(edits)
+}
+
+/// Where a char index of the buffer ends up once the sorted edits are applied
+///
+/// Indices in a replaced range go to the end of its replacement, indices at the start of an
+/// edit stay before its new text
+pub(crate) fn map_index(index: usize, edits: &[Edit]) -> usize {
+  let mut shift = 0isize;
+  for edit in edits {
+    let new_len = edit.new_text.chars().count();
+    if index <= edit.range.start {
+      break;
+    }
+    if index < edit.range.end {
+      return (edit.range.start as isize + shift) as usize + new_len;
+    }
+    shift += new_len as isize - edit.range.len() as isize;
+  }
+  index.saturating_add_signed(shift)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_sort_edits() {
+    let edits = vec![
+      Edit::new(6..8, "x"),
+      Edit::insert(0, "a"),
+      Edit::insert(0, "b"),
+      Edit::delete(2..6),
+    ];
+    let sorted = sort_edits(edits, 10).unwrap();
+    let ranges: Vec<_> = sorted.iter().map(|edit| edit.range.clone()).collect();
+    assert_eq!(ranges, [0..0, 0..0, 2..6, 6..8]);
+    assert_eq!(sorted[0].new_text, "a");
+
+    assert_eq!(
+      sort_edits(vec![Edit::delete(8..11)], 10),
+      Err(EditError::OutOfBounds(8..11))
+    );
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = Edit::delete(4..2);
+    assert_eq!(
+      sort_edits(vec![reversed], 10),
+      Err(EditError::InvalidRange(4..2))
+    );
+    assert_eq!(
+      sort_edits(vec![Edit::delete(4..6), Edit::new(2..5, "x")], 10),
+      Err(EditError::Overlapping(2..5, 4..6))
+    );
+  }
+
+  #[test]
+  fn test_map_index() {
+    // "hello world" with "hello" replaced by "hi" and "!" added at the end
+    let edits = vec![Edit::new(0..5, "hi"), Edit::insert(11, "!")];
+    assert_eq!(map_index(0, &edits), 0);
+    assert_eq!(map_index(3, &edits), 2);
+    assert_eq!(map_index(5, &edits), 2);
+    assert_eq!(map_index(6, &edits), 3);
+    assert_eq!(map_index(11, &edits), 8);
+  }
+}
@@ -0,0 +1,149 @@
+use crate::editor::Selection;
+use text::TextBuffer;
+
+/// Editor state an undo or redo step goes back to
+///
+/// Buffers are ropes, a snapshot shares the unchanged text with the live buffer
+#[derive(Debug, Clone)]
+pub(crate) struct Snapshot {
+  pub buffer: TextBuffer,
+  pub cursor_index: usize,
+  pub selection: Option<Selection>,
+}
+
+/// Undo and redo stacks of editor snapshots, each undo step stores the state before it
+#[derive(Debug, Default)]
+pub(crate) struct History {
+  undo: Vec<Snapshot>,
+  redo: Vec<Snapshot>,
+  pending: Option<Snapshot>, // State before the open transaction
+  depth: usize,              // Nesting of open transactions, only the outermost one makes a step
+  changed: bool,             // Whether the open transaction edited the buffer
+  typing_at: Option<usize>,  // Cursor index after the last typed character
+}
+
+impl History {
+  pub fn is_open(&self) -> bool {
+    self.depth > 0
+  }
+
+  /// Opens a transaction, `snapshot` is only needed by the outermost one
+  pub fn begin(&mut self, snapshot: Option<Snapshot>) {
+    if self.depth == 0 {
+      self.pending = snapshot;
+      self.changed = false;
+    }
+    self.depth += 1;
+  }
+
+  pub fn mark_changed(&mut self) {
+    self.changed = true;
+  }
+
+  /// Closes a transaction, the outermost one becomes an undo step if it edited the buffer
+  ///
+  /// Characters typed right after the previous ones join its step, so undo removes whole runs of typing
+  pub fn end(&mut self, typed: bool, cursor_index: usize) {
+    self.depth -= 1;
+    if self.depth > 0 {
+      return;
+    }
+
+    let Some(snapshot) = self.pending.take() else {
+      return;
+    };
+    if !self.changed {
+      return;
+    }
+
+    let extends_typing = typed
+      && snapshot.selection.is_none()
+      && self.typing_at == Some(snapshot.cursor_index)
+      && !self.undo.is_empty();
+    if !extends_typing {
+      self.undo.push(snapshot);
+    }
+    self.redo.clear();
+    self.typing_at = typed.then_some(cursor_index);
+  }
+
+  /// Swaps the last undo step with the current state, None when there is nothing to undo
+  pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+    let snapshot = self.undo.pop()?;
+    self.redo.push(current);
+    self.typing_at = None;
+    Some(snapshot)
+  }
+
+  pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+    let snapshot = self.redo.pop()?;
+    self.undo.push(current);
+    self.typing_at = None;
+    Some(snapshot)
+  }
+
+  pub fn can_undo(&self) -> bool {
+    !self.undo.is_empty()
+  }
+
+  pub fn can_redo(&self) -> bool {
+    !self.redo.is_empty()
+  }
+
+  pub fn clear(&mut self) {
+    self.undo.clear();
+    self.redo.clear();
+    self.typing_at = None;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn snapshot(text: &str, cursor_index: usize) -> Snapshot {
+    let mut buffer = TextBuffer::new();
+    buffer.insert(0, text);
+    Snapshot {
+      buffer,
+      cursor_index,
+      selection: None,
+    }
+  }
+
+  #[test]
+  fn test_only_outermost_edited_transaction_makes_a_step() {
+    let mut history = History::default();
+
+    history.begin(Some(snapshot("", 0)));
+    history.begin(None);
+    history.mark_changed();
+    history.end(false, 1);
+    history.end(false, 1);
+
+    // Nothing edited
+    history.begin(Some(snapshot("a", 1)));
+    history.end(false, 1);
+
+    let undone = history.undo(snapshot("a", 1)).unwrap();
+    assert_eq!(undone.buffer.as_str(), "");
+    assert!(!history.can_undo());
+    assert!(history.can_redo());
+  }
+
+  #[test]
+  fn test_consecutive_typing_joins_one_step() {
+    let mut history = History::default();
+    for (index, text) in ["", "a", "ab"].iter().enumerate() {
+      history.begin(Some(snapshot(text, index)));
+      history.mark_changed();
+      history.end(true, index + 1);
+    }
+
+    assert_eq!(
+      history.undo(snapshot("abc", 3)).unwrap().buffer.as_str(),
+      ""
+    );
+    assert!(!history.can_undo());
+  }
+}
@@ -1,5 +1,15 @@
-mod diff;
+mod anchors;
+mod display_map;
+mod edit_log;
 mod editor;
+mod edits;
+mod history;
+mod occurrences;
+mod scopes;
 
-pub use diff::{CharRange, DiffLine, DiffLineKind, Differ};
-pub use editor::Editor;
+pub use anchors::{Anchor, AnchorSet, Bias};
+pub use display_map::{DisplayMap, RowContent, RowPosition};
+pub use edit_log::{EditCause, EditEvent, EditLog};
+pub use editor::{ClipboardText, Editor, LineEdit, Selection, SelectionGranularity};
+pub use edits::{Edit, EditError};
+pub use occurrences::{OccurrenceQuery, find_occurrences};
@@ -0,0 +1,148 @@
+use cursor::Cursor;
+use std::ops::Range;
+use text::TextBuffer;
+
+/// What to look for when highlighting occurrences
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OccurrenceQuery {
+  pub text: String,
+  pub whole_word: bool, // Only match where `text` isn't part of a longer word
+}
+
+impl OccurrenceQuery {
+  /// The selected text when it fits on one line, otherwise the word at or
+  /// right before the cursor
+  pub fn at(
+    buffer: &TextBuffer,
+    cursor_index: usize,
+    selection: Option<Range<usize>>,
+  ) -> Option<Self> {
+    if let Some(range) = selection.filter(|range| !range.is_empty()) {
+      let text: String = buffer
+        .as_str()
+        .chars()
+        .skip(range.start)
+        .take(range.len())
+        .collect();
+      if text.contains('\n') || text.trim().is_empty() {
+        return None;
+      }
+      let whole_word = text.chars().all(Cursor::is_word_char);
+      return Some(Self { text, whole_word });
+    }
+
+    // The cursor right after a word still highlights it
+    [cursor_index, cursor_index.saturating_sub(1)]
+      .into_iter()
+      .find_map(|index| word_at(buffer, index))
+      .map(|text| Self {
+        text,
+        whole_word: true,
+      })
+  }
+
+  /// Char ranges of every occurrence in the buffer
+  pub fn find(&self, buffer: &TextBuffer) -> Vec<Range<usize>> {
+    find_occurrences(&buffer.as_str(), &self.text, self.whole_word)
+  }
+}
+
+fn word_at(buffer: &TextBuffer, index: usize) -> Option<String> {
+  if index >= buffer.len() {
+    return None;
+  }
+  let (start, end) = Cursor::find_word_boundaries(buffer, index);
+  let word: String = buffer
+    .as_str()
+    .chars()
+    .skip(start)
+    .take(end - start)
+    .collect();
+  (!word.is_empty() && word.chars().all(Cursor::is_word_char)).then_some(word)
+}
+
+/// Char ranges of the non-overlapping occurrences of `query` in `text`
+pub fn find_occurrences(text: &str, query: &str, whole_word: bool) -> Vec<Range<usize>> {
+  if query.is_empty() {
+    return Vec::new();
+  }
+
+  let chars: Vec<char> = text.chars().collect();
+  let query: Vec<char> = query.chars().collect();
+  let is_word_at = |index: usize| chars.get(index).is_some_and(|c| Cursor::is_word_char(*c));
+
+  let mut occurrences = Vec::new();
+  let mut index = 0;
+  while index + query.len() <= chars.len() {
+    let end = index + query.len();
+    let matches = chars[index..end] == query[..]
+      && (!whole_word || !((index > 0 && is_word_at(index - 1)) || is_word_at(end)));
+
+    if matches {
+      occurrences.push(index..end);
+      index = end;
+    } else {
+      index += 1;
+    }
+  }
+  occurrences
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn buffer(text: &str) -> TextBuffer {
+    let mut buffer = TextBuffer::new();
+    buffer.insert(0, text);
+    buffer
+  }
+
+  #[test]
+  fn test_find_whole_word_occurrences() {
+    let text = "let foo = foo_bar + foo;\nfoo()";
+    assert_eq!(
+      find_occurrences(text, "foo", true),
+      vec![4..7, 20..23, 25..28]
+    );
+  }
+
+  #[test]
+  fn test_find_occurrences_inside_words() {
+    assert_eq!(find_occurrences("aaaa", "aa", false), vec![0..2, 2..4]);
+    assert_eq!(find_occurrences("é+é", "é", false), vec![0..1, 2..3]);
+    assert!(find_occurrences("abc", "", false).is_empty());
+  }
+
+  #[test]
+  fn test_query_from_word_under_cursor() {
+    let buffer = buffer("foo bar foo");
+
+    let query = OccurrenceQuery::at(&buffer, 1, None).unwrap();
+    assert_eq!(query.text, "foo");
+    assert!(query.whole_word);
+    assert_eq!(query.find(&buffer), vec![0..3, 8..11]);
+
+    // Right after the word
+    assert_eq!(OccurrenceQuery::at(&buffer, 7, None).unwrap().text, "bar");
+  }
+
+  #[test]
+  fn test_query_from_selection() {
+    let buffer = buffer("a.b a.b\nx");
+
+    let query = OccurrenceQuery::at(&buffer, 3, Some(0..3)).unwrap();
+    assert_eq!(query.text, "a.b");
+    assert!(!query.whole_word);
+
+    // Multi-line and blank selections don't highlight anything
+    assert!(OccurrenceQuery::at(&buffer, 9, Some(4..9)).is_none());
+    assert!(OccurrenceQuery::at(&buffer, 4, Some(3..4)).is_none());
+  }
+
+  #[test]
+  fn test_no_query_outside_words() {
+    let buffer = buffer("a  + b");
+    assert!(OccurrenceQuery::at(&buffer, 2, None).is_none());
+  }
+}
@@ -0,0 +1,284 @@
+use cursor::WordChars;
+use std::ops::Range;
+
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+const QUOTES: [char; 3] = ['"', '\'', '`'];
+
+/// Smallest scope of `text` strictly containing the char range `range`, None once it's the
+/// whole text
+///
+/// Scopes grow from the word at the cursor to the content of each enclosing pair of brackets
+/// or quotes then the pair itself, the lines covered, each enclosing indentation block with
+/// its header line, and the whole text
+pub fn enclosing_scope(
+  text: &str,
+  range: Range<usize>,
+  word_chars: &WordChars,
+) -> Option<Range<usize>> {
+  let chars: Vec<char> = text.chars().collect();
+  let contains = |scope: &Range<usize>| {
+    scope.start <= range.start && range.end <= scope.end && scope.len() > range.len()
+  };
+
+  let mut scopes = Vec::new();
+  if range.is_empty() {
+    scopes.extend(word_at(&chars, range.start, word_chars));
+  }
+  for (open, close) in pairs(&chars) {
+    scopes.push(open + 1..close);
+    scopes.push(open..close + 1);
+  }
+  let lines = line_ranges(&chars);
+  let first = line_at(&lines, range.start);
+  let last = line_at(&lines, range.end);
+  scopes.push(lines[first].start..lines[last].end);
+  scopes.extend(indent_blocks(&chars, &lines, first..last + 1));
+  scopes.push(0..chars.len());
+
+  scopes
+    .into_iter()
+    .filter(contains)
+    .min_by_key(|scope| scope.len())
+}
+
+/// Word touching `index`, the cursor right after a word still selects it
+fn word_at(chars: &[char], index: usize, word_chars: &WordChars) -> Option<Range<usize>> {
+  let is_word = |index: usize| {
+    chars
+      .get(index)
+      .is_some_and(|c| word_chars.is_word_char(*c))
+  };
+  let index = if is_word(index) {
+    index
+  } else if index > 0 && is_word(index - 1) {
+    index - 1
+  } else {
+    return None;
+  };
+
+  let mut start = index;
+  while start > 0 && is_word(start - 1) {
+    start -= 1;
+  }
+  let mut end = index + 1;
+  while is_word(end) {
+    end += 1;
+  }
+  Some(start..end)
+}
+
+/// Indices of matching brackets anywhere in the text, and of matching quotes on a line,
+/// brackets inside quotes are skipped
+fn pairs(chars: &[char]) -> Vec<(usize, usize)> {
+  let mut pairs = Vec::new();
+  let mut open_brackets: Vec<(char, usize)> = Vec::new();
+  let mut quote: Option<(char, usize)> = None;
+  let mut escaped = false;
+
+  for (index, &c) in chars.iter().enumerate() {
+    if let Some((open, start)) = quote {
+      if escaped {
+        escaped = false;
+      } else if c == '\\' {
+        escaped = true;
+      } else if c == open {
+        pairs.push((start, index));
+        quote = None;
+      } else if c == '\n' {
+        quote = None; // Unterminated, like an apostrophe in a comment
+      }
+      continue;
+    }
+
+    if QUOTES.contains(&c) {
+      quote = Some((c, index));
+    } else if BRACKETS.iter().any(|(open, _)| *open == c) {
+      open_brackets.push((c, index));
+    } else if let Some((open, _)) = BRACKETS.iter().find(|(_, close)| *close == c) {
+      // An unmatched closing bracket closes nothing
+      if let Some(position) = open_brackets.iter().rposition(|(c, _)| c == open) {
+        let (_, start) = open_brackets[position];
+        open_brackets.truncate(position);
+        pairs.push((start, index));
+      }
+    }
+  }
+  pairs
+}
+
+/// Char ranges of the lines, newlines excluded
+fn line_ranges(chars: &[char]) -> Vec<Range<usize>> {
+  let mut lines = Vec::new();
+  let mut start = 0;
+  for (index, &c) in chars.iter().enumerate() {
+    if c == '\n' {
+      lines.push(start..index);
+      start = index + 1;
+    }
+  }
+  lines.push(start..chars.len());
+  lines
+}
+
+fn line_at(lines: &[Range<usize>], index: usize) -> usize {
+  lines
+    .iter()
+    .position(|line| index <= line.end)
+    .unwrap_or(lines.len() - 1)
+}
+
+/// Blocks of lines indented at least as much as `selected`, at each level down to the least
+/// indented one, with the line introducing them and the line closing them when there's one
+fn indent_blocks(
+  chars: &[char],
+  lines: &[Range<usize>],
+  selected: Range<usize>,
+) -> Vec<Range<usize>> {
+  let indent = |line: usize| {
+    let text = &chars[lines[line].clone()];
+    let width = text.iter().take_while(|c| c.is_whitespace()).count();
+    (width < text.len()).then_some(width) // Blank lines belong to any block
+  };
+  let starts_with_closing = |line: usize| {
+    chars[lines[line].clone()]
+      .iter()
+      .find(|c| !c.is_whitespace())
+      .is_some_and(|c| BRACKETS.iter().any(|(_, close)| close == c))
+  };
+
+  let Some(min_indent) = selected.clone().filter_map(indent).min() else {
+    return Vec::new();
+  };
+
+  let mut blocks = Vec::new();
+  for level in (0..=min_indent).rev() {
+    let inside = |line: usize| indent(line).is_none_or(|width| width >= level);
+    let mut first = selected.start;
+    while first > 0 && inside(first - 1) {
+      first -= 1;
+    }
+    let mut last = selected.end - 1;
+    while last + 1 < lines.len() && inside(last + 1) {
+      last += 1;
+    }
+    // Blank lines around the block aren't part of it
+    while first < selected.start && indent(first).is_none() {
+      first += 1;
+    }
+    while last + 1 > selected.end && indent(last).is_none() {
+      last -= 1;
+    }
+    blocks.push(lines[first].start..lines[last].end);
+
+    if first > 0 {
+      let header = first - 1;
+      let footer = (last + 1 < lines.len() && starts_with_closing(last + 1)).then_some(last + 1);
+      blocks.push(lines[header].start..lines[footer.unwrap_or(last)].end);
+    }
+  }
+  blocks
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Char range of the first occurrence of `part` in `text`
+  fn range_of(text: &str, part: &str) -> Range<usize> {
+    let start = text[..text.find(part).unwrap()].chars().count();
+    start..start + part.chars().count()
+  }
+
+  fn expand(text: &str, range: Range<usize>) -> String {
+    let scope = enclosing_scope(text, range, &WordChars::default()).unwrap();
+    text.chars().skip(scope.start).take(scope.len()).collect()
+  }
+
+  #[test]
+  fn test_word_then_brackets() {
+    let text = "call(a, [first, second])";
+    let cursor = range_of(text, "second").start + 2;
+
+    let mut range = cursor..cursor;
+    let mut scopes = Vec::new();
+    while let Some(scope) = enclosing_scope(text, range.clone(), &WordChars::default()) {
+      scopes.push(
+        text
+          .chars()
+          .skip(scope.start)
+          .take(scope.len())
+          .collect::<String>(),
+      );
+      range = scope;
+    }
+    assert_eq!(
+      scopes,
+      [
+        "second",
+        "first, second",
+        "[first, second]",
+        "a, [first, second]",
+        "(a, [first, second])",
+        "call(a, [first, second])",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_quotes() {
+    let text = r#"say("it's \"fine\"", 'x')"#;
+    let cursor = range_of(text, "fine").start;
+    assert_eq!(expand(text, cursor..cursor), "fine");
+    assert_eq!(expand(text, range_of(text, "fine")), r#"it's \"fine\""#);
+    assert_eq!(
+      expand(text, range_of(text, r#"it's \"fine\""#)),
+      r#""it's \"fine\"""#
+    );
+  }
+
+  #[test]
+  fn test_brackets_in_quotes_are_skipped() {
+    let text = "f(\")\", x)";
+    let cursor = range_of(text, "x").start;
+    assert_eq!(expand(text, range_of(text, "x")), "\")\", x");
+    assert_eq!(expand(text, cursor..cursor), "x");
+  }
+
+  #[test]
+  fn test_lines_then_indent_blocks() {
+    let text = "fn main() {\n  if ok {\n    run();\n\n    stop();\n  }\n}\n";
+    let run = range_of(text, "run();");
+
+    assert_eq!(expand(text, run), "    run();");
+    assert_eq!(
+      expand(text, range_of(text, "    run();")),
+      "    run();\n\n    stop();"
+    );
+    assert_eq!(
+      expand(text, range_of(text, "    run();\n\n    stop();")),
+      "\n    run();\n\n    stop();\n  "
+    );
+
+    // Without brackets, lines grow into indentation blocks
+    let text = "def main():\n  if ok:\n    run()\n\n    stop()\n  done()\n";
+    assert_eq!(
+      expand(text, range_of(text, "    run()")),
+      "    run()\n\n    stop()"
+    );
+    assert_eq!(
+      expand(text, range_of(text, "    run()\n\n    stop()")),
+      "  if ok:\n    run()\n\n    stop()"
+    );
+    assert_eq!(
+      expand(text, range_of(text, "  if ok:\n    run()\n\n    stop()")),
+      "  if ok:\n    run()\n\n    stop()\n  done()"
+    );
+  }
+
+  #[test]
+  fn test_whole_text_is_last() {
+    let text = "a\nb";
+    assert_eq!(expand(text, 0..1), "a\nb");
+    assert_eq!(enclosing_scope(text, 0..3, &WordChars::default()), None);
+  }
+}
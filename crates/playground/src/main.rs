@@ -1,27 +1,36 @@
-use gpui::{App, Application, Bounds, WindowBounds, WindowOptions, prelude::*, px, size};
+use gpui::{App, Application, WindowOptions, prelude::*};
 
 use std::path::PathBuf;
+mod session;
 mod workspace;
+use session::Session;
 use workspace::Workspace;
 
 fn main() {
   Application::new().run(|cx: &mut App| {
-    let bounds = Bounds::centered(None, size(px(1200.0), px(800.0)), cx);
-
     Workspace::register(cx);
 
-    let project_path = PathBuf::from("/Users/joris/workspace/git-playground/src");
-    let compare_file_path = PathBuf::from("/Users/joris/workspace/git-playground/src/AppOld.vue");
-
-    let compare_content =
-      std::fs::read_to_string(&compare_file_path).expect("Failed to read compare file");
+    // `playground [project dir] [file to compare with]` overrides the last session
+    let session_path = Session::default_path();
+    let mut session = session_path
+      .as_deref()
+      .map(Session::load)
+      .unwrap_or_default();
+    let mut args = std::env::args().skip(1).map(PathBuf::from);
+    if let Some(project_path) = args.next() {
+      session.project_path = Some(project_path);
+      session.selected_file = None;
+    }
+    if let Some(compare_file_path) = args.next() {
+      session.compare_file_path = Some(compare_file_path);
+    }
 
     cx.open_window(
       WindowOptions {
-        window_bounds: Some(WindowBounds::Windowed(bounds)),
+        window_bounds: Some(session.window_bounds(cx)),
         ..Default::default()
       },
-      |_, cx| cx.new(|cx| Workspace::new(project_path, compare_content, cx)),
+      |window, cx| cx.new(|cx| Workspace::new(session, session_path, window, cx)),
     )
     .unwrap();
 
@@ -0,0 +1,97 @@
+use gpui::{App, Bounds, WindowBounds, point, px, size};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_WINDOW_WIDTH: f32 = 1200.0;
+const DEFAULT_WINDOW_HEIGHT: f32 = 800.0;
+
+/// What the playground reopens on launch, kept in `~/.config/rediff/session.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+  pub project_path: Option<PathBuf>, // Directory whose files are listed
+  pub compare_file_path: Option<PathBuf>, // File the open file is diffed against
+  pub selected_file: Option<PathBuf>,
+  pub dark_mode: Option<bool>, // None follows the OS appearance
+  pub window: Option<SessionWindow>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SessionWindow {
+  pub x: f32,
+  pub y: f32,
+  pub width: f32,
+  pub height: f32,
+  pub maximized: bool, // The bounds are then the ones it's restored to
+}
+
+impl Session {
+  /// Under `$XDG_CONFIG_HOME`, or `~/.config` when it's not set
+  pub fn default_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+      .filter(|dir| !dir.is_empty())
+      .map(PathBuf::from)
+      .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("rediff").join("session.toml"))
+  }
+
+  /// The saved session, an empty one when there's none or it can't be read
+  pub fn load(path: &Path) -> Self {
+    let text = match fs::read_to_string(path) {
+      Ok(text) => text,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Self::default(),
+      Err(e) => {
+        eprintln!("Failed to read the session {:?}: {}", path, e);
+        return Self::default();
+      }
+    };
+    toml::from_str(&text).unwrap_or_else(|e| {
+      eprintln!("Failed to parse the session {:?}: {}", path, e);
+      Self::default()
+    })
+  }
+
+  pub fn save(&self, path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    let text = toml::to_string(self).map_err(io::Error::other)?;
+    fs::write(path, text)
+  }
+
+  /// Saved window bounds, or a window centered on the main display
+  pub fn window_bounds(&self, cx: &App) -> WindowBounds {
+    let Some(window) = self.window else {
+      return WindowBounds::Windowed(Bounds::centered(
+        None,
+        size(px(DEFAULT_WINDOW_WIDTH), px(DEFAULT_WINDOW_HEIGHT)),
+        cx,
+      ));
+    };
+    let bounds = Bounds::new(
+      point(px(window.x), px(window.y)),
+      size(px(window.width), px(window.height)),
+    );
+    if window.maximized {
+      WindowBounds::Maximized(bounds)
+    } else {
+      WindowBounds::Windowed(bounds)
+    }
+  }
+
+  pub fn set_window_bounds(&mut self, bounds: WindowBounds) {
+    let (restored, maximized) = match bounds {
+      WindowBounds::Windowed(bounds) => (bounds, false),
+      WindowBounds::Maximized(bounds) | WindowBounds::Fullscreen(bounds) => (bounds, true),
+    };
+    self.window = Some(SessionWindow {
+      x: restored.origin.x.into(),
+      y: restored.origin.y.into(),
+      width: restored.size.width.into(),
+      height: restored.size.height.into(),
+      maximized,
+    });
+  }
+}
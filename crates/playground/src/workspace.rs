@@ -3,15 +3,23 @@ use gpui::{
   rgb, white,
 };
 
-use rediff::{DiffEditor, EditorConfig};
+use crate::session::Session;
+use rediff::{
+  DiffEditor, DiffEditorEvent, DiffOptions, EditorConfig, FileStatus, FileTree, FileTreeEvent,
+  KeymapPlatform, ThemeMode, WhitespaceMode,
+};
 use std::path::PathBuf;
 
 actions!(playground, [Quit]);
 
 pub struct Workspace {
   editor: Entity<DiffEditor>,
-  files: Vec<PathBuf>,
+  file_tree: Entity<FileTree>,
   dark_mode: bool,
+  ignore_whitespace: bool,
+  align_hunks: bool,
+  session: Session,
+  session_path: Option<PathBuf>, // None when there's no config directory to save it in
 }
 
 const GRAY_COLOR: Hsla = Hsla {
@@ -22,47 +30,177 @@ const GRAY_COLOR: Hsla = Hsla {
 };
 
 impl Workspace {
-  pub fn new(path: PathBuf, compare_content: String, cx: &mut Context<Self>) -> Self {
-    let files: Vec<PathBuf> = std::fs::read_dir(&path)
-      .ok()
-      .map(|entries| {
-        entries
-          .filter_map(|entry| entry.ok())
-          .filter(|entry| entry.file_type().ok().is_some_and(|ft| ft.is_file()))
-          .map(|entry| entry.path())
-          .collect()
-      })
-      .unwrap_or_default();
-
-    let first_path = if files.is_empty() {
-      panic!("No files found in the provided path: {:?}", path);
-    } else {
-      files[0].clone()
-    };
+  pub fn new(
+    session: Session,
+    session_path: Option<PathBuf>,
+    window: &mut Window,
+    cx: &mut Context<Self>,
+  ) -> Self {
+    let project_path = session.project_path.clone().unwrap_or_default();
 
-    let editor = cx.new(|cx| {
-      DiffEditor::new(
-        first_path.clone(),
-        compare_content.clone(),
-        EditorConfig {
-          ..Default::default()
-        },
-        cx,
-      )
+    let config = EditorConfig {
+      collapse_unchanged: Some(10),
+      sticky_header: true,
+      status_bar: true,
+      minimap: true,
+      reload_on_focus: true,
+      theme_mode: match session.dark_mode {
+        Some(true) => ThemeMode::Dark,
+        Some(false) => ThemeMode::Light,
+        None => ThemeMode::Auto,
+      },
+      ..Default::default()
+    };
+    let theme = config
+      .get_theme(config.theme_mode == ThemeMode::Dark)
+      .clone();
+    let file_tree = cx.new(|_cx| FileTree::new(&project_path, theme));
+    // The file selected last time if it's still there, or the first file of the project
+    let selected_file = session
+      .selected_file
+      .clone()
+      .filter(|path| path.starts_with(&project_path) && path.is_file())
+      .or_else(|| {
+        let entries = file_tree.read(cx).model().entries();
+        let first_file = entries.into_iter().find(|entry| !entry.is_dir)?;
+        Some(first_file.path)
+      });
+    let editor = match &selected_file {
+      Some(selected_file) => {
+        let builder = DiffEditor::builder().file(selected_file).config(config);
+        match &session.compare_file_path {
+          Some(compare_file_path) => builder.compare_with_file(compare_file_path),
+          None => builder,
+        }
+        .build(cx)
+        .unwrap_or_else(|e| panic!("Can't open the editor: {}", e))
+      }
+      // Shows a placeholder, there's nothing to open
+      None => cx.new(|cx| DiffEditor::empty(config, cx)),
+    };
+    editor.update(cx, |editor, _cx| {
+      editor.on_load_error(|error, _cx| eprintln!("{}", error.label()));
     });
-
-    editor.as_mut(cx).toggle_dark_mode();
+    cx.subscribe(&editor, Self::on_editor_event).detach();
+    cx.subscribe(&file_tree, Self::on_file_tree_event).detach();
+    if let Some(selected_file) = &selected_file {
+      file_tree.update(cx, |file_tree, cx| file_tree.select(selected_file, cx));
+    }
+    cx.observe_window_bounds(window, |this, window, cx| {
+      this.session.set_window_bounds(window.window_bounds());
+      this.save_session(cx);
+    })
+    .detach();
 
     Self {
       editor,
-      files,
-      dark_mode: true,
+      file_tree,
+      dark_mode: session.dark_mode.unwrap_or(false), // Follows the editor, which follows the OS appearance
+      ignore_whitespace: false,
+      align_hunks: false,
+      session,
+      session_path,
+    }
+  }
+
+  /// Writes the project, the selected file and the appearance to the session file
+  fn save_session(&mut self, cx: &mut Context<Self>) {
+    let Some(session_path) = &self.session_path else {
+      return;
+    };
+    let file_path = self.editor.read(cx).file_path.clone();
+    self.session.selected_file = (!file_path.as_os_str().is_empty()).then_some(file_path);
+    if let Err(e) = self.session.save(session_path) {
+      eprintln!("Failed to save the session {:?}: {}", session_path, e);
+    }
+  }
+
+  fn on_editor_event(
+    &mut self,
+    editor: Entity<DiffEditor>,
+    event: &DiffEditorEvent,
+    cx: &mut Context<Self>,
+  ) {
+    match event {
+      // The tree colors the open file by its diff against the compared file
+      DiffEditorEvent::DiffStatsChanged { added, removed } => {
+        let path = editor.read(cx).file_path.clone();
+        let status = (added + removed > 0).then_some(FileStatus::Modified);
+        self
+          .file_tree
+          .update(cx, |file_tree, cx| file_tree.set_status(&path, status, cx));
+      }
+      DiffEditorEvent::AppearanceChanged { dark_mode } => {
+        self.dark_mode = *dark_mode;
+        let theme = editor.read(cx).get_theme().clone();
+        self
+          .file_tree
+          .update(cx, |file_tree, cx| file_tree.set_theme(theme, cx));
+      }
+      DiffEditorEvent::Edited
+      | DiffEditorEvent::Saved
+      | DiffEditorEvent::DirtyChanged(_)
+      | DiffEditorEvent::Zoomed
+      | DiffEditorEvent::ReviewChanged { .. }
+      | DiffEditorEvent::Scrolled
+      | DiffEditorEvent::Focused
+      | DiffEditorEvent::Blurred => return,
+    }
+    cx.notify();
+  }
+
+  fn on_file_tree_event(
+    &mut self,
+    _file_tree: Entity<FileTree>,
+    event: &FileTreeEvent,
+    cx: &mut Context<Self>,
+  ) {
+    match event {
+      FileTreeEvent::Opened(path) => {
+        self
+          .editor
+          .update(cx, |editor, cx| editor.open(path.clone(), cx));
+        self.save_session(cx);
+      }
     }
   }
 
   fn toggle_dark_mode(&mut self, cx: &mut Context<Self>) {
-    self.dark_mode = !self.dark_mode;
-    self.editor.as_mut(cx).toggle_dark_mode();
+    self
+      .editor
+      .update(cx, |editor, cx| editor.toggle_dark_mode(cx));
+    self.session.dark_mode = Some(self.editor.read(cx).theme_mode() == ThemeMode::Dark);
+    self.save_session(cx);
+  }
+
+  fn toggle_ignore_whitespace(&mut self, cx: &mut Context<Self>) {
+    self.ignore_whitespace = !self.ignore_whitespace;
+    let whitespace = if self.ignore_whitespace {
+      WhitespaceMode::IgnoreLeadingTrailing
+    } else {
+      WhitespaceMode::Compare
+    };
+
+    self.editor.update(cx, |editor, cx| {
+      let options = DiffOptions {
+        whitespace,
+        ..editor.diff_options().clone()
+      };
+      editor.set_diff_options(options, cx);
+    });
+  }
+
+  fn toggle_align_hunks(&mut self, cx: &mut Context<Self>) {
+    self.align_hunks = !self.align_hunks;
+    let align_hunks = self.align_hunks;
+
+    self.editor.update(cx, |editor, cx| {
+      let options = DiffOptions {
+        align_hunks,
+        ..editor.diff_options().clone()
+      };
+      editor.set_diff_options(options, cx);
+    });
   }
 
   fn quit(&mut self, _: &Quit, _window: &mut Window, cx: &mut Context<Self>) {
@@ -70,7 +208,6 @@ impl Workspace {
   }
 
   fn render_files_panel(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
-    let current_file_path = self.editor.as_mut(cx).file_path.clone();
     let dark_mode = self.dark_mode;
 
     div()
@@ -106,49 +243,57 @@ impl Workspace {
               .child(if self.dark_mode { "🌙" } else { "☀️" }),
           ),
       )
-      .children({
-        self.files.iter().enumerate().map(|(i, path)| {
-          let path_clone = path.clone();
-          let current_file_path = current_file_path.clone();
-
-          div()
-            .id(("file", i))
-            .px(px(10.0))
-            .py(px(2.0))
-            .on_click(cx.listener(move |this, _e, _w, cx| {
-              this.editor.update(cx, |editor, cx| {
-                editor.set_file_path(path_clone.clone(), cx);
-              });
-            }))
-            .when_else(
-              dark_mode,
-              |d| {
-                d.text_color(white()).when_else(
-                  current_file_path == *path,
-                  |d| d.bg(opaque_grey(0.5, 1.0)),
-                  |d| d.hover(|this| this.bg(opaque_grey(0.3, 1.0))),
-                )
-              },
-              |d| {
-                d.text_color(rgb(0x333333)).when_else(
-                  current_file_path == *path,
-                  |d| d.bg(opaque_grey(0.8, 1.0)),
-                  |d| d.hover(|this| this.bg(opaque_grey(0.9, 1.0))),
-                )
-              },
-            )
-            .child(
-              path
-                .file_name()
-                .and_then(|name| name.to_str().map(|s| s.to_string()))
-                .unwrap_or_else(|| "Unnamed".to_string()),
-            )
-        })
-      })
+      .child(
+        div()
+          .id("ignore_whitespace_toggle")
+          .px(px(10.0))
+          .py(px(2.0))
+          .border_b_1()
+          .border_color(GRAY_COLOR)
+          .cursor_pointer()
+          .on_click(cx.listener(|this, _e, _w, cx| {
+            this.toggle_ignore_whitespace(cx);
+          }))
+          .when_else(
+            dark_mode,
+            |d| d.text_color(white()),
+            |d| d.text_color(rgb(0x333333)),
+          )
+          .child(if self.ignore_whitespace {
+            "☑ Ignore whitespace"
+          } else {
+            "☐ Ignore whitespace"
+          }),
+      )
+      .child(
+        div()
+          .id("align_hunks_toggle")
+          .px(px(10.0))
+          .py(px(2.0))
+          .border_b_1()
+          .border_color(GRAY_COLOR)
+          .cursor_pointer()
+          .on_click(cx.listener(|this, _e, _w, cx| {
+            this.toggle_align_hunks(cx);
+          }))
+          .when_else(
+            dark_mode,
+            |d| d.text_color(white()),
+            |d| d.text_color(rgb(0x333333)),
+          )
+          .child(if self.align_hunks {
+            "☑ Align hunks"
+          } else {
+            "☐ Align hunks"
+          }),
+      )
+      .child(div().flex_1().min_h(px(0.0)).child(self.file_tree.clone()))
   }
 
   pub fn register(cx: &mut App) {
-    cx.bind_keys([KeyBinding::new("cmd-q", Quit, None)]);
+    let quit = KeymapPlatform::current().keystrokes("cmd-q");
+    cx.bind_keys([KeyBinding::new(&quit, Quit, None)]);
+    DiffEditor::register(cx, &EditorConfig::default().keymap);
   }
 }
 
@@ -0,0 +1,121 @@
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use rediff_core::{DiffOptions, Differ, Granularity};
+use std::hint::black_box;
+
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+const HUNK_SIZES: [usize; 3] = [100, 1_000, 5_000];
+
+/// Source-like text of `lines` lines, the same for a given size
+fn original(lines: usize) -> String {
+  (0..lines)
+    .map(|i| format!("    let value_{i} = compute({i}, \"line {i}\");\n"))
+    .collect()
+}
+
+/// `original` with a line changed, added or removed every `every` lines
+fn modified(original: &str, every: usize) -> String {
+  let mut text = String::with_capacity(original.len());
+  for (i, line) in original.lines().enumerate() {
+    match (i % every, i / every % 3) {
+      (0, 0) => text.push_str(&line.replace("compute", "recompute")),
+      (0, 1) => {
+        text.push_str(line);
+        text.push_str("\n    log(\"inserted\");");
+      }
+      (0, _) => continue,
+      _ => text.push_str(line),
+    }
+    text.push('\n');
+  }
+  text
+}
+
+/// `original` with `hunk` consecutive lines from the middle all changed
+fn rewritten(original: &str, hunk: usize) -> String {
+  let start = original.lines().count().saturating_sub(hunk) / 2;
+  let mut text = String::with_capacity(original.len());
+  for (i, line) in original.lines().enumerate() {
+    if (start..start + hunk).contains(&i) {
+      text.push_str(&line.replace("compute", "recompute"));
+    } else {
+      text.push_str(line);
+    }
+    text.push('\n');
+  }
+  text
+}
+
+fn bench_compute_diff(c: &mut Criterion) {
+  let mut group = c.benchmark_group("compute_diff");
+  group.sample_size(10);
+  for lines in SIZES {
+    let original = original(lines);
+    let modified = modified(&original, 50);
+    group.throughput(Throughput::Bytes(modified.len() as u64));
+
+    let differ = Differ::new(original.clone());
+    group.bench_with_input(
+      BenchmarkId::new("scattered", lines),
+      &modified,
+      |b, text| b.iter(|| differ.compute_diff(black_box(text))),
+    );
+
+    // A single change, the common case while typing
+    let mut typed = original.clone();
+    typed.insert_str(original.len() / 2, "x");
+    group.bench_with_input(BenchmarkId::new("single_edit", lines), &typed, |b, text| {
+      b.iter(|| differ.compute_diff(black_box(text)))
+    });
+
+    let differ = Differ::with_options(
+      original.clone(),
+      DiffOptions {
+        granularity: Granularity::None,
+        ..Default::default()
+      },
+    );
+    group.bench_with_input(
+      BenchmarkId::new("without_intra_line", lines),
+      &modified,
+      |b, text| b.iter(|| differ.compute_diff(black_box(text))),
+    );
+  }
+  group.finish();
+}
+
+fn bench_diff(c: &mut Criterion) {
+  let mut group = c.benchmark_group("diff");
+  group.sample_size(10);
+  for lines in SIZES {
+    let original = original(lines);
+    let modified = modified(&original, 50);
+    let options = DiffOptions::default();
+    group.bench_function(BenchmarkId::from_parameter(lines), |b| {
+      b.iter(|| rediff_core::diff(black_box(&original), black_box(&modified), &options))
+    });
+  }
+  group.finish();
+}
+
+fn bench_rewritten_hunk(c: &mut Criterion) {
+  let mut group = c.benchmark_group("rewritten_hunk");
+  group.sample_size(10);
+  let original = original(10_000);
+  let differ = Differ::new(original.clone());
+  for hunk in HUNK_SIZES {
+    // Every removed line is a candidate pair for every added one
+    let modified = rewritten(&original, hunk);
+    group.bench_with_input(BenchmarkId::from_parameter(hunk), &modified, |b, text| {
+      b.iter(|| differ.compute_diff(black_box(text)))
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(
+  benches,
+  bench_compute_diff,
+  bench_diff,
+  bench_rewritten_hunk
+);
+criterion_main!(benches);
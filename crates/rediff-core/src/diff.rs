@@ -0,0 +1,1507 @@
+use crate::options::{DiffOptions, Granularity};
+use cursor::Cursor;
+use similar::{Algorithm, ChangeTag, DiffTag, TextDiff, capture_diff_slices};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::ops::Range;
+use text::LineEnding;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DiffLineKind {
+  Unchanged,
+  Added,
+  Removed,
+  Modified, // A pair of removed + added lines
+  Moved,    // Same text removed in one place and added in another, both ends point at each other
+}
+
+// Moved blocks with less text than this are more likely a coincidence, like a lone closing brace
+const MIN_MOVED_CHARS: usize = 10;
+
+// Removed × added lines of a change compared each with each, bigger changes pair lines by position
+const MAX_PAIRED_CELLS: usize = 40_000;
+
+/// Range of chars in a line, not bytes: "é" is one char
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CharRange {
+  pub start: usize,
+  pub end: usize,
+}
+
+impl CharRange {
+  /// Range of the chars in the `bytes` range of `text`
+  pub fn from_byte_range(text: &str, bytes: Range<usize>) -> Self {
+    let char_count = |end: usize| text.get(..end).map_or(0, |prefix| prefix.chars().count());
+    Self {
+      start: char_count(bytes.start),
+      end: char_count(bytes.end),
+    }
+  }
+
+  /// Byte range of these chars in `text`, clamped to its end
+  pub fn byte_range(&self, text: &str) -> Range<usize> {
+    let byte_offset = |index: usize| {
+      text
+        .char_indices()
+        .nth(index)
+        .map_or(text.len(), |(offset, _)| offset)
+    };
+    byte_offset(self.start)..byte_offset(self.end.max(self.start))
+  }
+
+  /// Text of these chars in `text`
+  pub fn slice<'a>(&self, text: &'a str) -> &'a str {
+    &text[self.byte_range(text)]
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.start >= self.end
+  }
+
+  pub fn contains(&self, index: usize) -> bool {
+    self.start <= index && index < self.end
+  }
+}
+
+/// Removed half of a modified pair, kept on the added half
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinePair {
+  pub content: String,
+  pub ranges: Vec<CharRange>, // Text each of the added half's char_changes replaced, empty for insertions
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiffLine {
+  pub line_number: usize, // 0 means no line number (for removed lines in modified pairs)
+  pub kind: DiffLineKind,
+  pub content: String,
+  pub char_changes: Vec<CharRange>, // Highlighted character ranges for intra-line diff
+  pub is_first_in_group: bool,      // True if this is the first line in a modification group
+  pub pair: Option<LinePair>,       // Set on the added half of a modified pair
+  pub counterpart: Option<usize>,   // Row of the other end of a moved line
+}
+
+impl DiffLine {
+  /// Index of the highlighted change at char `index` of the content
+  pub fn change_at(&self, index: usize) -> Option<usize> {
+    self
+      .char_changes
+      .iter()
+      .position(|range| range.contains(index))
+  }
+
+  /// Original text a highlighted change replaced, None when it's a pure insertion
+  pub fn original_fragment(&self, change_idx: usize) -> Option<&str> {
+    let pair = self.pair.as_ref()?;
+    let range = pair.ranges.get(change_idx)?;
+    if range.is_empty() {
+      return None;
+    }
+    Some(range.slice(&pair.content))
+  }
+
+  /// Whether this is the last line of a side without final newline, git marks it with
+  /// "No newline at end of file"
+  pub fn lacks_final_newline(&self) -> bool {
+    !self.content.ends_with('\n')
+  }
+}
+
+/// A contiguous run of changed diff lines
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiffHunk {
+  pub rows: Range<usize>, // Indices into the Vec<DiffLine> the hunk was computed from
+  pub line_idx: usize, // Buffer line (0-based) the hunk starts at, or the line following a pure removal
+}
+
+/// Line counts of a diff, the two halves of a modified pair count as one modified line
+/// and both ends of a moved line as one moved line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiffStats {
+  pub added: usize,
+  pub removed: usize,
+  pub modified: usize,
+  pub moved: usize,
+}
+
+impl DiffStats {
+  pub fn has_changes(&self) -> bool {
+    self.added + self.removed + self.modified + self.moved > 0
+  }
+}
+
+pub struct Differ {
+  original: String,
+  options: DiffOptions,
+}
+
+impl Differ {
+  /// Line breaks of the original are normalized to '\n', like TextBuffer content
+  pub fn new(original: String) -> Self {
+    Self::with_options(original, DiffOptions::default())
+  }
+
+  pub fn with_options(original: String, options: DiffOptions) -> Self {
+    Self {
+      original: LineEnding::normalize(&original),
+      options,
+    }
+  }
+
+  pub fn options(&self) -> &DiffOptions {
+    &self.options
+  }
+
+  pub fn set_options(&mut self, options: DiffOptions) {
+    self.options = options;
+  }
+
+  pub fn compute_diff(&self, modified: &str) -> Vec<DiffLine> {
+    let old_lines: Vec<&str> = self.original.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = modified.split_inclusive('\n').collect();
+
+    // Lines are compared through their keys so that options like ignoring whitespace apply
+    let old_keys: Vec<_> = old_lines.iter().map(|l| self.options.line_key(l)).collect();
+    let new_keys: Vec<_> = new_lines.iter().map(|l| self.options.line_key(l)).collect();
+    let ops = capture_diff_slices(Algorithm::Myers, &old_keys, &new_keys);
+
+    let mut result = Vec::new();
+    let mut line_number = 0;
+    let mut pending_removes: Vec<String> = Vec::new();
+    let mut pending_adds: Vec<String> = Vec::new();
+
+    for op in ops {
+      let (tag, old_range, new_range) = op.as_tag_tuple();
+      match tag {
+        DiffTag::Equal => {
+          self.flush_pending(
+            &mut result,
+            &mut line_number,
+            &mut pending_removes,
+            &mut pending_adds,
+          );
+
+          // Equal lines may still differ when whitespace is ignored, show the modified side
+          for line in &new_lines[new_range] {
+            line_number += 1;
+            result.push(DiffLine {
+              line_number,
+              kind: DiffLineKind::Unchanged,
+              content: line.to_string(),
+              char_changes: vec![],
+              is_first_in_group: false,
+              pair: None,
+              counterpart: None,
+            });
+          }
+        }
+        DiffTag::Delete | DiffTag::Insert | DiffTag::Replace => {
+          pending_removes.extend(old_lines[old_range].iter().map(|l| l.to_string()));
+          pending_adds.extend(new_lines[new_range].iter().map(|l| l.to_string()));
+        }
+      }
+    }
+
+    self.flush_pending(
+      &mut result,
+      &mut line_number,
+      &mut pending_removes,
+      &mut pending_adds,
+    );
+    if self.options.detect_moves {
+      self.mark_moved_lines(&mut result);
+    }
+
+    // Ensure all lines from the modified buffer are represented
+    // Use split('\n') to correctly count all lines including empty ones
+    let modified_lines: Vec<&str> = if modified.is_empty() {
+      vec![""]
+    } else {
+      modified.split('\n').collect()
+    };
+
+    let modified_line_count = modified_lines.len();
+
+    while line_number < modified_line_count {
+      line_number += 1;
+      let line_content = modified_lines.get(line_number - 1).unwrap_or(&"");
+      result.push(DiffLine {
+        line_number,
+        kind: DiffLineKind::Unchanged,
+        content: format!("{}\n", line_content),
+        char_changes: vec![],
+        is_first_in_group: false,
+        pair: None,
+        counterpart: None,
+      });
+    }
+
+    result
+  }
+
+  /// Emits the pending removed and added lines of a change
+  ///
+  /// Similar lines are paired into modifications, the other lines stay removed or added lines
+  /// in their place between the pairs
+  fn flush_pending(
+    &self,
+    result: &mut Vec<DiffLine>,
+    line_number: &mut usize,
+    pending_removes: &mut Vec<String>,
+    pending_adds: &mut Vec<String>,
+  ) {
+    let removes = std::mem::take(pending_removes);
+    let adds = std::mem::take(pending_adds);
+    let pairs = if self.options.pair_modifications {
+      self.pair_lines(&removes, &adds)
+    } else {
+      Vec::new()
+    };
+
+    // Only changes with both removed and added lines have anything to align
+    let alignment = (self.options.align_hunks && !removes.is_empty() && !adds.is_empty())
+      .then(|| HunkAlignment::new(&removes, &adds, self.options.granularity));
+
+    let (mut next_remove, mut next_add) = (0, 0);
+    for pair in pairs.into_iter().map(Some).chain([None]) {
+      let (remove_end, add_end) = pair.unwrap_or((removes.len(), adds.len()));
+      for (i, removed) in removes
+        .iter()
+        .enumerate()
+        .take(remove_end)
+        .skip(next_remove)
+      {
+        let ranges = alignment
+          .as_ref()
+          .map_or_else(Vec::new, |alignment| alignment.removed_ranges(i));
+        push_line(result, 0, DiffLineKind::Removed, removed, ranges, None);
+      }
+      for (j, added) in adds.iter().enumerate().take(add_end).skip(next_add) {
+        *line_number += 1;
+        let ranges = alignment
+          .as_ref()
+          .map_or_else(Vec::new, |alignment| alignment.added_ranges(j, None).0);
+        push_line(
+          result,
+          *line_number,
+          DiffLineKind::Added,
+          added,
+          ranges,
+          None,
+        );
+      }
+
+      let Some((i, j)) = pair else {
+        break;
+      };
+      let (removed, added) = (&removes[i], &adds[j]);
+      let (removed_ranges, added_ranges, original_ranges) = match &alignment {
+        Some(alignment) => {
+          let (added_ranges, original_ranges) = alignment.added_ranges(j, Some(i));
+          (alignment.removed_ranges(i), added_ranges, original_ranges)
+        }
+        None => {
+          // Only the last line of a side can miss its newline, that's not a change of its text
+          let changes = Self::compute_intra_line_changes(
+            removed.trim_end_matches('\n'),
+            added.trim_end_matches('\n'),
+            self.options.granularity,
+          );
+          let (removed_ranges, added_ranges) = split_changes(&changes);
+          let original_ranges = changes
+            .iter()
+            .filter(|(_, new)| !new.is_empty())
+            .map(|(old, _)| old.clone())
+            .collect();
+          (removed_ranges, added_ranges, original_ranges)
+        }
+      };
+
+      *line_number += 1;
+      push_line(
+        result,
+        0,
+        DiffLineKind::Modified,
+        removed,
+        removed_ranges,
+        None,
+      );
+      push_line(
+        result,
+        *line_number,
+        DiffLineKind::Modified,
+        added,
+        added_ranges,
+        Some(LinePair {
+          content: removed.clone(),
+          ranges: original_ranges,
+        }),
+      );
+      next_remove = i + 1;
+      next_add = j + 1;
+    }
+  }
+
+  /// Removed and added lines to show as modifications, as (removed, added) indices
+  ///
+  /// Pairs keep the order of both sides, no pair crosses another, and the pairs chosen are the
+  /// ones with the highest total similarity. Lines not more similar than the threshold never pair,
+  /// and changes too big to compare each line with each only pair lines at the same position
+  fn pair_lines(&self, removes: &[String], adds: &[String]) -> Vec<(usize, usize)> {
+    let threshold = self.options.similarity_threshold;
+    let pair_similarity = |removed: &String, added: &String| {
+      if length_ratio(removed, added) > threshold {
+        Self::calculate_similarity(removed, added, threshold)
+      } else {
+        0.0
+      }
+    };
+    if removes.len() * adds.len() > MAX_PAIRED_CELLS {
+      // A whole rewritten block would stall on comparing each line with each
+      return removes
+        .iter()
+        .zip(adds)
+        .enumerate()
+        .filter(|(_, (removed, added))| pair_similarity(removed, added) > threshold)
+        .map(|(index, _)| (index, index))
+        .collect();
+    }
+
+    let width = adds.len() + 1;
+    let mut similarities = vec![0.0; removes.len() * adds.len()];
+    // Best total similarity of the pairs among the first i removed and j added lines
+    let mut scores = vec![0.0f32; (removes.len() + 1) * width];
+
+    for (i, removed) in removes.iter().enumerate() {
+      for (j, added) in adds.iter().enumerate() {
+        let similarity = pair_similarity(removed, added);
+        let paired = if similarity > threshold {
+          similarities[i * adds.len() + j] = similarity;
+          scores[i * width + j] + similarity
+        } else {
+          0.0
+        };
+        scores[(i + 1) * width + j + 1] = paired
+          .max(scores[i * width + j + 1])
+          .max(scores[(i + 1) * width + j]);
+      }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (removes.len(), adds.len());
+    while i > 0 && j > 0 {
+      let similarity = similarities[(i - 1) * adds.len() + j - 1];
+      let score = scores[i * width + j];
+      if similarity > 0.0 && score == scores[(i - 1) * width + j - 1] + similarity {
+        pairs.push((i - 1, j - 1));
+        i -= 1;
+        j -= 1;
+      } else if score == scores[(i - 1) * width + j] {
+        i -= 1;
+      } else {
+        j -= 1;
+      }
+    }
+    pairs.reverse();
+    pairs
+  }
+
+  /// Turns blocks of removed lines found again among the added lines into moved lines
+  ///
+  /// Each removed block is paired with the longest identical run of added lines, the first one
+  /// on ties. Lines are compared through their keys, like when diffing
+  fn mark_moved_lines(&self, lines: &mut [DiffLine]) {
+    // Keys of every row, made once since the same rows are compared again for each candidate
+    let keys: Vec<String> = lines
+      .iter()
+      .map(|line| self.options.line_key(&line.content).into_owned())
+      .collect();
+    let mut added_rows: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (row, line) in lines.iter().enumerate() {
+      if line.kind == DiffLineKind::Added {
+        added_rows.entry(&keys[row]).or_default().push(row);
+      }
+    }
+
+    let mut row = 0;
+    while row < lines.len() {
+      if lines[row].kind != DiffLineKind::Removed {
+        row += 1;
+        continue;
+      }
+
+      let best = added_rows
+        .get(keys[row].as_str())
+        .into_iter()
+        .flatten()
+        .map(|&added| (added, moved_len(lines, &keys, row, added)))
+        .filter(|&(_, len)| len > 0)
+        .max_by_key(|&(added, len)| (len, Reverse(added)));
+      let Some((added, len)) = best else {
+        row += 1;
+        continue;
+      };
+      let chars: usize = keys[row..row + len]
+        .iter()
+        .map(|key| key.trim().chars().count())
+        .sum();
+      if chars < MIN_MOVED_CHARS {
+        row += 1;
+        continue;
+      }
+
+      for offset in 0..len {
+        // Highlights of aligned hunks would show the same text as changed
+        lines[row + offset].char_changes.clear();
+        lines[added + offset].char_changes.clear();
+        lines[row + offset].kind = DiffLineKind::Moved;
+        lines[row + offset].counterpart = Some(added + offset);
+        lines[added + offset].kind = DiffLineKind::Moved;
+        lines[added + offset].counterpart = Some(row + offset);
+      }
+      row += len;
+    }
+  }
+
+  /// Share of the longer line kept by the edit distance between both lines, surrounding
+  /// whitespace aside: 1.0 for equal lines, 0.0 when every char has to change or when the lines
+  /// can't be more similar than `threshold`
+  fn calculate_similarity(a: &str, b: &str, threshold: f32) -> f32 {
+    let a_trimmed = a.trim();
+    let b_trimmed = b.trim();
+
+    if a_trimmed == b_trimmed {
+      return 1.0;
+    }
+
+    if a_trimmed.is_empty() || b_trimmed.is_empty() {
+      return 0.0;
+    }
+
+    let a_chars: Vec<char> = a_trimmed.chars().collect();
+    let b_chars: Vec<char> = b_trimmed.chars().collect();
+    let max_len = a_chars.len().max(b_chars.len());
+
+    // Past this many edits the similarity can't be above the threshold, no need to count them all
+    let max_distance = ((1.0 - threshold) * max_len as f32) as usize;
+    levenshtein(&a_chars, &b_chars, max_distance)
+      .map_or(0.0, |distance| 1.0 - distance as f32 / max_len as f32)
+  }
+
+  #[cfg(test)]
+  fn compute_intra_line_diff(
+    old: &str,
+    new: &str,
+    granularity: Granularity,
+  ) -> (Vec<CharRange>, Vec<CharRange>) {
+    split_changes(&Self::compute_intra_line_changes(old, new, granularity))
+  }
+
+  /// Changed ranges of both lines, paired up: each old range is replaced by the new one next to it
+  ///
+  /// One side is empty for pure deletions and insertions
+  fn compute_intra_line_changes(
+    old: &str,
+    new: &str,
+    granularity: Granularity,
+  ) -> Vec<(CharRange, CharRange)> {
+    let (old_words, new_words);
+    let diff = match granularity {
+      Granularity::None => return vec![],
+      Granularity::Char => TextDiff::from_chars(old, new),
+      Granularity::Word => {
+        old_words = tokenize_words(old);
+        new_words = tokenize_words(new);
+        TextDiff::from_slices(&old_words, &new_words)
+      }
+    };
+
+    let mut changes = Vec::new();
+    let mut current: Option<(CharRange, CharRange)> = None;
+    let mut old_pos = 0;
+    let mut new_pos = 0;
+
+    for change in diff.iter_all_changes() {
+      let len = change.value().chars().count();
+      if change.tag() == ChangeTag::Equal {
+        changes.extend(current.take());
+        old_pos += len;
+        new_pos += len;
+        continue;
+      }
+
+      let (old_range, new_range) = current.get_or_insert((
+        CharRange {
+          start: old_pos,
+          end: old_pos,
+        },
+        CharRange {
+          start: new_pos,
+          end: new_pos,
+        },
+      ));
+      if change.tag() == ChangeTag::Delete {
+        old_pos += len;
+        old_range.end = old_pos;
+      } else {
+        new_pos += len;
+        new_range.end = new_pos;
+      }
+    }
+    changes.extend(current);
+
+    changes
+  }
+
+  pub fn update_original(&mut self, new_original: String) {
+    self.original = LineEnding::normalize(&new_original);
+  }
+
+  /// Groups consecutive changed lines into hunks
+  pub fn compute_hunks(diff_lines: &[DiffLine]) -> Vec<DiffHunk> {
+    let mut hunks = Vec::new();
+    let mut row = 0;
+
+    while row < diff_lines.len() {
+      if diff_lines[row].kind == DiffLineKind::Unchanged {
+        row += 1;
+        continue;
+      }
+
+      let start = row;
+      while row < diff_lines.len() && diff_lines[row].kind != DiffLineKind::Unchanged {
+        row += 1;
+      }
+
+      // Removed lines have no buffer position, so anchor to the next real line
+      let line_number = diff_lines[start..]
+        .iter()
+        .find(|line| line.line_number > 0)
+        .or_else(|| {
+          diff_lines[..start]
+            .iter()
+            .rfind(|line| line.line_number > 0)
+        })
+        .map(|line| line.line_number)
+        .unwrap_or(1);
+
+      hunks.push(DiffHunk {
+        rows: start..row,
+        line_idx: line_number - 1,
+      });
+    }
+
+    hunks
+  }
+
+  /// Counts the added, removed and modified lines of a diff
+  pub fn compute_stats(diff_lines: &[DiffLine]) -> DiffStats {
+    let mut stats = DiffStats::default();
+    for line in diff_lines {
+      match line.kind {
+        DiffLineKind::Added => stats.added += 1,
+        DiffLineKind::Removed => stats.removed += 1,
+        DiffLineKind::Modified if line.line_number > 0 => stats.modified += 1,
+        DiffLineKind::Moved if line.line_number > 0 => stats.moved += 1,
+        DiffLineKind::Modified | DiffLineKind::Moved | DiffLineKind::Unchanged => {}
+      }
+    }
+    stats
+  }
+
+  /// Text restoring `lines` of `modified` to the original, to replace those whole lines with
+  ///
+  /// Modified lines go back to their removed half and added lines are dropped. Removed lines
+  /// come back when they were right above, inside or right below the range
+  pub fn revert_lines(diff_lines: &[DiffLine], modified: &str, lines: Range<usize>) -> String {
+    let modified_lines: Vec<&str> = modified.split_inclusive('\n').collect();
+    let mut next_line = diff_lines
+      .iter()
+      .map(|line| line.line_number)
+      .max()
+      .unwrap_or(0);
+    let mut parts = Vec::new();
+
+    // Backwards, so that removed lines know the buffer line following them
+    for line in diff_lines.iter().rev() {
+      if line.line_number == 0 {
+        let removed = matches!(line.kind, DiffLineKind::Removed | DiffLineKind::Moved);
+        if removed && lines.start <= next_line && next_line <= lines.end {
+          parts.push(line.content.as_str());
+        }
+        continue;
+      }
+
+      next_line = line.line_number - 1;
+      if !lines.contains(&next_line) {
+        continue;
+      }
+      match (&line.kind, &line.pair) {
+        (_, Some(pair)) => parts.push(pair.content.as_str()),
+        (DiffLineKind::Added | DiffLineKind::Moved, None) => {}
+        _ => parts.push(modified_lines.get(next_line).copied().unwrap_or_default()),
+      }
+    }
+
+    parts.reverse();
+    parts.concat()
+  }
+
+  /// Finds the first hunk starting after the given buffer line, wrapping around
+  pub fn next_hunk(hunks: &[DiffHunk], line_idx: usize) -> Option<&DiffHunk> {
+    hunks
+      .iter()
+      .find(|hunk| hunk.line_idx > line_idx)
+      .or_else(|| hunks.first())
+  }
+
+  /// Finds the last hunk starting before the given buffer line, wrapping around
+  pub fn prev_hunk(hunks: &[DiffHunk], line_idx: usize) -> Option<&DiffHunk> {
+    hunks
+      .iter()
+      .rfind(|hunk| hunk.line_idx < line_idx)
+      .or_else(|| hunks.last())
+  }
+}
+
+/// Splits a line into words, runs of whitespace and single other characters
+///
+/// Words follow `Cursor::is_word_char`, the same boundaries as word motions in the editor
+fn tokenize_words(line: &str) -> Vec<&str> {
+  #[derive(PartialEq)]
+  enum Class {
+    Word,
+    Whitespace,
+    Other,
+  }
+
+  let class = |c: char| {
+    if Cursor::is_word_char(c) {
+      Class::Word
+    } else if c.is_whitespace() {
+      Class::Whitespace
+    } else {
+      Class::Other
+    }
+  };
+
+  let mut tokens = Vec::new();
+  let mut start = 0;
+  let mut chars = line.char_indices().peekable();
+
+  while let Some((index, c)) = chars.next() {
+    let current = class(c);
+    let ends_token = match chars.peek() {
+      Some(&(_, next)) => current == Class::Other || class(next) != current,
+      None => true,
+    };
+    if ends_token {
+      let end = index + c.len_utf8();
+      tokens.push(&line[start..end]);
+      start = end;
+    }
+  }
+
+  tokens
+}
+
+/// Number of lines from `removed` on that were added again from `added` on, `keys` being
+/// the key of each line
+fn moved_len(lines: &[DiffLine], keys: &[String], removed: usize, added: usize) -> usize {
+  (removed..lines.len())
+    .zip(added..lines.len())
+    .take_while(|&(old, new)| {
+      lines[old].kind == DiffLineKind::Removed
+        && lines[new].kind == DiffLineKind::Added
+        && keys[old] == keys[new]
+    })
+    .count()
+}
+
+/// Appends a line, the first of a run of lines of the same kind starts a group
+fn push_line(
+  result: &mut Vec<DiffLine>,
+  line_number: usize,
+  kind: DiffLineKind,
+  content: &str,
+  char_changes: Vec<CharRange>,
+  pair: Option<LinePair>,
+) {
+  let is_first_in_group = result.last().is_none_or(|last| last.kind != kind);
+  result.push(DiffLine {
+    line_number,
+    kind,
+    content: content.to_string(),
+    char_changes,
+    is_first_in_group,
+    pair,
+    counterpart: None,
+  });
+}
+
+/// Insertions, deletions and substitutions needed to turn `a` into `b`, None when more than `max`
+fn levenshtein(a: &[char], b: &[char], max: usize) -> Option<usize> {
+  if a.len().abs_diff(b.len()) > max {
+    return None;
+  }
+  let mut previous: Vec<usize> = (0..=b.len()).collect();
+  let mut current = vec![0; b.len() + 1];
+  for (i, a_char) in a.iter().enumerate() {
+    current[0] = i + 1;
+    let mut row_min = current[0];
+    for (j, b_char) in b.iter().enumerate() {
+      let substitution = previous[j] + usize::from(a_char != b_char);
+      current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+      row_min = row_min.min(current[j + 1]);
+    }
+    // The distance is never less than the smallest one of a row
+    if row_min > max {
+      return None;
+    }
+    std::mem::swap(&mut previous, &mut current);
+  }
+  Some(previous[b.len()]).filter(|&distance| distance <= max)
+}
+
+/// Upper bound of `Differ::calculate_similarity`, the length difference has to be edited anyway
+fn length_ratio(a: &str, b: &str) -> f32 {
+  let (a_len, b_len) = (a.trim().chars().count(), b.trim().chars().count());
+  if a_len.max(b_len) == 0 {
+    return 1.0;
+  }
+  a_len.min(b_len) as f32 / a_len.max(b_len) as f32
+}
+
+/// Intra-line changes of a whole change, from the removed lines joined against the added ones
+///
+/// Ranges are cut at line ends into ranges of each line, line breaks are never highlighted
+struct HunkAlignment {
+  changes: Vec<(CharRange, CharRange)>, // Chars of the joined lines
+  removed_lines: Vec<Range<usize>>, // Chars of each removed line in the joined text, its line break aside
+  added_lines: Vec<Range<usize>>,
+}
+
+impl HunkAlignment {
+  fn new(removes: &[String], adds: &[String], granularity: Granularity) -> Self {
+    let spans = |lines: &[String]| {
+      let mut offset = 0;
+      lines
+        .iter()
+        .map(|line| {
+          let start = offset;
+          offset += line.chars().count();
+          start..start + line.trim_end_matches('\n').chars().count()
+        })
+        .collect()
+    };
+    Self {
+      changes: Differ::compute_intra_line_changes(&removes.concat(), &adds.concat(), granularity),
+      removed_lines: spans(removes),
+      added_lines: spans(adds),
+    }
+  }
+
+  fn removed_ranges(&self, line: usize) -> Vec<CharRange> {
+    let line = &self.removed_lines[line];
+    self
+      .changes
+      .iter()
+      .filter_map(|(old, _)| clip(old, line))
+      .collect()
+  }
+
+  /// Highlights of added line `line`, with the text each one replaced in removed line `pair`,
+  /// empty when it's on other lines
+  fn added_ranges(&self, line: usize, pair: Option<usize>) -> (Vec<CharRange>, Vec<CharRange>) {
+    let line = &self.added_lines[line];
+    let pair = pair.map(|pair| &self.removed_lines[pair]);
+    self
+      .changes
+      .iter()
+      .filter_map(|(old, new)| {
+        let new = clip(new, line)?;
+        let old = pair
+          .and_then(|pair| clip(old, pair))
+          .unwrap_or(CharRange { start: 0, end: 0 });
+        Some((new, old))
+      })
+      .unzip()
+  }
+}
+
+/// Part of `range` inside `line`, relative to the start of the line, None when it's empty
+fn clip(range: &CharRange, line: &Range<usize>) -> Option<CharRange> {
+  let start = range.start.max(line.start);
+  let end = range.end.min(line.end);
+  (start < end).then(|| CharRange {
+    start: start - line.start,
+    end: end - line.start,
+  })
+}
+
+/// Highlighted ranges of each line from paired changes
+fn split_changes(changes: &[(CharRange, CharRange)]) -> (Vec<CharRange>, Vec<CharRange>) {
+  let ranges = |side: fn(&(CharRange, CharRange)) -> &CharRange| {
+    changes
+      .iter()
+      .map(side)
+      .filter(|range| !range.is_empty())
+      .cloned()
+      .collect()
+  };
+  (ranges(|(old, _)| old), ranges(|(_, new)| new))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_differ_no_changes() {
+    let differ = Differ::new("Hello\nWorld".to_string());
+    let diff = differ.compute_diff("Hello\nWorld");
+    assert_eq!(diff.len(), 2);
+    assert!(diff.iter().all(|line| line.kind == DiffLineKind::Unchanged));
+  }
+
+  #[test]
+  fn test_differ_added_line() {
+    let differ = Differ::new("Hello\nWorld".to_string());
+    let diff = differ.compute_diff("Hello\nNew Line\nWorld");
+    let added = diff
+      .iter()
+      .filter(|line| line.kind == DiffLineKind::Added)
+      .count();
+    assert_eq!(added, 1);
+  }
+
+  #[test]
+  fn test_differ_removed_line() {
+    let differ = Differ::new("Hello\nRemove Me\nWorld".to_string());
+    let diff = differ.compute_diff("Hello\nWorld");
+    let removed = diff
+      .iter()
+      .filter(|line| line.kind == DiffLineKind::Removed)
+      .count();
+    assert_eq!(removed, 1);
+  }
+
+  #[test]
+  fn test_differ_update_original() {
+    let mut differ = Differ::new("Original".to_string());
+    differ.update_original("New Original".to_string());
+    let diff = differ.compute_diff("New Original");
+    assert!(diff.iter().all(|line| line.kind == DiffLineKind::Unchanged));
+  }
+
+  #[test]
+  fn test_differ_modified_line() {
+    let differ = Differ::new("Hello World".to_string());
+    let diff = differ.compute_diff("Hello Universe");
+    // Should have 2 lines: removed and added as Modified
+    assert_eq!(diff.len(), 2);
+    assert!(diff.iter().all(|line| line.kind == DiffLineKind::Modified));
+  }
+
+  #[test]
+  fn test_final_newline_change() {
+    let rows = |original: &str, modified: &str| {
+      Differ::new(original.to_string())
+        .compute_diff(modified)
+        .into_iter()
+        .map(|line| {
+          assert!(line.char_changes.is_empty());
+          let lacks_final_newline = line.lacks_final_newline();
+          (line.kind, line.content, lacks_final_newline)
+        })
+        .collect::<Vec<_>>()
+    };
+
+    // The last line changes without anything highlighted in it, the empty line after the
+    // newline follows when it's added
+    assert_eq!(
+      rows("a\nb", "a\nb\n"),
+      [
+        (DiffLineKind::Unchanged, "a\n".to_string(), false),
+        (DiffLineKind::Modified, "b".to_string(), true),
+        (DiffLineKind::Modified, "b\n".to_string(), false),
+        (DiffLineKind::Unchanged, "\n".to_string(), false),
+      ]
+    );
+    assert_eq!(
+      rows("a\nb\n", "a\nb"),
+      [
+        (DiffLineKind::Unchanged, "a\n".to_string(), false),
+        (DiffLineKind::Modified, "b\n".to_string(), false),
+        (DiffLineKind::Modified, "b".to_string(), true),
+      ]
+    );
+    // Unchanged last lines without newline stay unchanged
+    assert_eq!(
+      rows("a\nb", "a\nb"),
+      [
+        (DiffLineKind::Unchanged, "a\n".to_string(), false),
+        (DiffLineKind::Unchanged, "b".to_string(), true),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_intra_line_diff() {
+    let (old_ranges, new_ranges) =
+      Differ::compute_intra_line_diff("Hello World", "Hello Universe", Granularity::Char);
+    assert!(!old_ranges.is_empty());
+    assert!(!new_ranges.is_empty());
+  }
+
+  #[test]
+  fn test_intra_line_diff_merges_adjacent_ranges() {
+    let (old_ranges, new_ranges) =
+      Differ::compute_intra_line_diff("let a = 1;", "let a = 234;", Granularity::Char);
+    assert_eq!(old_ranges, vec![CharRange { start: 8, end: 9 }]);
+    assert_eq!(new_ranges, vec![CharRange { start: 8, end: 11 }]);
+  }
+
+  #[test]
+  fn test_modified_line_keeps_original_fragments() {
+    let differ = Differ::new("let a = 1;\n".to_string());
+    let diff = differ.compute_diff("let ab = 234;\n");
+    let added = diff.iter().find(|line| line.line_number == 1).unwrap();
+    assert_eq!(added.kind, DiffLineKind::Modified);
+    assert_eq!(added.pair.as_ref().unwrap().content, "let a = 1;\n");
+    assert!(diff[0].pair.is_none());
+
+    // "b" is inserted, "234" replaces "1"
+    assert_eq!(added.change_at(5), Some(0));
+    assert_eq!(added.original_fragment(0), None);
+    assert_eq!(added.change_at(9), Some(1));
+    assert_eq!(added.original_fragment(1), Some("1"));
+    assert_eq!(added.change_at(2), None);
+  }
+
+  #[test]
+  fn test_align_hunks_across_split_line() {
+    let original = "call(first, second);\n";
+    let modified = "call(first,\n     second);\n";
+    let whitespace_only = |line: &DiffLine| {
+      line
+        .char_changes
+        .iter()
+        .all(|range| range.slice(&line.content).trim().is_empty())
+    };
+
+    // Line by line, the text moved to the next line shows as removed
+    let diff = Differ::new(original.to_string()).compute_diff(modified);
+    assert!(!whitespace_only(&diff[0]));
+
+    let options = DiffOptions {
+      align_hunks: true,
+      ..Default::default()
+    };
+    let diff = Differ::with_options(original.to_string(), options).compute_diff(modified);
+    assert_eq!(diff.len(), 3);
+    assert_eq!(diff[0].kind, DiffLineKind::Modified);
+    assert!(diff[0].char_changes.is_empty());
+    assert!(diff[1].char_changes.is_empty());
+    // Only the new indentation is highlighted
+    assert_eq!(diff[2].kind, DiffLineKind::Added);
+    assert!(!diff[2].char_changes.is_empty());
+    assert!(whitespace_only(&diff[2]));
+  }
+
+  #[test]
+  fn test_align_hunks_keeps_original_fragments() {
+    let options = DiffOptions {
+      align_hunks: true,
+      ..Default::default()
+    };
+    let differ = Differ::with_options("a\nlet x = 1;\n".to_string(), options);
+    let diff = differ.compute_diff("let x = 2;\n");
+    let added = diff.iter().find(|line| line.line_number == 1).unwrap();
+    assert_eq!(added.kind, DiffLineKind::Modified);
+    assert_eq!(added.char_changes, vec![CharRange { start: 8, end: 9 }]);
+    assert_eq!(added.original_fragment(0), Some("1"));
+    // The removed line is highlighted whole, its line break aside
+    assert_eq!(diff[0].kind, DiffLineKind::Removed);
+    assert_eq!(diff[0].char_changes, vec![CharRange { start: 0, end: 1 }]);
+  }
+
+  #[test]
+  fn test_intra_line_diff_counts_chars() {
+    // "é" and "😀" take several bytes but are one char each
+    let (old_ranges, new_ranges) =
+      Differ::compute_intra_line_diff("café = 1;", "café = 2;", Granularity::Char);
+    assert_eq!(old_ranges, vec![CharRange { start: 7, end: 8 }]);
+    assert_eq!(new_ranges, vec![CharRange { start: 7, end: 8 }]);
+
+    let (_, new_ranges) =
+      Differ::compute_intra_line_diff("say 😀 hi", "say 😀😀 hi", Granularity::Char);
+    assert_eq!(new_ranges, vec![CharRange { start: 5, end: 6 }]);
+
+    let (old_ranges, new_ranges) =
+      Differ::compute_intra_line_diff("über alles", "über allem", Granularity::Word);
+    assert_eq!(old_ranges, vec![CharRange { start: 5, end: 10 }]);
+    assert_eq!(new_ranges, vec![CharRange { start: 5, end: 10 }]);
+  }
+
+  #[test]
+  fn test_original_fragment_with_multibyte_chars() {
+    let differ = Differ::new("naïve = \"😀\";\n".to_string());
+    let diff = differ.compute_diff("naïve = \"🎉\";\n");
+    let added = diff.iter().find(|line| line.line_number == 1).unwrap();
+
+    assert_eq!(added.char_changes, vec![CharRange { start: 9, end: 10 }]);
+    assert_eq!(added.change_at(9), Some(0));
+    assert_eq!(added.original_fragment(0), Some("😀"));
+  }
+
+  #[test]
+  fn test_char_range_byte_conversion() {
+    let text = "aé😀b";
+    let range = CharRange::from_byte_range(text, 1..7);
+    assert_eq!(range, CharRange { start: 1, end: 3 });
+    assert_eq!(range.byte_range(text), 1..7);
+    assert_eq!(range.slice(text), "é😀");
+    assert_eq!(CharRange { start: 3, end: 10 }.slice(text), "b");
+  }
+
+  #[test]
+  fn test_revert_lines() {
+    let original = "keep\nlet x = 1;\nmiddle\nremoved\nend\n";
+    let modified = "keep\nlet x = 2;\nmiddle\nend\nadded\n";
+    let differ = Differ::new(original.to_string());
+    let diff = differ.compute_diff(modified);
+
+    assert_eq!(Differ::revert_lines(&diff, modified, 1..2), "let x = 1;\n");
+    assert_eq!(Differ::revert_lines(&diff, modified, 4..5), "");
+    // The removed line was right below
+    assert_eq!(
+      Differ::revert_lines(&diff, modified, 2..3),
+      "middle\nremoved\n"
+    );
+    assert_eq!(Differ::revert_lines(&diff, modified, 0..6), original);
+  }
+
+  #[test]
+  fn test_intra_line_diff_word_granularity() {
+    let (old_ranges, new_ranges) = Differ::compute_intra_line_diff(
+      "let value = compute(x);",
+      "let total = compute(y);",
+      Granularity::Word,
+    );
+    // Whole tokens are highlighted, not the shared letters of "value" and "total"
+    assert_eq!(
+      old_ranges,
+      vec![
+        CharRange { start: 4, end: 9 },
+        CharRange { start: 20, end: 21 }
+      ]
+    );
+    assert_eq!(
+      new_ranges,
+      vec![
+        CharRange { start: 4, end: 9 },
+        CharRange { start: 20, end: 21 }
+      ]
+    );
+  }
+
+  #[test]
+  fn test_tokenize_words() {
+    assert_eq!(
+      tokenize_words("foo_bar  (x)+=1\n"),
+      vec!["foo_bar", "  ", "(", "x", ")", "+", "=", "1", "\n"]
+    );
+    assert!(tokenize_words("").is_empty());
+  }
+
+  #[test]
+  fn test_dissimilar_lines_as_separate_changes() {
+    let differ = Differ::new("<div class=\"wrapper\">\n<TheWelcome />".to_string());
+    let diff = differ.compute_diff("<HelloWorld msg=\"test\" />\n<adazd />");
+
+    // Should have: 2 removed lines + 2 added lines = 4 lines
+    // Find the removed and added lines (ignoring any trailing empty lines)
+    let removed_lines: Vec<_> = diff
+      .iter()
+      .filter(|l| l.kind == DiffLineKind::Removed)
+      .collect();
+    let added_lines: Vec<_> = diff
+      .iter()
+      .filter(|l| l.kind == DiffLineKind::Added)
+      .collect();
+
+    assert_eq!(removed_lines.len(), 2, "Should have 2 removed lines");
+    assert_eq!(added_lines.len(), 2, "Should have 2 added lines");
+
+    // Verify removed lines have no line number
+    assert_eq!(removed_lines[0].line_number, 0);
+    assert_eq!(removed_lines[1].line_number, 0);
+
+    // Verify added lines have line numbers
+    assert_eq!(added_lines[0].line_number, 1);
+    assert_eq!(added_lines[1].line_number, 2);
+
+    // Verify order: all removes come before all adds
+    let first_remove_idx = diff
+      .iter()
+      .position(|l| l.kind == DiffLineKind::Removed)
+      .unwrap();
+    let last_remove_idx = diff
+      .iter()
+      .rposition(|l| l.kind == DiffLineKind::Removed)
+      .unwrap();
+    let first_add_idx = diff
+      .iter()
+      .position(|l| l.kind == DiffLineKind::Added)
+      .unwrap();
+
+    assert!(first_remove_idx <= last_remove_idx);
+    assert!(
+      last_remove_idx < first_add_idx,
+      "All removes should come before adds"
+    );
+  }
+
+  #[test]
+  fn test_similarity_calculation() {
+    // Identical lines
+    assert_eq!(Differ::calculate_similarity("hello", "hello", 0.0), 1.0);
+
+    // Very similar lines
+    let sim =
+      Differ::calculate_similarity("<div class=\"container\">", "<div class=\"wrapper\">", 0.0);
+    assert!(sim > 0.5, "Similar lines should have > 50% similarity");
+
+    // Very different lines
+    let sim = Differ::calculate_similarity("<main>", "<TheWelcome />", 0.0);
+    assert!(sim < 0.3, "Different lines should have < 30% similarity");
+
+    // An insertion near the start doesn't shift every following char out of place
+    let sim =
+      Differ::calculate_similarity("let value = compute();", "let mut value = compute();", 0.0);
+    assert!(sim > 0.8);
+
+    // Lines that can't reach the threshold stop counting edits and aren't similar at all
+    assert_eq!(Differ::calculate_similarity("kitten", "sitting", 0.6), 0.0);
+    assert_eq!(
+      Differ::calculate_similarity("kitten", "sitting", 0.5),
+      1.0 - 3.0 / 7.0
+    );
+  }
+
+  #[test]
+  fn test_levenshtein() {
+    let chars = |s: &str| s.chars().collect::<Vec<_>>();
+    assert_eq!(
+      levenshtein(&chars("kitten"), &chars("sitting"), usize::MAX),
+      Some(3)
+    );
+    assert_eq!(levenshtein(&chars(""), &chars("abc"), usize::MAX), Some(3));
+    assert_eq!(
+      levenshtein(&chars("héllo"), &chars("hello"), usize::MAX),
+      Some(1)
+    );
+    assert_eq!(
+      levenshtein(&chars("same"), &chars("same"), usize::MAX),
+      Some(0)
+    );
+
+    // Bounded distances
+    assert_eq!(levenshtein(&chars("kitten"), &chars("sitting"), 3), Some(3));
+    assert_eq!(levenshtein(&chars("kitten"), &chars("sitting"), 2), None);
+    assert_eq!(levenshtein(&chars("a"), &chars("abcd"), 2), None);
+  }
+
+  #[test]
+  fn test_pairing_disabled() {
+    let options = DiffOptions {
+      pair_modifications: false,
+      ..Default::default()
+    };
+    let differ = Differ::with_options("line1\nold line\nline3\n".to_string(), options);
+    let diff = differ.compute_diff("line1\nnew line\nline3\n");
+
+    assert!(diff.iter().all(|l| l.kind != DiffLineKind::Modified));
+    let changed: Vec<_> = diff
+      .iter()
+      .filter(|l| l.kind != DiffLineKind::Unchanged)
+      .map(|l| (l.kind.clone(), l.content.as_str()))
+      .collect();
+    assert_eq!(
+      changed,
+      vec![
+        (DiffLineKind::Removed, "old line\n"),
+        (DiffLineKind::Added, "new line\n"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_mixed_modifications_and_pure_changes() {
+    let differ = Differ::new("line1\nold line\nline3\n".to_string());
+    let diff = differ.compute_diff("line1\nnew line\nline3\n");
+
+    // Should recognize "old line" -> "new line" as modification (similar)
+    let modified_lines: Vec<_> = diff
+      .iter()
+      .filter(|l| l.kind == DiffLineKind::Modified)
+      .collect();
+    assert_eq!(modified_lines.len(), 2); // One removed + one added = modification pair
+  }
+
+  #[test]
+  fn test_pairs_with_unpaired_lines_between() {
+    let differ = Differ::new("let a = 1;\nlet b = 2;\nlet c = 3;\n".to_string());
+    let diff = differ.compute_diff("let a = 10;\nlet c = 30;\n");
+
+    let changed: Vec<_> = diff
+      .iter()
+      .filter(|l| l.kind != DiffLineKind::Unchanged)
+      .map(|l| (l.kind.clone(), l.line_number, l.content.as_str()))
+      .collect();
+    assert_eq!(
+      changed,
+      vec![
+        (DiffLineKind::Modified, 0, "let a = 1;\n"),
+        (DiffLineKind::Modified, 1, "let a = 10;\n"),
+        (DiffLineKind::Removed, 0, "let b = 2;\n"),
+        (DiffLineKind::Modified, 0, "let c = 3;\n"),
+        (DiffLineKind::Modified, 2, "let c = 30;\n"),
+      ]
+    );
+    // Paired lines keep their highlights even though not every line found a pair
+    assert!(
+      diff
+        .iter()
+        .filter(|l| l.kind == DiffLineKind::Modified)
+        .all(|l| !l.char_changes.is_empty())
+    );
+  }
+
+  #[test]
+  fn test_pairs_keep_line_order() {
+    // The first removed line is most like the last added one, pairing them would cross the others
+    let differ = Differ::new("value = compute(1)\nfoo()\nbar()\n".to_string());
+    let diff = differ.compute_diff("foo(1)\nbar(2)\nvalue = compute(2)\n");
+
+    let line_numbers: Vec<_> = diff
+      .iter()
+      .filter(|l| l.line_number > 0)
+      .map(|l| l.line_number)
+      .collect();
+    assert_eq!(line_numbers, (1..=line_numbers.len()).collect::<Vec<_>>());
+    let modified = diff
+      .iter()
+      .filter(|l| l.kind == DiffLineKind::Modified && l.line_number > 0)
+      .count();
+    assert_eq!(modified, 2);
+  }
+
+  #[test]
+  fn test_large_change_pairs_lines_by_position() {
+    let original: String = (0..300)
+      .map(|i| format!("let value_{i} = {i};\n"))
+      .collect();
+    let modified: String = (0..300)
+      .map(|i| format!("let value_{i} = {i} + 1;\n"))
+      .collect();
+    let differ = Differ::new(original);
+    let diff = differ.compute_diff(&modified);
+
+    let added: Vec<_> = diff
+      .iter()
+      .filter(|l| l.line_number > 0)
+      .map(|l| (l.kind.clone(), l.line_number))
+      .collect();
+    assert_eq!(
+      added,
+      (1..=300)
+        .map(|line_number| (DiffLineKind::Modified, line_number))
+        .collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn test_order_removes_before_adds() {
+    let differ = Differ::new("A\nB\nC\n".to_string());
+    let diff = differ.compute_diff("X\nY\nZ\n");
+
+    // All lines are different, should be: 3 removes then 3 adds
+    let mut removes_done = false;
+    let mut adds_started = false;
+
+    for line in &diff {
+      if line.kind == DiffLineKind::Added {
+        removes_done = true;
+        adds_started = true;
+      }
+      if line.kind == DiffLineKind::Removed && adds_started {
+        panic!("Removed line found after added lines!");
+      }
+    }
+
+    assert!(
+      removes_done,
+      "Should have processed all removes before adds"
+    );
+  }
+
+  #[test]
+  fn test_moved_block() {
+    let differ = Differ::new("fn a() {}\nfn b() {}\nkeep\nfn c() {}\n".to_string());
+    let diff = differ.compute_diff("keep\nfn c() {}\nfn a() {}\nfn b() {}\n");
+
+    let moved: Vec<_> = diff
+      .iter()
+      .enumerate()
+      .filter(|(_, line)| line.kind == DiffLineKind::Moved)
+      .collect();
+    assert_eq!(moved.len(), 4);
+    for (row, line) in moved {
+      let counterpart = &diff[line.counterpart.unwrap()];
+      assert_eq!(counterpart.content, line.content);
+      assert_eq!(counterpart.counterpart, Some(row));
+      // One end is where the lines were, the other where they are now
+      assert_ne!(counterpart.line_number == 0, line.line_number == 0);
+    }
+    assert_eq!(Differ::compute_stats(&diff).moved, 2);
+  }
+
+  #[test]
+  fn test_short_moves_stay_added_and_removed() {
+    let differ = Differ::new("}\na\nb\n".to_string());
+    let diff = differ.compute_diff("a\nb\n}\n");
+    assert!(diff.iter().all(|line| line.kind != DiffLineKind::Moved));
+
+    let options = DiffOptions {
+      detect_moves: false,
+      ..Default::default()
+    };
+    let differ = Differ::with_options("fn a() {}\nkeep\n".to_string(), options);
+    let diff = differ.compute_diff("keep\nfn a() {}\n");
+    assert!(diff.iter().all(|line| line.kind != DiffLineKind::Moved));
+  }
+
+  #[test]
+  fn test_crlf_original_matches_lf_buffer() {
+    let differ = Differ::new("a\r\nb\r\nc\r\n".to_string());
+    let diff = differ.compute_diff("a\nb\nc\n");
+    assert!(diff.iter().all(|l| l.kind == DiffLineKind::Unchanged));
+    assert!(diff.iter().all(|l| !l.content.contains('\r')));
+  }
+
+  #[test]
+  fn test_compute_hunks() {
+    let differ = Differ::new("a\nb\nc\nd\ne\n".to_string());
+    let diff = differ.compute_diff("a\nB\nc\nd\nnew\ne\n");
+    let hunks = Differ::compute_hunks(&diff);
+
+    assert_eq!(hunks.len(), 2);
+    assert_eq!(hunks[0].line_idx, 1); // "B"
+    assert_eq!(hunks[1].line_idx, 4); // "new"
+    assert!(
+      diff[hunks[0].rows.clone()]
+        .iter()
+        .all(|l| l.kind != DiffLineKind::Unchanged)
+    );
+  }
+
+  #[test]
+  fn test_compute_stats() {
+    let differ = Differ::new("a\nlet value = 1;\nc\n".to_string());
+    assert!(!Differ::compute_stats(&differ.compute_diff("a\nlet value = 1;\nc\n")).has_changes());
+
+    let stats = Differ::compute_stats(&differ.compute_diff("a\nlet value = 2;\nc\nd\n"));
+    assert_eq!(
+      stats,
+      DiffStats {
+        added: 1,
+        removed: 0,
+        modified: 1,
+        moved: 0,
+      }
+    );
+
+    let stats = Differ::compute_stats(&differ.compute_diff("a\n"));
+    assert_eq!(stats.removed, 2);
+  }
+
+  #[test]
+  fn test_compute_hunks_no_changes() {
+    let differ = Differ::new("a\nb\n".to_string());
+    let diff = differ.compute_diff("a\nb\n");
+    assert!(Differ::compute_hunks(&diff).is_empty());
+  }
+
+  #[test]
+  fn test_compute_hunks_pure_removal_anchors_to_next_line() {
+    let differ = Differ::new("a\nremoved\nb\n".to_string());
+    let diff = differ.compute_diff("a\nb\n");
+    let hunks = Differ::compute_hunks(&diff);
+
+    assert_eq!(hunks.len(), 1);
+    assert_eq!(hunks[0].rows.len(), 1);
+    assert_eq!(hunks[0].line_idx, 1); // "b" follows the removed line
+  }
+
+  #[test]
+  fn test_compute_hunks_removal_at_end_anchors_to_previous_line() {
+    let diff = vec![
+      DiffLine {
+        line_number: 1,
+        kind: DiffLineKind::Unchanged,
+        content: "a\n".to_string(),
+        char_changes: vec![],
+        is_first_in_group: false,
+        pair: None,
+        counterpart: None,
+      },
+      DiffLine {
+        line_number: 0,
+        kind: DiffLineKind::Removed,
+        content: "b\n".to_string(),
+        char_changes: vec![],
+        is_first_in_group: true,
+        pair: None,
+        counterpart: None,
+      },
+    ];
+    let hunks = Differ::compute_hunks(&diff);
+    assert_eq!(hunks.len(), 1);
+    assert_eq!(hunks[0].line_idx, 0);
+  }
+
+  #[test]
+  fn test_next_and_prev_hunk() {
+    let hunks = vec![
+      DiffHunk {
+        rows: 1..2,
+        line_idx: 1,
+      },
+      DiffHunk {
+        rows: 5..7,
+        line_idx: 5,
+      },
+    ];
+
+    assert_eq!(Differ::next_hunk(&hunks, 0).unwrap().line_idx, 1);
+    assert_eq!(Differ::next_hunk(&hunks, 1).unwrap().line_idx, 5);
+    assert_eq!(Differ::prev_hunk(&hunks, 6).unwrap().line_idx, 5);
+    assert_eq!(Differ::prev_hunk(&hunks, 5).unwrap().line_idx, 1);
+
+    // Navigation wraps around at both ends
+    assert_eq!(Differ::next_hunk(&hunks, 5).unwrap().line_idx, 1);
+    assert_eq!(Differ::prev_hunk(&hunks, 1).unwrap().line_idx, 5);
+
+    assert!(Differ::next_hunk(&[], 0).is_none());
+    assert!(Differ::prev_hunk(&[], 0).is_none());
+  }
+}
@@ -0,0 +1,141 @@
+use crate::diff::DiffLine;
+use crate::patch::{PatchLineKind, patch_kind};
+
+// Line backgrounds of the rich text export, as RGB
+const RTF_ADDED_COLOR: (u8, u8, u8) = (0xd7, 0xf5, 0xdd);
+const RTF_REMOVED_COLOR: (u8, u8, u8) = (0xfd, 0xdc, 0xdc);
+
+/// Text format diff lines are exported to, like when copying them to paste elsewhere
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+  Markdown, // Fenced ```diff block, rendered with colors by most Markdown viewers
+  Rtf,      // Rich text document with colored line backgrounds
+}
+
+/// Diff lines as text in `format`, each line prefixed by ' ', '-' or '+' like in a patch
+pub fn export(lines: &[DiffLine], format: ExportFormat) -> String {
+  match format {
+    ExportFormat::Markdown => to_markdown(lines),
+    ExportFormat::Rtf => to_rtf(lines),
+  }
+}
+
+/// Diff lines as a fenced ```diff Markdown block
+pub fn to_markdown(lines: &[DiffLine]) -> String {
+  let body: String = lines
+    .iter()
+    .map(|line| format!("{}{}\n", prefix(line), content(line)))
+    .collect();
+  // The fence has to be longer than any run of backticks inside
+  let longest_run = body.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+  let fence = "`".repeat(longest_run.max(2) + 1);
+  format!("{}diff\n{}{}\n", fence, body, fence)
+}
+
+/// Diff lines as an RTF document in a monospace font, with green and red line backgrounds
+pub fn to_rtf(lines: &[DiffLine]) -> String {
+  let color = |(r, g, b): (u8, u8, u8)| format!("\\red{}\\green{}\\blue{};", r, g, b);
+  let mut rtf = format!(
+    "{{\\rtf1\\ansi\\deff0{{\\fonttbl{{\\f0\\fmodern Menlo;}}}}{{\\colortbl;{}{}}}\\f0\\fs24\n",
+    color(RTF_ADDED_COLOR),
+    color(RTF_REMOVED_COLOR)
+  );
+  for line in lines {
+    // Color table indices, 0 is the default
+    let highlight = match patch_kind(line) {
+      PatchLineKind::Context => 0,
+      PatchLineKind::Added => 1,
+      PatchLineKind::Removed => 2,
+    };
+    let text = format!("{}{}", prefix(line), content(line));
+    rtf.push_str(&format!(
+      "\\highlight{} {}\\highlight0\\par\n",
+      highlight,
+      rtf_escape(&text)
+    ));
+  }
+  rtf.push('}');
+  rtf
+}
+
+fn prefix(line: &DiffLine) -> char {
+  match patch_kind(line) {
+    PatchLineKind::Context => ' ',
+    PatchLineKind::Removed => '-',
+    PatchLineKind::Added => '+',
+  }
+}
+
+fn content(line: &DiffLine) -> &str {
+  line.content.trim_end_matches('\n')
+}
+
+/// Escapes RTF control chars, chars outside ASCII are written as UTF-16 code units
+fn rtf_escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '\\' | '{' | '}' => {
+        escaped.push('\\');
+        escaped.push(c);
+      }
+      '\t' => escaped.push_str("\\tab "),
+      c if c.is_ascii() => escaped.push(c),
+      c => {
+        let mut units = [0u16; 2];
+        for unit in c.encode_utf16(&mut units) {
+          // RTF takes signed 16-bit values, followed by a fallback char for older readers
+          escaped.push_str(&format!("\\u{}?", *unit as i16));
+        }
+      }
+    }
+  }
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Differ;
+
+  #[test]
+  fn test_to_markdown() {
+    let differ = Differ::new("keep\nold\n".to_string());
+    let diff = differ.compute_diff("keep\nnew value\n");
+    let lines = &diff[..diff.len() - 1];
+
+    assert_eq!(
+      to_markdown(lines),
+      "```diff\n keep\n-old\n+new value\n```\n"
+    );
+  }
+
+  #[test]
+  fn test_markdown_fence_outgrows_backticks() {
+    let differ = Differ::new("a\n".to_string());
+    let diff = differ.compute_diff("```rust\n");
+    let markdown = to_markdown(&diff[..diff.len() - 1]);
+    assert!(markdown.starts_with("````diff\n"));
+    assert!(markdown.ends_with("\n````\n"));
+  }
+
+  #[test]
+  fn test_to_rtf() {
+    let differ = Differ::new("fn a() {}\n".to_string());
+    let diff = differ.compute_diff("fn é() {}\n");
+    let rtf = to_rtf(&diff[..diff.len() - 1]);
+
+    assert!(rtf.starts_with("{\\rtf1"));
+    assert!(rtf.ends_with('}'));
+    assert!(rtf.contains("\\highlight2 -fn a() \\{\\}\\highlight0\\par"));
+    assert!(rtf.contains("\\highlight1 +fn \\u233?() \\{\\}\\highlight0\\par"));
+  }
+
+  #[test]
+  fn test_rtf_escape() {
+    assert_eq!(rtf_escape("a\\b"), "a\\\\b");
+    assert_eq!(rtf_escape("\tx"), "\\tab x");
+    // Surrogate pair, both halves negative as signed values
+    assert_eq!(rtf_escape("😀"), "\\u-10179?\\u-8704?");
+  }
+}
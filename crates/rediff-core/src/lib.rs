@@ -0,0 +1,227 @@
+//! Headless diff engine used by the rediff editor
+//!
+//! Computes line diffs with paired modifications and intra-line ranges, without any UI dependency.
+//!
+//! ```
+//! use rediff_core::{DiffLineKind, DiffOptions};
+//!
+//! let result = rediff_core::diff("a\nb\n", "a\nc\n", &DiffOptions::default());
+//! assert!(result.has_changes());
+//! assert_eq!(result.hunks.len(), 1);
+//! assert!(result.lines.iter().any(|line| line.kind == DiffLineKind::Added));
+//! ```
+
+mod diff;
+mod export;
+mod line_map;
+mod merge;
+mod options;
+mod patch;
+mod prose;
+#[cfg(feature = "structured")]
+mod structured;
+
+pub use diff::{CharRange, DiffHunk, DiffLine, DiffLineKind, DiffStats, Differ, LinePair};
+pub use export::{ExportFormat, export, to_markdown, to_rtf};
+pub use line_map::LineMap;
+pub use merge::{
+  Conflict, ConflictMarkers, MergeChunk, MergeResult, Resolution, find_conflicts, merge,
+};
+pub use options::{DiffOptions, Granularity, WhitespaceMode};
+pub use patch::{FilePatch, PatchError, PatchHunk, PatchLine, PatchLineKind, parse_patch};
+pub use prose::{ProseGranularity, ProseParagraph, ProseSpan, ProseSpanKind, prose_diff};
+#[cfg(feature = "structured")]
+pub use structured::{
+  ChangeGroup, PathSegment, StructuredDiff, StructuredError, StructuredFormat, ValueChange,
+  ValueChangeKind, ValuePath, structured_diff,
+};
+
+/// Lines, hunks and line counts of a diff between two texts
+///
+/// With the `serde` feature it serializes to the JSON read by other tools, fields are only
+/// ever added to it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiffResult {
+  pub lines: Vec<DiffLine>,
+  pub hunks: Vec<DiffHunk>,
+  pub stats: DiffStats,
+}
+
+impl DiffResult {
+  pub fn has_changes(&self) -> bool {
+    !self.hunks.is_empty()
+  }
+}
+
+/// Diffs `modified` against `original`, line breaks of both texts are normalized to '\n'
+pub fn diff(original: &str, modified: &str, options: &DiffOptions) -> DiffResult {
+  let differ = Differ::with_options(original.to_string(), options.clone());
+  let lines = differ.compute_diff(&text::LineEnding::normalize(modified));
+  let hunks = Differ::compute_hunks(&lines);
+  let stats = Differ::compute_stats(&lines);
+  DiffResult {
+    lines,
+    hunks,
+    stats,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_diff_without_changes() {
+    let result = diff("a\nb\n", "a\nb\n", &DiffOptions::default());
+    assert!(!result.has_changes());
+    assert!(
+      result
+        .lines
+        .iter()
+        .all(|l| l.kind == DiffLineKind::Unchanged)
+    );
+  }
+
+  #[test]
+  fn test_diff_normalizes_line_endings() {
+    let result = diff("a\r\nb\r\n", "a\nb\n", &DiffOptions::default());
+    assert!(!result.has_changes());
+  }
+
+  #[test]
+  fn test_diff_ignore_all_whitespace() {
+    let original = "fn main() {\n  run();\n}\n";
+    let modified = "fn main() {\n    run( );\n}\n";
+
+    let result = diff(original, modified, &DiffOptions::default());
+    assert!(result.has_changes());
+
+    let options = DiffOptions {
+      whitespace: WhitespaceMode::IgnoreAll,
+      ..Default::default()
+    };
+    let result = diff(original, modified, &options);
+    assert!(!result.has_changes());
+    // Unchanged lines show the modified text
+    assert_eq!(result.lines[1].content, "    run( );\n");
+  }
+
+  #[test]
+  fn test_diff_ignore_indentation_and_case() {
+    let original = "if ok {\nrun();\n}\n";
+    let modified = "if ok {\n    RUN();  \n}\n";
+
+    let options = DiffOptions {
+      whitespace: WhitespaceMode::IgnoreLeadingTrailing,
+      ..Default::default()
+    };
+    assert!(diff(original, modified, &options).has_changes());
+
+    let options = DiffOptions {
+      ignore_case: true,
+      ..options
+    };
+    assert!(!diff(original, modified, &options).has_changes());
+  }
+
+  #[test]
+  fn test_diff_similarity_threshold() {
+    let original = "let value = 1;\n";
+    let modified = "let other = 2;\n";
+
+    let result = diff(original, modified, &DiffOptions::default());
+    assert!(
+      result
+        .lines
+        .iter()
+        .all(|l| l.kind != DiffLineKind::Added && l.kind != DiffLineKind::Removed)
+    );
+
+    // A threshold of 1.0 can never be exceeded, nothing is paired
+    let options = DiffOptions {
+      similarity_threshold: 1.0,
+      ..Default::default()
+    };
+    let result = diff(original, modified, &options);
+    assert!(
+      result
+        .lines
+        .iter()
+        .all(|l| l.kind != DiffLineKind::Modified)
+    );
+  }
+
+  #[test]
+  fn test_diff_without_intra_line_ranges() {
+    let options = DiffOptions {
+      granularity: Granularity::None,
+      ..Default::default()
+    };
+    let result = diff("hello world\n", "hello there\n", &options);
+    let modified: Vec<_> = result
+      .lines
+      .iter()
+      .filter(|l| l.kind == DiffLineKind::Modified)
+      .collect();
+    assert_eq!(modified.len(), 2);
+    assert!(modified.iter().all(|l| l.char_changes.is_empty()));
+  }
+
+  // Other tools read this JSON, renaming or removing a field breaks them
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_json_schema() {
+    use serde_json::Value;
+
+    fn keys(value: &Value) -> Vec<&str> {
+      let mut keys: Vec<&str> = value
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+      keys.sort();
+      keys
+    }
+
+    let result = diff(
+      "a\nlet value = 1;\n",
+      "a\nlet value = 2;\n",
+      &DiffOptions::default(),
+    );
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(keys(&json), ["hunks", "lines", "stats"]);
+    assert_eq!(
+      keys(&json["stats"]),
+      ["added", "modified", "moved", "removed"]
+    );
+    assert_eq!(json["stats"]["modified"], 1);
+
+    let hunk = &json["hunks"][0];
+    assert_eq!(keys(hunk), ["line_idx", "rows"]);
+    assert_eq!(keys(&hunk["rows"]), ["end", "start"]);
+
+    let lines = json["lines"].as_array().unwrap();
+    assert_eq!(lines[0]["kind"], "unchanged");
+    let added_half = lines.iter().find(|line| !line["pair"].is_null()).unwrap();
+    assert_eq!(
+      keys(added_half),
+      [
+        "char_changes",
+        "content",
+        "counterpart",
+        "is_first_in_group",
+        "kind",
+        "line_number",
+        "pair"
+      ]
+    );
+    assert_eq!(added_half["kind"], "modified");
+    assert_eq!(keys(&added_half["pair"]), ["content", "ranges"]);
+    assert_eq!(keys(&added_half["char_changes"][0]), ["end", "start"]);
+
+    let parsed: DiffResult = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed, result);
+  }
+}
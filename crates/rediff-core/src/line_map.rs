@@ -0,0 +1,93 @@
+use similar::{Algorithm, DiffOp, capture_diff_slices};
+
+/// Maps lines of a text to the lines of a later version of it, through their line diff
+///
+/// Used to keep positions on the same content when a file changes under them, like on reload
+#[derive(Debug, Clone)]
+pub struct LineMap {
+  ops: Vec<DiffOp>,
+  new_line_count: usize,
+}
+
+impl LineMap {
+  pub fn new(old: &str, new: &str) -> Self {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    Self {
+      ops: capture_diff_slices(Algorithm::Myers, &old_lines, &new_lines),
+      new_line_count: new_lines.len(),
+    }
+  }
+
+  /// Line of the new text at the place of `line`, and whether it's the same content
+  ///
+  /// A rewritten line maps to the line replacing it, a removed one to the line that followed it
+  pub fn map_line(&self, line: usize) -> (usize, bool) {
+    for op in &self.ops {
+      let old_range = op.old_range();
+      if !old_range.contains(&line) {
+        continue;
+      }
+      let new_range = op.new_range();
+      let offset = line - old_range.start;
+      return match op {
+        DiffOp::Equal { .. } => (new_range.start + offset, true),
+        _ if new_range.is_empty() => (new_range.start, false),
+        _ => (new_range.start + offset.min(new_range.len() - 1), false),
+      };
+    }
+    // Past the end of the old text
+    (self.new_line_count, false)
+  }
+
+  /// Line and char column in the new text, the column is kept on lines that are still there
+  /// or were rewritten, removed lines move to the start of the following one
+  pub fn map_position(&self, line: usize, col: usize) -> (usize, usize) {
+    match self.map_line(line) {
+      (new_line, true) => (new_line, col),
+      (new_line, false) if self.was_removed(line) => (new_line, 0),
+      (new_line, false) => (new_line, col),
+    }
+  }
+
+  fn was_removed(&self, line: usize) -> bool {
+    self
+      .ops
+      .iter()
+      .any(|op| op.old_range().contains(&line) && op.new_range().is_empty())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_lines_shift_with_insertions_above() {
+    let map = LineMap::new("a\nb\nc\n", "new\na\nb\nc\n");
+    assert_eq!(map.map_line(0), (1, true));
+    assert_eq!(map.map_line(2), (3, true));
+    assert_eq!(map.map_position(1, 1), (2, 1));
+  }
+
+  #[test]
+  fn test_rewritten_and_removed_lines() {
+    let map = LineMap::new("a\nb\nc\nd\n", "a\nB\nd\n");
+    // "b" and "c" became "B", both land on it
+    assert_eq!(map.map_line(1), (1, false));
+    assert_eq!(map.map_line(2), (1, false));
+    assert_eq!(map.map_position(1, 1), (1, 1));
+    assert_eq!(map.map_line(3), (2, true));
+
+    let map = LineMap::new("a\nb\nc\n", "a\nc\n");
+    assert_eq!(map.map_position(1, 1), (1, 0));
+  }
+
+  #[test]
+  fn test_lines_past_the_end() {
+    let map = LineMap::new("a\nb\n", "a\n");
+    assert_eq!(map.map_line(1), (1, false));
+    assert_eq!(map.map_line(5), (1, false));
+    assert_eq!(LineMap::new("", "").map_line(0), (0, false));
+  }
+}
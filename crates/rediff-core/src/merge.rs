@@ -0,0 +1,445 @@
+use similar::{Algorithm, DiffTag, capture_diff_slices};
+use std::ops::Range;
+use text::LineEnding;
+
+const OURS_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SEPARATOR_MARKER: &str = "=======";
+const THEIRS_MARKER: &str = ">>>>>>>";
+
+/// Lines changed differently by both sides
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+  pub base: Vec<String>,
+  pub ours: Vec<String>,
+  pub theirs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+  Ours,
+  Theirs,
+  Both, // Ours then theirs
+}
+
+impl Conflict {
+  pub fn resolve(&self, resolution: Resolution) -> Vec<String> {
+    match resolution {
+      Resolution::Ours => self.ours.clone(),
+      Resolution::Theirs => self.theirs.clone(),
+      Resolution::Both => self.ours.iter().chain(&self.theirs).cloned().collect(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeChunk {
+  Unchanged(Vec<String>), // Same on all sides
+  Merged(Vec<String>),    // Changed by one side only, or identically by both
+  Conflict(Conflict),
+}
+
+/// Result of a three-way merge, lines keep their '\n'
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeResult {
+  pub chunks: Vec<MergeChunk>,
+}
+
+impl MergeResult {
+  pub fn has_conflicts(&self) -> bool {
+    self.conflicts().next().is_some()
+  }
+
+  pub fn conflicts(&self) -> impl Iterator<Item = &Conflict> {
+    self.chunks.iter().filter_map(|chunk| match chunk {
+      MergeChunk::Conflict(conflict) => Some(conflict),
+      _ => None,
+    })
+  }
+
+  /// Merged text with every conflict resolved the same way
+  pub fn resolved_text(&self, resolution: Resolution) -> String {
+    let mut text = String::new();
+    for chunk in &self.chunks {
+      match chunk {
+        MergeChunk::Unchanged(lines) | MergeChunk::Merged(lines) => push_lines(&mut text, lines),
+        MergeChunk::Conflict(conflict) => push_lines(&mut text, &conflict.resolve(resolution)),
+      }
+    }
+    text
+  }
+
+  /// Merged text with git style markers around conflicts
+  pub fn text_with_markers(&self, ours_label: &str, theirs_label: &str) -> String {
+    let mut text = String::new();
+    for chunk in &self.chunks {
+      match chunk {
+        MergeChunk::Unchanged(lines) | MergeChunk::Merged(lines) => push_lines(&mut text, lines),
+        MergeChunk::Conflict(conflict) => {
+          push_marker(&mut text, OURS_MARKER, ours_label);
+          push_lines(&mut text, &conflict.ours);
+          push_marker(&mut text, SEPARATOR_MARKER, "");
+          push_lines(&mut text, &conflict.theirs);
+          push_marker(&mut text, THEIRS_MARKER, theirs_label);
+        }
+      }
+    }
+    text
+  }
+}
+
+fn push_lines(text: &mut String, lines: &[String]) {
+  for line in lines {
+    text.push_str(line);
+  }
+}
+
+fn push_marker(text: &mut String, marker: &str, label: &str) {
+  // The last line of a side may have no newline
+  if !text.is_empty() && !text.ends_with('\n') {
+    text.push('\n');
+  }
+  text.push_str(marker);
+  if !label.is_empty() {
+    text.push(' ');
+    text.push_str(label);
+  }
+  text.push('\n');
+}
+
+/// Changed base lines and the lines replacing them on one side
+#[derive(Debug, Clone)]
+struct SideChange {
+  base: Range<usize>,
+  side: Range<usize>,
+}
+
+fn side_changes(base: &[&str], side: &[&str]) -> Vec<SideChange> {
+  let mut changes: Vec<SideChange> = Vec::new();
+
+  for op in capture_diff_slices(Algorithm::Myers, base, side) {
+    let (tag, base_range, side_range) = op.as_tag_tuple();
+    if tag == DiffTag::Equal {
+      continue;
+    }
+
+    // A delete followed by an insert is a single replacement
+    match changes.last_mut() {
+      Some(last) if last.base.end == base_range.start && last.side.end == side_range.start => {
+        last.base.end = base_range.end;
+        last.side.end = side_range.end;
+      }
+      _ => changes.push(SideChange {
+        base: base_range,
+        side: side_range,
+      }),
+    }
+  }
+
+  changes
+}
+
+/// Lines of one side covering the base region, given the side changes that fall in it
+fn side_lines(
+  region: &Range<usize>,
+  changes: &[SideChange],
+  base: &[&str],
+  side: &[&str],
+) -> Vec<String> {
+  let (Some(first), Some(last)) = (changes.first(), changes.last()) else {
+    return base[region.clone()].iter().map(|l| l.to_string()).collect();
+  };
+
+  // Outside of its changes a side has the base lines, offsets stay the same
+  let start = first.side.start - (first.base.start - region.start);
+  let end = last.side.end + (region.end - last.base.end);
+  side[start..end].iter().map(|l| l.to_string()).collect()
+}
+
+/// Three-way merge of `ours` and `theirs`, both derived from `base`
+///
+/// Changes of both sides touching the same or adjacent base lines conflict,
+/// unless they're identical
+pub fn merge(base: &str, ours: &str, theirs: &str) -> MergeResult {
+  let base = LineEnding::normalize(base);
+  let ours = LineEnding::normalize(ours);
+  let theirs = LineEnding::normalize(theirs);
+
+  let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+  let ours_lines: Vec<&str> = ours.split_inclusive('\n').collect();
+  let theirs_lines: Vec<&str> = theirs.split_inclusive('\n').collect();
+
+  let ours_changes = side_changes(&base_lines, &ours_lines);
+  let theirs_changes = side_changes(&base_lines, &theirs_lines);
+
+  let mut chunks = Vec::new();
+  let mut base_pos = 0;
+  let (mut i, mut j) = (0, 0);
+
+  while i < ours_changes.len() || j < theirs_changes.len() {
+    // Start from the earliest change, then absorb every change touching the region
+    let ours_first = match (ours_changes.get(i), theirs_changes.get(j)) {
+      (Some(o), Some(t)) => o.base.start <= t.base.start,
+      (Some(_), None) => true,
+      _ => false,
+    };
+    let mut region = if ours_first {
+      ours_changes[i].base.clone()
+    } else {
+      theirs_changes[j].base.clone()
+    };
+    let (ours_start, theirs_start) = (i, j);
+
+    loop {
+      if let Some(change) = ours_changes.get(i)
+        && change.base.start <= region.end
+      {
+        region.start = region.start.min(change.base.start);
+        region.end = region.end.max(change.base.end);
+        i += 1;
+        continue;
+      }
+      if let Some(change) = theirs_changes.get(j)
+        && change.base.start <= region.end
+      {
+        region.start = region.start.min(change.base.start);
+        region.end = region.end.max(change.base.end);
+        j += 1;
+        continue;
+      }
+      break;
+    }
+
+    if base_pos < region.start {
+      chunks.push(MergeChunk::Unchanged(
+        base_lines[base_pos..region.start]
+          .iter()
+          .map(|l| l.to_string())
+          .collect(),
+      ));
+    }
+    base_pos = region.end;
+
+    let ours_in_region = &ours_changes[ours_start..i];
+    let theirs_in_region = &theirs_changes[theirs_start..j];
+    let ours_text = side_lines(&region, ours_in_region, &base_lines, &ours_lines);
+    let theirs_text = side_lines(&region, theirs_in_region, &base_lines, &theirs_lines);
+
+    let chunk = if theirs_in_region.is_empty() {
+      MergeChunk::Merged(ours_text)
+    } else if ours_in_region.is_empty() || ours_text == theirs_text {
+      MergeChunk::Merged(theirs_text)
+    } else {
+      MergeChunk::Conflict(Conflict {
+        base: base_lines[region].iter().map(|l| l.to_string()).collect(),
+        ours: ours_text,
+        theirs: theirs_text,
+      })
+    };
+    chunks.push(chunk);
+  }
+
+  if base_pos < base_lines.len() {
+    chunks.push(MergeChunk::Unchanged(
+      base_lines[base_pos..]
+        .iter()
+        .map(|l| l.to_string())
+        .collect(),
+    ));
+  }
+
+  MergeResult { chunks }
+}
+
+/// A conflict found in text through its markers, ranges are 0-based line indices
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConflictMarkers {
+  pub lines: Range<usize>, // Whole conflict, markers included
+  pub ours: Range<usize>,
+  pub base: Option<Range<usize>>, // Only with diff3 style markers
+  pub theirs: Range<usize>,
+}
+
+impl ConflictMarkers {
+  /// Text replacing the whole conflict for the given resolution
+  pub fn resolution_text(&self, text: &str, resolution: Resolution) -> String {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let side = |range: &Range<usize>| lines[range.clone()].concat();
+
+    let mut resolved = match resolution {
+      Resolution::Ours => side(&self.ours),
+      Resolution::Theirs => side(&self.theirs),
+      Resolution::Both => {
+        let mut ours = side(&self.ours);
+        if !ours.is_empty() && !ours.ends_with('\n') {
+          ours.push('\n');
+        }
+        ours + &side(&self.theirs)
+      }
+    };
+
+    // The conflict keeps the line break of its closing marker
+    let ends_with_newline = lines
+      .get(self.lines.end - 1)
+      .is_some_and(|line| line.ends_with('\n'));
+    if !ends_with_newline && resolved.ends_with('\n') {
+      resolved.pop();
+    }
+    resolved
+  }
+}
+
+/// Finds the conflicts marked with `<<<<<<<`, `=======` and `>>>>>>>` in text
+pub fn find_conflicts(text: &str) -> Vec<ConflictMarkers> {
+  let mut conflicts = Vec::new();
+  let mut start = None;
+  let mut base_start = None;
+  let mut separator = None;
+
+  for (line_idx, line) in text.lines().enumerate() {
+    if line.starts_with(OURS_MARKER) {
+      start = Some(line_idx);
+      base_start = None;
+      separator = None;
+    } else if line.starts_with(BASE_MARKER) && start.is_some() && separator.is_none() {
+      base_start = Some(line_idx);
+    } else if line.starts_with(SEPARATOR_MARKER) && start.is_some() {
+      separator = Some(line_idx);
+    } else if line.starts_with(THEIRS_MARKER)
+      && let (Some(start), Some(separator)) = (start.take(), separator.take())
+    {
+      let ours_end = base_start.unwrap_or(separator);
+      conflicts.push(ConflictMarkers {
+        lines: start..line_idx + 1,
+        ours: start + 1..ours_end,
+        base: base_start
+          .take()
+          .map(|base_start| base_start + 1..separator),
+        theirs: separator + 1..line_idx,
+      });
+    }
+  }
+
+  conflicts
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lines(text: &str) -> Vec<String> {
+    text.split_inclusive('\n').map(|l| l.to_string()).collect()
+  }
+
+  #[test]
+  fn test_merge_without_changes() {
+    let result = merge("a\nb\n", "a\nb\n", "a\nb\n");
+    assert_eq!(result.chunks, vec![MergeChunk::Unchanged(lines("a\nb\n"))]);
+    assert!(!result.has_conflicts());
+  }
+
+  #[test]
+  fn test_merge_changes_on_different_lines() {
+    let base = "a\nb\nc\nd\ne\n";
+    let ours = "A\nb\nc\nd\ne\n";
+    let theirs = "a\nb\nc\nd\nE\n";
+
+    let result = merge(base, ours, theirs);
+    assert!(!result.has_conflicts());
+    assert_eq!(result.resolved_text(Resolution::Ours), "A\nb\nc\nd\nE\n");
+  }
+
+  #[test]
+  fn test_merge_identical_changes() {
+    let result = merge("a\nb\n", "a\nB\n", "a\nB\n");
+    assert!(!result.has_conflicts());
+    assert_eq!(result.resolved_text(Resolution::Theirs), "a\nB\n");
+  }
+
+  #[test]
+  fn test_merge_conflict() {
+    let result = merge("a\nb\nc\n", "a\nours\nc\n", "a\ntheirs\nc\n");
+
+    let conflicts: Vec<_> = result.conflicts().collect();
+    assert_eq!(
+      conflicts,
+      vec![&Conflict {
+        base: lines("b\n"),
+        ours: lines("ours\n"),
+        theirs: lines("theirs\n"),
+      }]
+    );
+    assert_eq!(result.resolved_text(Resolution::Ours), "a\nours\nc\n");
+    assert_eq!(result.resolved_text(Resolution::Theirs), "a\ntheirs\nc\n");
+    assert_eq!(
+      result.resolved_text(Resolution::Both),
+      "a\nours\ntheirs\nc\n"
+    );
+  }
+
+  #[test]
+  fn test_merge_adjacent_changes_conflict() {
+    let result = merge("a\nb\nc\n", "A\nb\nc\n", "a\nB\nc\n");
+    let conflict = result.conflicts().next().unwrap();
+    assert_eq!(conflict.ours, lines("A\nb\n"));
+    assert_eq!(conflict.theirs, lines("a\nB\n"));
+  }
+
+  #[test]
+  fn test_merge_insertions_and_deletions() {
+    let base = "a\nb\nc\nd\n";
+    let ours = "a\nc\nd\n"; // b deleted
+    let theirs = "a\nb\nc\nd\ne\n"; // e appended
+
+    let result = merge(base, ours, theirs);
+    assert!(!result.has_conflicts());
+    assert_eq!(result.resolved_text(Resolution::Ours), "a\nc\nd\ne\n");
+  }
+
+  #[test]
+  fn test_text_with_markers() {
+    let result = merge("a\nb", "a\nours", "a\ntheirs");
+    assert_eq!(
+      result.text_with_markers("HEAD", "feature"),
+      "a\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> feature\n"
+    );
+  }
+
+  #[test]
+  fn test_find_conflicts_roundtrip() {
+    let result = merge("x\nb\ny\n", "x\nours\ny\n", "x\ntheirs\ny\n");
+    let text = result.text_with_markers("ours", "theirs");
+    let conflicts = find_conflicts(&text);
+
+    assert_eq!(
+      conflicts,
+      vec![ConflictMarkers {
+        lines: 1..6,
+        ours: 2..3,
+        base: None,
+        theirs: 4..5,
+      }]
+    );
+    assert_eq!(
+      conflicts[0].resolution_text(&text, Resolution::Both),
+      "ours\ntheirs\n"
+    );
+  }
+
+  #[test]
+  fn test_find_conflicts_diff3_style() {
+    let text = "<<<<<<< ours\no\n||||||| base\nb\n=======\nt\n>>>>>>> theirs";
+    let conflicts = find_conflicts(text);
+
+    assert_eq!(conflicts[0].ours, 1..2);
+    assert_eq!(conflicts[0].base, Some(3..4));
+    assert_eq!(conflicts[0].theirs, 5..6);
+    // The closing marker had no newline, neither has the resolution
+    assert_eq!(conflicts[0].resolution_text(text, Resolution::Theirs), "t");
+  }
+
+  #[test]
+  fn test_find_conflicts_ignores_unterminated_markers() {
+    assert!(find_conflicts("<<<<<<< ours\na\n=======\nb\n").is_empty());
+  }
+}
@@ -0,0 +1,122 @@
+/// How whitespace is taken into account when comparing lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WhitespaceMode {
+  #[default]
+  Compare,
+  IgnoreLeadingTrailing, // Indentation and trailing whitespace are ignored
+  IgnoreAll,             // Lines differing only by whitespace are unchanged
+}
+
+/// Precision of the highlights inside modified lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Granularity {
+  #[default]
+  Char,
+  Word, // Whole changed words, split like cursor word motions
+  None, // Modified lines are paired but carry no intra-line ranges
+}
+
+/// Options controlling how a diff is computed
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct DiffOptions {
+  pub whitespace: WhitespaceMode,
+  pub similarity_threshold: f32, // Minimum similarity (0.0 to 1.0) for a removed/added pair to be a modification
+  pub pair_modifications: bool,  // False shows every change as removed and added lines
+  pub granularity: Granularity,
+  pub ignore_case: bool,
+  pub detect_moves: bool, // Blocks removed in one place and added in another are shown as moved
+  pub align_hunks: bool, // Highlights come from diffing all removed lines of a change against all its added lines, so text only moved across a line break isn't highlighted
+}
+
+impl Default for DiffOptions {
+  fn default() -> Self {
+    Self {
+      whitespace: WhitespaceMode::default(),
+      similarity_threshold: 0.3,
+      pair_modifications: true,
+      granularity: Granularity::default(),
+      ignore_case: false,
+      detect_moves: true,
+      align_hunks: false,
+    }
+  }
+}
+
+impl DiffOptions {
+  /// Key used to decide whether two lines are equal
+  pub(crate) fn line_key<'a>(&self, line: &'a str) -> std::borrow::Cow<'a, str> {
+    let key: std::borrow::Cow<'a, str> = match self.whitespace {
+      WhitespaceMode::Compare => line.into(),
+      WhitespaceMode::IgnoreLeadingTrailing => line.trim().into(),
+      WhitespaceMode::IgnoreAll => line.chars().filter(|c| !c.is_whitespace()).collect(),
+    };
+
+    if self.ignore_case {
+      key.to_lowercase().into()
+    } else {
+      key
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_default_options() {
+    let options = DiffOptions::default();
+    assert_eq!(options.whitespace, WhitespaceMode::Compare);
+    assert_eq!(options.similarity_threshold, 0.3);
+    assert!(options.pair_modifications);
+    assert_eq!(options.granularity, Granularity::Char);
+    assert!(!options.ignore_case);
+    assert!(options.detect_moves);
+    assert!(!options.align_hunks);
+  }
+
+  #[test]
+  fn test_line_key() {
+    let options = DiffOptions::default();
+    assert_eq!(options.line_key("  a b\n"), "  a b\n");
+
+    let options = DiffOptions {
+      whitespace: WhitespaceMode::IgnoreAll,
+      ..Default::default()
+    };
+    assert_eq!(options.line_key("  a b\n"), "ab");
+    assert_eq!(options.line_key("\tab"), "ab");
+  }
+
+  #[test]
+  fn test_line_key_ignore_leading_trailing() {
+    let options = DiffOptions {
+      whitespace: WhitespaceMode::IgnoreLeadingTrailing,
+      ..Default::default()
+    };
+    assert_eq!(options.line_key("    a  b \n"), "a  b");
+    assert_eq!(options.line_key("a b"), "a b");
+  }
+
+  #[test]
+  fn test_line_key_ignore_case() {
+    let options = DiffOptions {
+      ignore_case: true,
+      ..Default::default()
+    };
+    assert_eq!(options.line_key("Hello World\n"), "hello world\n");
+
+    let options = DiffOptions {
+      whitespace: WhitespaceMode::IgnoreAll,
+      ignore_case: true,
+      ..Default::default()
+    };
+    assert_eq!(options.line_key(" Foo Bar"), "foobar");
+  }
+}
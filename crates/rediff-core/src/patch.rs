@@ -0,0 +1,449 @@
+use crate::diff::{DiffLine, DiffLineKind};
+use std::fmt;
+
+const NO_NEWLINE_MARKER: &str = "\\ No newline at end of file";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchLineKind {
+  Context,
+  Removed,
+  Added,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchLine {
+  pub kind: PatchLineKind,
+  pub content: String, // Ends with '\n' unless the line is the last one of a file without final newline
+}
+
+/// A `@@ -old_start,old_len +new_start,new_len @@` section of a unified diff
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchHunk {
+  pub old_start: usize,
+  pub old_len: usize,
+  pub new_start: usize,
+  pub new_len: usize,
+  pub lines: Vec<PatchLine>,
+}
+
+/// The changes of a single file in unified diff format
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilePatch {
+  pub old_path: String,
+  pub new_path: String,
+  pub hunks: Vec<PatchHunk>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchError {
+  pub line: usize, // 1-based line of the patch where parsing failed
+  pub message: String,
+}
+
+impl fmt::Display for PatchError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "invalid patch at line {}: {}", self.line, self.message)
+  }
+}
+
+impl std::error::Error for PatchError {}
+
+impl FilePatch {
+  /// Builds a patch from computed diff lines, keeping `context` unchanged lines around changes
+  pub fn from_diff(lines: &[DiffLine], old_path: &str, new_path: &str, context: usize) -> Self {
+    let lines = without_padding(lines);
+
+    // Line numbers on both sides before each row, rows don't carry the original line number
+    let mut positions = Vec::with_capacity(lines.len() + 1);
+    let (mut old_line, mut new_line) = (0, 0);
+    for line in lines {
+      positions.push((old_line, new_line));
+      match patch_kind(line) {
+        PatchLineKind::Context => {
+          old_line += 1;
+          new_line += 1;
+        }
+        PatchLineKind::Removed => old_line += 1,
+        PatchLineKind::Added => new_line += 1,
+      }
+    }
+    positions.push((old_line, new_line));
+
+    let mut hunks = Vec::new();
+    for rows in hunk_rows(lines, context) {
+      let (old_before, new_before) = positions[rows.start];
+      let (old_after, new_after) = positions[rows.end];
+      let old_len = old_after - old_before;
+      let new_len = new_after - new_before;
+
+      hunks.push(PatchHunk {
+        // An empty side starts at the line preceding the hunk
+        old_start: if old_len == 0 {
+          old_before
+        } else {
+          old_before + 1
+        },
+        old_len,
+        new_start: if new_len == 0 {
+          new_before
+        } else {
+          new_before + 1
+        },
+        new_len,
+        lines: lines[rows]
+          .iter()
+          .map(|line| PatchLine {
+            kind: patch_kind(line),
+            content: line.content.clone(),
+          })
+          .collect(),
+      });
+    }
+
+    Self {
+      old_path: old_path.to_string(),
+      new_path: new_path.to_string(),
+      hunks,
+    }
+  }
+
+  /// Path of the patched file, without the `a/` `b/` prefixes
+  pub fn path(&self) -> &str {
+    let path = if self.new_path == "/dev/null" {
+      &self.old_path
+    } else {
+      &self.new_path
+    };
+    path
+      .strip_prefix("b/")
+      .or_else(|| path.strip_prefix("a/"))
+      .unwrap_or(path)
+  }
+
+  /// Text made of the context and removed lines of every hunk
+  pub fn original_text(&self) -> String {
+    self.side_text(PatchLineKind::Removed)
+  }
+
+  /// Text made of the context and added lines of every hunk
+  pub fn modified_text(&self) -> String {
+    self.side_text(PatchLineKind::Added)
+  }
+
+  fn side_text(&self, kind: PatchLineKind) -> String {
+    self
+      .hunks
+      .iter()
+      .flat_map(|hunk| &hunk.lines)
+      .filter(|line| line.kind == kind || line.kind == PatchLineKind::Context)
+      .map(|line| line.content.as_str())
+      .collect()
+  }
+}
+
+impl fmt::Display for FilePatch {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "--- {}", self.old_path)?;
+    writeln!(f, "+++ {}", self.new_path)?;
+
+    for hunk in &self.hunks {
+      writeln!(
+        f,
+        "@@ -{},{} +{},{} @@",
+        hunk.old_start, hunk.old_len, hunk.new_start, hunk.new_len
+      )?;
+
+      for line in &hunk.lines {
+        let prefix = match line.kind {
+          PatchLineKind::Context => ' ',
+          PatchLineKind::Removed => '-',
+          PatchLineKind::Added => '+',
+        };
+        write!(f, "{}{}", prefix, line.content)?;
+        if !line.content.ends_with('\n') {
+          writeln!(f)?;
+          writeln!(f, "{}", NO_NEWLINE_MARKER)?;
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// Parses a unified diff, possibly containing several files
+pub fn parse_patch(text: &str) -> Result<Vec<FilePatch>, PatchError> {
+  let lines: Vec<&str> = text.lines().collect();
+  let mut files = Vec::new();
+  let mut i = 0;
+
+  let error = |line: usize, message: &str| PatchError {
+    line: line + 1,
+    message: message.to_string(),
+  };
+
+  while i < lines.len() {
+    // Skip `diff --git`, `index` and other extended headers
+    let Some(old_path) = lines[i].strip_prefix("--- ") else {
+      i += 1;
+      continue;
+    };
+    let new_path = lines
+      .get(i + 1)
+      .and_then(|line| line.strip_prefix("+++ "))
+      .ok_or_else(|| error(i + 1, "expected `+++` after `---`"))?;
+    i += 2;
+
+    let mut file = FilePatch {
+      old_path: header_path(old_path),
+      new_path: header_path(new_path),
+      hunks: Vec::new(),
+    };
+
+    while i < lines.len() && lines[i].starts_with("@@") {
+      let (old_start, old_len, new_start, new_len) =
+        parse_hunk_header(lines[i]).ok_or_else(|| error(i, "malformed hunk header"))?;
+      i += 1;
+
+      let mut hunk = PatchHunk {
+        old_start,
+        old_len,
+        new_start,
+        new_len,
+        lines: Vec::new(),
+      };
+      let (mut old_seen, mut new_seen) = (0, 0);
+
+      while old_seen < old_len || new_seen < new_len {
+        let line = *lines
+          .get(i)
+          .ok_or_else(|| error(i, "hunk is shorter than its header"))?;
+
+        // Some tools strip the space of empty context lines
+        let (kind, content) = match line.chars().next() {
+          Some(' ') => (PatchLineKind::Context, &line[1..]),
+          None => (PatchLineKind::Context, ""),
+          Some('-') => (PatchLineKind::Removed, &line[1..]),
+          Some('+') => (PatchLineKind::Added, &line[1..]),
+          Some('\\') => {
+            strip_last_newline(&mut hunk);
+            i += 1;
+            continue;
+          }
+          _ => return Err(error(i, "unexpected line in hunk")),
+        };
+
+        match kind {
+          PatchLineKind::Context => {
+            old_seen += 1;
+            new_seen += 1;
+          }
+          PatchLineKind::Removed => old_seen += 1,
+          PatchLineKind::Added => new_seen += 1,
+        }
+        if old_seen > old_len || new_seen > new_len {
+          return Err(error(i, "hunk is longer than its header"));
+        }
+
+        hunk.lines.push(PatchLine {
+          kind,
+          content: format!("{}\n", content),
+        });
+        i += 1;
+      }
+
+      // The marker can follow the last line of the hunk
+      if lines.get(i).is_some_and(|line| line.starts_with('\\')) {
+        strip_last_newline(&mut hunk);
+        i += 1;
+      }
+
+      file.hunks.push(hunk);
+    }
+
+    files.push(file);
+  }
+
+  Ok(files)
+}
+
+pub(crate) fn patch_kind(line: &DiffLine) -> PatchLineKind {
+  match line.kind {
+    DiffLineKind::Unchanged => PatchLineKind::Context,
+    DiffLineKind::Removed => PatchLineKind::Removed,
+    DiffLineKind::Added => PatchLineKind::Added,
+    DiffLineKind::Modified | DiffLineKind::Moved if line.line_number == 0 => PatchLineKind::Removed,
+    DiffLineKind::Modified | DiffLineKind::Moved => PatchLineKind::Added,
+  }
+}
+
+/// Drops the trailing row `compute_diff` adds for the empty line after a final newline
+///
+/// That row is always last, unchanged and made of a lone '\n': a real line like this
+/// would end the text with a newline and thus be followed by the padding row itself
+fn without_padding(lines: &[DiffLine]) -> &[DiffLine] {
+  match lines.last() {
+    Some(last) if last.kind == DiffLineKind::Unchanged && last.content == "\n" => {
+      &lines[..lines.len() - 1]
+    }
+    _ => lines,
+  }
+}
+
+/// Row ranges of the hunks, changes closer than twice the context are merged
+fn hunk_rows(lines: &[DiffLine], context: usize) -> Vec<std::ops::Range<usize>> {
+  let mut hunks: Vec<std::ops::Range<usize>> = Vec::new();
+
+  for (row, line) in lines.iter().enumerate() {
+    if line.kind == DiffLineKind::Unchanged {
+      continue;
+    }
+
+    let start = row.saturating_sub(context);
+    let end = (row + 1 + context).min(lines.len());
+    match hunks.last_mut() {
+      Some(last) if start <= last.end => last.end = end,
+      _ => hunks.push(start..end),
+    }
+  }
+
+  hunks
+}
+
+/// Removes the trailing tab and timestamp some tools append to header paths
+fn header_path(path: &str) -> String {
+  path
+    .split('\t')
+    .next()
+    .unwrap_or(path)
+    .trim_end()
+    .to_string()
+}
+
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize, usize)> {
+  let ranges = line.strip_prefix("@@ ")?.split(" @@").next()?;
+  let (old, new) = ranges.split_once(' ')?;
+  let (old_start, old_len) = parse_range(old.strip_prefix('-')?)?;
+  let (new_start, new_len) = parse_range(new.strip_prefix('+')?)?;
+  Some((old_start, old_len, new_start, new_len))
+}
+
+/// Parses `start,len`, the length defaults to 1 when omitted
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+  match range.split_once(',') {
+    Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+    None => Some((range.parse().ok()?, 1)),
+  }
+}
+
+fn strip_last_newline(hunk: &mut PatchHunk) {
+  if let Some(line) = hunk.lines.last_mut()
+    && line.content.ends_with('\n')
+  {
+    line.content.pop();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Differ;
+
+  fn patch_for(original: &str, modified: &str) -> FilePatch {
+    let lines = Differ::new(original.to_string()).compute_diff(modified);
+    FilePatch::from_diff(&lines, "a/file.txt", "b/file.txt", 3)
+  }
+
+  #[test]
+  fn test_export_single_change() {
+    let patch = patch_for("a\nb\nc\n", "a\nB\nc\n");
+    assert_eq!(
+      patch.to_string(),
+      "--- a/file.txt\n+++ b/file.txt\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n"
+    );
+  }
+
+  #[test]
+  fn test_export_without_changes_has_no_hunk() {
+    let patch = patch_for("a\nb\n", "a\nb\n");
+    assert!(patch.hunks.is_empty());
+    assert_eq!(patch.to_string(), "--- a/file.txt\n+++ b/file.txt\n");
+  }
+
+  #[test]
+  fn test_export_limits_context_and_splits_hunks() {
+    let original: String = (1..=20).map(|i| format!("{}\n", i)).collect();
+    let modified: String = (1..=20)
+      .map(|i| match i {
+        2 => "two\n".to_string(),
+        18 => "eighteen\n".to_string(),
+        _ => format!("{}\n", i),
+      })
+      .collect();
+    let patch = patch_for(&original, &modified);
+
+    assert_eq!(patch.hunks.len(), 2);
+    assert_eq!((patch.hunks[0].old_start, patch.hunks[0].old_len), (1, 5));
+    assert_eq!((patch.hunks[1].old_start, patch.hunks[1].old_len), (15, 6));
+  }
+
+  #[test]
+  fn test_export_pure_addition_at_start() {
+    let patch = patch_for("a\n", "new\na\n");
+    let hunk = &patch.hunks[0];
+    assert_eq!((hunk.old_start, hunk.old_len), (1, 1));
+    assert_eq!((hunk.new_start, hunk.new_len), (1, 2));
+  }
+
+  #[test]
+  fn test_export_no_newline_at_end() {
+    let patch = patch_for("a\nb", "a\nc");
+    assert!(
+      patch
+        .to_string()
+        .ends_with("-b\n\\ No newline at end of file\n+c\n\\ No newline at end of file\n")
+    );
+  }
+
+  #[test]
+  fn test_roundtrip() {
+    let patch = patch_for("one\ntwo\nthree\nfour\n", "one\n2\nthree\nfour\nfive");
+    let parsed = parse_patch(&patch.to_string()).unwrap();
+    assert_eq!(parsed, vec![patch]);
+  }
+
+  #[test]
+  fn test_parse_git_patch() {
+    let text = "diff --git a/src/lib.rs b/src/lib.rs\nindex 123..456 100644\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@ fn main\n-old\n+new\n \n";
+    let files = parse_patch(text).unwrap();
+
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path(), "src/lib.rs");
+    assert_eq!(files[0].original_text(), "old\n\n");
+    assert_eq!(files[0].modified_text(), "new\n\n");
+  }
+
+  #[test]
+  fn test_parse_multiple_files_and_default_len() {
+    let text =
+      "--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n+b\n--- a/y\n+++ /dev/null\n@@ -1 +0,0 @@\n-gone\n";
+    let files = parse_patch(text).unwrap();
+
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].hunks[0].old_len, 1);
+    assert_eq!(files[1].path(), "y");
+    assert_eq!(files[1].modified_text(), "");
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    assert_eq!(parse_patch("--- a/x\n").unwrap_err().line, 2);
+
+    let err = parse_patch("--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n a\n").unwrap_err();
+    assert_eq!(err.line, 5);
+    assert!(err.to_string().contains("shorter"));
+
+    let err = parse_patch("--- a/x\n+++ b/x\n@@ nope @@\n").unwrap_err();
+    assert_eq!(err.line, 3);
+  }
+}
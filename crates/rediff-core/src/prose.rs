@@ -0,0 +1,311 @@
+use similar::{Algorithm, DiffTag, capture_diff_slices};
+
+/// Unit of text compared in a prose diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ProseGranularity {
+  Sentence, // A sentence with any change in it shows as removed and added whole
+  #[default]
+  Word,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ProseSpanKind {
+  Unchanged,
+  Added,
+  Removed,
+}
+
+/// Run of words of the same kind, separated by single spaces
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProseSpan {
+  pub kind: ProseSpanKind,
+  pub text: String,
+}
+
+/// Paragraph of the modified text with the removed words shown where they were
+///
+/// Spans are meant to be joined with spaces
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProseParagraph {
+  pub spans: Vec<ProseSpan>,
+}
+
+impl ProseParagraph {
+  pub fn has_changes(&self) -> bool {
+    self
+      .spans
+      .iter()
+      .any(|span| span.kind != ProseSpanKind::Unchanged)
+  }
+
+  /// Text of the paragraph, removed words included
+  pub fn text(&self) -> String {
+    self
+      .spans
+      .iter()
+      .map(|span| span.text.as_str())
+      .collect::<Vec<_>>()
+      .join(" ")
+  }
+
+  fn push(&mut self, kind: ProseSpanKind, text: &str) {
+    match self.spans.last_mut() {
+      Some(span) if span.kind == kind => {
+        span.text.push(' ');
+        span.text.push_str(text);
+      }
+      _ => self.spans.push(ProseSpan {
+        kind,
+        text: text.to_string(),
+      }),
+    }
+  }
+}
+
+/// Diffs two prose texts by word or sentence, regardless of where their lines wrap
+///
+/// Lines are joined into paragraphs, which end at blank lines and before Markdown headings,
+/// list items and quotes. Whitespace is collapsed, so reflowing a paragraph changes nothing.
+/// Paragraphs follow the modified text, two paragraphs merged show as one
+pub fn prose_diff(
+  original: &str,
+  modified: &str,
+  granularity: ProseGranularity,
+) -> Vec<ProseParagraph> {
+  let old = tokens(original, granularity);
+  let new = tokens(modified, granularity);
+
+  let mut paragraphs = vec![ProseParagraph::default()];
+  let mut push = |kind: ProseSpanKind, tokens: &[Token]| {
+    for (idx, token) in tokens.iter().enumerate() {
+      match token {
+        Token::Text(text) => paragraphs.last_mut().unwrap().push(kind, text),
+        // A removed break only splits off removed paragraphs, two merged ones stay merged
+        Token::Break
+          if kind == ProseSpanKind::Removed
+            && !matches!(tokens.get(idx + 1), Some(Token::Text(_)))
+            && !matches!(
+              idx.checked_sub(1).map(|idx| &tokens[idx]),
+              Some(Token::Text(_))
+            ) => {}
+        Token::Break => {
+          if !paragraphs.last().unwrap().spans.is_empty() {
+            paragraphs.push(ProseParagraph::default());
+          }
+        }
+      }
+    }
+  };
+
+  for op in capture_diff_slices(Algorithm::Myers, &old, &new) {
+    let (tag, old_range, new_range) = op.as_tag_tuple();
+    match tag {
+      DiffTag::Equal => push(ProseSpanKind::Unchanged, &new[new_range]),
+      DiffTag::Delete => push(ProseSpanKind::Removed, &old[old_range]),
+      DiffTag::Insert => push(ProseSpanKind::Added, &new[new_range]),
+      DiffTag::Replace => {
+        push(ProseSpanKind::Removed, &old[old_range]);
+        push(ProseSpanKind::Added, &new[new_range]);
+      }
+    }
+  }
+
+  paragraphs.retain(|paragraph| !paragraph.spans.is_empty());
+  paragraphs
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Token {
+  Text(String), // A word or a sentence
+  Break,        // Between two paragraphs
+}
+
+fn tokens(text: &str, granularity: ProseGranularity) -> Vec<Token> {
+  let mut tokens = Vec::new();
+  for (idx, words) in paragraphs(text).into_iter().enumerate() {
+    if idx > 0 {
+      tokens.push(Token::Break);
+    }
+    match granularity {
+      ProseGranularity::Word => {
+        tokens.extend(words.into_iter().map(|word| Token::Text(word.to_string())))
+      }
+      ProseGranularity::Sentence => {
+        let mut sentence: Vec<&str> = Vec::new();
+        for word in words {
+          sentence.push(word);
+          if ends_sentence(word) {
+            tokens.push(Token::Text(sentence.join(" ")));
+            sentence.clear();
+          }
+        }
+        if !sentence.is_empty() {
+          tokens.push(Token::Text(sentence.join(" ")));
+        }
+      }
+    }
+  }
+  tokens
+}
+
+/// Words of each paragraph of `text`
+fn paragraphs(text: &str) -> Vec<Vec<&str>> {
+  let mut paragraphs = Vec::new();
+  let mut current: Vec<&str> = Vec::new();
+  for line in text.lines() {
+    let line = line.trim();
+    if (line.is_empty() || starts_block(line)) && !current.is_empty() {
+      paragraphs.push(std::mem::take(&mut current));
+    }
+    current.extend(line.split_whitespace());
+    // Headings are a single line
+    if line.starts_with('#') {
+      paragraphs.push(std::mem::take(&mut current));
+    }
+  }
+  if !current.is_empty() {
+    paragraphs.push(current);
+  }
+  paragraphs
+}
+
+/// Whether a trimmed line starts a Markdown block that can't continue the previous line
+fn starts_block(line: &str) -> bool {
+  if line.starts_with(['#', '>', '|']) || line.starts_with("```") {
+    return true;
+  }
+  if let Some(rest) = line.strip_prefix(['-', '*', '+']) {
+    return rest.starts_with(' ');
+  }
+  // Ordered list items, like "1. " or "2) "
+  let digits = line.chars().take_while(char::is_ascii_digit).count();
+  digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") "))
+}
+
+/// Whether a word ends a sentence, closing quotes and brackets after the punctuation included
+fn ends_sentence(word: &str) -> bool {
+  word
+    .trim_end_matches(['"', '\'', ')', ']', '”', '’', '»'])
+    .ends_with(['.', '!', '?', '…'])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Removed spans in [-…-], added ones in {+…+}, paragraphs on their own lines
+  fn describe(paragraphs: &[ProseParagraph]) -> Vec<String> {
+    paragraphs
+      .iter()
+      .map(|paragraph| {
+        paragraph
+          .spans
+          .iter()
+          .map(|span| match span.kind {
+            ProseSpanKind::Unchanged => span.text.clone(),
+            ProseSpanKind::Added => format!("{{+{}+}}", span.text),
+            ProseSpanKind::Removed => format!("[-{}-]", span.text),
+          })
+          .collect::<Vec<_>>()
+          .join(" ")
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_word_changes() {
+    let paragraphs = prose_diff(
+      "The quick brown fox jumps.\n",
+      "The quick red fox leaps.\n",
+      ProseGranularity::Word,
+    );
+    assert_eq!(
+      describe(&paragraphs),
+      ["The quick [-brown-] {+red+} fox [-jumps.-] {+leaps.+}"]
+    );
+    assert!(paragraphs[0].has_changes());
+  }
+
+  #[test]
+  fn test_reflowed_paragraph_is_unchanged() {
+    let original =
+      "Lorem ipsum dolor sit amet,\nconsectetur adipiscing elit.\n\nSecond paragraph.\n";
+    let modified =
+      "Lorem ipsum dolor\nsit amet, consectetur\n   adipiscing    elit.\n\n\nSecond paragraph.\n";
+    let paragraphs = prose_diff(original, modified, ProseGranularity::Word);
+    assert_eq!(
+      describe(&paragraphs),
+      [
+        "Lorem ipsum dolor sit amet, consectetur adipiscing elit.",
+        "Second paragraph."
+      ]
+    );
+    assert!(paragraphs.iter().all(|paragraph| !paragraph.has_changes()));
+  }
+
+  #[test]
+  fn test_sentences() {
+    let paragraphs = prose_diff(
+      "One sentence. Two sentences! Three?\n",
+      "One sentence. Two\nsentence! Three?\n",
+      ProseGranularity::Sentence,
+    );
+    assert_eq!(
+      describe(&paragraphs),
+      ["One sentence. [-Two sentences!-] {+Two sentence!+} Three?"]
+    );
+  }
+
+  #[test]
+  fn test_paragraphs_added_removed_and_merged() {
+    let paragraphs = prose_diff(
+      "First.\n\nGone away.\n\nLast.\n",
+      "First.\n\nLast.\n",
+      ProseGranularity::Word,
+    );
+    assert_eq!(describe(&paragraphs), ["First.", "[-Gone away.-]", "Last."]);
+
+    let paragraphs = prose_diff("First.\n", "First.\n\nNew one.\n", ProseGranularity::Word);
+    assert_eq!(describe(&paragraphs), ["First.", "{+New one.+}"]);
+
+    // Merged paragraphs follow the modified text
+    let paragraphs = prose_diff("One.\n\nTwo.\n", "One. Two.\n", ProseGranularity::Word);
+    assert_eq!(describe(&paragraphs), ["One. Two."]);
+  }
+
+  #[test]
+  fn test_markdown_blocks() {
+    let text = "# Title\nSome text\n- first item\n- second\n1. ordered\n> quote\n-not a list\n";
+    let words = paragraphs(text);
+    assert_eq!(
+      words,
+      [
+        vec!["#", "Title"],
+        vec!["Some", "text"],
+        vec!["-", "first", "item"],
+        vec!["-", "second"],
+        vec!["1.", "ordered"],
+        vec![">", "quote", "-not", "a", "list"],
+      ]
+    );
+  }
+
+  #[test]
+  fn test_empty_texts() {
+    assert!(prose_diff("", "", ProseGranularity::Word).is_empty());
+    assert_eq!(
+      describe(&prose_diff("", "Hello\n", ProseGranularity::Word)),
+      ["{+Hello+}"]
+    );
+    assert_eq!(
+      describe(&prose_diff("A\n\nB\n", "", ProseGranularity::Word)),
+      ["[-A-]", "[-B-]"]
+    );
+  }
+}
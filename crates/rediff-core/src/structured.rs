@@ -0,0 +1,453 @@
+use std::fmt;
+use std::path::Path;
+
+/// Document formats that can be diffed by value instead of by line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructuredFormat {
+  Json,
+  Toml,
+}
+
+impl StructuredFormat {
+  /// Format of a file from its extension
+  pub fn from_path(path: &Path) -> Option<Self> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+      "json" => Some(Self::Json),
+      "toml" => Some(Self::Toml),
+      _ => None,
+    }
+  }
+}
+
+/// Step from a value to one inside it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+  Key(String),  // Key of an object or a table
+  Index(usize), // Index of an array
+}
+
+/// Where a value sits in a document, like `dependencies.serde.features[0]`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValuePath(pub Vec<PathSegment>);
+
+impl ValuePath {
+  pub fn is_root(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  /// Last segment on its own, like `features` or `[0]`, empty for the root
+  pub fn last(&self) -> String {
+    match self.0.last() {
+      Some(segment) => ValuePath(vec![segment.clone()]).to_string(),
+      None => String::new(),
+    }
+  }
+}
+
+impl fmt::Display for ValuePath {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (idx, segment) in self.0.iter().enumerate() {
+      match segment {
+        PathSegment::Index(index) => write!(f, "[{}]", index)?,
+        // Keys that can't be written bare are quoted like in TOML
+        PathSegment::Key(key)
+          if key.is_empty()
+            || !key
+              .chars()
+              .all(|c| c.is_alphanumeric() || c == '_' || c == '-') =>
+        {
+          write!(f, "[{:?}]", key)?
+        }
+        PathSegment::Key(key) if idx == 0 => write!(f, "{}", key)?,
+        PathSegment::Key(key) => write!(f, ".{}", key)?,
+      }
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueChangeKind {
+  Added,
+  Removed,
+  Changed, // Another value, or a value of another type
+}
+
+/// A value added, removed or replaced, values are written compactly like JSON
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueChange {
+  pub path: ValuePath,
+  pub kind: ValueChangeKind,
+  pub original: Option<String>, // None when added
+  pub modified: Option<String>, // None when removed
+}
+
+/// Changes of the values directly inside an object or an array
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeGroup {
+  pub path: ValuePath, // Of the object or array, the root for changes of the whole document
+  pub changes: Vec<ValueChange>,
+}
+
+/// Changes between two documents by value, grouped by the object they're in
+///
+/// Objects are compared key by key and arrays index by index, keys come sorted and the
+/// changes of an object come before those of the objects inside it
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructuredDiff {
+  pub format: StructuredFormat,
+  pub groups: Vec<ChangeGroup>,
+}
+
+impl StructuredDiff {
+  pub fn has_changes(&self) -> bool {
+    !self.groups.is_empty()
+  }
+
+  pub fn changes(&self) -> impl Iterator<Item = &ValueChange> {
+    self.groups.iter().flat_map(|group| group.changes.iter())
+  }
+}
+
+/// A side that isn't a valid document, a line diff has to be shown instead
+#[derive(Debug, Clone, PartialEq)]
+pub enum StructuredError {
+  Original(String), // Parse error of the original text
+  Modified(String),
+}
+
+impl fmt::Display for StructuredError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Original(message) => write!(f, "invalid original document: {}", message),
+      Self::Modified(message) => write!(f, "invalid modified document: {}", message),
+    }
+  }
+}
+
+impl std::error::Error for StructuredError {}
+
+/// Diffs two JSON or TOML documents by value
+pub fn structured_diff(
+  original: &str,
+  modified: &str,
+  format: StructuredFormat,
+) -> Result<StructuredDiff, StructuredError> {
+  let original = Node::parse(original, format).map_err(StructuredError::Original)?;
+  let modified = Node::parse(modified, format).map_err(StructuredError::Modified)?;
+
+  let mut groups = Vec::new();
+  diff_nodes(&mut Vec::new(), &original, &modified, &mut groups);
+  Ok(StructuredDiff { format, groups })
+}
+
+/// Parsed value of either format
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+  Null,
+  Bool(bool),
+  Number(String), // As written, so that 1.0 and 1 differ like they do in the text
+  String(String),
+  Array(Vec<Node>),
+  Object(Vec<(String, Node)>),
+}
+
+impl Node {
+  fn parse(text: &str, format: StructuredFormat) -> Result<Self, String> {
+    match format {
+      StructuredFormat::Json => serde_json::from_str::<serde_json::Value>(text)
+        .map(Self::from_json)
+        .map_err(|error| error.to_string()),
+      StructuredFormat::Toml => toml::from_str::<toml::Table>(text)
+        .map(|table| Self::from_toml(toml::Value::Table(table)))
+        .map_err(|error| error.message().to_string()),
+    }
+  }
+
+  fn from_json(value: serde_json::Value) -> Self {
+    use serde_json::Value;
+    match value {
+      Value::Null => Self::Null,
+      Value::Bool(value) => Self::Bool(value),
+      Value::Number(number) => Self::Number(number.to_string()),
+      Value::String(value) => Self::String(value),
+      Value::Array(values) => Self::Array(values.into_iter().map(Self::from_json).collect()),
+      Value::Object(map) => Self::Object(
+        map
+          .into_iter()
+          .map(|(key, value)| (key, Self::from_json(value)))
+          .collect(),
+      ),
+    }
+  }
+
+  fn from_toml(value: toml::Value) -> Self {
+    use toml::Value;
+    match value {
+      Value::String(value) => Self::String(value),
+      Value::Integer(value) => Self::Number(value.to_string()),
+      Value::Float(value) => Self::Number(value.to_string()),
+      Value::Boolean(value) => Self::Bool(value),
+      Value::Datetime(value) => Self::String(value.to_string()),
+      Value::Array(values) => Self::Array(values.into_iter().map(Self::from_toml).collect()),
+      Value::Table(table) => Self::Object(
+        table
+          .into_iter()
+          .map(|(key, value)| (key, Self::from_toml(value)))
+          .collect(),
+      ),
+    }
+  }
+
+  /// Compact JSON-like text of the value
+  fn format(&self) -> String {
+    match self {
+      Self::Null => "null".to_string(),
+      Self::Bool(value) => value.to_string(),
+      Self::Number(number) => number.clone(),
+      Self::String(value) => format!("{:?}", value),
+      Self::Array(values) => format!(
+        "[{}]",
+        values
+          .iter()
+          .map(Self::format)
+          .collect::<Vec<_>>()
+          .join(", ")
+      ),
+      Self::Object(entries) => format!(
+        "{{{}}}",
+        entries
+          .iter()
+          .map(|(key, value)| format!("{:?}: {}", key, value.format()))
+          .collect::<Vec<_>>()
+          .join(", ")
+      ),
+    }
+  }
+}
+
+fn diff_nodes(
+  path: &mut Vec<PathSegment>,
+  original: &Node,
+  modified: &Node,
+  groups: &mut Vec<ChangeGroup>,
+) {
+  let mut changes = Vec::new();
+  let mut nested = Vec::new();
+  let mut compare = |path: &mut Vec<PathSegment>,
+                     segment: PathSegment,
+                     original: Option<&Node>,
+                     modified: Option<&Node>| {
+    path.push(segment);
+    match (original, modified) {
+      (Some(original), Some(modified)) if original == modified => {}
+      // Containers of the same kind are compared inside, in a group of their own
+      (Some(original @ Node::Object(_)), Some(modified @ Node::Object(_)))
+      | (Some(original @ Node::Array(_)), Some(modified @ Node::Array(_))) => {
+        diff_nodes(path, original, modified, &mut nested)
+      }
+      (original, modified) => changes.push(ValueChange {
+        path: ValuePath(path.clone()),
+        kind: match (original, modified) {
+          (None, _) => ValueChangeKind::Added,
+          (_, None) => ValueChangeKind::Removed,
+          _ => ValueChangeKind::Changed,
+        },
+        original: original.map(Node::format),
+        modified: modified.map(Node::format),
+      }),
+    }
+    path.pop();
+  };
+
+  match (original, modified) {
+    (Node::Object(original), Node::Object(modified)) => {
+      for (key, value) in modified {
+        compare(
+          path,
+          PathSegment::Key(key.clone()),
+          entry(original, key),
+          Some(value),
+        );
+      }
+      for (key, value) in original {
+        if entry(modified, key).is_none() {
+          compare(path, PathSegment::Key(key.clone()), Some(value), None);
+        }
+      }
+    }
+    (Node::Array(original), Node::Array(modified)) => {
+      for index in 0..original.len().max(modified.len()) {
+        compare(
+          path,
+          PathSegment::Index(index),
+          original.get(index),
+          modified.get(index),
+        );
+      }
+    }
+    (original, modified) if original == modified => {}
+    // Values of another type, or scalars at the root
+    (original, modified) => changes.push(ValueChange {
+      path: ValuePath(path.clone()),
+      kind: ValueChangeKind::Changed,
+      original: Some(original.format()),
+      modified: Some(modified.format()),
+    }),
+  }
+
+  if !changes.is_empty() {
+    groups.push(ChangeGroup {
+      path: ValuePath(path.clone()),
+      changes,
+    });
+  }
+  groups.extend(nested);
+}
+
+fn entry<'a>(entries: &'a [(String, Node)], key: &str) -> Option<&'a Node> {
+  entries
+    .iter()
+    .find(|(other, _)| other == key)
+    .map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn describe(diff: &StructuredDiff) -> Vec<String> {
+    diff
+      .groups
+      .iter()
+      .flat_map(|group| {
+        group.changes.iter().map(move |change| {
+          let value = match change.kind {
+            ValueChangeKind::Added => format!("+ {}", change.modified.as_deref().unwrap()),
+            ValueChangeKind::Removed => format!("- {}", change.original.as_deref().unwrap()),
+            ValueChangeKind::Changed => format!(
+              "~ {} -> {}",
+              change.original.as_deref().unwrap(),
+              change.modified.as_deref().unwrap()
+            ),
+          };
+          format!("{} | {} {}", group.path, change.path.last(), value)
+        })
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_json_changes_grouped_by_object() {
+    let original =
+      r#"{"name": "app", "version": 1, "deps": {"a": "1.0", "b": "2.0"}, "old": true}"#;
+    let modified = r#"{"name": "app", "version": 2, "deps": {"a": "1.1", "b": "2.0", "c": "3.0"}}"#;
+    let diff = structured_diff(original, modified, StructuredFormat::Json).unwrap();
+
+    assert_eq!(
+      describe(&diff),
+      [
+        " | version ~ 1 -> 2",
+        " | old - true",
+        "deps | a ~ \"1.0\" -> \"1.1\"",
+        "deps | c + \"3.0\"",
+      ]
+    );
+    assert_eq!(diff.changes().count(), 4);
+  }
+
+  #[test]
+  fn test_key_order_does_not_matter() {
+    let diff = structured_diff(
+      r#"{"a": 1, "b": [1, 2]}"#,
+      "{\n  \"b\": [1, 2],\n  \"a\": 1\n}\n",
+      StructuredFormat::Json,
+    )
+    .unwrap();
+    assert!(!diff.has_changes());
+  }
+
+  #[test]
+  fn test_arrays_by_index() {
+    let diff = structured_diff(
+      r#"{"items": [1, 2, 3], "nested": [{"id": 1}]}"#,
+      r#"{"items": [1, 5], "nested": [{"id": 2}]}"#,
+      StructuredFormat::Json,
+    )
+    .unwrap();
+    assert_eq!(
+      describe(&diff),
+      [
+        "items | [1] ~ 2 -> 5",
+        "items | [2] - 3",
+        "nested[0] | id ~ 1 -> 2",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_type_change() {
+    let diff = structured_diff(
+      r#"{"a": {"b": 1}}"#,
+      r#"{"a": [1]}"#,
+      StructuredFormat::Json,
+    )
+    .unwrap();
+    assert_eq!(describe(&diff), [" | a ~ {\"b\": 1} -> [1]"]);
+
+    let diff = structured_diff("1", "\"one\"", StructuredFormat::Json).unwrap();
+    assert_eq!(describe(&diff), [" |  ~ 1 -> \"one\""]);
+  }
+
+  #[test]
+  fn test_toml() {
+    let original =
+      "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1\"\n";
+    let modified = "[package]\nname = \"app\"\nversion = \"0.2.0\"\n\n[dependencies]\nserde = \"1\"\ntoml = \"0.9\"\n";
+    let diff = structured_diff(original, modified, StructuredFormat::Toml).unwrap();
+    assert_eq!(
+      describe(&diff),
+      // Tables come sorted by key
+      [
+        "dependencies | toml + \"0.9\"",
+        "package | version ~ \"0.1.0\" -> \"0.2.0\"",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    let error = structured_diff("{", "{}", StructuredFormat::Json).unwrap_err();
+    assert!(matches!(error, StructuredError::Original(_)));
+    let error = structured_diff("a = 1", "a = ", StructuredFormat::Toml).unwrap_err();
+    assert!(matches!(error, StructuredError::Modified(_)));
+  }
+
+  #[test]
+  fn test_path_display() {
+    let path = ValuePath(vec![
+      PathSegment::Key("deps".to_string()),
+      PathSegment::Key("serde".to_string()),
+      PathSegment::Key("features".to_string()),
+      PathSegment::Index(0),
+      PathSegment::Key("a.b".to_string()),
+    ]);
+    assert_eq!(path.to_string(), "deps.serde.features[0][\"a.b\"]");
+    assert_eq!(path.last(), "[\"a.b\"]");
+    assert!(ValuePath::default().is_root());
+  }
+
+  #[test]
+  fn test_format_from_path() {
+    assert_eq!(
+      StructuredFormat::from_path(Path::new("package.JSON")),
+      Some(StructuredFormat::Json)
+    );
+    assert_eq!(
+      StructuredFormat::from_path(Path::new("Cargo.toml")),
+      Some(StructuredFormat::Toml)
+    );
+    assert_eq!(StructuredFormat::from_path(Path::new("main.rs")), None);
+  }
+}
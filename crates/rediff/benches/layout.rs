@@ -0,0 +1,143 @@
+//! Layout work done on every frame or edit that needs no window: display rows with collapsed
+//! regions and folds, tab expansion, minimap blocks and line cache invalidation
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use editor::LineEdit;
+use rediff::{
+  Folds, LineCache, MinimapLine, TabMap, UnchangedRegions, hide_folded_rows, indent_fold_ranges,
+};
+use rediff_core::Differ;
+use std::hint::black_box;
+
+const SIZES: [usize; 2] = [10_000, 100_000];
+const TAB_SIZE: usize = 4;
+
+/// Nested blocks of tab indented code, the same for a given size
+fn original(lines: usize) -> String {
+  (0..lines)
+    .map(|i| match i % 8 {
+      0 => format!("fn item_{i}() {{\n"),
+      7 => "}\n".to_string(),
+      depth => format!(
+        "{}let value_{i} = compute({i});\n",
+        "\t".repeat(depth.min(3))
+      ),
+    })
+    .collect()
+}
+
+/// `original` with a line changed every `every` lines
+fn modified(original: &str, every: usize) -> String {
+  original
+    .lines()
+    .enumerate()
+    .map(|(i, line)| {
+      if i % every == 3 {
+        line.replace("compute", "recompute") + "\n"
+      } else {
+        line.to_string() + "\n"
+      }
+    })
+    .collect()
+}
+
+fn bench_display_rows(c: &mut Criterion) {
+  let mut group = c.benchmark_group("display_rows");
+  group.sample_size(20);
+  for lines in SIZES {
+    let original = original(lines);
+    let modified = modified(&original, 200);
+    let diff_lines = Differ::new(original).compute_diff(&modified);
+    let regions = UnchangedRegions::new();
+
+    group.bench_function(BenchmarkId::new("collapsed", lines), |b| {
+      b.iter_batched(
+        || diff_lines.clone(),
+        |diff_lines| regions.display_rows(diff_lines, 8),
+        BatchSize::LargeInput,
+      )
+    });
+
+    let ranges = indent_fold_ranges(&modified, TAB_SIZE);
+    let mut folds = Folds::new();
+    for range in ranges.iter().step_by(2) {
+      folds.fold(range.start_line);
+    }
+    let folded = folds.folded_ranges(&ranges);
+    group.bench_function(BenchmarkId::new("folded", lines), |b| {
+      b.iter_batched(
+        || regions.display_rows(diff_lines.clone(), usize::MAX),
+        |rows| hide_folded_rows(rows, black_box(&folded)),
+        BatchSize::LargeInput,
+      )
+    });
+
+    group.bench_function(BenchmarkId::new("indent_fold_ranges", lines), |b| {
+      b.iter(|| indent_fold_ranges(black_box(&modified), TAB_SIZE))
+    });
+  }
+  group.finish();
+}
+
+fn bench_lines(c: &mut Criterion) {
+  let mut group = c.benchmark_group("lines");
+  let text = original(10_000);
+
+  group.bench_function("tab_map", |b| {
+    b.iter(|| {
+      for line in text.lines() {
+        let tab_map = TabMap::new(line, TAB_SIZE);
+        black_box(tab_map.expand(line));
+      }
+    })
+  });
+  group.bench_function("minimap_line", |b| {
+    b.iter(|| {
+      for line in text.lines() {
+        black_box(MinimapLine::new(line, TAB_SIZE));
+      }
+    })
+  });
+  group.finish();
+}
+
+fn bench_line_cache(c: &mut Criterion) {
+  let mut group = c.benchmark_group("line_cache");
+  let text = original(10_000);
+  let minimap_lines: Vec<MinimapLine> = text
+    .lines()
+    .map(|line| MinimapLine::new(line, TAB_SIZE))
+    .collect();
+  let filled = || {
+    let mut cache = LineCache::new();
+    for (line_idx, line) in minimap_lines.iter().enumerate() {
+      cache.insert_minimap(line_idx, line.clone());
+    }
+    cache
+  };
+
+  // Typing a newline in the middle shifts every line after it
+  let split = LineEdit {
+    line_start: 5_000,
+    line_end: 5_000,
+    line_delta: 1,
+  };
+  group.bench_function("apply_edit", |b| {
+    b.iter_batched_ref(
+      filled,
+      |cache| cache.apply_edit(black_box(&split)),
+      BatchSize::LargeInput,
+    )
+  });
+  group.bench_function("mark_dirty_range", |b| {
+    b.iter_batched_ref(
+      LineCache::new,
+      |cache| cache.mark_dirty_range(0, 9_999),
+      BatchSize::LargeInput,
+    )
+  });
+  group.finish();
+}
+
+criterion_group!(benches, bench_display_rows, bench_lines, bench_line_cache);
+criterion_main!(benches);
@@ -0,0 +1,201 @@
+use crate::display_rows::DisplayRow;
+use editor::LineEdit;
+use gpui::{Context, Hsla, Render, SharedString, Window, div, prelude::*, px};
+use std::collections::BTreeMap;
+
+/// Text shown next to a buffer line in the annotation column, like git blame output
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineAnnotation {
+  pub line: usize, // Buffer line (0-based)
+  pub text: String,
+  pub tooltip: Option<String>, // Shown when hovering the annotation
+}
+
+/// Annotations supplied by the host, one per buffer line at most
+#[derive(Debug, Clone, Default)]
+pub struct LineAnnotations {
+  annotations: BTreeMap<usize, LineAnnotation>,
+}
+
+impl LineAnnotations {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Replaces every annotation, the last one wins when several target the same line
+  pub fn set(&mut self, annotations: Vec<LineAnnotation>) {
+    self.annotations = annotations
+      .into_iter()
+      .map(|annotation| (annotation.line, annotation))
+      .collect();
+  }
+
+  pub fn clear(&mut self) {
+    self.annotations.clear();
+  }
+
+  pub fn get(&self, line_idx: usize) -> Option<&LineAnnotation> {
+    self.annotations.get(&line_idx)
+  }
+
+  pub fn len(&self) -> usize {
+    self.annotations.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.annotations.is_empty()
+  }
+
+  /// Moves the annotations that follow edited lines, annotations of deleted lines are dropped
+  pub fn apply_edits(&mut self, edits: &[LineEdit]) {
+    for edit in edits {
+      let deleted = if edit.line_delta < 0 {
+        edit.line_end + 1 - edit.line_delta.unsigned_abs()..edit.line_end + 1
+      } else {
+        0..0
+      };
+
+      self.annotations = std::mem::take(&mut self.annotations)
+        .into_iter()
+        .filter(|(line_idx, _)| !deleted.contains(line_idx))
+        .filter_map(|(line_idx, mut annotation)| {
+          if line_idx > edit.line_end {
+            annotation.line = line_idx.checked_add_signed(edit.line_delta)?;
+          }
+          Some((annotation.line, annotation))
+        })
+        .collect();
+    }
+  }
+
+  /// Annotation of each display row, removed lines and collapsed rows get none
+  pub fn for_rows(&self, rows: &[DisplayRow]) -> Vec<Option<LineAnnotation>> {
+    rows
+      .iter()
+      .map(|row| {
+        let line = row.diff_line()?;
+        self.get(line.line_number.checked_sub(1)?).cloned()
+      })
+      .collect()
+  }
+}
+
+/// Tooltip view of an annotation
+pub struct AnnotationTooltip {
+  pub text: SharedString,
+  pub bg_color: Hsla,
+  pub text_color: Hsla,
+}
+
+impl Render for AnnotationTooltip {
+  fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+    div()
+      .px_2()
+      .py_1()
+      .rounded(px(4.0))
+      .border_1()
+      .border_color(self.text_color.alpha(0.2))
+      .bg(self.bg_color)
+      .text_color(self.text_color)
+      .child(self.text.clone())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rediff_core::{DiffLine, DiffLineKind};
+
+  fn annotation(line: usize, text: &str) -> LineAnnotation {
+    LineAnnotation {
+      line,
+      text: text.to_string(),
+      tooltip: None,
+    }
+  }
+
+  fn row(row: usize, line_number: usize) -> DisplayRow {
+    DisplayRow::Line {
+      row,
+      line: DiffLine {
+        line_number,
+        kind: if line_number == 0 {
+          DiffLineKind::Removed
+        } else {
+          DiffLineKind::Unchanged
+        },
+        content: String::new(),
+        char_changes: vec![],
+        is_first_in_group: false,
+        pair: None,
+        counterpart: None,
+      },
+    }
+  }
+
+  #[test]
+  fn test_set_replaces_annotations() {
+    let mut annotations = LineAnnotations::new();
+    annotations.set(vec![annotation(0, "a"), annotation(2, "b")]);
+    annotations.set(vec![annotation(1, "c"), annotation(1, "d")]);
+
+    assert_eq!(annotations.len(), 1);
+    assert_eq!(annotations.get(1).unwrap().text, "d");
+    assert!(annotations.get(0).is_none());
+  }
+
+  #[test]
+  fn test_rows_stay_aligned_with_removed_lines() {
+    let mut annotations = LineAnnotations::new();
+    annotations.set(vec![annotation(0, "first"), annotation(1, "second")]);
+
+    let rows = vec![row(0, 1), row(1, 0), row(2, 2)];
+    let texts: Vec<_> = annotations
+      .for_rows(&rows)
+      .into_iter()
+      .map(|a| a.map(|a| a.text))
+      .collect();
+
+    assert_eq!(
+      texts,
+      vec![Some("first".to_string()), None, Some("second".to_string())]
+    );
+  }
+
+  #[test]
+  fn test_annotations_follow_inserted_lines() {
+    let mut annotations = LineAnnotations::new();
+    annotations.set(vec![annotation(1, "a"), annotation(5, "b")]);
+
+    annotations.apply_edits(&[LineEdit {
+      line_start: 3,
+      line_end: 3,
+      line_delta: 2,
+    }]);
+
+    assert_eq!(annotations.get(1).unwrap().text, "a");
+    assert_eq!(annotations.get(7).unwrap().text, "b");
+    assert_eq!(annotations.get(7).unwrap().line, 7);
+  }
+
+  #[test]
+  fn test_annotations_of_deleted_lines_are_dropped() {
+    let mut annotations = LineAnnotations::new();
+    annotations.set(vec![
+      annotation(2, "a"),
+      annotation(3, "b"),
+      annotation(6, "c"),
+    ]);
+
+    // Lines 3 and 4 joined into line 2
+    annotations.apply_edits(&[LineEdit {
+      line_start: 2,
+      line_end: 4,
+      line_delta: -2,
+    }]);
+
+    assert_eq!(annotations.get(2).unwrap().text, "a");
+    assert_eq!(annotations.len(), 2);
+    assert_eq!(annotations.get(4).unwrap().text, "c");
+  }
+}
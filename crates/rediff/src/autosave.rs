@@ -0,0 +1,96 @@
+use crate::config::Autosave;
+
+/// Autosave timer state, driven by timers scheduled from the editor
+///
+/// Like cursor blink timers, every timer carries the epoch it was scheduled at
+/// and only saves when nothing bumped the epoch in the meantime
+#[derive(Debug, Clone, Default)]
+pub struct AutosaveTimer {
+  epoch: usize,
+  scheduled: bool,
+}
+
+impl AutosaveTimer {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Called on every edit, returns the epoch of a timer to schedule, if any
+  pub fn edited(&mut self, autosave: Autosave) -> Option<usize> {
+    match autosave {
+      Autosave::Off => None,
+      // Every edit pushes the save back
+      Autosave::AfterDelay => {
+        self.epoch += 1;
+        self.scheduled = true;
+        Some(self.epoch)
+      }
+      Autosave::Interval if self.scheduled => None,
+      Autosave::Interval => {
+        self.scheduled = true;
+        Some(self.epoch)
+      }
+    }
+  }
+
+  /// Whether a timer of `epoch` is still the scheduled one, it should save then
+  pub fn fire(&mut self, epoch: usize) -> bool {
+    if epoch != self.epoch || !self.scheduled {
+      return false;
+    }
+    self.scheduled = false;
+    true
+  }
+
+  /// Whether a timer is waiting to fire
+  pub fn is_scheduled(&self) -> bool {
+    self.scheduled
+  }
+
+  /// Drops the scheduled timer, like after a save or when another file is opened
+  pub fn cancel(&mut self) {
+    self.epoch += 1;
+    self.scheduled = false;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_after_delay_restarts_on_edit() {
+    let mut timer = AutosaveTimer::new();
+    let first = timer.edited(Autosave::AfterDelay).unwrap();
+    let second = timer.edited(Autosave::AfterDelay).unwrap();
+
+    assert!(!timer.fire(first));
+    assert!(timer.fire(second));
+    assert!(!timer.fire(second));
+  }
+
+  #[test]
+  fn test_interval_keeps_first_timer() {
+    let mut timer = AutosaveTimer::new();
+    let epoch = timer.edited(Autosave::Interval).unwrap();
+    assert_eq!(timer.edited(Autosave::Interval), None);
+
+    assert!(timer.fire(epoch));
+    // The next edit starts another interval
+    let next = timer.edited(Autosave::Interval).unwrap();
+    assert!(timer.fire(next));
+  }
+
+  #[test]
+  fn test_cancel() {
+    let mut timer = AutosaveTimer::new();
+    assert_eq!(timer.edited(Autosave::Off), None);
+
+    let epoch = timer.edited(Autosave::Interval).unwrap();
+    assert!(timer.is_scheduled());
+    timer.cancel();
+    assert!(!timer.is_scheduled());
+    assert!(!timer.fire(epoch));
+    assert!(timer.edited(Autosave::Interval).is_some());
+  }
+}
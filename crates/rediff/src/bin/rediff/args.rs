@@ -0,0 +1,248 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+pub const USAGE: &str = "\
+Usage: rediff [options] <old> <new>
+       rediff [options] --against <old> <new>
+
+Opens a window with the changes from <old> to <new>, or prints them. Either
+of them can be `-` to read it from stdin
+
+Options:
+      --against <old>  File to compare with, the only other file is the new one
+  -u, --unified[=<n>]  Print a unified diff with <n> lines of context (3 by default)
+      --stat           Print the number of inserted and deleted lines
+      --json           Print the diff lines, hunks and stats as JSON
+      --wait           Block until the window is closed, like for `git difftool`
+      --watch          Update the window as the files change on disk
+  -h, --help           Print this help
+";
+
+const DEFAULT_CONTEXT: usize = 3;
+const STDIN: &str = "-";
+
+/// What the command does with the two files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+  Window { wait: bool, watch: bool },
+  Unified { context: usize },
+  Stat,
+  Json,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Args {
+  pub old: PathBuf,
+  pub new: PathBuf,
+  pub output: Output,
+}
+
+/// `-` is read from stdin instead of being a file
+pub fn is_stdin(path: &Path) -> bool {
+  path.as_os_str() == STDIN
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgsError {
+  Help, // Not an error, the usage is printed
+  UnknownOption(String),
+  InvalidContext(String),
+  ConflictingOutputs, // More than one of the printed outputs, or one with a window option
+  MissingValue(String), // Option given last without its value
+  MissingFiles,
+  TooManyFiles,
+  StdinTwice, // Both sides are `-`
+  WatchStdin,
+}
+
+impl fmt::Display for ArgsError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Help => write!(f, "{}", USAGE),
+      Self::UnknownOption(option) => write!(f, "unknown option: {}", option),
+      Self::InvalidContext(value) => write!(f, "invalid number of context lines: {}", value),
+      Self::ConflictingOutputs => {
+        write!(
+          f,
+          "--unified, --stat and --json can't be combined, nor used with --wait or --watch"
+        )
+      }
+      Self::MissingValue(option) => write!(f, "missing value for {}", option),
+      Self::MissingFiles => write!(f, "expected two files to compare"),
+      Self::TooManyFiles => write!(f, "expected only two files to compare"),
+      Self::StdinTwice => write!(f, "only one side can be read from stdin"),
+      Self::WatchStdin => write!(f, "stdin can't be watched"),
+    }
+  }
+}
+
+impl std::error::Error for ArgsError {}
+
+impl Args {
+  /// Parses the arguments after the program name
+  pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, ArgsError> {
+    let mut files = Vec::new();
+    let mut outputs = Vec::new();
+    let mut wait = false;
+    let mut watch = false;
+    let mut against = None;
+    let mut only_files = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+      if only_files || !arg.starts_with('-') || arg == STDIN {
+        files.push(PathBuf::from(arg));
+        continue;
+      }
+      match arg.as_str() {
+        "--" => only_files = true,
+        "-h" | "--help" => return Err(ArgsError::Help),
+        "-u" | "--unified" => outputs.push(Output::Unified {
+          context: DEFAULT_CONTEXT,
+        }),
+        "--stat" => outputs.push(Output::Stat),
+        "--json" => outputs.push(Output::Json),
+        "--wait" => wait = true,
+        "--watch" => watch = true,
+        "--against" => {
+          let value = args.next().ok_or(ArgsError::MissingValue(arg))?;
+          against = Some(PathBuf::from(value));
+        }
+        _ => {
+          if let Some(value) = arg.strip_prefix("--unified=") {
+            let context = value
+              .parse()
+              .map_err(|_| ArgsError::InvalidContext(value.to_string()))?;
+            outputs.push(Output::Unified { context });
+          } else if let Some(value) = arg.strip_prefix("--against=") {
+            against = Some(PathBuf::from(value));
+          } else {
+            return Err(ArgsError::UnknownOption(arg));
+          }
+        }
+      }
+    }
+
+    let output = match (outputs.as_slice(), wait || watch) {
+      ([], _) => Output::Window { wait, watch },
+      ([output], false) => *output,
+      _ => return Err(ArgsError::ConflictingOutputs),
+    };
+    let mut files = against.into_iter().chain(files);
+    let (Some(old), Some(new)) = (files.next(), files.next()) else {
+      return Err(ArgsError::MissingFiles);
+    };
+    if files.next().is_some() {
+      return Err(ArgsError::TooManyFiles);
+    }
+    if is_stdin(&old) && is_stdin(&new) {
+      return Err(ArgsError::StdinTwice);
+    }
+    if watch && (is_stdin(&old) || is_stdin(&new)) {
+      return Err(ArgsError::WatchStdin);
+    }
+    Ok(Self { old, new, output })
+  }
+
+  /// Whether one of the sides is read from stdin
+  pub fn reads_stdin(&self) -> bool {
+    is_stdin(&self.old) || is_stdin(&self.new)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(args: &[&str]) -> Result<Args, ArgsError> {
+    Args::parse(args.iter().map(|arg| arg.to_string()))
+  }
+
+  #[test]
+  fn test_parse_outputs() {
+    let args = parse(&["a.txt", "b.txt"]).unwrap();
+    assert_eq!(args.old, PathBuf::from("a.txt"));
+    assert_eq!(args.new, PathBuf::from("b.txt"));
+    assert_eq!(
+      args.output,
+      Output::Window {
+        wait: false,
+        watch: false
+      }
+    );
+
+    assert_eq!(
+      parse(&["--wait", "a", "b"]).unwrap().output,
+      Output::Window {
+        wait: true,
+        watch: false
+      }
+    );
+    assert_eq!(
+      parse(&["--watch", "a", "b"]).unwrap().output,
+      Output::Window {
+        wait: false,
+        watch: true
+      }
+    );
+    assert_eq!(
+      parse(&["a", "-u", "b"]).unwrap().output,
+      Output::Unified { context: 3 }
+    );
+    assert_eq!(
+      parse(&["--unified=0", "a", "b"]).unwrap().output,
+      Output::Unified { context: 0 }
+    );
+    assert_eq!(parse(&["a", "b", "--stat"]).unwrap().output, Output::Stat);
+    assert_eq!(parse(&["--json", "a", "b"]).unwrap().output, Output::Json);
+    // Files starting with a dash
+    assert_eq!(parse(&["--", "-a", "b"]).unwrap().old, PathBuf::from("-a"));
+  }
+
+  #[test]
+  fn test_parse_stdin() {
+    let args = parse(&["--against", "old.txt", "-"]).unwrap();
+    assert_eq!(args.old, PathBuf::from("old.txt"));
+    assert!(is_stdin(&args.new));
+    assert!(args.reads_stdin());
+
+    let args = parse(&["-", "--against=old.txt", "--unified"]).unwrap();
+    assert_eq!(args.old, PathBuf::from("old.txt"));
+    assert_eq!(args.output, Output::Unified { context: 3 });
+
+    assert!(!parse(&["a", "b"]).unwrap().reads_stdin());
+    assert_eq!(parse(&["-", "-"]), Err(ArgsError::StdinTwice));
+    assert_eq!(parse(&["--watch", "a", "-"]), Err(ArgsError::WatchStdin));
+    assert_eq!(
+      parse(&["a", "--against"]),
+      Err(ArgsError::MissingValue("--against".to_string()))
+    );
+    assert_eq!(
+      parse(&["--against", "a", "b", "c"]),
+      Err(ArgsError::TooManyFiles)
+    );
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    assert_eq!(parse(&["-h"]), Err(ArgsError::Help));
+    assert_eq!(parse(&["a"]), Err(ArgsError::MissingFiles));
+    assert_eq!(parse(&["a", "b", "c"]), Err(ArgsError::TooManyFiles));
+    assert_eq!(
+      parse(&["--color", "a", "b"]),
+      Err(ArgsError::UnknownOption("--color".to_string()))
+    );
+    assert_eq!(
+      parse(&["--unified=x", "a", "b"]),
+      Err(ArgsError::InvalidContext("x".to_string()))
+    );
+    assert_eq!(
+      parse(&["--stat", "--wait", "a", "b"]),
+      Err(ArgsError::ConflictingOutputs)
+    );
+    assert_eq!(
+      parse(&["-u", "--watch", "a", "b"]),
+      Err(ArgsError::ConflictingOutputs)
+    );
+  }
+}
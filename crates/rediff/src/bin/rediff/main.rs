@@ -0,0 +1,195 @@
+use gpui::{
+  App, Application, Bounds, Focusable, TitlebarOptions, WindowBounds, WindowOptions, prelude::*,
+  px, size,
+};
+use rediff::{DiffEditor, DiffEditorBuilder, EditorConfig};
+use rediff_core::{DiffOptions, DiffResult};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+
+mod args;
+mod output;
+use args::{Args, ArgsError, Output, is_stdin};
+
+// Exit codes of `diff`
+const SAME: u8 = 0;
+const DIFFERENT: u8 = 1;
+const TROUBLE: u8 = 2;
+
+fn main() -> ExitCode {
+  let args = match Args::parse(std::env::args().skip(1)) {
+    Ok(args) => args,
+    Err(ArgsError::Help) => {
+      print!("{}", args::USAGE);
+      return ExitCode::from(SAME);
+    }
+    Err(e) => {
+      eprintln!("rediff: {}\n\n{}", e, args::USAGE);
+      return ExitCode::from(TROUBLE);
+    }
+  };
+
+  match args.output {
+    // Stdin can't be handed over to another process
+    Output::Window { wait: false, watch } if !args.reads_stdin() => spawn_window(&args, watch),
+    Output::Window { watch, .. } => open_window(&args, watch),
+    Output::Unified { context } => print_diff(&args, |result, old, new| {
+      output::unified(&result.lines, old, new, context)
+    }),
+    Output::Stat => print_diff(&args, |result, _old, new| output::stat(&result.lines, new)),
+    Output::Json => print_diff(&args, output::json),
+  }
+}
+
+/// Prints the diff made by `format` from the result, the old path and the new path
+fn print_diff(args: &Args, format: impl Fn(&DiffResult, &str, &str) -> String) -> ExitCode {
+  let (old, new) = match (read(&args.old), read(&args.new)) {
+    (Ok(old), Ok(new)) => (old, new),
+    (Err(e), _) | (_, Err(e)) => {
+      eprintln!("rediff: {}", e);
+      return ExitCode::from(TROUBLE);
+    }
+  };
+  let old_path = label(&args.old);
+  let new_path = label(&args.new);
+  let same = old == new;
+  match (String::from_utf8(old), String::from_utf8(new)) {
+    (Ok(old), Ok(new)) => {
+      let result = rediff_core::diff(&old, &new, &DiffOptions::default());
+      print!("{}", format(&result, &old_path, &new_path));
+      ExitCode::from(if result.has_changes() {
+        DIFFERENT
+      } else {
+        SAME
+      })
+    }
+    _ if same => ExitCode::from(SAME),
+    _ => {
+      println!("Binary files {} and {} differ", old_path, new_path);
+      ExitCode::from(DIFFERENT)
+    }
+  }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, String> {
+  if is_stdin(path) {
+    let mut bytes = Vec::new();
+    return io::stdin()
+      .read_to_end(&mut bytes)
+      .map(|_| bytes)
+      .map_err(|e| format!("stdin: {}", e));
+  }
+  std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn read_text(path: &Path) -> Result<String, String> {
+  String::from_utf8(read(path)?).map_err(|_| format!("{}: not a text file", label(path)))
+}
+
+fn label(path: &Path) -> String {
+  if is_stdin(path) {
+    "stdin".to_string()
+  } else {
+    path.display().to_string()
+  }
+}
+
+/// What the window shows
+enum Contents {
+  Files(DiffEditorBuilder),          // The new file stays editable
+  Text { old: String, new: String }, // The new side comes from stdin, only shown
+}
+
+/// Checks the files exist, and reads the side given on stdin
+fn contents(args: &Args, watch: bool) -> Result<Contents, String> {
+  if is_stdin(&args.new) {
+    let old = read_text(&args.old)?;
+    let new = read_text(&args.new)?;
+    return Ok(Contents::Text { old, new });
+  }
+
+  let builder = DiffEditor::builder().file(&args.new).watch(watch);
+  let builder = if is_stdin(&args.old) {
+    builder.compare_with_string(read_text(&args.old)?)
+  } else {
+    builder.compare_with_file(&args.old)
+  };
+  builder.validate().map_err(|e| e.to_string())?;
+  Ok(Contents::Files(builder))
+}
+
+/// Opens the window from a detached process, so the terminal is given back right away
+fn spawn_window(args: &Args, watch: bool) -> ExitCode {
+  if let Err(e) = contents(args, watch) {
+    eprintln!("rediff: {}", e);
+    return ExitCode::from(TROUBLE);
+  }
+  let spawned = std::env::current_exe().and_then(|exe| {
+    let mut command = Command::new(exe);
+    command.arg("--wait");
+    if watch {
+      command.arg("--watch");
+    }
+    command.arg("--").arg(&args.old).arg(&args.new).spawn()
+  });
+  match spawned {
+    Ok(_) => ExitCode::from(SAME),
+    Err(e) => {
+      eprintln!("rediff: can't open the window: {}", e);
+      ExitCode::from(TROUBLE)
+    }
+  }
+}
+
+/// Opens the window and returns once it's closed
+fn open_window(args: &Args, watch: bool) -> ExitCode {
+  let contents = match contents(args, watch) {
+    Ok(contents) => contents,
+    Err(e) => {
+      eprintln!("rediff: {}", e);
+      return ExitCode::from(TROUBLE);
+    }
+  };
+  let new_label = label(&args.new);
+  let title = format!("{} ↔ {}", label(&args.old), new_label);
+
+  Application::new().run(move |cx: &mut App| {
+    let config = EditorConfig {
+      status_bar: watch, // With the time of the last update
+      ..Default::default()
+    };
+    DiffEditor::register(cx, &config.keymap);
+
+    let bounds = Bounds::centered(None, size(px(1200.0), px(800.0)), cx);
+    cx.open_window(
+      WindowOptions {
+        window_bounds: Some(WindowBounds::Windowed(bounds)),
+        titlebar: Some(TitlebarOptions {
+          title: Some(title.into()),
+          ..Default::default()
+        }),
+        ..Default::default()
+      },
+      |window, cx| {
+        let editor = match contents {
+          Contents::Files(builder) => builder
+            .config(config)
+            .build(cx)
+            .unwrap_or_else(|e| panic!("Can't open the editor: {}", e)),
+          Contents::Text { old, new } => {
+            cx.new(|cx| DiffEditor::from_text(PathBuf::from(new_label), &new, old, config, cx))
+          }
+        };
+        window.focus(&editor.focus_handle(cx));
+        editor
+      },
+    )
+    .unwrap();
+
+    // `git difftool` moves on to the next file once the process exits
+    cx.on_window_closed(|cx| cx.quit()).detach();
+    cx.activate(true);
+  });
+  ExitCode::from(SAME)
+}
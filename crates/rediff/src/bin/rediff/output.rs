@@ -0,0 +1,144 @@
+use rediff_core::{DiffLine, DiffResult, FilePatch, PatchLineKind};
+use serde::Serialize;
+
+const MAX_GRAPH_WIDTH: usize = 50;
+
+/// Unified diff of the lines, like `diff -u`, empty without changes
+pub fn unified(lines: &[DiffLine], old_path: &str, new_path: &str, context: usize) -> String {
+  let patch = FilePatch::from_diff(lines, old_path, new_path, context);
+  if patch.hunks.is_empty() {
+    return String::new();
+  }
+  patch.to_string()
+}
+
+/// Diff result with the paths it compares, for other tools to read
+#[derive(Serialize)]
+struct JsonDiff<'a> {
+  old_path: &'a str,
+  new_path: &'a str,
+  #[serde(flatten)]
+  result: &'a DiffResult,
+}
+
+pub fn json(result: &DiffResult, old_path: &str, new_path: &str) -> String {
+  let diff = JsonDiff {
+    old_path,
+    new_path,
+    result,
+  };
+  // Only maps with string keys, serializing can't fail
+  let mut json = serde_json::to_string_pretty(&diff).unwrap();
+  json.push('\n');
+  json
+}
+
+/// Inserted and deleted lines, like `git diff --stat`
+///
+/// ```text
+///  src/main.rs | 5 ++---
+///  1 file changed, 2 insertions(+), 3 deletions(-)
+/// ```
+pub fn stat(lines: &[DiffLine], path: &str) -> String {
+  let patch = FilePatch::from_diff(lines, path, path, 0);
+  let (mut insertions, mut deletions) = (0, 0);
+  for line in patch.hunks.iter().flat_map(|hunk| &hunk.lines) {
+    match line.kind {
+      PatchLineKind::Added => insertions += 1,
+      PatchLineKind::Removed => deletions += 1,
+      PatchLineKind::Context => {}
+    }
+  }
+  if insertions + deletions == 0 {
+    return String::new();
+  }
+
+  // Scaled down to fit, a side with changes keeps at least one mark
+  let total = insertions + deletions;
+  let scale = |count: usize| match count {
+    0 => 0,
+    _ if total <= MAX_GRAPH_WIDTH => count,
+    _ => (count * MAX_GRAPH_WIDTH / total).max(1),
+  };
+  let graph = format!(
+    "{}{}",
+    "+".repeat(scale(insertions)),
+    "-".repeat(scale(deletions))
+  );
+
+  let mut summary = " 1 file changed".to_string();
+  if insertions > 0 {
+    summary.push_str(&format!(
+      ", {} {}(+)",
+      insertions,
+      plural(insertions, "insertion")
+    ));
+  }
+  if deletions > 0 {
+    summary.push_str(&format!(
+      ", {} {}(-)",
+      deletions,
+      plural(deletions, "deletion")
+    ));
+  }
+  format!(" {} | {} {}\n{}\n", path, total, graph, summary)
+}
+
+fn plural(count: usize, word: &str) -> String {
+  if count == 1 {
+    word.to_string()
+  } else {
+    format!("{}s", word)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rediff_core::{DiffOptions, diff};
+
+  #[test]
+  fn test_stat() {
+    let result = diff("a\nb\nc\n", "a\nB\nc\nd\n", &DiffOptions::default());
+    assert_eq!(
+      stat(&result.lines, "notes.txt"),
+      " notes.txt | 3 ++-\n 1 file changed, 2 insertions(+), 1 deletion(-)\n"
+    );
+
+    let result = diff("a\n", "a\n", &DiffOptions::default());
+    assert_eq!(stat(&result.lines, "notes.txt"), "");
+  }
+
+  #[test]
+  fn test_stat_scales_graph() {
+    let original = "x\n".repeat(100);
+    let result = diff(&original, "y\n", &DiffOptions::default());
+    let stat = stat(&result.lines, "big.txt");
+    let graph = stat.lines().next().unwrap().rsplit(' ').next().unwrap();
+    assert!(graph.len() <= MAX_GRAPH_WIDTH + 1);
+    assert!(graph.starts_with('+') && graph.ends_with('-'));
+  }
+
+  #[test]
+  fn test_json() {
+    let result = diff("a\n", "b\n", &DiffOptions::default());
+    let json: serde_json::Value =
+      serde_json::from_str(&json(&result, "old.txt", "new.txt")).unwrap();
+    assert_eq!(json["old_path"], "old.txt");
+    assert_eq!(json["new_path"], "new.txt");
+    assert!(json["lines"].is_array());
+    assert!(json["stats"].is_object());
+  }
+
+  #[test]
+  fn test_unified() {
+    let result = diff("a\nb\n", "a\nc\n", &DiffOptions::default());
+    assert_eq!(
+      unified(&result.lines, "old.txt", "new.txt", 3),
+      "--- old.txt\n+++ new.txt\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n"
+    );
+
+    let result = diff("a\n", "a\n", &DiffOptions::default());
+    assert_eq!(unified(&result.lines, "old.txt", "new.txt", 3), "");
+  }
+}
@@ -0,0 +1,73 @@
+const BYTES_PER_ROW: usize = 16;
+
+/// Hex dump of `bytes`, one line of 16 bytes each, like "00000010  48 65 6c 6c 6f …  |Hello…|"
+///
+/// Both sides of a binary diff are dumped the same way, so the char changes of a row
+/// highlight the bytes that differ at the same offset
+pub fn hex_dump(bytes: &[u8]) -> String {
+  let mut dump = String::with_capacity(bytes.len().div_ceil(BYTES_PER_ROW) * 79);
+
+  for (row, chunk) in bytes.chunks(BYTES_PER_ROW).enumerate() {
+    dump.push_str(&format!("{:08x}  ", row * BYTES_PER_ROW));
+    for col in 0..BYTES_PER_ROW {
+      match chunk.get(col) {
+        Some(byte) => dump.push_str(&format!("{:02x} ", byte)),
+        None => dump.push_str("   "),
+      }
+      if col == BYTES_PER_ROW / 2 - 1 {
+        dump.push(' ');
+      }
+    }
+    dump.push_str(" |");
+    dump.extend(chunk.iter().map(|byte| match byte {
+      0x20..=0x7e => *byte as char,
+      _ => '.',
+    }));
+    dump.push_str("|\n");
+  }
+
+  dump
+}
+
+/// Like "1.5 KB", for the binary placeholder
+pub fn format_size(len: usize) -> String {
+  const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+  let mut size = len as f64;
+  let mut unit = 0;
+  while size >= 1024.0 && unit < UNITS.len() - 1 {
+    size /= 1024.0;
+    unit += 1;
+  }
+
+  if unit == 0 {
+    format!("{} B", len)
+  } else {
+    format!("{:.1} {}", size, UNITS[unit])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_hex_dump() {
+    let dump = hex_dump(b"Hello, binary\x00\x01\x02\xff!");
+    let lines: Vec<&str> = dump.lines().collect();
+    assert_eq!(
+      lines,
+      vec![
+        "00000000  48 65 6c 6c 6f 2c 20 62  69 6e 61 72 79 00 01 02  |Hello, binary...|",
+        "00000010  ff 21                                             |.!|",
+      ]
+    );
+    assert_eq!(hex_dump(b""), "");
+  }
+
+  #[test]
+  fn test_format_size() {
+    assert_eq!(format_size(512), "512 B");
+    assert_eq!(format_size(1536), "1.5 KB");
+    assert_eq!(format_size(3 * 1024 * 1024), "3.0 MB");
+  }
+}
@@ -0,0 +1,155 @@
+use editor::{Anchor, Bias, Editor};
+
+/// Bookmarked lines of a buffer, kept as anchors at their start so that they follow edits
+///
+/// The anchors belong to the editor they were created in, a bookmark set moves with its editor
+#[derive(Debug, Clone, Default)]
+pub struct Bookmarks {
+  anchors: Vec<Anchor>,
+}
+
+impl Bookmarks {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.anchors.is_empty()
+  }
+
+  /// Bookmarked buffer lines in order, bookmarks that ended up on the same line count once
+  pub fn lines(&self, editor: &Editor) -> Vec<usize> {
+    let mut lines: Vec<usize> = self
+      .anchors
+      .iter()
+      .filter_map(|anchor| editor.anchor_position(*anchor))
+      .map(|index| editor.buffer.char_to_line_col(index).0)
+      .collect();
+    lines.sort_unstable();
+    lines.dedup();
+    lines
+  }
+
+  pub fn contains(&self, editor: &Editor, line: usize) -> bool {
+    self.lines(editor).contains(&line)
+  }
+
+  pub fn add(&mut self, editor: &mut Editor, line: usize) {
+    if self.contains(editor, line) || line >= editor.buffer.line_count() {
+      return;
+    }
+    // Right bias, so that a line break typed at the start of the line pushes the bookmark down with it
+    let index = editor.buffer.line_col_to_char(line, 0);
+    self.anchors.push(editor.create_anchor(index, Bias::Right));
+  }
+
+  /// Removes the bookmarks of a line, or adds one, returns whether the line is now bookmarked
+  pub fn toggle(&mut self, editor: &mut Editor, line: usize) -> bool {
+    let len = self.anchors.len();
+    self.anchors.retain(|anchor| {
+      let on_line = editor
+        .anchor_position(*anchor)
+        .is_some_and(|index| editor.buffer.char_to_line_col(index).0 == line);
+      if on_line {
+        editor.remove_anchor(*anchor);
+      }
+      !on_line
+    });
+
+    if self.anchors.len() < len {
+      return false;
+    }
+    self.add(editor, line);
+    true
+  }
+
+  /// Replaces every bookmark
+  pub fn set_lines(&mut self, editor: &mut Editor, lines: impl IntoIterator<Item = usize>) {
+    self.clear(editor);
+    for line in lines {
+      self.add(editor, line);
+    }
+  }
+
+  pub fn clear(&mut self, editor: &mut Editor) {
+    for anchor in self.anchors.drain(..) {
+      editor.remove_anchor(anchor);
+    }
+  }
+
+  /// First bookmarked line after `line`, wrapping around
+  pub fn next(&self, editor: &Editor, line: usize) -> Option<usize> {
+    let lines = self.lines(editor);
+    lines
+      .iter()
+      .find(|l| **l > line)
+      .or_else(|| lines.first())
+      .copied()
+  }
+
+  /// Last bookmarked line before `line`, wrapping around
+  pub fn previous(&self, editor: &Editor, line: usize) -> Option<usize> {
+    let lines = self.lines(editor);
+    lines
+      .iter()
+      .rfind(|l| **l < line)
+      .or_else(|| lines.last())
+      .copied()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn editor(text: &str) -> Editor {
+    let mut editor = Editor::new();
+    editor.paste(text);
+    editor
+  }
+
+  #[test]
+  fn test_toggle() {
+    let mut editor = editor("a\nb\nc\n");
+    let mut bookmarks = Bookmarks::new();
+    assert!(bookmarks.toggle(&mut editor, 1));
+    assert!(bookmarks.toggle(&mut editor, 2));
+    assert_eq!(bookmarks.lines(&editor), vec![1, 2]);
+
+    assert!(!bookmarks.toggle(&mut editor, 1));
+    assert_eq!(bookmarks.lines(&editor), vec![2]);
+    // Past the last line
+    bookmarks.add(&mut editor, 9);
+    assert_eq!(bookmarks.lines(&editor), vec![2]);
+  }
+
+  #[test]
+  fn test_bookmarks_follow_edits() {
+    let mut editor = editor("a\nb\nc\n");
+    let mut bookmarks = Bookmarks::new();
+    bookmarks.set_lines(&mut editor, [1, 2]);
+
+    editor.cursor.index = 2; // "|b"
+    editor.paste("new\n");
+    assert_eq!(bookmarks.lines(&editor), vec![2, 3]);
+
+    // Deleting "b\n" leaves its bookmark on the following line
+    editor.select_range(6, 8);
+    editor.delete_selection();
+    assert_eq!(editor.buffer.as_str(), "a\nnew\nc\n");
+    assert_eq!(bookmarks.lines(&editor), vec![2]);
+  }
+
+  #[test]
+  fn test_next_and_previous_wrap_around() {
+    let mut editor = editor("a\nb\nc\nd\n");
+    let mut bookmarks = Bookmarks::new();
+    assert_eq!(bookmarks.next(&editor, 0), None);
+
+    bookmarks.set_lines(&mut editor, [1, 3]);
+    assert_eq!(bookmarks.next(&editor, 1), Some(3));
+    assert_eq!(bookmarks.next(&editor, 3), Some(1));
+    assert_eq!(bookmarks.previous(&editor, 3), Some(1));
+    assert_eq!(bookmarks.previous(&editor, 0), Some(3));
+  }
+}
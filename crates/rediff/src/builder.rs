@@ -0,0 +1,213 @@
+use crate::config::{EditorConfig, EditorTheme, ThemeMode};
+use crate::diff_editor::DiffEditor;
+use gpui::{App, AppContext, Entity};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// What the file is diffed against
+#[derive(Debug, Clone, PartialEq)]
+enum Baseline {
+  Text(String),
+  File(PathBuf), // Read on the background executor like the file itself
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+  MissingFile,                  // `file` wasn't called
+  FileNotFound(PathBuf),        // Neither a file nor a directory
+  NotAFile(PathBuf),            // A directory or another non-regular file
+  CompareFileNotFound(PathBuf), // Baseline given with `compare_with_file`
+}
+
+impl fmt::Display for BuildError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::MissingFile => write!(f, "no file to open, call `file` before `build`"),
+      Self::FileNotFound(path) => write!(f, "file not found: {}", path.display()),
+      Self::NotAFile(path) => write!(f, "not a file: {}", path.display()),
+      Self::CompareFileNotFound(path) => {
+        write!(f, "file to compare with not found: {}", path.display())
+      }
+    }
+  }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Sets up a diff editor to embed in another gpui app
+///
+/// ```no_run
+/// # fn open(cx: &mut gpui::App) -> Result<(), rediff::BuildError> {
+/// use rediff::DiffEditor;
+///
+/// let editor = DiffEditor::builder()
+///   .file("src/main.rs")
+///   .compare_with_string("fn main() {}\n")
+///   .read_only(true)
+///   .build(cx)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DiffEditorBuilder {
+  file: Option<PathBuf>,
+  baseline: Option<Baseline>, // Empty text when not set, so that every line shows as added
+  config: EditorConfig,
+  bookmarks: Vec<usize>,
+  watch: bool,
+}
+
+impl DiffEditorBuilder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// File opened in the editor, read on the background executor once built
+  pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+    self.file = Some(path.into());
+    self
+  }
+
+  pub fn compare_with_string(mut self, text: impl Into<String>) -> Self {
+    self.baseline = Some(Baseline::Text(text.into()));
+    self
+  }
+
+  pub fn compare_with_file(mut self, path: impl Into<PathBuf>) -> Self {
+    self.baseline = Some(Baseline::File(path.into()));
+    self
+  }
+
+  /// Replaces the whole config, call it before the setters of single options
+  pub fn config(mut self, config: EditorConfig) -> Self {
+    self.config = config;
+    self
+  }
+
+  /// Theme of both the light and the dark mode
+  pub fn theme(mut self, theme: EditorTheme) -> Self {
+    self.config.theme_light = theme.clone();
+    self.config.theme_dark = theme;
+    self
+  }
+
+  pub fn themes(mut self, light: EditorTheme, dark: EditorTheme) -> Self {
+    self.config.theme_light = light;
+    self.config.theme_dark = dark;
+    self
+  }
+
+  pub fn dark_mode(self, dark_mode: bool) -> Self {
+    self.theme_mode(if dark_mode {
+      ThemeMode::Dark
+    } else {
+      ThemeMode::Light
+    })
+  }
+
+  pub fn theme_mode(mut self, mode: ThemeMode) -> Self {
+    self.config.theme_mode = mode;
+    self
+  }
+
+  pub fn read_only(mut self, read_only: bool) -> Self {
+    self.config.read_only = read_only;
+    self
+  }
+
+  /// Keeps the diff up to date as the files change on disk, see `DiffEditor::watch`
+  pub fn watch(mut self, watch: bool) -> Self {
+    self.watch = watch;
+    self
+  }
+
+  /// Buffer lines (0-based) bookmarked once the file is loaded
+  pub fn bookmarks(mut self, lines: impl IntoIterator<Item = usize>) -> Self {
+    self.bookmarks = lines.into_iter().collect();
+    self
+  }
+
+  /// Checks the paths exist, files are only read once the editor is created
+  pub fn validate(&self) -> Result<&Path, BuildError> {
+    let file = self.file.as_deref().ok_or(BuildError::MissingFile)?;
+    if !file.exists() {
+      return Err(BuildError::FileNotFound(file.to_path_buf()));
+    }
+    if !file.is_file() {
+      return Err(BuildError::NotAFile(file.to_path_buf()));
+    }
+
+    if let Some(Baseline::File(path)) = &self.baseline
+      && !path.is_file()
+    {
+      return Err(BuildError::CompareFileNotFound(path.clone()));
+    }
+
+    Ok(file)
+  }
+
+  pub fn build(self, cx: &mut App) -> Result<Entity<DiffEditor>, BuildError> {
+    let file = self.validate()?.to_path_buf();
+
+    Ok(cx.new(|cx| {
+      let compare_content = match &self.baseline {
+        Some(Baseline::Text(text)) => text.clone(),
+        Some(Baseline::File(_)) | None => String::new(),
+      };
+      let mut editor = DiffEditor::new(file, compare_content, self.config, cx);
+      if let Some(Baseline::File(path)) = self.baseline {
+        editor.load_compare_file(path, cx);
+      }
+      if !self.bookmarks.is_empty() {
+        editor.set_bookmarks(self.bookmarks, cx);
+      }
+      if self.watch {
+        editor.watch(cx);
+      }
+      editor
+    }))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_validate() {
+    assert_eq!(
+      DiffEditorBuilder::new().validate(),
+      Err(BuildError::MissingFile)
+    );
+
+    let missing = std::env::temp_dir().join("rediff_builder_missing.txt");
+    assert_eq!(
+      DiffEditorBuilder::new().file(&missing).validate(),
+      Err(BuildError::FileNotFound(missing.clone()))
+    );
+
+    let dir = std::env::temp_dir();
+    assert_eq!(
+      DiffEditorBuilder::new().file(&dir).validate(),
+      Err(BuildError::NotAFile(dir.clone()))
+    );
+
+    let file = dir.join("rediff_builder_file.txt");
+    std::fs::write(&file, "text").unwrap();
+    assert_eq!(
+      DiffEditorBuilder::new()
+        .file(&file)
+        .compare_with_file(&missing)
+        .validate(),
+      Err(BuildError::CompareFileNotFound(missing))
+    );
+    assert_eq!(
+      DiffEditorBuilder::new()
+        .file(&file)
+        .compare_with_string("old")
+        .validate(),
+      Ok(file.as_path())
+    );
+    std::fs::remove_file(&file).unwrap();
+  }
+}
@@ -0,0 +1,193 @@
+use crate::keymap::Keymap;
+use gpui::{Action, FocusHandle};
+
+const MAX_MATCHES: usize = 12;
+
+/// Entry of the command palette, running it dispatches its action to the editor
+pub struct Command {
+  pub label: String,
+  pub action: Box<dyn Action>,
+}
+
+impl Command {
+  pub fn new(label: impl Into<String>, action: Box<dyn Action>) -> Self {
+    Self {
+      label: label.into(),
+      action,
+    }
+  }
+
+  /// Command of an editor action, labelled after its name like "Go to next hunk"
+  pub fn from_action(action: Box<dyn Action>) -> Self {
+    Self::new(command_label(action.name()), action)
+  }
+}
+
+impl Clone for Command {
+  fn clone(&self) -> Self {
+    Self::new(self.label.clone(), self.action.boxed_clone())
+  }
+}
+
+impl std::fmt::Debug for Command {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Command")
+      .field("label", &self.label)
+      .field("action", &self.action.name())
+      .finish()
+  }
+}
+
+/// Open command palette, with the commands matching the query
+pub struct CommandPalette {
+  pub query: String,
+  pub selected: usize, // Index in `matches`
+  pub focus_handle: FocusHandle,
+  commands: Vec<(Command, Option<String>)>, // With the keystrokes bound to them
+  matches: Vec<usize>,
+}
+
+impl CommandPalette {
+  pub fn new(commands: Vec<Command>, keymap: &Keymap, focus_handle: FocusHandle) -> Self {
+    let commands = commands
+      .into_iter()
+      .map(|command| {
+        let keystrokes = keymap
+          .keystrokes_for(command.action.as_ref())
+          .map(|keystrokes| keymap.platform().keystrokes(keystrokes));
+        (command, keystrokes)
+      })
+      .collect();
+    let mut palette = Self {
+      query: String::new(),
+      selected: 0,
+      focus_handle,
+      commands,
+      matches: Vec::new(),
+    };
+    palette.update_matches();
+    palette
+  }
+
+  pub fn set_query(&mut self, query: String) {
+    self.query = query;
+    self.update_matches();
+  }
+
+  /// Matching commands with their keystrokes, best match first
+  pub fn matches(&self) -> impl Iterator<Item = (&Command, Option<&str>)> {
+    self.matches.iter().map(|&ix| {
+      let (command, keystrokes) = &self.commands[ix];
+      (command, keystrokes.as_deref())
+    })
+  }
+
+  pub fn select_next(&mut self) {
+    if !self.matches.is_empty() {
+      self.selected = (self.selected + 1) % self.matches.len();
+    }
+  }
+
+  pub fn select_previous(&mut self) {
+    if !self.matches.is_empty() {
+      self.selected = (self.selected + self.matches.len() - 1) % self.matches.len();
+    }
+  }
+
+  pub fn selected_command(&self) -> Option<&Command> {
+    let ix = *self.matches.get(self.selected)?;
+    Some(&self.commands[ix].0)
+  }
+
+  fn update_matches(&mut self) {
+    let mut scored: Vec<(usize, usize)> = self
+      .commands
+      .iter()
+      .enumerate()
+      .filter_map(|(ix, (command, _))| Some((fuzzy_score(&self.query, &command.label)?, ix)))
+      .collect();
+    // Best score first, then in label order
+    scored.sort_by(|(a_score, a), (b_score, b)| {
+      b_score
+        .cmp(a_score)
+        .then_with(|| self.commands[*a].0.label.cmp(&self.commands[*b].0.label))
+    });
+    self.matches = scored
+      .into_iter()
+      .take(MAX_MATCHES)
+      .map(|(_, ix)| ix)
+      .collect();
+    self.selected = 0;
+  }
+}
+
+/// Sentence case label of an action name, "rediff::GoToNextHunk" gives "Go to next hunk"
+pub fn command_label(action_name: &str) -> String {
+  let name = action_name.rsplit("::").next().unwrap_or(action_name);
+  let mut label = String::new();
+  for (i, c) in name.chars().enumerate() {
+    if c.is_uppercase() && i > 0 {
+      label.push(' ');
+      label.extend(c.to_lowercase());
+    } else {
+      label.push(c);
+    }
+  }
+  label
+}
+
+/// Score of `text` for a fuzzy `query`, None when the query chars aren't all in it in order
+///
+/// Chars right after the previous match and at the start of a word score higher, so
+/// "gnh" ranks "Go to next hunk" above "Toggle inlay hints"
+pub fn fuzzy_score(query: &str, text: &str) -> Option<usize> {
+  let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+  let mut score = 0;
+  let mut from = 0;
+  let mut previous: Option<usize> = None;
+  for q in query
+    .chars()
+    .flat_map(char::to_lowercase)
+    .filter(|c| !c.is_whitespace())
+  {
+    let ix = from + text[from..].iter().position(|&c| c == q)?;
+    score += 1;
+    if previous.is_some_and(|previous| previous + 1 == ix) {
+      score += 3;
+    }
+    if ix == 0 || text[ix - 1] == ' ' {
+      score += 2;
+    }
+    previous = Some(ix);
+    from = ix + 1;
+  }
+  Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_command_label() {
+    assert_eq!(command_label("rediff::GoToNextHunk"), "Go to next hunk");
+    assert_eq!(command_label("rediff::Undo"), "Undo");
+    assert_eq!(command_label("SaveFile"), "Save file");
+  }
+
+  #[test]
+  fn test_fuzzy_score() {
+    assert_eq!(fuzzy_score("", "Undo"), Some(0));
+    assert_eq!(fuzzy_score("xyz", "Undo"), None);
+    // Chars have to come in order
+    assert_eq!(fuzzy_score("odnu", "Undo"), None);
+
+    let word_starts = fuzzy_score("gnh", "Go to next hunk").unwrap();
+    let scattered = fuzzy_score("gnh", "Toggle inlay hints").unwrap();
+    assert!(word_starts > scattered);
+
+    let consecutive = fuzzy_score("copy", "Copy").unwrap();
+    let spread = fuzzy_score("copy", "Cut or paste yesterday").unwrap();
+    assert!(consecutive > spread);
+  }
+}
@@ -0,0 +1,301 @@
+use crate::display_rows::DisplayRow;
+use editor::LineEdit;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+  pub author: String,
+  pub body: String, // One row per line
+}
+
+impl Comment {
+  pub fn new(author: impl Into<String>, body: impl Into<String>) -> Self {
+    Self {
+      author: author.into(),
+      body: body.into(),
+    }
+  }
+}
+
+/// Identifies a thread, given back by `CommentThreads::add`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ThreadId(usize);
+
+/// Comments shown between the rows of the diff, below the buffer line they're attached to
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommentThread {
+  pub line: usize, // Buffer line (0-based)
+  pub comments: Vec<Comment>,
+  pub expanded: bool, // Only the header row shows when collapsed
+}
+
+impl CommentThread {
+  pub fn new(line: usize, comments: Vec<Comment>) -> Self {
+    Self {
+      line,
+      comments,
+      expanded: true,
+    }
+  }
+
+  /// Rows of the thread, the header first
+  pub fn rows(&self) -> Vec<ThreadRowKind> {
+    let mut rows = vec![ThreadRowKind::Header];
+    if !self.expanded {
+      return rows;
+    }
+    for (comment, content) in self.comments.iter().enumerate() {
+      rows.push(ThreadRowKind::Author(comment));
+      rows.extend(
+        (0..content.body.lines().count().max(1)).map(|line| ThreadRowKind::Body { comment, line }),
+      );
+    }
+    rows
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadRowKind {
+  Header,
+  Author(usize),                        // Index of the comment
+  Body { comment: usize, line: usize }, // Line of the comment body
+}
+
+/// Display row of a comment thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThreadRow {
+  pub id: ThreadId,
+  pub kind: ThreadRowKind,
+}
+
+/// Comment threads of the open file, supplied by the host like review comments of a pull request
+#[derive(Debug, Clone, Default)]
+pub struct CommentThreads {
+  threads: BTreeMap<ThreadId, CommentThread>,
+  next_id: usize,
+}
+
+impl CommentThreads {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn add(&mut self, thread: CommentThread) -> ThreadId {
+    let id = ThreadId(self.next_id);
+    self.next_id += 1;
+    self.threads.insert(id, thread);
+    id
+  }
+
+  pub fn remove(&mut self, id: ThreadId) -> Option<CommentThread> {
+    self.threads.remove(&id)
+  }
+
+  pub fn get(&self, id: ThreadId) -> Option<&CommentThread> {
+    self.threads.get(&id)
+  }
+
+  pub fn get_mut(&mut self, id: ThreadId) -> Option<&mut CommentThread> {
+    self.threads.get_mut(&id)
+  }
+
+  pub fn clear(&mut self) {
+    self.threads.clear();
+  }
+
+  pub fn len(&self) -> usize {
+    self.threads.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.threads.is_empty()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (ThreadId, &CommentThread)> {
+    self.threads.iter().map(|(id, thread)| (*id, thread))
+  }
+
+  /// Expands a collapsed thread or collapses an expanded one
+  pub fn toggle(&mut self, id: ThreadId) {
+    if let Some(thread) = self.threads.get_mut(&id) {
+      thread.expanded = !thread.expanded;
+    }
+  }
+
+  /// Moves the threads that follow edited lines, threads of deleted lines move up to the edit
+  /// instead of disappearing with their line
+  pub fn apply_edits(&mut self, edits: &[LineEdit]) {
+    for edit in edits {
+      let deleted = if edit.line_delta < 0 {
+        edit.line_end + 1 - edit.line_delta.unsigned_abs()..edit.line_end + 1
+      } else {
+        0..0
+      };
+
+      for thread in self.threads.values_mut() {
+        if deleted.contains(&thread.line) {
+          thread.line = edit.line_start;
+        } else if thread.line > edit.line_end {
+          thread.line = thread.line.saturating_add_signed(edit.line_delta);
+        }
+      }
+    }
+  }
+
+  /// Inserts the rows of each thread after the row of its line, threads of lines hidden in a
+  /// collapsed region follow that region and threads of folded lines stay hidden
+  pub fn insert_rows(&self, rows: Vec<DisplayRow>) -> Vec<DisplayRow> {
+    if self.threads.is_empty() {
+      return rows;
+    }
+
+    let mut result = Vec::with_capacity(rows.len());
+    for row in rows {
+      let lines = match &row {
+        DisplayRow::Line { line, .. } if line.line_number > 0 => {
+          line.line_number - 1..line.line_number
+        }
+        DisplayRow::Collapsed(region) => region.start_line..region.start_line + region.line_count(),
+        _ => 0..0,
+      };
+      result.push(row);
+
+      for (id, thread) in self
+        .iter()
+        .filter(|(_, thread)| lines.contains(&thread.line))
+      {
+        result.extend(
+          thread
+            .rows()
+            .into_iter()
+            .map(|kind| DisplayRow::Thread(ThreadRow { id, kind })),
+        );
+      }
+    }
+    result
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::display_rows::CollapsedRegion;
+  use rediff_core::{DiffLine, DiffLineKind};
+
+  fn row(row: usize, line_number: usize) -> DisplayRow {
+    DisplayRow::Line {
+      row,
+      line: DiffLine {
+        line_number,
+        kind: DiffLineKind::Unchanged,
+        content: String::new(),
+        char_changes: vec![],
+        is_first_in_group: false,
+        pair: None,
+        counterpart: None,
+      },
+    }
+  }
+
+  fn kinds(rows: &[DisplayRow]) -> Vec<Option<ThreadRowKind>> {
+    rows
+      .iter()
+      .map(|row| match row {
+        DisplayRow::Thread(thread_row) => Some(thread_row.kind),
+        _ => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_thread_rows() {
+    let mut thread = CommentThread::new(
+      0,
+      vec![
+        Comment::new("ana", "Why?\nThis looks off"),
+        Comment::new("bo", ""),
+      ],
+    );
+    assert_eq!(
+      thread.rows(),
+      vec![
+        ThreadRowKind::Header,
+        ThreadRowKind::Author(0),
+        ThreadRowKind::Body {
+          comment: 0,
+          line: 0
+        },
+        ThreadRowKind::Body {
+          comment: 0,
+          line: 1
+        },
+        ThreadRowKind::Author(1),
+        ThreadRowKind::Body {
+          comment: 1,
+          line: 0
+        },
+      ]
+    );
+
+    thread.expanded = false;
+    assert_eq!(thread.rows(), vec![ThreadRowKind::Header]);
+  }
+
+  #[test]
+  fn test_insert_rows_after_their_line() {
+    let mut threads = CommentThreads::new();
+    let id = threads.add(CommentThread::new(1, vec![Comment::new("ana", "nit")]));
+    threads.add(CommentThread::new(5, vec![]));
+    threads.toggle(id);
+
+    let rows = vec![
+      row(0, 1),
+      row(1, 2),
+      row(2, 0), // Removed line
+      DisplayRow::Collapsed(CollapsedRegion {
+        rows: 3..8,
+        start_line: 2,
+      }),
+    ];
+    let rows = threads.insert_rows(rows);
+    assert_eq!(
+      kinds(&rows),
+      vec![
+        None,
+        None,
+        Some(ThreadRowKind::Header),
+        None,
+        None,
+        Some(ThreadRowKind::Header),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_apply_edits_keeps_threads_of_deleted_lines() {
+    let mut threads = CommentThreads::new();
+    let above = threads.add(CommentThread::new(1, vec![]));
+    let deleted = threads.add(CommentThread::new(4, vec![]));
+    let below = threads.add(CommentThread::new(8, vec![]));
+
+    // Lines 3 to 5 joined into line 3
+    threads.apply_edits(&[LineEdit {
+      line_start: 3,
+      line_end: 5,
+      line_delta: -2,
+    }]);
+    assert_eq!(threads.get(above).unwrap().line, 1);
+    assert_eq!(threads.get(deleted).unwrap().line, 3);
+    assert_eq!(threads.get(below).unwrap().line, 6);
+
+    // Two lines inserted at line 2
+    threads.apply_edits(&[LineEdit {
+      line_start: 2,
+      line_end: 2,
+      line_delta: 2,
+    }]);
+    assert_eq!(threads.get(above).unwrap().line, 1);
+    assert_eq!(threads.get(deleted).unwrap().line, 5);
+    assert_eq!(threads.get(below).unwrap().line, 8);
+  }
+}
@@ -1,54 +1,419 @@
-use gpui::{Hsla, black, blue, green, opaque_grey, red, white};
+use crate::diagnostics::DiagnosticSeverity;
+use crate::keymap::Keymap;
+use crate::line_cache::DEFAULT_CAPACITY;
+use cursor::WordChars;
+use gpui::{
+  Font, FontFallbacks, FontFeatures, FontWeight, Hsla, WindowAppearance, black, blue, green, hsla,
+  opaque_grey, red, white,
+};
+use rediff_core::{DiffOptions, ProseGranularity};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EditorThemeGitColor {
   pub line_bg_color: Hsla,
   pub char_highlight_color: Hsla,
   pub gutter_color: Hsla,
 }
 
-#[derive(Clone, Debug)]
+impl EditorThemeGitColor {
+  /// Both ends of moved lines
+  pub fn moved() -> Self {
+    let purple = hsla(0.78, 0.6, 0.55, 1.0);
+    Self {
+      line_bg_color: purple.alpha(0.3),
+      char_highlight_color: purple.alpha(0.6),
+      gutter_color: purple.alpha(0.8),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EditorThemeGit {
   pub added: EditorThemeGitColor,
   pub removed: EditorThemeGitColor,
   pub modified: EditorThemeGitColor,
+  #[serde(default = "EditorThemeGitColor::moved")]
+  // Missing from theme files written before moved lines
+  pub moved: EditorThemeGitColor,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EditorThemeCursorColor {
   pub color: Hsla,
   pub selection_color: Hsla,
+  pub occurrence_color: Hsla, // Other occurrences of the selected text or the word under the cursor
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EditorThemePairColor {
   pub bg_color: Hsla,
   pub text_color: Hsla,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EditorThemeScrollbarColor {
+  pub track_color: Hsla,
+  pub thumb_color: Hsla,
+}
+
+/// Squiggles and gutter badges of diagnostics
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EditorThemeDiagnostics {
+  pub error: Hsla,
+  pub warning: Hsla,
+  pub info: Hsla,
+  pub hint: Hsla,
+}
+
+impl Default for EditorThemeDiagnostics {
+  fn default() -> Self {
+    Self {
+      error: red(),
+      warning: hsla(0.11, 0.9, 0.5, 1.0),
+      info: blue(),
+      hint: opaque_grey(0.5, 1.0),
+    }
+  }
+}
+
+impl EditorThemeDiagnostics {
+  pub fn color(&self, severity: DiagnosticSeverity) -> Hsla {
+    match severity {
+      DiagnosticSeverity::Error => self.error,
+      DiagnosticSeverity::Warning => self.warning,
+      DiagnosticSeverity::Info => self.info,
+      DiagnosticSeverity::Hint => self.hint,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EditorTheme {
   pub cursor: EditorThemeCursorColor,
   pub code: EditorThemePairColor,
   pub line_numbers: EditorThemePairColor,
+  pub scrollbar: EditorThemeScrollbarColor,
+  pub annotations: EditorThemePairColor, // Annotation column, its tooltips use the same colors
+  pub status_bar: EditorThemePairColor,
+  pub whitespace_color: Hsla, // Markers of rendered spaces and tabs
   pub git: EditorThemeGit,
+  #[serde(default)] // Missing from theme files written before diagnostics
+  pub diagnostics: EditorThemeDiagnostics,
+}
+
+/// Which spaces and tabs get a visible marker
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderWhitespace {
+  #[default]
+  None,
+  All,
+  Selection, // Only inside the selection
+  Trailing,  // Only after the last non-whitespace character of a line
+}
+
+/// What the line numbers count
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineNumbers {
+  #[default]
+  Absolute,
+  Relative, // Distance from the cursor line, 0 on the cursor line
+  Hybrid,   // Relative, but the cursor line shows its own number
+}
+
+impl LineNumbers {
+  /// Number shown next to buffer line `line_idx` with the cursor on `cursor_line`
+  pub fn label(&self, line_idx: usize, cursor_line: usize) -> String {
+    match self {
+      LineNumbers::Absolute => line_idx + 1,
+      LineNumbers::Hybrid if line_idx == cursor_line => line_idx + 1,
+      LineNumbers::Relative | LineNumbers::Hybrid => line_idx.abs_diff(cursor_line),
+    }
+    .to_string()
+  }
+}
+
+/// How a smooth scroll speeds up and slows down
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollEasing {
+  Linear,
+  #[default]
+  EaseOut, // Fast at first, settles on the target
+  EaseInOut,
+}
+
+impl ScrollEasing {
+  /// Share of the distance covered once `t` of the duration elapsed, both from 0 to 1
+  pub fn apply(&self, t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    match self {
+      ScrollEasing::Linear => t,
+      ScrollEasing::EaseOut => 1.0 - (1.0 - t).powi(3),
+      ScrollEasing::EaseInOut if t < 0.5 => 4.0 * t.powi(3),
+      ScrollEasing::EaseInOut => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+    }
+  }
+}
+
+/// Font of the buffer text
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FontConfig {
+  pub family: String,
+  pub fallbacks: Vec<String>, // Tried in order for characters the family doesn't have
+  pub weight: f32,            // 400 is regular, 700 bold
+  pub ligatures: bool,
+  pub features: Vec<(String, u32)>, // Other OpenType features, like ("ss01", 1)
+}
+
+impl Default for FontConfig {
+  fn default() -> Self {
+    Self {
+      family: "monospace".to_string(),
+      fallbacks: Vec::new(),
+      weight: FontWeight::NORMAL.0,
+      ligatures: true,
+      features: Vec::new(),
+    }
+  }
+}
+
+impl FontConfig {
+  pub fn font(&self) -> Font {
+    let mut features = self.features.clone();
+    if !self.ligatures {
+      features.retain(|(tag, _)| tag != "calt" && tag != "liga");
+      features.push(("calt".to_string(), 0));
+      features.push(("liga".to_string(), 0));
+    }
+
+    Font {
+      family: self.family.clone().into(),
+      features: FontFeatures(Arc::new(features)),
+      fallbacks: (!self.fallbacks.is_empty())
+        .then(|| FontFallbacks::from_fonts(self.fallbacks.clone())),
+      weight: FontWeight(self.weight),
+      style: Default::default(),
+    }
+  }
+}
+
+/// Which of the light and dark themes is used
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeMode {
+  #[default]
+  Light,
+  Dark,
+  Auto, // Follows the appearance of the window, which follows the OS
+}
+
+impl ThemeMode {
+  pub fn is_dark(&self, appearance: WindowAppearance) -> bool {
+    match self {
+      Self::Light => false,
+      Self::Dark => true,
+      Self::Auto => matches!(
+        appearance,
+        WindowAppearance::Dark | WindowAppearance::VibrantDark
+      ),
+    }
+  }
+}
+
+/// How binary files are shown
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BinaryView {
+  #[default]
+  Placeholder, // Only tells whether the files differ
+  Hex, // Hex dumps of both sides, diffed byte by byte
+}
+
+/// How PNG and JPEG files are compared
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageDiffMode {
+  #[default]
+  SideBySide,
+  OnionSkin,  // The modified image over the original one, see-through
+  Difference, // Pixels that differ highlighted over a faded copy of the modified image
+}
+
+impl ImageDiffMode {
+  pub const ALL: [Self; 3] = [Self::SideBySide, Self::OnionSkin, Self::Difference];
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      Self::SideBySide => "Side by side",
+      Self::OnionSkin => "Onion skin",
+      Self::Difference => "Difference",
+    }
+  }
+}
+
+/// When unsaved edits are written to disk without being asked
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Autosave {
+  #[default]
+  Off,
+  AfterDelay, // Once no edit was made for `autosave_delay`
+  Interval,   // `autosave_delay` after the first unsaved edit, then again after the next one
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorStyle {
+  #[default]
+  Bar,
+  Block,     // Covers the character under the cursor
+  Underline, // Drawn under the character under the cursor
+}
+
+/// Comment syntax of a language
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommentTokens {
+  pub line: Option<String>,            // Like "//"
+  pub block: Option<(String, String)>, // Like ("/*", "*/")
+}
+
+impl CommentTokens {
+  pub fn line(token: &str) -> Self {
+    Self {
+      line: Some(token.to_string()),
+      block: None,
+    }
+  }
+
+  pub fn block(open: &str, close: &str) -> Self {
+    Self {
+      line: None,
+      block: Some((open.to_string(), close.to_string())),
+    }
+  }
+
+  pub fn with_block(self, open: &str, close: &str) -> Self {
+    Self {
+      block: Some((open.to_string(), close.to_string())),
+      ..self
+    }
+  }
+}
+
+/// Command starting a language server, see `DiffEditor::start_language_server`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguageServerConfig {
+  pub command: String, // Like "rust-analyzer", looked up in PATH
+  #[serde(default)]
+  pub args: Vec<String>,
+  pub language_id: String, // Like "rust", sent with the files opened in the server
 }
 
-#[derive(Clone, Debug)]
+/// Serializable except for the keymap, missing fields keep their default
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct EditorConfig {
+  pub font: FontConfig,
   pub font_size: f32,
   pub tab_size: usize,
+  pub insert_spaces: bool, // Tab inserts `tab_size` spaces instead of a tab character
+  pub read_only: bool,     // View-only mode: no edits, selection and navigation still work
   pub theme_light: EditorTheme,
   pub theme_dark: EditorTheme,
+  pub theme_mode: ThemeMode,
+  #[serde(skip)]
+  pub keymap: Keymap,
+  pub diff_options: DiffOptions,
+  pub collapse_unchanged: Option<usize>, // Runs of more unchanged lines than this collapse into one row
+  pub cursor_style: CursorStyle,
+  #[serde(with = "optional_millis")]
+  pub cursor_blink_interval: Option<Duration>, // None keeps the cursor steady
+  pub scroll_sensitivity: f32, // Multiplies mouse wheel and trackpad scroll deltas
+  #[serde(with = "optional_millis")]
+  pub smooth_scroll: Option<Duration>, // Going to a hunk or a line animates the scroll over it, wheel scrolling stays instant
+  pub scroll_easing: ScrollEasing,
+  pub sticky_header: bool, // Pins the header of the hunk scrolled past at the top of the viewport
+  pub status_bar: bool, // Shows diff stats, cursor position, file path and unsaved state below the editor
+  pub selection_info: bool, // Shows the size of the selection and its diff stats next to it
+  pub comment_tokens: HashMap<String, CommentTokens>, // By file extension, or file name for files without one
+  pub language_servers: HashMap<String, LanguageServerConfig>, // By file extension, none by default
+  pub word_chars: HashMap<String, String>, // Characters that are part of words besides letters, digits and '_', by file extension
+  pub subword_navigation: bool, // Word motions and deletions stop at camelCase humps and underscores inside identifiers
+  pub render_whitespace: RenderWhitespace,
+  pub trim_trailing_whitespace: bool, // Trims every line before saving
+  pub binary_view: BinaryView,
+  pub image_diff: ImageDiffMode,
+  pub structured_diff: bool, // JSON and TOML files show their changes by value when both sides parse
+  pub prose_diff: HashMap<String, ProseGranularity>, // By file extension, files of those types show their paragraphs diffed by word or sentence
+  pub minimap: bool, // Shows a scaled-down view of the whole file next to the scrollbar
+  pub render_profiling: bool, // Shows frame times, lines shaped, line cache hits and diff times over the editor
+  pub line_cache_size: usize, // Shaped lines kept in memory, the least recently used are dropped past it, 0 for no limit
+  pub overscroll: f32, // Fraction of the viewport the last line can be scrolled up past the bottom edge
+  pub reload_on_focus: bool, // Reads the open file again when the editor gets focus and it changed on disk, unless there are unsaved edits
+  pub tab_index: Option<isize>, // Position of the editor in the tab order of its window, None leaves it out
+  pub autosave: Autosave,
+  #[serde(with = "millis")]
+  pub autosave_delay: Duration,
+  pub journal_dir: Option<PathBuf>, // Unsaved edits are journaled there to recover them after a crash, None disables it
+  pub review_dir: Option<PathBuf>, // Hunk review states are kept there across sessions, None keeps them in memory
+  pub copy_removed_lines: bool,    // Copying a selection includes the removed lines shown inside it
+  pub edit_log: bool, // Records every change of the buffer with its time, see `DiffEditor::export_edit_log`
+  pub line_numbers: LineNumbers,
 }
 
 impl Default for EditorConfig {
   fn default() -> Self {
     Self {
+      font: FontConfig::default(),
       font_size: 16.0,
       tab_size: 2,
+      insert_spaces: true,
+      read_only: false,
       theme_light: Self::default_theme_light(),
       theme_dark: Self::default_theme_dark(),
+      theme_mode: ThemeMode::default(),
+      keymap: Keymap::default(),
+      diff_options: DiffOptions::default(),
+      collapse_unchanged: None,
+      cursor_style: CursorStyle::default(),
+      cursor_blink_interval: Some(Duration::from_millis(500)),
+      scroll_sensitivity: 1.0,
+      smooth_scroll: None,
+      scroll_easing: ScrollEasing::default(),
+      sticky_header: false,
+      status_bar: false,
+      selection_info: true,
+      comment_tokens: Self::default_comment_tokens(),
+      language_servers: HashMap::new(),
+      word_chars: Self::default_word_chars(),
+      subword_navigation: false,
+      render_whitespace: RenderWhitespace::default(),
+      trim_trailing_whitespace: false,
+      overscroll: 0.5,
+      minimap: false,
+      render_profiling: false,
+      line_cache_size: DEFAULT_CAPACITY,
+      binary_view: BinaryView::default(),
+      image_diff: ImageDiffMode::default(),
+      structured_diff: false,
+      prose_diff: HashMap::new(),
+      reload_on_focus: false,
+      tab_index: None,
+      autosave: Autosave::default(),
+      autosave_delay: Duration::from_secs(1),
+      journal_dir: None,
+      review_dir: None,
+      copy_removed_lines: false,
+      edit_log: false,
+      line_numbers: LineNumbers::default(),
     }
   }
 }
@@ -58,11 +423,106 @@ impl EditorConfig {
     self.font_size * 1.5
   }
 
+  /// Text inserted by Tab and by indent commands
+  pub fn indent_unit(&self) -> String {
+    if self.insert_spaces {
+      " ".repeat(self.tab_size)
+    } else {
+      "\t".to_string()
+    }
+  }
+
+  /// Comment tokens of the file, looked up by extension then by file name
+  pub fn comment_tokens_for(&self, path: &Path) -> Option<&CommentTokens> {
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    let file_name = path.file_name().and_then(|name| name.to_str());
+    extension
+      .and_then(|extension| self.comment_tokens.get(&extension.to_lowercase()))
+      .or_else(|| file_name.and_then(|name| self.comment_tokens.get(name)))
+  }
+
+  /// Language server of the file, looked up by extension
+  pub fn language_server_for(&self, path: &Path) -> Option<&LanguageServerConfig> {
+    let extension = path.extension()?.to_str()?;
+    self.language_servers.get(&extension.to_lowercase())
+  }
+
+  /// Unit the paragraphs of the file are diffed by, None when it shows the line diff
+  pub fn prose_granularity_for(&self, path: &Path) -> Option<ProseGranularity> {
+    let extension = path.extension()?.to_str()?;
+    self.prose_diff.get(&extension.to_lowercase()).copied()
+  }
+
+  /// Word characters of the file, looked up by extension
+  pub fn word_chars_for(&self, path: &Path) -> WordChars {
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    extension
+      .and_then(|extension| self.word_chars.get(&extension.to_lowercase()))
+      .map(|chars| WordChars::new(chars.chars()))
+      .unwrap_or_default()
+  }
+
+  pub fn default_word_chars() -> HashMap<String, String> {
+    let mut word_chars = HashMap::new();
+    // Hyphenated identifiers
+    for extension in [
+      "css", "html", "htm", "vue", "lisp", "el", "clj", "cljs", "scm", "rkt",
+    ] {
+      word_chars.insert(extension.to_string(), "-".to_string());
+    }
+    for extension in ["js", "jsx", "mjs", "ts", "tsx", "php"] {
+      word_chars.insert(extension.to_string(), "$".to_string());
+    }
+    word_chars.insert("scss".to_string(), "-$".to_string());
+    word_chars.insert("less".to_string(), "-@".to_string());
+    word_chars
+  }
+
+  pub fn default_comment_tokens() -> HashMap<String, CommentTokens> {
+    let c_like = CommentTokens::line("//").with_block("/*", "*/");
+    let hash = CommentTokens::line("#");
+    let markup = CommentTokens::block("<!--", "-->");
+
+    let mut tokens = HashMap::new();
+    for extension in [
+      "rs", "c", "h", "cc", "cpp", "hpp", "cs", "go", "java", "kt", "swift", "js", "jsx", "mjs",
+      "ts", "tsx", "scss", "less", "php", "dart", "zig",
+    ] {
+      tokens.insert(extension.to_string(), c_like.clone());
+    }
+    for extension in [
+      "py", "rb", "sh", "bash", "zsh", "fish", "toml", "yaml", "yml", "pl", "r", "ex", "exs",
+    ] {
+      tokens.insert(extension.to_string(), hash.clone());
+    }
+    for extension in ["html", "htm", "xml", "svg", "md", "vue"] {
+      tokens.insert(extension.to_string(), markup.clone());
+    }
+    for file_name in ["Makefile", "Dockerfile"] {
+      tokens.insert(file_name.to_string(), hash.clone());
+    }
+    tokens.insert("css".to_string(), CommentTokens::block("/*", "*/"));
+    tokens.insert(
+      "sql".to_string(),
+      CommentTokens::line("--").with_block("/*", "*/"),
+    );
+    tokens.insert(
+      "lua".to_string(),
+      CommentTokens::line("--").with_block("--[[", "]]"),
+    );
+    tokens.insert(
+      "hs".to_string(),
+      CommentTokens::line("--").with_block("{-", "-}"),
+    );
+    tokens
+  }
+
   pub fn default_theme_light() -> EditorTheme {
     EditorTheme {
       cursor: EditorThemeCursorColor {
         color: blue(),
-        selection_color: blue(),
+        selection_color: blue().alpha(0.3),
+        occurrence_color: opaque_grey(0.5, 0.2),
       },
       code: EditorThemePairColor {
         bg_color: white(),
@@ -72,6 +532,19 @@ impl EditorConfig {
         bg_color: white(),
         text_color: opaque_grey(0.3, 1.0),
       },
+      scrollbar: EditorThemeScrollbarColor {
+        track_color: opaque_grey(0.95, 1.0),
+        thumb_color: opaque_grey(0.4, 0.3),
+      },
+      annotations: EditorThemePairColor {
+        bg_color: opaque_grey(0.97, 1.0),
+        text_color: opaque_grey(0.45, 1.0),
+      },
+      status_bar: EditorThemePairColor {
+        bg_color: opaque_grey(0.94, 1.0),
+        text_color: opaque_grey(0.3, 1.0),
+      },
+      whitespace_color: opaque_grey(0.5, 0.5),
       git: EditorThemeGit {
         added: EditorThemeGitColor {
           line_bg_color: green().alpha(0.4),
@@ -103,7 +576,9 @@ impl EditorConfig {
             a: 1.0,
           },
         },
+        moved: EditorThemeGitColor::moved(),
       },
+      diagnostics: EditorThemeDiagnostics::default(),
     }
   }
 
@@ -111,7 +586,8 @@ impl EditorConfig {
     EditorTheme {
       cursor: EditorThemeCursorColor {
         color: blue(),
-        selection_color: blue(),
+        selection_color: blue().alpha(0.3),
+        occurrence_color: opaque_grey(0.5, 0.2),
       },
       code: EditorThemePairColor {
         bg_color: black(),
@@ -121,6 +597,19 @@ impl EditorConfig {
         bg_color: black(),
         text_color: opaque_grey(0.7, 1.0),
       },
+      scrollbar: EditorThemeScrollbarColor {
+        track_color: opaque_grey(0.08, 1.0),
+        thumb_color: opaque_grey(0.7, 0.3),
+      },
+      annotations: EditorThemePairColor {
+        bg_color: opaque_grey(0.05, 1.0),
+        text_color: opaque_grey(0.55, 1.0),
+      },
+      status_bar: EditorThemePairColor {
+        bg_color: opaque_grey(0.12, 1.0),
+        text_color: opaque_grey(0.7, 1.0),
+      },
+      whitespace_color: opaque_grey(0.5, 0.5),
       git: EditorThemeGit {
         added: EditorThemeGitColor {
           line_bg_color: green().alpha(0.8),
@@ -152,7 +641,9 @@ impl EditorConfig {
             a: 1.0,
           },
         },
+        moved: EditorThemeGitColor::moved(),
       },
+      diagnostics: EditorThemeDiagnostics::default(),
     }
   }
 
@@ -164,3 +655,39 @@ impl EditorConfig {
     }
   }
 }
+
+/// Durations as milliseconds
+mod millis {
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+  use std::time::Duration;
+
+  pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    (duration.as_millis() as u64).serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    Ok(Duration::from_millis(u64::deserialize(deserializer)?))
+  }
+}
+
+/// Optional durations as milliseconds, 0 for none since TOML has no null
+mod optional_millis {
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+  use std::time::Duration;
+
+  pub fn serialize<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    duration
+      .map_or(0, |duration| duration.as_millis() as u64)
+      .serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Option<Duration>, D::Error> {
+    let millis = u64::deserialize(deserializer)?;
+    Ok((millis > 0).then(|| Duration::from_millis(millis)))
+  }
+}
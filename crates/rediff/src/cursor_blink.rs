@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+/// Cursor blinking state, driven by timers scheduled from the editor
+///
+/// Every timer carries the epoch it was scheduled at, pausing bumps the epoch
+/// so the timers of the previous blink cycle stop
+#[derive(Debug, Clone)]
+pub struct CursorBlink {
+  interval: Option<Duration>, // None disables blinking
+  visible: bool,
+  epoch: usize,
+}
+
+impl CursorBlink {
+  pub fn new(interval: Option<Duration>) -> Self {
+    Self {
+      interval: interval.filter(|interval| !interval.is_zero()),
+      visible: true,
+      epoch: 0,
+    }
+  }
+
+  pub fn interval(&self) -> Option<Duration> {
+    self.interval
+  }
+
+  pub fn set_interval(&mut self, interval: Option<Duration>) {
+    self.interval = interval.filter(|interval| !interval.is_zero());
+    self.stop();
+  }
+
+  pub fn is_visible(&self) -> bool {
+    self.visible
+  }
+
+  /// Shows the cursor and starts a new blink cycle, returns its epoch
+  ///
+  /// Called on every edit or cursor move so the cursor stays visible while typing
+  pub fn pause(&mut self) -> usize {
+    self.visible = true;
+    self.epoch += 1;
+    self.epoch
+  }
+
+  /// Shows the cursor without blinking until the next pause
+  pub fn stop(&mut self) {
+    self.visible = true;
+    self.epoch += 1;
+  }
+
+  /// Toggles the cursor for a timer of the given epoch, returns false when
+  /// the timer is stale and shouldn't be scheduled again
+  pub fn blink(&mut self, epoch: usize) -> bool {
+    if epoch != self.epoch || self.interval.is_none() {
+      return false;
+    }
+    self.visible = !self.visible;
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_blink_toggles_visibility() {
+    let mut blink = CursorBlink::new(Some(Duration::from_millis(500)));
+    let epoch = blink.pause();
+
+    assert!(blink.blink(epoch));
+    assert!(!blink.is_visible());
+    assert!(blink.blink(epoch));
+    assert!(blink.is_visible());
+  }
+
+  #[test]
+  fn test_pause_shows_cursor_and_stops_old_timers() {
+    let mut blink = CursorBlink::new(Some(Duration::from_millis(500)));
+    let epoch = blink.pause();
+    blink.blink(epoch);
+
+    let new_epoch = blink.pause();
+    assert!(blink.is_visible());
+    assert!(!blink.blink(epoch));
+    assert!(blink.blink(new_epoch));
+  }
+
+  #[test]
+  fn test_disabled_blink_keeps_cursor_visible() {
+    let mut blink = CursorBlink::new(None);
+    let epoch = blink.pause();
+    assert!(!blink.blink(epoch));
+    assert!(blink.is_visible());
+
+    let blink = CursorBlink::new(Some(Duration::ZERO));
+    assert!(blink.interval().is_none());
+  }
+}
@@ -0,0 +1,203 @@
+use editor::{Anchor, Bias, Editor};
+use std::collections::HashMap;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DiagnosticSeverity {
+  Hint,
+  Info,
+  Warning,
+  Error, // The most severe one wins when showing a single badge for a line
+}
+
+/// Message attached to a range of the buffer, like a linter warning or a CI failure
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+  pub range: Range<usize>, // Buffer char range, an empty one underlines the char at its start
+  pub severity: DiagnosticSeverity,
+  pub message: String,
+  pub source: Option<String>, // Like "clippy", shown before the message
+}
+
+impl Diagnostic {
+  pub fn new(
+    range: Range<usize>,
+    severity: DiagnosticSeverity,
+    message: impl Into<String>,
+  ) -> Self {
+    Self {
+      range,
+      severity,
+      message: message.into(),
+      source: None,
+    }
+  }
+
+  pub fn with_source(mut self, source: impl Into<String>) -> Self {
+    self.source = Some(source.into());
+    self
+  }
+
+  /// Message with its source, as shown when hovering the range
+  pub fn label(&self) -> String {
+    match &self.source {
+      Some(source) => format!("{}: {}", source, self.message),
+      None => self.message.clone(),
+    }
+  }
+}
+
+/// Diagnostics of a buffer, their ranges are anchored so that they follow edits
+///
+/// Like bookmarks, the anchors belong to the editor the diagnostics were set in
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+  entries: Vec<(Anchor, Anchor, Diagnostic)>,
+}
+
+impl Diagnostics {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Replaces every diagnostic, ranges are clamped to the buffer
+  pub fn set(&mut self, editor: &mut Editor, diagnostics: Vec<Diagnostic>) {
+    self.clear(editor);
+    let len = editor.buffer.len();
+    for diagnostic in diagnostics {
+      let end = diagnostic.range.end.min(len);
+      let start = diagnostic.range.start.min(end);
+      // Text typed at either edge stays out of the range
+      let start_anchor = editor.create_anchor(start, Bias::Right);
+      let end_anchor = editor.create_anchor(end, Bias::Left);
+      self.entries.push((start_anchor, end_anchor, diagnostic));
+    }
+  }
+
+  pub fn clear(&mut self, editor: &mut Editor) {
+    for (start, end, _) in self.entries.drain(..) {
+      editor.remove_anchor(start);
+      editor.remove_anchor(end);
+    }
+  }
+
+  /// Diagnostics with their current ranges, in the order they were set
+  pub fn current(&self, editor: &Editor) -> Vec<Diagnostic> {
+    self
+      .entries
+      .iter()
+      .filter_map(|(start, end, diagnostic)| {
+        let start = editor.anchor_position(*start)?;
+        let end = editor.anchor_position(*end)?.max(start);
+        Some(Diagnostic {
+          range: start..end,
+          ..diagnostic.clone()
+        })
+      })
+      .collect()
+  }
+
+  /// Diagnostics covering a char index, most severe first
+  pub fn at(&self, editor: &Editor, index: usize) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = self
+      .current(editor)
+      .into_iter()
+      .filter(|diagnostic| underlined_range(&diagnostic.range).contains(&index))
+      .collect();
+    diagnostics.sort_by_key(|diagnostic| std::cmp::Reverse(diagnostic.severity));
+    diagnostics
+  }
+
+  /// Most severe diagnostic of each buffer line a range starts on
+  pub fn line_severities(&self, editor: &Editor) -> HashMap<usize, DiagnosticSeverity> {
+    let mut severities: HashMap<usize, DiagnosticSeverity> = HashMap::new();
+    for diagnostic in self.current(editor) {
+      let (line, _) = editor.buffer.char_to_line_col(diagnostic.range.start);
+      let severity = severities.entry(line).or_insert(diagnostic.severity);
+      *severity = (*severity).max(diagnostic.severity);
+    }
+    severities
+  }
+}
+
+/// Chars drawn with a squiggle, an empty range still marks the char it's on
+pub fn underlined_range(range: &Range<usize>) -> Range<usize> {
+  range.start..range.end.max(range.start + 1)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn editor(text: &str) -> Editor {
+    let mut editor = Editor::new();
+    editor.paste(text);
+    editor
+  }
+
+  #[test]
+  fn test_ranges_follow_edits() {
+    let mut editor = editor("let x = 1;\nlet y = x;\n");
+    let mut diagnostics = Diagnostics::new();
+    diagnostics.set(
+      &mut editor,
+      vec![
+        Diagnostic::new(15..16, DiagnosticSeverity::Warning, "unused variable")
+          .with_source("rustc"),
+      ],
+    );
+
+    editor.cursor.index = 0;
+    editor.paste("// header\n");
+    let current = diagnostics.current(&editor);
+    assert_eq!(current[0].range, 25..26);
+    assert_eq!(current[0].label(), "rustc: unused variable");
+
+    // Typing right after the range doesn't extend it
+    editor.cursor.index = 26;
+    editor.insert_char('z');
+    assert_eq!(diagnostics.current(&editor)[0].range, 25..26);
+  }
+
+  #[test]
+  fn test_at_and_line_severities() {
+    let mut editor = editor("a\nbc\n");
+    let mut diagnostics = Diagnostics::new();
+    diagnostics.set(
+      &mut editor,
+      vec![
+        Diagnostic::new(2..4, DiagnosticSeverity::Info, "info"),
+        Diagnostic::new(3..3, DiagnosticSeverity::Error, "error"),
+        Diagnostic::new(0..99, DiagnosticSeverity::Hint, "clamped"),
+      ],
+    );
+
+    let messages: Vec<String> = diagnostics
+      .at(&editor, 3)
+      .into_iter()
+      .map(|d| d.message)
+      .collect();
+    assert_eq!(messages, vec!["error", "info", "clamped"]);
+    assert!(
+      diagnostics
+        .at(&editor, 4)
+        .iter()
+        .all(|d| d.message == "clamped")
+    );
+
+    let severities = diagnostics.line_severities(&editor);
+    assert_eq!(severities.get(&0), Some(&DiagnosticSeverity::Hint));
+    assert_eq!(severities.get(&1), Some(&DiagnosticSeverity::Error));
+
+    diagnostics.clear(&mut editor);
+    assert!(diagnostics.current(&editor).is_empty());
+  }
+}
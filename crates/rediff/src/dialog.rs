@@ -0,0 +1,102 @@
+use crate::config::EditorThemePairColor;
+use gpui::{
+  AnyElement, App, FontWeight, MouseButton, SharedString, Window, black, div, prelude::*, px,
+};
+
+const DIALOG_WIDTH: f32 = 420.0;
+
+type ButtonHandler = Box<dyn Fn(&mut Window, &mut App) + 'static>;
+
+/// Modal dialog drawn over its parent, with a message, an optional body and a row of buttons
+///
+/// Clicks outside of the buttons are swallowed so they don't reach the view behind it
+#[derive(IntoElement)]
+pub struct Dialog {
+  title: SharedString,
+  message: SharedString,
+  body: Option<AnyElement>, // Shown below the message, the dialog grows to fit it
+  buttons: Vec<(SharedString, ButtonHandler)>,
+  colors: EditorThemePairColor,
+}
+
+impl Dialog {
+  pub fn new(
+    title: impl Into<SharedString>,
+    message: impl Into<SharedString>,
+    colors: EditorThemePairColor,
+  ) -> Self {
+    Self {
+      title: title.into(),
+      message: message.into(),
+      body: None,
+      buttons: Vec::new(),
+      colors,
+    }
+  }
+
+  pub fn body(mut self, body: impl IntoElement) -> Self {
+    self.body = Some(body.into_any_element());
+    self
+  }
+
+  /// Adds a button after the previous ones
+  pub fn button(
+    mut self,
+    label: impl Into<SharedString>,
+    on_click: impl Fn(&mut Window, &mut App) + 'static,
+  ) -> Self {
+    self.buttons.push((label.into(), Box::new(on_click)));
+    self
+  }
+}
+
+impl RenderOnce for Dialog {
+  fn render(self, _window: &mut Window, _cx: &mut App) -> impl IntoElement {
+    let colors = self.colors;
+    let has_body = self.body.is_some();
+
+    let buttons = self.buttons.into_iter().map(|(label, on_click)| {
+      div()
+        .px_2()
+        .cursor_pointer()
+        .border_1()
+        .border_color(colors.text_color.alpha(0.3))
+        .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+          cx.stop_propagation();
+          on_click(window, cx);
+        })
+        .child(label)
+    });
+
+    div()
+      .absolute()
+      .top_0()
+      .left_0()
+      .size_full()
+      .flex()
+      .items_center()
+      .justify_center()
+      .bg(black().alpha(0.3))
+      .on_mouse_down(MouseButton::Left, |_, _window, cx| cx.stop_propagation())
+      .child(
+        div()
+          .flex()
+          .flex_col()
+          .gap_2()
+          .p_3()
+          .when_else(has_body, |d| d.w_3_4().h_3_4(), |d| d.w(px(DIALOG_WIDTH)))
+          .bg(colors.bg_color)
+          .text_color(colors.text_color)
+          .border_1()
+          .border_color(colors.text_color.alpha(0.3))
+          .child(div().font_weight(FontWeight::SEMIBOLD).child(self.title))
+          .child(self.message)
+          .children(
+            self
+              .body
+              .map(|body| div().flex_1().min_h(px(0.0)).child(body)),
+          )
+          .child(div().flex().justify_end().gap_2().children(buttons)),
+      )
+  }
+}
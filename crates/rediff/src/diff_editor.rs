@@ -1,580 +1,6098 @@
-use crate::config::{EditorConfig, EditorTheme};
-use crate::line_cache::LineCache;
+use crate::annotations::{AnnotationTooltip, LineAnnotation, LineAnnotations};
+use crate::autosave::AutosaveTimer;
+use crate::binary::{format_size, hex_dump};
+use crate::bookmarks::Bookmarks;
+use crate::builder::DiffEditorBuilder;
+use crate::command_palette::{Command, CommandPalette};
+use crate::comment_threads::{CommentThread, CommentThreads, ThreadId, ThreadRowKind};
+use crate::config::{
+  Autosave, BinaryView, CursorStyle, EditorConfig, EditorTheme, FontConfig, ImageDiffMode,
+  ThemeMode,
+};
+use crate::cursor_blink::CursorBlink;
+use crate::diagnostics::{Diagnostic, DiagnosticSeverity, Diagnostics, underlined_range};
+use crate::dialog::Dialog;
+use crate::display_rows::{
+  DisplayRow, UnchangedRegions, display_index, display_map, line_rows, selection_spans_boundary,
+  text_with_removed_lines,
+};
+use crate::edit_log::export_edit_log;
+use crate::file_state::{FileState, FileStates};
+use crate::folds::{FoldRange, Folds, hide_folded_rows, indent_fold_ranges};
+use crate::gutter::{GutterColumn, GutterColumnId, GutterColumns, GutterRow};
+use crate::image_diff::{ImageKind, ImageSide, ImageSides};
+use crate::inlay_hints::{InlayHint, InlayHints, original_value_hints};
+use crate::input_hooks::{EventResult, InputEvent, InputHookId, InputHooks};
+use crate::journal::Journal;
+use crate::jump_list::JumpList;
+use crate::keymap::{
+  COMMAND_PALETTE_KEY_CONTEXT, KEY_CONTEXT, Keymap, actions::*, command_palette_bindings,
+};
+use crate::line_cache::{LineCache, LineCacheStats};
 use crate::line_element::{DiffBackground, EditorState, LineConfig, LineElement};
-use editor::{DiffLine, DiffLineKind, Differ, Editor};
+use crate::loading::{LoadError, LoadTarget, Loading, content_hash, modified_time};
+use crate::lsp::{LanguageServer, LspEvent, diagnostics_from_lsp, file_uri};
+use crate::minimap::{DiffMinimap, MinimapColors, MinimapState};
+use crate::original_previews::{OriginalPreviews, has_original};
+use crate::render_profile::{FrameStats, RenderProfiler};
+use crate::review::{Review, ReviewState, hunk_key};
+use crate::revisions::{Baseline, Revision, RevisionId, Revisions};
+use crate::scroll_sync::ScrollPosition;
+use crate::scrollbar::{DiffScrollbar, ScrollbarColors, ScrollbarMarker, ScrollbarState};
+use crate::smooth_scroll::ScrollAnimation;
+use crate::sticky_header::{StickyHeader, sticky_header, top_diff_row};
+use crate::tab_map::TabMap;
+use crate::viewport::Viewport;
+use crate::watch::{FileWatcher, WATCH_DEBOUNCE, WATCH_POLL_INTERVAL};
+use editor::{
+  ClipboardText, DisplayMap, Edit, EditError, EditLog, Editor, RowPosition, Selection,
+  SelectionGranularity,
+};
 use gpui::{
-  App, ClipboardItem, Context, FocusHandle, Focusable, Font, Hsla, KeyDownEvent, MouseButton,
-  MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels, Point, Render, TextRun,
-  UniformListScrollHandle, Window, black, div, prelude::*, px, uniform_list,
+  Action, AnyElement, App, ClipboardEntry, ClipboardItem, Context, Entity, EventEmitter,
+  FocusHandle, Focusable, Font, FontWeight, HighlightStyle, Hsla, KeyDownEvent, MouseButton,
+  MouseDownEvent, MouseMoveEvent, MouseUpEvent, ObjectFit, Overflow, Pixels, Point, Render,
+  ScrollStrategy, ScrollWheelEvent, ShapedLine, SharedString, StrikethroughStyle, StyledText,
+  Subscription, TextRun, UniformList, UniformListScrollHandle, Window, black, canvas, div, img,
+  point, prelude::*, px, uniform_list,
 };
+use rediff_core::{
+  ConflictMarkers, DiffHunk, DiffLine, DiffLineKind, DiffOptions, DiffStats, Differ, ExportFormat,
+  FilePatch, LineMap, ProseGranularity, ProseParagraph, ProseSpanKind, Resolution, StructuredDiff,
+  StructuredError, StructuredFormat, ValueChangeKind, export, find_conflicts, merge,
+};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::io;
 use std::ops::Range;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
-use text::TextBuffer;
+use std::time::{Duration, Instant, SystemTime};
+use text::{Encoding, FileContent, LineEnding, LoadProgress, TextBuffer};
 
-const LINE_NUMBERS_WIDTH: f32 = 60.0;
+const MIN_LINE_NUMBER_DIGITS: usize = 2; // Room kept for line numbers however short the file
+const LINE_NUMBERS_PADDING: f32 = 8.0; // Right of the line numbers
+const MARKER_GAP: f32 = 4.0; // Between the bookmark and diagnostic markers and the line number
 const DIFF_GUTTER_WIDTH: f32 = 8.0;
 const EDITOR_PADDING: f32 = 8.0;
+const SCROLLBAR_WIDTH: f32 = 12.0;
+const MINIMAP_WIDTH: f32 = 80.0;
+const FOLD_INDICATOR_WIDTH: f32 = 16.0;
+const ANNOTATIONS_WIDTH: f32 = 160.0;
+const COMMAND_PALETTE_WIDTH: f32 = 420.0;
+const TAB_BAR_HEIGHT: f32 = 30.0;
+const STATUS_BAR_HEIGHT: f32 = 24.0;
+// Space kept between the cursor and the pane edges when scrolling horizontally
+const HORIZONTAL_SCROLL_MARGIN: f32 = 32.0;
+const ZOOM_STEP: f32 = 1.0;
+const MIN_FONT_SIZE: f32 = 6.0;
+const MAX_FONT_SIZE: f32 = 72.0;
+const LOAD_REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+const LOAD_PROGRESS_WIDTH: f32 = 200.0;
+const LSP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const LSP_HOVER_DELAY: Duration = Duration::from_millis(300);
+const LSP_HOVER_MAX_LINES: usize = 12;
+const ONION_SKIN_STEP: f32 = 0.25;
+
+/// Events emitted for hosts to keep window titles and file lists in sync
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffEditorEvent {
+  Edited,
+  Saved,
+  DirtyChanged(bool),
+  DiffStatsChanged {
+    added: usize,
+    removed: usize,
+  },
+  AppearanceChanged {
+    dark_mode: bool,
+  }, // The dark or light theme is now used
+  Zoomed, // The font size changed, for hosts keeping the zoom level across sessions
+  // Hunks of the open file reviewed, for hosts adding up the progress of several files
+  ReviewChanged {
+    path: PathBuf,
+    reviewed: usize,
+    total: usize,
+  },
+  Scrolled, // Another line is at the top of the viewport, see `DiffEditor::scroll_position`
+  Focused,  // The editor or one of its popups got focus
+  Blurred,  // Focus left the editor and its popups
+}
+
+/// How the open file is shown, picked from the content of both sides
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+  Text,
+  Binary, // Placeholder or hex dumps, see `BinaryView`
+  Image,  // Both sides are PNG or JPEG images, or one is and the other side is empty
+}
+
+/// Buffer of a tab that isn't active, keeps unsaved edits
+struct OpenBuffer {
+  editor: Editor,
+  bookmarks: Bookmarks, // Anchored in `editor`
+  jump_list: JumpList,  // Anchored in `editor`
+  journal: Option<Journal>,
+  is_dirty: bool,
+  disk_modified: Option<SystemTime>,
+  disk_hash: Option<u64>,
+  file_bytes: Option<Vec<u8>>,
+}
+
+/// Save held back because the file changed on disk since it was read
+struct SaveConflict {
+  disk_content: String,
+  diff: Option<Entity<DiffEditor>>, // Disk content diffed against the buffer, once asked for
+}
+
+/// Highlighted change of a modified line under the mouse, with the text it replaced
+#[derive(Debug, Clone, PartialEq)]
+struct HoveredChange {
+  row: usize,
+  change_idx: usize,
+  start_x: Pixels, // Start of the change, relative to the line text
+  original: String,
+}
+
+/// Language server info about the char under the mouse, shown once the server answers
+#[derive(Debug, Clone, PartialEq)]
+struct LspHover {
+  row: usize,
+  start_x: Pixels,      // Start of the hovered char, relative to the line text
+  index: usize,         // Buffer char under the mouse
+  request: Option<u64>, // Sent once the mouse rested on the char
+  text: Option<String>,
+}
+
+/// Diagnostics under the mouse, most severe first
+#[derive(Debug, Clone, PartialEq)]
+struct HoveredDiagnostic {
+  row: usize,
+  start_x: Pixels, // Start of the hovered char, relative to the line text
+  messages: Vec<(DiagnosticSeverity, String)>,
+}
+
+/// Renders the cell of a column for a display row, rows past the last one included
+type CellRenderer = Box<dyn Fn(usize, &mut Window, &mut App) -> AnyElement>;
+
+/// Column left of the text, rendered in the same list as the text so that they scroll together
+struct RowColumn {
+  width: f32,
+  bg_color: Hsla,
+  render_cell: CellRenderer,
+}
 
 pub struct DiffEditor {
   editor: Editor,
   focus_handle: FocusHandle,
   config: EditorConfig,
-  scroll_handle: UniformListScrollHandle,
+  font: Font,                             // Built from the font config
+  default_font_size: f32,                 // Font size of the config, restored by ResetZoom
+  scroll_handle: UniformListScrollHandle, // Tracked by the list of rows, gutter columns included
+  gutter_width: Pixels,                   // Columns left of the text at the last render
+  scroll_x: Pixels,
+  autoscroll_cursor_index: Option<usize>,
   is_selecting: bool,
-  selection_anchor: Option<usize>,
+  selection_anchor: Option<Range<usize>>, // Char, word or line picked at mouse down
+  selection_granularity: SelectionGranularity,
+  is_gutter_selecting: bool,
+  gutter_anchor_line: Option<usize>, // Line the last gutter click started from, Shift+click extends from it
+  cursor_blink: CursorBlink,
+  blink_cursor_index: Option<usize>, // Cursor position the blink cycle started at, None while unfocused
   line_cache: Arc<Mutex<LineCache>>,
+  profiler: Rc<RefCell<RenderProfiler>>, // Times frames while `render_profiling` is on, ended when the frame is painted
+  scrollbar_state: Arc<Mutex<ScrollbarState>>,
+  minimap_state: Arc<Mutex<MinimapState>>,
+  displayed_rows: Vec<DisplayRow>, // Rows of the last render, for hover hit-testing
+  display_map: Rc<DisplayMap>,     // Buffer lines of the rows of the last render
+  display_map_versions: Option<(u64, u64)>, // Buffer and differ versions of the last render
+  reported_scroll: Option<ScrollPosition>, // Scroll position of the last render
+  scroll_set: bool, // Scrolled by `set_scroll_position` since the last render, not reported
+  hovered_change: Option<HoveredChange>,
+  hovered_diagnostic: Option<HoveredDiagnostic>,
+  lsp_hover: Option<LspHover>,
+  lsp_hover_epoch: usize, // Bumped when the mouse moves to another char, older hover timers do nothing then
+  scroll_anchor: Option<(usize, Pixels)>, // Buffer line kept at this height of the pane on the next render
+  scroll_animation: Option<ScrollAnimation>, // Smooth scroll in progress, stepped on every frame
   pub file_path: PathBuf,
+  file_backed: bool, // False when the buffer doesn't come from `file_path`, like a patch preview
   is_dirty: bool,
-  compare_content: String,
+  disk_modified: Option<SystemTime>, // Modification time of `file_path` when last read or written
+  disk_hash: Option<u64>,            // Hash of the text of `file_path` when last read or written
+  save_conflict: Option<SaveConflict>,
+  journal: Option<Journal>, // Unsaved edits of the open file, while journaling is enabled
+  recovered: Option<String>, // Buffer rebuilt from the journal of a previous run, until restored or discarded
+  autosave_timer: AutosaveTimer,
+  journal_timer: AutosaveTimer, // Journals the edits once they pause for `autosave_delay`
+  file_bytes: Option<Vec<u8>>,  // Content of the open file when it's binary
+  compare_bytes: Option<Vec<u8>>, // Original baseline when it's binary
+  image_sides: Option<Rc<ImageSides>>, // Decoded once the image diff shows, dropped when a side changes
+  onion_skin_mix: f32,                 // Opacity of the modified image in the onion skin view
+  loads: Vec<Loading>,
+  next_load_id: usize,
+  failed_load: Option<(LoadError, LoadTarget)>, // Shown until retried or dismissed
+  on_load_error: Option<Rc<dyn Fn(&LoadError, &mut App)>>,
+  compare_content: String,            // Text of the current baseline
+  original_content: String,           // Text of the original baseline
+  compare_file_path: Option<PathBuf>, // File the original baseline is read from, if any
+  watcher: Option<FileWatcher>,
+  language_server: Option<LanguageServer>,
+  lsp_synced: Option<(PathBuf, String)>, // File and text the language server last got
+  lsp_edited: bool,                      // The buffer changed since the last sync
+  lsp_epoch: usize, // Bumped when the language server starts or stops, older poll timers do nothing then
+  watch_epoch: usize, // Bumped when watching starts or stops, older poll timers do nothing then
+  watch_label: String, // Last "Updated …" label shown in the status bar
+  revisions: Revisions, // Snapshots of the open file, cleared when switching files
+  baseline: Baseline,
+  baseline_menu_open: bool,
+  command_palette: Option<CommandPalette>,
+  commands: Vec<Command>, // Added by the host, listed after the editor actions in the palette
   differ: Differ,
+  differ_version: u64, // Bumped when the original or the diff options change
+  diff_cache: RefCell<Option<((u64, u64), Rc<Vec<DiffLine>>)>>, // Last diff, with the buffer and differ versions it was computed at
+  fold_cache: RefCell<Option<((u64, usize), Rc<Vec<FoldRange>>)>>, // Last fold ranges, with the buffer version and the tab size they were found at
+  longest_line: Cell<Option<((u64, u64, usize), usize)>>, // Columns of the longest line of both sides, with the buffer and differ versions and the tab size it was measured at
+  unchanged_regions: UnchangedRegions,
+  revealed_cursor_index: usize, // Cursor position the collapsed regions were last checked against
+  folds: Folds,
+  line_annotations: LineAnnotations, // Annotation column, shown when not empty
+  gutter_columns: GutterColumns,
+  comment_threads: CommentThreads, // Shown between the rows, cleared when switching files
+  original_previews: OriginalPreviews, // Original lines shown below the lines that replaced them
+  bookmarks: Bookmarks,
+  pending_bookmarks: Option<Vec<usize>>, // Set while the file is still loading
+  jump_list: JumpList,                   // Positions left by large jumps in the open file
+  diagnostics: Diagnostics,              // Of the open file, cleared when switching files
+  pending_diagnostics: Option<Vec<Diagnostic>>,
+  inlay_hints: InlayHints, // Of the open file, cleared when switching files
+  pending_inlay_hints: Option<Vec<InlayHint>>,
+  diff_stats: Option<DiffStats>,     // Last stats reported to hosts
+  reviews: HashMap<PathBuf, Review>, // Hunk review states by file, read from the review dir when a file opens
+  review_progress: Option<(PathBuf, usize, usize)>, // Last progress reported to hosts
+  file_states: FileStates,
+  tabs: Vec<PathBuf>,
+  open_buffers: HashMap<PathBuf, OpenBuffer>,
   dark_mode: bool,
+  appearance_subscription: Option<Subscription>, // Window appearance observer, while the theme mode is auto
+  focus_subscriptions: Vec<Subscription>, // Focus and blur observers, set up on the first render
+  input_hooks: InputHooks,
 }
 
 impl DiffEditor {
+  /// Starts setting up an editor, see `DiffEditorBuilder`
+  pub fn builder() -> DiffEditorBuilder {
+    DiffEditorBuilder::new()
+  }
+
   pub fn new(
     file_path: PathBuf,
     compare_content: String,
     config: EditorConfig,
     cx: &mut Context<Self>,
   ) -> Self {
-    let focus_handle = cx.focus_handle();
+    let mut this = Self::with_editor(
+      Editor::new(),
+      file_path.clone(),
+      compare_content,
+      config,
+      cx,
+    );
+    this.start_loading(file_path, LoadTarget::Open(FileState::default()), cx);
+    this
+  }
 
-    let editor = match TextBuffer::from_file(&file_path) {
-      Ok(buffer) => editor::Editor {
-        buffer,
-        cursor: cursor::Cursor::new(),
-        selection: None,
-      },
-      Err(e) => {
-        eprintln!("Failed to load file: {}", e);
-        editor::Editor::new()
-      }
-    };
+  /// Opens a read-only view of a parsed patch, the hunks of both sides are shown one after another
+  pub fn from_patch(patch: &FilePatch, config: EditorConfig, cx: &mut Context<Self>) -> Self {
+    Self::from_text(
+      PathBuf::from(patch.path()),
+      &patch.modified_text(),
+      patch.original_text(),
+      config,
+      cx,
+    )
+  }
+
+  /// Opens a read-only diff of two texts, `file_path` is only shown, never read or written
+  pub fn from_text(
+    file_path: PathBuf,
+    text: &str,
+    compare_content: String,
+    config: EditorConfig,
+    cx: &mut Context<Self>,
+  ) -> Self {
+    let mut buffer = TextBuffer::new();
+    buffer.insert(0, text);
+
+    let mut this = Self::with_editor(
+      Editor::from_buffer(buffer),
+      file_path,
+      compare_content,
+      config,
+      cx,
+    );
+    this.file_backed = false;
+    this.tabs.clear();
+    this.config.read_only = true;
+    this
+  }
+
+  /// Opens the three-way merge of `ours` and `theirs` into `file_path`
+  ///
+  /// Conflicts are written with git style markers and diffed against ours, so the
+  /// lines of theirs show up as added. Saving writes the merge result to `file_path`
+  pub fn from_merge(
+    file_path: PathBuf,
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    config: EditorConfig,
+    cx: &mut Context<Self>,
+  ) -> Self {
+    let result = merge(base, ours, theirs);
+    let mut buffer = TextBuffer::new();
+    buffer.insert(0, &result.text_with_markers("ours", "theirs"));
+
+    let mut this = Self::with_editor(
+      Editor::from_buffer(buffer),
+      file_path,
+      ours.to_string(),
+      config,
+      cx,
+    );
+    this.file_backed = false;
+    this.tabs.clear();
+    this.is_dirty = true;
+    this
+  }
+
+  /// Opens an editor without a file, it shows a placeholder until `open` is called
+  pub fn empty(config: EditorConfig, cx: &mut Context<Self>) -> Self {
+    let mut this = Self::with_editor(Editor::new(), PathBuf::new(), String::new(), config, cx);
+    this.file_backed = false;
+    this.tabs.clear();
+    this
+  }
+
+  fn with_editor(
+    mut editor: Editor,
+    file_path: PathBuf,
+    compare_content: String,
+    config: EditorConfig,
+    cx: &mut Context<Self>,
+  ) -> Self {
+    let focus_handle = with_tab_index(cx.focus_handle(), config.tab_index);
 
-    let differ = Differ::new(compare_content.clone());
+    let differ = Differ::with_options(compare_content.clone(), config.diff_options.clone());
+    configure_editor(&mut editor, &config, &file_path);
+    let cursor_index = editor.cursor.index;
+    let cursor_blink = CursorBlink::new(config.cursor_blink_interval);
+    let dark_mode = config.theme_mode == ThemeMode::Dark;
+    let line_cache = LineCache::with_capacity(config.line_cache_size);
 
     Self {
       editor,
       focus_handle,
+      font: config.font.font(),
+      default_font_size: config.font_size,
       config,
       scroll_handle: UniformListScrollHandle::new(),
+      gutter_width: px(0.0),
+      scroll_x: px(0.0),
+      autoscroll_cursor_index: None,
       is_selecting: false,
       selection_anchor: None,
-      line_cache: Arc::new(Mutex::new(LineCache::new())),
+      selection_granularity: SelectionGranularity::Char,
+      is_gutter_selecting: false,
+      gutter_anchor_line: None,
+      cursor_blink,
+      blink_cursor_index: None,
+      line_cache: Arc::new(Mutex::new(line_cache)),
+      profiler: Rc::new(RefCell::new(RenderProfiler::new())),
+      scrollbar_state: Arc::new(Mutex::new(ScrollbarState::default())),
+      minimap_state: Arc::new(Mutex::new(MinimapState::default())),
+      displayed_rows: Vec::new(),
+      display_map: Rc::default(),
+      display_map_versions: None,
+      reported_scroll: None,
+      scroll_set: false,
+      hovered_change: None,
+      hovered_diagnostic: None,
+      lsp_hover: None,
+      lsp_hover_epoch: 0,
+      scroll_anchor: None,
+      scroll_animation: None,
+      tabs: vec![file_path.clone()],
       file_path,
+      file_backed: true,
       is_dirty: false,
+      disk_modified: None,
+      disk_hash: None,
+      save_conflict: None,
+      journal: None,
+      recovered: None,
+      autosave_timer: AutosaveTimer::new(),
+      journal_timer: AutosaveTimer::new(),
+      file_bytes: None,
+      compare_bytes: None,
+      image_sides: None,
+      onion_skin_mix: 0.5,
+      loads: Vec::new(),
+      next_load_id: 0,
+      failed_load: None,
+      on_load_error: None,
+      original_content: compare_content.clone(),
       compare_content,
+      compare_file_path: None,
+      watcher: None,
+      language_server: None,
+      lsp_synced: None,
+      lsp_edited: false,
+      lsp_epoch: 0,
+      watch_epoch: 0,
+      watch_label: String::new(),
+      revisions: Revisions::new(),
+      baseline: Baseline::Original,
+      baseline_menu_open: false,
+      command_palette: None,
+      commands: Vec::new(),
       differ,
-      dark_mode: false,
+      differ_version: 0,
+      diff_cache: RefCell::new(None),
+      fold_cache: RefCell::new(None),
+      longest_line: Cell::new(None),
+      unchanged_regions: UnchangedRegions::new(),
+      revealed_cursor_index: cursor_index,
+      folds: Folds::new(),
+      line_annotations: LineAnnotations::new(),
+      gutter_columns: GutterColumns::new(),
+      comment_threads: CommentThreads::new(),
+      original_previews: OriginalPreviews::new(),
+      bookmarks: Bookmarks::new(),
+      pending_bookmarks: None,
+      jump_list: JumpList::new(),
+      diagnostics: Diagnostics::new(),
+      pending_diagnostics: None,
+      inlay_hints: InlayHints::new(),
+      pending_inlay_hints: None,
+      diff_stats: None,
+      reviews: HashMap::new(),
+      review_progress: None,
+      file_states: FileStates::new(),
+      open_buffers: HashMap::new(),
+      dark_mode,
+      appearance_subscription: None,
+      focus_subscriptions: Vec::new(),
+      input_hooks: InputHooks::new(),
     }
   }
 
-  pub fn toggle_dark_mode(&mut self) {
-    self.dark_mode = !self.dark_mode;
+  /// Binds the keymap's keystrokes to the editor actions
+  pub fn register(cx: &mut App, keymap: &Keymap) {
+    cx.bind_keys(keymap.key_bindings());
+    cx.bind_keys(command_palette_bindings());
   }
 
-  pub fn get_theme(&self) -> &EditorTheme {
-    self.config.get_theme(self.dark_mode)
+  /// Binary files can't be edited, not even in the hex view
+  pub fn is_read_only(&self) -> bool {
+    self.config.read_only || self.file_bytes.is_some()
   }
 
-  pub fn set_file_path(&mut self, path: PathBuf, cx: &mut Context<Self>) {
-    self.file_path = path;
-    self.reload_file(cx);
+  pub fn config(&self) -> &EditorConfig {
+    &self.config
   }
 
-  pub fn editor(&mut self) -> &mut Editor {
-    &mut self.editor
+  /// Replaces the whole config, like after the settings changed, per-editor overrides are
+  /// applied by the host with `ConfigOverrides::apply`
+  ///
+  /// What depends on the changed settings is rebuilt, the zoom is kept unless the font size
+  /// changed. Key bindings are app-wide, they're registered again with `DiffEditor::register`
+  pub fn update_config(&mut self, config: EditorConfig, cx: &mut Context<Self>) {
+    let old = std::mem::replace(&mut self.config, config);
+
+    let font_size = self.config.font_size;
+    self.config.font_size = old.font_size;
+    if font_size != self.default_font_size {
+      self.default_font_size = font_size;
+      self.set_font_size(font_size, cx);
+    }
+
+    if self.config.font != old.font {
+      self.font = self.config.font.font();
+    }
+    // Shaped lines carry the font, the tab size and the theme colors
+    {
+      let mut line_cache = self.line_cache.lock().unwrap();
+      line_cache.clear();
+      line_cache.set_capacity(self.config.line_cache_size);
+    }
+
+    if self.config.diff_options != old.diff_options {
+      self.differ.set_options(self.config.diff_options.clone());
+      self.differ_version += 1;
+    }
+    if self.config.theme_mode != old.theme_mode {
+      self.set_theme_mode(self.config.theme_mode, cx);
+    }
+    if self.config.tab_index != old.tab_index {
+      self.focus_handle = with_tab_index(self.focus_handle.clone(), self.config.tab_index);
+    }
+    if self.config.cursor_blink_interval != old.cursor_blink_interval {
+      self
+        .cursor_blink
+        .set_interval(self.config.cursor_blink_interval);
+      self.blink_cursor_index = None;
+    }
+    if self.config.binary_view != old.binary_view {
+      self.show_binary();
+      self.sync_differ_original();
+    }
+    if self.config.autosave != old.autosave {
+      self.autosave_timer.cancel();
+    }
+    configure_editor(&mut self.editor, &self.config, &self.file_path);
+    cx.notify();
   }
 
-  fn compute_diff(&self) -> Vec<DiffLine> {
-    self.differ.compute_diff(&self.editor.buffer.as_str())
+  pub fn set_read_only(&mut self, read_only: bool, cx: &mut Context<Self>) {
+    self.config.read_only = read_only;
+    cx.notify();
   }
 
-  pub fn update_compare_content(&mut self, content: String) {
-    self.compare_content = content.clone();
-    self.differ = Differ::new(content);
+  pub fn is_dark_mode(&self) -> bool {
+    self.dark_mode
   }
 
-  fn mark_dirty(&mut self) {
-    self.is_dirty = true;
+  pub fn theme_mode(&self) -> ThemeMode {
+    self.config.theme_mode
   }
 
-  fn reload_file(&mut self, cx: &mut Context<Self>) {
-    match TextBuffer::from_file(&self.file_path) {
-      Ok(buffer) => {
-        let cursor_index = self.editor.cursor.index.min(buffer.len());
-        self.editor.buffer = buffer;
-        self.editor.cursor.index = cursor_index;
-        self.editor.selection = None;
-        self.is_dirty = false;
-        cx.notify();
-      }
-      Err(e) => {
-        eprintln!("Failed to reload file: {}", e);
-      }
+  /// The auto mode starts following the window appearance on the next render
+  pub fn set_theme_mode(&mut self, mode: ThemeMode, cx: &mut Context<Self>) {
+    self.config.theme_mode = mode;
+    if mode != ThemeMode::Auto {
+      self.appearance_subscription = None;
+      self.apply_dark_mode(mode == ThemeMode::Dark, cx);
     }
+    cx.notify();
   }
 
-  fn calculate_index_from_position(&self, mouse_pos: Point<Pixels>, window: &mut Window) -> usize {
-    let line_height = px(self.config.line_height());
-    let line_numbers_width = px(LINE_NUMBERS_WIDTH + DIFF_GUTTER_WIDTH);
-    let padding = px(EDITOR_PADDING);
-
-    let clicked_visual_line = (mouse_pos.y / line_height).floor() as usize;
+  /// Switches to the other theme, leaving the auto mode
+  pub fn toggle_dark_mode(&mut self, cx: &mut Context<Self>) {
+    let mode = if self.dark_mode {
+      ThemeMode::Light
+    } else {
+      ThemeMode::Dark
+    };
+    self.set_theme_mode(mode, cx);
+  }
 
-    let diff_lines = self.compute_diff();
+  fn apply_dark_mode(&mut self, dark_mode: bool, cx: &mut Context<Self>) {
+    if self.dark_mode == dark_mode {
+      return;
+    }
+    self.dark_mode = dark_mode;
+    // Shaped lines carry the text color
+    self.line_cache.lock().unwrap().clear();
+    cx.emit(DiffEditorEvent::AppearanceChanged { dark_mode });
+    cx.notify();
+  }
 
-    if clicked_visual_line >= diff_lines.len() {
-      return self.editor.buffer.len();
+  /// Applies the window appearance in auto mode, and keeps following it
+  fn observe_appearance(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+    if self.config.theme_mode != ThemeMode::Auto || self.appearance_subscription.is_some() {
+      return;
     }
 
-    let diff_line = &diff_lines[clicked_visual_line];
+    self.apply_dark_mode(ThemeMode::Auto.is_dark(window.appearance()), cx);
+    self.appearance_subscription =
+      Some(cx.observe_window_appearance(window, |this, window, cx| {
+        let dark_mode = this.config.theme_mode.is_dark(window.appearance());
+        this.apply_dark_mode(dark_mode, cx);
+      }));
+  }
 
-    // If clicking on a removed line (no line number), ignore the click
-    if diff_line.line_number == 0 {
-      return self.editor.cursor.index;
+  /// Emits focus and blur events from now on, the window is only known once rendering
+  fn observe_focus(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+    if !self.focus_subscriptions.is_empty() {
+      return;
     }
+    self.focus_subscriptions = vec![
+      cx.on_focus_in(&self.focus_handle, window, |this, _window, cx| {
+        this.focus_in(cx)
+      }),
+      cx.on_focus_out(&self.focus_handle, window, |_this, _event, _window, cx| {
+        cx.emit(DiffEditorEvent::Blurred);
+        cx.notify();
+      }),
+    ];
+  }
 
-    let buffer_line_idx = diff_line.line_number - 1;
-    let buffer = &self.editor.buffer;
+  fn focus_in(&mut self, cx: &mut Context<Self>) {
+    if self.config.reload_on_focus
+      && !self.is_dirty
+      && self.file_backed
+      && self.editor_loaded()
+      && modified_time(&self.file_path) != self.disk_modified
+    {
+      self.reload_file(cx);
+    }
+    cx.emit(DiffEditorEvent::Focused);
+    cx.notify();
+  }
 
-    if buffer_line_idx >= buffer.line_count() {
-      return buffer.len();
+  /// Moves keyboard focus to the editor
+  pub fn focus(&self, window: &mut Window) {
+    window.focus(&self.focus_handle);
+  }
+
+  /// Whether the editor or one of its popups has keyboard focus
+  pub fn is_focused(&self, window: &Window, cx: &App) -> bool {
+    self.focus_handle.contains_focused(window, cx)
+  }
+
+  /// Runs `hook` on the key and mouse events of the editor before it handles them, it stops
+  /// the ones it returns Handled for
+  pub fn add_input_hook(
+    &mut self,
+    hook: impl Fn(&InputEvent, &mut Window, &mut App) -> EventResult + 'static,
+    cx: &mut Context<Self>,
+  ) -> InputHookId {
+    let id = self.input_hooks.add(hook);
+    cx.notify();
+    id
+  }
+
+  pub fn remove_input_hook(&mut self, id: InputHookId, cx: &mut Context<Self>) -> bool {
+    let removed = self.input_hooks.remove(id);
+    cx.notify();
+    removed
+  }
+
+  pub fn font_config(&self) -> &FontConfig {
+    &self.config.font
+  }
+
+  /// Lines are shaped again with the new font on the next render
+  pub fn set_font(&mut self, font: FontConfig, cx: &mut Context<Self>) {
+    self.font = font.font();
+    self.config.font = font;
+    cx.notify();
+  }
+
+  pub fn font_size(&self) -> f32 {
+    self.config.font_size
+  }
+
+  /// Scales the text and the line height, the line of the cursor stays where it is on screen
+  pub fn set_font_size(&mut self, font_size: f32, cx: &mut Context<Self>) {
+    let font_size = font_size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+    if font_size == self.config.font_size {
+      return;
     }
 
-    let text = buffer
-      .line(buffer_line_idx)
-      .unwrap_or_default()
-      .trim_end_matches('\n')
-      .to_string();
+    let old_line_height = self.config.line_height();
+    self.config.font_size = font_size;
+    let line_height = self.config.line_height();
 
-    let font_size = px(self.config.font_size);
-    let monospace_font = Font {
-      family: "monospace".into(),
-      features: Default::default(),
-      fallbacks: Default::default(),
-      weight: Default::default(),
-      style: Default::default(),
-    };
+    let (cursor_line, _) = self
+      .editor
+      .buffer
+      .char_to_line_col(self.editor.cursor.index);
+    let cursor_row = self.display_map.line_row(cursor_line);
+    let base_handle = self.scroll_handle.0.borrow().base_handle.clone();
+    let offset = base_handle.offset();
+    let scroll_top = zoomed_scroll_top(
+      -f32::from(offset.y),
+      cursor_row,
+      old_line_height,
+      line_height,
+    );
+    base_handle.set_offset(point(offset.x, px(-scroll_top)));
+    self.scroll_x *= line_height / old_line_height;
 
-    let text_run = TextRun {
-      len: text.len(),
-      font: monospace_font,
-      color: black(),
-      background_color: None,
-      underline: None,
-      strikethrough: None,
-    };
+    cx.emit(DiffEditorEvent::Zoomed);
+    cx.notify();
+  }
 
-    let shaped_line = window
-      .text_system()
-      .shape_line(text.into(), font_size, &[text_run], None);
+  fn zoom_in(&mut self, _: &ZoomIn, _window: &mut Window, cx: &mut Context<Self>) {
+    self.set_font_size(self.config.font_size + ZOOM_STEP, cx);
+  }
+
+  fn zoom_out(&mut self, _: &ZoomOut, _window: &mut Window, cx: &mut Context<Self>) {
+    self.set_font_size(self.config.font_size - ZOOM_STEP, cx);
+  }
 
-    let relative_x = mouse_pos.x - line_numbers_width - padding;
-    let col = shaped_line.closest_index_for_x(relative_x);
+  fn reset_zoom(&mut self, _: &ResetZoom, _window: &mut Window, cx: &mut Context<Self>) {
+    self.set_font_size(self.default_font_size, cx);
+  }
 
-    let mut offset = 0;
-    for i in 0..buffer_line_idx {
-      if let Some(line) = buffer.line(i) {
-        offset += line.len();
-      }
+  /// Replaces the theme of both the light and the dark mode
+  pub fn set_theme(&mut self, theme: EditorTheme, cx: &mut Context<Self>) {
+    self.set_themes(theme.clone(), theme, cx);
+  }
+
+  pub fn set_themes(&mut self, light: EditorTheme, dark: EditorTheme, cx: &mut Context<Self>) {
+    self.config.theme_light = light;
+    self.config.theme_dark = dark;
+    self.line_cache.lock().unwrap().clear();
+    cx.notify();
+  }
+
+  pub fn get_theme(&self) -> &EditorTheme {
+    self.config.get_theme(self.dark_mode)
+  }
+
+  /// Opens another file in the active tab, the view state of the current one is kept to restore it when reopened
+  pub fn set_file_path(&mut self, path: PathBuf, cx: &mut Context<Self>) {
+    let previous_tab = self.active_tab().filter(|_| path != self.file_path);
+    self.open(path, cx);
+    if let Some(tab_ix) = previous_tab {
+      self.close_tab(tab_ix, cx);
     }
-    offset += col.min(buffer.line(buffer_line_idx).unwrap_or_default().len());
-    offset.min(buffer.len())
   }
 
-  fn on_mouse_down(&mut self, event: &MouseDownEvent, window: &mut Window, cx: &mut Context<Self>) {
-    let index = self.calculate_index_from_position(event.position, window);
+  /// Opens a file in a new tab after the active one, or activates its tab if it's already open
+  pub fn open(&mut self, path: PathBuf, cx: &mut Context<Self>) {
+    if !self.tabs.contains(&path) {
+      let tab_ix = self.active_tab().map_or(self.tabs.len(), |ix| ix + 1);
+      self.tabs.insert(tab_ix, path.clone());
+    }
+    self.switch_to(path, cx);
+  }
 
-    match event.click_count {
-      1 => {
-        self.editor.cursor.index = index;
-        self.editor.clear_selection();
-        self.is_selecting = true;
-        self.selection_anchor = Some(index);
-      }
-      2 => {
-        self.editor.select_word_at(index);
-        self.is_selecting = false;
-      }
-      3 => {
-        self.editor.select_line_at(index);
-        self.is_selecting = false;
-      }
-      _ => {}
+  pub fn tabs(&self) -> &[PathBuf] {
+    &self.tabs
+  }
+
+  pub fn active_tab(&self) -> Option<usize> {
+    if !self.file_backed {
+      return None;
     }
-    cx.notify();
+    self.tabs.iter().position(|path| *path == self.file_path)
   }
 
-  fn on_mouse_move(&mut self, event: &MouseMoveEvent, window: &mut Window, cx: &mut Context<Self>) {
-    if self.is_selecting || event.pressed_button == Some(MouseButton::Left) {
-      let index = self.calculate_index_from_position(event.position, window);
+  /// Whether the tab has unsaved edits
+  pub fn is_tab_dirty(&self, tab_ix: usize) -> bool {
+    match self.tabs.get(tab_ix) {
+      Some(_) if Some(tab_ix) == self.active_tab() => self.is_dirty,
+      Some(path) => self
+        .open_buffers
+        .get(path)
+        .is_some_and(|buffer| buffer.is_dirty),
+      None => false,
+    }
+  }
+
+  pub fn activate_tab(&mut self, tab_ix: usize, cx: &mut Context<Self>) {
+    if let Some(path) = self.tabs.get(tab_ix).cloned() {
+      self.switch_to(path, cx);
+    }
+  }
+
+  /// Closes a tab, unsaved edits of its buffer are discarded
+  pub fn close_tab(&mut self, tab_ix: usize, cx: &mut Context<Self>) {
+    if tab_ix >= self.tabs.len() {
+      return;
+    }
 
-      if let Some(anchor) = self.selection_anchor {
-        self.editor.select_range(anchor, index);
+    if Some(tab_ix) == self.active_tab() {
+      let neighbour = if tab_ix + 1 < self.tabs.len() {
+        Some(tab_ix + 1)
       } else {
-        self.editor.select_range(self.editor.cursor.index, index);
+        tab_ix.checked_sub(1)
+      };
+
+      match neighbour {
+        Some(neighbour) => self.activate_tab(neighbour, cx),
+        None => self.close_active_buffer(cx),
       }
-      self.editor.cursor.index = index;
-      cx.notify();
     }
-  }
 
-  fn on_mouse_up(&mut self, _event: &MouseUpEvent, _window: &mut Window, cx: &mut Context<Self>) {
-    self.is_selecting = false;
-    self.selection_anchor = None;
+    let path = self.tabs.remove(tab_ix);
+    if let Some(journal) = self
+      .open_buffers
+      .remove(&path)
+      .and_then(|buffer| buffer.journal)
+    {
+      discard_journal(journal);
+    }
     cx.notify();
   }
 
-  fn on_mouse_up_out(
-    &mut self,
-    _event: &MouseUpEvent,
-    _window: &mut Window,
-    _cx: &mut Context<Self>,
-  ) {
-    self.is_selecting = false;
-    self.selection_anchor = None;
+  /// Activates the next tab, `offset` can be negative to go backwards and wraps around
+  fn cycle_tabs(&mut self, offset: isize, cx: &mut Context<Self>) {
+    let Some(tab_ix) = self.active_tab() else {
+      return;
+    };
+    let len = self.tabs.len() as isize;
+    let target = (tab_ix as isize + offset).rem_euclid(len) as usize;
+    self.activate_tab(target, cx);
   }
 
-  fn render_diff_gutter(
-    &self,
-    diff_lines: Vec<DiffLine>,
-    scroll_handle: UniformListScrollHandle,
-  ) -> impl IntoElement {
-    let line_height = self.config.line_height();
-    let item_count = diff_lines.len();
-    let theme = self.get_theme();
-    let added_gutter_color = theme.git.added.gutter_color;
-    let removed_gutter_color = theme.git.removed.gutter_color;
-    let line_numbers_bg_color = theme.line_numbers.bg_color;
+  /// Keeps the active buffer and its view state aside and loads another file
+  fn switch_to(&mut self, path: PathBuf, cx: &mut Context<Self>) {
+    if self.file_backed && path == self.file_path {
+      return;
+    }
 
-    uniform_list(
-      "diff-gutter",
-      item_count,
-      move |range: Range<usize>, _window, _cx| {
-        range
-          .map(|idx| {
-            let line = &diff_lines[idx];
-            let bg_color: Hsla = match line.kind {
-              DiffLineKind::Added => added_gutter_color,
-              DiffLineKind::Removed => removed_gutter_color,
-              DiffLineKind::Modified if line.line_number == 0 => removed_gutter_color,
-              DiffLineKind::Modified => added_gutter_color,
-              DiffLineKind::Unchanged => line_numbers_bg_color,
-            };
-
-            div().h(px(line_height)).w_full().bg(bg_color)
-          })
-          .collect::<Vec<_>>()
-      },
-    )
-    .w(px(DIFF_GUTTER_WIDTH))
-    .track_scroll(scroll_handle)
+    self.loads.retain(|loading| !loading.loads_buffer());
+    self.clear_buffer_load_error();
+    // Diagnostics describe the previous file
+    self.diagnostics.clear(&mut self.editor);
+    self.pending_diagnostics = None;
+    self.inlay_hints.clear(&mut self.editor);
+    self.pending_inlay_hints = None;
+    if self.file_backed && self.editor_loaded() {
+      self.flush_journal();
+      let state = self.file_state();
+      self.file_states.save(&self.file_path, state);
+      self.open_buffers.insert(
+        self.file_path.clone(),
+        OpenBuffer {
+          editor: std::mem::take(&mut self.editor),
+          bookmarks: std::mem::take(&mut self.bookmarks),
+          jump_list: std::mem::take(&mut self.jump_list),
+          journal: self.journal.take(),
+          is_dirty: self.is_dirty,
+          disk_modified: self.disk_modified,
+          disk_hash: self.disk_hash,
+          file_bytes: self.file_bytes.take(),
+        },
+      );
+    }
+
+    let state = self.file_states.take(&path).unwrap_or_default();
+    self.file_path = path;
+    self.file_backed = true;
+    self.disk_modified = None;
+    self.disk_hash = None;
+    self.save_conflict = None;
+    self.journal = None;
+    self.recovered = None;
+    self.autosave_timer.cancel();
+    self.journal_timer.cancel();
+    self.line_cache.lock().unwrap().clear();
+    self.line_annotations.clear(); // Annotations describe the previous file
+    self.comment_threads.clear();
+    self.original_previews.clear();
+    self.clear_revisions();
+
+    match self.open_buffers.remove(&self.file_path) {
+      Some(buffer) => {
+        self.editor = buffer.editor;
+        self.bookmarks = buffer.bookmarks;
+        self.jump_list = buffer.jump_list;
+        self.journal = buffer.journal;
+        self.editor.take_line_edits();
+        self.disk_modified = buffer.disk_modified;
+        self.disk_hash = buffer.disk_hash;
+        self.file_bytes = buffer.file_bytes;
+        // The binary view may have changed since
+        self.show_binary();
+        self.sync_differ_original();
+        self.set_dirty(buffer.is_dirty, cx);
+        self.restore_file_state(state);
+      }
+      None => {
+        self.editor = Editor::new();
+        self.bookmarks = Bookmarks::new();
+        self.jump_list = JumpList::new();
+        self.file_bytes = None;
+        self.set_dirty(false, cx);
+        self.start_loading(self.file_path.clone(), LoadTarget::Open(state), cx);
+      }
+    }
+    configure_editor(&mut self.editor, &self.config, &self.file_path);
+
+    cx.notify();
   }
 
-  fn render_line_numbers(
-    &self,
-    diff_lines: Vec<DiffLine>,
-    scroll_handle: UniformListScrollHandle,
-  ) -> impl IntoElement {
-    let line_height = self.config.line_height();
-    let item_count = diff_lines.len();
-    let theme = self.get_theme();
-    let line_numbers_bg_color = theme.line_numbers.bg_color;
-    let line_numbers_text_color = theme.line_numbers.text_color;
+  /// Leaves the editor empty once its last tab is closed
+  fn close_active_buffer(&mut self, cx: &mut Context<Self>) {
+    let state = self.file_state();
+    self.file_states.save(&self.file_path, state);
 
-    uniform_list(
-      "line-numbers",
-      item_count,
-      move |range: Range<usize>, _window, _cx| {
-        range
-          .map(|idx| {
-            let line = &diff_lines[idx];
-            let line_num_text = if line.line_number == 0 {
-              "".to_string()
-            } else {
-              line.line_number.to_string()
-            };
+    self.loads.retain(|loading| !loading.loads_buffer());
+    self.clear_buffer_load_error();
+    if let Some(journal) = self.journal.take() {
+      discard_journal(journal);
+    }
+    self.recovered = None;
+    self.autosave_timer.cancel();
+    self.journal_timer.cancel();
+    self.editor = Editor::new();
+    self.bookmarks = Bookmarks::new();
+    self.pending_bookmarks = None;
+    self.jump_list = JumpList::new();
+    self.diagnostics = Diagnostics::new();
+    self.pending_diagnostics = None;
+    self.inlay_hints = InlayHints::new();
+    self.pending_inlay_hints = None;
+    self.file_path = PathBuf::new();
+    self.file_backed = false;
+    self.disk_modified = None;
+    self.disk_hash = None;
+    self.save_conflict = None;
+    self.file_bytes = None;
+    self.sync_differ_original();
+    self.set_dirty(false, cx);
+    self.folds.unfold_all();
+    self.unchanged_regions.reset();
+    self.line_annotations.clear();
+    self.comment_threads.clear();
+    self.original_previews.clear();
+    self.clear_revisions();
+    self.line_cache.lock().unwrap().clear();
+  }
 
-            div()
-              .w(px(LINE_NUMBERS_WIDTH))
-              .h(px(line_height))
-              .flex()
-              .items_end()
-              .justify_end()
-              .pr_2()
-              .text_color(line_numbers_text_color)
-              .child(line_num_text)
-          })
-          .collect::<Vec<_>>()
-      },
-    )
-    .w(px(LINE_NUMBERS_WIDTH))
-    .bg(line_numbers_bg_color)
-    .track_scroll(scroll_handle)
+  /// Current view state of the open file
+  pub fn file_state(&self) -> FileState {
+    FileState {
+      cursor_index: self.editor.cursor.index,
+      selection: self.editor.selection,
+      scroll_offset: self.scroll_handle.0.borrow().base_handle.offset(),
+      scroll_x: self.scroll_x,
+      folds: self.folds.clone(),
+      unchanged_regions: self.unchanged_regions.clone(),
+    }
   }
 
-  fn render_editor(
+  fn restore_file_state(&mut self, mut state: FileState) {
+    state.clamp(self.editor.buffer.len());
+
+    self.editor.cursor.move_to(state.cursor_index);
+    self.editor.selection = state.selection;
+    self.revealed_cursor_index = state.cursor_index;
+    // Keep the restored horizontal scroll instead of revealing the cursor
+    self.autoscroll_cursor_index = Some(state.cursor_index);
+    self
+      .scroll_handle
+      .0
+      .borrow()
+      .base_handle
+      .set_offset(state.scroll_offset);
+    self.scroll_x = state.scroll_x;
+    self.folds = state.folds;
+    self.unchanged_regions = state.unchanged_regions;
+  }
+
+  pub fn editor(&mut self) -> &mut Editor {
+    &mut self.editor
+  }
+
+  /// Line ending of the open file, used again when saving
+  pub fn line_ending(&self) -> LineEnding {
+    self.editor.buffer.line_ending()
+  }
+
+  /// Encoding of the open file, used again when saving
+  pub fn encoding(&self) -> Encoding {
+    self.editor.buffer.encoding()
+  }
+
+  /// Diff of the buffer against the original, computed again only after an edit or when the
+  /// original or the diff options changed
+  fn compute_diff(&self) -> Rc<Vec<DiffLine>> {
+    let versions = (self.editor.buffer.version(), self.differ_version);
+    if let Some((cached_versions, diff_lines)) = &*self.diff_cache.borrow()
+      && *cached_versions == versions
+    {
+      return diff_lines.clone();
+    }
+
+    let start = Instant::now();
+    let diff_lines = Rc::new(self.differ.compute_diff(&self.editor.buffer.as_str()));
+    if self.config.render_profiling {
+      self.profiler.borrow_mut().record_diff(start.elapsed());
+    }
+    *self.diff_cache.borrow_mut() = Some((versions, diff_lines.clone()));
+    diff_lines
+  }
+
+  /// Diff rows as displayed, with long unchanged runs collapsed when enabled and folded lines hidden
+  fn display_rows(&self, diff_lines: &[DiffLine]) -> Vec<DisplayRow> {
+    let rows = match self.config.collapse_unchanged {
+      Some(min_lines) => self
+        .unchanged_regions
+        .display_rows(diff_lines.to_vec(), min_lines),
+      None => line_rows(diff_lines.to_vec()),
+    };
+    let rows = hide_folded_rows(rows, &self.folded_ranges());
+    let rows = self.comment_threads.insert_rows(rows);
+    self.original_previews.insert_rows(rows)
+  }
+
+  /// Display map of the rows as they are now, the one of the last render unless an edit or a
+  /// baseline change came since
+  fn current_display_map(&self) -> Rc<DisplayMap> {
+    let versions = (self.editor.buffer.version(), self.differ_version);
+    if self.display_map_versions == Some(versions) {
+      return self.display_map.clone();
+    }
+    Rc::new(display_map(
+      &self.display_rows(&self.compute_diff()),
+      self.editor.buffer.line_count(),
+    ))
+  }
+
+  /// Ranges that can be folded, found again only after an edit or when the tab size changed
+  fn fold_ranges(&self) -> Rc<Vec<FoldRange>> {
+    let key = (self.editor.buffer.version(), self.config.tab_size);
+    if let Some((cached_key, ranges)) = &*self.fold_cache.borrow()
+      && *cached_key == key
+    {
+      return ranges.clone();
+    }
+
+    let ranges = Rc::new(indent_fold_ranges(
+      &self.editor.buffer.as_str(),
+      self.config.tab_size,
+    ));
+    *self.fold_cache.borrow_mut() = Some((key, ranges.clone()));
+    ranges
+  }
+
+  fn folded_ranges(&self) -> Vec<FoldRange> {
+    self.folds.folded_ranges(&self.fold_ranges())
+  }
+
+  /// Folds or unfolds the range whose header is `start_line`
+  pub fn toggle_fold(&mut self, start_line: usize, cx: &mut Context<Self>) {
+    self.folds.toggle(start_line);
+
+    // Keep the cursor visible on the header instead of unfolding right away
+    let cursor_line = self.cursor_line();
+    if let Some(range) = self
+      .folded_ranges()
+      .iter()
+      .find(|range| range.start_line == start_line && range.hides_line(cursor_line))
+    {
+      let index = self.editor.buffer.line_col_to_char(range.start_line, 0);
+      self.editor.cursor.move_to(index);
+      self.editor.clear_selection();
+    }
+    cx.notify();
+  }
+
+  /// Unfolds the ranges hiding the cursor, after an edit or a jump moved it there
+  fn unfold_at_cursor(&mut self) {
+    let cursor_line = self.cursor_line();
+    for range in self.folded_ranges() {
+      if range.hides_line(cursor_line) {
+        self.folds.unfold(range.start_line);
+      }
+    }
+  }
+
+  /// Rows drawn by the last render, in display order
+  pub fn displayed_rows(&self) -> &[DisplayRow] {
+    &self.displayed_rows
+  }
+
+  /// Which rows of the last render show which buffer lines
+  pub fn display_map(&self) -> &DisplayMap {
+    &self.display_map
+  }
+
+  /// Buffer line at the top of the viewport, and how much of its row is scrolled past
+  pub fn scroll_position(&self) -> ScrollPosition {
+    let viewport = self.viewport();
+    if viewport.line_height <= px(0.0) {
+      return ScrollPosition::default();
+    }
+    let rows = (viewport.scroll_top / viewport.line_height).max(0.0);
+    match self.display_map.row_position(rows.floor() as usize) {
+      RowPosition::Line(line) => ScrollPosition {
+        line,
+        fraction: rows.fract(),
+      },
+      RowPosition::Before(line) => ScrollPosition {
+        line,
+        fraction: 0.0,
+      },
+    }
+  }
+
+  /// Scrolls `position` to the top of the viewport, a line that's folded scrolls its fold there
+  ///
+  /// Doesn't emit `DiffEditorEvent::Scrolled`, so that editors kept in sync by a `ScrollSync`
+  /// don't bounce scrolls back and forth
+  pub fn set_scroll_position(&mut self, position: ScrollPosition, cx: &mut Context<Self>) {
+    let row = self
+      .display_map
+      .row_containing_line(position.line)
+      .unwrap_or(self.display_map.len());
+    let base_handle = self.scroll_handle.0.borrow().base_handle.clone();
+    let offset = base_handle.offset();
+    let max_scroll_top = f32::from(base_handle.max_offset().height).max(0.0);
+    let scroll_top =
+      ((row as f32 + position.fraction) * self.config.line_height()).clamp(0.0, max_scroll_top);
+    self.scroll_animation = None;
+    base_handle.set_offset(point(offset.x, px(-scroll_top)));
+    self.scroll_set = true;
+    cx.notify();
+  }
+
+  /// Text of the buffer
+  pub fn text(&self) -> String {
+    self.editor.buffer.as_str()
+  }
+
+  /// Rows the editor pane shows whole, what Page Up and Page Down move by
+  pub fn visible_row_count(&self) -> usize {
+    let viewport = self.viewport();
+    if viewport.line_height <= px(0.0) {
+      return 1;
+    }
+    ((viewport.bounds.size.height / viewport.line_height).floor() as usize).max(1)
+  }
+
+  /// Moves the cursor a pane height up or down, the rows scroll by as much so that it stays
+  /// at the same height
+  fn move_by_page(&mut self, down: bool, select: bool, cx: &mut Context<Self>) {
+    let page = self.visible_row_count();
+    let cursor_line = self.cursor_line();
+    let cursor_row = self.display_map.line_row(cursor_line);
+
+    // Removed lines and collapsed regions have no line to land on, the nearest one before
+    // the target row is taken
+    let target_line = cursor_row.and_then(|cursor_row| {
+      let target_row = if down {
+        (cursor_row + page).min(self.display_map.len().saturating_sub(1))
+      } else {
+        cursor_row.saturating_sub(page)
+      };
+      let rows: Vec<usize> = if down {
+        (cursor_row + 1..=target_row).rev().collect()
+      } else {
+        (target_row..cursor_row).collect()
+      };
+      self.display_map.first_line_in(rows).map(|(_, line)| line)
+    });
+    // Not rendered yet, or the last line was reached already
+    let lines = target_line.map_or(page, |line| line.abs_diff(cursor_line));
+
+    match (down, select) {
+      (false, false) => {
+        self.editor.clear_selection();
+        self.editor.cursor.move_up_by(&self.editor.buffer, lines);
+      }
+      (true, false) => {
+        self.editor.clear_selection();
+        self.editor.cursor.move_down_by(&self.editor.buffer, lines);
+      }
+      (false, true) => self.editor.extend_selection_up_by(lines),
+      (true, true) => self.editor.extend_selection_down_by(lines),
+    }
+
+    let new_row = self.display_map.line_row(self.cursor_line());
+    if let (Some(cursor_row), Some(new_row)) = (cursor_row, new_row) {
+      self.scroll_animation = None;
+      let base_handle = self.scroll_handle.0.borrow().base_handle.clone();
+      let offset = base_handle.offset();
+      let max_scroll_top = f32::from(base_handle.max_offset().height).max(0.0);
+      let delta = (new_row as f32 - cursor_row as f32) * self.config.line_height();
+      let scroll_top = (-f32::from(offset.y) + delta).clamp(0.0, max_scroll_top);
+      base_handle.set_offset(point(offset.x, px(-scroll_top)));
+    }
+    cx.notify();
+  }
+
+  /// Repeats a vertical motion while the cursor lands on folded lines
+  fn skip_folded_lines(&mut self, motion: impl Fn(&mut Editor)) {
+    let folded = self.folded_ranges();
+    loop {
+      let line = self.cursor_line();
+      if !folded.iter().any(|range| range.hides_line(line)) {
+        break;
+      }
+      motion(&mut self.editor);
+      if self.cursor_line() == line {
+        break;
+      }
+    }
+  }
+
+  /// Expands the collapsed region of unchanged lines starting at `start_line`
+  pub fn expand_unchanged_region(&mut self, start_line: usize, cx: &mut Context<Self>) {
+    self.unchanged_regions.expand(start_line);
+    cx.notify();
+  }
+
+  /// Expands the collapsed region the cursor moved into, so that it stays visible
+  fn expand_region_at_cursor(&mut self, diff_lines: &[DiffLine]) {
+    let Some(min_lines) = self.config.collapse_unchanged else {
+      return;
+    };
+    if self.revealed_cursor_index == self.editor.cursor.index {
+      return;
+    }
+    self.revealed_cursor_index = self.editor.cursor.index;
+
+    let cursor_line = self.cursor_line();
+    if let Some(region) = self
+      .unchanged_regions
+      .collapsed_regions(diff_lines, min_lines)
+      .into_iter()
+      .find(|region| region.contains_line(cursor_line))
+    {
+      self.unchanged_regions.expand(region.start_line);
+    }
+  }
+
+  /// Serializes the current diff as a unified diff with three lines of context
+  pub fn export_patch(&self) -> String {
+    let path = self.file_path.to_string_lossy();
+    FilePatch::from_diff(
+      &self.compute_diff(),
+      &format!("a/{}", path),
+      &format!("b/{}", path),
+      3,
+    )
+    .to_string()
+  }
+
+  /// Shows host supplied annotations, like git blame, in a column before the line numbers
+  pub fn set_line_annotations(&mut self, annotations: Vec<LineAnnotation>, cx: &mut Context<Self>) {
+    self.line_annotations.set(annotations);
+    cx.notify();
+  }
+
+  /// Adds a gutter column after the line numbers, like coverage markers or review checkboxes
+  pub fn add_gutter_column(
+    &mut self,
+    column: GutterColumn,
+    cx: &mut Context<Self>,
+  ) -> GutterColumnId {
+    let id = self.gutter_columns.add(column);
+    cx.notify();
+    id
+  }
+
+  /// Replaces a column added with `add_gutter_column`, it keeps its place
+  pub fn replace_gutter_column(
+    &mut self,
+    id: GutterColumnId,
+    column: GutterColumn,
+    cx: &mut Context<Self>,
+  ) -> bool {
+    let replaced = self.gutter_columns.replace(id, column);
+    cx.notify();
+    replaced
+  }
+
+  pub fn remove_gutter_column(&mut self, id: GutterColumnId, cx: &mut Context<Self>) -> bool {
+    let removed = self.gutter_columns.remove(id).is_some();
+    cx.notify();
+    removed
+  }
+
+  pub fn clear_line_annotations(&mut self, cx: &mut Context<Self>) {
+    self.line_annotations.clear();
+    cx.notify();
+  }
+
+  /// Bookmarked buffer lines (0-based) of the open file, in order
+  pub fn bookmarks(&self) -> Vec<usize> {
+    match &self.pending_bookmarks {
+      Some(lines) => lines.clone(),
+      None => self.bookmarks.lines(&self.editor),
+    }
+  }
+
+  /// Replaces the bookmarks of the open file, like review comment locations from the host
+  pub fn set_bookmarks(&mut self, lines: Vec<usize>, cx: &mut Context<Self>) {
+    if self.editor_loaded() {
+      self.bookmarks.set_lines(&mut self.editor, lines);
+    } else {
+      self.pending_bookmarks = Some(lines);
+    }
+    cx.notify();
+  }
+
+  /// Returns whether the line is bookmarked now
+  pub fn toggle_bookmark_at(&mut self, line: usize, cx: &mut Context<Self>) -> bool {
+    let bookmarked = self.bookmarks.toggle(&mut self.editor, line);
+    cx.notify();
+    bookmarked
+  }
+
+  fn toggle_bookmark(&mut self, _: &ToggleBookmark, _window: &mut Window, cx: &mut Context<Self>) {
+    self.toggle_bookmark_at(self.cursor_line(), cx);
+  }
+
+  /// Review state of the hunk at a buffer line (0-based), None when the line isn't part of a change
+  pub fn review_state_at(&self, line: usize) -> Option<ReviewState> {
+    let key = self.review_key_at(&self.compute_diff(), line)?;
+    Some(
+      self
+        .reviews
+        .get(&self.file_path)
+        .map(|review| review.state(key))
+        .unwrap_or_default(),
+    )
+  }
+
+  /// Sets the review state of the hunk at a buffer line, returns false when the line isn't part of a change
+  pub fn set_review_state_at(
+    &mut self,
+    line: usize,
+    state: ReviewState,
+    cx: &mut Context<Self>,
+  ) -> bool {
+    let Some(key) = self.review_key_at(&self.compute_diff(), line) else {
+      return false;
+    };
+    self
+      .reviews
+      .entry(self.file_path.clone())
+      .or_default()
+      .set(key, state);
+    self.save_review();
+    cx.notify();
+    true
+  }
+
+  /// Reviewed hunks of the open file, and how many hunks it has
+  pub fn review_progress(&self) -> (usize, usize) {
+    self.diff_review_progress(&self.compute_diff())
+  }
+
+  fn diff_review_progress(&self, diff_lines: &[DiffLine]) -> (usize, usize) {
+    let keys: Vec<u64> = self
+      .review_hunks(diff_lines)
+      .into_iter()
+      .map(|(_, key)| key)
+      .collect();
+    match self.reviews.get(&self.file_path) {
+      Some(review) => review.progress(&keys),
+      None => (0, keys.len()),
+    }
+  }
+
+  /// Hunks of the diff with their review key
+  fn review_hunks(&self, diff_lines: &[DiffLine]) -> Vec<(DiffHunk, u64)> {
+    Differ::compute_hunks(diff_lines)
+      .into_iter()
+      .map(|hunk| {
+        let key = hunk_key(diff_lines, &hunk);
+        (hunk, key)
+      })
+      .collect()
+  }
+
+  fn review_key_at(&self, diff_lines: &[DiffLine], line: usize) -> Option<u64> {
+    let hunk = hunk_at_line(diff_lines, line)?;
+    Some(hunk_key(diff_lines, &hunk))
+  }
+
+  /// Sets `state` on a hunk, or goes back to unviewed when it already is in it
+  fn toggle_review_state(&mut self, key: u64, state: ReviewState, cx: &mut Context<Self>) {
+    self
+      .reviews
+      .entry(self.file_path.clone())
+      .or_default()
+      .toggle(key, state);
+    self.save_review();
+    cx.notify();
+  }
+
+  fn toggle_hunk_viewed(
+    &mut self,
+    _: &ToggleHunkViewed,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    if let Some(key) = self.review_key_at(&self.compute_diff(), self.cursor_line()) {
+      self.toggle_review_state(key, ReviewState::Viewed, cx);
+    }
+  }
+
+  fn toggle_hunk_needs_work(
+    &mut self,
+    _: &ToggleHunkNeedsWork,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    if let Some(key) = self.review_key_at(&self.compute_diff(), self.cursor_line()) {
+      self.toggle_review_state(key, ReviewState::NeedsWork, cx);
+    }
+  }
+
+  /// Reads the review of the open file from the review dir, the first time it's opened
+  fn load_review(&mut self) {
+    if !self.file_backed || self.reviews.contains_key(&self.file_path) {
+      return;
+    }
+    let Some(dir) = self.config.review_dir.as_deref() else {
+      return;
+    };
+    match Review::load(dir, &self.file_path) {
+      Ok(review) => {
+        self.reviews.insert(self.file_path.clone(), review);
+      }
+      Err(e) => eprintln!("Failed to read the review of {:?}: {}", self.file_path, e),
+    }
+  }
+
+  /// Writes the review of the open file to the review dir, hunks no longer in the diff are dropped
+  fn save_review(&mut self) {
+    let Some(dir) = self.config.review_dir.clone().filter(|_| self.file_backed) else {
+      return;
+    };
+    let keys: Vec<u64> = self
+      .review_hunks(&self.compute_diff())
+      .into_iter()
+      .map(|(_, key)| key)
+      .collect();
+    let Some(review) = self.reviews.get_mut(&self.file_path) else {
+      return;
+    };
+    review.retain(&keys);
+    if let Err(e) = review.save(&dir, &self.file_path) {
+      eprintln!("Failed to save the review of {:?}: {}", self.file_path, e);
+    }
+  }
+
+  /// Emits the review progress when it changed since the last render
+  fn update_review_progress(&mut self, (reviewed, total): (usize, usize), cx: &mut Context<Self>) {
+    let progress = (self.file_path.clone(), reviewed, total);
+    if self.review_progress.as_ref() != Some(&progress) {
+      self.review_progress = Some(progress);
+      cx.emit(DiffEditorEvent::ReviewChanged {
+        path: self.file_path.clone(),
+        reviewed,
+        total,
+      });
+    }
+  }
+
+  fn go_to_next_bookmark(
+    &mut self,
+    _: &GoToNextBookmark,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    if let Some(line) = self.bookmarks.next(&self.editor, self.cursor_line()) {
+      self.go_to_line(line, cx);
+    }
+  }
+
+  fn go_to_previous_bookmark(
+    &mut self,
+    _: &GoToPreviousBookmark,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    if let Some(line) = self.bookmarks.previous(&self.editor, self.cursor_line()) {
+      self.go_to_line(line, cx);
+    }
+  }
+
+  /// Moves the cursor to the start of a buffer line and centers its row
+  fn go_to_line(&mut self, line: usize, cx: &mut Context<Self>) {
+    self.push_jump();
+    let index = self.editor.buffer.line_col_to_char(line, 0);
+    self.move_cursor_and_center(index, cx);
+  }
+
+  /// Goes back to where the cursor was before the last jump, returns false when there's none
+  pub fn jump_back(&mut self, cx: &mut Context<Self>) -> bool {
+    let index = self.editor.cursor.index;
+    match self.jump_list.back(&mut self.editor, index) {
+      Some(index) => {
+        self.move_cursor_and_center(index, cx);
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Goes forward to the position left by going back, returns false when there's none
+  pub fn jump_forward(&mut self, cx: &mut Context<Self>) -> bool {
+    match self
+      .jump_list
+      .forward(&self.editor, self.editor.cursor.index)
+    {
+      Some(index) => {
+        self.move_cursor_and_center(index, cx);
+        true
+      }
+      None => false,
+    }
+  }
+
+  fn jump_back_action(&mut self, _: &JumpBack, _window: &mut Window, cx: &mut Context<Self>) {
+    self.jump_back(cx);
+  }
+
+  fn jump_forward_action(&mut self, _: &JumpForward, _window: &mut Window, cx: &mut Context<Self>) {
+    self.jump_forward(cx);
+  }
+
+  /// Records the cursor position before a large jump
+  fn push_jump(&mut self) {
+    let index = self.editor.cursor.index;
+    self.jump_list.push(&mut self.editor, index);
+  }
+
+  /// Moves the cursor to a char index and centers the row of its line
+  fn move_cursor_and_center(&mut self, index: usize, cx: &mut Context<Self>) {
+    let line = self.editor.buffer.char_to_line_col(index).0;
+    self.editor.cursor.move_to(index);
+    self.editor.clear_selection();
+    if let Some(row) = self.current_display_map().line_row(line) {
+      self.scroll_to_row(row);
+    }
+    cx.notify();
+  }
+
+  /// Current diagnostics of the open file, their ranges follow the edits made since they were set
+  pub fn diagnostics(&self) -> Vec<Diagnostic> {
+    match &self.pending_diagnostics {
+      Some(diagnostics) => diagnostics.clone(),
+      None => self.diagnostics.current(&self.editor),
+    }
+  }
+
+  /// Replaces the diagnostics of the open file, like the results of a linter or a CI run
+  pub fn set_diagnostics(&mut self, diagnostics: Vec<Diagnostic>, cx: &mut Context<Self>) {
+    if self.editor_loaded() {
+      self.diagnostics.set(&mut self.editor, diagnostics);
+    } else {
+      self.pending_diagnostics = Some(diagnostics);
+    }
+    self.hovered_diagnostic = None;
+    cx.notify();
+  }
+
+  pub fn clear_diagnostics(&mut self, cx: &mut Context<Self>) {
+    self.set_diagnostics(Vec::new(), cx);
+  }
+
+  /// Current inlay hints of the open file, their indices follow the edits made since they were set
+  pub fn inlay_hints(&self) -> Vec<InlayHint> {
+    match &self.pending_inlay_hints {
+      Some(hints) => hints.clone(),
+      None => self.inlay_hints.current(&self.editor),
+    }
+  }
+
+  /// Replaces the inlay hints of the open file, like parameter names or inferred types
+  pub fn set_inlay_hints(&mut self, hints: Vec<InlayHint>, cx: &mut Context<Self>) {
+    if self.editor_loaded() {
+      self.inlay_hints.set(&mut self.editor, hints);
+    } else {
+      self.pending_inlay_hints = Some(hints);
+    }
+    cx.notify();
+  }
+
+  pub fn clear_inlay_hints(&mut self, cx: &mut Context<Self>) {
+    self.set_inlay_hints(Vec::new(), cx);
+  }
+
+  /// Replaces the inlay hints by the original text of each modified line, shown after the line
+  pub fn show_original_value_hints(&mut self, cx: &mut Context<Self>) {
+    let hints = original_value_hints(&self.compute_diff(), &self.editor);
+    self.set_inlay_hints(hints, cx);
+  }
+
+  /// Tab map of a buffer line, with its inlay hints
+  fn line_tab_map(&self, line: usize, text: &str) -> TabMap {
+    let tab_map = TabMap::new(text, self.config.tab_size);
+    if self.inlay_hints.is_empty() {
+      return tab_map;
+    }
+    let inlays = self.inlay_hints.by_line(&self.editor).remove(&line);
+    tab_map.with_inlays(&inlays.unwrap_or_default())
+  }
+
+  pub fn line_annotations(&self) -> &LineAnnotations {
+    &self.line_annotations
+  }
+
+  pub fn comment_threads(&self) -> &CommentThreads {
+    &self.comment_threads
+  }
+
+  /// Shows a comment thread below its buffer line, pushing the following rows down
+  pub fn add_comment_thread(&mut self, thread: CommentThread, cx: &mut Context<Self>) -> ThreadId {
+    let id = self.comment_threads.add(thread);
+    cx.notify();
+    id
+  }
+
+  /// Replaces the comments of a thread, like when a reply comes in
+  pub fn update_comment_thread(
+    &mut self,
+    id: ThreadId,
+    thread: CommentThread,
+    cx: &mut Context<Self>,
+  ) {
+    if let Some(existing) = self.comment_threads.get_mut(id) {
+      *existing = thread;
+      cx.notify();
+    }
+  }
+
+  pub fn remove_comment_thread(
+    &mut self,
+    id: ThreadId,
+    cx: &mut Context<Self>,
+  ) -> Option<CommentThread> {
+    let thread = self.comment_threads.remove(id);
+    cx.notify();
+    thread
+  }
+
+  pub fn clear_comment_threads(&mut self, cx: &mut Context<Self>) {
+    self.comment_threads.clear();
+    cx.notify();
+  }
+
+  /// Collapses an expanded thread to its header, or expands it back
+  pub fn toggle_comment_thread(&mut self, id: ThreadId, cx: &mut Context<Self>) {
+    self.comment_threads.toggle(id);
+    cx.notify();
+  }
+
+  /// Shows the original lines replaced by buffer line `line` right below it, or hides them
+  pub fn toggle_original_preview(&mut self, line: usize, cx: &mut Context<Self>) {
+    self.original_previews.toggle(line);
+    cx.notify();
+  }
+
+  fn toggle_original_preview_action(
+    &mut self,
+    _: &ToggleOriginalPreview,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    let line = self.cursor_line();
+    if self
+      .compute_diff()
+      .iter()
+      .any(|diff_line| diff_line.line_number == line + 1 && has_original(diff_line))
+    {
+      self.toggle_original_preview(line, cx);
+    }
+  }
+
+  /// Conflicts left in the buffer, found through their markers
+  pub fn conflicts(&self) -> Vec<ConflictMarkers> {
+    find_conflicts(&self.editor.buffer.as_str())
+  }
+
+  /// Replaces the conflict under the cursor by its resolution
+  pub fn resolve_conflict_at_cursor(&mut self, resolution: Resolution, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    let text = self.editor.buffer.as_str();
+    let cursor_line = self.cursor_line();
+    let Some(conflict) = find_conflicts(&text)
+      .into_iter()
+      .find(|conflict| conflict.lines.contains(&cursor_line))
+    else {
+      return;
+    };
+
+    let buffer = &self.editor.buffer;
+    let start = buffer.line_col_to_char(conflict.lines.start, 0);
+    let end = if conflict.lines.end < buffer.line_count() {
+      buffer.line_col_to_char(conflict.lines.end, 0)
+    } else {
+      buffer.len()
+    };
+
+    self.editor.select_range(start, end);
+    self
+      .editor
+      .paste(&conflict.resolution_text(&text, resolution));
+    self.editor.cursor.move_to(start);
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  /// Restores the selected lines, or the cursor line, to the content they're diffed against
+  ///
+  /// Removed lines right above or below come back too, the whole revert is a single undo step
+  pub fn revert_selection(&mut self, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    let lines = self.editor.selected_line_range();
+    let text = self.editor.buffer.as_str();
+    let original = Differ::revert_lines(&self.compute_diff(), &text, lines.clone());
+    let current: String = text
+      .split_inclusive('\n')
+      .skip(lines.start)
+      .take(lines.len())
+      .collect();
+    if original == current {
+      return;
+    }
+
+    self.editor.replace_lines(lines, &original);
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  /// Applies a batch of edits to the buffer as a single undo step, like the changes of a
+  /// formatter or LSP text edits, see `Editor::apply_edits`
+  pub fn apply_edits(&mut self, edits: Vec<Edit>, cx: &mut Context<Self>) -> Result<(), EditError> {
+    if self.is_read_only() || edits.is_empty() {
+      return Ok(());
+    }
+    self.editor.apply_edits(edits)?;
+    self.mark_dirty(cx);
+    cx.notify();
+    Ok(())
+  }
+
+  /// Diff of the selected lines with the removed lines between them, or of the hunk at the
+  /// cursor without selection, in `format`. None when that's not part of a change
+  pub fn export_selection(&self, format: ExportFormat) -> Option<String> {
+    let diff_lines = self.compute_diff();
+    let rows = if self.editor.has_selection() {
+      selected_rows(&diff_lines, self.editor.selected_line_range())?
+    } else {
+      hunk_at_line(&diff_lines, self.cursor_line())?.rows
+    };
+    Some(export(&diff_lines[rows], format))
+  }
+
+  /// Line counts of the diff of the selected lines with the removed lines between them,
+  /// None without selection
+  pub fn selection_diff_stats(&self) -> Option<DiffStats> {
+    if !self.editor.has_selection() {
+      return None;
+    }
+    let diff_lines = self.compute_diff();
+    let rows = selected_rows(&diff_lines, self.editor.selected_line_range())?;
+    Some(Differ::compute_stats(&diff_lines[rows]))
+  }
+
+  /// Reads the content to diff against on the background executor, replacing the current one
+  pub fn load_compare_file(&mut self, path: PathBuf, cx: &mut Context<Self>) {
+    self.loads.retain(|loading| loading.loads_buffer());
+    self.compare_file_path = Some(path.clone());
+    self.start_loading(path, LoadTarget::Baseline, cx);
+  }
+
+  /// Replaces the original baseline and compares against it again
+  pub fn update_compare_content(&mut self, content: String) {
+    self.compare_file_path = None;
+    self.replace_compare_content(content);
+  }
+
+  fn replace_compare_content(&mut self, content: String) {
+    // Removed rows come and go, the lines on screen stay put
+    self.anchor_scroll(None);
+    self.original_content = content.clone();
+    self.compare_bytes = None;
+    self.baseline = Baseline::Original;
+    self.set_baseline_content(content);
+  }
+
+  fn set_baseline_content(&mut self, content: String) {
+    self.compare_content = content;
+    self.sync_differ_original();
+  }
+
+  /// Captures the current buffer as a revision the diff can be switched to
+  pub fn snapshot(&mut self, label: impl Into<String>, cx: &mut Context<Self>) -> RevisionId {
+    let id = self.revisions.add(label, self.editor.buffer.as_str());
+    cx.notify();
+    id
+  }
+
+  /// Diffs against the current buffer from now on, so only the changes made since show up
+  ///
+  /// The buffer is kept as a checkpoint revision, the previous baseline stays available in
+  /// the baseline picker
+  pub fn rebaseline(&mut self, cx: &mut Context<Self>) -> RevisionId {
+    let label = format!("Checkpoint {}", self.revisions.len() + 1);
+    let id = self.snapshot(label, cx);
+    self.compare_with(Baseline::Revision(id), cx);
+    id
+  }
+
+  fn rebaseline_action(&mut self, _: &Rebaseline, _window: &mut Window, cx: &mut Context<Self>) {
+    if !self.is_binary() {
+      self.rebaseline(cx);
+    }
+  }
+
+  pub fn revisions(&self) -> &Revisions {
+    &self.revisions
+  }
+
+  /// Drops a revision, the diff goes back to the original when it was the baseline
+  pub fn remove_revision(&mut self, id: RevisionId, cx: &mut Context<Self>) -> Option<Revision> {
+    let revision = self.revisions.remove(id);
+    if self.baseline == Baseline::Revision(id) {
+      self.compare_with(Baseline::Original, cx);
+    }
+    cx.notify();
+    revision
+  }
+
+  pub fn baseline(&self) -> Baseline {
+    self.baseline
+  }
+
+  /// Diffs against the original or a revision, a custom baseline needs `compare_with_text`
+  pub fn compare_with(&mut self, baseline: Baseline, cx: &mut Context<Self>) {
+    let content = match baseline {
+      Baseline::Original => self.original_content.clone(),
+      Baseline::Revision(id) => match self.revisions.get(id) {
+        Some(revision) => revision.content.clone(),
+        None => return,
+      },
+      Baseline::Custom => return,
+    };
+
+    self.anchor_scroll(None);
+    self.baseline = baseline;
+    self.baseline_menu_open = false;
+    self.set_baseline_content(content);
+    cx.notify();
+  }
+
+  /// Diffs against any text, the original stays available in the baseline picker
+  pub fn compare_with_text(&mut self, text: String, cx: &mut Context<Self>) {
+    self.anchor_scroll(None);
+    self.baseline = Baseline::Custom;
+    self.baseline_menu_open = false;
+    self.set_baseline_content(text);
+    cx.notify();
+  }
+
+  /// Revisions describe the previous file, its diff goes back to the original
+  fn clear_revisions(&mut self) {
+    self.revisions.clear();
+    self.baseline_menu_open = false;
+    if matches!(self.baseline, Baseline::Revision(_)) {
+      self.baseline = Baseline::Original;
+      self.set_baseline_content(self.original_content.clone());
+    }
+  }
+
+  /// Bytes of the baseline when it's binary, only the original baseline can be
+  fn baseline_bytes(&self) -> Option<&[u8]> {
+    self
+      .compare_bytes
+      .as_deref()
+      .filter(|_| self.baseline == Baseline::Original)
+  }
+
+  /// Whether the open file or the baseline is binary, their diff can't be shown as text
+  pub fn is_binary(&self) -> bool {
+    self.file_bytes.is_some() || self.baseline_bytes().is_some()
+  }
+
+  /// Text, binary or image, what the view shows instead of text rows for the last two
+  pub fn file_kind(&self) -> FileKind {
+    if !self.is_binary() {
+      return FileKind::Text;
+    }
+    let is_image = |bytes: &[u8]| ImageKind::detect(bytes).is_some();
+    let images = match (self.baseline_bytes(), self.file_bytes.as_deref()) {
+      (Some(original), Some(current)) => is_image(original) && is_image(current),
+      // An image added or deleted, the text side is empty
+      (None, Some(current)) => is_image(current) && self.compare_content.is_empty(),
+      (Some(original), None) => is_image(original) && self.editor.buffer.len() == 0,
+      (None, None) => false,
+    };
+    if images {
+      FileKind::Image
+    } else {
+      FileKind::Binary
+    }
+  }
+
+  pub fn image_diff_mode(&self) -> ImageDiffMode {
+    self.config.image_diff
+  }
+
+  pub fn set_image_diff_mode(&mut self, mode: ImageDiffMode, cx: &mut Context<Self>) {
+    self.config.image_diff = mode;
+    cx.notify();
+  }
+
+  /// Opacity of the modified image over the original one in the onion skin view, 0 to 1
+  pub fn set_onion_skin_mix(&mut self, mix: f32, cx: &mut Context<Self>) {
+    self.onion_skin_mix = mix.clamp(0.0, 1.0);
+    cx.notify();
+  }
+
+  /// Changes of a JSON or TOML file by value, None for other files
+  ///
+  /// Errs when a side doesn't parse, the view then falls back to the line diff
+  pub fn structured_diff(&self) -> Option<Result<StructuredDiff, StructuredError>> {
+    if self.file_kind() != FileKind::Text {
+      return None;
+    }
+    let format = StructuredFormat::from_path(&self.file_path)?;
+    Some(rediff_core::structured_diff(
+      &self.compare_content,
+      &self.editor.buffer.as_str(),
+      format,
+    ))
+  }
+
+  pub fn shows_structured_diff(&self) -> bool {
+    self.config.structured_diff
+  }
+
+  /// Shows JSON and TOML files by value instead of by line, other files keep the line diff
+  pub fn set_structured_diff(&mut self, enabled: bool, cx: &mut Context<Self>) {
+    self.config.structured_diff = enabled;
+    cx.notify();
+  }
+
+  fn toggle_structured_diff(
+    &mut self,
+    _: &ToggleStructuredDiff,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.set_structured_diff(!self.config.structured_diff, cx);
+  }
+
+  /// Paragraphs of a prose file diffed by word or sentence, None for files of other types
+  pub fn prose_diff(&self) -> Option<Vec<ProseParagraph>> {
+    if self.file_kind() != FileKind::Text {
+      return None;
+    }
+    let granularity = self.config.prose_granularity_for(&self.file_path)?;
+    Some(rediff_core::prose_diff(
+      &self.compare_content,
+      &self.editor.buffer.as_str(),
+      granularity,
+    ))
+  }
+
+  /// Diffs files of the same type as the open one by word or sentence, or by line for None
+  pub fn set_prose_granularity(
+    &mut self,
+    granularity: Option<ProseGranularity>,
+    cx: &mut Context<Self>,
+  ) {
+    let Some(extension) = self
+      .file_path
+      .extension()
+      .and_then(|extension| extension.to_str())
+    else {
+      return;
+    };
+    let extension = extension.to_lowercase();
+    match granularity {
+      Some(granularity) => self.config.prose_diff.insert(extension, granularity),
+      None => self.config.prose_diff.remove(&extension),
+    };
+    cx.notify();
+  }
+
+  fn toggle_prose_diff(
+    &mut self,
+    _: &ToggleProseDiff,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    let granularity = match self.config.prose_granularity_for(&self.file_path) {
+      Some(_) => None,
+      None => Some(ProseGranularity::default()),
+    };
+    self.set_prose_granularity(granularity, cx);
+  }
+
+  fn image_sides(&mut self) -> Rc<ImageSides> {
+    if self.image_sides.is_none() {
+      let sides = ImageSides::new(self.baseline_bytes(), self.file_bytes.as_deref());
+      self.image_sides = Some(Rc::new(sides));
+    }
+    self.image_sides.clone().unwrap_or_default()
+  }
+
+  pub fn binary_view(&self) -> BinaryView {
+    self.config.binary_view
+  }
+
+  /// Switches binary files between the placeholder and the hex view
+  pub fn set_binary_view(&mut self, view: BinaryView, cx: &mut Context<Self>) {
+    self.config.binary_view = view;
+    self.show_binary();
+    self.sync_differ_original();
+    cx.notify();
+  }
+
+  /// Only binary files get the hex view, a text file against a binary baseline shows the placeholder
+  fn shows_hex(&self) -> bool {
+    self.file_bytes.is_some() && self.config.binary_view == BinaryView::Hex
+  }
+
+  /// Fills the editor of a binary file with its hex dump in the hex view, leaves it empty otherwise
+  fn show_binary(&mut self) {
+    let Some(bytes) = &self.file_bytes else {
+      return;
+    };
+    let mut buffer = TextBuffer::new();
+    if self.config.binary_view == BinaryView::Hex {
+      buffer.insert(0, &hex_dump(bytes));
+    }
+    self.editor = Editor::from_buffer(buffer);
+    self.bookmarks = Bookmarks::new();
+    self.jump_list = JumpList::new();
+    self.diagnostics = Diagnostics::new();
+    self.inlay_hints = InlayHints::new();
+    self.line_cache.lock().unwrap().clear();
+  }
+
+  /// Diffs against the hex dump of the baseline in the hex view, against its text otherwise
+  fn sync_differ_original(&mut self) {
+    self.image_sides = None;
+    let original = if self.shows_hex() {
+      hex_dump(
+        self
+          .baseline_bytes()
+          .unwrap_or(self.compare_content.as_bytes()),
+      )
+    } else {
+      self.compare_content.clone()
+    };
+    self.differ.update_original(original);
+    self.differ_version += 1;
+  }
+
+  pub fn diff_options(&self) -> &DiffOptions {
+    &self.config.diff_options
+  }
+
+  /// Changes how lines are compared, the diff is recomputed on the next render
+  pub fn set_diff_options(&mut self, options: DiffOptions, cx: &mut Context<Self>) {
+    self.differ.set_options(options.clone());
+    self.differ_version += 1;
+    self.config.diff_options = options;
+    cx.notify();
+  }
+
+  /// Added, removed and modified line counts of the current diff
+  pub fn diff_stats(&self) -> DiffStats {
+    Differ::compute_stats(&self.compute_diff())
+  }
+
+  /// Emits the line counts when they changed since the last render, a modified
+  /// line is reported as one added and one removed line
+  fn update_diff_stats(&mut self, stats: DiffStats, cx: &mut Context<Self>) {
+    if self.diff_stats != Some(stats) {
+      self.diff_stats = Some(stats);
+      cx.emit(DiffEditorEvent::DiffStatsChanged {
+        added: stats.added + stats.modified,
+        removed: stats.removed + stats.modified,
+      });
+    }
+  }
+
+  fn mark_dirty(&mut self, cx: &mut Context<Self>) {
+    self.set_dirty(true, cx);
+    self.schedule_journal(cx);
+    self.schedule_autosave(cx);
+    cx.emit(DiffEditorEvent::Edited);
+  }
+
+  /// Journals the edits once they pause, the buffer is copied and diffed with the journal then
+  /// rather than on every keystroke
+  fn schedule_journal(&mut self, cx: &mut Context<Self>) {
+    if self.config.journal_dir.is_none() || !self.file_backed {
+      return;
+    }
+    let Some(epoch) = self.journal_timer.edited(Autosave::AfterDelay) else {
+      return;
+    };
+
+    let delay = self.config.autosave_delay;
+    cx.spawn(async move |this, cx| {
+      cx.background_executor().timer(delay).await;
+      this
+        .update(cx, |this, _cx| {
+          if this.journal_timer.fire(epoch) && this.is_dirty {
+            this.record_journal();
+          }
+        })
+        .ok();
+    })
+    .detach();
+  }
+
+  /// Journals the edits still waiting on the timer, before the buffer is put aside
+  fn flush_journal(&mut self) {
+    if self.journal_timer.is_scheduled() {
+      self.journal_timer.cancel();
+      if self.is_dirty {
+        self.record_journal();
+      }
+    }
+  }
+
+  /// Appends the edits since the last entry to the journal, started on the first unsaved edit
+  fn record_journal(&mut self) {
+    let Some(dir) = self
+      .config
+      .journal_dir
+      .as_deref()
+      .filter(|_| self.file_backed)
+    else {
+      return;
+    };
+
+    let content = self.editor.buffer.as_str();
+    let result = match &mut self.journal {
+      Some(journal) => journal.record(&content),
+      None => Journal::start(dir, &self.file_path, &content).map(|journal| {
+        self.journal = Some(journal);
+      }),
+    };
+    if let Err(e) = result {
+      eprintln!("Failed to journal edits of {:?}: {}", self.file_path, e);
+    }
+  }
+
+  /// Offers the edits journaled by a previous run when the journal is newer than the file
+  fn check_journal(&mut self) {
+    self.recovered = None;
+    let Some(dir) = self.config.journal_dir.as_deref() else {
+      return;
+    };
+    if !Journal::is_newer(dir, &self.file_path) {
+      return;
+    }
+
+    match Journal::recover(dir, &self.file_path) {
+      Ok(recovered) => {
+        let content = self.editor.buffer.as_str();
+        self.recovered = recovered.filter(|recovered| *recovered != content);
+      }
+      Err(e) => eprintln!("Failed to read the journal of {:?}: {}", self.file_path, e),
+    }
+  }
+
+  /// Whether unsaved edits of a previous run were found for the open file
+  pub fn has_recovered_edits(&self) -> bool {
+    self.recovered.is_some()
+  }
+
+  /// Puts the edits of a previous run back in the buffer, as an edit that can be undone
+  pub fn restore_recovered_edits(&mut self, cx: &mut Context<Self>) {
+    let Some(content) = self.recovered.take() else {
+      return;
+    };
+    let line_count = self.editor.buffer.line_count();
+    self.editor.replace_lines(0..line_count, &content);
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  /// Forgets the edits of a previous run
+  pub fn discard_recovered_edits(&mut self, cx: &mut Context<Self>) {
+    if self.recovered.take().is_none() {
+      return;
+    }
+    // A journal started since holds the edits made in this run
+    if let (Some(dir), None) = (&self.config.journal_dir, &self.journal) {
+      Journal::remove(dir, &self.file_path).ok();
+    }
+    cx.notify();
+  }
+
+  fn schedule_autosave(&mut self, cx: &mut Context<Self>) {
+    if !self.file_backed || self.is_read_only() {
+      return;
+    }
+    let Some(epoch) = self.autosave_timer.edited(self.config.autosave) else {
+      return;
+    };
+
+    let delay = self.config.autosave_delay;
+    cx.spawn(async move |this, cx| {
+      cx.background_executor().timer(delay).await;
+      this
+        .update(cx, |this, cx| {
+          if this.autosave_timer.fire(epoch) && this.is_dirty {
+            this.save(cx);
+          }
+        })
+        .ok();
+    })
+    .detach();
+  }
+
+  fn set_dirty(&mut self, is_dirty: bool, cx: &mut Context<Self>) {
+    if self.is_dirty != is_dirty {
+      self.is_dirty = is_dirty;
+      cx.emit(DiffEditorEvent::DirtyChanged(is_dirty));
+    }
+  }
+
+  fn reload_file(&mut self, cx: &mut Context<Self>) {
+    self.start_loading(self.file_path.clone(), LoadTarget::Reload, cx);
+  }
+
+  /// Keeps the diff up to date as the open file or the file it's compared with change on
+  /// disk, like generated output drifting from a golden file
+  ///
+  /// Reads are debounced until the files stop changing, unsaved edits are kept over the disk
+  pub fn watch(&mut self, cx: &mut Context<Self>) {
+    let file_path = self.file_backed.then(|| self.file_path.clone());
+    let paths = file_path.into_iter().chain(self.compare_file_path.clone());
+    let watcher = FileWatcher::new(paths, WATCH_DEBOUNCE, Instant::now());
+    self.watch_label = watcher.updated_label(Instant::now());
+    self.watcher = Some(watcher);
+    self.watch_epoch += 1;
+    self.schedule_watch_poll(self.watch_epoch, cx);
+    cx.notify();
+  }
+
+  pub fn unwatch(&mut self, cx: &mut Context<Self>) {
+    if self.watcher.take().is_some() {
+      self.watch_epoch += 1;
+      cx.notify();
+    }
+  }
+
+  pub fn is_watching(&self) -> bool {
+    self.watcher.is_some()
+  }
+
+  /// Starts the language server configured for the extension of the open file, its
+  /// diagnostics and hover info then show up in the editor
+  ///
+  /// The server is stopped when switching to a file it isn't configured for
+  pub fn start_language_server(&mut self, cx: &mut Context<Self>) -> io::Result<()> {
+    let Some(config) = self.config.language_server_for(&self.file_path).cloned() else {
+      return Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("no language server for {}", self.file_path.display()),
+      ));
+    };
+    let root = match self.file_path.parent() {
+      Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+      _ => std::env::current_dir()?,
+    };
+
+    self.stop_language_server(cx);
+    self.language_server = Some(LanguageServer::start(&config, &root)?);
+    self.lsp_epoch += 1;
+    self.schedule_lsp_poll(self.lsp_epoch, cx);
+    Ok(())
+  }
+
+  /// Stops the language server, its diagnostics are cleared
+  pub fn stop_language_server(&mut self, cx: &mut Context<Self>) {
+    if self.language_server.take().is_none() {
+      return;
+    }
+    self.lsp_epoch += 1;
+    self.lsp_synced = None;
+    self.lsp_hover = None;
+    self.clear_diagnostics(cx);
+  }
+
+  /// Changes made to the buffer of the open file, when `edit_log` is enabled
+  pub fn edit_log(&self) -> Option<&EditLog> {
+    self.editor.edit_log()
+  }
+
+  /// Writes the edit log of the open file to `path` as JSON Lines, empty when it's disabled
+  pub fn export_edit_log(&self, path: &Path) -> io::Result<()> {
+    let exported = self
+      .editor
+      .edit_log()
+      .map(export_edit_log)
+      .unwrap_or_default();
+    std::fs::write(path, exported)
+  }
+
+  /// Hits, misses and evictions of the shaped line cache, to tell whether `line_cache_size`
+  /// fits the files being viewed
+  pub fn line_cache_stats(&self) -> LineCacheStats {
+    self.line_cache.lock().unwrap().stats()
+  }
+
+  /// Counters of the last frame painted while `render_profiling` is on
+  pub fn frame_stats(&self) -> Option<FrameStats> {
+    self.profiler.borrow().last_frame()
+  }
+
+  pub fn shows_render_profiling(&self) -> bool {
+    self.config.render_profiling
+  }
+
+  /// Shows how long frames take and what they spend it on, over the editor
+  pub fn set_render_profiling(&mut self, enabled: bool, cx: &mut Context<Self>) {
+    self.config.render_profiling = enabled;
+    cx.notify();
+  }
+
+  fn toggle_render_profiling(
+    &mut self,
+    _: &ToggleRenderProfiling,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.set_render_profiling(!self.config.render_profiling, cx);
+  }
+
+  pub fn has_language_server(&self) -> bool {
+    self.language_server.is_some()
+  }
+
+  fn schedule_lsp_poll(&self, epoch: usize, cx: &mut Context<Self>) {
+    cx.spawn(async move |this, cx| {
+      cx.background_executor().timer(LSP_POLL_INTERVAL).await;
+      this
+        .update(cx, |this, cx| {
+          if this.lsp_epoch == epoch {
+            this.poll_language_server(cx);
+            this.schedule_lsp_poll(epoch, cx);
+          }
+        })
+        .ok();
+    })
+    .detach();
+  }
+
+  fn poll_language_server(&mut self, cx: &mut Context<Self>) {
+    let Some(server) = &self.language_server else {
+      return;
+    };
+    if self.config.language_server_for(&self.file_path) != Some(server.config()) {
+      self.stop_language_server(cx);
+      return;
+    }
+
+    let events = self.sync_language_server().and_then(|_| {
+      let server = self.language_server.as_mut().unwrap();
+      server.poll()
+    });
+    let events = match events {
+      Ok(events) => events,
+      Err(e) => {
+        if let Some(server) = &self.language_server {
+          eprintln!("Language server {} stopped: {}", server.config().command, e);
+        }
+        self.stop_language_server(cx);
+        return;
+      }
+    };
+
+    for event in events {
+      match event {
+        LspEvent::Diagnostics { uri, items } => {
+          if uri == file_uri(&self.file_path) {
+            let text = self.editor.buffer.as_str();
+            self.set_diagnostics(diagnostics_from_lsp(&items, &text), cx);
+          }
+        }
+        LspEvent::Hover { id, text } => {
+          if let Some(hover) = self
+            .lsp_hover
+            .as_mut()
+            .filter(|hover| hover.request == Some(id))
+          {
+            hover.text = text;
+            cx.notify();
+          }
+        }
+      }
+    }
+  }
+
+  /// Opens the file in the language server, or sends what changed since the last sync
+  fn sync_language_server(&mut self) -> io::Result<()> {
+    if !self.file_backed || !self.editor_loaded() {
+      return Ok(());
+    }
+    let Some(server) = self.language_server.as_mut() else {
+      return Ok(());
+    };
+
+    match &mut self.lsp_synced {
+      Some((path, text)) if *path == self.file_path => {
+        if !self.lsp_edited {
+          return Ok(());
+        }
+        let new_text = self.editor.buffer.as_str();
+        server.did_change(path, text, &new_text)?;
+        *text = new_text;
+      }
+      synced => {
+        if let Some((path, _)) = synced.take() {
+          server.did_close(&path)?;
+        }
+        let text = self.editor.buffer.as_str();
+        server.did_open(&self.file_path, &text)?;
+        *synced = Some((self.file_path.clone(), text));
+      }
+    }
+    self.lsp_edited = false;
+    Ok(())
+  }
+
+  fn schedule_watch_poll(&self, epoch: usize, cx: &mut Context<Self>) {
+    cx.spawn(async move |this, cx| {
+      cx.background_executor().timer(WATCH_POLL_INTERVAL).await;
+      this
+        .update(cx, |this, cx| {
+          if this.watch_epoch == epoch {
+            this.poll_watched_files(cx);
+            this.schedule_watch_poll(epoch, cx);
+          }
+        })
+        .ok();
+    })
+    .detach();
+  }
+
+  fn poll_watched_files(&mut self, cx: &mut Context<Self>) {
+    let Some(watcher) = self.watcher.as_mut() else {
+      return;
+    };
+    let now = Instant::now();
+    let changed = watcher.poll(now);
+    let label = watcher.updated_label(now);
+
+    // Files switched since watching started aren't the ones on screen anymore
+    for path in changed {
+      if self.file_backed && path == self.file_path {
+        self.reload_file(cx);
+      } else if self.compare_file_path.as_ref() == Some(&path) {
+        self.load_compare_file(path, cx);
+      }
+    }
+    if label != self.watch_label {
+      self.watch_label = label;
+      cx.notify();
+    }
+  }
+
+  /// Swaps in the content read from disk, the cursor, selection and scroll position follow
+  /// the lines they were on through the diff between the old and the new content
+  fn replace_buffer(&mut self, buffer: TextBuffer, cx: &mut Context<Self>) {
+    let (old_text, new_text) = (self.editor.buffer.as_str(), buffer.as_str());
+    if old_text != new_text {
+      self.line_cache.lock().unwrap().clear();
+      let line_map = LineMap::new(&old_text, &new_text);
+      self.anchor_scroll(Some(&line_map));
+
+      let old = &self.editor.buffer.clone();
+      let cursor_index = remap_index(&line_map, old, &buffer, self.editor.cursor.index);
+      self.editor.selection = self
+        .editor
+        .selection
+        .map(|selection| Selection {
+          start: remap_index(&line_map, old, &buffer, selection.start),
+          end: remap_index(&line_map, old, &buffer, selection.end),
+          reversed: selection.reversed,
+        })
+        .filter(|selection| !selection.is_empty());
+      self
+        .editor
+        .anchors_mut()
+        .remap(|index| remap_index(&line_map, old, &buffer, index));
+      self.editor.cursor.index = cursor_index;
+      // The scroll anchor already keeps the view, don't reveal the cursor
+      self.autoscroll_cursor_index = Some(cursor_index);
+    }
+    self.editor.buffer = buffer;
+    self.editor.take_line_edits();
+    self.set_dirty(false, cx);
+  }
+
+  /// Remembers the first buffer line on screen and its height in the pane, mapped through
+  /// `line_map` when the buffer is about to change, to scroll back to it once rows are rebuilt
+  fn anchor_scroll(&mut self, line_map: Option<&LineMap>) {
+    let viewport = self.viewport();
+    let anchor = self
+      .display_map
+      .first_line_in(viewport.visible_rows())
+      .map(|(row, line)| {
+        let line = line_map.map_or(line, |line_map| line_map.map_line(line).0);
+        (
+          line,
+          viewport.line_height * row as f32 - viewport.scroll_top,
+        )
+      });
+    if anchor.is_some() {
+      self.scroll_anchor = anchor;
+    }
+  }
+
+  /// Centers a display row, smoothly when enabled
+  fn scroll_to_row(&mut self, row: usize) {
+    let viewport = self.viewport();
+    let Some(duration) = self
+      .config
+      .smooth_scroll
+      .filter(|_| viewport.bounds.size.height > px(0.0))
+    else {
+      self.scroll_animation = None;
+      self
+        .scroll_handle
+        .scroll_to_item(row, ScrollStrategy::Center);
+      return;
+    };
+
+    let base_handle = self.scroll_handle.0.borrow().base_handle.clone();
+    let max_scroll_top = f32::from(base_handle.max_offset().height).max(0.0);
+    let line_height = f32::from(viewport.line_height);
+    let target =
+      line_height * row as f32 - (f32::from(viewport.bounds.size.height) - line_height) / 2.0;
+    self.scroll_animation = Some(ScrollAnimation::new(
+      f32::from(viewport.scroll_top),
+      target.clamp(0.0, max_scroll_top),
+      Instant::now(),
+      duration,
+      self.config.scroll_easing,
+    ));
+  }
+
+  /// Moves a smooth scroll on to where it should be by now, until it's done
+  fn step_scroll_animation(&mut self, window: &mut Window) {
+    let Some(animation) = &mut self.scroll_animation else {
+      return;
+    };
+    let base_handle = self.scroll_handle.0.borrow().base_handle.clone();
+    let offset = base_handle.offset();
+    // The scrollbar or the minimap was dragged meanwhile
+    if animation.is_interrupted(-f32::from(offset.y)) {
+      self.scroll_animation = None;
+      return;
+    }
+
+    let now = Instant::now();
+    let scroll_top = animation.step(now);
+    base_handle.set_offset(point(offset.x, px(-scroll_top)));
+    if animation.is_done(now) {
+      self.scroll_animation = None;
+    } else {
+      window.request_animation_frame();
+    }
+  }
+
+  fn resolve_scroll_anchor(&mut self, display_map: &DisplayMap) {
+    let Some((line, screen_y)) = self.scroll_anchor.take() else {
+      return;
+    };
+    let Some(row) = display_map.line_row(line) else {
+      return;
+    };
+
+    let line_height = px(self.config.line_height());
+    let scroll_top = (line_height * row as f32 - screen_y).max(px(0.0));
+    let base_handle = self.scroll_handle.0.borrow().base_handle.clone();
+    let offset = base_handle.offset();
+    base_handle.set_offset(point(offset.x, -scroll_top));
+  }
+
+  /// Whether the file or the baseline is still being read, the diff shows once both are loaded
+  pub fn is_loading(&self) -> bool {
+    self.loads.iter().any(Loading::blocks_view)
+  }
+
+  /// Whether no file is open, like once the last tab is closed
+  pub fn is_empty(&self) -> bool {
+    !self.file_backed && self.file_path.as_os_str().is_empty()
+  }
+
+  /// Last file that couldn't be read, until the load is retried or dismissed
+  pub fn load_error(&self) -> Option<&LoadError> {
+    self.failed_load.as_ref().map(|(error, _)| error)
+  }
+
+  /// Called every time a file can't be read, the error is shown in the editor too
+  pub fn on_load_error(&mut self, callback: impl Fn(&LoadError, &mut App) + 'static) {
+    self.on_load_error = Some(Rc::new(callback));
+  }
+
+  /// Reads the file that couldn't be read again
+  pub fn retry_load(&mut self, cx: &mut Context<Self>) {
+    if let Some((error, target)) = self.failed_load.take() {
+      self.start_loading(error.path, target, cx);
+      cx.notify();
+    }
+  }
+
+  pub fn dismiss_load_error(&mut self, cx: &mut Context<Self>) {
+    self.failed_load = None;
+    cx.notify();
+  }
+
+  /// Forgets a failed load of the active file, another file is open now
+  fn clear_buffer_load_error(&mut self) {
+    if self
+      .failed_load
+      .as_ref()
+      .is_some_and(|(_, target)| target.loads_buffer())
+    {
+      self.failed_load = None;
+    }
+  }
+
+  /// False while the buffer of the active file is still being read
+  fn editor_loaded(&self) -> bool {
+    !self.loads.iter().any(Loading::loads_buffer)
+  }
+
+  /// Reads `path` on the background executor, `target` gets the content once done
+  fn start_loading(&mut self, path: PathBuf, target: LoadTarget, cx: &mut Context<Self>) {
+    if self
+      .failed_load
+      .as_ref()
+      .is_some_and(|(_, failed)| failed.loads_buffer() == target.loads_buffer())
+    {
+      self.failed_load = None;
+    }
+    let id = self.next_load_id;
+    self.next_load_id += 1;
+    let progress = Arc::new(Mutex::new(LoadProgress::default()));
+    self.loads.push(Loading {
+      id,
+      path: path.clone(),
+      target,
+      progress: progress.clone(),
+    });
+
+    let task = cx.background_executor().spawn(async move {
+      // Taken before reading so that a write during the load is caught by the next check
+      let modified = modified_time(&path);
+      let result = FileContent::load(&path, |loaded| *progress.lock().unwrap() = loaded);
+      (result, modified)
+    });
+    cx.spawn(async move |this, cx| {
+      let (result, modified) = task.await;
+      this
+        .update(cx, |this, cx| this.finish_loading(id, result, modified, cx))
+        .ok();
+    })
+    .detach();
+
+    self.schedule_load_refresh(id, cx);
+  }
+
+  /// Repaints the loading progress until the load is done
+  fn schedule_load_refresh(&self, id: usize, cx: &mut Context<Self>) {
+    cx.spawn(async move |this, cx| {
+      cx.background_executor().timer(LOAD_REFRESH_INTERVAL).await;
+      this
+        .update(cx, |this, cx| {
+          if this.loads.iter().any(|loading| loading.id == id) {
+            this.schedule_load_refresh(id, cx);
+            cx.notify();
+          }
+        })
+        .ok();
+    })
+    .detach();
+  }
+
+  fn finish_loading(
+    &mut self,
+    id: usize,
+    result: io::Result<FileContent>,
+    modified: Option<SystemTime>,
+    cx: &mut Context<Self>,
+  ) {
+    // Gone when another file was opened in the meantime
+    let Some(ix) = self.loads.iter().position(|loading| loading.id == id) else {
+      return;
+    };
+    let loading = self.loads.remove(ix);
+    // Edits made while reading win, the disk content they'd overwrite wasn't seen
+    let keeps_edits = matches!(loading.target, LoadTarget::Reload) && self.is_dirty;
+    if loading.loads_buffer() && !keeps_edits {
+      self.disk_modified = modified;
+      self.disk_hash = match &result {
+        Ok(FileContent::Text(buffer)) => Some(content_hash(&buffer.as_str())),
+        _ => None,
+      };
+    }
+
+    let content = match result {
+      Ok(content) => content,
+      Err(e) => {
+        let error = LoadError {
+          path: loading.path,
+          message: e.to_string(),
+          baseline: !loading.target.loads_buffer(),
+        };
+        if let Some(on_load_error) = self.on_load_error.clone() {
+          on_load_error(&error, cx);
+        }
+        self.failed_load = Some((error, loading.target));
+        cx.notify();
+        return;
+      }
+    };
+
+    match (loading.target, content) {
+      (LoadTarget::Open(state), content) => {
+        match content {
+          FileContent::Text(buffer) => {
+            self.file_bytes = None;
+            self.editor = Editor::from_buffer(buffer);
+            configure_editor(&mut self.editor, &self.config, &self.file_path);
+            self.bookmarks = Bookmarks::new();
+            self.jump_list = JumpList::new();
+            if let Some(lines) = self.pending_bookmarks.take() {
+              self.bookmarks.set_lines(&mut self.editor, lines);
+            }
+            self.diagnostics = Diagnostics::new();
+            if let Some(diagnostics) = self.pending_diagnostics.take() {
+              self.diagnostics.set(&mut self.editor, diagnostics);
+            }
+            self.inlay_hints = InlayHints::new();
+            if let Some(hints) = self.pending_inlay_hints.take() {
+              self.inlay_hints.set(&mut self.editor, hints);
+            }
+            self.line_cache.lock().unwrap().clear();
+            self.check_journal();
+            self.load_review();
+          }
+          FileContent::Binary(bytes) => {
+            self.file_bytes = Some(bytes);
+            self.show_binary();
+          }
+        }
+        self.sync_differ_original();
+        self.restore_file_state(state);
+      }
+      // Edits made while reading win over the disk content
+      (LoadTarget::Reload, _) if self.is_dirty => {}
+      (LoadTarget::Reload, FileContent::Text(buffer)) => {
+        if self.file_bytes.take().is_some() {
+          self.editor = Editor::new();
+          configure_editor(&mut self.editor, &self.config, &self.file_path);
+          self.bookmarks = Bookmarks::new();
+          self.jump_list = JumpList::new();
+          self.diagnostics = Diagnostics::new();
+          self.inlay_hints = InlayHints::new();
+          self.sync_differ_original();
+        }
+        self.replace_buffer(buffer, cx);
+      }
+      (LoadTarget::Reload, FileContent::Binary(bytes)) => {
+        self.file_bytes = Some(bytes);
+        self.show_binary();
+        self.sync_differ_original();
+      }
+      (LoadTarget::Baseline, FileContent::Text(buffer)) => {
+        self.replace_compare_content(buffer.as_str())
+      }
+      (LoadTarget::Baseline, FileContent::Binary(bytes)) => {
+        self.original_content = String::new();
+        self.compare_bytes = Some(bytes);
+        self.baseline = Baseline::Original;
+        self.set_baseline_content(String::new());
+      }
+    }
+    cx.notify();
+  }
+
+  fn calculate_index_from_position(&self, mouse_pos: Point<Pixels>, window: &mut Window) -> usize {
+    let viewport = self.viewport();
+    let row = viewport.row_at(mouse_pos);
+    let display_map = self.current_display_map();
+    let buffer = &self.editor.buffer;
+
+    if row >= display_map.len() {
+      return buffer.len();
+    }
+
+    // Rows without buffer text, like removed lines, land between the lines around them so that
+    // drag selections go past them
+    let buffer_line_idx = match display_map.row_position(row) {
+      RowPosition::Line(line) => line,
+      RowPosition::Before(line) => return buffer.line_col_to_char(line, 0),
+    };
+
+    if buffer_line_idx >= buffer.line_count() {
+      return buffer.len();
+    }
+
+    let text = buffer
+      .line(buffer_line_idx)
+      .unwrap_or_default()
+      .trim_end_matches('\n')
+      .to_string();
+
+    let tab_map = self.line_tab_map(buffer_line_idx, &text);
+    let shaped_line = self.shape_text(tab_map.expand(&text), window);
+    let col = tab_map.closest_index_for_x(&shaped_line, viewport.text_x(mouse_pos.x));
+
+    buffer.line_col_to_char(buffer_line_idx, col.min(text.chars().count()))
+  }
+
+  /// Keeps the cursor visible while typing or moving it, blinks it otherwise while focused
+  fn update_cursor_blink(&mut self, is_focused: bool, edited: bool, cx: &mut Context<Self>) {
+    if !is_focused {
+      if self.blink_cursor_index.take().is_some() {
+        self.cursor_blink.stop();
+      }
+      return;
+    }
+
+    let cursor_index = self.editor.cursor.index;
+    if edited || self.blink_cursor_index != Some(cursor_index) {
+      self.blink_cursor_index = Some(cursor_index);
+      let epoch = self.cursor_blink.pause();
+      self.schedule_cursor_blink(epoch, cx);
+    }
+  }
+
+  fn schedule_cursor_blink(&self, epoch: usize, cx: &mut Context<Self>) {
+    let Some(interval) = self.cursor_blink.interval() else {
+      return;
+    };
+
+    cx.spawn(async move |this, cx| {
+      cx.background_executor().timer(interval).await;
+      this
+        .update(cx, |this, cx| {
+          if this.cursor_blink.blink(epoch) {
+            this.schedule_cursor_blink(epoch, cx);
+            cx.notify();
+          }
+        })
+        .ok();
+    })
+    .detach();
+  }
+
+  /// Changes the cursor style and blink interval, None keeps the cursor steady
+  pub fn set_cursor_style(
+    &mut self,
+    style: CursorStyle,
+    blink_interval: Option<Duration>,
+    cx: &mut Context<Self>,
+  ) {
+    self.config.cursor_style = style;
+    self.config.cursor_blink_interval = blink_interval;
+    self.cursor_blink.set_interval(blink_interval);
+    self.blink_cursor_index = None; // Restarts blinking on the next render
+    cx.notify();
+  }
+
+  fn shape_text(&self, text: String, window: &mut Window) -> ShapedLine {
+    let font_size = px(self.config.font_size);
+    let text_run = TextRun {
+      len: text.len(),
+      font: self.font.clone(),
+      color: black(),
+      background_color: None,
+      underline: None,
+      strikethrough: None,
+    };
+
+    window
+      .text_system()
+      .shape_line(text.into(), font_size, &[text_run], None)
+  }
+
+  /// Blank rows after the last line, so it can be scrolled up into the viewport
+  fn overscroll_rows(&self) -> usize {
+    let viewport_height = self
+      .scroll_handle
+      .0
+      .borrow()
+      .base_handle
+      .bounds()
+      .size
+      .height;
+    overscroll_rows(
+      f32::from(viewport_height),
+      self.config.line_height(),
+      self.config.overscroll,
+    )
+  }
+
+  /// Geometry of the editor pane from its last layout, to map window positions to rows
+  pub fn viewport(&self) -> Viewport {
+    Viewport::from_scroll_handle(
+      &self.scroll_handle,
+      self.gutter_width,
+      self.scroll_x,
+      px(self.config.line_height()),
+      px(EDITOR_PADDING),
+    )
+  }
+
+  /// Columns of the longest line of the buffer and the baseline once tabs are expanded, measured
+  /// again only after an edit or when the baseline or the tab size changed
+  fn longest_line_columns(&self) -> usize {
+    let key = (
+      self.editor.buffer.version(),
+      self.differ_version,
+      self.config.tab_size,
+    );
+    if let Some((cached_key, columns)) = self.longest_line.get()
+      && cached_key == key
+    {
+      return columns;
+    }
+
+    let columns = self
+      .editor
+      .buffer
+      .as_str()
+      .lines()
+      .chain(self.compare_content.lines())
+      .map(|line| TabMap::new(line, self.config.tab_size).columns())
+      .max()
+      .unwrap_or(0);
+    self.longest_line.set(Some((key, columns)));
+    columns
+  }
+
+  fn max_scroll_x(&self, window: &mut Window) -> Pixels {
+    let longest_line = self.longest_line_columns();
+    let char_width = self.shape_text("m".to_string(), window).width;
+    let content_width = char_width * longest_line as f32 + px(HORIZONTAL_SCROLL_MARGIN);
+    (content_width - self.viewport().text_width()).max(px(0.0))
+  }
+
+  fn set_scroll_x(&mut self, scroll_x: Pixels, window: &mut Window) {
+    let max_scroll_x = self.max_scroll_x(window);
+    self.scroll_x = scroll_x.max(px(0.0)).min(max_scroll_x);
+  }
+
+  fn on_scroll_wheel(
+    &mut self,
+    event: &ScrollWheelEvent,
+    window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    // Trackpads report pixel deltas, kept as is for precise scrolling, wheels report lines
+    let delta =
+      event.delta.pixel_delta(px(self.config.line_height())) * self.config.scroll_sensitivity;
+
+    // Platforms usually turn Shift+wheel into a horizontal delta already
+    let (delta_x, delta_y) = if event.modifiers.shift && delta.x == px(0.0) {
+      (delta.y, px(0.0))
+    } else if delta.x.abs() > delta.y.abs() {
+      (delta.x, px(0.0)) // Scroll along the main axis of the gesture only
+    } else {
+      (px(0.0), delta.y)
+    };
+
+    if delta_x != px(0.0) {
+      self.set_scroll_x(self.scroll_x - delta_x, window);
+    }
+    if delta_y != px(0.0) {
+      self.scroll_animation = None;
+      let scroll_state = self.scroll_handle.0.borrow();
+      let handle = &scroll_state.base_handle;
+      let offset = handle.offset();
+      let offset_y = scrolled_offset_y(
+        f32::from(offset.y),
+        f32::from(delta_y),
+        f32::from(handle.max_offset().height),
+      );
+      handle.set_offset(point(offset.x, px(offset_y)));
+    }
+
+    cx.stop_propagation();
+    cx.notify();
+  }
+
+  /// Scrolls the cursor into view after it moved, restored view states keep their scroll
+  fn autoscroll(&mut self, display_map: &DisplayMap, window: &mut Window) {
+    let cursor_index = self.editor.cursor.index;
+    if self.autoscroll_cursor_index == Some(cursor_index) {
+      return;
+    }
+
+    let viewport = self.viewport();
+    if viewport.text_width() <= px(0.0) {
+      return;
+    }
+    self.autoscroll_cursor_index = Some(cursor_index);
+
+    let (line, col) = self.editor.buffer.char_to_line_col(cursor_index);
+    let cursor_row = display_map.line_row(line);
+    // A smooth scroll is already on its way to the cursor
+    if let Some(scroll_top) = cursor_row
+      .filter(|_| self.scroll_animation.is_none())
+      .and_then(|row| viewport.scroll_top_to_reveal(row))
+    {
+      let base_handle = self.scroll_handle.0.borrow().base_handle.clone();
+      let offset = base_handle.offset();
+      base_handle.set_offset(point(offset.x, -scroll_top));
+    }
+
+    let cursor_x = self.column_x(line, col, window);
+    let scroll_x = scroll_x_to_reveal(
+      f32::from(cursor_x),
+      f32::from(self.scroll_x),
+      f32::from(viewport.text_width()),
+      HORIZONTAL_SCROLL_MARGIN,
+    );
+    self.set_scroll_x(px(scroll_x), window);
+  }
+
+  /// Offset of a column of a buffer line from the start of the line text
+  fn column_x(&self, line: usize, col: usize, window: &mut Window) -> Pixels {
+    let text = self
+      .editor
+      .buffer
+      .line(line)
+      .unwrap_or_default()
+      .trim_end_matches('\n')
+      .to_string();
+    let tab_map = self.line_tab_map(line, &text);
+    let shaped_line = self.shape_text(tab_map.expand(&text), window);
+    tab_map.x_for_index(&shaped_line, col)
+  }
+
+  fn go_to_next_hunk(&mut self, _: &GoToNextHunk, _window: &mut Window, cx: &mut Context<Self>) {
+    let hunks = Differ::compute_hunks(&self.compute_diff());
+    let current_line = self.cursor_line();
+    if let Some(hunk) = Differ::next_hunk(&hunks, current_line).cloned() {
+      self.go_to_hunk(&hunk, cx);
+    }
+  }
+
+  fn go_to_previous_hunk(
+    &mut self,
+    _: &GoToPreviousHunk,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    let hunks = Differ::compute_hunks(&self.compute_diff());
+    let current_line = self.cursor_line();
+    if let Some(hunk) = Differ::prev_hunk(&hunks, current_line).cloned() {
+      self.go_to_hunk(&hunk, cx);
+    }
+  }
+
+  /// Lists a command in the command palette, its action is dispatched to the editor when it's run
+  ///
+  /// Actions the editor doesn't handle bubble up to the host's handlers
+  pub fn add_command(&mut self, command: Command) {
+    self.commands.push(command);
+  }
+
+  /// Editor actions registered with gpui, then the commands added by the host
+  fn palette_commands(&self, cx: &App) -> Vec<Command> {
+    let palette_actions = [
+      ToggleCommandPalette.name(),
+      SelectNextCommand.name(),
+      SelectPreviousCommand.name(),
+      ConfirmCommand.name(),
+      DismissCommandPalette.name(),
+    ];
+    let mut commands: Vec<Command> = cx
+      .all_action_names()
+      .iter()
+      .filter(|name| name.starts_with("rediff::") && !palette_actions.contains(*name))
+      .filter_map(|name| cx.build_action(name, None).ok())
+      .map(Command::from_action)
+      .collect();
+    commands.extend(self.commands.iter().cloned());
+    commands
+  }
+
+  fn toggle_command_palette(
+    &mut self,
+    _: &ToggleCommandPalette,
+    window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    let palette = CommandPalette::new(
+      self.palette_commands(cx),
+      &self.config.keymap,
+      cx.focus_handle(),
+    );
+    window.focus(&palette.focus_handle);
+    self.command_palette = Some(palette);
+    cx.notify();
+  }
+
+  fn dismiss_command_palette(
+    &mut self,
+    _: &DismissCommandPalette,
+    window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.command_palette = None;
+    window.focus(&self.focus_handle);
+    cx.notify();
+  }
+
+  fn select_next_command(
+    &mut self,
+    _: &SelectNextCommand,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    if let Some(palette) = &mut self.command_palette {
+      palette.select_next();
+      cx.notify();
+    }
+  }
+
+  fn select_previous_command(
+    &mut self,
+    _: &SelectPreviousCommand,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    if let Some(palette) = &mut self.command_palette {
+      palette.select_previous();
+      cx.notify();
+    }
+  }
+
+  fn confirm_command(&mut self, _: &ConfirmCommand, window: &mut Window, cx: &mut Context<Self>) {
+    let Some(palette) = self.command_palette.take() else {
+      return;
+    };
+    window.focus(&self.focus_handle);
+    if let Some(command) = palette.selected_command() {
+      window.dispatch_action(command.action.boxed_clone(), cx);
+    }
+    cx.notify();
+  }
+
+  fn run_command(&mut self, command: Command, window: &mut Window, cx: &mut Context<Self>) {
+    self.command_palette = None;
+    window.focus(&self.focus_handle);
+    window.dispatch_action(command.action, cx);
+    cx.notify();
+  }
+
+  fn command_palette_backspace(
+    &mut self,
+    _: &Backspace,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    if let Some(palette) = &mut self.command_palette {
+      let mut query = palette.query.clone();
+      query.pop();
+      palette.set_query(query);
+      cx.notify();
+    }
+  }
+
+  /// Typed chars go to the query, they don't reach the buffer
+  fn command_palette_key_down(
+    &mut self,
+    event: &KeyDownEvent,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    cx.stop_propagation();
+    let modifiers = &event.keystroke.modifiers;
+    if self.config.keymap.platform().is_shortcut(modifiers) {
+      return;
+    }
+    let Some(palette) = &mut self.command_palette else {
+      return;
+    };
+    let text = match event.keystroke.key.as_str() {
+      "space" => " ".to_string(),
+      key if key.chars().count() == 1 && modifiers.shift => key.to_uppercase(),
+      key if key.chars().count() == 1 => key.to_string(),
+      _ => return,
+    };
+    let query = format!("{}{}", palette.query, text);
+    palette.set_query(query);
+    cx.notify();
+  }
+
+  /// Jumps from a moved line under the cursor to where it was moved from or to
+  fn go_to_moved_counterpart(
+    &mut self,
+    _: &GoToMovedCounterpart,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    let line_number = self.cursor_line() + 1;
+    let counterpart = self
+      .compute_diff()
+      .iter()
+      .find(|line| line.line_number == line_number)
+      .and_then(|line| line.counterpart);
+    if let Some(row) = counterpart {
+      self.go_to_diff_row(row, cx);
+    }
+  }
+
+  /// Scrolls a row of the diff to the middle of the view, the cursor goes there when it's
+  /// a buffer line
+  pub fn go_to_diff_row(&mut self, row: usize, cx: &mut Context<Self>) {
+    let diff_lines = self.compute_diff();
+    let Some(line) = diff_lines.get(row) else {
+      return;
+    };
+    if line.line_number > 0 {
+      self.push_jump();
+      let index = self.editor.buffer.line_col_to_char(line.line_number - 1, 0);
+      self.editor.cursor.move_to(index);
+      self.editor.clear_selection();
+    }
+    let display_row = display_index(&self.display_rows(&self.compute_diff()), row);
+    self.scroll_to_row(display_row);
+    cx.notify();
+  }
+
+  fn take_ours(&mut self, _: &TakeOurs, _window: &mut Window, cx: &mut Context<Self>) {
+    self.resolve_conflict_at_cursor(Resolution::Ours, cx);
+  }
+
+  fn take_theirs(&mut self, _: &TakeTheirs, _window: &mut Window, cx: &mut Context<Self>) {
+    self.resolve_conflict_at_cursor(Resolution::Theirs, cx);
+  }
+
+  fn take_both(&mut self, _: &TakeBoth, _window: &mut Window, cx: &mut Context<Self>) {
+    self.resolve_conflict_at_cursor(Resolution::Both, cx);
+  }
+
+  fn revert_selection_action(
+    &mut self,
+    _: &RevertSelection,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.revert_selection(cx);
+  }
+
+  fn cursor_line(&self) -> usize {
+    let (line, _) = self
+      .editor
+      .buffer
+      .char_to_line_col(self.editor.cursor.index);
+    line
+  }
+
+  fn go_to_hunk(&mut self, hunk: &DiffHunk, cx: &mut Context<Self>) {
+    self.push_jump();
+    let line_idx = hunk
+      .line_idx
+      .min(self.editor.buffer.line_count().saturating_sub(1));
+    let index = self.editor.buffer.line_col_to_char(line_idx, 0);
+    self.editor.cursor.move_to(index);
+    self.editor.clear_selection();
+    let row = display_index(&self.display_rows(&self.compute_diff()), hunk.rows.start);
+    self.scroll_to_row(row);
+    cx.notify();
+  }
+
+  fn on_mouse_down(&mut self, event: &MouseDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+    let index = self.calculate_index_from_position(event.position, window);
+    self.gutter_anchor_line = None;
+
+    let granularity = match event.click_count {
+      1 => {
+        self.editor.cursor.index = index;
+        self.editor.clear_selection();
+        SelectionGranularity::Char
+      }
+      2 => {
+        self.editor.select_word_at(index);
+        SelectionGranularity::Word
+      }
+      3 => {
+        self.editor.select_line_at(index);
+        SelectionGranularity::Line
+      }
+      _ => return,
+    };
+
+    // Dragging from here extends the selection by the same unit
+    self.is_selecting = true;
+    self.selection_granularity = granularity;
+    self.selection_anchor = Some(self.editor.selection_range().unwrap_or(index..index));
+    cx.notify();
+  }
+
+  fn on_mouse_move(&mut self, event: &MouseMoveEvent, window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_gutter_selecting {
+      let index = self.calculate_index_from_position(event.position, window);
+      let (line_idx, _) = self.editor.buffer.char_to_line_col(index);
+      self.on_gutter_drag(line_idx, cx);
+      return;
+    }
+
+    if self.is_selecting || event.pressed_button == Some(MouseButton::Left) {
+      let index = self.calculate_index_from_position(event.position, window);
+
+      if let Some(anchor) = self.selection_anchor.clone() {
+        self
+          .editor
+          .select_to(anchor, index, self.selection_granularity);
+      } else {
+        self.editor.select_range(self.editor.cursor.index, index);
+        self.editor.cursor.index = index;
+      }
+      cx.notify();
+      return;
+    }
+
+    let hovered_change = self.hovered_change_at(event.position, window);
+    let hovered_diagnostic = self.hovered_diagnostic_at(event.position, window);
+    if hovered_change != self.hovered_change || hovered_diagnostic != self.hovered_diagnostic {
+      self.hovered_change = hovered_change;
+      self.hovered_diagnostic = hovered_diagnostic;
+      cx.notify();
+    }
+    self.update_lsp_hover(event.position, window, cx);
+  }
+
+  /// Diagnostics covering the char under `position`
+  fn hovered_diagnostic_at(
+    &self,
+    position: Point<Pixels>,
+    window: &mut Window,
+  ) -> Option<HoveredDiagnostic> {
+    if self.diagnostics.is_empty() {
+      return None;
+    }
+    let (row, start_x, index) = self.hovered_char_at(position, window)?;
+    let diagnostics = self.diagnostics.at(&self.editor, index);
+    if diagnostics.is_empty() {
+      return None;
+    }
+
+    Some(HoveredDiagnostic {
+      row,
+      start_x,
+      messages: diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.severity, diagnostic.label()))
+        .collect(),
+    })
+  }
+
+  /// Row, start x relative to the line text and buffer index of the char under `position`
+  fn hovered_char_at(
+    &self,
+    position: Point<Pixels>,
+    window: &mut Window,
+  ) -> Option<(usize, Pixels, usize)> {
+    let viewport = self.viewport();
+    if !viewport.contains(position) {
+      return None;
+    }
+
+    let row = viewport.row_at(position);
+    let diff_line = self.displayed_rows.get(row)?.diff_line()?;
+    let line = diff_line.line_number.checked_sub(1)?;
+
+    let text = diff_line.content.trim_end_matches('\n');
+    let tab_map = self.line_tab_map(line, text);
+    let shaped_line = self.shape_text(tab_map.expand(text), window);
+    let text_x = viewport.text_x(position.x);
+    if text_x < px(0.0) {
+      return None;
+    }
+
+    let col = tab_map.index_for_x(&shaped_line, text_x)?;
+    let index = self.editor.buffer.line_col_to_char(line, col);
+    Some((row, tab_map.x_for_index(&shaped_line, col), index))
+  }
+
+  /// Asks the language server about the char under the mouse once it rests there
+  fn update_lsp_hover(
+    &mut self,
+    position: Point<Pixels>,
+    window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    if self.language_server.is_none() {
+      return;
+    }
+    let hovered = self.hovered_char_at(position, window);
+    if hovered.map(|(_, _, index)| index) == self.lsp_hover.as_ref().map(|hover| hover.index) {
+      return;
+    }
+
+    let was_shown = self
+      .lsp_hover
+      .as_ref()
+      .is_some_and(|hover| hover.text.is_some());
+    self.lsp_hover = hovered.map(|(row, start_x, index)| LspHover {
+      row,
+      start_x,
+      index,
+      request: None,
+      text: None,
+    });
+    self.lsp_hover_epoch += 1;
+    if was_shown {
+      cx.notify();
+    }
+    if self.lsp_hover.is_none() {
+      return;
+    }
+
+    let epoch = self.lsp_hover_epoch;
+    cx.spawn(async move |this, cx| {
+      cx.background_executor().timer(LSP_HOVER_DELAY).await;
+      this
+        .update(cx, |this, _cx| {
+          if this.lsp_hover_epoch == epoch {
+            this.request_lsp_hover();
+          }
+        })
+        .ok();
+    })
+    .detach();
+  }
+
+  fn request_lsp_hover(&mut self) {
+    let (Some(server), Some(hover)) = (self.language_server.as_mut(), self.lsp_hover.as_mut())
+    else {
+      return;
+    };
+    let text = self.editor.buffer.as_str();
+    match server.hover(&self.file_path, &text, hover.index) {
+      Ok(id) => hover.request = Some(id),
+      Err(e) => eprintln!(
+        "Failed to ask {} for hover info: {}",
+        server.config().command,
+        e
+      ),
+    }
+  }
+
+  /// Change of a modified line under `position`, when it replaced some original text
+  fn hovered_change_at(
+    &self,
+    position: Point<Pixels>,
+    window: &mut Window,
+  ) -> Option<HoveredChange> {
+    let viewport = self.viewport();
+    if !viewport.contains(position) {
+      return None;
+    }
+
+    let row = viewport.row_at(position);
+    let diff_line = self.displayed_rows.get(row)?.diff_line()?;
+    diff_line.pair.as_ref()?;
+
+    let text = diff_line.content.trim_end_matches('\n');
+    let tab_map = self.line_tab_map(diff_line.line_number - 1, text);
+    let shaped_line = self.shape_text(tab_map.expand(text), window);
+    let text_x = viewport.text_x(position.x);
+    if text_x < px(0.0) {
+      return None;
+    }
+
+    let col = tab_map.index_for_x(&shaped_line, text_x)?;
+    let change_idx = diff_line.change_at(col)?;
+    let original = diff_line.original_fragment(change_idx)?;
+    let start = diff_line.char_changes[change_idx].start;
+
+    Some(HoveredChange {
+      row,
+      change_idx,
+      start_x: tab_map.x_for_index(&shaped_line, start),
+      original: original.to_string(),
+    })
+  }
+
+  fn on_mouse_up(&mut self, _event: &MouseUpEvent, _window: &mut Window, cx: &mut Context<Self>) {
+    self.is_selecting = false;
+    self.is_gutter_selecting = false;
+    self.selection_anchor = None;
+    cx.notify();
+  }
+
+  fn on_mouse_up_out(
+    &mut self,
+    _event: &MouseUpEvent,
+    _window: &mut Window,
+    _cx: &mut Context<Self>,
+  ) {
+    self.is_selecting = false;
+    self.is_gutter_selecting = false;
+    self.selection_anchor = None;
+  }
+
+  /// Selects the clicked line, or extends the selection from the last clicked line with Shift
+  fn on_gutter_mouse_down(&mut self, line_idx: usize, shift: bool, cx: &mut Context<Self>) {
+    let anchor_line = if shift {
+      self
+        .gutter_anchor_line
+        .unwrap_or_else(|| self.cursor_line())
+    } else {
+      line_idx
+    };
+
+    self.editor.select_lines(anchor_line, line_idx);
+    self.gutter_anchor_line = Some(anchor_line);
+    self.is_gutter_selecting = true;
+    self.is_selecting = false;
+    self.selection_anchor = None;
+    cx.notify();
+  }
+
+  /// Extends a selection started in the gutter line by line, returns false when none is in progress
+  fn on_gutter_drag(&mut self, line_idx: usize, cx: &mut Context<Self>) -> bool {
+    let Some(anchor_line) = self.gutter_anchor_line.filter(|_| self.is_gutter_selecting) else {
+      return false;
+    };
+
+    self.editor.select_lines(anchor_line, line_idx);
+    cx.notify();
+    true
+  }
+
+  fn annotations_column(&self, rows: &[DisplayRow]) -> RowColumn {
+    let line_height = self.config.line_height();
+    let theme = self.get_theme();
+    let bg_color = theme.annotations.bg_color;
+    let text_color = theme.annotations.text_color;
+    let annotations = self.line_annotations.for_rows(rows);
+
+    RowColumn {
+      width: ANNOTATIONS_WIDTH,
+      bg_color,
+      render_cell: Box::new(move |idx, _window, _cx| {
+        let row = div()
+          .id(("annotation", idx))
+          .flex_none()
+          .w(px(ANNOTATIONS_WIDTH))
+          .h(px(line_height))
+          .px_2()
+          .overflow_hidden()
+          .whitespace_nowrap()
+          .text_color(text_color);
+        let Some(annotation) = annotations.get(idx).cloned().flatten() else {
+          return row.into_any_element();
+        };
+
+        let row = match annotation.tooltip {
+          Some(tooltip) => {
+            let tooltip = SharedString::from(tooltip);
+            row.tooltip(move |_window, cx| {
+              cx.new(|_| AnnotationTooltip {
+                text: tooltip.clone(),
+                bg_color,
+                text_color,
+              })
+              .into()
+            })
+          }
+          None => row,
+        };
+        row.child(annotation.text).into_any_element()
+      }),
+    }
+  }
+
+  /// Cells of a gutter column for every row, rows past the last one are empty cells
+  fn gutter_row_column(&self, column: GutterColumn, rows: &[DisplayRow]) -> RowColumn {
+    let line_height = self.config.line_height();
+    let width = column.width();
+    let gutter_rows: Vec<GutterRow> = rows
+      .iter()
+      .enumerate()
+      .map(|(idx, row)| GutterRow::new(idx, row.diff_line()))
+      .collect();
+
+    RowColumn {
+      width,
+      bg_color: self.get_theme().line_numbers.bg_color,
+      render_cell: Box::new(move |idx, window, cx| {
+        let row = gutter_rows.get(idx).cloned().unwrap_or(GutterRow {
+          row: idx,
+          ..Default::default()
+        });
+        let cell = div()
+          .flex_none()
+          .w(px(width))
+          .h(px(line_height))
+          .child(column.render_row(&row, window, cx));
+        match column.click_handler() {
+          // Cells handle their own clicks so they don't reach text hit-testing
+          Some(on_click) => cell
+            .on_mouse_down(MouseButton::Left, move |event, window, cx| {
+              cx.stop_propagation();
+              on_click(&row, event, window, cx);
+            })
+            .into_any_element(),
+          None => cell.into_any_element(),
+        }
+      }),
+    }
+  }
+
+  /// Color strip of the changed lines, faded once their hunk is viewed
+  ///
+  /// Clicking a hunk marks it as viewed, or as needing work with Shift, Alt-clicking an added
+  /// or modified line previews the original lines it replaced below it
+  fn diff_strip_column(
+    &self,
+    diff_lines: &[DiffLine],
+    rows: &[DisplayRow],
+    cx: &mut Context<Self>,
+  ) -> GutterColumn {
+    let line_height = self.config.line_height();
+    let theme = self.get_theme();
+    let added_gutter_color = theme.git.added.gutter_color;
+    let removed_gutter_color = theme.git.removed.gutter_color;
+    let moved_gutter_color = theme.git.moved.gutter_color;
+    let needs_work_color = theme.diagnostics.color(DiagnosticSeverity::Warning);
+    let line_numbers_bg_color = theme.line_numbers.bg_color;
+    let this = cx.entity().downgrade();
+
+    // Review key and state of the hunk of each display row
+    let hunks = self.review_hunks(diff_lines);
+    let review = self.reviews.get(&self.file_path);
+    let row_reviews: Rc<HashMap<usize, (u64, ReviewState)>> = Rc::new(
+      rows
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, row)| {
+          let DisplayRow::Line { row, .. } = row else {
+            return None;
+          };
+          let (_, key) = hunks.iter().find(|(hunk, _)| hunk.rows.contains(row))?;
+          let state = review.map(|review| review.state(*key)).unwrap_or_default();
+          Some((idx, (*key, state)))
+        })
+        .collect(),
+    );
+
+    let on_click = {
+      let row_reviews = row_reviews.clone();
+      move |row: &GutterRow, event: &MouseDownEvent, _window: &mut Window, cx: &mut App| {
+        // Alt-click previews the original lines of an added or modified line
+        if event.modifiers.alt {
+          if let (Some(line), Some(diff_line)) = (row.line, &row.diff_line)
+            && has_original(diff_line)
+          {
+            this
+              .update(cx, |editor, cx| editor.toggle_original_preview(line, cx))
+              .ok();
+          }
+          return;
+        }
+        let Some((key, _)) = row_reviews.get(&row.row).copied() else {
+          return;
+        };
+        let state = if event.modifiers.shift {
+          ReviewState::NeedsWork
+        } else {
+          ReviewState::Viewed
+        };
+        this
+          .update(cx, |editor, cx| editor.toggle_review_state(key, state, cx))
+          .ok();
+      }
+    };
+
+    GutterColumn::new(DIFF_GUTTER_WIDTH, move |row, _window, _cx| {
+      let review_state = row_reviews.get(&row.row).map(|(_, state)| *state);
+      let bg_color: Hsla = match &row.diff_line {
+        None => line_numbers_bg_color,
+        Some(line) => match line.kind {
+          DiffLineKind::Added => added_gutter_color,
+          DiffLineKind::Removed => removed_gutter_color,
+          DiffLineKind::Modified if line.line_number == 0 => removed_gutter_color,
+          DiffLineKind::Modified => added_gutter_color,
+          DiffLineKind::Moved => moved_gutter_color,
+          DiffLineKind::Unchanged => line_numbers_bg_color,
+        },
+      };
+      let bg_color = match review_state {
+        Some(ReviewState::Viewed) => bg_color.alpha(0.35),
+        Some(ReviewState::NeedsWork) => needs_work_color,
+        Some(ReviewState::Unviewed) | None => bg_color,
+      };
+      div()
+        .h(px(line_height))
+        .w_full()
+        .bg(bg_color)
+        .when(review_state.is_some(), |d| d.cursor_pointer())
+        .into_any_element()
+    })
+    .on_click(on_click)
+  }
+
+  /// Line numbers with the fold, bookmark and diagnostic markers, clicking selects lines
+  ///
+  /// The column fits the number of the last line, it widens as the file grows past a power of ten
+  fn line_numbers_column(&self, window: &mut Window, cx: &mut Context<Self>) -> GutterColumn {
+    let line_height = self.config.line_height();
+    let theme = self.get_theme();
+    let line_numbers_text_color = theme.line_numbers.text_color;
+    let this = cx.entity().downgrade();
+    let line_numbers = self.config.line_numbers;
+    let cursor_line = self.cursor_line();
+
+    // Header line of each fold range, and whether it's folded
+    let fold_headers: HashMap<usize, bool> = self
+      .fold_ranges()
+      .iter()
+      .map(|range| (range.start_line, self.folds.is_folded(range.start_line)))
+      .collect();
+    let bookmarks: HashSet<usize> = self.bookmarks.lines(&self.editor).into_iter().collect();
+    let bookmark_color = theme.cursor.color;
+    let diagnostic_badges: HashMap<usize, Hsla> = self
+      .diagnostics
+      .line_severities(&self.editor)
+      .into_iter()
+      .map(|(line, severity)| (line, theme.diagnostics.color(severity)))
+      .collect();
+
+    let digit_width = f32::from(self.shape_text("0".to_string(), window).width);
+    let markers_width: f32 = [
+      ("◆", !bookmarks.is_empty()),
+      ("●", !diagnostic_badges.is_empty()),
+    ]
+    .into_iter()
+    .filter(|(_, shown)| *shown)
+    .map(|(marker, _)| f32::from(self.shape_text(marker.to_string(), window).width) + MARKER_GAP)
+    .sum();
+    let width = line_numbers_width(self.editor.buffer.line_count(), digit_width) + markers_width;
+
+    let on_click = {
+      let this = this.clone();
+      move |row: &GutterRow, event: &MouseDownEvent, _window: &mut Window, cx: &mut App| {
+        if let Some(line_idx) = row.line {
+          this
+            .update(cx, |editor, cx| {
+              editor.on_gutter_mouse_down(line_idx, event.modifiers.shift, cx)
+            })
+            .ok();
+        }
+      }
+    };
+
+    GutterColumn::new(width, move |row, _window, _cx| {
+      let line_idx = row.line;
+      let counterpart = row.diff_line.as_ref().and_then(|line| line.counterpart);
+      let line_num_text = match line_idx {
+        Some(line_idx) => line_numbers.label(line_idx, cursor_line),
+        None => "".to_string(),
+      };
+
+      let fold_indicator = div().w(px(FOLD_INDICATOR_WIDTH)).flex().justify_center();
+      let fold_indicator = match line_idx.and_then(|l| fold_headers.get(&l).map(|f| (l, *f))) {
+        Some((start_line, folded)) => {
+          let this = this.clone();
+          fold_indicator
+            .cursor_pointer()
+            .on_mouse_down(MouseButton::Left, move |_, _window, cx| {
+              cx.stop_propagation();
+              this
+                .update(cx, |editor, cx| editor.toggle_fold(start_line, cx))
+                .ok();
+            })
+            .child(if folded { "▸" } else { "▾" })
+        }
+        // Moved lines link to where they were, or to where they went
+        None => match counterpart {
+          Some(counterpart) => {
+            let this = this.clone();
+            fold_indicator
+              .cursor_pointer()
+              .on_mouse_down(MouseButton::Left, move |_, _window, cx| {
+                cx.stop_propagation();
+                this
+                  .update(cx, |editor, cx| editor.go_to_diff_row(counterpart, cx))
+                  .ok();
+              })
+              .child("⇅")
+          }
+          None => fold_indicator,
+        },
+      };
+
+      let on_mouse_move = {
+        let this = this.clone();
+        move |event: &MouseMoveEvent, _window: &mut Window, cx: &mut App| {
+          let Some(line_idx) = line_idx.filter(|_| event.dragging()) else {
+            return;
+          };
+          let handled = this
+            .update(cx, |editor, cx| editor.on_gutter_drag(line_idx, cx))
+            .unwrap_or(false);
+          if handled {
+            cx.stop_propagation();
+          }
+        }
+      };
+
+      div()
+        .w_full()
+        .h(px(line_height))
+        .on_mouse_move(on_mouse_move)
+        .flex()
+        .items_end()
+        .justify_between()
+        .pr_2()
+        .text_color(line_numbers_text_color)
+        .child(fold_indicator)
+        .child(
+          div()
+            .flex()
+            .gap_1()
+            .when(line_idx.is_some_and(|l| bookmarks.contains(&l)), |d| {
+              d.child(div().text_color(bookmark_color).child("◆"))
+            })
+            .when_some(
+              line_idx.and_then(|l| diagnostic_badges.get(&l).copied()),
+              |d, color| d.child(div().text_color(color).child("●")),
+            )
+            .child(line_num_text),
+        )
+        .into_any_element()
+    })
+    .on_click(on_click)
+  }
+
+  /// Columns added with `add_gutter_column`, between the line numbers and the text
+  fn host_gutter_columns(&self, rows: &[DisplayRow]) -> Vec<RowColumn> {
+    self
+      .gutter_columns
+      .iter()
+      .map(|(_, column)| self.gutter_row_column(column.clone(), rows))
+      .collect()
+  }
+
+  /// Renders the gutter columns and the text of every row as one list, so that they share
+  /// a single scroll offset and are laid out together
+  fn render_rows(
+    &self,
+    columns: Vec<RowColumn>,
+    rows: Rc<Vec<DisplayRow>>,
+    render_text: CellRenderer,
+    scroll_handle: UniformListScrollHandle,
+  ) -> impl IntoElement {
+    let line_cache = self.line_cache.clone();
+    let display_map = self.display_map.clone();
+    let line_height = self.config.line_height();
+    let item_count = rows.len() + self.overscroll_rows();
+    // Columns are painted down to the bottom of the pane, past the last row
+    let backgrounds = div()
+      .absolute()
+      .top_0()
+      .bottom_0()
+      .left_0()
+      .flex()
+      .children(
+        columns
+          .iter()
+          .map(|column| div().w(px(column.width)).h_full().bg(column.bg_color)),
+      );
+
+    let mut list = uniform_list(
+      "editor-rows",
+      item_count,
+      move |range: Range<usize>, window, cx| {
+        if let Some(lines) = display_map.buffer_lines(range.clone()) {
+          line_cache.lock().unwrap().set_viewport(lines);
+        }
+        range
+          .map(|idx| {
+            div()
+              .flex()
+              .w_full()
+              .h(px(line_height))
+              .children(
+                columns
+                  .iter()
+                  .map(|column| (column.render_cell)(idx, window, cx)),
+              )
+              .child(
+                div()
+                  .flex_1()
+                  .min_w(px(0.0))
+                  .h_full()
+                  .px(px(EDITOR_PADDING))
+                  .overflow_hidden()
+                  .cursor_text()
+                  .child(render_text(idx, window, cx)),
+              )
+              .into_any_element()
+          })
+          .collect::<Vec<_>>()
+      },
+    )
+    .size_full()
+    .track_scroll(scroll_handle);
+    disable_wheel_scroll(&mut list);
+
+    div().relative().size_full().child(backgrounds).child(list)
+  }
+
+  /// Text cell of every row: the line, a collapsed region or a comment thread row
+  fn text_cells(
+    &self,
+    rows: Rc<Vec<DisplayRow>>,
+    buffer: Arc<TextBuffer>,
+    editor_state: EditorState,
+    cx: &mut Context<Self>,
+  ) -> CellRenderer {
+    let line_cache = self.line_cache.clone();
+    let display_map = self.display_map.clone();
+    let scroll_x = self.scroll_x;
+    let line_height = self.config.line_height();
+    let font_size = self.config.font_size;
+    let theme = self.get_theme();
+    let text_color = theme.code.text_color;
+    let cursor_color = theme.cursor.color;
+    let selection_color = theme.cursor.selection_color;
+    let occurrence_color = theme.cursor.occurrence_color;
+    let render_whitespace = self.config.render_whitespace;
+    let whitespace_color = theme.whitespace_color;
+    // A single occurrence is the one under the cursor, nothing else to point at
+    let occurrences = Some(self.editor.occurrences())
+      .filter(|occurrences| occurrences.len() > 1)
+      .unwrap_or_default();
+    let occurrences = Arc::new(occurrences);
+    let mut diagnostics = self.diagnostics.current(&self.editor);
+    // Painted in order, the most severe squiggles end up on top
+    diagnostics.sort_by_key(|diagnostic| diagnostic.severity);
+    let diagnostics: Arc<Vec<(Range<usize>, Hsla)>> = Arc::new(
+      diagnostics
+        .iter()
+        .map(|diagnostic| {
+          (
+            underlined_range(&diagnostic.range),
+            theme.diagnostics.color(diagnostic.severity),
+          )
+        })
+        .collect(),
+    );
+    let inlays = Arc::new(self.inlay_hints.by_line(&self.editor));
+    let inlay_color = theme.line_numbers.text_color;
+    let collapsed_text_color = theme.line_numbers.text_color;
+    let collapsed_bg_color = theme.line_numbers.bg_color;
+    let thread_bg_color = theme.annotations.bg_color;
+    let thread_text_color = theme.annotations.text_color;
+    let comment_threads = Arc::new(self.comment_threads.clone());
+    let this = cx.entity().downgrade();
+    let cursor_visible = !self.is_read_only() && self.cursor_blink.is_visible();
+    // Whitespace insensitive diffs show the last line unchanged when only its newline changed
+    let final_newline_changed =
+      self.editor.buffer.ends_with_newline() != self.compare_content.ends_with('\n');
+
+    let line_config = LineConfig {
+      font: self.font.clone(),
+      font_size,
+      line_height,
+      text_color,
+      cursor_color,
+      cursor_style: self.config.cursor_style,
+      selection_color,
+      tab_size: self.config.tab_size,
+    };
+
+    let theme = self.get_theme();
+    let added_line_bg_color = theme.git.added.line_bg_color;
+    let added_char_highlight_color = theme.git.added.char_highlight_color;
+    let removed_line_bg_color = theme.git.removed.line_bg_color;
+    let removed_char_highlight_color = theme.git.removed.char_highlight_color;
+    let moved_line_bg_color = theme.git.moved.line_bg_color;
+    let moved_char_highlight_color = theme.git.moved.char_highlight_color;
+
+    Box::new(move |idx, _window, _cx| {
+      // Rows past the last line are the overscroll
+      let Some(row) = rows.get(idx) else {
+        return div().h(px(line_height)).w_full().into_any_element();
+      };
+      let line = match row {
+        DisplayRow::Line { line, .. } => line,
+        DisplayRow::Collapsed(region) => {
+          let start_line = region.start_line;
+          let this = this.clone();
+          let label = match region.line_count() {
+            1 => "… 1 unchanged line …".to_string(),
+            count => format!("… {} unchanged lines …", count),
+          };
+
+          return div()
+            .h(px(line_height))
+            .w_full()
+            .flex()
+            .items_center()
+            .bg(collapsed_bg_color)
+            .text_color(collapsed_text_color)
+            .cursor_pointer()
+            .on_mouse_down(MouseButton::Left, move |_, _window, cx| {
+              cx.stop_propagation();
+              this
+                .update(cx, |editor, cx| {
+                  editor.expand_unchanged_region(start_line, cx)
+                })
+                .ok();
+            })
+            .child(label)
+            .into_any_element();
+        }
+        DisplayRow::Thread(thread_row) => {
+          let id = thread_row.id;
+          let row = div()
+            .h(px(line_height))
+            .w_full()
+            .flex()
+            .items_center()
+            .bg(thread_bg_color)
+            .text_color(thread_text_color);
+          let Some(thread) = comment_threads.get(id) else {
+            return row.into_any_element();
+          };
+
+          return match thread_row.kind {
+            ThreadRowKind::Header => {
+              let this = this.clone();
+              let label = match thread.comments.len() {
+                1 => "1 comment".to_string(),
+                count => format!("{} comments", count),
+              };
+              row
+                .cursor_pointer()
+                .on_mouse_down(MouseButton::Left, move |_, _window, cx| {
+                  cx.stop_propagation();
+                  this
+                    .update(cx, |editor, cx| editor.toggle_comment_thread(id, cx))
+                    .ok();
+                })
+                .child(if thread.expanded { "▾ " } else { "▸ " })
+                .child(label)
+                .into_any_element()
+            }
+            ThreadRowKind::Author(comment) => row
+              .pl(px(line_height))
+              .font_weight(FontWeight::BOLD)
+              .child(thread.comments[comment].author.clone())
+              .into_any_element(),
+            ThreadRowKind::Body { comment, line } => row
+              .pl(px(line_height))
+              .child(
+                thread.comments[comment]
+                  .body
+                  .lines()
+                  .nth(line)
+                  .unwrap_or_default()
+                  .to_string(),
+              )
+              .into_any_element(),
+          };
+        }
+        DisplayRow::Original(original) => {
+          let this = this.clone();
+          let line = original.line;
+          return div()
+            .h(px(line_height))
+            .w_full()
+            .flex()
+            .items_center()
+            .bg(removed_line_bg_color)
+            .text_color(collapsed_text_color)
+            .cursor_pointer()
+            .on_mouse_down(MouseButton::Left, move |_, _window, cx| {
+              cx.stop_propagation();
+              this
+                .update(cx, |editor, cx| editor.toggle_original_preview(line, cx))
+                .ok();
+            })
+            .child(match &original.content {
+              Some(content) => content.clone(),
+              None => "No original line, added".to_string(),
+            })
+            .into_any_element();
+        }
+      };
+
+      // For removed/modified lines without line number, don't show cursor
+      // Use an impossible line_idx so the cursor won't be calculated for this line
+      let line_idx = if line.line_number == 0 {
+        usize::MAX
+      } else {
+        line.line_number - 1
+      };
+
+      // Removed lines have no buffer text, the selection covers them as a whole when it
+      // goes past them
+      let position = display_map.row_position(idx);
+      let selected = match (position, &editor_state.selection_range) {
+        (RowPosition::Before(next_line), Some(selection)) => {
+          selection_spans_boundary(&buffer, selection, next_line)
+        }
+        _ => false,
+      };
+
+      // Create a modified editor_state that hides cursor on removed lines
+      let modified_editor_state = if line.line_number == 0 {
+        // Hide cursor by setting it to an impossible position
+        EditorState {
+          cursor_index: usize::MAX,
+          selection_range: editor_state.selection_range.clone(),
+        }
+      } else {
+        editor_state.clone()
+      };
+
+      // For removed lines, use text override since they're not in the buffer
+      let text_override = match line.kind {
+        DiffLineKind::Removed => Some(line.content.clone()),
+        DiffLineKind::Modified | DiffLineKind::Moved if line.line_number == 0 => {
+          Some(line.content.clone())
+        }
+        _ => None,
+      };
+
+      let diff_bg = match line.kind {
+        DiffLineKind::Added => Some(DiffBackground {
+          color: added_line_bg_color,
+          char_highlights: line.char_changes.clone(),
+          highlight_color: added_char_highlight_color,
+        }),
+        DiffLineKind::Removed => Some(DiffBackground {
+          color: removed_line_bg_color,
+          char_highlights: line.char_changes.clone(),
+          highlight_color: removed_char_highlight_color,
+        }),
+        DiffLineKind::Modified if line.line_number == 0 => Some(DiffBackground {
+          color: removed_line_bg_color,
+          char_highlights: line.char_changes.clone(),
+          highlight_color: removed_char_highlight_color,
+        }),
+        DiffLineKind::Modified => Some(DiffBackground {
+          color: added_line_bg_color,
+          char_highlights: line.char_changes.clone(),
+          highlight_color: added_char_highlight_color,
+        }),
+        DiffLineKind::Moved => Some(DiffBackground {
+          color: moved_line_bg_color,
+          char_highlights: line.char_changes.clone(),
+          highlight_color: moved_char_highlight_color,
+        }),
+        DiffLineKind::Unchanged => None,
+      };
+
+      let mut element = LineElement::new(
+        line_idx,
+        buffer.clone(),
+        modified_editor_state,
+        line_cache.clone(),
+        line_config.clone(),
+      )
+      .with_scroll_x(scroll_x)
+      .with_cursor_visible(cursor_visible)
+      .with_highlights(occurrences.clone(), occurrence_color)
+      .with_diagnostics(diagnostics.clone())
+      .with_render_whitespace(render_whitespace, whitespace_color)
+      .with_selected(selected);
+
+      if let Some(text) = text_override {
+        element = element.with_text_override(text);
+      } else if let Some(line_inlays) = inlays.get(&line_idx) {
+        element = element.with_inlays(line_inlays.clone(), inlay_color);
+      }
+
+      if let Some(bg) = diff_bg {
+        element = element.with_diff_background(bg);
+      }
+
+      if shows_no_newline_marker(&line, final_newline_changed) {
+        return div()
+          .relative()
+          .w_full()
+          .child(element)
+          .child(
+            div()
+              .absolute()
+              .top_0()
+              .right_2()
+              .h(px(line_height))
+              .flex()
+              .items_center()
+              .text_color(inlay_color)
+              .child("⊘ No newline at end of file"),
+          )
+          .into_any_element();
+      }
+
+      element.into_any_element()
+    })
+  }
+
+  /// Header of the hunk scrolled past the top of the viewport, when enabled
+  fn sticky_header(&self, diff_lines: &[DiffLine], rows: &[DisplayRow]) -> Option<StickyHeader> {
+    if !self.config.sticky_header {
+      return None;
+    }
+
+    let line_height = self.config.line_height();
+    let offset_y = -f32::from(self.scroll_handle.0.borrow().base_handle.offset().y);
+    let top_row = (offset_y / line_height).floor().max(0.0) as usize;
+    // Nothing is hidden at the very top
+    if top_row == 0 {
+      return None;
+    }
+
+    let hunks = Differ::compute_hunks(diff_lines);
+    sticky_header(diff_lines, &hunks, top_diff_row(rows, top_row)?)
+  }
+
+  fn render_sticky_header(&self, header: StickyHeader, cx: &mut Context<Self>) -> impl IntoElement {
+    let theme = self.get_theme();
+
+    div()
+      .absolute()
+      .top_0()
+      .left_0()
+      .w_full()
+      .h(px(self.config.line_height()))
+      .px(px(EDITOR_PADDING))
+      .flex()
+      .items_center()
+      .overflow_hidden()
+      .whitespace_nowrap()
+      .bg(theme.line_numbers.bg_color)
+      .text_color(theme.line_numbers.text_color)
+      .border_b_1()
+      .border_color(theme.line_numbers.text_color.alpha(0.2))
+      .cursor_pointer()
+      .on_mouse_down(
+        MouseButton::Left,
+        cx.listener(move |this, _, _window, cx| {
+          cx.stop_propagation();
+          this.go_to_hunk(&header.hunk, cx);
+        }),
+      )
+      .child(header.text)
+  }
+
+  /// Dropdown in the top right corner to pick what the buffer is diffed against
+  fn render_baseline_picker(&self, cx: &mut Context<Self>) -> impl IntoElement {
+    let theme = self.get_theme();
+    let label = self.revisions.baseline_label(self.baseline);
+
+    let mut choices = vec![(Baseline::Original, "Original".to_string())];
+    choices.extend(
+      self
+        .revisions
+        .iter()
+        .map(|(id, revision)| (Baseline::Revision(id), revision.label.clone())),
+    );
+
+    let button = div()
+      .px_2()
+      .cursor_pointer()
+      .on_mouse_down(
+        MouseButton::Left,
+        cx.listener(|this, _, _window, cx| {
+          cx.stop_propagation();
+          this.baseline_menu_open = !this.baseline_menu_open;
+          cx.notify();
+        }),
+      )
+      .child(format!("Compare with: {} ▾", label));
+
+    let menu = self.baseline_menu_open.then(|| {
+      div()
+        .flex()
+        .flex_col()
+        .border_t_1()
+        .border_color(theme.line_numbers.text_color.alpha(0.2))
+        .children(choices.into_iter().map(|(baseline, label)| {
+          div()
+            .px_2()
+            .cursor_pointer()
+            .when(baseline == self.baseline, |d| {
+              d.bg(theme.cursor.selection_color)
+            })
+            .on_mouse_down(
+              MouseButton::Left,
+              cx.listener(move |this, _, _window, cx| {
+                cx.stop_propagation();
+                this.compare_with(baseline, cx);
+              }),
+            )
+            .child(label)
+        }))
+    });
+
+    div()
+      .absolute()
+      .top_0()
+      .right_0()
+      .flex()
+      .flex_col()
+      .whitespace_nowrap()
+      .bg(theme.line_numbers.bg_color)
+      .text_color(theme.line_numbers.text_color)
+      .border_1()
+      .border_color(theme.line_numbers.text_color.alpha(0.2))
+      .child(button)
+      .children(menu)
+  }
+
+  /// Counters of the last frame in the bottom right corner, ends the frame in progress once
+  /// it's painted
+  fn render_profiling_overlay(&self) -> impl IntoElement {
+    let theme = self.get_theme();
+    let lines = match self.frame_stats() {
+      Some(frame) => vec![
+        format!("Frame {:.1} ms", frame.frame_time.as_secs_f64() * 1000.0),
+        format!("Shaped {} lines", frame.lines_shaped),
+        match frame.hit_rate() {
+          Some(rate) => format!("Cache hits {:.0}%", rate * 100.0),
+          None => "Cache hits –".to_string(),
+        },
+        format!(
+          "Diff {:.1} ms ({}×)",
+          frame.diff_time.as_secs_f64() * 1000.0,
+          frame.diffs
+        ),
+      ],
+      None => vec!["Profiling…".to_string()],
+    };
+
+    let profiler = self.profiler.clone();
+    let line_cache = self.line_cache.clone();
+    div()
+      .absolute()
+      .bottom_0()
+      .right_0()
+      .flex()
+      .flex_col()
+      .px_2()
+      .whitespace_nowrap()
+      .text_size(px(self.config.font_size * 0.8))
+      .bg(theme.line_numbers.bg_color.alpha(0.9))
+      .text_color(theme.line_numbers.text_color)
+      .border_1()
+      .border_color(theme.line_numbers.text_color.alpha(0.2))
+      .children(lines)
+      // Painted after the rows, the frame ends there
+      .child(
+        canvas(
+          |_, _, _| {},
+          move |_, _, _, _| {
+            let cache_stats = line_cache.lock().unwrap().stats();
+            profiler.borrow_mut().end_frame(cache_stats);
+          },
+        )
+        .size_0(),
+      )
+  }
+
+  /// Query and matching commands at the top of the pane, with the keystrokes bound to them
+  fn render_command_palette(
+    &self,
+    palette: &CommandPalette,
+    cx: &mut Context<Self>,
+  ) -> impl IntoElement {
+    let theme = self.get_theme();
+    let border_color = theme.line_numbers.text_color.alpha(0.2);
+    let query = if palette.query.is_empty() {
+      div().opacity(0.5).child("Type a command…")
+    } else {
+      div().child(palette.query.clone())
+    };
+
+    let rows = palette
+      .matches()
+      .enumerate()
+      .map(|(ix, (command, keystrokes))| {
+        let label = command.label.clone();
+        let command = command.clone();
+        div()
+          .flex()
+          .justify_between()
+          .gap_3()
+          .px_2()
+          .cursor_pointer()
+          .when(ix == palette.selected, |d| {
+            d.bg(theme.cursor.selection_color)
+          })
+          .on_mouse_down(
+            MouseButton::Left,
+            cx.listener(move |this, _, window, cx| {
+              cx.stop_propagation();
+              this.run_command(command.clone(), window, cx);
+            }),
+          )
+          .child(label)
+          .children(keystrokes.map(|keystrokes| div().opacity(0.6).child(keystrokes.to_string())))
+      })
+      .collect::<Vec<_>>();
+
+    div()
+      .absolute()
+      .top_0()
+      .left_0()
+      .w_full()
+      .flex()
+      .justify_center()
+      .child(
+        div()
+          .key_context(COMMAND_PALETTE_KEY_CONTEXT)
+          .track_focus(&palette.focus_handle)
+          .on_action(cx.listener(Self::select_next_command))
+          .on_action(cx.listener(Self::select_previous_command))
+          .on_action(cx.listener(Self::confirm_command))
+          .on_action(cx.listener(Self::dismiss_command_palette))
+          .on_action(cx.listener(Self::command_palette_backspace))
+          .on_key_down(cx.listener(Self::command_palette_key_down))
+          .on_mouse_down(MouseButton::Left, |_, _window, cx| cx.stop_propagation())
+          .w(px(COMMAND_PALETTE_WIDTH))
+          .mt_2()
+          .flex()
+          .flex_col()
+          .whitespace_nowrap()
+          .bg(theme.line_numbers.bg_color)
+          .text_color(theme.line_numbers.text_color)
+          .border_1()
+          .border_color(border_color)
+          .child(
+            div()
+              .px_2()
+              .py_1()
+              .border_b_1()
+              .border_color(border_color)
+              .child(query),
+          )
+          .children(rows),
+      )
+  }
+
+  /// Position of a tooltip in the pane, below the row or above it when there's no room left
+  fn tooltip_position(&self, row: usize, start_x: Pixels, height: Pixels) -> Point<Pixels> {
+    let viewport = self.viewport();
+    let line_height = px(self.config.line_height());
+
+    let position = viewport.position_of(row + 1, start_x) - viewport.bounds.origin;
+    let top = if position.y + height > viewport.bounds.size.height {
+      position.y - line_height - height
+    } else {
+      position.y
+    };
+    point(position.x, top)
+  }
+
+  /// Original text of the hovered change, below the line or above it near the bottom of the pane
+  fn render_change_tooltip(&self, hovered: HoveredChange) -> impl IntoElement {
+    let theme = self.get_theme();
+    let tooltip_height = px(self.config.line_height()) + px(8.0);
+    let position = self.tooltip_position(hovered.row, hovered.start_x, tooltip_height);
+
+    div()
+      .absolute()
+      .left(position.x)
+      .top(position.y)
+      .h(tooltip_height)
+      .px_2()
+      .flex()
+      .items_center()
+      .whitespace_nowrap()
+      .rounded(px(4.0))
+      .border_1()
+      .border_color(theme.code.text_color.alpha(0.2))
+      .bg(theme.code.bg_color)
+      .text_color(theme.code.text_color)
+      .child(
+        div()
+          .bg(theme.git.removed.char_highlight_color)
+          .child(hovered.original),
+      )
+  }
+
+  fn render_diagnostic_tooltip(&self, hovered: HoveredDiagnostic) -> impl IntoElement {
+    let theme = self.get_theme();
+    let line_height = px(self.config.line_height());
+    let tooltip_height = line_height * hovered.messages.len() as f32 + px(8.0);
+    let position = self.tooltip_position(hovered.row, hovered.start_x, tooltip_height);
+
+    div()
+      .absolute()
+      .left(position.x)
+      .top(position.y)
+      .py_1()
+      .px_2()
+      .flex()
+      .flex_col()
+      .whitespace_nowrap()
+      .rounded(px(4.0))
+      .border_1()
+      .border_color(theme.code.text_color.alpha(0.2))
+      .bg(theme.code.bg_color)
+      .text_color(theme.code.text_color)
+      .children(hovered.messages.into_iter().map(|(severity, message)| {
+        div()
+          .h(line_height)
+          .flex()
+          .items_center()
+          .gap_2()
+          .child(
+            div()
+              .text_color(theme.diagnostics.color(severity))
+              .child("●"),
+          )
+          .child(message)
+      }))
+  }
+
+  /// Size of the selection and its diff stats, next to the cursor end of the selection
+  fn render_selection_info(&self, window: &mut Window) -> Option<AnyElement> {
+    let selection = self.editor.selection_range()?;
+    let (line, col) = self
+      .editor
+      .buffer
+      .char_to_line_col(self.editor.cursor.index);
+    let row = self.display_map.line_row(line)?;
+    if !self.viewport().visible_rows().contains(&row) {
+      return None;
+    }
+
+    let theme = self.get_theme();
+    let hud_height = px(self.config.line_height()) + px(8.0);
+    let start_x = self.column_x(line, col, window);
+    let position = self.tooltip_position(row, start_x, hud_height);
+    let stats = self
+      .selection_diff_stats()
+      .map(|stats| (stats.added + stats.modified, stats.removed + stats.modified))
+      .filter(|(added, removed)| added + removed > 0);
+
+    Some(
+      div()
+        .absolute()
+        .left(position.x)
+        .top(position.y)
+        .h(hud_height)
+        .px_2()
+        .flex()
+        .items_center()
+        .gap_2()
+        .whitespace_nowrap()
+        .rounded(px(4.0))
+        .text_size(px(self.config.font_size * 0.8))
+        .bg(theme.status_bar.bg_color.alpha(0.9))
+        .text_color(theme.status_bar.text_color)
+        .child(selection_label(
+          selection.len(),
+          self.editor.selected_line_range().len(),
+        ))
+        .children(stats.map(|(added, removed)| {
+          div()
+            .flex()
+            .gap_1()
+            .child(
+              div()
+                .text_color(theme.git.added.gutter_color)
+                .child(format!("+{}", added)),
+            )
+            .child(
+              div()
+                .text_color(theme.git.removed.gutter_color)
+                .child(format!("−{}", removed)),
+            )
+            .child("in selection")
+        }))
+        .into_any_element(),
+    )
+  }
+
+  fn render_lsp_hover_tooltip(&self, hover: &LspHover, text: &str) -> impl IntoElement {
+    let theme = self.get_theme();
+    let line_height = px(self.config.line_height());
+    let lines: Vec<String> = text
+      .lines()
+      .take(LSP_HOVER_MAX_LINES)
+      .map(str::to_string)
+      .collect();
+    let tooltip_height = line_height * lines.len() as f32 + px(8.0);
+    let position = self.tooltip_position(hover.row, hover.start_x, tooltip_height);
+
+    div()
+      .absolute()
+      .left(position.x)
+      .top(position.y)
+      .py_1()
+      .px_2()
+      .flex()
+      .flex_col()
+      .whitespace_nowrap()
+      .rounded(px(4.0))
+      .border_1()
+      .border_color(theme.code.text_color.alpha(0.2))
+      .bg(theme.code.bg_color)
+      .text_color(theme.code.text_color)
+      .children(
+        lines
+          .into_iter()
+          .map(|line| div().h(line_height).flex().items_center().child(line)),
+      )
+  }
+
+  fn render_scrollbar(
     &self,
-    diff_lines: Vec<DiffLine>,
-    buffer: Arc<TextBuffer>,
-    editor_state: EditorState,
+    rows: &[DisplayRow],
+    scroll_handle: UniformListScrollHandle,
+  ) -> DiffScrollbar {
+    let theme = self.get_theme();
+    let colors = ScrollbarColors {
+      track_color: theme.scrollbar.track_color,
+      thumb_color: theme.scrollbar.thumb_color,
+      added_color: theme.git.added.gutter_color,
+      removed_color: theme.git.removed.gutter_color,
+      modified_color: theme.git.modified.gutter_color,
+      moved_color: theme.git.moved.gutter_color,
+    };
+
+    DiffScrollbar::new(
+      ScrollbarMarker::from_display_rows(rows),
+      rows.len() + self.overscroll_rows(),
+      self.config.line_height(),
+      SCROLLBAR_WIDTH,
+      scroll_handle,
+      self.scrollbar_state.clone(),
+      colors,
+    )
+  }
+
+  fn render_minimap(
+    &self,
+    rows: Vec<DisplayRow>,
     scroll_handle: UniformListScrollHandle,
+  ) -> DiffMinimap {
+    let theme = self.get_theme();
+    let colors = MinimapColors {
+      bg_color: theme.code.bg_color,
+      text_color: theme.code.text_color.alpha(0.35),
+      viewport_color: theme.scrollbar.thumb_color,
+      added_color: theme.git.added.line_bg_color,
+      removed_color: theme.git.removed.line_bg_color,
+      modified_color: theme.git.modified.line_bg_color,
+      moved_color: theme.git.moved.line_bg_color,
+    };
+    let row_count = rows.len() + self.overscroll_rows();
+
+    DiffMinimap::new(
+      rows,
+      row_count,
+      self.line_cache.clone(),
+      self.config.tab_size,
+      self.config.line_height(),
+      MINIMAP_WIDTH,
+      scroll_handle,
+      self.minimap_state.clone(),
+      colors,
+    )
+  }
+
+  fn close_active_tab(&mut self, _: &CloseTab, _window: &mut Window, cx: &mut Context<Self>) {
+    if let Some(tab_ix) = self.active_tab() {
+      self.close_tab(tab_ix, cx);
+    }
+  }
+
+  fn activate_next_tab(
+    &mut self,
+    _: &ActivateNextTab,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.cycle_tabs(1, cx);
+  }
+
+  fn activate_previous_tab(
+    &mut self,
+    _: &ActivatePreviousTab,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.cycle_tabs(-1, cx);
+  }
+
+  fn render_tab_bar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+    let theme = self.get_theme();
+    let bar_bg_color = theme.line_numbers.bg_color;
+    let active_bg_color = theme.code.bg_color;
+    let text_color = theme.code.text_color;
+    let inactive_text_color = theme.line_numbers.text_color;
+    let active_tab = self.active_tab();
+
+    div()
+      .id("tab-bar")
+      .flex()
+      .flex_none()
+      .h(px(TAB_BAR_HEIGHT))
+      .w_full()
+      .overflow_x_scroll()
+      .bg(bar_bg_color)
+      .children(self.tabs.iter().enumerate().map(|(tab_ix, path)| {
+        let is_active = Some(tab_ix) == active_tab;
+        let name = path
+          .file_name()
+          .map(|name| name.to_string_lossy().to_string())
+          .unwrap_or_else(|| "Untitled".to_string());
+
+        div()
+          .id(("tab", tab_ix))
+          .flex()
+          .items_center()
+          .gap_2()
+          .px_3()
+          .h_full()
+          .cursor_pointer()
+          .when_else(
+            is_active,
+            |d| d.bg(active_bg_color).text_color(text_color),
+            |d| d.text_color(inactive_text_color),
+          )
+          .on_click(cx.listener(move |this, _, _window, cx| this.activate_tab(tab_ix, cx)))
+          .child(name)
+          .child(
+            div()
+              .id(("tab-close", tab_ix))
+              .child(if self.is_tab_dirty(tab_ix) {
+                "●"
+              } else {
+                "×"
+              })
+              .on_click(cx.listener(move |this, _, _window, cx| {
+                cx.stop_propagation();
+                this.close_tab(tab_ix, cx);
+              })),
+          )
+      }))
+  }
+
+  /// Placeholder shown while the file or the baseline is read
+  fn render_loading(&self, loading: &Loading, cx: &mut Context<Self>) -> impl IntoElement {
+    let theme = self.get_theme();
+    let fraction = loading.progress.lock().unwrap().fraction();
+
+    self.render_placeholder(
+      div()
+        .flex()
+        .flex_col()
+        .items_center()
+        .gap(px(8.0))
+        .child(loading.label())
+        .child(
+          div()
+            .w(px(LOAD_PROGRESS_WIDTH))
+            .h(px(4.0))
+            .bg(theme.scrollbar.track_color)
+            .child(
+              div()
+                .h_full()
+                .w(px(LOAD_PROGRESS_WIDTH * fraction))
+                .bg(theme.scrollbar.thumb_color),
+            ),
+        ),
+      cx,
+    )
+  }
+
+  /// Placeholder shown when the open file couldn't be read
+  fn render_load_failed(&self, error: &LoadError, cx: &mut Context<Self>) -> impl IntoElement {
+    let theme = self.get_theme();
+
+    self.render_placeholder(
+      div()
+        .flex()
+        .flex_col()
+        .items_center()
+        .gap(px(8.0))
+        .child(error.label())
+        .child(
+          div()
+            .px_2()
+            .cursor_pointer()
+            .border_1()
+            .border_color(theme.line_numbers.text_color.alpha(0.3))
+            .on_mouse_down(
+              MouseButton::Left,
+              cx.listener(|this, _, _window, cx| {
+                cx.stop_propagation();
+                this.retry_load(cx);
+              }),
+            )
+            .child("Retry"),
+        ),
+      cx,
+    )
+  }
+
+  /// Placeholder of binary diffs outside the hex view
+  fn render_binary(&self, cx: &mut Context<Self>) -> impl IntoElement {
+    let original = self
+      .baseline_bytes()
+      .unwrap_or(self.compare_content.as_bytes());
+    let current = self.file_bytes.as_deref().unwrap_or(original);
+    let text = if original == current {
+      "Binary files are identical".to_string()
+    } else {
+      format!(
+        "Binary files differ ({} → {})",
+        format_size(original.len()),
+        format_size(current.len())
+      )
+    };
+
+    self.render_placeholder(text, cx)
+  }
+
+  /// Both images side by side, one over the other or their pixel difference, with the mode
+  /// switcher above them
+  fn render_image_diff(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+    let sides = self.image_sides();
+    let theme = self.get_theme();
+    let text_color = theme.code.text_color;
+    let muted_color = theme.line_numbers.text_color;
+    let border_color = muted_color.alpha(0.3);
+    let mode = self.config.image_diff;
+
+    let button = |label: String, active: bool| {
+      div()
+        .px_2()
+        .cursor_pointer()
+        .border_1()
+        .border_color(border_color)
+        .when(active, |d| d.text_color(text_color).bg(border_color))
+        .child(label)
+    };
+    let mut toolbar = div().flex().items_center().gap(px(8.0)).p_2();
+    for option in ImageDiffMode::ALL {
+      toolbar = toolbar.child(
+        button(option.label().to_string(), option == mode).on_mouse_down(
+          MouseButton::Left,
+          cx.listener(move |this, _, _window, cx| {
+            cx.stop_propagation();
+            this.set_image_diff_mode(option, cx);
+          }),
+        ),
+      );
+    }
+    match mode {
+      ImageDiffMode::SideBySide => {}
+      ImageDiffMode::OnionSkin => {
+        let mix = self.onion_skin_mix;
+        toolbar = toolbar
+          .child(button("−".to_string(), false).on_mouse_down(
+            MouseButton::Left,
+            cx.listener(move |this, _, _window, cx| {
+              cx.stop_propagation();
+              this.set_onion_skin_mix(mix - ONION_SKIN_STEP, cx);
+            }),
+          ))
+          .child(format!("{:.0}% modified", mix * 100.0))
+          .child(button("+".to_string(), false).on_mouse_down(
+            MouseButton::Left,
+            cx.listener(move |this, _, _window, cx| {
+              cx.stop_propagation();
+              this.set_onion_skin_mix(mix + ONION_SKIN_STEP, cx);
+            }),
+          ));
+      }
+      ImageDiffMode::Difference => {
+        toolbar = toolbar.child(match sides.difference() {
+          Some((_, count)) if count.changed == 0 => "Images are identical".to_string(),
+          Some((_, count)) => format!("{} of {} pixels differ", count.changed, count.total),
+          None => "Images couldn't be compared".to_string(),
+        });
+      }
+    }
+
+    let image = |side: &Option<ImageSide>| match side {
+      Some(side) => img(side.image.clone())
+        .size_full()
+        .object_fit(ObjectFit::Contain)
+        .into_any_element(),
+      None => div().child("No image").into_any_element(),
+    };
+    let caption =
+      |label: &str, side: &Option<ImageSide>| match side.as_ref().and_then(|side| side.size) {
+        Some((width, height)) => format!("{} {}×{}", label, width, height),
+        None => label.to_string(),
+      };
+    let pane = |content: AnyElement| {
+      div()
+        .relative()
+        .flex_1()
+        .min_w(px(0.0))
+        .min_h(px(0.0))
+        .flex()
+        .items_center()
+        .justify_center()
+        .child(content)
+    };
+
+    let content = match mode {
+      ImageDiffMode::SideBySide => div()
+        .flex()
+        .flex_1()
+        .min_h(px(0.0))
+        .gap(px(8.0))
+        .p_2()
+        .child(
+          div()
+            .flex()
+            .flex_col()
+            .flex_1()
+            .min_w(px(0.0))
+            .child(caption("Original", &sides.original))
+            .child(pane(image(&sides.original))),
+        )
+        .child(
+          div()
+            .flex()
+            .flex_col()
+            .flex_1()
+            .min_w(px(0.0))
+            .child(caption("Modified", &sides.current))
+            .child(pane(image(&sides.current))),
+        )
+        .into_any_element(),
+      ImageDiffMode::OnionSkin => pane(
+        div()
+          .relative()
+          .size_full()
+          .child(div().absolute().size_full().child(image(&sides.original)))
+          .child(
+            div()
+              .absolute()
+              .size_full()
+              .opacity(self.onion_skin_mix)
+              .child(image(&sides.current)),
+          )
+          .into_any_element(),
+      )
+      .p_2()
+      .into_any_element(),
+      ImageDiffMode::Difference => pane(match sides.difference() {
+        Some((difference, _)) => img(difference.clone())
+          .size_full()
+          .object_fit(ObjectFit::Contain)
+          .into_any_element(),
+        None => div().into_any_element(),
+      })
+      .p_2()
+      .into_any_element(),
+    };
+
+    self.render_placeholder(
+      div()
+        .size_full()
+        .flex()
+        .flex_col()
+        .child(toolbar)
+        .child(content),
+      cx,
+    )
+  }
+
+  /// Changes of a JSON or TOML file by value, under a header per object or array holding them
+  fn render_structured_diff(
+    &self,
+    diff: &StructuredDiff,
+    cx: &mut Context<Self>,
   ) -> impl IntoElement {
-    let line_cache = self.line_cache.clone();
-    let line_height = self.config.line_height();
-    let font_size = self.config.font_size;
     let theme = self.get_theme();
     let text_color = theme.code.text_color;
-    let cursor_color = theme.cursor.color;
-    let item_count = diff_lines.len();
+    let muted_color = theme.line_numbers.text_color;
+    let border_color = muted_color.alpha(0.3);
+
+    let summary = match diff.changes().count() {
+      0 => "No changes by value".to_string(),
+      1 => "1 value changed".to_string(),
+      count => format!("{} values changed", count),
+    };
+    let toolbar = div()
+      .flex()
+      .items_center()
+      .gap(px(8.0))
+      .p_2()
+      .child(summary)
+      .child(
+        div()
+          .px_2()
+          .cursor_pointer()
+          .border_1()
+          .border_color(border_color)
+          .on_mouse_down(
+            MouseButton::Left,
+            cx.listener(|this, _, _window, cx| {
+              cx.stop_propagation();
+              this.set_structured_diff(false, cx);
+            }),
+          )
+          .child("Text diff"),
+      );
+
+    let mut list = div()
+      .id("structured-diff")
+      .flex_1()
+      .min_h(px(0.0))
+      .overflow_y_scroll()
+      .px_2()
+      .font_family(self.font.family.clone())
+      .text_color(text_color);
+    for group in &diff.groups {
+      let header = if group.path.is_root() {
+        "(root)".to_string()
+      } else {
+        group.path.to_string()
+      };
+      list = list.child(
+        div()
+          .pt_2()
+          .text_color(muted_color)
+          .border_b_1()
+          .border_color(border_color)
+          .child(header),
+      );
+      for change in &group.changes {
+        let (marker, color, value) = match change.kind {
+          ValueChangeKind::Added => (
+            "+",
+            theme.git.added.line_bg_color,
+            change.modified.clone().unwrap_or_default(),
+          ),
+          ValueChangeKind::Removed => (
+            "-",
+            theme.git.removed.line_bg_color,
+            change.original.clone().unwrap_or_default(),
+          ),
+          ValueChangeKind::Changed => (
+            "~",
+            theme.git.modified.line_bg_color,
+            format!(
+              "{} → {}",
+              change.original.as_deref().unwrap_or_default(),
+              change.modified.as_deref().unwrap_or_default()
+            ),
+          ),
+        };
+        list = list.child(
+          div()
+            .flex()
+            .gap(px(8.0))
+            .bg(color)
+            .child(marker)
+            .child(format!("{}: {}", change.path.last(), value)),
+        );
+      }
+    }
+
+    self.render_placeholder(
+      div()
+        .size_full()
+        .flex()
+        .flex_col()
+        .child(toolbar)
+        .child(list),
+      cx,
+    )
+  }
+
+  /// Paragraphs of a prose file with removed words struck through next to the added ones
+  fn render_prose_diff(
+    &self,
+    paragraphs: &[ProseParagraph],
+    cx: &mut Context<Self>,
+  ) -> impl IntoElement {
+    let theme = self.get_theme();
+    let text_color = theme.code.text_color;
+    let muted_color = theme.line_numbers.text_color;
+    let border_color = muted_color.alpha(0.3);
+    let granularity = self.config.prose_granularity_for(&self.file_path);
+
+    let button = |label: &'static str, active: bool| {
+      div()
+        .px_2()
+        .cursor_pointer()
+        .border_1()
+        .border_color(border_color)
+        .when(active, |d| d.text_color(text_color).bg(border_color))
+        .child(label)
+    };
+    let summary = match paragraphs.iter().filter(|p| p.has_changes()).count() {
+      0 => "No changes".to_string(),
+      1 => "1 paragraph changed".to_string(),
+      count => format!("{} paragraphs changed", count),
+    };
+    let mut toolbar = div()
+      .flex()
+      .items_center()
+      .gap(px(8.0))
+      .p_2()
+      .child(summary);
+    for (option, label) in [
+      (Some(ProseGranularity::Word), "Words"),
+      (Some(ProseGranularity::Sentence), "Sentences"),
+      (None, "Lines"),
+    ] {
+      toolbar = toolbar.child(button(label, option == granularity).on_mouse_down(
+        MouseButton::Left,
+        cx.listener(move |this, _, _window, cx| {
+          cx.stop_propagation();
+          this.set_prose_granularity(option, cx);
+        }),
+      ));
+    }
+
+    let added_color = theme.git.added.char_highlight_color;
+    let removed_color = theme.git.removed.char_highlight_color;
+    let mut list = div()
+      .id("prose-diff")
+      .flex_1()
+      .min_h(px(0.0))
+      .overflow_y_scroll()
+      .px_4()
+      .flex()
+      .flex_col()
+      .gap(px(12.0))
+      .text_color(text_color);
+    for paragraph in paragraphs {
+      let mut text = String::new();
+      let mut highlights = Vec::new();
+      for span in &paragraph.spans {
+        if !text.is_empty() {
+          text.push(' ');
+        }
+        let start = text.len();
+        text.push_str(&span.text);
+        let style = match span.kind {
+          ProseSpanKind::Unchanged => continue,
+          ProseSpanKind::Added => HighlightStyle {
+            background_color: Some(added_color),
+            ..Default::default()
+          },
+          ProseSpanKind::Removed => HighlightStyle {
+            background_color: Some(removed_color),
+            strikethrough: Some(StrikethroughStyle {
+              thickness: px(1.0),
+              color: Some(text_color),
+            }),
+            ..Default::default()
+          },
+        };
+        highlights.push((start..text.len(), style));
+      }
+      list = list.child(div().child(StyledText::new(text).with_highlights(highlights)));
+    }
+
+    self.render_placeholder(
+      div()
+        .size_full()
+        .flex()
+        .flex_col()
+        .child(toolbar)
+        .child(list),
+      cx,
+    )
+  }
+
+  /// Centers `content` in place of the diff, tabs can still be switched
+  fn render_placeholder(
+    &self,
+    content: impl IntoElement,
+    cx: &mut Context<Self>,
+  ) -> impl IntoElement {
+    let theme = self.get_theme();
+
+    div()
+      .id("editor-view")
+      .key_context(KEY_CONTEXT)
+      .track_focus(&self.focus_handle)
+      .size_full()
+      .bg(theme.code.bg_color)
+      .text_size(px(self.config.font_size))
+      .on_action(cx.listener(Self::close_active_tab))
+      .on_action(cx.listener(Self::activate_next_tab))
+      .on_action(cx.listener(Self::activate_previous_tab))
+      .on_action(cx.listener(Self::toggle_structured_diff))
+      .on_action(cx.listener(Self::toggle_prose_diff))
+      .flex()
+      .flex_col()
+      .when(!self.tabs.is_empty(), |d| d.child(self.render_tab_bar(cx)))
+      .child(
+        div()
+          .flex_1()
+          .flex()
+          .items_center()
+          .justify_center()
+          .text_color(theme.line_numbers.text_color)
+          .child(content),
+      )
+  }
+
+  fn render_save_conflict(&self, conflict: &SaveConflict, cx: &mut Context<Self>) -> Dialog {
+    let theme = self.get_theme();
+    let name = self
+      .file_path
+      .file_name()
+      .map(|name| name.to_string_lossy())
+      .unwrap_or_else(|| self.file_path.to_string_lossy());
+    let this = cx.entity().downgrade();
+    let action = |action: fn(&mut Self, &mut Context<Self>)| {
+      let this = this.clone();
+      move |_: &mut Window, cx: &mut App| {
+        this.update(cx, |editor, cx| action(editor, cx)).ok();
+      }
+    };
+
+    let dialog = Dialog::new(
+      "File changed on disk",
+      format!(
+        "{} was changed by another program since it was opened, saving overwrites those changes",
+        name
+      ),
+      theme.status_bar.clone(),
+    )
+    .button("Overwrite", action(Self::overwrite_disk_changes))
+    .button("Reload", action(Self::reload_disk_changes));
+    let dialog = match &conflict.diff {
+      Some(diff) => dialog.body(diff.clone()),
+      None => dialog.button("Show diff", action(Self::show_disk_changes)),
+    };
+    dialog.button("Cancel", action(Self::cancel_save))
+  }
+
+  /// Bar above the editor offering the unsaved edits of a previous run
+  fn render_recovery_prompt(&self, cx: &mut Context<Self>) -> impl IntoElement {
+    let theme = self.get_theme();
+    let button = |label: &'static str| {
+      div()
+        .px_2()
+        .cursor_pointer()
+        .border_1()
+        .border_color(theme.status_bar.text_color.alpha(0.3))
+        .child(label)
+    };
+
+    div()
+      .flex()
+      .flex_none()
+      .items_center()
+      .gap_3()
+      .h(px(STATUS_BAR_HEIGHT))
+      .w_full()
+      .px_2()
+      .text_size(px(self.config.font_size * 0.8))
+      .bg(theme.status_bar.bg_color)
+      .text_color(theme.status_bar.text_color)
+      .child(
+        div()
+          .flex_1()
+          .min_w(px(0.0))
+          .overflow_hidden()
+          .child("Unsaved changes from a previous session were found"),
+      )
+      .child(button("Restore").on_mouse_down(
+        MouseButton::Left,
+        cx.listener(|this, _, _window, cx| {
+          cx.stop_propagation();
+          this.restore_recovered_edits(cx);
+        }),
+      ))
+      .child(button("Discard").on_mouse_down(
+        MouseButton::Left,
+        cx.listener(|this, _, _window, cx| {
+          cx.stop_propagation();
+          this.discard_recovered_edits(cx);
+        }),
+      ))
+  }
+
+  /// Banner of a baseline or a reload that couldn't be read, the buffer stays as it was
+  fn render_load_error_banner(
+    &self,
+    error: &LoadError,
+    cx: &mut Context<Self>,
+  ) -> impl IntoElement {
+    let theme = self.get_theme();
+    let button = |label: &'static str| {
+      div()
+        .px_2()
+        .cursor_pointer()
+        .border_1()
+        .border_color(theme.status_bar.text_color.alpha(0.3))
+        .child(label)
+    };
+
+    div()
+      .flex()
+      .flex_none()
+      .items_center()
+      .gap_3()
+      .h(px(STATUS_BAR_HEIGHT))
+      .w_full()
+      .px_2()
+      .text_size(px(self.config.font_size * 0.8))
+      .bg(theme.status_bar.bg_color)
+      .text_color(theme.diagnostics.error)
+      .child(
+        div()
+          .flex_1()
+          .min_w(px(0.0))
+          .overflow_hidden()
+          .child(error.label()),
+      )
+      .child(button("Retry").on_mouse_down(
+        MouseButton::Left,
+        cx.listener(|this, _, _window, cx| {
+          cx.stop_propagation();
+          this.retry_load(cx);
+        }),
+      ))
+      .child(button("Dismiss").on_mouse_down(
+        MouseButton::Left,
+        cx.listener(|this, _, _window, cx| {
+          cx.stop_propagation();
+          this.dismiss_load_error(cx);
+        }),
+      ))
+  }
+
+  fn render_status_bar(
+    &self,
+    stats: DiffStats,
+    (reviewed, total): (usize, usize),
+  ) -> impl IntoElement {
+    let theme = self.get_theme();
+    let (line, col) = self
+      .editor
+      .buffer
+      .char_to_line_col(self.editor.cursor.index);
+    let path = self.file_path.to_string_lossy().to_string();
+
+    div()
+      .flex()
+      .flex_none()
+      .items_center()
+      .gap_3()
+      .h(px(STATUS_BAR_HEIGHT))
+      .w_full()
+      .px_2()
+      .text_size(px(self.config.font_size * 0.8))
+      .bg(theme.status_bar.bg_color)
+      .text_color(theme.status_bar.text_color)
+      .child(
+        div()
+          .flex()
+          .gap_1()
+          .child(
+            div()
+              .text_color(theme.git.added.gutter_color)
+              .child(format!("+{}", stats.added)),
+          )
+          .child(
+            div()
+              .text_color(theme.git.removed.gutter_color)
+              .child(format!("−{}", stats.removed)),
+          )
+          .child(
+            div()
+              .text_color(theme.git.modified.gutter_color)
+              .child(format!("~{}", stats.modified)),
+          ),
+      )
+      .child(format!("{}:{}", line + 1, col + 1))
+      .child(if self.file_bytes.is_some() {
+        "Binary"
+      } else {
+        self.encoding().label()
+      })
+      .child(div().flex_1().min_w(px(0.0)).overflow_hidden().child(path))
+      .when(total > 0, |d| {
+        d.child(format!(
+          "{}/{} {} reviewed",
+          reviewed,
+          total,
+          if total == 1 { "hunk" } else { "hunks" }
+        ))
+      })
+      .when(self.is_watching(), |d| d.child(self.watch_label.clone()))
+      .when(self.is_dirty, |d| d.child("●"))
+  }
+
+  fn save_file(&mut self, _: &SaveFile, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    if self.config.trim_trailing_whitespace && self.editor.trim_trailing_whitespace() {
+      self.mark_dirty(cx);
+    }
+    self.save(cx);
+  }
+
+  /// Writes the buffer as is, autosaves don't trim the whitespace being typed
+  ///
+  /// Held back when the file changed on disk since, the conflict dialog asks what to do then
+  fn save(&mut self, cx: &mut Context<Self>) {
+    if let Some(disk_content) = self.disk_conflict() {
+      self.autosave_timer.cancel();
+      self.save_conflict = Some(SaveConflict {
+        disk_content,
+        diff: None,
+      });
+      cx.notify();
+      return;
+    }
+    self.write_file(cx);
+  }
+
+  /// Text of the file when it changed on disk since it was last read or written
+  fn disk_conflict(&self) -> Option<String> {
+    if !self.file_backed || modified_time(&self.file_path) == self.disk_modified {
+      return None;
+    }
+    // Only touched, or deleted: writing loses nothing
+    let disk_content = TextBuffer::from_file(&self.file_path).ok()?.as_str();
+    (Some(content_hash(&disk_content)) != self.disk_hash).then_some(disk_content)
+  }
+
+  pub fn has_save_conflict(&self) -> bool {
+    self.save_conflict.is_some()
+  }
+
+  /// Saves over the changes made on disk
+  pub fn overwrite_disk_changes(&mut self, cx: &mut Context<Self>) {
+    if self.save_conflict.take().is_some() {
+      self.write_file(cx);
+    }
+  }
+
+  /// Throws the unsaved edits away for the content on disk
+  pub fn reload_disk_changes(&mut self, cx: &mut Context<Self>) {
+    if self.save_conflict.take().is_none() {
+      return;
+    }
+    self.journal_timer.cancel();
+    if let Some(journal) = self.journal.take() {
+      discard_journal(journal);
+    }
+    self.set_dirty(false, cx);
+    self.reload_file(cx);
+    cx.notify();
+  }
+
+  /// Shows the content on disk diffed against the buffer in the conflict dialog
+  pub fn show_disk_changes(&mut self, cx: &mut Context<Self>) {
+    let Some(conflict) = self
+      .save_conflict
+      .as_mut()
+      .filter(|conflict| conflict.diff.is_none())
+    else {
+      return;
+    };
+
+    let mut config = self.config.clone();
+    config.theme_mode = if self.dark_mode {
+      ThemeMode::Dark
+    } else {
+      ThemeMode::Light
+    };
+    let path = self.file_path.clone();
+    let text = self.editor.buffer.as_str();
+    let disk_content = conflict.disk_content.clone();
+    conflict.diff = Some(cx.new(|cx| DiffEditor::from_text(path, &text, disk_content, config, cx)));
+    cx.notify();
+  }
+
+  /// Keeps the edits unsaved
+  pub fn cancel_save(&mut self, cx: &mut Context<Self>) {
+    self.save_conflict = None;
+    cx.notify();
+  }
+
+  fn write_file(&mut self, cx: &mut Context<Self>) {
+    match self.editor.buffer.save_to_file(&self.file_path) {
+      Ok(_) => {
+        self.disk_modified = modified_time(&self.file_path);
+        self.disk_hash = Some(content_hash(&self.editor.buffer.as_str()));
+        self.autosave_timer.cancel();
+        self.journal_timer.cancel();
+        if let Some(journal) = self.journal.take() {
+          discard_journal(journal);
+        }
+        self.set_dirty(false, cx);
+        cx.emit(DiffEditorEvent::Saved);
+        println!("File saved: {:?}", self.file_path);
+        cx.notify();
+      }
+      Err(e) => {
+        eprintln!("Failed to save file: {}", e);
+      }
+    }
+  }
 
-    let line_config = LineConfig {
-      font_size,
-      line_height,
-      text_color,
-      cursor_color,
+  fn move_left(&mut self, _: &MoveLeft, _window: &mut Window, cx: &mut Context<Self>) {
+    self.editor.clear_selection();
+    self.editor.cursor.move_left();
+    cx.notify();
+  }
+
+  fn move_right(&mut self, _: &MoveRight, _window: &mut Window, cx: &mut Context<Self>) {
+    self.editor.clear_selection();
+    self.editor.cursor.move_right(self.editor.buffer.len());
+    cx.notify();
+  }
+
+  fn move_up(&mut self, _: &MoveUp, _window: &mut Window, cx: &mut Context<Self>) {
+    self.editor.clear_selection();
+    self.editor.cursor.move_up(&self.editor.buffer);
+    self.skip_folded_lines(|editor| editor.cursor.move_up(&editor.buffer));
+    cx.notify();
+  }
+
+  fn move_down(&mut self, _: &MoveDown, _window: &mut Window, cx: &mut Context<Self>) {
+    self.editor.clear_selection();
+    self.editor.cursor.move_down(&self.editor.buffer);
+    self.skip_folded_lines(|editor| editor.cursor.move_down(&editor.buffer));
+    cx.notify();
+  }
+
+  fn move_word_left(&mut self, _: &MoveWordLeft, _window: &mut Window, cx: &mut Context<Self>) {
+    self.editor.clear_selection();
+    self.editor.move_word_left();
+    cx.notify();
+  }
+
+  fn move_word_right(&mut self, _: &MoveWordRight, _window: &mut Window, cx: &mut Context<Self>) {
+    self.editor.clear_selection();
+    self.editor.move_word_right();
+    cx.notify();
+  }
+
+  fn move_to_line_start(
+    &mut self,
+    _: &MoveToLineStart,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.editor.clear_selection();
+    self.editor.cursor.move_to_line_start(&self.editor.buffer);
+    cx.notify();
+  }
+
+  fn move_to_line_end(&mut self, _: &MoveToLineEnd, _window: &mut Window, cx: &mut Context<Self>) {
+    self.editor.clear_selection();
+    self.editor.cursor.move_to_line_end(&self.editor.buffer);
+    cx.notify();
+  }
+
+  fn move_to_buffer_start(
+    &mut self,
+    _: &MoveToBufferStart,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.editor.clear_selection();
+    self.editor.cursor.move_to_buffer_start();
+    cx.notify();
+  }
+
+  fn move_to_buffer_end(
+    &mut self,
+    _: &MoveToBufferEnd,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.editor.clear_selection();
+    self.editor.cursor.move_to_buffer_end(&self.editor.buffer);
+    cx.notify();
+  }
+
+  fn page_up(&mut self, _: &PageUp, _window: &mut Window, cx: &mut Context<Self>) {
+    self.move_by_page(false, false, cx);
+  }
+
+  fn page_down(&mut self, _: &PageDown, _window: &mut Window, cx: &mut Context<Self>) {
+    self.move_by_page(true, false, cx);
+  }
+
+  fn center_cursor(&mut self, _: &CenterCursor, _window: &mut Window, cx: &mut Context<Self>) {
+    let cursor_line = self.cursor_line();
+    if let Some(row) = self.current_display_map().line_row(cursor_line) {
+      self.scroll_to_row(row);
+    }
+    cx.notify();
+  }
+
+  fn select_left(&mut self, _: &SelectLeft, _window: &mut Window, cx: &mut Context<Self>) {
+    self.editor.extend_selection_left();
+    cx.notify();
+  }
+
+  fn select_right(&mut self, _: &SelectRight, _window: &mut Window, cx: &mut Context<Self>) {
+    self.editor.extend_selection_right();
+    cx.notify();
+  }
+
+  fn select_up(&mut self, _: &SelectUp, _window: &mut Window, cx: &mut Context<Self>) {
+    self.editor.extend_selection_up();
+    self.skip_folded_lines(Editor::extend_selection_up);
+    cx.notify();
+  }
+
+  fn select_down(&mut self, _: &SelectDown, _window: &mut Window, cx: &mut Context<Self>) {
+    self.editor.extend_selection_down();
+    self.skip_folded_lines(Editor::extend_selection_down);
+    cx.notify();
+  }
+
+  fn select_page_up(&mut self, _: &SelectPageUp, _window: &mut Window, cx: &mut Context<Self>) {
+    self.move_by_page(false, true, cx);
+  }
+
+  fn select_page_down(&mut self, _: &SelectPageDown, _window: &mut Window, cx: &mut Context<Self>) {
+    self.move_by_page(true, true, cx);
+  }
+
+  fn select_word_left(&mut self, _: &SelectWordLeft, _window: &mut Window, cx: &mut Context<Self>) {
+    self.editor.extend_selection_word_left();
+    cx.notify();
+  }
+
+  fn select_word_right(
+    &mut self,
+    _: &SelectWordRight,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.editor.extend_selection_word_right();
+    cx.notify();
+  }
+
+  fn select_to_line_start(
+    &mut self,
+    _: &SelectToLineStart,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.editor.extend_selection_to_line_start();
+    cx.notify();
+  }
+
+  fn select_to_line_end(
+    &mut self,
+    _: &SelectToLineEnd,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.editor.extend_selection_to_line_end();
+    cx.notify();
+  }
+
+  fn select_to_buffer_start(
+    &mut self,
+    _: &SelectToBufferStart,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.editor.extend_selection_to_buffer_start();
+    cx.notify();
+  }
+
+  fn select_to_buffer_end(
+    &mut self,
+    _: &SelectToBufferEnd,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.editor.extend_selection_to_buffer_end();
+    cx.notify();
+  }
+
+  fn select_all(&mut self, _: &SelectAll, _window: &mut Window, cx: &mut Context<Self>) {
+    self.editor.select_all();
+    cx.notify();
+  }
+
+  fn expand_selection(
+    &mut self,
+    _: &ExpandSelection,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.editor.expand_selection();
+    cx.notify();
+  }
+
+  fn shrink_selection(
+    &mut self,
+    _: &ShrinkSelection,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    self.editor.shrink_selection();
+    cx.notify();
+  }
+
+  fn backspace(&mut self, _: &Backspace, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    if self.editor.has_selection() {
+      self.editor.delete_selection();
+    } else {
+      self.editor.backspace();
+    }
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn delete_word(&mut self, _: &DeleteWord, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    if self.editor.has_selection() {
+      self.editor.delete_selection();
+    } else {
+      self.editor.delete_word();
+    }
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn delete(&mut self, _: &Delete, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    if self.editor.has_selection() {
+      self.editor.delete_selection();
+    } else {
+      self.editor.delete();
+    }
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn delete_word_forward(
+    &mut self,
+    _: &DeleteWordForward,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    if self.is_read_only() {
+      return;
+    }
+
+    if self.editor.has_selection() {
+      self.editor.delete_selection();
+    } else {
+      self.editor.delete_word_forward();
+    }
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn delete_to_line_end(
+    &mut self,
+    _: &DeleteToLineEnd,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    if self.is_read_only() {
+      return;
+    }
+
+    if self.editor.has_selection() {
+      self.editor.delete_selection();
+    } else {
+      self.editor.delete_to_line_end();
+    }
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn delete_line(&mut self, _: &DeleteLine, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    if self.editor.has_selection() {
+      self.editor.delete_selection();
+    } else {
+      self.editor.delete_line();
+    }
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn newline(&mut self, _: &Newline, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    self.editor.delete_selection();
+    self.editor.insert_char('\n');
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn tab(&mut self, _: &Tab, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    let indent_unit = self.config.indent_unit();
+    if self.editor.has_multiline_selection() {
+      self.editor.indent_lines(&indent_unit);
+    } else {
+      self.editor.replace_selection(&indent_unit);
+    }
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn duplicate_lines_up(
+    &mut self,
+    _: &DuplicateLinesUp,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    if self.is_read_only() {
+      return;
+    }
+
+    self.editor.duplicate_lines_up();
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn duplicate_lines_down(
+    &mut self,
+    _: &DuplicateLinesDown,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    if self.is_read_only() {
+      return;
+    }
+
+    self.editor.duplicate_lines_down();
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn move_lines_up(&mut self, _: &MoveLinesUp, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    self.editor.move_lines_up();
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn move_lines_down(&mut self, _: &MoveLinesDown, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    self.editor.move_lines_down();
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  /// Toggles line comments, or a block comment in languages without line comments
+  fn toggle_comment(&mut self, _: &ToggleComment, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+    let Some(tokens) = self.config.comment_tokens_for(&self.file_path).cloned() else {
+      return;
+    };
+
+    match (tokens.line, tokens.block) {
+      (Some(token), _) => self.editor.toggle_line_comments(&token),
+      (None, Some((open, close))) => self.editor.toggle_block_comment(&open, &close),
+      (None, None) => return,
+    }
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn toggle_block_comment(
+    &mut self,
+    _: &ToggleBlockComment,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    if self.is_read_only() {
+      return;
+    }
+    let Some((open, close)) = self
+      .config
+      .comment_tokens_for(&self.file_path)
+      .and_then(|tokens| tokens.block.clone())
+    else {
+      return;
     };
 
-    let theme = self.get_theme();
-    let added_line_bg_color = theme.git.added.line_bg_color;
-    let added_char_highlight_color = theme.git.added.char_highlight_color;
-    let removed_line_bg_color = theme.git.removed.line_bg_color;
-    let removed_char_highlight_color = theme.git.removed.char_highlight_color;
+    self.editor.toggle_block_comment(&open, &close);
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn undo(&mut self, _: &Undo, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    if self.editor.undo() {
+      self.mark_dirty(cx);
+      cx.notify();
+    }
+  }
+
+  fn redo(&mut self, _: &Redo, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    if self.editor.redo() {
+      self.mark_dirty(cx);
+      cx.notify();
+    }
+  }
+
+  fn indent(&mut self, _: &Indent, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    self.editor.indent_lines(&self.config.indent_unit());
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn outdent(&mut self, _: &Outdent, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    self.editor.outdent_lines(self.config.tab_size);
+    self.mark_dirty(cx);
+    cx.notify();
+  }
+
+  fn copy(&mut self, _: &Copy, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.config.copy_removed_lines
+      && let Some(selection) = self.editor.selection_range()
+    {
+      let text = text_with_removed_lines(&self.compute_diff(), &self.editor.buffer, selection);
+      cx.write_to_clipboard(clipboard_item(ClipboardText {
+        text,
+        whole_line: false,
+      }));
+      return;
+    }
+
+    if let Some(clipboard) = self.editor.copy() {
+      cx.write_to_clipboard(clipboard_item(clipboard));
+    }
+  }
+
+  fn copy_diff_as_markdown(
+    &mut self,
+    _: &CopyDiffAsMarkdown,
+    _window: &mut Window,
+    cx: &mut Context<Self>,
+  ) {
+    if let Some(markdown) = self.export_selection(ExportFormat::Markdown) {
+      cx.write_to_clipboard(ClipboardItem::new_string(markdown));
+    }
+  }
 
-    uniform_list(
-      "editor-lines",
-      item_count,
-      move |range: Range<usize>, _window, _cx| {
-        range
-          .map(|idx| {
-            let line = &diff_lines[idx];
+  /// The clipboard only carries text here, so the RTF document is copied as its source: hosts
+  /// that can put rich text on the platform clipboard use `export_selection` instead
+  fn copy_diff_as_rtf(&mut self, _: &CopyDiffAsRtf, _window: &mut Window, cx: &mut Context<Self>) {
+    if let Some(rtf) = self.export_selection(ExportFormat::Rtf) {
+      cx.write_to_clipboard(ClipboardItem::new_string(rtf));
+    }
+  }
 
-            // For removed/modified lines without line number, don't show cursor
-            // Use an impossible line_idx so the cursor won't be calculated for this line
-            let line_idx = if line.line_number == 0 {
-              usize::MAX
-            } else {
-              line.line_number - 1
-            };
-
-            // Create a modified editor_state that hides cursor on removed lines
-            let modified_editor_state = if line.line_number == 0 {
-              // Hide cursor by setting it to an impossible position
-              EditorState {
-                cursor_index: usize::MAX,
-                selection_range: editor_state.selection_range.clone(),
-              }
-            } else {
-              editor_state.clone()
-            };
-
-            // For removed lines, use text override since they're not in the buffer
-            let text_override = match line.kind {
-              DiffLineKind::Removed => Some(line.content.clone()),
-              DiffLineKind::Modified if line.line_number == 0 => Some(line.content.clone()),
-              _ => None,
-            };
-
-            let diff_bg = match line.kind {
-              DiffLineKind::Added => Some(DiffBackground {
-                color: added_line_bg_color,
-                char_highlights: line.char_changes.clone(),
-                highlight_color: added_char_highlight_color,
-              }),
-              DiffLineKind::Removed => Some(DiffBackground {
-                color: removed_line_bg_color,
-                char_highlights: line.char_changes.clone(),
-                highlight_color: removed_char_highlight_color,
-              }),
-              DiffLineKind::Modified if line.line_number == 0 => Some(DiffBackground {
-                color: removed_line_bg_color,
-                char_highlights: line.char_changes.clone(),
-                highlight_color: removed_char_highlight_color,
-              }),
-              DiffLineKind::Modified => Some(DiffBackground {
-                color: added_line_bg_color,
-                char_highlights: line.char_changes.clone(),
-                highlight_color: added_char_highlight_color,
-              }),
-              DiffLineKind::Unchanged => None,
-            };
-
-            let mut element = LineElement::new(
-              line_idx,
-              buffer.clone(),
-              modified_editor_state,
-              line_cache.clone(),
-              line_config.clone(),
-            );
-
-            if let Some(text) = text_override {
-              element = element.with_text_override(text);
-            }
+  fn cut(&mut self, _: &Cut, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
 
-            if let Some(bg) = diff_bg {
-              element = element.with_diff_background(bg);
-            }
+    if let Some(clipboard) = self.editor.cut() {
+      cx.write_to_clipboard(clipboard_item(clipboard));
+      self.mark_dirty(cx);
+      cx.notify();
+    }
+  }
 
-            element
-          })
-          .collect::<Vec<_>>()
-      },
-    )
-    .w_full()
-    .px(px(EDITOR_PADDING))
-    .cursor_text()
-    .track_scroll(scroll_handle)
+  fn paste(&mut self, _: &Paste, _window: &mut Window, cx: &mut Context<Self>) {
+    if self.is_read_only() {
+      return;
+    }
+
+    if let Some(item) = cx.read_from_clipboard()
+      && let Some(text) = item.text()
+    {
+      // Set by `clipboard_item` on lines copied without selection, other apps don't set it
+      let whole_line = item.entries().iter().any(|entry| match entry {
+        ClipboardEntry::String(string) => string.metadata_json::<bool>().unwrap_or(false),
+        _ => false,
+      });
+      self
+        .editor
+        .paste_clipboard(&ClipboardText { text, whole_line });
+      self.mark_dirty(cx);
+      cx.notify();
+    }
   }
 
+  /// Handles text input, keys bound in the keymap are dispatched as actions instead
   fn on_key_down(&mut self, event: &KeyDownEvent, _window: &mut Window, cx: &mut Context<Self>) {
-    let shift = event.keystroke.modifiers.shift;
-    let cmd = event.keystroke.modifiers.platform;
-    let alt = event.keystroke.modifiers.alt;
-    let config = &self.config;
-
-    match event.keystroke.key.as_str() {
-      "s" if cmd && !shift && !alt => match self.editor.buffer.save_to_file(&self.file_path) {
-        Ok(_) => {
-          self.is_dirty = false;
-          println!("File saved: {:?}", self.file_path);
-          cx.notify();
-        }
-        Err(e) => {
-          eprintln!("Failed to save file: {}", e);
-        }
-      },
-      "left" => {
-        if cmd && shift {
-          self.editor.extend_selection_to_line_start();
-        } else if cmd {
-          self.editor.clear_selection();
-          self.editor.cursor.move_to_line_start(&self.editor.buffer);
-        } else if alt && shift {
-          self.editor.extend_selection_word_left();
-        } else if alt {
-          self.editor.clear_selection();
-          self.editor.cursor.move_word_left(&self.editor.buffer);
-        } else if shift {
-          self.editor.extend_selection_left();
-        } else {
-          self.editor.clear_selection();
-          self.editor.cursor.move_left();
-        }
-      }
-      "right" => {
-        if cmd && shift {
-          self.editor.extend_selection_to_line_end();
-        } else if cmd {
-          self.editor.clear_selection();
-          self.editor.cursor.move_to_line_end(&self.editor.buffer);
-        } else if alt && shift {
-          self.editor.extend_selection_word_right();
-        } else if alt {
-          self.editor.clear_selection();
-          self.editor.cursor.move_word_right(&self.editor.buffer);
-        } else if shift {
-          self.editor.extend_selection_right();
-        } else {
-          self.editor.clear_selection();
-          self.editor.cursor.move_right(self.editor.buffer.len());
-        }
-      }
-      "up" => {
-        if cmd && shift {
-          self.editor.extend_selection_to_buffer_start();
-        } else if cmd {
-          self.editor.clear_selection();
-          self.editor.cursor.move_to_buffer_start();
-        } else if shift {
-          self.editor.extend_selection_up();
-        } else {
-          self.editor.clear_selection();
-          self.editor.cursor.move_up(&self.editor.buffer);
-        }
-      }
-      "down" => {
-        if cmd && shift {
-          self.editor.extend_selection_to_buffer_end();
-        } else if cmd {
-          self.editor.clear_selection();
-          self.editor.cursor.move_to_buffer_end(&self.editor.buffer);
-        } else if shift {
-          self.editor.extend_selection_down();
-        } else {
-          self.editor.clear_selection();
-          self.editor.cursor.move_down(&self.editor.buffer);
-        }
-      }
-      "backspace" => {
-        if self.editor.has_selection() {
-          self.editor.delete_selection();
-        } else if cmd {
-          self.editor.delete_line();
-        } else if alt {
-          self.editor.delete_word();
+    let modifiers = &event.keystroke.modifiers;
+    if self.is_read_only() || self.config.keymap.platform().is_shortcut(modifiers) {
+      return;
+    }
+
+    let char = match event.keystroke.key.as_str() {
+      "space" => ' ',
+      key if key.chars().count() == 1 => {
+        let Some(c) = key.chars().next() else {
+          return;
+        };
+        if modifiers.shift {
+          c.to_ascii_uppercase()
         } else {
-          self.editor.backspace();
-        }
-        self.mark_dirty();
-      }
-      "enter" => {
-        self.editor.delete_selection();
-        self.editor.insert_char('\n');
-        self.mark_dirty();
-      }
-      "a" if cmd => {
-        self.editor.select_all();
-      }
-      "c" if cmd => {
-        if let Some(text) = self.editor.copy() {
-          cx.write_to_clipboard(ClipboardItem::new_string(text));
-        }
-      }
-      "x" if cmd => {
-        if let Some(text) = self.editor.cut() {
-          cx.write_to_clipboard(ClipboardItem::new_string(text));
-          self.mark_dirty();
-        }
-      }
-      "v" if cmd => {
-        if let Some(item) = cx.read_from_clipboard()
-          && let Some(text) = item.text()
-        {
-          self.editor.paste(&text);
-          self.mark_dirty();
-        }
-      }
-      "space" => {
-        self.editor.delete_selection();
-        self.editor.insert_char(' ');
-        self.mark_dirty();
-      }
-      "tab" => {
-        self.editor.delete_selection();
-        for _ in 0..config.tab_size {
-          self.editor.insert_char(' ');
-        }
-        self.mark_dirty();
-      }
-      key => {
-        if key.len() == 1
-          && !cmd
-          && !event.keystroke.modifiers.control
-          && let Some(c) = key.chars().next()
-        {
-          self.editor.delete_selection();
-          let char = if shift { c.to_ascii_uppercase() } else { c };
-          self.editor.insert_char(char);
-          self.mark_dirty();
+          c
         }
       }
-    }
+      _ => return,
+    };
+
+    self.editor.delete_selection();
+    self.editor.insert_char(char);
+    self.mark_dirty(cx);
     cx.notify();
   }
 }
 
+impl EventEmitter<DiffEditorEvent> for DiffEditor {}
+
 impl Focusable for DiffEditor {
   fn focus_handle(&self, _cx: &App) -> FocusHandle {
     self.focus_handle.clone()
@@ -583,17 +6101,77 @@ impl Focusable for DiffEditor {
 
 impl Render for DiffEditor {
   fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    if self.config.render_profiling {
+      let cache_stats = self.line_cache_stats();
+      self.profiler.borrow_mut().begin_frame(cache_stats);
+    }
+    let view = self.render_view(window, cx);
+    self.input_hooks.wrap(view)
+  }
+}
+
+impl DiffEditor {
+  fn render_view(&mut self, window: &mut Window, cx: &mut Context<Self>) -> AnyElement {
     let is_focused = self.focus_handle.is_focused(window);
+    self.observe_appearance(window, cx);
+    self.observe_focus(window, cx);
 
-    if is_focused && !self.is_dirty {
-      self.reload_file(cx);
+    if let Some(loading) = self.loads.iter().find(|loading| loading.blocks_view()) {
+      return self.render_loading(loading, cx).into_any_element();
+    }
+    if let Some((error, LoadTarget::Open(_))) = &self.failed_load {
+      return self.render_load_failed(error, cx).into_any_element();
+    }
+    if self.is_empty() {
+      return self
+        .render_placeholder("Open a file to see its changes", cx)
+        .into_any_element();
+    }
+    match self.file_kind() {
+      FileKind::Image => return self.render_image_diff(cx).into_any_element(),
+      FileKind::Binary if !self.shows_hex() => return self.render_binary(cx).into_any_element(),
+      FileKind::Binary | FileKind::Text => {}
+    }
+    // Files that don't parse show the line diff
+    if self.config.structured_diff
+      && let Some(Ok(diff)) = self.structured_diff()
+    {
+      return self.render_structured_diff(&diff, cx).into_any_element();
     }
+    if let Some(paragraphs) = self.prose_diff() {
+      return self.render_prose_diff(&paragraphs, cx).into_any_element();
+    }
+
+    let line_edits = self.editor.take_line_edits();
+    if !line_edits.is_empty() {
+      self.lsp_edited = true;
+    }
+    {
+      let mut line_cache = self.line_cache.lock().unwrap();
+      line_cache.check_font(&self.font, self.config.font_size);
+      line_cache.apply_edits(&line_edits, self.editor.buffer.len());
+    }
+    self.unchanged_regions.apply_edits(&line_edits);
+    self.line_annotations.apply_edits(&line_edits);
+    self.comment_threads.apply_edits(&line_edits);
+    self.original_previews.apply_edits(&line_edits);
+    // Computed once for the frame, the helpers below share it
+    let diff_lines = self.compute_diff();
+    self.expand_region_at_cursor(&diff_lines);
+    self.folds.apply_edits(&line_edits);
+    self.folds.retain_ranges(&self.fold_ranges());
+    self.unfold_at_cursor();
+    self.update_cursor_blink(is_focused, !line_edits.is_empty(), cx);
+    let diff_stats = Differ::compute_stats(&diff_lines);
+    self.update_diff_stats(diff_stats, cx);
+    let review_progress = self.diff_review_progress(&diff_lines);
+    self.update_review_progress(review_progress, cx);
 
     let font_size = self.config.font_size;
     let focus_handle = self.focus_handle.clone();
-    let scroll_handle_diff_gutter = self.scroll_handle.clone();
-    let scroll_handle_line_numbers = self.scroll_handle.clone();
-    let scroll_handle_editor = self.scroll_handle.clone();
+    let scroll_handle_rows = self.scroll_handle.clone();
+    let scroll_handle_scrollbar = self.scroll_handle.clone();
+    let scroll_handle_minimap = self.scroll_handle.clone();
 
     let buffer = Arc::new(self.editor.buffer.clone());
     let editor_state = EditorState {
@@ -601,43 +6179,477 @@ impl Render for DiffEditor {
       selection_range: self.editor.selection_range(),
     };
 
-    let diff_lines = self.compute_diff();
-    let diff_lines2 = diff_lines.clone();
-    let diff_lines3 = diff_lines.clone();
+    let rows = self.display_rows(&diff_lines);
+    let display_map = display_map(&rows, self.editor.buffer.line_count());
+    self.resolve_scroll_anchor(&display_map);
+    self.step_scroll_animation(window);
+    self.autoscroll(&display_map, window);
+    self.displayed_rows = rows.clone();
+    self.display_map = Rc::new(display_map);
+    self.display_map_versions = Some((self.editor.buffer.version(), self.differ_version));
+    let scroll_position = self.scroll_position();
+    let scroll_set = std::mem::take(&mut self.scroll_set);
+    if let Some(previous) = self.reported_scroll.replace(scroll_position)
+      && previous != scroll_position
+      && !scroll_set
+    {
+      cx.emit(DiffEditorEvent::Scrolled);
+    }
+    // Scrolling and edits move the text under a tooltip
+    if self.hovered_change.is_some() {
+      self.hovered_change = self.hovered_change_at(window.mouse_position(), window);
+    }
+    if self.hovered_diagnostic.is_some() {
+      self.hovered_diagnostic = self.hovered_diagnostic_at(window.mouse_position(), window);
+    }
+    if let Some(hover) = &self.lsp_hover {
+      let hovered = self.hovered_char_at(window.mouse_position(), window);
+      if hovered.map(|(_, _, index)| index) != Some(hover.index) {
+        self.lsp_hover = None;
+      }
+    }
+    let selection_info = self
+      .config
+      .selection_info
+      .then(|| self.render_selection_info(window))
+      .flatten();
+    // A diagnostic over a change hides what the change replaced, and so does hover info
+    let lsp_hover = self
+      .lsp_hover
+      .as_ref()
+      .and_then(|hover| Some((hover, hover.text.as_deref()?)));
+    let tooltip = match (
+      self.hovered_diagnostic.clone(),
+      lsp_hover,
+      self.hovered_change.clone(),
+    ) {
+      (Some(hovered), _, _) => Some(self.render_diagnostic_tooltip(hovered).into_any_element()),
+      (None, Some((hover, text)), _) => Some(
+        self
+          .render_lsp_hover_tooltip(hover, text)
+          .into_any_element(),
+      ),
+      (None, None, Some(hovered)) => Some(self.render_change_tooltip(hovered).into_any_element()),
+      (None, None, None) => None,
+    };
+    let scrollbar = self.render_scrollbar(&rows, scroll_handle_scrollbar);
+    let sticky_header = self.sticky_header(&diff_lines, &rows);
+    let minimap = self
+      .config
+      .minimap
+      .then(|| self.render_minimap(rows.clone(), scroll_handle_minimap));
+    let mut columns = Vec::new();
+    if !self.line_annotations.is_empty() {
+      columns.push(self.annotations_column(&rows));
+    }
+    columns.push(self.gutter_row_column(self.diff_strip_column(&diff_lines, &rows, cx), &rows));
+    columns.push(self.gutter_row_column(self.line_numbers_column(window, cx), &rows));
+    columns.extend(self.host_gutter_columns(&rows));
+    self.gutter_width = px(columns.iter().map(|column| column.width).sum());
+    let rows = Rc::new(rows);
+    let text_cells = self.text_cells(rows.clone(), buffer, editor_state, cx);
 
     let theme = self.get_theme();
     let bg_color = theme.code.bg_color;
 
     div()
       .id("editor-view")
+      .key_context(KEY_CONTEXT)
       .track_focus(&focus_handle)
+      .relative()
       .size_full()
       .bg(bg_color)
       .text_size(px(font_size))
+      .on_action(cx.listener(Self::save_file))
+      .on_action(cx.listener(Self::toggle_bookmark))
+      .on_action(cx.listener(Self::jump_back_action))
+      .on_action(cx.listener(Self::jump_forward_action))
+      .on_action(cx.listener(Self::toggle_hunk_viewed))
+      .on_action(cx.listener(Self::toggle_hunk_needs_work))
+      .on_action(cx.listener(Self::toggle_original_preview_action))
+      .on_action(cx.listener(Self::toggle_structured_diff))
+      .on_action(cx.listener(Self::toggle_prose_diff))
+      .on_action(cx.listener(Self::toggle_render_profiling))
+      .on_action(cx.listener(Self::go_to_next_bookmark))
+      .on_action(cx.listener(Self::go_to_previous_bookmark))
+      .on_action(cx.listener(Self::zoom_in))
+      .on_action(cx.listener(Self::zoom_out))
+      .on_action(cx.listener(Self::reset_zoom))
+      .on_action(cx.listener(Self::move_left))
+      .on_action(cx.listener(Self::move_right))
+      .on_action(cx.listener(Self::move_up))
+      .on_action(cx.listener(Self::move_down))
+      .on_action(cx.listener(Self::move_word_left))
+      .on_action(cx.listener(Self::move_word_right))
+      .on_action(cx.listener(Self::move_to_line_start))
+      .on_action(cx.listener(Self::move_to_line_end))
+      .on_action(cx.listener(Self::move_to_buffer_start))
+      .on_action(cx.listener(Self::move_to_buffer_end))
+      .on_action(cx.listener(Self::page_up))
+      .on_action(cx.listener(Self::page_down))
+      .on_action(cx.listener(Self::center_cursor))
+      .on_action(cx.listener(Self::select_left))
+      .on_action(cx.listener(Self::select_right))
+      .on_action(cx.listener(Self::select_up))
+      .on_action(cx.listener(Self::select_down))
+      .on_action(cx.listener(Self::select_word_left))
+      .on_action(cx.listener(Self::select_word_right))
+      .on_action(cx.listener(Self::select_to_line_start))
+      .on_action(cx.listener(Self::select_to_line_end))
+      .on_action(cx.listener(Self::select_to_buffer_start))
+      .on_action(cx.listener(Self::select_to_buffer_end))
+      .on_action(cx.listener(Self::select_page_up))
+      .on_action(cx.listener(Self::select_page_down))
+      .on_action(cx.listener(Self::select_all))
+      .on_action(cx.listener(Self::expand_selection))
+      .on_action(cx.listener(Self::shrink_selection))
+      .on_action(cx.listener(Self::backspace))
+      .on_action(cx.listener(Self::delete))
+      .on_action(cx.listener(Self::delete_word_forward))
+      .on_action(cx.listener(Self::delete_to_line_end))
+      .on_action(cx.listener(Self::delete_word))
+      .on_action(cx.listener(Self::delete_line))
+      .on_action(cx.listener(Self::duplicate_lines_up))
+      .on_action(cx.listener(Self::duplicate_lines_down))
+      .on_action(cx.listener(Self::move_lines_up))
+      .on_action(cx.listener(Self::move_lines_down))
+      .on_action(cx.listener(Self::toggle_comment))
+      .on_action(cx.listener(Self::toggle_block_comment))
+      .on_action(cx.listener(Self::undo))
+      .on_action(cx.listener(Self::redo))
+      .on_action(cx.listener(Self::newline))
+      .on_action(cx.listener(Self::tab))
+      .on_action(cx.listener(Self::indent))
+      .on_action(cx.listener(Self::outdent))
+      .on_action(cx.listener(Self::copy))
+      .on_action(cx.listener(Self::copy_diff_as_markdown))
+      .on_action(cx.listener(Self::copy_diff_as_rtf))
+      .on_action(cx.listener(Self::cut))
+      .on_action(cx.listener(Self::paste))
+      .on_action(cx.listener(Self::go_to_next_hunk))
+      .on_action(cx.listener(Self::go_to_previous_hunk))
+      .on_action(cx.listener(Self::go_to_moved_counterpart))
+      .on_action(cx.listener(Self::close_active_tab))
+      .on_action(cx.listener(Self::activate_next_tab))
+      .on_action(cx.listener(Self::activate_previous_tab))
+      .on_action(cx.listener(Self::take_ours))
+      .on_action(cx.listener(Self::take_theirs))
+      .on_action(cx.listener(Self::take_both))
+      .on_action(cx.listener(Self::revert_selection_action))
+      .on_action(cx.listener(Self::rebaseline_action))
+      .on_action(cx.listener(Self::toggle_command_palette))
       .on_key_down(cx.listener(Self::on_key_down))
       .on_mouse_down(MouseButton::Left, cx.listener(Self::on_mouse_down))
       .on_mouse_move(cx.listener(Self::on_mouse_move))
       .on_mouse_up(MouseButton::Left, cx.listener(Self::on_mouse_up))
       .on_mouse_up_out(MouseButton::Left, cx.listener(Self::on_mouse_up_out))
+      .flex()
+      .flex_col()
+      .when(!self.tabs.is_empty(), |d| d.child(self.render_tab_bar(cx)))
+      .when(self.recovered.is_some(), |d| {
+        d.child(self.render_recovery_prompt(cx))
+      })
+      .children(
+        self
+          .failed_load
+          .as_ref()
+          .map(|(error, _)| self.render_load_error_banner(error, cx)),
+      )
       .child(
         div()
           .flex()
-          .size_full()
-          .child(self.render_diff_gutter(diff_lines, scroll_handle_diff_gutter))
-          .child(self.render_line_numbers(diff_lines2, scroll_handle_line_numbers))
-          .child(self.render_editor(diff_lines3, buffer, editor_state, scroll_handle_editor)),
+          .flex_1()
+          .min_h(px(0.0))
+          .w_full()
+          .on_scroll_wheel(cx.listener(Self::on_scroll_wheel))
+          .child(
+            div()
+              .relative()
+              .flex_1()
+              .min_w(px(0.0))
+              .h_full()
+              .child(self.render_rows(columns, rows, text_cells, scroll_handle_rows))
+              // Overlays of the text pane, right of the gutter columns
+              .child(
+                div()
+                  .absolute()
+                  .top_0()
+                  .bottom_0()
+                  .left(self.gutter_width)
+                  .right_0()
+                  .children(sticky_header.map(|header| self.render_sticky_header(header, cx)))
+                  .when(
+                    !self.revisions.is_empty() || self.baseline != Baseline::Original,
+                    |d| d.child(self.render_baseline_picker(cx)),
+                  )
+                  .children(selection_info)
+                  .children(tooltip)
+                  .when(self.config.render_profiling, |d| {
+                    d.child(self.render_profiling_overlay())
+                  }),
+              ),
+          )
+          .children(minimap)
+          .child(scrollbar),
       )
+      .when(self.config.status_bar, |d| {
+        d.child(self.render_status_bar(diff_stats, review_progress))
+      })
+      .children(
+        self
+          .command_palette
+          .as_ref()
+          .map(|palette| self.render_command_palette(palette, cx)),
+      )
+      .children(
+        self
+          .save_conflict
+          .as_ref()
+          .map(|conflict| self.render_save_conflict(conflict, cx)),
+      )
+      .into_any_element()
+  }
+}
+
+/// Rows from the first to the last line of `lines` (buffer lines, 0-based), with the removed
+/// lines between them
+fn selected_rows(diff_lines: &[DiffLine], lines: Range<usize>) -> Option<Range<usize>> {
+  let selected = |line: &DiffLine| {
+    line
+      .line_number
+      .checked_sub(1)
+      .is_some_and(|line_idx| lines.contains(&line_idx))
+  };
+  let first = diff_lines.iter().position(selected)?;
+  let last = diff_lines.iter().rposition(selected)?;
+  Some(first..last + 1)
+}
+
+/// Like "12 chars, 2 lines selected"
+fn selection_label(chars: usize, lines: usize) -> String {
+  let plural = |count: usize, word: &str| match count {
+    1 => format!("1 {}", word),
+    _ => format!("{} {}s", count, word),
+  };
+  format!(
+    "{}, {} selected",
+    plural(chars, "char"),
+    plural(lines, "line")
+  )
+}
+
+/// Hunk with a line at a buffer line (0-based), or right above it for a pure removal
+fn hunk_at_line(diff_lines: &[DiffLine], line: usize) -> Option<DiffHunk> {
+  Differ::compute_hunks(diff_lines).into_iter().find(|hunk| {
+    hunk.line_idx == line
+      || diff_lines[hunk.rows.clone()]
+        .iter()
+        .any(|diff_line| diff_line.line_number == line + 1)
+  })
+}
+
+/// `focus_handle` at `index` in the tab order, or out of it for None
+fn with_tab_index(focus_handle: FocusHandle, index: Option<isize>) -> FocusHandle {
+  match index {
+    Some(index) => focus_handle.tab_index(index).tab_stop(true),
+    None => focus_handle.tab_stop(false),
+  }
+}
+
+/// Settings of the editor that come from the config, some depending on the file's language
+fn configure_editor(editor: &mut Editor, config: &EditorConfig, path: &Path) {
+  editor.word_chars = config.word_chars_for(path);
+  editor.subwords = config.subword_navigation;
+  if config.edit_log {
+    editor.start_edit_log();
+  } else {
+    editor.stop_edit_log();
+  }
+}
+
+/// Removes a journal whose edits were saved or thrown away
+fn discard_journal(journal: Journal) {
+  if let Err(e) = journal.discard() {
+    eprintln!("Failed to remove journal: {}", e);
+  }
+}
+
+/// Clipboard item of copied text, whole lines carry a `true` metadata flag
+fn clipboard_item(clipboard: ClipboardText) -> ClipboardItem {
+  if clipboard.whole_line {
+    ClipboardItem::new_string_with_json_metadata(clipboard.text, true)
+  } else {
+    ClipboardItem::new_string(clipboard.text)
+  }
+}
+
+/// Leaves wheel scrolling to `DiffEditor::on_scroll_wheel`, which scrolls every column at once
+fn disable_wheel_scroll(list: &mut UniformList) {
+  list.style().overflow.y = Some(Overflow::Hidden);
+  list.style().restrict_scroll_to_axis = Some(true);
+}
+
+/// Like git, the last line of a side without newline says so when it's part of a change
+fn shows_no_newline_marker(line: &DiffLine, final_newline_changed: bool) -> bool {
+  line.lacks_final_newline() && (line.kind != DiffLineKind::Unchanged || final_newline_changed)
+}
+
+/// Width of the line numbers column for `line_count` lines, with the fold indicator and padding
+fn line_numbers_width(line_count: usize, digit_width: f32) -> f32 {
+  let digits = line_count.max(1).ilog10() as usize + 1;
+  FOLD_INDICATOR_WIDTH
+    + digits.max(MIN_LINE_NUMBER_DIGITS) as f32 * digit_width
+    + LINE_NUMBERS_PADDING
+}
+
+/// Rows covering `fraction` of the viewport, at least one line always stays visible
+fn overscroll_rows(viewport_height: f32, line_height: f32, fraction: f32) -> usize {
+  if line_height <= 0.0 {
+    return 0;
+  }
+  let viewport_rows = (viewport_height / line_height).floor() as usize;
+  let rows = (viewport_height * fraction.clamp(0.0, 1.0) / line_height).floor() as usize;
+  rows.min(viewport_rows.saturating_sub(1))
+}
+
+/// Vertical list offset after scrolling by `delta`, offsets are negative when scrolled down
+fn scrolled_offset_y(offset_y: f32, delta_y: f32, max_offset_y: f32) -> f32 {
+  (offset_y + delta_y).clamp(-max_offset_y.max(0.0), 0.0)
+}
+
+/// Char index in `new` of the content at `index` in `old`, the column stays on its line
+fn remap_index(line_map: &LineMap, old: &TextBuffer, new: &TextBuffer, index: usize) -> usize {
+  let (line, col) = old.char_to_line_col(index);
+  let (line, col) = line_map.map_position(line, col);
+  let line_len = new
+    .line(line)
+    .map_or(0, |text| text.trim_end_matches('\n').chars().count());
+  new.line_col_to_char(line, col.min(line_len))
+}
+
+/// Scroll position after the line height changed, keeping `anchor_row` at the same height
+/// on screen, or the same rows at the top without one
+fn zoomed_scroll_top(
+  scroll_top: f32,
+  anchor_row: Option<usize>,
+  old_line_height: f32,
+  line_height: f32,
+) -> f32 {
+  if old_line_height <= 0.0 {
+    return scroll_top;
+  }
+  match anchor_row {
+    Some(row) => {
+      let screen_y = row as f32 * old_line_height - scroll_top;
+      (row as f32 * line_height - screen_y).max(0.0)
+    }
+    None => scroll_top * line_height / old_line_height,
+  }
+}
+
+/// Returns the horizontal scroll offset needed to keep `cursor_x` at least
+/// `margin` away from the viewport edges
+fn scroll_x_to_reveal(cursor_x: f32, scroll_x: f32, viewport_width: f32, margin: f32) -> f32 {
+  if cursor_x - margin < scroll_x {
+    (cursor_x - margin).max(0.0)
+  } else if cursor_x + margin > scroll_x + viewport_width {
+    cursor_x + margin - viewport_width
+  } else {
+    scroll_x
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::config::LineNumbers;
+  use crate::test_support::EditorTestContext;
+  use gpui::{Modifiers, TestAppContext};
+  use rediff_core::WhitespaceMode;
+  use std::path::Path;
 
   #[test]
   fn test_editor_config_default() {
     let config = EditorConfig::default();
     assert_eq!(config.font_size, 16.0);
+    assert!(!config.reload_on_focus);
+    assert_eq!(config.tab_index, None);
+    assert!(!config.render_profiling);
+  }
+
+  #[test]
+  fn test_editor_config_editable_by_default() {
+    let config = EditorConfig::default();
+    assert!(!config.read_only);
+
+    let config = EditorConfig {
+      read_only: true,
+      ..Default::default()
+    };
+    assert!(config.read_only);
+  }
+
+  #[test]
+  fn test_selection_label() {
+    assert_eq!(selection_label(1, 1), "1 char, 1 line selected");
+    assert_eq!(selection_label(12, 3), "12 chars, 3 lines selected");
+  }
+
+  #[test]
+  fn test_selected_rows() {
+    // "b" was removed between the selected "a" and "c"
+    let diff_lines = rediff_core::diff("a\nb\nc\nd\n", "a\nc\nd\n", &DiffOptions::default()).lines;
+    let rows = selected_rows(&diff_lines, 0..2).unwrap();
+    assert_eq!(rows, 0..3);
+    assert_eq!(Differ::compute_stats(&diff_lines[rows]).removed, 1);
+    assert!(selected_rows(&diff_lines, 5..6).is_none());
+  }
+
+  #[test]
+  fn test_hunk_at_line() {
+    let diff_lines = rediff_core::diff("a\nb\nc\nd\n", "a\nB\nc\n", &DiffOptions::default()).lines;
+    assert_eq!(
+      hunk_at_line(&diff_lines, 1).map(|hunk| hunk.line_idx),
+      Some(1)
+    );
+    assert!(hunk_at_line(&diff_lines, 0).is_none());
+    // "d" was removed at the end, its hunk sits on the last line
+    assert_eq!(
+      hunk_at_line(&diff_lines, 2).map(|hunk| hunk.line_idx),
+      Some(2)
+    );
+  }
+
+  #[test]
+  fn test_remap_index_follows_content() {
+    let buffer = |text: &str| {
+      let mut buffer = TextBuffer::new();
+      buffer.insert(0, text);
+      buffer
+    };
+    let old = buffer("fn a() {}\nfn b() {}\n");
+    let new = buffer("// header\n\nfn a() {}\nfn b2() {}\n");
+    let line_map = LineMap::new(&old.as_str(), &new.as_str());
+
+    // "a" of the first line, now two lines down
+    assert_eq!(remap_index(&line_map, &old, &new, 3), 14);
+    // End of "fn b() {}" lands on the rewritten line
+    assert_eq!(remap_index(&line_map, &old, &new, 19), 30);
+    assert_eq!(remap_index(&line_map, &old, &new, old.len()), new.len());
+
+    // Clamped to the shorter line instead of wrapping onto the next one
+    let new = buffer("fn a() {}\nx\n");
+    let line_map = LineMap::new(&old.as_str(), &new.as_str());
+    assert_eq!(remap_index(&line_map, &old, &new, 19), 11);
+  }
+
+  #[test]
+  fn test_zoomed_scroll_top_keeps_anchor_row() {
+    // Row 20 is 100px below the top of the pane
+    assert_eq!(zoomed_scroll_top(380.0, Some(20), 24.0, 30.0), 500.0);
+    assert_eq!(zoomed_scroll_top(380.0, None, 24.0, 30.0), 475.0);
+    // Near the start there's nothing to scroll back to
+    assert_eq!(zoomed_scroll_top(0.0, Some(2), 24.0, 12.0), 0.0);
   }
 
   #[test]
@@ -655,6 +6667,145 @@ mod tests {
     assert_eq!(config.line_height(), 30.0);
   }
 
+  #[test]
+  fn test_line_number_labels() {
+    assert_eq!(LineNumbers::Absolute.label(4, 9), "5");
+    assert_eq!(LineNumbers::Relative.label(4, 9), "5");
+    assert_eq!(LineNumbers::Relative.label(12, 9), "3");
+    assert_eq!(LineNumbers::Relative.label(9, 9), "0");
+    assert_eq!(LineNumbers::Hybrid.label(12, 9), "3");
+    assert_eq!(LineNumbers::Hybrid.label(9, 9), "10");
+  }
+
+  #[test]
+  fn test_word_chars_for_path() {
+    let config = EditorConfig::default();
+    assert!(
+      config
+        .word_chars_for(Path::new("style.CSS"))
+        .is_word_char('-')
+    );
+    assert!(config.word_chars_for(Path::new("app.ts")).is_word_char('$'));
+    assert!(
+      !config
+        .word_chars_for(Path::new("main.rs"))
+        .is_word_char('-')
+    );
+    assert!(
+      !config
+        .word_chars_for(Path::new("Makefile"))
+        .is_word_char('$')
+    );
+  }
+
+  #[test]
+  fn test_prose_granularity_for_path() {
+    let mut config = EditorConfig::default();
+    assert_eq!(config.prose_granularity_for(Path::new("README.md")), None);
+
+    config
+      .prose_diff
+      .insert("md".to_string(), ProseGranularity::Sentence);
+    assert_eq!(
+      config.prose_granularity_for(Path::new("NOTES.MD")),
+      Some(ProseGranularity::Sentence)
+    );
+    assert_eq!(config.prose_granularity_for(Path::new("md")), None);
+  }
+
+  #[test]
+  fn test_comment_tokens_for_path() {
+    let config = EditorConfig::default();
+    let tokens = config.comment_tokens_for(Path::new("src/main.RS")).unwrap();
+    assert_eq!(tokens.line.as_deref(), Some("//"));
+
+    let tokens = config.comment_tokens_for(Path::new("style.css")).unwrap();
+    assert!(tokens.line.is_none());
+    assert!(tokens.block.is_some());
+
+    assert!(config.comment_tokens_for(Path::new("Makefile")).is_some());
+    assert!(
+      config
+        .comment_tokens_for(Path::new("notes.unknown"))
+        .is_none()
+    );
+  }
+
+  #[test]
+  fn test_scroll_x_to_reveal_keeps_visible_cursor() {
+    assert_eq!(scroll_x_to_reveal(100.0, 0.0, 400.0, 32.0), 0.0);
+    assert_eq!(scroll_x_to_reveal(300.0, 50.0, 400.0, 32.0), 50.0);
+  }
+
+  #[test]
+  fn test_scroll_x_to_reveal_scrolls_right() {
+    // Cursor past the right edge ends up one margin inside the viewport
+    assert_eq!(scroll_x_to_reveal(500.0, 0.0, 400.0, 32.0), 132.0);
+  }
+
+  #[test]
+  fn test_scroll_x_to_reveal_scrolls_left() {
+    assert_eq!(scroll_x_to_reveal(100.0, 200.0, 400.0, 32.0), 68.0);
+    assert_eq!(scroll_x_to_reveal(10.0, 200.0, 400.0, 32.0), 0.0);
+  }
+
+  #[test]
+  fn test_overscroll_rows() {
+    assert_eq!(overscroll_rows(480.0, 24.0, 0.5), 10);
+    assert_eq!(overscroll_rows(480.0, 24.0, 0.0), 0);
+    // The last line can't scroll out of the viewport
+    assert_eq!(overscroll_rows(480.0, 24.0, 1.0), 19);
+    // Not laid out yet
+    assert_eq!(overscroll_rows(0.0, 24.0, 0.5), 0);
+  }
+
+  #[test]
+  fn test_no_newline_marker() {
+    let marked = |original: &str, modified: &str, options: DiffOptions| {
+      let final_newline_changed = original.ends_with('\n') != modified.ends_with('\n');
+      Differ::with_options(original.to_string(), options)
+        .compute_diff(modified)
+        .into_iter()
+        .filter(|line| shows_no_newline_marker(line, final_newline_changed))
+        .map(|line| line.content)
+        .collect::<Vec<_>>()
+    };
+
+    // Only the side without newline is marked
+    assert_eq!(marked("a\nb\n", "a\nb", DiffOptions::default()), ["b"]);
+    assert_eq!(marked("a\nb", "a\nb\n", DiffOptions::default()), ["b"]);
+    // Nothing when both sides end the same way
+    assert!(marked("a\nb\n", "a\nc\n", DiffOptions::default()).is_empty());
+    assert!(marked("a\nb", "a\nb", DiffOptions::default()).is_empty());
+
+    // Whitespace insensitive diffs keep the last line unchanged, it's marked all the same
+    let ignore_whitespace = DiffOptions {
+      whitespace: WhitespaceMode::IgnoreAll,
+      ..Default::default()
+    };
+    assert_eq!(marked("a\nb\n", "a\nb", ignore_whitespace), ["b"]);
+  }
+
+  #[test]
+  fn test_line_numbers_width() {
+    let padding = FOLD_INDICATOR_WIDTH + LINE_NUMBERS_PADDING;
+    // Short files keep room for two digits
+    assert_eq!(line_numbers_width(0, 10.0), padding + 20.0);
+    assert_eq!(line_numbers_width(99, 10.0), padding + 20.0);
+    assert_eq!(line_numbers_width(100, 10.0), padding + 30.0);
+    assert_eq!(line_numbers_width(150_000, 10.0), padding + 60.0);
+    assert_eq!(line_numbers_width(1_000_000, 8.0), padding + 56.0);
+  }
+
+  #[test]
+  fn test_scrolled_offset_y_is_clamped() {
+    assert_eq!(scrolled_offset_y(-100.0, -50.0, 1000.0), -150.0);
+    assert_eq!(scrolled_offset_y(-20.0, 50.0, 1000.0), 0.0);
+    assert_eq!(scrolled_offset_y(-980.0, -50.0, 1000.0), -1000.0);
+    // Content shorter than the viewport doesn't scroll
+    assert_eq!(scrolled_offset_y(0.0, -50.0, 0.0), 0.0);
+  }
+
   #[test]
   fn test_editor_state_creation() {
     let editor_state = EditorState {
@@ -685,4 +6836,321 @@ mod tests {
     assert_eq!(cloned.cursor_index, 100);
     assert_eq!(cloned.selection_range, Some(50..100));
   }
+
+  const ORIGINAL: &str = "fn main() {\n  let a = 1;\n  let b = 2;\n  drop(b);\n}\n";
+  const MODIFIED: &str = "fn main() {\n  let a = 10;\n  let b = 2;\n}\nfn new() {}\n";
+
+  #[gpui::test]
+  fn test_rows_golden(cx: &mut TestAppContext) {
+    let mut cx = EditorTestContext::new(cx, ORIGINAL, MODIFIED);
+    assert_eq!(
+      cx.rows(),
+      [
+        "    1 fn main() {",
+        "-       let a = 1;",
+        "+   2   let a = 10;",
+        "    3   let b = 2;",
+        "-       drop(b);",
+        "    4 }",
+        "+   5 fn new() {}",
+      ]
+      .join("\n")
+    );
+  }
+
+  #[gpui::test]
+  fn test_collapsed_rows_golden(cx: &mut TestAppContext) {
+    let original: String = (1..=12).map(|i| format!("line {}\n", i)).collect();
+    let modified = original.replace("line 6\n", "line 6!\n");
+    let config = EditorConfig {
+      collapse_unchanged: Some(3),
+      ..Default::default()
+    };
+    let mut cx = EditorTestContext::with_config(cx, &original, &modified, config);
+    assert_eq!(
+      cx.rows(),
+      [
+        "… 2 unchanged lines",
+        "    3 line 3",
+        "    4 line 4",
+        "    5 line 5",
+        "-     line 6",
+        "+   6 line 6!",
+        "    7 line 7",
+        "    8 line 8",
+        "    9 line 9",
+        "… 3 unchanged lines",
+      ]
+      .join("\n")
+    );
+
+    // Clicking a collapsed row expands it
+    cx.click_row(0, Modifiers::none());
+    assert!(
+      cx.rows()
+        .starts_with("    1 line 1\n    2 line 2\n    3 line 3")
+    );
+  }
+
+  #[gpui::test]
+  fn test_display_map_follows_rows(cx: &mut TestAppContext) {
+    let mut cx = EditorTestContext::new(cx, ORIGINAL, MODIFIED);
+    let (line_rows, positions) = cx.editor.update(cx.cx, |editor, _cx| {
+      let display_map = editor.display_map();
+      let line_rows: Vec<Option<usize>> = (0..5).map(|line| display_map.line_row(line)).collect();
+      let positions: Vec<RowPosition> = (0..display_map.len())
+        .map(|row| display_map.row_position(row))
+        .collect();
+      (line_rows, positions)
+    });
+    assert_eq!(line_rows, [Some(0), Some(2), Some(3), Some(5), Some(6)]);
+    assert_eq!(
+      positions,
+      [
+        RowPosition::Line(0),
+        RowPosition::Before(1),
+        RowPosition::Line(1),
+        RowPosition::Line(2),
+        RowPosition::Before(3),
+        RowPosition::Line(3),
+        RowPosition::Line(4),
+      ]
+    );
+
+    // Rebuilt with the rows once an edit adds a line
+    cx.simulate_keystrokes("enter");
+    let line_rows = cx.editor.update(cx.cx, |editor, _cx| {
+      (0..6)
+        .map(|line| editor.display_map().line_row(line))
+        .collect::<Vec<_>>()
+    });
+    assert_eq!(line_rows.iter().flatten().count(), 6);
+  }
+
+  #[gpui::test]
+  fn test_diff_follows_edits_and_options(cx: &mut TestAppContext) {
+    let mut cx = EditorTestContext::new(cx, ORIGINAL, MODIFIED);
+    let diff_stats =
+      |cx: &mut EditorTestContext| cx.editor.update(cx.cx, |editor, _cx| editor.diff_stats());
+    assert_eq!(diff_stats(&mut cx).modified, 1);
+
+    // The cached diff is dropped when the options change
+    cx.editor.update(cx.cx, |editor, cx| {
+      editor.set_diff_options(
+        DiffOptions {
+          pair_modifications: false,
+          ..Default::default()
+        },
+        cx,
+      );
+    });
+    assert_eq!(diff_stats(&mut cx).modified, 0);
+
+    // And after an edit
+    let added = diff_stats(&mut cx).added;
+    cx.simulate_keystrokes("enter");
+    assert_eq!(diff_stats(&mut cx).added, added + 1);
+  }
+
+  #[gpui::test]
+  fn test_click_places_cursor(cx: &mut TestAppContext) {
+    let mut cx = EditorTestContext::new(cx, ORIGINAL, MODIFIED);
+    cx.click_row(3, Modifiers::none());
+    assert_eq!(cx.cursor(), (2, 0));
+
+    // Removed rows have no text, the cursor lands on the line after them
+    cx.click_row(4, Modifiers::none());
+    assert_eq!(cx.cursor(), (3, 0));
+    assert_eq!(cx.selection(), None);
+  }
+
+  #[gpui::test]
+  fn test_drag_selects_rows(cx: &mut TestAppContext) {
+    let mut cx = EditorTestContext::new(cx, ORIGINAL, MODIFIED);
+    cx.drag_rows(0, 3);
+    assert_eq!(
+      cx.selected_text().as_deref(),
+      Some("fn main() {\n  let a = 10;\n")
+    );
+
+    cx.simulate_keystrokes("shift-down");
+    assert_eq!(
+      cx.selected_text().as_deref(),
+      Some("fn main() {\n  let a = 10;\n  let b = 2;\n")
+    );
+  }
+
+  #[gpui::test]
+  fn test_input_hooks(cx: &mut TestAppContext) {
+    let mut cx = EditorTestContext::new(cx, ORIGINAL, MODIFIED);
+    let keys = Rc::new(std::cell::RefCell::new(Vec::new()));
+    let hook = cx.editor.update(cx.cx, |editor, cx| {
+      let keys = keys.clone();
+      editor.add_input_hook(
+        move |event, _window, _cx| match event {
+          InputEvent::KeyDown(event) => {
+            let key = event.keystroke.key.clone();
+            let handled = key == "f7" || key == "x";
+            keys.borrow_mut().push(key);
+            if handled {
+              EventResult::Handled
+            } else {
+              EventResult::Pass
+            }
+          }
+          _ => EventResult::Pass,
+        },
+        cx,
+      )
+    });
+    cx.cx.run_until_parked();
+
+    // Bound keys and text input are both stopped
+    cx.simulate_keystrokes("f7");
+    assert_eq!(cx.cursor(), (0, 0));
+    cx.type_text("xy");
+    assert!(cx.text().starts_with("yfn main()"));
+    assert_eq!(*keys.borrow(), ["f7", "x", "y"]);
+
+    cx.editor.update(cx.cx, |editor, cx| {
+      assert!(editor.remove_input_hook(hook, cx))
+    });
+    cx.cx.run_until_parked();
+    cx.simulate_keystrokes("f7");
+    assert_eq!(cx.cursor(), (1, 0));
+    assert_eq!(keys.borrow().len(), 3);
+  }
+
+  #[gpui::test]
+  fn test_render_profiling(cx: &mut TestAppContext) {
+    let mut cx = EditorTestContext::new(cx, ORIGINAL, MODIFIED);
+    let frame_stats =
+      |cx: &mut EditorTestContext| cx.editor.update(cx.cx, |editor, _cx| editor.frame_stats());
+    assert_eq!(frame_stats(&mut cx), None);
+
+    cx.editor
+      .update(cx.cx, |editor, cx| editor.set_render_profiling(true, cx));
+    cx.cx.run_until_parked();
+    let frame = frame_stats(&mut cx).expect("the frame is painted");
+    assert!(frame.diffs > 0);
+    assert!(frame.frame_time >= frame.diff_time);
+  }
+
+  #[gpui::test]
+  fn test_hunk_navigation_keys(cx: &mut TestAppContext) {
+    let mut cx = EditorTestContext::new(cx, ORIGINAL, MODIFIED);
+    cx.simulate_keystrokes("f7");
+    assert_eq!(cx.cursor(), (1, 0));
+    // The removal is anchored to the line after it
+    cx.simulate_keystrokes("f7");
+    assert_eq!(cx.cursor(), (3, 0));
+    cx.simulate_keystrokes("shift-f7");
+    assert_eq!(cx.cursor(), (1, 0));
+  }
+
+  #[gpui::test]
+  fn test_typing_updates_rows(cx: &mut TestAppContext) {
+    let mut cx = EditorTestContext::new(cx, ORIGINAL, MODIFIED);
+    cx.click_row(6, Modifiers::none());
+    cx.type_text("pub ");
+    assert_eq!(
+      cx.text(),
+      "fn main() {\n  let a = 10;\n  let b = 2;\n}\npub fn new() {}\n"
+    );
+    assert!(cx.rows().ends_with("    4 }\n+   5 pub fn new() {}"));
+  }
+
+  #[gpui::test]
+  fn test_original_preview(cx: &mut TestAppContext) {
+    let mut cx = EditorTestContext::new(cx, ORIGINAL, MODIFIED);
+    cx.editor
+      .update(cx.cx, |editor, cx| editor.toggle_original_preview(1, cx));
+    cx.cx.run_until_parked();
+    assert_eq!(
+      cx.rows(),
+      [
+        "    1 fn main() {",
+        "-       let a = 1;",
+        "+   2   let a = 10;",
+        "~        let a = 1;",
+        "    3   let b = 2;",
+        "-       drop(b);",
+        "    4 }",
+        "+   5 fn new() {}",
+      ]
+      .join("\n")
+    );
+
+    // Clicking the preview hides it
+    cx.click_row(3, Modifiers::none());
+    assert!(!cx.rows().contains('~'));
+  }
+
+  #[gpui::test]
+  fn test_structured_diff(cx: &mut TestAppContext) {
+    let (editor, cx) = cx.add_window_view(|_window, cx| {
+      DiffEditor::from_text(
+        PathBuf::from("package.json"),
+        "{\"name\": \"app\", \"version\": \"2.0\"}\n",
+        "{\n  \"version\": \"1.0\",\n  \"name\": \"app\"\n}\n".to_string(),
+        EditorConfig::default(),
+        cx,
+      )
+    });
+    editor.update(cx, |editor, _cx| {
+      let diff = editor.structured_diff().unwrap().unwrap();
+      assert_eq!(diff.changes().count(), 1);
+      assert_eq!(diff.groups[0].changes[0].path.to_string(), "version");
+
+      // Unparsable text falls back to the line diff
+      editor.editor.buffer.insert(0, "{");
+      assert!(editor.structured_diff().unwrap().is_err());
+    });
+  }
+
+  #[gpui::test]
+  fn test_scroll_position(cx: &mut TestAppContext) {
+    let text: String = (0..500).map(|line| format!("line {}\n", line)).collect();
+    let mut cx = EditorTestContext::new(cx, &text, &text);
+    let position = ScrollPosition {
+      line: 100,
+      fraction: 0.5,
+    };
+    cx.editor
+      .update(cx.cx, |editor, cx| editor.set_scroll_position(position, cx));
+    cx.cx.run_until_parked();
+    assert_eq!(
+      cx.editor
+        .update(cx.cx, |editor, _cx| editor.scroll_position()),
+      position
+    );
+  }
+
+  #[gpui::test]
+  fn test_focus_events(cx: &mut TestAppContext) {
+    let mut cx = EditorTestContext::new(cx, ORIGINAL, MODIFIED);
+    let events = Rc::new(std::cell::RefCell::new(Vec::new()));
+    let _subscription = cx.cx.update(|_window, app| {
+      let events = events.clone();
+      app.subscribe(&cx.editor, move |_editor, event: &DiffEditorEvent, _cx| {
+        if matches!(event, DiffEditorEvent::Focused | DiffEditorEvent::Blurred) {
+          events.borrow_mut().push(event.clone());
+        }
+      })
+    });
+    assert!(
+      cx.editor
+        .update_in(cx.cx, |editor, window, cx| editor.is_focused(window, cx))
+    );
+
+    cx.cx.update(|window, _cx| window.blur());
+    cx.cx.run_until_parked();
+    cx.editor
+      .update_in(cx.cx, |editor, window, _cx| editor.focus(window));
+    cx.cx.run_until_parked();
+    assert_eq!(
+      *events.borrow(),
+      [DiffEditorEvent::Blurred, DiffEditorEvent::Focused]
+    );
+  }
 }
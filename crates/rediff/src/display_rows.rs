@@ -0,0 +1,435 @@
+use crate::comment_threads::ThreadRow;
+use crate::original_previews::OriginalRow;
+use editor::{DisplayMap, LineEdit, RowContent};
+use rediff_core::{DiffLine, DiffLineKind};
+use std::collections::BTreeSet;
+use std::ops::Range;
+use text::TextBuffer;
+
+/// Unchanged lines kept visible next to each change when a region collapses
+pub const CONTEXT_LINES: usize = 3;
+
+/// A run of unchanged diff rows shown as a single expandable row
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollapsedRegion {
+  pub rows: Range<usize>, // Hidden rows, as indices into the Vec<DiffLine>
+  pub start_line: usize,  // Buffer line (0-based) of the first hidden row, identifies the region
+}
+
+impl CollapsedRegion {
+  pub fn line_count(&self) -> usize {
+    self.rows.len()
+  }
+
+  pub fn contains_line(&self, line_idx: usize) -> bool {
+    (self.start_line..self.start_line + self.line_count()).contains(&line_idx)
+  }
+}
+
+/// A row of the diff view
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisplayRow {
+  Line { row: usize, line: DiffLine }, // `row` indexes the Vec<DiffLine> the line comes from
+  Collapsed(CollapsedRegion),
+  Thread(ThreadRow), // Row of a comment thread, below the line the thread is attached to
+  Original(OriginalRow), // Original line previewed below the line that replaced it
+}
+
+impl DisplayRow {
+  pub fn diff_line(&self) -> Option<&DiffLine> {
+    match self {
+      DisplayRow::Line { line, .. } => Some(line),
+      DisplayRow::Collapsed(_) | DisplayRow::Thread(_) | DisplayRow::Original(_) => None,
+    }
+  }
+}
+
+/// Returns the display row showing the given diff row, or the first one after it when it's hidden
+pub fn display_index(rows: &[DisplayRow], diff_row: usize) -> usize {
+  rows
+    .iter()
+    .position(|row| match row {
+      DisplayRow::Line { row, .. } => *row >= diff_row,
+      DisplayRow::Collapsed(region) => region.rows.end > diff_row,
+      DisplayRow::Thread(_) | DisplayRow::Original(_) => false,
+    })
+    .unwrap_or(rows.len().saturating_sub(1))
+}
+
+/// Mapping of `rows` to the lines of a buffer of `line_count` lines, what the view hit-tests,
+/// scrolls and moves the cursor with
+pub fn display_map(rows: &[DisplayRow], line_count: usize) -> DisplayMap {
+  let rows = rows
+    .iter()
+    .map(|row| match row {
+      DisplayRow::Line { line, .. } => line
+        .line_number
+        .checked_sub(1)
+        .map_or(RowContent::Virtual, RowContent::Line),
+      DisplayRow::Collapsed(region) => {
+        RowContent::Folded(region.start_line..region.start_line + region.line_count())
+      }
+      DisplayRow::Thread(_) | DisplayRow::Original(_) => RowContent::Virtual,
+    })
+    .collect();
+  DisplayMap::new(rows, line_count)
+}
+
+/// Wraps diff lines into display rows without collapsing anything
+pub fn line_rows(diff_lines: Vec<DiffLine>) -> Vec<DisplayRow> {
+  diff_lines
+    .into_iter()
+    .enumerate()
+    .map(|(row, line)| DisplayRow::Line { row, line })
+    .collect()
+}
+
+/// Whether `selection` covers the rows shown before buffer line `line`
+///
+/// The selection has to go on past them, or up to the end of the buffer for the rows after
+/// the last line
+pub fn selection_spans_boundary(
+  buffer: &TextBuffer,
+  selection: &Range<usize>,
+  line: usize,
+) -> bool {
+  // Past the last line, that's the end of the buffer
+  let boundary = buffer.line_col_to_char(line, 0);
+  selection.start < boundary && (boundary < selection.end || selection.end == buffer.len())
+}
+
+/// Text of `selection` with the removed lines it covers put back where they show
+pub fn text_with_removed_lines(
+  diff_lines: &[DiffLine],
+  buffer: &TextBuffer,
+  selection: Range<usize>,
+) -> String {
+  // Removed lines grouped by the buffer line they show before
+  let mut groups: Vec<(usize, String)> = Vec::new();
+  let mut removed = String::new();
+  for line in diff_lines {
+    if line.line_number == 0 {
+      removed.push_str(&line.content);
+    } else if !removed.is_empty() {
+      groups.push((line.line_number - 1, std::mem::take(&mut removed)));
+    }
+  }
+  if !removed.is_empty() {
+    groups.push((buffer.line_count(), removed));
+  }
+
+  let content: Vec<char> = buffer.as_str().chars().collect();
+  let mut text = String::new();
+  let mut start = selection.start;
+  for (line, removed) in groups {
+    if !selection_spans_boundary(buffer, &selection, line) {
+      continue;
+    }
+    let boundary = buffer.line_col_to_char(line, 0);
+    text.extend(&content[start..boundary]);
+    // Removed lines after a last line without newline
+    if !text.is_empty() && !text.ends_with('\n') {
+      text.push('\n');
+    }
+    text.push_str(&removed);
+    start = boundary;
+  }
+  text.extend(&content[start..selection.end.max(start)]);
+  text
+}
+
+/// Which unchanged regions the user expanded, kept across re-diffs
+#[derive(Debug, Clone, Default)]
+pub struct UnchangedRegions {
+  expanded: BTreeSet<usize>, // `start_line` of the expanded regions
+}
+
+impl UnchangedRegions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn expand(&mut self, start_line: usize) {
+    self.expanded.insert(start_line);
+  }
+
+  pub fn is_expanded(&self, start_line: usize) -> bool {
+    self.expanded.contains(&start_line)
+  }
+
+  /// Collapses every region again
+  pub fn reset(&mut self) {
+    self.expanded.clear();
+  }
+
+  /// Shifts expanded regions that follow edited lines so they stay expanded
+  pub fn apply_edits(&mut self, edits: &[LineEdit]) {
+    for edit in edits {
+      self.expanded = std::mem::take(&mut self.expanded)
+        .into_iter()
+        .filter_map(|line_idx| {
+          if line_idx <= edit.line_end {
+            Some(line_idx)
+          } else {
+            line_idx.checked_add_signed(edit.line_delta)
+          }
+        })
+        .collect();
+    }
+  }
+
+  /// Finds the collapsed regions: runs of more than `min_lines` unchanged rows,
+  /// minus `CONTEXT_LINES` rows next to the surrounding changes
+  pub fn collapsed_regions(
+    &self,
+    diff_lines: &[DiffLine],
+    min_lines: usize,
+  ) -> Vec<CollapsedRegion> {
+    let mut regions = Vec::new();
+    let mut row = 0;
+
+    while row < diff_lines.len() {
+      if diff_lines[row].kind != DiffLineKind::Unchanged {
+        row += 1;
+        continue;
+      }
+
+      let run_start = row;
+      while row < diff_lines.len() && diff_lines[row].kind == DiffLineKind::Unchanged {
+        row += 1;
+      }
+      let run_end = row;
+
+      // A diff without changes is shown as is
+      if run_start == 0 && run_end == diff_lines.len() {
+        break;
+      }
+      if run_end - run_start <= min_lines {
+        continue;
+      }
+
+      let start = if run_start == 0 {
+        0
+      } else {
+        run_start + CONTEXT_LINES
+      };
+      let end = if run_end == diff_lines.len() {
+        run_end
+      } else {
+        run_end.saturating_sub(CONTEXT_LINES)
+      };
+
+      // Hiding a single line behind a row of the same height gains nothing
+      if end <= start + 1 {
+        continue;
+      }
+
+      let start_line = diff_lines[start].line_number - 1;
+      if !self.is_expanded(start_line) {
+        regions.push(CollapsedRegion {
+          rows: start..end,
+          start_line,
+        });
+      }
+    }
+
+    regions
+  }
+
+  /// Replaces the collapsed regions of the diff by a single row each
+  pub fn display_rows(&self, diff_lines: Vec<DiffLine>, min_lines: usize) -> Vec<DisplayRow> {
+    let regions = self.collapsed_regions(&diff_lines, min_lines);
+    if regions.is_empty() {
+      return line_rows(diff_lines);
+    }
+
+    let mut rows = Vec::with_capacity(diff_lines.len());
+    let mut regions = regions.into_iter().peekable();
+    let mut lines = diff_lines.into_iter().enumerate();
+
+    while let Some((row, line)) = lines.next() {
+      match regions.next_if(|region| region.rows.start == row) {
+        Some(region) => {
+          // Skip the other hidden rows
+          for _ in 1..region.line_count() {
+            lines.next();
+          }
+          rows.push(DisplayRow::Collapsed(region));
+        }
+        None => rows.push(DisplayRow::Line { row, line }),
+      }
+    }
+
+    rows
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use editor::RowPosition;
+
+  fn diff_lines(kinds: &str) -> Vec<DiffLine> {
+    let mut line_number = 0;
+    kinds
+      .chars()
+      .map(|c| {
+        let kind = match c {
+          'u' => DiffLineKind::Unchanged,
+          'a' => DiffLineKind::Added,
+          _ => DiffLineKind::Removed,
+        };
+        if kind != DiffLineKind::Removed {
+          line_number += 1;
+        }
+        DiffLine {
+          line_number: if kind == DiffLineKind::Removed {
+            0
+          } else {
+            line_number
+          },
+          kind,
+          content: format!("{}\n", c),
+          char_changes: vec![],
+          is_first_in_group: false,
+          pair: None,
+          counterpart: None,
+        }
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_short_runs_stay_visible() {
+    let lines = diff_lines("uuuauuu");
+    let regions = UnchangedRegions::new().collapsed_regions(&lines, 5);
+    assert!(regions.is_empty());
+  }
+
+  #[test]
+  fn test_collapse_keeps_context_around_changes() {
+    // 10 unchanged lines between two changes
+    let lines = diff_lines("auuuuuuuuuua");
+    let regions = UnchangedRegions::new().collapsed_regions(&lines, 5);
+
+    assert_eq!(
+      regions,
+      vec![CollapsedRegion {
+        rows: 4..8,
+        start_line: 4,
+      }]
+    );
+  }
+
+  #[test]
+  fn test_collapse_file_start_and_end_without_outer_context() {
+    let lines = diff_lines("uuuuuuuuauuuuuuuu");
+    let regions = UnchangedRegions::new().collapsed_regions(&lines, 5);
+
+    assert_eq!(regions.len(), 2);
+    assert_eq!(regions[0].rows, 0..5);
+    assert_eq!(regions[1].rows, 12..17);
+  }
+
+  #[test]
+  fn test_diff_without_changes_is_not_collapsed() {
+    let lines = diff_lines("uuuuuuuuuuuuu");
+    assert!(
+      UnchangedRegions::new()
+        .collapsed_regions(&lines, 2)
+        .is_empty()
+    );
+  }
+
+  #[test]
+  fn test_display_rows_replace_regions() {
+    let lines = diff_lines("auuuuuuuuuua");
+    let rows = UnchangedRegions::new().display_rows(lines, 5);
+
+    assert_eq!(rows.len(), 9);
+    assert!(matches!(&rows[4], DisplayRow::Collapsed(r) if r.line_count() == 4));
+    assert_eq!(rows[5].diff_line().unwrap().line_number, 9);
+  }
+
+  #[test]
+  fn test_expanded_region_is_shown() {
+    let lines = diff_lines("auuuuuuuuuua");
+    let mut regions = UnchangedRegions::new();
+    regions.expand(4);
+
+    assert_eq!(regions.display_rows(lines, 5).len(), 12);
+  }
+
+  #[test]
+  fn test_expanded_regions_follow_edits() {
+    let mut regions = UnchangedRegions::new();
+    regions.expand(4);
+    regions.expand(20);
+
+    // Two lines inserted at line 10
+    regions.apply_edits(&[LineEdit {
+      line_start: 10,
+      line_end: 10,
+      line_delta: 2,
+    }]);
+
+    assert!(regions.is_expanded(4));
+    assert!(regions.is_expanded(22));
+    assert!(!regions.is_expanded(20));
+  }
+
+  #[test]
+  fn test_display_index() {
+    let lines = diff_lines("auuuuuuuuuua");
+    let rows = UnchangedRegions::new().display_rows(lines, 5);
+
+    assert_eq!(display_index(&rows, 0), 0);
+    assert_eq!(display_index(&rows, 5), 4); // Hidden in the region
+    assert_eq!(display_index(&rows, 11), 8);
+  }
+
+  #[test]
+  fn test_display_map() {
+    let map = display_map(&line_rows(diff_lines("urrau")), 3);
+    assert_eq!(map.line_row(0), Some(0));
+    assert_eq!(map.line_row(1), Some(3));
+    assert_eq!(map.row_line(1), None); // Removed
+    assert_eq!(map.row_position(1), RowPosition::Before(1));
+    assert_eq!(map.buffer_lines(1..4), Some(1..2));
+
+    let rows = UnchangedRegions::new().display_rows(diff_lines("auuuuuuuuuua"), 5);
+    let map = display_map(&rows, 12);
+    assert_eq!(map.line_row(5), None); // Hidden in the region
+    assert_eq!(map.row_containing_line(5), Some(4));
+    assert_eq!(map.row_position(4), RowPosition::Before(4));
+    assert_eq!(map.line_row(11), Some(8));
+  }
+
+  #[test]
+  fn test_text_with_removed_lines() {
+    let mut buffer = TextBuffer::new();
+    buffer.insert(0, "u\na\nu\n");
+    let lines = diff_lines("urrauur");
+
+    // Selections going past the removed lines include them, the others don't
+    assert_eq!(
+      text_with_removed_lines(&lines, &buffer, 0..4),
+      "u\nr\nr\na\n"
+    );
+    assert_eq!(text_with_removed_lines(&lines, &buffer, 0..2), "u\n");
+    assert_eq!(text_with_removed_lines(&lines, &buffer, 2..4), "a\n");
+    // Removed lines at the end come with a selection up to the end
+    assert_eq!(text_with_removed_lines(&lines, &buffer, 4..6), "u\nr\n");
+  }
+
+  #[test]
+  fn test_region_contains_line() {
+    let region = CollapsedRegion {
+      rows: 4..8,
+      start_line: 4,
+    };
+    assert!(region.contains_line(4));
+    assert!(region.contains_line(7));
+    assert!(!region.contains_line(8));
+  }
+}
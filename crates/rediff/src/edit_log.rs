@@ -0,0 +1,72 @@
+use editor::{EditCause, EditEvent, EditLog};
+use serde::Serialize;
+use std::time::UNIX_EPOCH;
+
+/// Logged change as exported, one JSON object per line
+#[derive(Debug, Serialize)]
+struct ExportedEdit<'a> {
+  time_ms: u128, // Since the Unix epoch
+  transaction: usize,
+  cause: &'static str,
+  start: usize,
+  end: usize,
+  removed: &'a str,
+  inserted: &'a str,
+}
+
+impl<'a> From<&'a EditEvent> for ExportedEdit<'a> {
+  fn from(event: &'a EditEvent) -> Self {
+    Self {
+      time_ms: event
+        .time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis()),
+      transaction: event.transaction,
+      cause: match event.cause {
+        EditCause::Edit => "edit",
+        EditCause::Undo => "undo",
+        EditCause::Redo => "redo",
+      },
+      start: event.range.start,
+      end: event.range.end,
+      removed: &event.removed,
+      inserted: &event.inserted,
+    }
+  }
+}
+
+/// The log as JSON Lines, for replay or analysis tools
+pub fn export_edit_log(log: &EditLog) -> String {
+  log
+    .events()
+    .iter()
+    .map(|event| serde_json::to_string(&ExportedEdit::from(event)).unwrap() + "\n")
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use editor::Editor;
+
+  #[test]
+  fn test_export_edit_log() {
+    let mut editor = Editor::new();
+    editor.start_edit_log();
+    editor.paste("a\"b");
+    editor.undo();
+
+    let exported = export_edit_log(editor.edit_log().unwrap());
+    let lines: Vec<serde_json::Value> = exported
+      .lines()
+      .map(|line| serde_json::from_str(line).unwrap())
+      .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["cause"], "edit");
+    assert_eq!(lines[0]["inserted"], "a\"b");
+    assert_eq!(lines[1]["cause"], "undo");
+    assert_eq!(lines[1]["start"], 0);
+    assert_eq!(lines[1]["end"], 3);
+    assert_eq!(lines[1]["removed"], "a\"b");
+  }
+}
@@ -0,0 +1,150 @@
+use crate::display_rows::UnchangedRegions;
+use crate::folds::Folds;
+use editor::Selection;
+use gpui::{Pixels, Point, point, px};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// View state of a file, restored when the file is opened again
+#[derive(Debug, Clone)]
+pub struct FileState {
+  pub cursor_index: usize,
+  pub selection: Option<Selection>,
+  pub scroll_offset: Point<Pixels>, // Vertical list offset, negative when scrolled down
+  pub scroll_x: Pixels,
+  pub folds: Folds,
+  pub unchanged_regions: UnchangedRegions,
+}
+
+impl Default for FileState {
+  fn default() -> Self {
+    Self {
+      cursor_index: 0,
+      selection: None,
+      scroll_offset: point(px(0.0), px(0.0)),
+      scroll_x: px(0.0),
+      folds: Folds::new(),
+      unchanged_regions: UnchangedRegions::new(),
+    }
+  }
+}
+
+impl FileState {
+  /// Keeps the cursor and selection inside a buffer of `len` characters,
+  /// the file may have changed while it wasn't open
+  pub fn clamp(&mut self, len: usize) {
+    self.cursor_index = self.cursor_index.min(len);
+    self.selection = self
+      .selection
+      .map(|selection| Selection {
+        start: selection.start.min(len),
+        end: selection.end.min(len),
+        reversed: selection.reversed,
+      })
+      .filter(|selection| !selection.is_empty());
+  }
+}
+
+/// Per-file view states of the files previously opened in an editor
+#[derive(Debug, Clone, Default)]
+pub struct FileStates {
+  states: HashMap<PathBuf, FileState>,
+}
+
+impl FileStates {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn save(&mut self, path: &Path, state: FileState) {
+    self.states.insert(path.to_path_buf(), state);
+  }
+
+  pub fn get(&self, path: &Path) -> Option<&FileState> {
+    self.states.get(path)
+  }
+
+  /// Removes and returns the state of a file, to restore it
+  pub fn take(&mut self, path: &Path) -> Option<FileState> {
+    self.states.remove(path)
+  }
+
+  pub fn forget(&mut self, path: &Path) {
+    self.states.remove(path);
+  }
+
+  pub fn len(&self) -> usize {
+    self.states.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.states.is_empty()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_save_and_take() {
+    let mut states = FileStates::new();
+    let path = Path::new("src/main.rs");
+
+    states.save(
+      path,
+      FileState {
+        cursor_index: 42,
+        scroll_x: px(10.0),
+        ..Default::default()
+      },
+    );
+    assert_eq!(states.len(), 1);
+    assert_eq!(states.get(path).unwrap().cursor_index, 42);
+
+    let state = states.take(path).unwrap();
+    assert_eq!(state.scroll_x, px(10.0));
+    assert!(states.is_empty());
+  }
+
+  #[test]
+  fn test_states_are_per_path() {
+    let mut states = FileStates::new();
+    states.save(
+      Path::new("a.rs"),
+      FileState {
+        cursor_index: 1,
+        ..Default::default()
+      },
+    );
+    states.save(
+      Path::new("b.rs"),
+      FileState {
+        cursor_index: 2,
+        ..Default::default()
+      },
+    );
+
+    assert_eq!(states.get(Path::new("a.rs")).unwrap().cursor_index, 1);
+    assert_eq!(states.get(Path::new("b.rs")).unwrap().cursor_index, 2);
+
+    states.forget(Path::new("a.rs"));
+    assert!(states.get(Path::new("a.rs")).is_none());
+  }
+
+  #[test]
+  fn test_clamp_to_shorter_file() {
+    let mut state = FileState {
+      cursor_index: 50,
+      selection: Some(Selection::new(10, 50)),
+      ..Default::default()
+    };
+
+    state.clamp(20);
+    assert_eq!(state.cursor_index, 20);
+    assert_eq!(state.selection, Some(Selection::new(10, 20)));
+
+    state.clamp(5);
+    assert!(state.selection.is_none());
+  }
+}
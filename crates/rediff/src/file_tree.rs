@@ -0,0 +1,471 @@
+use crate::config::EditorTheme;
+use gpui::{
+  Context, EventEmitter, MouseButton, UniformListScrollHandle, Window, div, prelude::*, px,
+  uniform_list,
+};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+const ROW_HEIGHT: f32 = 22.0;
+const INDENT_WIDTH: f32 = 12.0;
+
+/// How a file differs from the baseline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+  Added, // Not in the baseline
+  Modified,
+}
+
+/// Visible row of a file tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileTreeEntry {
+  pub path: PathBuf,
+  pub depth: usize, // 0 for the entries of the root
+  pub is_dir: bool,
+  pub expanded: bool,
+  pub status: Option<FileStatus>, // Directories get the status of the changed files read inside them
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+  path: PathBuf,
+  is_dir: bool,
+  expanded: bool,
+  children: Option<Vec<Node>>, // Read the first time the directory is expanded
+}
+
+/// Directories and files under a root, read lazily as directories are expanded
+///
+/// With a baseline directory, files are compared with the file at the same relative path
+/// in it when their directory is read
+#[derive(Debug, Clone)]
+pub struct FileTreeModel {
+  root: PathBuf,
+  baseline: Option<PathBuf>,
+  nodes: Vec<Node>,
+  statuses: HashMap<PathBuf, FileStatus>,
+}
+
+impl FileTreeModel {
+  pub fn new(root: impl Into<PathBuf>) -> Self {
+    let mut model = Self {
+      root: root.into(),
+      baseline: None,
+      nodes: Vec::new(),
+      statuses: HashMap::new(),
+    };
+    model.nodes = model.read_dir(&model.root.clone());
+    model
+  }
+
+  pub fn root(&self) -> &Path {
+    &self.root
+  }
+
+  /// Sets the directory files are compared with, the tree is read again
+  pub fn set_baseline(&mut self, baseline: Option<PathBuf>) {
+    self.baseline = baseline;
+    self.refresh();
+  }
+
+  /// Reads the expanded directories again, like after files were added or removed
+  pub fn refresh(&mut self) {
+    let expanded: Vec<PathBuf> = self
+      .entries()
+      .into_iter()
+      .filter(|entry| entry.expanded)
+      .map(|entry| entry.path)
+      .collect();
+    self.statuses.clear();
+    self.nodes = self.read_dir(&self.root.clone());
+    // Parents come first, so each directory is found once its parent is read again
+    for path in expanded {
+      self.set_expanded(&path, true);
+    }
+  }
+
+  /// Expands or collapses a directory, returns false when `path` isn't a directory of the tree
+  pub fn toggle(&mut self, path: &Path) -> bool {
+    let Some(expanded) = self.node_mut(path).map(|node| node.expanded) else {
+      return false;
+    };
+    self.set_expanded(path, !expanded)
+  }
+
+  pub fn set_expanded(&mut self, path: &Path, expanded: bool) -> bool {
+    let children = match self.node_mut(path) {
+      Some(node) if node.is_dir => {
+        if node.children.is_some() || !expanded {
+          node.expanded = expanded;
+          return true;
+        }
+        node.path.clone()
+      }
+      _ => return false,
+    };
+    let children = self.read_dir(&children);
+    if let Some(node) = self.node_mut(path) {
+      node.children = Some(children);
+      node.expanded = true;
+    }
+    true
+  }
+
+  /// Expands the directories above `path` so that it's visible
+  pub fn reveal(&mut self, path: &Path) {
+    let Ok(relative) = path.strip_prefix(&self.root) else {
+      return;
+    };
+    let mut dir = self.root.clone();
+    let components: Vec<_> = relative.components().collect();
+    for component in components.iter().take(components.len().saturating_sub(1)) {
+      dir.push(component);
+      if !self.set_expanded(&dir, true) {
+        return;
+      }
+    }
+  }
+
+  /// Overrides the status of a file, like one whose unsaved edits differ from the baseline
+  pub fn set_status(&mut self, path: &Path, status: Option<FileStatus>) {
+    match status {
+      Some(status) => self.statuses.insert(path.to_path_buf(), status),
+      None => self.statuses.remove(path),
+    };
+  }
+
+  pub fn status(&self, path: &Path) -> Option<FileStatus> {
+    self.statuses.get(path).copied()
+  }
+
+  /// Rows of the expanded directories, directories before files
+  pub fn entries(&self) -> Vec<FileTreeEntry> {
+    let mut entries = Vec::new();
+    self.push_entries(&self.nodes, 0, &mut entries);
+    entries
+  }
+
+  fn push_entries(&self, nodes: &[Node], depth: usize, entries: &mut Vec<FileTreeEntry>) {
+    for node in nodes {
+      let status = if node.is_dir {
+        self
+          .statuses
+          .keys()
+          .any(|path| path.starts_with(&node.path))
+          .then_some(FileStatus::Modified)
+      } else {
+        self.status(&node.path)
+      };
+      entries.push(FileTreeEntry {
+        path: node.path.clone(),
+        depth,
+        is_dir: node.is_dir,
+        expanded: node.expanded,
+        status,
+      });
+      if node.expanded
+        && let Some(children) = &node.children
+      {
+        self.push_entries(children, depth + 1, entries);
+      }
+    }
+  }
+
+  fn node_mut(&mut self, path: &Path) -> Option<&mut Node> {
+    let mut nodes = &mut self.nodes;
+    loop {
+      let current = nodes;
+      let node = current
+        .iter_mut()
+        .find(|node| path.starts_with(&node.path))?;
+      if node.path == path {
+        return Some(node);
+      }
+      nodes = node.children.as_mut()?;
+    }
+  }
+
+  /// Entries of a directory, the statuses of its files are computed along the way
+  fn read_dir(&mut self, dir: &Path) -> Vec<Node> {
+    let Ok(entries) = fs::read_dir(dir) else {
+      return Vec::new();
+    };
+    let mut nodes: Vec<Node> = entries
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.file_name() != ".git")
+      .map(|entry| Node {
+        path: entry.path(),
+        is_dir: entry.file_type().is_ok_and(|file_type| file_type.is_dir()),
+        expanded: false,
+        children: None,
+      })
+      .collect();
+    nodes.sort_by(|a, b| {
+      b.is_dir
+        .cmp(&a.is_dir)
+        .then_with(|| a.path.file_name().cmp(&b.path.file_name()))
+    });
+
+    if let Some(baseline) = &self.baseline {
+      for node in nodes.iter().filter(|node| !node.is_dir) {
+        let Ok(relative) = node.path.strip_prefix(&self.root) else {
+          continue;
+        };
+        if let Some(status) = file_status(&node.path, &baseline.join(relative)) {
+          self.statuses.insert(node.path.clone(), status);
+        }
+      }
+    }
+    nodes
+  }
+}
+
+/// Status of `path` against the file at `baseline_path`, None when they're the same
+fn file_status(path: &Path, baseline_path: &Path) -> Option<FileStatus> {
+  let Ok(baseline_metadata) = fs::metadata(baseline_path) else {
+    return Some(FileStatus::Added);
+  };
+  // Sizes differ far more often than contents of the same size
+  if fs::metadata(path).is_ok_and(|metadata| metadata.len() != baseline_metadata.len()) {
+    return Some(FileStatus::Modified);
+  }
+  match (fs::read(path), fs::read(baseline_path)) {
+    (Ok(content), Ok(baseline)) if content == baseline => None,
+    _ => Some(FileStatus::Modified),
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileTreeEvent {
+  Opened(PathBuf), // A file was clicked
+}
+
+/// File tree panel to show next to a `DiffEditor`, files are opened through `FileTreeEvent`
+pub struct FileTree {
+  model: FileTreeModel,
+  selected: Option<PathBuf>,
+  theme: EditorTheme,
+  scroll_handle: UniformListScrollHandle,
+}
+
+impl EventEmitter<FileTreeEvent> for FileTree {}
+
+impl FileTree {
+  pub fn new(root: impl Into<PathBuf>, theme: EditorTheme) -> Self {
+    Self {
+      model: FileTreeModel::new(root),
+      selected: None,
+      theme,
+      scroll_handle: UniformListScrollHandle::new(),
+    }
+  }
+
+  pub fn model(&self) -> &FileTreeModel {
+    &self.model
+  }
+
+  pub fn set_baseline(&mut self, baseline: Option<PathBuf>, cx: &mut Context<Self>) {
+    self.model.set_baseline(baseline);
+    cx.notify();
+  }
+
+  pub fn set_status(&mut self, path: &Path, status: Option<FileStatus>, cx: &mut Context<Self>) {
+    self.model.set_status(path, status);
+    cx.notify();
+  }
+
+  pub fn set_theme(&mut self, theme: EditorTheme, cx: &mut Context<Self>) {
+    self.theme = theme;
+    cx.notify();
+  }
+
+  pub fn refresh(&mut self, cx: &mut Context<Self>) {
+    self.model.refresh();
+    cx.notify();
+  }
+
+  /// Highlights a file and expands the directories above it, without emitting `Opened`
+  pub fn select(&mut self, path: &Path, cx: &mut Context<Self>) {
+    self.model.reveal(path);
+    self.selected = Some(path.to_path_buf());
+    cx.notify();
+  }
+
+  fn on_entry_click(&mut self, entry: &FileTreeEntry, cx: &mut Context<Self>) {
+    if entry.is_dir {
+      self.model.toggle(&entry.path);
+    } else {
+      self.selected = Some(entry.path.clone());
+      cx.emit(FileTreeEvent::Opened(entry.path.clone()));
+    }
+    cx.notify();
+  }
+}
+
+impl Render for FileTree {
+  fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    let entries = self.model.entries();
+    let selected = self.selected.clone();
+    let theme = &self.theme;
+    let text_color = theme.code.text_color;
+    let selected_bg_color = theme.cursor.selection_color;
+    let added_color = theme.git.added.gutter_color;
+    let modified_color = theme.git.modified.gutter_color;
+    let this = cx.entity().downgrade();
+
+    div().size_full().bg(theme.code.bg_color).child(
+      uniform_list(
+        "file-tree",
+        entries.len(),
+        move |range: Range<usize>, _window, _cx| {
+          range
+            .filter_map(|idx| {
+              let entry = entries.get(idx)?.clone();
+              let name = entry
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+              let color = match entry.status {
+                Some(FileStatus::Added) => added_color,
+                Some(FileStatus::Modified) => modified_color,
+                None => text_color,
+              };
+              let chevron = match (entry.is_dir, entry.expanded) {
+                (true, true) => "▾",
+                (true, false) => "▸",
+                (false, _) => "",
+              };
+              let is_selected = selected.as_ref() == Some(&entry.path);
+              let this = this.clone();
+
+              Some(
+                div()
+                  .h(px(ROW_HEIGHT))
+                  .w_full()
+                  .flex()
+                  .items_center()
+                  .gap_1()
+                  .pl(px(4.0 + entry.depth as f32 * INDENT_WIDTH))
+                  .overflow_hidden()
+                  .whitespace_nowrap()
+                  .cursor_pointer()
+                  .text_color(color)
+                  .when(is_selected, |d| d.bg(selected_bg_color))
+                  .on_mouse_down(MouseButton::Left, move |_, _window, cx| {
+                    this
+                      .update(cx, |tree, cx| tree.on_entry_click(&entry, cx))
+                      .ok();
+                  })
+                  .child(div().w(px(INDENT_WIDTH)).child(chevron))
+                  .child(name),
+              )
+            })
+            .collect::<Vec<_>>()
+        },
+      )
+      .size_full()
+      .track_scroll(self.scroll_handle.clone()),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn tree_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    fs::remove_dir_all(&dir).ok();
+    for (path, content) in files {
+      let path = dir.join(path);
+      fs::create_dir_all(path.parent().unwrap()).unwrap();
+      fs::write(path, content).unwrap();
+    }
+    dir
+  }
+
+  fn names(model: &FileTreeModel) -> Vec<(usize, String)> {
+    model
+      .entries()
+      .into_iter()
+      .map(|entry| {
+        let name = entry.path.strip_prefix(model.root()).unwrap();
+        (entry.depth, name.to_string_lossy().to_string())
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_expand_collapse() {
+    let root = tree_dir(
+      "rediff_test_file_tree",
+      &[("b.txt", ""), ("src/main.rs", ""), ("src/ui/view.rs", "")],
+    );
+    let mut model = FileTreeModel::new(&root);
+    assert_eq!(
+      names(&model),
+      vec![(0, "src".to_string()), (0, "b.txt".to_string())]
+    );
+
+    assert!(model.toggle(&root.join("src")));
+    assert!(!model.toggle(&root.join("b.txt")));
+    assert_eq!(
+      names(&model),
+      vec![
+        (0, "src".to_string()),
+        (1, "src/ui".to_string()),
+        (1, "src/main.rs".to_string()),
+        (0, "b.txt".to_string()),
+      ]
+    );
+
+    // Collapsing keeps what was read, expanding again shows it as it was
+    model.reveal(&root.join("src/ui/view.rs"));
+    model.toggle(&root.join("src"));
+    assert_eq!(names(&model).len(), 2);
+    model.toggle(&root.join("src"));
+    assert_eq!(names(&model).len(), 5);
+    fs::remove_dir_all(&root).ok();
+  }
+
+  #[test]
+  fn test_baseline_statuses() {
+    let root = tree_dir(
+      "rediff_test_file_tree_current",
+      &[("same.txt", "a"), ("changed.txt", "b"), ("src/new.rs", "")],
+    );
+    let baseline = tree_dir(
+      "rediff_test_file_tree_baseline",
+      &[("same.txt", "a"), ("changed.txt", "c")],
+    );
+    let mut model = FileTreeModel::new(&root);
+    model.set_baseline(Some(baseline.clone()));
+
+    assert_eq!(model.status(&root.join("same.txt")), None);
+    assert_eq!(
+      model.status(&root.join("changed.txt")),
+      Some(FileStatus::Modified)
+    );
+    // Read once the directory is expanded
+    assert_eq!(model.status(&root.join("src/new.rs")), None);
+    model.toggle(&root.join("src"));
+    assert_eq!(
+      model.status(&root.join("src/new.rs")),
+      Some(FileStatus::Added)
+    );
+    let src = model
+      .entries()
+      .into_iter()
+      .find(|entry| entry.is_dir)
+      .unwrap();
+    assert_eq!(src.status, Some(FileStatus::Modified));
+
+    model.set_status(&root.join("changed.txt"), None);
+    assert_eq!(model.status(&root.join("changed.txt")), None);
+    fs::remove_dir_all(&root).ok();
+    fs::remove_dir_all(&baseline).ok();
+  }
+}
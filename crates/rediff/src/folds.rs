@@ -0,0 +1,296 @@
+use crate::display_rows::DisplayRow;
+use editor::LineEdit;
+use std::collections::BTreeSet;
+
+/// Lines that can be folded under a header line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoldRange {
+  pub start_line: usize, // Header line (0-based), stays visible when folded
+  pub end_line: usize,   // Last hidden line
+}
+
+impl FoldRange {
+  pub fn hides_line(&self, line_idx: usize) -> bool {
+    line_idx > self.start_line && line_idx <= self.end_line
+  }
+}
+
+/// Computes fold ranges from indentation: a line starts a range covering the
+/// following lines that are more indented, blank lines don't end a range
+pub fn indent_fold_ranges(text: &str, tab_size: usize) -> Vec<FoldRange> {
+  let mut ranges = Vec::new();
+  let mut open: Vec<(usize, usize, Option<usize>)> = Vec::new(); // (start line, indent, last nested line)
+
+  for (line_idx, line) in text.lines().enumerate() {
+    let Some(indent) = indent_width(line, tab_size) else {
+      continue;
+    };
+
+    while let Some(&(start_line, open_indent, end_line)) = open.last() {
+      if open_indent < indent {
+        break;
+      }
+      open.pop();
+      if let Some(end_line) = end_line {
+        ranges.push(FoldRange {
+          start_line,
+          end_line,
+        });
+      }
+    }
+
+    for (_, _, end_line) in open.iter_mut() {
+      *end_line = Some(line_idx);
+    }
+    open.push((line_idx, indent, None));
+  }
+
+  ranges.extend(open.into_iter().filter_map(|(start_line, _, end_line)| {
+    end_line.map(|end_line| FoldRange {
+      start_line,
+      end_line,
+    })
+  }));
+  ranges.sort_by_key(|range| range.start_line);
+  ranges
+}
+
+/// Width of the leading whitespace, tabs count as `tab_size` columns. None for blank lines
+fn indent_width(line: &str, tab_size: usize) -> Option<usize> {
+  let tab_size = tab_size.max(1);
+  let mut width = 0;
+  for c in line.chars() {
+    match c {
+      ' ' => width += 1,
+      '\t' => width += tab_size - width % tab_size,
+      c if c.is_whitespace() => width += 1,
+      _ => return Some(width),
+    }
+  }
+  None
+}
+
+/// Header lines of the folded ranges, kept across edits
+#[derive(Debug, Clone, Default)]
+pub struct Folds {
+  folded: BTreeSet<usize>,
+}
+
+impl Folds {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn is_folded(&self, start_line: usize) -> bool {
+    self.folded.contains(&start_line)
+  }
+
+  pub fn fold(&mut self, start_line: usize) {
+    self.folded.insert(start_line);
+  }
+
+  pub fn unfold(&mut self, start_line: usize) {
+    self.folded.remove(&start_line);
+  }
+
+  pub fn toggle(&mut self, start_line: usize) {
+    if !self.folded.remove(&start_line) {
+      self.folded.insert(start_line);
+    }
+  }
+
+  pub fn unfold_all(&mut self) {
+    self.folded.clear();
+  }
+
+  /// Shifts the folds that follow edited lines
+  pub fn apply_edits(&mut self, edits: &[LineEdit]) {
+    for edit in edits {
+      self.folded = std::mem::take(&mut self.folded)
+        .into_iter()
+        .filter_map(|line_idx| {
+          if line_idx <= edit.line_end {
+            Some(line_idx)
+          } else {
+            line_idx.checked_add_signed(edit.line_delta)
+          }
+        })
+        .collect();
+    }
+  }
+
+  /// Drops folds whose header no longer starts a range
+  pub fn retain_ranges(&mut self, ranges: &[FoldRange]) {
+    self
+      .folded
+      .retain(|start_line| ranges.iter().any(|range| range.start_line == *start_line));
+  }
+
+  pub fn folded_ranges(&self, ranges: &[FoldRange]) -> Vec<FoldRange> {
+    ranges
+      .iter()
+      .filter(|range| self.is_folded(range.start_line))
+      .copied()
+      .collect()
+  }
+}
+
+/// Removes the rows hidden by folded ranges
+///
+/// Removed lines have no buffer line, they're hidden with the buffer line preceding them
+/// unless it's the last line of the fold
+pub fn hide_folded_rows(rows: Vec<DisplayRow>, folded: &[FoldRange]) -> Vec<DisplayRow> {
+  if folded.is_empty() {
+    return rows;
+  }
+
+  let hidden = |line_idx: usize| folded.iter().any(|range| range.hides_line(line_idx));
+  let hides_removed_after = |line_idx: usize| {
+    folded
+      .iter()
+      .any(|range| line_idx >= range.start_line && line_idx < range.end_line)
+  };
+
+  let mut previous_line = None;
+  rows
+    .into_iter()
+    .filter(|row| match row {
+      DisplayRow::Line { line, .. } if line.line_number > 0 => {
+        let line_idx = line.line_number - 1;
+        previous_line = Some(line_idx);
+        !hidden(line_idx)
+      }
+      DisplayRow::Line { .. } => !previous_line.is_some_and(hides_removed_after),
+      DisplayRow::Collapsed(region) => {
+        previous_line = Some(region.start_line + region.line_count() - 1);
+        !hidden(region.start_line)
+      }
+      DisplayRow::Thread(_) | DisplayRow::Original(_) => true,
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rediff_core::{DiffLine, DiffLineKind};
+
+  fn range(start_line: usize, end_line: usize) -> FoldRange {
+    FoldRange {
+      start_line,
+      end_line,
+    }
+  }
+
+  #[test]
+  fn test_indent_fold_ranges() {
+    let text = "fn main() {\n  if ok {\n    run();\n  }\n}\n";
+    assert_eq!(indent_fold_ranges(text, 2), vec![range(0, 3), range(1, 2)]);
+  }
+
+  #[test]
+  fn test_indent_fold_ranges_skip_blank_lines() {
+    let text = "a:\n  b\n\n  c\nd\n\n";
+    assert_eq!(indent_fold_ranges(text, 2), vec![range(0, 3)]);
+  }
+
+  #[test]
+  fn test_indent_fold_ranges_with_tabs() {
+    let text = "a\n\tb\n    c\nd";
+    assert_eq!(indent_fold_ranges(text, 4), vec![range(0, 2)]);
+  }
+
+  #[test]
+  fn test_indent_fold_ranges_open_at_end() {
+    let text = "a\n  b\n    c";
+    assert_eq!(indent_fold_ranges(text, 2), vec![range(0, 2), range(1, 2)]);
+  }
+
+  #[test]
+  fn test_flat_text_has_no_ranges() {
+    assert!(indent_fold_ranges("a\nb\nc\n", 2).is_empty());
+  }
+
+  #[test]
+  fn test_toggle_and_folded_ranges() {
+    let ranges = vec![range(0, 3), range(1, 2)];
+    let mut folds = Folds::new();
+
+    folds.toggle(1);
+    assert_eq!(folds.folded_ranges(&ranges), vec![range(1, 2)]);
+
+    folds.toggle(1);
+    assert!(folds.folded_ranges(&ranges).is_empty());
+  }
+
+  #[test]
+  fn test_folds_follow_edits() {
+    let mut folds = Folds::new();
+    folds.fold(2);
+    folds.fold(10);
+
+    folds.apply_edits(&[LineEdit {
+      line_start: 5,
+      line_end: 6,
+      line_delta: -1,
+    }]);
+
+    assert!(folds.is_folded(2));
+    assert!(folds.is_folded(9));
+  }
+
+  #[test]
+  fn test_retain_ranges() {
+    let mut folds = Folds::new();
+    folds.fold(0);
+    folds.fold(4);
+
+    folds.retain_ranges(&[range(0, 2)]);
+
+    assert!(folds.is_folded(0));
+    assert!(!folds.is_folded(4));
+  }
+
+  fn row(row: usize, line_number: usize) -> DisplayRow {
+    DisplayRow::Line {
+      row,
+      line: DiffLine {
+        line_number,
+        kind: if line_number == 0 {
+          DiffLineKind::Removed
+        } else {
+          DiffLineKind::Unchanged
+        },
+        content: String::new(),
+        char_changes: vec![],
+        is_first_in_group: false,
+        pair: None,
+        counterpart: None,
+      },
+    }
+  }
+
+  #[test]
+  fn test_hide_folded_rows() {
+    // Lines 1 to 3 hidden under line 0, a removed line inside the fold and one after it
+    let rows = vec![
+      row(0, 1),
+      row(1, 2),
+      row(2, 0),
+      row(3, 3),
+      row(4, 4),
+      row(5, 0),
+      row(6, 5),
+    ];
+    let visible = hide_folded_rows(rows, &[range(0, 3)]);
+
+    let visible_rows: Vec<_> = visible
+      .iter()
+      .map(|r| match r {
+        DisplayRow::Line { row, .. } => *row,
+        DisplayRow::Collapsed(_) | DisplayRow::Thread(_) | DisplayRow::Original(_) => usize::MAX,
+      })
+      .collect();
+    assert_eq!(visible_rows, vec![0, 5, 6]);
+  }
+}
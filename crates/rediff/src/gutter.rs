@@ -0,0 +1,220 @@
+use gpui::{AnyElement, App, ElementId, MouseDownEvent, Window};
+use rediff_core::DiffLine;
+use std::rc::Rc;
+
+type RenderGutterRow = Rc<dyn Fn(&GutterRow, &mut Window, &mut App) -> AnyElement>;
+type GutterClickHandler = Rc<dyn Fn(&GutterRow, &MouseDownEvent, &mut Window, &mut App)>;
+
+/// Identifies a gutter column, given back by `GutterColumns::add`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GutterColumnId(usize);
+
+impl From<GutterColumnId> for ElementId {
+  fn from(id: GutterColumnId) -> Self {
+    ("gutter-column", id.0).into()
+  }
+}
+
+/// Display row a gutter cell is drawn for
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GutterRow {
+  pub row: usize,
+  pub line: Option<usize>, // Buffer line, None for removed lines and rows without a line
+  pub diff_line: Option<DiffLine>,
+}
+
+impl GutterRow {
+  pub fn new(row: usize, diff_line: Option<&DiffLine>) -> Self {
+    Self {
+      row,
+      line: diff_line.and_then(|line| line.line_number.checked_sub(1)),
+      diff_line: diff_line.cloned(),
+    }
+  }
+}
+
+/// Column of the gutter, the diff strip and the line numbers are columns too
+///
+/// The cell of each visible row is rendered by the column, clicks on a cell go to its click
+/// handler and don't reach the text behind it
+#[derive(Clone)]
+pub struct GutterColumn {
+  width: f32,
+  render: RenderGutterRow,
+  on_click: Option<GutterClickHandler>,
+}
+
+impl GutterColumn {
+  pub fn new(
+    width: f32,
+    render: impl Fn(&GutterRow, &mut Window, &mut App) -> AnyElement + 'static,
+  ) -> Self {
+    Self {
+      width,
+      render: Rc::new(render),
+      on_click: None,
+    }
+  }
+
+  pub fn on_click(
+    mut self,
+    on_click: impl Fn(&GutterRow, &MouseDownEvent, &mut Window, &mut App) + 'static,
+  ) -> Self {
+    self.on_click = Some(Rc::new(on_click));
+    self
+  }
+
+  pub fn width(&self) -> f32 {
+    self.width
+  }
+
+  pub fn render_row(&self, row: &GutterRow, window: &mut Window, cx: &mut App) -> AnyElement {
+    (self.render)(row, window, cx)
+  }
+
+  pub fn click_handler(&self) -> Option<GutterClickHandler> {
+    self.on_click.clone()
+  }
+}
+
+impl std::fmt::Debug for GutterColumn {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("GutterColumn")
+      .field("width", &self.width)
+      .field("on_click", &self.on_click.is_some())
+      .finish()
+  }
+}
+
+/// Columns added by the host, drawn left to right between the line numbers and the text
+#[derive(Debug, Clone, Default)]
+pub struct GutterColumns {
+  columns: Vec<(GutterColumnId, GutterColumn)>,
+  next_id: usize,
+}
+
+impl GutterColumns {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds a column after the previous ones
+  pub fn add(&mut self, column: GutterColumn) -> GutterColumnId {
+    let id = GutterColumnId(self.next_id);
+    self.next_id += 1;
+    self.columns.push((id, column));
+    id
+  }
+
+  pub fn get(&self, id: GutterColumnId) -> Option<&GutterColumn> {
+    self
+      .columns
+      .iter()
+      .find(|(column_id, _)| *column_id == id)
+      .map(|(_, column)| column)
+  }
+
+  /// Replaces a column in place, like when the data it shows changed
+  pub fn replace(&mut self, id: GutterColumnId, column: GutterColumn) -> bool {
+    match self
+      .columns
+      .iter_mut()
+      .find(|(column_id, _)| *column_id == id)
+    {
+      Some((_, existing)) => {
+        *existing = column;
+        true
+      }
+      None => false,
+    }
+  }
+
+  pub fn remove(&mut self, id: GutterColumnId) -> Option<GutterColumn> {
+    let ix = self
+      .columns
+      .iter()
+      .position(|(column_id, _)| *column_id == id)?;
+    Some(self.columns.remove(ix).1)
+  }
+
+  pub fn len(&self) -> usize {
+    self.columns.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.columns.is_empty()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (GutterColumnId, &GutterColumn)> {
+    self.columns.iter().map(|(id, column)| (*id, column))
+  }
+
+  /// Width of all the columns together
+  pub fn width(&self) -> f32 {
+    self.columns.iter().map(|(_, column)| column.width).sum()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use gpui::{IntoElement, div};
+  use rediff_core::DiffLineKind;
+
+  fn column(width: f32) -> GutterColumn {
+    GutterColumn::new(width, |_, _, _| div().into_any_element())
+  }
+
+  #[test]
+  fn test_add_replace_remove() {
+    let mut columns = GutterColumns::new();
+    let coverage = columns.add(column(4.0));
+    let review = columns.add(column(16.0).on_click(|_, _, _, _| {}));
+
+    assert_eq!(columns.width(), 20.0);
+    let widths: Vec<f32> = columns.iter().map(|(_, column)| column.width()).collect();
+    assert_eq!(widths, vec![4.0, 16.0]);
+    assert!(columns.get(review).unwrap().click_handler().is_some());
+
+    assert!(columns.replace(coverage, column(6.0)));
+    assert_eq!(columns.get(coverage).unwrap().width(), 6.0);
+
+    assert_eq!(columns.remove(coverage).unwrap().width(), 6.0);
+    assert!(columns.remove(coverage).is_none());
+    assert!(!columns.replace(coverage, column(6.0)));
+    assert_eq!(columns.len(), 1);
+
+    // Ids aren't reused once removed
+    let third = columns.add(column(4.0));
+    assert_ne!(third, coverage);
+    assert_ne!(third, review);
+  }
+
+  fn diff_line(line_number: usize, kind: DiffLineKind) -> DiffLine {
+    DiffLine {
+      line_number,
+      kind,
+      content: String::new(),
+      char_changes: vec![],
+      is_first_in_group: false,
+      pair: None,
+      counterpart: None,
+    }
+  }
+
+  #[test]
+  fn test_gutter_row_line() {
+    let added = diff_line(3, DiffLineKind::Added);
+    let removed = diff_line(0, DiffLineKind::Removed);
+
+    assert_eq!(GutterRow::new(5, Some(&added)).line, Some(2));
+    assert_eq!(GutterRow::new(6, Some(&removed)).line, None);
+    assert_eq!(
+      GutterRow::new(7, None),
+      GutterRow {
+        row: 7,
+        ..Default::default()
+      }
+    );
+  }
+}
@@ -0,0 +1,249 @@
+use gpui::{Image, ImageFormat};
+use image::{ImageError, ImageReader, Rgba, RgbaImage};
+use std::cell::OnceCell;
+use std::io::Cursor;
+use std::sync::Arc;
+
+/// Largest difference of a channel between pixels that count as the same, JPEG compression
+/// shifts colors a little
+pub const PIXEL_TOLERANCE: u8 = 8;
+
+const HIGHLIGHT: Rgba<u8> = Rgba([255, 0, 255, 255]);
+
+/// Image formats shown as images instead of the binary placeholder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageKind {
+  Png,
+  Jpeg,
+}
+
+impl ImageKind {
+  /// Format of `bytes` from their signature, None when they're not an image
+  pub fn detect(bytes: &[u8]) -> Option<Self> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+      Some(Self::Png)
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+      Some(Self::Jpeg)
+    } else {
+      None
+    }
+  }
+
+  fn format(self) -> ImageFormat {
+    match self {
+      Self::Png => ImageFormat::Png,
+      Self::Jpeg => ImageFormat::Jpeg,
+    }
+  }
+}
+
+/// Width and height of an encoded image, None when it can't be read
+pub fn image_size(bytes: &[u8]) -> Option<(u32, u32)> {
+  ImageReader::new(Cursor::new(bytes))
+    .with_guessed_format()
+    .ok()?
+    .into_dimensions()
+    .ok()
+}
+
+pub fn decode(bytes: &[u8]) -> Result<RgbaImage, ImageError> {
+  Ok(image::load_from_memory(bytes)?.to_rgba8())
+}
+
+/// Pixels of two images compared at the same position
+#[derive(Debug, Clone, PartialEq)]
+pub struct PixelDiff {
+  pub image: RgbaImage, // Covers both images, changed pixels highlighted over a faded gray copy of the current one
+  pub changed: usize,   // Pixels that differ, or that only one image has
+}
+
+impl PixelDiff {
+  pub fn new(original: &RgbaImage, current: &RgbaImage) -> Self {
+    let width = original.width().max(current.width());
+    let height = original.height().max(current.height());
+    let mut changed = 0;
+    let image = RgbaImage::from_fn(width, height, |x, y| {
+      match (
+        original.get_pixel_checked(x, y),
+        current.get_pixel_checked(x, y),
+      ) {
+        (Some(a), Some(b)) if same_pixel(a, b) => faded(b),
+        _ => {
+          changed += 1;
+          HIGHLIGHT
+        }
+      }
+    });
+    Self { image, changed }
+  }
+
+  pub fn total(&self) -> usize {
+    self.image.width() as usize * self.image.height() as usize
+  }
+
+  pub fn to_png(&self) -> Result<Vec<u8>, ImageError> {
+    let mut png = Vec::new();
+    self
+      .image
+      .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)?;
+    Ok(png)
+  }
+}
+
+/// Fully transparent pixels are the same whatever their color
+fn same_pixel(a: &Rgba<u8>, b: &Rgba<u8>) -> bool {
+  if a[3] == 0 && b[3] == 0 {
+    return true;
+  }
+  a.0
+    .iter()
+    .zip(b.0.iter())
+    .all(|(a, b)| a.abs_diff(*b) <= PIXEL_TOLERANCE)
+}
+
+/// Gray see-through copy of an unchanged pixel, so that changes stand out
+fn faded(pixel: &Rgba<u8>) -> Rgba<u8> {
+  let [r, g, b, a] = pixel.0;
+  let luminance = (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000;
+  Rgba([luminance as u8, luminance as u8, luminance as u8, a / 3])
+}
+
+/// One side of an image diff
+#[derive(Debug, Clone)]
+pub struct ImageSide {
+  pub image: Arc<Image>,
+  pub size: Option<(u32, u32)>, // None when the header can't be read
+}
+
+impl ImageSide {
+  /// None when `bytes` aren't a known image format
+  pub fn new(bytes: &[u8]) -> Option<Self> {
+    let kind = ImageKind::detect(bytes)?;
+    Some(Self {
+      image: Arc::new(Image::from_bytes(kind.format(), bytes.to_vec())),
+      size: image_size(bytes),
+    })
+  }
+}
+
+/// Both sides of an image diff, a missing side is an image added or deleted
+///
+/// The pixel difference is only computed once it's first shown
+#[derive(Debug, Default)]
+pub struct ImageSides {
+  pub original: Option<ImageSide>,
+  pub current: Option<ImageSide>,
+  difference: OnceCell<Option<(Arc<Image>, PixelCount)>>,
+}
+
+/// Changed and total pixels of a difference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelCount {
+  pub changed: usize,
+  pub total: usize,
+}
+
+impl ImageSides {
+  pub fn new(original: Option<&[u8]>, current: Option<&[u8]>) -> Self {
+    Self {
+      original: original.and_then(ImageSide::new),
+      current: current.and_then(ImageSide::new),
+      difference: OnceCell::new(),
+    }
+  }
+
+  /// Difference image with its pixel counts, None when a side can't be decoded
+  pub fn difference(&self) -> Option<&(Arc<Image>, PixelCount)> {
+    self
+      .difference
+      .get_or_init(|| {
+        let decode_side = |side: &Option<ImageSide>| match side {
+          Some(side) => decode(side.image.bytes()),
+          None => Ok(RgbaImage::new(0, 0)),
+        };
+        let result = decode_side(&self.original).and_then(|original| {
+          let current = decode_side(&self.current)?;
+          let diff = PixelDiff::new(&original, &current);
+          let count = PixelCount {
+            changed: diff.changed,
+            total: diff.total(),
+          };
+          Ok((
+            Arc::new(Image::from_bytes(ImageFormat::Png, diff.to_png()?)),
+            count,
+          ))
+        });
+        match result {
+          Ok(difference) => Some(difference),
+          Err(error) => {
+            eprintln!("Failed to compare images: {}", error);
+            None
+          }
+        }
+      })
+      .as_ref()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn filled(width: u32, height: u32, color: [u8; 4]) -> RgbaImage {
+    RgbaImage::from_pixel(width, height, Rgba(color))
+  }
+
+  #[test]
+  fn test_detect() {
+    assert_eq!(
+      ImageKind::detect(b"\x89PNG\r\n\x1a\n rest"),
+      Some(ImageKind::Png)
+    );
+    assert_eq!(
+      ImageKind::detect(&[0xff, 0xd8, 0xff, 0xe0]),
+      Some(ImageKind::Jpeg)
+    );
+    assert_eq!(ImageKind::detect(b"GIF89a"), None);
+    assert_eq!(ImageKind::detect(b""), None);
+  }
+
+  #[test]
+  fn test_pixel_diff() {
+    let original = filled(2, 2, [10, 20, 30, 255]);
+    let mut current = original.clone();
+    current.put_pixel(1, 0, Rgba([200, 20, 30, 255]));
+    current.put_pixel(0, 1, Rgba([14, 24, 34, 255])); // Within the tolerance
+
+    let diff = PixelDiff::new(&original, &current);
+    assert_eq!(diff.changed, 1);
+    assert_eq!(diff.total(), 4);
+    assert_eq!(*diff.image.get_pixel(1, 0), HIGHLIGHT);
+    assert_ne!(*diff.image.get_pixel(0, 0), HIGHLIGHT);
+  }
+
+  #[test]
+  fn test_pixel_diff_of_different_sizes() {
+    let diff = PixelDiff::new(&filled(2, 1, [0, 0, 0, 255]), &filled(1, 2, [0, 0, 0, 255]));
+    assert_eq!((diff.image.width(), diff.image.height()), (2, 2));
+    // Only the top left pixel is in both
+    assert_eq!(diff.changed, 3);
+
+    let diff = PixelDiff::new(&RgbaImage::new(0, 0), &filled(2, 2, [0, 0, 0, 255]));
+    assert_eq!(diff.changed, 4);
+  }
+
+  #[test]
+  fn test_transparent_pixels_are_the_same() {
+    let diff = PixelDiff::new(&filled(1, 1, [255, 0, 0, 0]), &filled(1, 1, [0, 0, 255, 0]));
+    assert_eq!(diff.changed, 0);
+  }
+
+  #[test]
+  fn test_png_round_trip() {
+    let diff = PixelDiff::new(&filled(3, 2, [0, 0, 0, 255]), &filled(3, 2, [9, 9, 9, 255]));
+    let png = diff.to_png().unwrap();
+    assert_eq!(ImageKind::detect(&png), Some(ImageKind::Png));
+    assert_eq!(image_size(&png), Some((3, 2)));
+    assert_eq!(decode(&png).unwrap(), diff.image);
+  }
+}
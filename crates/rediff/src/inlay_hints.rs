@@ -0,0 +1,156 @@
+use editor::{Anchor, Bias, Editor};
+use rediff_core::DiffLine;
+use std::collections::HashMap;
+
+/// Text shown in the line without being part of the buffer, like a parameter name or a type
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlayHint {
+  pub index: usize, // Buffer char index the text shows before, the end of a line shows it after the line
+  pub text: String,
+}
+
+impl InlayHint {
+  pub fn new(index: usize, text: impl Into<String>) -> Self {
+    Self {
+      index,
+      text: text.into(),
+    }
+  }
+
+  /// Hint shown after the last char of a buffer line
+  pub fn at_line_end(editor: &Editor, line: usize, text: impl Into<String>) -> Self {
+    let line_len = editor
+      .buffer
+      .line(line)
+      .map_or(0, |content| content.trim_end_matches('\n').chars().count());
+    Self::new(editor.buffer.line_col_to_char(line, line_len), text)
+  }
+}
+
+/// Inlay hints of a buffer, anchored so that they follow edits
+///
+/// Like diagnostics, the anchors belong to the editor the hints were set in
+#[derive(Debug, Clone, Default)]
+pub struct InlayHints {
+  entries: Vec<(Anchor, String)>,
+}
+
+impl InlayHints {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Replaces every hint, indices are clamped to the buffer
+  pub fn set(&mut self, editor: &mut Editor, hints: Vec<InlayHint>) {
+    self.clear(editor);
+    for hint in hints {
+      // Text typed at the hint goes before it, the cursor stays in front of the hint
+      let anchor = editor.create_anchor(hint.index, Bias::Right);
+      self.entries.push((anchor, hint.text));
+    }
+  }
+
+  pub fn clear(&mut self, editor: &mut Editor) {
+    for (anchor, _) in self.entries.drain(..) {
+      editor.remove_anchor(anchor);
+    }
+  }
+
+  /// Hints with their current index, in the order they were set
+  pub fn current(&self, editor: &Editor) -> Vec<InlayHint> {
+    self
+      .entries
+      .iter()
+      .filter_map(|(anchor, text)| {
+        let index = editor.anchor_position(*anchor)?;
+        Some(InlayHint::new(index, text.clone()))
+      })
+      .collect()
+  }
+
+  /// Hints of each buffer line as (column, text), sorted by column
+  pub fn by_line(&self, editor: &Editor) -> HashMap<usize, Vec<(usize, String)>> {
+    let mut lines: HashMap<usize, Vec<(usize, String)>> = HashMap::new();
+    for hint in self.current(editor) {
+      let (line, col) = editor.buffer.char_to_line_col(hint.index);
+      lines.entry(line).or_default().push((col, hint.text));
+    }
+    for hints in lines.values_mut() {
+      hints.sort_by_key(|(col, _)| *col);
+    }
+    lines
+  }
+}
+
+/// A hint at the end of each modified line with the text it replaced
+pub fn original_value_hints(diff_lines: &[DiffLine], editor: &Editor) -> Vec<InlayHint> {
+  diff_lines
+    .iter()
+    .filter(|line| line.line_number > 0)
+    .filter_map(|line| {
+      let pair = line.pair.as_ref()?;
+      let original = pair.content.trim();
+      let text = format!("  original: {}", original);
+      Some(InlayHint::at_line_end(editor, line.line_number - 1, text))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rediff_core::Differ;
+
+  fn editor(text: &str) -> Editor {
+    let mut editor = Editor::new();
+    editor.paste(text);
+    editor
+  }
+
+  #[test]
+  fn test_hints_follow_edits() {
+    let mut editor = editor("foo(1, 2);\n");
+    let mut hints = InlayHints::new();
+    hints.set(
+      &mut editor,
+      vec![InlayHint::new(4, "a: "), InlayHint::new(7, "b: ")],
+    );
+
+    editor.cursor.index = 0;
+    editor.paste("\n");
+    let current = hints.current(&editor);
+    assert_eq!(current[0], InlayHint::new(5, "a: "));
+
+    // Typing right at a hint pushes it along, it stays in front of the same text
+    editor.cursor.index = 5;
+    editor.insert_char('x');
+    assert_eq!(hints.current(&editor)[0].index, 6);
+
+    let lines = hints.by_line(&editor);
+    assert_eq!(
+      lines.get(&1),
+      Some(&vec![(5, "a: ".to_string()), (8, "b: ".to_string())])
+    );
+
+    hints.clear(&mut editor);
+    assert!(hints.current(&editor).is_empty());
+  }
+
+  #[test]
+  fn test_original_value_hints() {
+    let editor = editor("keep\nlet x = 2;\n");
+    let differ = Differ::new("keep\nlet x = 1;\n".to_string());
+    let diff_lines = differ.compute_diff(&editor.buffer.as_str());
+
+    let hints = original_value_hints(&diff_lines, &editor);
+    assert_eq!(hints, vec![InlayHint::new(15, "  original: let x = 1;")]);
+  }
+}
@@ -0,0 +1,144 @@
+use gpui::{
+  AnyElement, App, DispatchPhase, InteractiveElement, IntoElement, KeyDownEvent, MouseDownEvent,
+  MouseEvent, MouseMoveEvent, MouseUpEvent, ParentElement, ScrollWheelEvent, Styled, Window,
+  canvas, div,
+};
+use std::rc::Rc;
+
+/// Key or mouse event reaching the editor
+#[derive(Debug, Clone, Copy)]
+pub enum InputEvent<'a> {
+  KeyDown(&'a KeyDownEvent),
+  MouseDown(&'a MouseDownEvent),
+  MouseUp(&'a MouseUpEvent),
+  MouseMove(&'a MouseMoveEvent),
+  ScrollWheel(&'a ScrollWheelEvent),
+}
+
+/// What a hook did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventResult {
+  Handled, // Stops the event, the editor and the hooks after this one don't get it
+  Pass,
+}
+
+/// Identifies a hook, given back by `InputHooks::add`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InputHookId(usize);
+
+type InputHook = Rc<dyn Fn(&InputEvent, &mut Window, &mut App) -> EventResult>;
+
+/// Hooks of the host that see key and mouse events before the editor, for its own shortcuts,
+/// telemetry or to turn off some operations
+///
+/// Hooks run in the order they were added until one handles the event. Keys reach them before
+/// the keymap, mouse events only while over the editor
+#[derive(Clone, Default)]
+pub struct InputHooks {
+  hooks: Vec<(InputHookId, InputHook)>,
+  next_id: usize,
+}
+
+impl InputHooks {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn add(
+    &mut self,
+    hook: impl Fn(&InputEvent, &mut Window, &mut App) -> EventResult + 'static,
+  ) -> InputHookId {
+    let id = InputHookId(self.next_id);
+    self.next_id += 1;
+    self.hooks.push((id, Rc::new(hook)));
+    id
+  }
+
+  /// Whether the hook was still there
+  pub fn remove(&mut self, id: InputHookId) -> bool {
+    let len = self.hooks.len();
+    self.hooks.retain(|(hook_id, _)| *hook_id != id);
+    self.hooks.len() != len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.hooks.is_empty()
+  }
+
+  pub fn len(&self) -> usize {
+    self.hooks.len()
+  }
+
+  /// Runs the hooks on `event`, Handled as soon as one of them handles it
+  pub fn dispatch(&self, event: &InputEvent, window: &mut Window, cx: &mut App) -> EventResult {
+    for (_, hook) in &self.hooks {
+      if hook(event, window, cx) == EventResult::Handled {
+        return EventResult::Handled;
+      }
+    }
+    EventResult::Pass
+  }
+
+  /// Wraps the editor view so that the hooks get its events first, `content` as is without hooks
+  pub fn wrap(&self, content: AnyElement) -> AnyElement {
+    if self.is_empty() {
+      return content;
+    }
+
+    let hooks = self.clone();
+    let mouse_hooks = self.clone();
+    div()
+      .relative()
+      .size_full()
+      .capture_key_down(move |event, window, cx| {
+        if hooks.dispatch(&InputEvent::KeyDown(event), window, cx) == EventResult::Handled {
+          cx.stop_propagation();
+        }
+      })
+      .child(content)
+      // Paints nothing, only listens to the mouse over the whole view
+      .child(
+        canvas(
+          |_, _, _| {},
+          move |bounds, _, window, _| {
+            let contains = move |position| bounds.contains(&position);
+            mouse_hooks.listen(window, move |event: &MouseDownEvent| {
+              contains(event.position).then_some(InputEvent::MouseDown(event))
+            });
+            mouse_hooks.listen(window, move |event: &MouseUpEvent| {
+              contains(event.position).then_some(InputEvent::MouseUp(event))
+            });
+            mouse_hooks.listen(window, move |event: &MouseMoveEvent| {
+              contains(event.position).then_some(InputEvent::MouseMove(event))
+            });
+            mouse_hooks.listen(window, move |event: &ScrollWheelEvent| {
+              contains(event.position).then_some(InputEvent::ScrollWheel(event))
+            });
+          },
+        )
+        .absolute()
+        .size_full(),
+      )
+      .into_any_element()
+  }
+
+  /// Runs the hooks on mouse events of type `E` before the elements under the mouse get them,
+  /// `filter` gives the events they see
+  fn listen<E: MouseEvent>(
+    &self,
+    window: &mut Window,
+    filter: impl for<'a> Fn(&'a E) -> Option<InputEvent<'a>> + 'static,
+  ) {
+    let hooks = self.clone();
+    window.on_mouse_event(move |event: &E, phase, window, cx| {
+      if phase != DispatchPhase::Capture {
+        return;
+      }
+      if let Some(event) = filter(event)
+        && hooks.dispatch(&event, window, cx) == EventResult::Handled
+      {
+        cx.stop_propagation();
+      }
+    });
+  }
+}
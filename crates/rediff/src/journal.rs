@@ -0,0 +1,294 @@
+use crate::loading::{modified_time, stable_hash};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+/// Line of a journal file, written as JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum JournalEntry {
+  // Buffer when the journal started, the edits apply to it
+  Base {
+    path: PathBuf,
+    content: String,
+  },
+  Edit {
+    index: usize, // In chars, like the buffer
+    deleted: usize,
+    inserted: String,
+  },
+}
+
+/// Write-ahead journal of the unsaved edits of a file, to get them back after a crash
+///
+/// Lives in the journal directory under a name derived from the file path, it's removed
+/// once the edits are saved or thrown away. Entries are written by a thread of their own,
+/// in the order they were recorded
+#[derive(Debug)]
+pub struct Journal {
+  path: PathBuf,
+  lines: Sender<String>, // Entries waiting to be written, one JSON line each
+  writer: JoinHandle<()>,
+  text: String, // Buffer as of the last entry
+}
+
+impl Journal {
+  /// Journal file of `file_path` in `dir`
+  pub fn path_for(dir: &Path, file_path: &Path) -> PathBuf {
+    // The name has to stay the same across builds to find journals of previous runs
+    let hash = stable_hash(file_path.to_string_lossy().bytes());
+    dir.join(format!("{:016x}.journal", hash))
+  }
+
+  /// Starts a journal of `file_path` from the current buffer, replacing any previous one
+  pub fn start(dir: &Path, file_path: &Path, content: &str) -> io::Result<Self> {
+    fs::create_dir_all(dir)?;
+    let path = Self::path_for(dir, file_path);
+    let mut file = File::create(&path)?;
+    let (lines, receiver) = mpsc::channel::<String>();
+    let writer_path = path.clone();
+    let writer = thread::spawn(move || {
+      for line in receiver {
+        // One write per entry, a crash can only cut the last line short
+        if let Err(e) = file.write_all(line.as_bytes()) {
+          // Later edits don't apply without this one
+          eprintln!("Failed to write journal {:?}: {}", writer_path, e);
+          break;
+        }
+      }
+    });
+    let mut journal = Self {
+      path,
+      lines,
+      writer,
+      text: content.to_string(),
+    };
+    journal.append(&JournalEntry::Base {
+      path: file_path.to_path_buf(),
+      content: content.to_string(),
+    })?;
+    Ok(journal)
+  }
+
+  /// Appends the change from the last recorded buffer to `content`, if any
+  pub fn record(&mut self, content: &str) -> io::Result<()> {
+    if let Some(entry) = text_edit(&self.text, content) {
+      self.append(&entry)?;
+      self.text = content.to_string();
+    }
+    Ok(())
+  }
+
+  fn append(&mut self, entry: &JournalEntry) -> io::Result<()> {
+    let line = serde_json::to_string(entry).map_err(io::Error::other)?;
+    self
+      .lines
+      .send(format!("{}\n", line))
+      .map_err(|_| io::Error::other("the journal writer stopped"))
+  }
+
+  /// Waits until the entries recorded so far are written
+  pub fn close(self) {
+    drop(self.lines);
+    self.writer.join().ok();
+  }
+
+  /// Removes the journal file, the edits are saved or no longer wanted
+  pub fn discard(self) -> io::Result<()> {
+    let path = self.path.clone();
+    self.close();
+    remove_file(&path)
+  }
+
+  /// Buffer of `file_path` rebuilt from its journal, None when there's none
+  ///
+  /// Entries after the first unreadable one are ignored, the run crashed while writing it
+  pub fn recover(dir: &Path, file_path: &Path) -> io::Result<Option<String>> {
+    let file = match File::open(Self::path_for(dir, file_path)) {
+      Ok(file) => file,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(e),
+    };
+
+    let mut entries = BufReader::new(file)
+      .lines()
+      .map_while(|line| serde_json::from_str::<JournalEntry>(&line.ok()?).ok());
+    let mut text = match entries.next() {
+      // Another file whose path hashes the same
+      Some(JournalEntry::Base { path, content }) if path == file_path => content,
+      _ => return Ok(None),
+    };
+    for entry in entries {
+      let JournalEntry::Edit {
+        index,
+        deleted,
+        inserted,
+      } = entry
+      else {
+        break;
+      };
+      if index + deleted > text.chars().count() {
+        break;
+      }
+      let start = byte_offset(&text, index);
+      let end = byte_offset(&text, index + deleted);
+      text.replace_range(start..end, &inserted);
+    }
+    Ok(Some(text))
+  }
+
+  /// Whether `file_path` has a journal written after the file was last modified
+  pub fn is_newer(dir: &Path, file_path: &Path) -> bool {
+    let Some(journal_modified) = modified_time(&Self::path_for(dir, file_path)) else {
+      return false;
+    };
+    modified_time(file_path).is_none_or(|file_modified| journal_modified >= file_modified)
+  }
+
+  /// Removes the journal of `file_path` without reading it
+  pub fn remove(dir: &Path, file_path: &Path) -> io::Result<()> {
+    remove_file(&Self::path_for(dir, file_path))
+  }
+}
+
+fn remove_file(path: &Path) -> io::Result<()> {
+  match fs::remove_file(path) {
+    Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+    _ => Ok(()),
+  }
+}
+
+/// Replacement of the text between the common start and end of both texts
+fn text_edit(old: &str, new: &str) -> Option<JournalEntry> {
+  if old == new {
+    return None;
+  }
+  let (old_len, new_len) = (old.chars().count(), new.chars().count());
+  let prefix = old
+    .chars()
+    .zip(new.chars())
+    .take_while(|(a, b)| a == b)
+    .count();
+  let suffix = old
+    .chars()
+    .rev()
+    .zip(new.chars().rev())
+    .take_while(|(a, b)| a == b)
+    .count()
+    .min(old_len.min(new_len) - prefix);
+
+  Some(JournalEntry::Edit {
+    index: prefix,
+    deleted: old_len - prefix - suffix,
+    inserted: new
+      .chars()
+      .skip(prefix)
+      .take(new_len - prefix - suffix)
+      .collect(),
+  })
+}
+
+fn byte_offset(text: &str, char_index: usize) -> usize {
+  text
+    .char_indices()
+    .nth(char_index)
+    .map_or(text.len(), |(offset, _)| offset)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn journal_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    fs::remove_dir_all(&dir).ok();
+    dir
+  }
+
+  #[test]
+  fn test_text_edit() {
+    assert_eq!(text_edit("abc", "abc"), None);
+    assert_eq!(
+      text_edit("héllo", "héllo wörld"),
+      Some(JournalEntry::Edit {
+        index: 5,
+        deleted: 0,
+        inserted: " wörld".to_string(),
+      })
+    );
+    // Deleting one of two identical chars
+    assert_eq!(
+      text_edit("aab", "ab"),
+      Some(JournalEntry::Edit {
+        index: 1,
+        deleted: 1,
+        inserted: String::new(),
+      })
+    );
+  }
+
+  #[test]
+  fn test_recover_replays_edits() {
+    let dir = journal_dir("rediff_test_journal_recover");
+    let file_path = Path::new("/project/src/main.rs");
+
+    let mut journal = Journal::start(&dir, file_path, "fn main() {}\n").unwrap();
+    journal.record("fn main() { run(); }\n").unwrap();
+    journal.record("// 😀\nfn main() { run(); }\n").unwrap();
+    journal.record("// 😀\nfn main() { go(); }\n").unwrap();
+    journal.close();
+
+    assert_eq!(
+      Journal::recover(&dir, file_path).unwrap(),
+      Some("// 😀\nfn main() { go(); }\n".to_string())
+    );
+    assert_eq!(
+      Journal::recover(&dir, Path::new("/project/other.rs")).unwrap(),
+      None
+    );
+
+    Journal::remove(&dir, file_path).unwrap();
+    assert_eq!(Journal::recover(&dir, file_path).unwrap(), None);
+    fs::remove_dir_all(&dir).ok();
+  }
+
+  #[test]
+  fn test_recover_ignores_torn_entry() {
+    let dir = journal_dir("rediff_test_journal_torn");
+    let file_path = Path::new("/project/notes.txt");
+
+    let mut journal = Journal::start(&dir, file_path, "a\n").unwrap();
+    journal.record("a\nb\n").unwrap();
+    journal.close();
+    let path = Journal::path_for(&dir, file_path);
+    let mut content = fs::read_to_string(&path).unwrap();
+    content.push_str("{\"kind\":\"edit\",\"index\":");
+    fs::write(&path, content).unwrap();
+
+    assert_eq!(
+      Journal::recover(&dir, file_path).unwrap(),
+      Some("a\nb\n".to_string())
+    );
+    Journal::remove(&dir, file_path).unwrap();
+    fs::remove_dir_all(&dir).ok();
+  }
+
+  #[test]
+  fn test_is_newer() {
+    let dir = journal_dir("rediff_test_journal_newer");
+    let file_path = dir.join("file.txt");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(&file_path, "saved\n").unwrap();
+    assert!(!Journal::is_newer(&dir, &file_path));
+
+    let journal = Journal::start(&dir, &file_path, "unsaved\n").unwrap();
+    assert!(Journal::is_newer(&dir, &file_path));
+
+    journal.discard().unwrap();
+    assert!(!Journal::is_newer(&dir, &file_path));
+    fs::remove_dir_all(&dir).ok();
+  }
+}
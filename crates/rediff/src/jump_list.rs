@@ -0,0 +1,142 @@
+use editor::{Anchor, Bias, Editor};
+
+const MAX_JUMPS: usize = 100;
+
+/// Cursor positions left by large jumps, like going to a hunk or a bookmark, to go back and
+/// forth between them
+///
+/// Positions are anchors so that they follow edits, they belong to the editor they were
+/// created in like bookmarks
+#[derive(Debug, Clone, Default)]
+pub struct JumpList {
+  anchors: Vec<Anchor>,
+  current: usize, // Position going back returns to the one before, `anchors.len()` past the newest
+}
+
+impl JumpList {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.anchors.is_empty()
+  }
+
+  /// Records the position the cursor jumps away from, positions ahead of the current one are dropped
+  pub fn push(&mut self, editor: &mut Editor, index: usize) {
+    for anchor in self.anchors.drain(self.current..) {
+      editor.remove_anchor(anchor);
+    }
+    // Jumping again from the same line keeps a single entry
+    if let Some(last) = self.anchors.last()
+      && self.same_line(editor, *last, index)
+    {
+      editor.remove_anchor(self.anchors.pop().unwrap());
+    }
+    if self.anchors.len() == MAX_JUMPS {
+      editor.remove_anchor(self.anchors.remove(0));
+    }
+    self.anchors.push(editor.create_anchor(index, Bias::Left));
+    self.current = self.anchors.len();
+  }
+
+  /// Position before the current one, `index` is where the cursor is to come back to it
+  pub fn back(&mut self, editor: &mut Editor, index: usize) -> Option<usize> {
+    if self.current == 0 {
+      return None;
+    }
+    if self.current == self.anchors.len() {
+      self.push(editor, index);
+      self.current = self.anchors.len() - 1;
+    }
+    // The newest entry may be the line the cursor is on
+    while self.current > 0 {
+      self.current -= 1;
+      let anchor = self.anchors[self.current];
+      if !self.same_line(editor, anchor, index) {
+        return editor.anchor_position(anchor);
+      }
+    }
+    None
+  }
+
+  /// Position after the current one, once gone back
+  pub fn forward(&mut self, editor: &Editor, index: usize) -> Option<usize> {
+    while self.current + 1 < self.anchors.len() {
+      self.current += 1;
+      let anchor = self.anchors[self.current];
+      if !self.same_line(editor, anchor, index) {
+        return editor.anchor_position(anchor);
+      }
+    }
+    None
+  }
+
+  pub fn clear(&mut self, editor: &mut Editor) {
+    for anchor in self.anchors.drain(..) {
+      editor.remove_anchor(anchor);
+    }
+    self.current = 0;
+  }
+
+  fn same_line(&self, editor: &Editor, anchor: Anchor, index: usize) -> bool {
+    let line = |index| editor.buffer.char_to_line_col(index).0;
+    editor
+      .anchor_position(anchor)
+      .is_some_and(|position| line(position) == line(index))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn editor(text: &str) -> Editor {
+    let mut editor = Editor::new();
+    editor.paste(text);
+    editor
+  }
+
+  #[test]
+  fn test_back_and_forward() {
+    let mut editor = editor("a\nb\nc\nd\n");
+    let mut jumps = JumpList::new();
+    assert_eq!(jumps.back(&mut editor, 0), None);
+
+    // Jumps from "a" to "c", then from "c" to "d"
+    jumps.push(&mut editor, 0);
+    jumps.push(&mut editor, 4);
+    assert_eq!(jumps.back(&mut editor, 6), Some(4));
+    assert_eq!(jumps.back(&mut editor, 4), Some(0));
+    assert_eq!(jumps.back(&mut editor, 0), None);
+    assert_eq!(jumps.forward(&editor, 0), Some(4));
+    assert_eq!(jumps.forward(&editor, 4), Some(6));
+    assert_eq!(jumps.forward(&editor, 6), None);
+  }
+
+  #[test]
+  fn test_push_drops_forward_positions() {
+    let mut editor = editor("a\nb\nc\nd\n");
+    let mut jumps = JumpList::new();
+    jumps.push(&mut editor, 0);
+    jumps.push(&mut editor, 4);
+    assert_eq!(jumps.back(&mut editor, 6), Some(4));
+
+    // Jumping from "c" to "b" replaces "d" ahead
+    jumps.push(&mut editor, 4);
+    assert_eq!(jumps.forward(&editor, 2), None);
+    assert_eq!(jumps.back(&mut editor, 2), Some(4));
+    assert_eq!(jumps.back(&mut editor, 4), Some(0));
+  }
+
+  #[test]
+  fn test_positions_follow_edits() {
+    let mut editor = editor("a\nb\n");
+    let mut jumps = JumpList::new();
+    jumps.push(&mut editor, 2); // "|b"
+
+    editor.cursor.index = 0;
+    editor.paste("new\n");
+    assert_eq!(jumps.back(&mut editor, 0), Some(6));
+  }
+}
@@ -0,0 +1,621 @@
+use gpui::{Action, KeyBinding, Modifiers};
+use std::fmt;
+
+/// Key context set on the diff editor root, bindings only apply inside it
+pub const KEY_CONTEXT: &str = "DiffEditor";
+
+/// Key context of the command palette, inside the editor's one
+pub const COMMAND_PALETTE_KEY_CONTEXT: &str = "CommandPalette";
+
+pub mod actions {
+  gpui::actions!(
+    rediff,
+    [
+      SaveFile,
+      MoveLeft,
+      MoveRight,
+      MoveUp,
+      MoveDown,
+      MoveWordLeft,
+      MoveWordRight,
+      MoveToLineStart,
+      MoveToLineEnd,
+      MoveToBufferStart,
+      MoveToBufferEnd,
+      PageUp,
+      PageDown,
+      CenterCursor,
+      SelectLeft,
+      SelectRight,
+      SelectUp,
+      SelectDown,
+      SelectWordLeft,
+      SelectWordRight,
+      SelectToLineStart,
+      SelectToLineEnd,
+      SelectToBufferStart,
+      SelectToBufferEnd,
+      SelectPageUp,
+      SelectPageDown,
+      SelectAll,
+      ExpandSelection,
+      ShrinkSelection,
+      Backspace,
+      Delete,
+      DeleteWord,
+      DeleteWordForward,
+      DeleteToLineEnd,
+      DeleteLine,
+      DuplicateLinesUp,
+      DuplicateLinesDown,
+      MoveLinesUp,
+      MoveLinesDown,
+      ToggleComment,
+      ToggleBlockComment,
+      Undo,
+      Redo,
+      Newline,
+      Tab,
+      Indent,
+      Outdent,
+      Copy,
+      CopyDiffAsMarkdown,
+      CopyDiffAsRtf,
+      Cut,
+      Paste,
+      GoToNextHunk,
+      GoToPreviousHunk,
+      GoToMovedCounterpart,
+      JumpBack,
+      JumpForward,
+      CloseTab,
+      ActivateNextTab,
+      ActivatePreviousTab,
+      TakeOurs,
+      TakeTheirs,
+      TakeBoth,
+      RevertSelection,
+      Rebaseline,
+      ToggleBookmark,
+      GoToNextBookmark,
+      GoToPreviousBookmark,
+      ToggleHunkViewed,
+      ToggleHunkNeedsWork,
+      ToggleOriginalPreview,
+      ToggleStructuredDiff,
+      ToggleProseDiff,
+      ToggleRenderProfiling,
+      ZoomIn,
+      ZoomOut,
+      ResetZoom,
+      ToggleCommandPalette,
+      SelectNextCommand,
+      SelectPreviousCommand,
+      ConfirmCommand,
+      DismissCommandPalette,
+    ]
+  );
+}
+
+use actions::*;
+
+/// Platform a keymap is made for
+///
+/// `cmd` in keystrokes stands for the platform modifier, it's Ctrl outside macOS
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeymapPlatform {
+  Mac,
+  Linux,
+  Windows,
+}
+
+impl KeymapPlatform {
+  /// Platform the editor was built for
+  pub fn current() -> Self {
+    if cfg!(target_os = "macos") {
+      Self::Mac
+    } else if cfg!(target_os = "windows") {
+      Self::Windows
+    } else {
+      Self::Linux
+    }
+  }
+
+  /// Keystrokes as gpui reads them on this platform, "cmd-s" is "ctrl-s" outside macOS
+  pub fn keystrokes(&self, keystrokes: &str) -> String {
+    if *self == Self::Mac {
+      return keystrokes.to_string();
+    }
+    keystrokes
+      .split(' ')
+      .map(|keystroke| {
+        keystroke
+          .split('-')
+          .map(|part| if part == "cmd" { "ctrl" } else { part })
+          .collect::<Vec<_>>()
+          .join("-")
+      })
+      .collect::<Vec<_>>()
+      .join(" ")
+  }
+
+  /// Whether a key pressed with these modifiers is a shortcut rather than typed text
+  ///
+  /// Ctrl+Alt is AltGr on Windows, it types characters on many keyboard layouts
+  pub fn is_shortcut(&self, modifiers: &Modifiers) -> bool {
+    match self {
+      Self::Mac => modifiers.platform || modifiers.control,
+      Self::Linux | Self::Windows => modifiers.platform || (modifiers.control && !modifiers.alt),
+    }
+  }
+}
+
+/// Keystroke to action bindings for the diff editor
+///
+/// Embedders can start from the default keymap and rebind or unbind keys
+/// before passing it to `DiffEditor::register`. Keystrokes are kept as written, with `cmd`
+/// standing for the platform modifier
+#[derive(Clone)]
+pub struct Keymap {
+  platform: KeymapPlatform,
+  bindings: Vec<(String, KeyBinding)>,
+}
+
+impl Default for Keymap {
+  fn default() -> Self {
+    Self::for_platform(KeymapPlatform::current())
+  }
+}
+
+impl Keymap {
+  /// Default bindings of a platform, text navigation follows its conventions
+  pub fn for_platform(platform: KeymapPlatform) -> Self {
+    let mut keymap = Self::new_for(platform);
+
+    keymap.bind("cmd-s", SaveFile);
+
+    keymap.bind("left", MoveLeft);
+    keymap.bind("right", MoveRight);
+    keymap.bind("up", MoveUp);
+    keymap.bind("down", MoveDown);
+    keymap.bind("home", MoveToLineStart);
+    keymap.bind("end", MoveToLineEnd);
+    keymap.bind("shift-left", SelectLeft);
+    keymap.bind("shift-right", SelectRight);
+    keymap.bind("shift-up", SelectUp);
+    keymap.bind("shift-down", SelectDown);
+    keymap.bind("shift-home", SelectToLineStart);
+    keymap.bind("shift-end", SelectToLineEnd);
+    keymap.bind("pageup", PageUp);
+    keymap.bind("pagedown", PageDown);
+    keymap.bind("shift-pageup", SelectPageUp);
+    keymap.bind("shift-pagedown", SelectPageDown);
+    keymap.bind("ctrl-l", CenterCursor);
+    keymap.bind("cmd-a", SelectAll);
+    keymap.bind("backspace", Backspace);
+    keymap.bind("delete", Delete);
+
+    match platform {
+      KeymapPlatform::Mac => {
+        keymap.bind("alt-left", MoveWordLeft);
+        keymap.bind("alt-right", MoveWordRight);
+        keymap.bind("cmd-left", MoveToLineStart);
+        keymap.bind("cmd-right", MoveToLineEnd);
+        keymap.bind("cmd-up", MoveToBufferStart);
+        keymap.bind("cmd-down", MoveToBufferEnd);
+
+        keymap.bind("alt-shift-left", SelectWordLeft);
+        keymap.bind("alt-shift-right", SelectWordRight);
+        keymap.bind("ctrl-shift-cmd-right", ExpandSelection);
+        keymap.bind("ctrl-shift-cmd-left", ShrinkSelection);
+        keymap.bind("cmd-shift-left", SelectToLineStart);
+        keymap.bind("cmd-shift-right", SelectToLineEnd);
+        keymap.bind("cmd-shift-up", SelectToBufferStart);
+        keymap.bind("cmd-shift-down", SelectToBufferEnd);
+
+        keymap.bind("alt-backspace", DeleteWord);
+        keymap.bind("cmd-backspace", DeleteLine);
+        keymap.bind("alt-delete", DeleteWordForward);
+        keymap.bind("ctrl-k", DeleteToLineEnd);
+
+        keymap.bind("ctrl--", JumpBack);
+        keymap.bind("ctrl-shift--", JumpForward);
+      }
+      KeymapPlatform::Linux | KeymapPlatform::Windows => {
+        keymap.bind("ctrl-left", MoveWordLeft);
+        keymap.bind("ctrl-right", MoveWordRight);
+        keymap.bind("ctrl-home", MoveToBufferStart);
+        keymap.bind("ctrl-end", MoveToBufferEnd);
+
+        keymap.bind("ctrl-shift-left", SelectWordLeft);
+        keymap.bind("ctrl-shift-right", SelectWordRight);
+        keymap.bind("ctrl-shift-home", SelectToBufferStart);
+        keymap.bind("ctrl-shift-end", SelectToBufferEnd);
+        keymap.bind("alt-shift-right", ExpandSelection);
+        keymap.bind("alt-shift-left", ShrinkSelection);
+
+        keymap.bind("ctrl-backspace", DeleteWord);
+        keymap.bind("ctrl-shift-k", DeleteLine);
+        keymap.bind("ctrl-delete", DeleteWordForward);
+        // Ctrl+K starts chords there
+        keymap.bind("ctrl-shift-delete", DeleteToLineEnd);
+        keymap.bind("ctrl-y", Redo);
+
+        // Ctrl+- zooms out there
+        keymap.bind("alt-left", JumpBack);
+        keymap.bind("alt-right", JumpForward);
+      }
+    }
+    keymap.bind("alt-shift-up", DuplicateLinesUp);
+    keymap.bind("alt-shift-down", DuplicateLinesDown);
+    keymap.bind("alt-up", MoveLinesUp);
+    keymap.bind("alt-down", MoveLinesDown);
+    keymap.bind("enter", Newline);
+    keymap.bind("tab", Tab);
+    keymap.bind("shift-tab", Outdent);
+    keymap.bind("cmd-]", Indent);
+    keymap.bind("cmd-[", Outdent);
+    keymap.bind("cmd-/", ToggleComment);
+    keymap.bind("alt-shift-a", ToggleBlockComment);
+
+    keymap.bind("cmd-c", Copy);
+    keymap.bind("cmd-k m", CopyDiffAsMarkdown);
+    keymap.bind("cmd-k r", CopyDiffAsRtf);
+    keymap.bind("cmd-x", Cut);
+    keymap.bind("cmd-v", Paste);
+    keymap.bind("cmd-z", Undo);
+    keymap.bind("cmd-shift-z", Redo);
+
+    keymap.bind("f7", GoToNextHunk);
+    keymap.bind("shift-f7", GoToPreviousHunk);
+    keymap.bind("alt-f7", GoToMovedCounterpart);
+
+    keymap.bind("cmd-w", CloseTab);
+    keymap.bind("cmd-shift-]", ActivateNextTab);
+    keymap.bind("cmd-shift-[", ActivatePreviousTab);
+
+    keymap.bind("cmd-k o", TakeOurs);
+    keymap.bind("cmd-k t", TakeTheirs);
+    keymap.bind("cmd-k b", TakeBoth);
+
+    keymap.bind("cmd-alt-z", RevertSelection);
+    keymap.bind("cmd-k c", Rebaseline);
+
+    keymap.bind("cmd-f2", ToggleBookmark);
+    keymap.bind("f2", GoToNextBookmark);
+    keymap.bind("shift-f2", GoToPreviousBookmark);
+
+    keymap.bind("cmd-k v", ToggleHunkViewed);
+    keymap.bind("cmd-k n", ToggleHunkNeedsWork);
+    keymap.bind("cmd-k p", ToggleOriginalPreview);
+    keymap.bind("cmd-k j", ToggleStructuredDiff);
+    keymap.bind("cmd-k w", ToggleProseDiff);
+    keymap.bind("cmd-k f", ToggleRenderProfiling);
+
+    keymap.bind("cmd-=", ZoomIn);
+    keymap.bind("cmd-+", ZoomIn);
+    keymap.bind("cmd--", ZoomOut);
+    keymap.bind("cmd-0", ResetZoom);
+
+    keymap.bind("cmd-shift-p", ToggleCommandPalette);
+
+    keymap
+  }
+
+  /// Creates a keymap without any binding
+  pub fn new() -> Self {
+    Self::new_for(KeymapPlatform::current())
+  }
+
+  /// Creates a keymap without any binding for another platform than the current one
+  pub fn new_for(platform: KeymapPlatform) -> Self {
+    Self {
+      platform,
+      bindings: Vec::new(),
+    }
+  }
+
+  pub fn platform(&self) -> KeymapPlatform {
+    self.platform
+  }
+
+  /// Binds keystrokes to an action, replacing any existing binding for them
+  ///
+  /// Panics if the keystrokes can't be parsed, like `KeyBinding::new`
+  pub fn bind<A: Action>(&mut self, keystrokes: &str, action: A) {
+    self.unbind(keystrokes);
+    let platform_keystrokes = self.platform.keystrokes(keystrokes);
+    self.bindings.push((
+      keystrokes.to_string(),
+      KeyBinding::new(&platform_keystrokes, action, Some(KEY_CONTEXT)),
+    ));
+  }
+
+  pub fn unbind(&mut self, keystrokes: &str) {
+    self.bindings.retain(|(k, _)| k != keystrokes);
+  }
+
+  /// Returns the action bound to the given keystrokes
+  pub fn action_for(&self, keystrokes: &str) -> Option<&dyn Action> {
+    self
+      .bindings
+      .iter()
+      .find(|(k, _)| k == keystrokes)
+      .map(|(_, binding)| binding.action())
+  }
+
+  /// Returns the first keystrokes bound to the given action
+  pub fn keystrokes_for(&self, action: &dyn Action) -> Option<&str> {
+    self
+      .bindings
+      .iter()
+      .find(|(_, binding)| binding.action().partial_eq(action))
+      .map(|(k, _)| k.as_str())
+  }
+
+  pub fn key_bindings(&self) -> Vec<KeyBinding> {
+    self
+      .bindings
+      .iter()
+      .map(|(_, binding)| binding.clone())
+      .collect()
+  }
+
+  pub fn len(&self) -> usize {
+    self.bindings.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.bindings.is_empty()
+  }
+}
+
+/// Keys of the command palette while it's open, they win over the editor's bindings
+pub fn command_palette_bindings() -> Vec<KeyBinding> {
+  let context = Some(COMMAND_PALETTE_KEY_CONTEXT);
+  let toggle = KeymapPlatform::current().keystrokes("cmd-shift-p");
+  vec![
+    KeyBinding::new("down", SelectNextCommand, context),
+    KeyBinding::new("up", SelectPreviousCommand, context),
+    KeyBinding::new("enter", ConfirmCommand, context),
+    KeyBinding::new("escape", DismissCommandPalette, context),
+    KeyBinding::new(&toggle, DismissCommandPalette, context),
+    KeyBinding::new("backspace", Backspace, context),
+  ]
+}
+
+impl fmt::Debug for Keymap {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_map()
+      .entries(
+        self
+          .bindings
+          .iter()
+          .map(|(keystrokes, binding)| (keystrokes, binding.action().name())),
+      )
+      .finish()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_default_keymap() {
+    let keymap = Keymap::default();
+    assert!(!keymap.is_empty());
+    assert!(
+      keymap
+        .action_for("cmd-s")
+        .is_some_and(|a| a.partial_eq(&SaveFile))
+    );
+    assert!(
+      keymap
+        .action_for("f7")
+        .is_some_and(|a| a.partial_eq(&GoToNextHunk))
+    );
+  }
+
+  #[test]
+  fn test_default_zoom_bindings() {
+    let keymap = Keymap::default();
+    assert!(
+      keymap
+        .action_for("cmd-=")
+        .is_some_and(|a| a.partial_eq(&ZoomIn))
+    );
+    assert!(
+      keymap
+        .action_for("cmd--")
+        .is_some_and(|a| a.partial_eq(&ZoomOut))
+    );
+    assert!(
+      keymap
+        .action_for("cmd-0")
+        .is_some_and(|a| a.partial_eq(&ResetZoom))
+    );
+  }
+
+  #[test]
+  fn test_default_tab_bindings() {
+    let keymap = Keymap::default();
+    assert!(
+      keymap
+        .action_for("cmd-w")
+        .is_some_and(|a| a.partial_eq(&CloseTab))
+    );
+    assert!(
+      keymap
+        .action_for("cmd-shift-]")
+        .is_some_and(|a| a.partial_eq(&ActivateNextTab))
+    );
+    assert!(
+      keymap
+        .action_for("cmd-shift-[")
+        .is_some_and(|a| a.partial_eq(&ActivatePreviousTab))
+    );
+  }
+
+  #[test]
+  fn test_default_conflict_bindings() {
+    let keymap = Keymap::default();
+    assert!(
+      keymap
+        .action_for("cmd-k o")
+        .is_some_and(|a| a.partial_eq(&TakeOurs))
+    );
+    assert!(
+      keymap
+        .action_for("cmd-k b")
+        .is_some_and(|a| a.partial_eq(&TakeBoth))
+    );
+  }
+
+  #[test]
+  fn test_default_line_bindings() {
+    let keymap = Keymap::default();
+    assert!(
+      keymap
+        .action_for("alt-shift-down")
+        .is_some_and(|a| a.partial_eq(&DuplicateLinesDown))
+    );
+    assert!(
+      keymap
+        .action_for("alt-up")
+        .is_some_and(|a| a.partial_eq(&MoveLinesUp))
+    );
+    assert!(
+      keymap
+        .action_for("cmd-shift-z")
+        .is_some_and(|a| a.partial_eq(&Redo))
+    );
+  }
+
+  #[test]
+  fn test_platform_keystrokes() {
+    assert_eq!(KeymapPlatform::Mac.keystrokes("cmd-k m"), "cmd-k m");
+    assert_eq!(KeymapPlatform::Linux.keystrokes("cmd-k m"), "ctrl-k m");
+    assert_eq!(KeymapPlatform::Windows.keystrokes("cmd--"), "ctrl--");
+    assert_eq!(
+      KeymapPlatform::Linux.keystrokes("cmd-shift-left"),
+      "ctrl-shift-left"
+    );
+  }
+
+  #[test]
+  fn test_platform_navigation_bindings() {
+    let mac = Keymap::for_platform(KeymapPlatform::Mac);
+    let linux = Keymap::for_platform(KeymapPlatform::Linux);
+    assert!(
+      mac
+        .action_for("alt-left")
+        .is_some_and(|a| a.partial_eq(&MoveWordLeft))
+    );
+    assert!(
+      linux
+        .action_for("ctrl-left")
+        .is_some_and(|a| a.partial_eq(&MoveWordLeft))
+    );
+    for keymap in [&mac, &linux] {
+      assert!(
+        keymap
+          .action_for("home")
+          .is_some_and(|a| a.partial_eq(&MoveToLineStart))
+      );
+    }
+    assert!(
+      linux
+        .action_for("alt-left")
+        .is_some_and(|a| a.partial_eq(&JumpBack))
+    );
+    // Ctrl+K starts chords outside macOS
+    assert!(
+      mac
+        .action_for("ctrl-k")
+        .is_some_and(|a| a.partial_eq(&DeleteToLineEnd))
+    );
+    assert!(linux.action_for("ctrl-k").is_none());
+  }
+
+  #[test]
+  fn test_is_shortcut() {
+    let ctrl_alt = Modifiers {
+      control: true,
+      alt: true,
+      ..Default::default()
+    };
+    assert!(KeymapPlatform::Mac.is_shortcut(&ctrl_alt));
+    // AltGr
+    assert!(!KeymapPlatform::Windows.is_shortcut(&ctrl_alt));
+    let ctrl = Modifiers {
+      control: true,
+      ..Default::default()
+    };
+    let shift = Modifiers {
+      shift: true,
+      ..Default::default()
+    };
+    assert!(KeymapPlatform::Linux.is_shortcut(&ctrl));
+    assert!(!KeymapPlatform::Linux.is_shortcut(&shift));
+  }
+
+  #[test]
+  fn test_keystrokes_for() {
+    let keymap = Keymap::default();
+    assert_eq!(keymap.keystrokes_for(&ZoomIn), Some("cmd-="));
+    assert_eq!(
+      keymap.keystrokes_for(&ToggleCommandPalette),
+      Some("cmd-shift-p")
+    );
+    assert_eq!(keymap.keystrokes_for(&SelectNextCommand), None);
+  }
+
+  #[test]
+  fn test_new_keymap_is_empty() {
+    let keymap = Keymap::new();
+    assert!(keymap.is_empty());
+    assert!(keymap.key_bindings().is_empty());
+  }
+
+  #[test]
+  fn test_bind_replaces_existing_binding() {
+    let mut keymap = Keymap::default();
+    let len = keymap.len();
+
+    keymap.bind("cmd-s", Copy);
+
+    assert_eq!(keymap.len(), len);
+    assert!(
+      keymap
+        .action_for("cmd-s")
+        .is_some_and(|a| a.partial_eq(&Copy))
+    );
+  }
+
+  #[test]
+  fn test_rebind_to_new_keystrokes() {
+    let mut keymap = Keymap::default();
+    keymap.unbind("cmd-s");
+    keymap.bind("ctrl-s", SaveFile);
+
+    assert!(keymap.action_for("cmd-s").is_none());
+    assert!(
+      keymap
+        .action_for("ctrl-s")
+        .is_some_and(|a| a.partial_eq(&SaveFile))
+    );
+  }
+
+  #[test]
+  fn test_key_bindings_use_editor_context() {
+    let keymap = Keymap::default();
+    assert_eq!(keymap.key_bindings().len(), keymap.len());
+    assert!(
+      keymap
+        .key_bindings()
+        .iter()
+        .all(|binding| binding.predicate().is_some())
+    );
+  }
+}
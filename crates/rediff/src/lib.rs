@@ -1,12 +1,102 @@
+mod annotations;
+mod autosave;
+mod binary;
+mod bookmarks;
+mod builder;
+mod command_palette;
+mod comment_threads;
 mod config;
+mod cursor_blink;
+mod diagnostics;
+mod dialog;
 mod diff_editor;
+mod display_rows;
+mod edit_log;
+mod file_state;
+mod file_tree;
+mod folds;
+mod gutter;
+mod image_diff;
+mod inlay_hints;
+mod input_hooks;
+mod journal;
+mod jump_list;
+mod keymap;
 mod line_cache;
 mod line_element;
+mod loading;
+mod lsp;
+mod minimap;
+mod original_previews;
+mod overrides;
+mod render_profile;
+mod review;
+mod revisions;
+mod scroll_sync;
+mod scrollbar;
+mod smooth_scroll;
+mod sticky_header;
+mod tab_map;
+#[cfg(test)]
+mod test_support;
+mod theme;
+mod viewport;
+mod watch;
 
+pub use annotations::{AnnotationTooltip, LineAnnotation, LineAnnotations};
+pub use autosave::AutosaveTimer;
+pub use bookmarks::Bookmarks;
+pub use builder::{BuildError, DiffEditorBuilder};
+pub use command_palette::{Command, CommandPalette, command_label, fuzzy_score};
+pub use comment_threads::{
+  Comment, CommentThread, CommentThreads, ThreadId, ThreadRow, ThreadRowKind,
+};
 pub use config::{
-  EditorConfig, EditorTheme, EditorThemeCursorColor, EditorThemeGit, EditorThemeGitColor,
-  EditorThemePairColor,
+  Autosave, BinaryView, CommentTokens, CursorStyle, EditorConfig, EditorTheme,
+  EditorThemeCursorColor, EditorThemeDiagnostics, EditorThemeGit, EditorThemeGitColor,
+  EditorThemePairColor, EditorThemeScrollbarColor, FontConfig, LanguageServerConfig, LineNumbers,
+  RenderWhitespace, ScrollEasing, ThemeMode,
+};
+pub use cursor_blink::CursorBlink;
+pub use diagnostics::{Diagnostic, DiagnosticSeverity, Diagnostics};
+pub use dialog::Dialog;
+pub use diff_editor::{DiffEditor, DiffEditorEvent};
+pub use display_rows::{CONTEXT_LINES, CollapsedRegion, DisplayRow, UnchangedRegions, display_map};
+pub use edit_log::export_edit_log;
+pub use editor::{
+  DisplayMap, Edit, EditCause, EditError, EditEvent, EditLog, RowContent, RowPosition,
+};
+pub use file_state::{FileState, FileStates};
+pub use file_tree::{FileStatus, FileTree, FileTreeEntry, FileTreeEvent, FileTreeModel};
+pub use folds::{FoldRange, Folds, hide_folded_rows, indent_fold_ranges};
+pub use gutter::{GutterColumn, GutterColumnId, GutterColumns, GutterRow};
+pub use image_diff::{ImageKind, ImageSide, ImageSides, PixelCount, PixelDiff};
+pub use inlay_hints::{InlayHint, InlayHints, original_value_hints};
+pub use input_hooks::{EventResult, InputEvent, InputHookId, InputHooks};
+pub use journal::Journal;
+pub use jump_list::JumpList;
+pub use keymap::{
+  COMMAND_PALETTE_KEY_CONTEXT, KEY_CONTEXT, Keymap, KeymapPlatform, actions,
+  command_palette_bindings,
 };
-pub use diff_editor::DiffEditor;
-pub use line_cache::LineCache;
+pub use line_cache::{LineCache, LineCacheStats};
 pub use line_element::{EditorState, LineConfig, LineElement};
+pub use loading::LoadError;
+pub use minimap::{DiffMinimap, MinimapColors, MinimapLine, MinimapMetrics, MinimapState};
+pub use original_previews::{OriginalPreviews, OriginalRow};
+pub use overrides::{ConfigError, ConfigOverrides};
+pub use rediff_core::{
+  ConflictMarkers, DiffOptions, DiffStats, ExportFormat, Granularity, Resolution, WhitespaceMode,
+};
+pub use render_profile::{FrameStats, RenderProfiler};
+pub use review::{Review, ReviewState, hunk_key};
+pub use revisions::{Baseline, Revision, RevisionId, Revisions};
+pub use scroll_sync::{ScrollPosition, ScrollSync};
+pub use scrollbar::{
+  DiffScrollbar, ScrollbarColors, ScrollbarMarker, ScrollbarMetrics, ScrollbarState,
+};
+pub use sticky_header::{StickyHeader, sticky_header, top_diff_row};
+pub use tab_map::TabMap;
+pub use theme::{ThemeError, ThemeFormat, ThemeRegistry};
+pub use viewport::Viewport;
+pub use watch::FileWatcher;
@@ -1,14 +1,50 @@
-use gpui::ShapedLine;
+use crate::minimap::MinimapLine;
+use editor::LineEdit;
+use gpui::{Font, ShapedLine};
 use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// Shaped lines kept by default, a few screens of a large file
+pub const DEFAULT_CAPACITY: usize = 5_000;
+
+/// Cache lookups since the cache was created or the stats reset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineCacheStats {
+  pub hits: usize,
+  pub misses: usize,
+  pub evictions: usize,
+}
 
 /// Granular cache for shaped lines
 /// Allows invalidating only modified lines instead of recalculating everything
-#[derive(Default)]
+///
+/// Past `capacity` lines the least recently used ones are evicted, lines near the viewport last.
+/// Minimap blocks are kept up to the same capacity
 pub struct LineCache {
   /// Map: line_idx → ShapedLine
   pub shaped_lines: HashMap<usize, ShapedLine>,
   pub buffer_version: usize,
   pub dirty_lines: HashSet<usize>,
+  /// Map: line_idx → blocks drawn for the line in the minimap
+  pub minimap_lines: HashMap<usize, MinimapLine>,
+  /// Font and size bits the cached lines were shaped with
+  pub text_style: Option<(Font, u32)>,
+  /// Most shaped lines kept, 0 for no limit
+  pub capacity: usize,
+  /// Buffer lines on screen, evicted last
+  pub viewport: Range<usize>,
+  /// Map: line_idx → tick of the last time the shaped line was used
+  last_used: HashMap<usize, u64>,
+  /// Map: line_idx → tick of the last time the minimap blocks were used
+  minimap_last_used: HashMap<usize, u64>,
+  tick: u64,
+  stats: LineCacheStats,
+}
+
+impl Default for LineCache {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 impl LineCache {
@@ -17,47 +53,182 @@ impl LineCache {
       shaped_lines: HashMap::new(),
       buffer_version: 0,
       dirty_lines: HashSet::new(),
+      minimap_lines: HashMap::new(),
+      text_style: None,
+      capacity: DEFAULT_CAPACITY,
+      viewport: 0..0,
+      last_used: HashMap::new(),
+      minimap_last_used: HashMap::new(),
+      tick: 0,
+      stats: LineCacheStats::default(),
+    }
+  }
+
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self {
+      capacity,
+      ..Self::new()
     }
   }
 
+  /// Changes the number of shaped lines kept, evicting lines right away when it shrinks
+  pub fn set_capacity(&mut self, capacity: usize) {
+    self.capacity = capacity;
+    self.evict();
+  }
+
+  pub fn set_viewport(&mut self, viewport: Range<usize>) {
+    self.viewport = viewport;
+  }
+
   /// Retrieves a line from cache, or None if not present
-  pub fn get(&self, line_idx: usize) -> Option<&ShapedLine> {
-    if self.dirty_lines.contains(&line_idx) {
-      return None;
+  pub fn get(&mut self, line_idx: usize) -> Option<&ShapedLine> {
+    if !self.dirty_lines.contains(&line_idx)
+      && let Some(shaped) = self.shaped_lines.get(&line_idx)
+    {
+      self.tick += 1;
+      self.last_used.insert(line_idx, self.tick);
+      self.stats.hits += 1;
+      return Some(shaped);
     }
-    self.shaped_lines.get(&line_idx)
+    self.stats.misses += 1;
+    None
   }
 
   /// Inserts a line into the cache
   pub fn insert(&mut self, line_idx: usize, shaped: ShapedLine) {
     self.shaped_lines.insert(line_idx, shaped);
     self.dirty_lines.remove(&line_idx);
+    self.tick += 1;
+    self.last_used.insert(line_idx, self.tick);
+    self.evict();
+  }
+
+  pub fn stats(&self) -> LineCacheStats {
+    self.stats
+  }
+
+  pub fn reset_stats(&mut self) {
+    self.stats = LineCacheStats::default();
+  }
+
+  /// Drops shaped lines and minimap blocks once over capacity
+  fn evict(&mut self) {
+    // Lines a screen above or below the viewport are likely to be scrolled back to
+    let margin = self.viewport.len();
+    let near = self.viewport.start.saturating_sub(margin)..self.viewport.end + margin;
+
+    for line_idx in evicted_lines(&self.shaped_lines, &self.last_used, &near, self.capacity) {
+      self.shaped_lines.remove(&line_idx);
+      self.last_used.remove(&line_idx);
+      self.stats.evictions += 1;
+    }
+    let minimap_last_used = &self.minimap_last_used;
+    for line_idx in evicted_lines(&self.minimap_lines, minimap_last_used, &near, self.capacity) {
+      self.minimap_lines.remove(&line_idx);
+      self.minimap_last_used.remove(&line_idx);
+    }
+  }
+
+  /// Retrieves the minimap blocks of a line, or None if not present
+  pub fn get_minimap(&mut self, line_idx: usize) -> Option<&MinimapLine> {
+    let line = self.minimap_lines.get(&line_idx)?;
+    self.tick += 1;
+    self.minimap_last_used.insert(line_idx, self.tick);
+    Some(line)
+  }
+
+  pub fn insert_minimap(&mut self, line_idx: usize, line: MinimapLine) {
+    self.minimap_lines.insert(line_idx, line);
+    self.tick += 1;
+    self.minimap_last_used.insert(line_idx, self.tick);
+    self.evict();
   }
 
   /// Marks a line as dirty (needs reshaping)
   pub fn mark_dirty(&mut self, line_idx: usize) {
     self.dirty_lines.insert(line_idx);
+    self.minimap_lines.remove(&line_idx);
+    self.minimap_last_used.remove(&line_idx);
   }
 
   /// Marks a range of lines as dirty
   pub fn mark_dirty_range(&mut self, start: usize, end: usize) {
     for line_idx in start..=end {
-      self.dirty_lines.insert(line_idx);
+      self.mark_dirty(line_idx);
     }
   }
 
   /// Clears the entire cache (if buffer version changes drastically)
   pub fn clear(&mut self) {
+    self.shaped_lines.clear();
+    self.last_used.clear();
+    self.dirty_lines.clear();
+    self.minimap_lines.clear();
+    self.minimap_last_used.clear();
+  }
+
+  /// Drops the lines touched by an edit and shifts the cached lines after it
+  pub fn apply_edit(&mut self, edit: &LineEdit) {
+    let shift = |line_idx: usize| -> Option<usize> {
+      if line_idx < edit.line_start {
+        Some(line_idx)
+      } else if line_idx <= edit.line_end {
+        None
+      } else {
+        line_idx.checked_add_signed(edit.line_delta)
+      }
+    };
+
+    self.shaped_lines = std::mem::take(&mut self.shaped_lines)
+      .into_iter()
+      .filter_map(|(line_idx, shaped)| shift(line_idx).map(|line_idx| (line_idx, shaped)))
+      .collect();
+    self.dirty_lines = std::mem::take(&mut self.dirty_lines)
+      .into_iter()
+      .filter_map(shift)
+      .collect();
+    self.last_used = std::mem::take(&mut self.last_used)
+      .into_iter()
+      .filter_map(|(line_idx, tick)| shift(line_idx).map(|line_idx| (line_idx, tick)))
+      .collect();
+    self.minimap_lines = std::mem::take(&mut self.minimap_lines)
+      .into_iter()
+      .filter_map(|(line_idx, line)| shift(line_idx).map(|line_idx| (line_idx, line)))
+      .collect();
+    self.minimap_last_used = std::mem::take(&mut self.minimap_last_used)
+      .into_iter()
+      .filter_map(|(line_idx, tick)| shift(line_idx).map(|line_idx| (line_idx, tick)))
+      .collect();
+  }
+
+  /// Applies buffer edits and records the resulting buffer version,
+  /// so that `check_buffer_version` only clears on untracked changes
+  pub fn apply_edits(&mut self, edits: &[LineEdit], current_version: usize) {
+    for edit in edits {
+      self.apply_edit(edit);
+    }
+    self.buffer_version = current_version;
+  }
+
+  /// Checks if the font changed and drops the shaped lines if so, minimap blocks don't depend on it
+  pub fn check_font(&mut self, font: &Font, font_size: f32) -> bool {
+    let text_style = (font.clone(), font_size.to_bits());
+    if self.text_style.as_ref() == Some(&text_style) {
+      return false;
+    }
+
     self.shaped_lines.clear();
     self.dirty_lines.clear();
+    self.last_used.clear();
+    self.text_style = Some(text_style);
+    true
   }
 
   /// Checks if buffer has changed and clears if necessary
   pub fn check_buffer_version(&mut self, current_version: usize) -> bool {
     if self.buffer_version != current_version {
-      // Buffer has changed, clear everything
-      // Note: in a more sophisticated version, we could
-      // try to preserve some lines
+      // Buffer has changed without going through apply_edits, clear everything
       self.clear();
       self.buffer_version = current_version;
       true
@@ -77,9 +248,39 @@ impl LineCache {
   }
 }
 
+/// Lines to drop from `lines` once over `capacity`, down to three quarters of it so that the
+/// next inserts don't evict again. Lines away from `near` go first, then the least recently used
+fn evicted_lines<T>(
+  lines: &HashMap<usize, T>,
+  last_used: &HashMap<usize, u64>,
+  near: &Range<usize>,
+  capacity: usize,
+) -> Vec<usize> {
+  if capacity == 0 || lines.len() <= capacity {
+    return Vec::new();
+  }
+
+  let mut order: Vec<(bool, u64, usize)> = lines
+    .keys()
+    .map(|&line_idx| {
+      let last_used = last_used.get(&line_idx).copied().unwrap_or(0);
+      (near.contains(&line_idx), last_used, line_idx)
+    })
+    .collect();
+  order.sort_unstable();
+
+  let target = capacity - capacity / 4;
+  order
+    .into_iter()
+    .take(lines.len() - target)
+    .map(|(_, _, line_idx)| line_idx)
+    .collect()
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::config::FontConfig;
 
   #[test]
   fn test_line_cache_new() {
@@ -149,6 +350,28 @@ mod tests {
     assert!(cache.get(3).is_none());
   }
 
+  #[test]
+  fn test_font_change_clears_shaped_lines() {
+    let mut cache = LineCache::new();
+    let font = FontConfig::default().font();
+    assert!(cache.check_font(&font, 16.0));
+    cache.insert(1, ShapedLine::default());
+    cache.insert_minimap(1, MinimapLine::new("a", 2));
+
+    assert!(!cache.check_font(&font, 16.0));
+    assert_eq!(cache.len(), 1);
+    assert!(cache.check_font(&font, 18.0));
+    assert!(cache.is_empty());
+    assert!(cache.get_minimap(1).is_some());
+
+    let bold = FontConfig {
+      weight: 700.0,
+      ..FontConfig::default()
+    }
+    .font();
+    assert!(cache.check_font(&bold, 18.0));
+  }
+
   #[test]
   fn test_clear() {
     let mut cache = LineCache::new();
@@ -166,4 +389,181 @@ mod tests {
     let cache = LineCache::new();
     assert_eq!(cache.len(), 0);
   }
+
+  #[test]
+  fn test_apply_edit_drops_touched_lines() {
+    let mut cache = LineCache::new();
+    for i in 0..5 {
+      cache.insert(i, ShapedLine::default());
+    }
+
+    cache.apply_edit(&LineEdit {
+      line_start: 2,
+      line_end: 2,
+      line_delta: 0,
+    });
+
+    assert_eq!(cache.len(), 4);
+    assert!(cache.get(2).is_none());
+    assert!(cache.get(1).is_some());
+    assert!(cache.get(3).is_some());
+  }
+
+  #[test]
+  fn test_apply_edit_shifts_following_lines() {
+    let mut cache = LineCache::new();
+    for i in 0..5 {
+      cache.insert(i, ShapedLine::default());
+    }
+    cache.mark_dirty(4);
+
+    // Newline typed on line 1
+    cache.apply_edit(&LineEdit {
+      line_start: 1,
+      line_end: 1,
+      line_delta: 1,
+    });
+
+    let mut lines: Vec<_> = cache.shaped_lines.keys().copied().collect();
+    lines.sort();
+    assert_eq!(lines, vec![0, 3, 4, 5]);
+    assert!(cache.dirty_lines.contains(&5));
+  }
+
+  #[test]
+  fn test_apply_edit_removed_lines() {
+    let mut cache = LineCache::new();
+    for i in 0..6 {
+      cache.insert(i, ShapedLine::default());
+    }
+
+    // Lines 1 to 3 joined into one
+    cache.apply_edit(&LineEdit {
+      line_start: 1,
+      line_end: 3,
+      line_delta: -2,
+    });
+
+    let mut lines: Vec<_> = cache.shaped_lines.keys().copied().collect();
+    lines.sort();
+    assert_eq!(lines, vec![0, 2, 3]);
+  }
+
+  #[test]
+  fn test_eviction_keeps_recent_lines() {
+    let mut cache = LineCache::with_capacity(8);
+    for i in 0..8 {
+      cache.insert(i, ShapedLine::default());
+    }
+    cache.get(0);
+
+    cache.insert(8, ShapedLine::default());
+    assert_eq!(cache.len(), 6);
+    assert_eq!(cache.stats().evictions, 3);
+    assert!(cache.get(0).is_some());
+    assert!(cache.get(8).is_some());
+    assert!(cache.get(1).is_none());
+    assert!(cache.get(3).is_none());
+    assert!(cache.get(4).is_some());
+  }
+
+  #[test]
+  fn test_eviction_keeps_viewport_lines() {
+    let mut cache = LineCache::with_capacity(8);
+    cache.set_viewport(2..4);
+    for i in 0..9 {
+      cache.insert(i, ShapedLine::default());
+    }
+
+    // Lines 0 to 5 are within a screen of the viewport, the oldest of the others go first
+    let mut lines: Vec<_> = cache.shaped_lines.keys().copied().collect();
+    lines.sort();
+    assert_eq!(lines, vec![0, 1, 2, 3, 4, 5]);
+
+    cache.set_capacity(0);
+    for i in 10..100 {
+      cache.insert(i, ShapedLine::default());
+    }
+    assert_eq!(cache.len(), 96);
+  }
+
+  #[test]
+  fn test_eviction_of_minimap_lines() {
+    let mut cache = LineCache::with_capacity(8);
+    cache.set_viewport(20..22);
+    for i in 0..8 {
+      cache.insert_minimap(i, MinimapLine::new("a", 2));
+    }
+    assert!(cache.get_minimap(0).is_some());
+
+    // Line 20 is in the viewport, the oldest of the others go first
+    cache.insert_minimap(20, MinimapLine::new("a", 2));
+    let mut lines: Vec<_> = cache.minimap_lines.keys().copied().collect();
+    lines.sort();
+    assert_eq!(lines, vec![0, 4, 5, 6, 7, 20]);
+    // Shaped lines are counted apart
+    assert_eq!(cache.stats().evictions, 0);
+  }
+
+  #[test]
+  fn test_stats() {
+    let mut cache = LineCache::new();
+    cache.insert(0, ShapedLine::default());
+    cache.mark_dirty(1);
+
+    assert!(cache.get(0).is_some());
+    assert!(cache.get(1).is_none());
+    assert!(cache.get(2).is_none());
+    assert_eq!(
+      cache.stats(),
+      LineCacheStats {
+        hits: 1,
+        misses: 2,
+        evictions: 0,
+      }
+    );
+
+    cache.reset_stats();
+    assert_eq!(cache.stats(), LineCacheStats::default());
+  }
+
+  #[test]
+  fn test_minimap_lines_follow_edits() {
+    let mut cache = LineCache::new();
+    for i in 0..4 {
+      cache.insert_minimap(i, MinimapLine::new("let a = 1;", 2));
+    }
+
+    cache.mark_dirty(0);
+    cache.apply_edit(&LineEdit {
+      line_start: 1,
+      line_end: 1,
+      line_delta: 1,
+    });
+
+    let mut lines: Vec<_> = cache.minimap_lines.keys().copied().collect();
+    lines.sort();
+    assert_eq!(lines, vec![3, 4]);
+  }
+
+  #[test]
+  fn test_apply_edits_keeps_cache_on_version_check() {
+    let mut cache = LineCache::new();
+    cache.check_buffer_version(10);
+    cache.insert(0, ShapedLine::default());
+    cache.insert(1, ShapedLine::default());
+
+    cache.apply_edits(
+      &[LineEdit {
+        line_start: 1,
+        line_end: 1,
+        line_delta: 0,
+      }],
+      11,
+    );
+
+    assert!(!cache.check_buffer_version(11));
+    assert!(cache.get(0).is_some());
+    assert!(cache.get(1).is_none());
+  }
 }
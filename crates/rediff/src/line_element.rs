@@ -1,17 +1,35 @@
+use crate::config::{CursorStyle, RenderWhitespace};
 use crate::line_cache::LineCache;
-use editor::CharRange;
+use crate::tab_map::TabMap;
 use gpui::{
-  App, Bounds, Element, ElementId, Font, GlobalElementId, Hsla, InspectorElementId, IntoElement,
-  LayoutId, Pixels, ShapedLine, Style, TextRun, Window, fill, point, px, relative, rgba, size,
+  App, Bounds, ContentMask, Element, ElementId, Font, GlobalElementId, Hsla, InspectorElementId,
+  IntoElement, LayoutId, Pixels, ShapedLine, Style, TextRun, UnderlineStyle, Window, fill, point,
+  px, relative, size,
 };
+use rediff_core::CharRange;
 use std::ops::Range;
 use std::sync::{Arc, Mutex};
 use text::TextBuffer;
 
+const SELECTION_CORNER_RADIUS: f32 = 3.0;
+const SPACE_MARKER: &str = "·";
+const TAB_MARKER: &str = "→";
+
 pub struct LinePrepaintState {
   pub shaped_line: ShapedLine,
+  pub tab_map: TabMap,
   pub cursor_bounds: Option<CursorBounds>,
   pub selection_bounds: Vec<SelectionBounds>,
+  pub highlight_bounds: Vec<SelectionBounds>,
+  pub diagnostic_bounds: Vec<SelectionBounds>, // Squiggles under the text
+  pub whitespace_markers: Vec<WhitespaceMarker>,
+}
+
+/// Glyph drawn over a rendered space or tab
+#[derive(Clone)]
+pub struct WhitespaceMarker {
+  pub x: Pixels,
+  pub shaped: ShapedLine,
 }
 
 #[derive(Debug, Clone)]
@@ -29,16 +47,25 @@ pub struct SelectionBounds {
 
 #[derive(Clone)]
 pub struct LineConfig {
+  pub font: Font,
   pub font_size: f32,
   pub line_height: f32,
   pub text_color: Hsla,
   pub cursor_color: Hsla,
+  pub cursor_style: CursorStyle,
+  pub selection_color: Hsla,
+  pub tab_size: usize,
 }
 
 impl LineConfig {
   pub fn line_height_px(&self) -> Pixels {
     px(self.line_height)
   }
+
+  /// Width of a cell past the end of the line, where there's no character to measure
+  fn empty_cell_width(&self) -> Pixels {
+    px(self.font_size * 0.6)
+  }
 }
 
 #[derive(Clone, Debug)]
@@ -64,6 +91,16 @@ pub struct LineElement {
   config: LineConfig,
   diff_background: Option<DiffBackground>,
   text_override: Option<String>,
+  scroll_x: Pixels,
+  cursor_visible: bool,
+  highlights: Arc<Vec<Range<usize>>>, // Buffer char ranges painted behind the selection
+  highlight_color: Hsla,
+  diagnostics: Arc<Vec<(Range<usize>, Hsla)>>, // Buffer char ranges underlined with a squiggle
+  render_whitespace: RenderWhitespace,
+  whitespace_color: Hsla,
+  inlays: Vec<(usize, String)>, // (column, text) of the virtual text shown in the line
+  inlay_color: Hsla,
+  selected: bool, // Selected as a whole, for lines that aren't in the buffer like removed lines
 }
 
 impl LineElement {
@@ -82,6 +119,16 @@ impl LineElement {
       config,
       diff_background: None,
       text_override: None,
+      scroll_x: px(0.0),
+      cursor_visible: true,
+      highlights: Arc::new(Vec::new()),
+      highlight_color: Hsla::transparent_black(),
+      diagnostics: Arc::new(Vec::new()),
+      render_whitespace: RenderWhitespace::None,
+      whitespace_color: Hsla::transparent_black(),
+      inlays: Vec::new(),
+      inlay_color: Hsla::transparent_black(),
+      selected: false,
     }
   }
 
@@ -95,33 +142,71 @@ impl LineElement {
     self
   }
 
-  /// Retrieves or shapes a line from the buffer
-  fn get_or_shape_line(&self, window: &mut Window) -> ShapedLine {
+  /// Shifts the line content left by the horizontal scroll offset
+  pub fn with_selected(mut self, selected: bool) -> Self {
+    self.selected = selected;
+    self
+  }
+
+  pub fn with_scroll_x(mut self, scroll_x: Pixels) -> Self {
+    self.scroll_x = scroll_x;
+    self
+  }
+
+  pub fn with_cursor_visible(mut self, cursor_visible: bool) -> Self {
+    self.cursor_visible = cursor_visible;
+    self
+  }
+
+  /// Highlights ranges of the buffer, like the occurrences of the word under the cursor
+  pub fn with_highlights(mut self, highlights: Arc<Vec<Range<usize>>>, color: Hsla) -> Self {
+    self.highlights = highlights;
+    self.highlight_color = color;
+    self
+  }
+
+  /// Underlines ranges of the buffer with a squiggle of their color
+  pub fn with_diagnostics(mut self, diagnostics: Arc<Vec<(Range<usize>, Hsla)>>) -> Self {
+    self.diagnostics = diagnostics;
+    self
+  }
+
+  /// Draws markers over the spaces and tabs picked by `mode`
+  pub fn with_render_whitespace(mut self, mode: RenderWhitespace, color: Hsla) -> Self {
+    self.render_whitespace = mode;
+    self.whitespace_color = color;
+    self
+  }
+
+  /// Shows virtual text before the given columns, the text isn't part of the buffer
+  pub fn with_inlays(mut self, inlays: Vec<(usize, String)>, color: Hsla) -> Self {
+    self.inlays = inlays;
+    self.inlay_color = color;
+    self
+  }
+
+  /// Text of the line without its trailing newline
+  fn line_text(&self) -> String {
+    match self.text_override {
+      Some(ref text_override) => text_override.trim_end_matches('\n').to_string(),
+      None => self
+        .buffer
+        .line(self.line_idx)
+        .unwrap_or_default()
+        .trim_end_matches('\n')
+        .to_string(),
+    }
+  }
+
+  /// Retrieves or shapes a line from the buffer, with tabs expanded
+  fn get_or_shape_line(&self, text: &str, tab_map: &TabMap, window: &mut Window) -> ShapedLine {
     // If we have a text override, skip cache and shape directly
-    if let Some(ref text_override) = self.text_override {
-      let text = text_override.trim_end_matches('\n').to_string();
-
-      let font_size = px(self.config.font_size);
-      let monospace_font = Font {
-        family: "monospace".into(),
-        features: Default::default(),
-        fallbacks: Default::default(),
-        weight: Default::default(),
-        style: Default::default(),
-      };
-
-      let text_run = TextRun {
-        len: text.len(),
-        font: monospace_font,
-        color: self.config.text_color,
-        background_color: None,
-        underline: None,
-        strikethrough: None,
-      };
-
-      return window
-        .text_system()
-        .shape_line(text.into(), font_size, &[text_run], None);
+    if self.text_override.is_some() {
+      return self.shape_text(tab_map.expand(text), window);
+    }
+    // The cache holds the buffer text only
+    if tab_map.has_inlays() {
+      return self.shape_with_inlays(tab_map.expand(text), tab_map, window);
     }
 
     let mut cache = self.line_cache.lock().unwrap();
@@ -133,89 +218,284 @@ impl LineElement {
       return shaped.clone();
     }
 
-    let text = self
-      .buffer
-      .line(self.line_idx)
-      .unwrap_or_default()
-      .trim_end_matches('\n')
-      .to_string();
+    let shaped = self.shape_text(tab_map.expand(text), window);
+    cache.insert(self.line_idx, shaped.clone());
+    shaped
+  }
 
-    let font_size = px(self.config.font_size);
-    let monospace_font = Font {
-      family: "monospace".into(),
-      features: Default::default(),
-      fallbacks: Default::default(),
-      weight: Default::default(),
-      style: Default::default(),
-    };
+  fn shape_text(&self, text: String, window: &mut Window) -> ShapedLine {
+    self.shape_text_with_color(text, self.config.text_color, window)
+  }
 
-    let text_run = TextRun {
-      len: text.len(),
-      font: monospace_font,
-      color: self.config.text_color,
-      background_color: None,
-      underline: None,
-      strikethrough: None,
-    };
+  fn shape_text_with_color(&self, text: String, color: Hsla, window: &mut Window) -> ShapedLine {
+    let font_size = px(self.config.font_size);
+    let text_run = self.text_run(text.len(), color);
 
-    let shaped = window
+    window
       .text_system()
-      .shape_line(text.into(), font_size, &[text_run], None);
+      .shape_line(text.into(), font_size, &[text_run], None)
+  }
 
-    cache.insert(self.line_idx, shaped.clone());
+  /// Shapes the expanded text with the inlays in their own color
+  fn shape_with_inlays(&self, text: String, tab_map: &TabMap, window: &mut Window) -> ShapedLine {
+    let font_size = px(self.config.font_size);
+    let runs = inlay_runs(text.len(), &tab_map.inlay_ranges())
+      .into_iter()
+      .map(|(len, is_inlay)| {
+        let color = if is_inlay {
+          self.inlay_color
+        } else {
+          self.config.text_color
+        };
+        self.text_run(len, color)
+      })
+      .collect::<Vec<_>>();
+
+    window
+      .text_system()
+      .shape_line(text.into(), font_size, &runs, None)
+  }
 
-    shaped
+  fn text_run(&self, len: usize, color: Hsla) -> TextRun {
+    TextRun {
+      len,
+      font: self.config.font.clone(),
+      color,
+      background_color: None,
+      underline: None,
+      strikethrough: None,
+    }
   }
 
   /// Calculates cursor bounds if it is on this line
-  fn calculate_cursor_bounds(&self, shaped_line: &ShapedLine) -> Option<CursorBounds> {
+  fn calculate_cursor_bounds(
+    &self,
+    shaped_line: &ShapedLine,
+    tab_map: &TabMap,
+  ) -> Option<CursorBounds> {
     let (cursor_row, cursor_col) = self.buffer.char_to_line_col(self.editor_state.cursor_index);
 
     if cursor_row != self.line_idx {
       return None;
     }
 
-    let x = shaped_line.x_for_index(cursor_col);
+    let display_col = tab_map.to_display(cursor_col);
+    let x = shaped_line.x_for_index(display_col);
+
+    let width = match self.config.cursor_style {
+      CursorStyle::Bar => px(2.0),
+      CursorStyle::Block | CursorStyle::Underline => {
+        // Cover the whole character, a tab spans several display columns
+        let next_col = tab_map.to_display(cursor_col + 1);
+        if display_col < shaped_line.len && next_col > display_col {
+          shaped_line.x_for_index(next_col) - x
+        } else {
+          self.config.empty_cell_width()
+        }
+      }
+    };
 
-    Some(CursorBounds { x, width: px(2.0) })
+    Some(CursorBounds { x, width })
   }
 
   /// Calculates selection bounds for this line
-  fn calculate_selection_bounds(&self, shaped_line: &ShapedLine) -> Vec<SelectionBounds> {
+  fn calculate_selection_bounds(
+    &self,
+    shaped_line: &ShapedLine,
+    tab_map: &TabMap,
+  ) -> Vec<SelectionBounds> {
+    if self.selected {
+      return vec![SelectionBounds {
+        x: px(0.0),
+        width: shaped_line.x_for_index(shaped_line.len) + self.config.empty_cell_width(),
+        color: self.config.selection_color,
+      }];
+    }
+
     let Some(ref range) = self.editor_state.selection_range else {
       return Vec::new();
     };
 
-    let (start_row, start_col) = self.buffer.char_to_line_col(range.start);
-    let (end_row, end_col) = self.buffer.char_to_line_col(range.end);
+    self
+      .range_bounds(range, self.config.selection_color, shaped_line, tab_map)
+      .into_iter()
+      .collect()
+  }
 
-    if self.line_idx < start_row || self.line_idx > end_row {
+  /// Calculates the bounds of the highlights on this line
+  fn calculate_highlight_bounds(
+    &self,
+    shaped_line: &ShapedLine,
+    tab_map: &TabMap,
+  ) -> Vec<SelectionBounds> {
+    if self.text_override.is_some() {
       return Vec::new();
     }
 
-    let col_start = if self.line_idx == start_row {
-      start_col
-    } else {
-      0
-    };
+    self
+      .highlights
+      .iter()
+      .filter_map(|range| self.range_bounds(range, self.highlight_color, shaped_line, tab_map))
+      .collect()
+  }
 
-    let col_end = if self.line_idx == end_row {
-      end_col
-    } else {
-      shaped_line.len
+  /// Calculates the bounds of the diagnostic squiggles on this line
+  fn calculate_diagnostic_bounds(
+    &self,
+    shaped_line: &ShapedLine,
+    tab_map: &TabMap,
+  ) -> Vec<SelectionBounds> {
+    if self.text_override.is_some() {
+      return Vec::new();
+    }
+
+    self
+      .diagnostics
+      .iter()
+      .filter_map(|(range, color)| self.range_bounds(range, *color, shaped_line, tab_map))
+      .collect()
+  }
+
+  /// Markers centered in the cells of the rendered whitespace, tab markers start their cell
+  fn calculate_whitespace_markers(
+    &self,
+    text: &str,
+    shaped_line: &ShapedLine,
+    tab_map: &TabMap,
+    window: &mut Window,
+  ) -> Vec<WhitespaceMarker> {
+    let selected = match self.render_whitespace {
+      RenderWhitespace::None => return Vec::new(),
+      RenderWhitespace::Selection => {
+        let Some(range) = self
+          .editor_state
+          .selection_range
+          .as_ref()
+          .filter(|_| self.text_override.is_none())
+        else {
+          return Vec::new();
+        };
+        selected_columns(&self.buffer, self.line_idx, range)
+      }
+      RenderWhitespace::All | RenderWhitespace::Trailing => None,
     };
 
-    let x_start = shaped_line.x_for_index(col_start);
-    let x_end = shaped_line.x_for_index(col_end);
+    let columns = whitespace_columns(text, self.render_whitespace, selected);
+    if columns.is_empty() {
+      return Vec::new();
+    }
+
+    let space = self.shape_text_with_color(SPACE_MARKER.to_string(), self.whitespace_color, window);
+    let tab = self.shape_text_with_color(TAB_MARKER.to_string(), self.whitespace_color, window);
+    let chars: Vec<char> = text.chars().collect();
+
+    columns
+      .into_iter()
+      .map(|col| {
+        let x = tab_map.x_for_index(shaped_line, col);
+        if chars[col] == '\t' {
+          WhitespaceMarker {
+            x,
+            shaped: tab.clone(),
+          }
+        } else {
+          let cell_width = tab_map.x_for_index(shaped_line, col + 1) - x;
+          WhitespaceMarker {
+            x: x + (cell_width - space.width) / 2.0,
+            shaped: space.clone(),
+          }
+        }
+      })
+      .collect()
+  }
+
+  fn range_bounds(
+    &self,
+    range: &Range<usize>,
+    color: Hsla,
+    shaped_line: &ShapedLine,
+    tab_map: &TabMap,
+  ) -> Option<SelectionBounds> {
+    let (col_start, col_end) = selected_columns(&self.buffer, self.line_idx, range)?;
+
+    let x_start = tab_map.x_for_index(shaped_line, col_start);
+    let x_end = match col_end {
+      Some(col_end) => tab_map.x_for_index(shaped_line, col_end),
+      // The newline is selected too, shown as one more cell so selected blank lines are visible
+      None => shaped_line.x_for_index(shaped_line.len) + self.config.empty_cell_width(),
+    };
 
-    vec![SelectionBounds {
+    Some(SelectionBounds {
       x: x_start,
       width: x_end - x_start,
-      color: rgba(0x3d3d3da1).into(),
-    }]
+      color,
+    })
   }
 }
 
+/// Lengths of the runs of a line of `len` bytes, and whether each run is an inlay
+fn inlay_runs(len: usize, inlay_ranges: &[Range<usize>]) -> Vec<(usize, bool)> {
+  let mut runs = Vec::new();
+  let mut start = 0;
+  for range in inlay_ranges {
+    if range.start > start {
+      runs.push((range.start - start, false));
+    }
+    if !range.is_empty() {
+      runs.push((range.len(), true));
+    }
+    start = range.end;
+  }
+  if len > start {
+    runs.push((len - start, false));
+  }
+  runs
+}
+
+/// Columns of the spaces and tabs of a line rendered in `mode`, `selected` being the
+/// selected columns of the line like returned by `selected_columns`
+fn whitespace_columns(
+  text: &str,
+  mode: RenderWhitespace,
+  selected: Option<(usize, Option<usize>)>,
+) -> Vec<usize> {
+  let trailing_start = text.trim_end_matches([' ', '\t']).chars().count();
+  let is_rendered = |col: usize| match mode {
+    RenderWhitespace::None => false,
+    RenderWhitespace::All => true,
+    RenderWhitespace::Trailing => col >= trailing_start,
+    RenderWhitespace::Selection => {
+      selected.is_some_and(|(start, end)| col >= start && end.is_none_or(|end| col < end))
+    }
+  };
+
+  text
+    .chars()
+    .enumerate()
+    .filter(|(col, c)| (*c == ' ' || *c == '\t') && is_rendered(*col))
+    .map(|(col, _)| col)
+    .collect()
+}
+
+/// Columns of a line covered by a selection, the end is None when the selection
+/// goes past the end of the line
+fn selected_columns(
+  buffer: &TextBuffer,
+  line_idx: usize,
+  range: &Range<usize>,
+) -> Option<(usize, Option<usize>)> {
+  let (start_row, start_col) = buffer.char_to_line_col(range.start);
+  let (end_row, end_col) = buffer.char_to_line_col(range.end);
+
+  if line_idx < start_row || line_idx > end_row || range.is_empty() {
+    return None;
+  }
+
+  let col_start = if line_idx == start_row { start_col } else { 0 };
+  let col_end = (line_idx == end_row).then_some(end_col);
+  Some((col_start, col_end))
+}
+
 impl IntoElement for LineElement {
   type Element = Self;
 
@@ -263,14 +543,29 @@ impl Element for LineElement {
     window: &mut Window,
     _cx: &mut App,
   ) -> Self::PrepaintState {
-    let shaped_line = self.get_or_shape_line(window);
-    let cursor_bounds = self.calculate_cursor_bounds(&shaped_line);
-    let selection_bounds = self.calculate_selection_bounds(&shaped_line);
+    let text = self.line_text();
+    let tab_map = TabMap::new(&text, self.config.tab_size).with_inlays(&self.inlays);
+
+    let shaped_line = self.get_or_shape_line(&text, &tab_map, window);
+    let cursor_bounds = if self.cursor_visible {
+      self.calculate_cursor_bounds(&shaped_line, &tab_map)
+    } else {
+      None
+    };
+    let selection_bounds = self.calculate_selection_bounds(&shaped_line, &tab_map);
+    let highlight_bounds = self.calculate_highlight_bounds(&shaped_line, &tab_map);
+    let diagnostic_bounds = self.calculate_diagnostic_bounds(&shaped_line, &tab_map);
+    let whitespace_markers =
+      self.calculate_whitespace_markers(&text, &shaped_line, &tab_map, window);
 
     LinePrepaintState {
       shaped_line,
+      tab_map,
       cursor_bounds,
       selection_bounds,
+      highlight_bounds,
+      diagnostic_bounds,
+      whitespace_markers,
     }
   }
 
@@ -290,53 +585,98 @@ impl Element for LineElement {
     if let Some(ref diff_bg) = self.diff_background {
       let bg_bounds = Bounds::new(bounds.origin, size(bounds.size.width, line_height));
       window.paint_quad(fill(bg_bounds, diff_bg.color));
-
-      // Paint intra-line character highlights
-      for char_range in &diff_bg.char_highlights {
-        let x_start = prepaint.shaped_line.x_for_index(char_range.start);
-        let x_end = prepaint.shaped_line.x_for_index(char_range.end);
-        let highlight_bounds = Bounds::new(
-          point(bounds.origin.x + x_start, bounds.origin.y),
-          size(x_end - x_start, line_height),
-        );
-        window.paint_quad(fill(highlight_bounds, diff_bg.highlight_color));
-      }
     }
 
-    for selection in &prepaint.selection_bounds {
-      let selection_bounds = Bounds::new(
-        point(bounds.origin.x + selection.x, bounds.origin.y),
-        size(selection.width, line_height),
-      );
+    // Content is shifted by the horizontal scroll and clipped to the line bounds
+    let origin = point(bounds.origin.x - self.scroll_x, bounds.origin.y);
+
+    window.with_content_mask(Some(ContentMask { bounds }), |window| {
+      if let Some(ref diff_bg) = self.diff_background {
+        // Paint intra-line character highlights
+        for char_range in &diff_bg.char_highlights {
+          let x_start = (prepaint.tab_map).x_for_index(&prepaint.shaped_line, char_range.start);
+          let x_end = (prepaint.tab_map).x_for_index(&prepaint.shaped_line, char_range.end);
+
+          let highlight_bounds = Bounds::new(
+            point(origin.x + x_start, origin.y),
+            size(x_end - x_start, line_height),
+          );
+          window.paint_quad(fill(highlight_bounds, diff_bg.highlight_color));
+        }
+      }
 
-      window.paint_quad(fill(selection_bounds, selection.color));
-    }
+      for selection in prepaint
+        .highlight_bounds
+        .iter()
+        .chain(&prepaint.selection_bounds)
+      {
+        let selection_bounds = Bounds::new(
+          point(origin.x + selection.x, origin.y),
+          size(selection.width, line_height),
+        );
+        window.paint_quad(
+          fill(selection_bounds, selection.color).corner_radii(px(SELECTION_CORNER_RADIUS)),
+        );
+      }
 
-    prepaint
-      .shaped_line
-      .paint(bounds.origin, line_height, window, cx)
-      .ok();
+      for marker in &prepaint.whitespace_markers {
+        marker
+          .shaped
+          .paint(
+            point(origin.x + marker.x, origin.y),
+            line_height,
+            window,
+            cx,
+          )
+          .ok();
+      }
 
-    if let Some(cursor) = &prepaint.cursor_bounds {
-      let cursor_bounds = Bounds::new(
-        point(bounds.origin.x + cursor.x, bounds.origin.y),
-        size(cursor.width, line_height),
-      );
+      prepaint
+        .shaped_line
+        .paint(origin, line_height, window, cx)
+        .ok();
+
+      for diagnostic in &prepaint.diagnostic_bounds {
+        window.paint_underline(
+          point(origin.x + diagnostic.x, origin.y + line_height - px(4.0)),
+          diagnostic.width,
+          &UnderlineStyle {
+            thickness: px(1.0),
+            color: Some(diagnostic.color),
+            wavy: true,
+          },
+        );
+      }
 
-      window.paint_quad(fill(cursor_bounds, cursor_color));
-    }
+      if let Some(cursor) = &prepaint.cursor_bounds {
+        let underline_height = px(2.0);
+        let (y, height, color) = match self.config.cursor_style {
+          CursorStyle::Bar => (origin.y, line_height, cursor_color),
+          // Painted over the text, translucent so the character stays readable
+          CursorStyle::Block => (origin.y, line_height, cursor_color.alpha(0.5)),
+          CursorStyle::Underline => (
+            origin.y + line_height - underline_height,
+            underline_height,
+            cursor_color,
+          ),
+        };
+        let cursor_bounds = Bounds::new(point(origin.x + cursor.x, y), size(cursor.width, height));
+        window.paint_quad(fill(cursor_bounds, color));
+      }
+    });
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
-  use gpui::blue;
+  use gpui::{blue, rgba};
   use text::TextBuffer;
 
   #[test]
   fn test_line_config_line_height_px() {
     let config = LineConfig {
+      font: crate::FontConfig::default().font(),
       font_size: 16.0,
       line_height: 24.0,
       text_color: Hsla {
@@ -346,6 +686,9 @@ mod tests {
         a: 1.,
       },
       cursor_color: blue(),
+      cursor_style: CursorStyle::Bar,
+      selection_color: blue(),
+      tab_size: 2,
     };
     assert_eq!(config.line_height_px(), px(24.0));
   }
@@ -403,6 +746,7 @@ mod tests {
 
     let cache = Arc::new(Mutex::new(LineCache::new()));
     let config = LineConfig {
+      font: crate::FontConfig::default().font(),
       font_size: 16.0,
       line_height: 24.0,
       text_color: Hsla {
@@ -412,6 +756,9 @@ mod tests {
         a: 1.,
       },
       cursor_color: blue(),
+      cursor_style: CursorStyle::Bar,
+      selection_color: blue(),
+      tab_size: 2,
     };
 
     let element = LineElement::new(1, Arc::new(buffer), editor_state, cache, config);
@@ -431,6 +778,7 @@ mod tests {
 
     let cache = Arc::new(Mutex::new(LineCache::new()));
     let config = LineConfig {
+      font: crate::FontConfig::default().font(),
       font_size: 16.0,
       line_height: 24.0,
       text_color: Hsla {
@@ -440,6 +788,9 @@ mod tests {
         a: 1.,
       },
       cursor_color: blue(),
+      cursor_style: CursorStyle::Bar,
+      selection_color: blue(),
+      tab_size: 2,
     };
 
     let element = LineElement::new(0, Arc::new(buffer), editor_state, cache, config);
@@ -456,6 +807,7 @@ mod tests {
     };
     let cache = Arc::new(Mutex::new(LineCache::new()));
     let config = LineConfig {
+      font: crate::FontConfig::default().font(),
       font_size: 14.0,
       line_height: 21.0,
       text_color: Hsla {
@@ -465,6 +817,9 @@ mod tests {
         a: 1.,
       },
       cursor_color: blue(),
+      cursor_style: CursorStyle::Bar,
+      selection_color: blue(),
+      tab_size: 2,
     };
 
     let element = LineElement::new(5, Arc::new(buffer), editor_state, cache, config.clone());
@@ -490,4 +845,57 @@ mod tests {
     assert!(cursor_bounds.is_some());
     assert_eq!(selection_bounds.len(), 1);
   }
+
+  #[test]
+  fn test_selected_columns() {
+    let mut buffer = TextBuffer::new();
+    buffer.insert(0, "abc\n\ndef\n");
+
+    // From "b" to "e": the first lines are selected up to their newline
+    let range = 1..7;
+    assert_eq!(selected_columns(&buffer, 0, &range), Some((1, None)));
+    assert_eq!(selected_columns(&buffer, 1, &range), Some((0, None)));
+    assert_eq!(selected_columns(&buffer, 2, &range), Some((0, Some(2))));
+    assert_eq!(selected_columns(&buffer, 3, &range), None);
+  }
+
+  #[test]
+  fn test_whitespace_columns() {
+    let text = "\tlet a = 1;  ";
+    assert!(whitespace_columns(text, RenderWhitespace::None, None).is_empty());
+    assert_eq!(
+      whitespace_columns(text, RenderWhitespace::All, None),
+      vec![0, 4, 6, 8, 11, 12]
+    );
+    assert_eq!(
+      whitespace_columns(text, RenderWhitespace::Trailing, None),
+      vec![11, 12]
+    );
+    assert_eq!(
+      whitespace_columns(text, RenderWhitespace::Selection, Some((3, Some(7)))),
+      vec![4, 6]
+    );
+    assert_eq!(
+      whitespace_columns(text, RenderWhitespace::Selection, Some((10, None))),
+      vec![11, 12]
+    );
+  }
+
+  #[test]
+  fn test_selected_columns_within_a_line() {
+    let mut buffer = TextBuffer::new();
+    buffer.insert(0, "abc\ndef");
+
+    assert_eq!(selected_columns(&buffer, 1, &(5..7)), Some((1, Some(3))));
+    assert_eq!(selected_columns(&buffer, 1, &(5..5)), None);
+  }
+
+  #[test]
+  fn test_inlay_runs() {
+    assert_eq!(inlay_runs(5, &[]), vec![(5, false)]);
+    assert_eq!(
+      inlay_runs(12, &[0..3, 5..7, 9..12]),
+      vec![(3, true), (2, false), (2, true), (2, false), (3, true)]
+    );
+  }
 }
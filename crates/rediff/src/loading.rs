@@ -0,0 +1,153 @@
+use crate::file_state::FileState;
+use std::borrow::Cow;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use text::LoadProgress;
+
+/// What a background load fills in once done
+#[derive(Debug)]
+pub enum LoadTarget {
+  Open(FileState), // The file of the active tab, with the view state to restore
+  Reload,          // The active file changed on disk, its previous content stays visible meanwhile
+  Baseline,        // Content the buffer is diffed against
+}
+
+impl LoadTarget {
+  /// Whether the buffer of the active file gets replaced once done
+  pub fn loads_buffer(&self) -> bool {
+    !matches!(self, Self::Baseline)
+  }
+}
+
+/// A file being read on the background executor
+#[derive(Debug)]
+pub struct Loading {
+  pub id: usize,
+  pub path: PathBuf,
+  pub target: LoadTarget,
+  pub progress: Arc<Mutex<LoadProgress>>, // Written by the background task
+}
+
+impl Loading {
+  /// Whether the editor shows the loading indicator instead of the diff
+  pub fn blocks_view(&self) -> bool {
+    !matches!(self.target, LoadTarget::Reload)
+  }
+
+  /// Whether the buffer of the active file gets replaced once done
+  pub fn loads_buffer(&self) -> bool {
+    self.target.loads_buffer()
+  }
+
+  /// Like "Loading app.log… 42%"
+  pub fn label(&self) -> String {
+    let percent = (self.progress.lock().unwrap().fraction() * 100.0).floor();
+    format!("Loading {}… {}%", file_name(&self.path), percent)
+  }
+}
+
+/// A file that couldn't be read, shown until the load is retried or dismissed
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadError {
+  pub path: PathBuf,
+  pub message: String,
+  pub baseline: bool, // The file the buffer is diffed against, not the open file
+}
+
+impl LoadError {
+  /// Like "Can't read app.log: Permission denied (os error 13)"
+  pub fn label(&self) -> String {
+    let purpose = if self.baseline {
+      " to compare with"
+    } else {
+      ""
+    };
+    format!(
+      "Can't read {}{}: {}",
+      file_name(&self.path),
+      purpose,
+      self.message
+    )
+  }
+}
+
+fn file_name(path: &Path) -> Cow<'_, str> {
+  path
+    .file_name()
+    .map(|name| name.to_string_lossy())
+    .unwrap_or_else(|| path.to_string_lossy())
+}
+
+/// Last modification time of a file, None when it can't be read
+pub fn modified_time(path: &Path) -> Option<SystemTime> {
+  std::fs::metadata(path).ok()?.modified().ok()
+}
+
+/// Hash of a file's text, to tell whether a file with a new modification time really changed
+pub fn content_hash(text: &str) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  text.hash(&mut hasher);
+  hasher.finish()
+}
+
+/// FNV-1a hash of some bytes, it stays the same across builds unlike `DefaultHasher`, for
+/// names and keys written to disk
+pub fn stable_hash(bytes: impl IntoIterator<Item = u8>) -> u64 {
+  bytes.into_iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+    (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_loading_label() {
+    let loading = Loading {
+      id: 0,
+      path: PathBuf::from("/var/log/app.log"),
+      target: LoadTarget::Reload,
+      progress: Arc::new(Mutex::new(LoadProgress {
+        loaded_bytes: 42,
+        total_bytes: 100,
+      })),
+    };
+    assert_eq!(loading.label(), "Loading app.log… 42%");
+    assert!(!loading.blocks_view());
+    assert!(loading.loads_buffer());
+  }
+
+  #[test]
+  fn test_load_error_label() {
+    let mut error = LoadError {
+      path: PathBuf::from("/var/log/app.log"),
+      message: "Permission denied (os error 13)".to_string(),
+      baseline: false,
+    };
+    assert_eq!(
+      error.label(),
+      "Can't read app.log: Permission denied (os error 13)"
+    );
+    error.baseline = true;
+    assert_eq!(
+      error.label(),
+      "Can't read app.log to compare with: Permission denied (os error 13)"
+    );
+  }
+
+  #[test]
+  fn test_content_hash() {
+    assert_eq!(content_hash("a\nb\n"), content_hash("a\nb\n"));
+    assert_ne!(content_hash("a\nb\n"), content_hash("a\nb"));
+  }
+
+  #[test]
+  fn test_stable_hash() {
+    // Reference value of FNV-1a, names written by previous builds must still match
+    assert_eq!(stable_hash(*b"a"), 0xaf63dc4c8601ec8c);
+    assert_eq!(stable_hash([]), 0xcbf29ce484222325);
+  }
+}
@@ -0,0 +1,638 @@
+use crate::config::LanguageServerConfig;
+use crate::diagnostics::{Diagnostic, DiagnosticSeverity};
+use serde_json::{Value, json};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const INITIALIZE_ID: u64 = 0;
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(300); // For the answer to `shutdown` and then for the exit, the server is killed after that
+
+/// Position in a text as LSP counts it, columns are UTF-16 code units
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+  pub line: usize,
+  pub character: usize,
+}
+
+impl Position {
+  fn to_json(self) -> Value {
+    json!({ "line": self.line, "character": self.character })
+  }
+
+  fn from_json(value: &Value) -> Option<Self> {
+    Some(Self {
+      line: value.get("line")?.as_u64()? as usize,
+      character: value.get("character")?.as_u64()? as usize,
+    })
+  }
+}
+
+/// Position of char `index` of `text`, clamped to its end
+pub fn position_at(text: &str, index: usize) -> Position {
+  let mut position = Position {
+    line: 0,
+    character: 0,
+  };
+  for c in text.chars().take(index) {
+    if c == '\n' {
+      position.line += 1;
+      position.character = 0;
+    } else {
+      position.character += c.len_utf16();
+    }
+  }
+  position
+}
+
+/// Char index of `position` in `text`, columns past the end of a line stop at its end
+pub fn char_index_at(text: &str, position: Position) -> usize {
+  let mut line = 0;
+  let mut character = 0;
+  for (index, c) in text.chars().enumerate() {
+    if line == position.line && (character >= position.character || c == '\n') {
+      return index;
+    }
+    if c == '\n' {
+      line += 1;
+    } else if line == position.line {
+      character += c.len_utf16();
+    }
+  }
+  text.chars().count()
+}
+
+/// Range of `old` replaced and its replacement to turn it into `new`, None when they're equal
+pub fn text_change(old: &str, new: &str) -> Option<(Range<Position>, String)> {
+  let prefix = old
+    .chars()
+    .zip(new.chars())
+    .take_while(|(a, b)| a == b)
+    .count();
+  let (old_len, new_len) = (old.chars().count(), new.chars().count());
+  if prefix == old_len && prefix == new_len {
+    return None;
+  }
+  let suffix = old
+    .chars()
+    .rev()
+    .zip(new.chars().rev())
+    .take_while(|(a, b)| a == b)
+    .count()
+    .min(old_len.min(new_len) - prefix);
+
+  let range = position_at(old, prefix)..position_at(old, old_len - suffix);
+  let text = new
+    .chars()
+    .skip(prefix)
+    .take(new_len - prefix - suffix)
+    .collect();
+  Some((range, text))
+}
+
+/// `file://` URI of an absolute path
+pub fn file_uri(path: &Path) -> String {
+  let path = path.to_string_lossy().replace('\\', "/");
+  let mut uri = if path.starts_with('/') {
+    "file://".to_string()
+  } else {
+    "file:///".to_string() // Windows drive letters
+  };
+  for byte in path.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+        uri.push(byte as char)
+      }
+      _ => uri.push_str(&format!("%{:02X}", byte)),
+    }
+  }
+  uri
+}
+
+/// Diagnostics of a `textDocument/publishDiagnostics` notification, positioned in `text`
+pub fn diagnostics_from_lsp(items: &[Value], text: &str) -> Vec<Diagnostic> {
+  items
+    .iter()
+    .filter_map(|item| {
+      let range = item.get("range")?;
+      let start = char_index_at(text, Position::from_json(range.get("start")?)?);
+      let end = char_index_at(text, Position::from_json(range.get("end")?)?);
+      let severity = match item.get("severity").and_then(Value::as_u64) {
+        Some(2) => DiagnosticSeverity::Warning,
+        Some(3) => DiagnosticSeverity::Info,
+        Some(4) => DiagnosticSeverity::Hint,
+        _ => DiagnosticSeverity::Error,
+      };
+      // Multi-line messages show their first line, like in most editors' underlines
+      let message = item.get("message")?.as_str()?.lines().next().unwrap_or("");
+      let diagnostic = Diagnostic::new(start..end.max(start), severity, message);
+      Some(match item.get("source").and_then(Value::as_str) {
+        Some(source) => diagnostic.with_source(source),
+        None => diagnostic,
+      })
+    })
+    .collect()
+}
+
+/// Text of a `textDocument/hover` result, None when there's nothing to show
+pub fn hover_text(result: &Value) -> Option<String> {
+  fn marked_string(value: &Value) -> Option<&str> {
+    match value {
+      Value::String(text) => Some(text),
+      // MarkupContent and language strings
+      Value::Object(object) => object.get("value")?.as_str(),
+      _ => None,
+    }
+  }
+
+  let contents = result.get("contents")?;
+  let text = match contents {
+    Value::Array(items) => items
+      .iter()
+      .filter_map(marked_string)
+      .collect::<Vec<_>>()
+      .join("\n\n"),
+    contents => marked_string(contents)?.to_string(),
+  };
+  let text = text.trim();
+  (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Answer to a request of the server, like progress or configuration, all of them are empty
+fn request_result(method: &str, params: &Value) -> Value {
+  match method {
+    // One setting per item asked for, null leaves each to the server's default
+    "workspace/configuration" => {
+      let items = params["items"].as_array().map_or(0, Vec::len);
+      Value::Array(vec![Value::Null; items])
+    }
+    _ => Value::Null,
+  }
+}
+
+/// Checks `done` until it holds or `timeout` passed, whether it held
+fn wait_until(timeout: Duration, mut done: impl FnMut() -> bool) -> bool {
+  let deadline = Instant::now() + timeout;
+  loop {
+    if done() {
+      return true;
+    }
+    if Instant::now() >= deadline {
+      return false;
+    }
+    thread::sleep(Duration::from_millis(10));
+  }
+}
+
+fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+  let body = message.to_string();
+  write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+  writer.flush()
+}
+
+/// Next message of the stream, None once it's closed
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+  let mut content_length = None;
+  loop {
+    let mut header = String::new();
+    if reader.read_line(&mut header)? == 0 {
+      return Ok(None);
+    }
+    let header = header.trim_end();
+    if header.is_empty() {
+      break;
+    }
+    if let Some(value) = header.strip_prefix("Content-Length:") {
+      content_length = value.trim().parse().ok();
+    }
+  }
+
+  let len = content_length
+    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length"))?;
+  let mut body = vec![0; len];
+  reader.read_exact(&mut body)?;
+  serde_json::from_slice(&body)
+    .map(Some)
+    .map_err(io::Error::other)
+}
+
+/// What the server sent since the last poll
+#[derive(Debug, Clone, PartialEq)]
+pub enum LspEvent {
+  Diagnostics { uri: String, items: Vec<Value> },
+  Hover { id: u64, text: Option<String> },
+}
+
+/// Language server process talking JSON-RPC over its stdin and stdout
+///
+/// Messages are read on a thread and handed over on `poll`, which the editor calls from a
+/// timer, and written on another one so that a slow server doesn't hold the editor up.
+/// Documents are synced incrementally, with one change per sync
+pub struct LanguageServer {
+  config: LanguageServerConfig,
+  child: Child,
+  outgoing: Sender<Value>, // Written to stdin by the writer thread, in order
+  incoming: Arc<Mutex<VecDeque<Value>>>, // Filled by the reader thread
+  next_id: u64,
+  initialized: bool,
+  queued: Vec<Value>,             // Sent once the server answered `initialize`
+  versions: HashMap<String, i64>, // Of the open documents, by URI
+}
+
+impl LanguageServer {
+  /// Launches the server and starts initializing it, `root` is the workspace folder
+  pub fn start(config: &LanguageServerConfig, root: &Path) -> io::Result<Self> {
+    let mut child = Command::new(&config.command)
+      .args(&config.args)
+      .current_dir(root)
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::null())
+      .spawn()?;
+    let mut stdin = child
+      .stdin
+      .take()
+      .ok_or_else(|| io::Error::other("no stdin"))?;
+    let stdout = child
+      .stdout
+      .take()
+      .ok_or_else(|| io::Error::other("no stdout"))?;
+
+    let incoming = Arc::new(Mutex::new(VecDeque::new()));
+    let reader_incoming = incoming.clone();
+    thread::spawn(move || {
+      let mut reader = BufReader::new(stdout);
+      while let Ok(Some(message)) = read_message(&mut reader) {
+        reader_incoming.lock().unwrap().push_back(message);
+      }
+    });
+
+    let (outgoing, messages) = mpsc::channel::<Value>();
+    thread::spawn(move || {
+      for message in messages {
+        // Nothing is read after `exit`, and later messages can't be sent once a write failed
+        if write_message(&mut stdin, &message).is_err() || message["method"] == "exit" {
+          break;
+        }
+      }
+    });
+
+    let server = Self {
+      config: config.clone(),
+      child,
+      outgoing,
+      incoming,
+      next_id: INITIALIZE_ID + 1,
+      initialized: false,
+      queued: Vec::new(),
+      versions: HashMap::new(),
+    };
+    server.write(json!({
+      "jsonrpc": "2.0",
+      "id": INITIALIZE_ID,
+      "method": "initialize",
+      "params": {
+        "processId": std::process::id(),
+        "rootUri": file_uri(root),
+        "capabilities": {
+          "general": { "positionEncodings": ["utf-16"] },
+          "textDocument": {
+            "synchronization": { "dynamicRegistration": false },
+            "hover": { "contentFormat": ["plaintext", "markdown"] },
+            "publishDiagnostics": { "relatedInformation": false },
+          },
+        },
+      },
+    }))?;
+    Ok(server)
+  }
+
+  pub fn config(&self) -> &LanguageServerConfig {
+    &self.config
+  }
+
+  pub fn did_open(&mut self, path: &Path, text: &str) -> io::Result<()> {
+    let uri = file_uri(path);
+    self.versions.insert(uri.clone(), 0);
+    let language_id = self.config.language_id.clone();
+    self.notify(
+      "textDocument/didOpen",
+      json!({
+        "textDocument": { "uri": uri, "languageId": language_id, "version": 0, "text": text },
+      }),
+    )
+  }
+
+  /// Sends the range of `old_text` that changed, `old_text` being what the server last got
+  pub fn did_change(&mut self, path: &Path, old_text: &str, new_text: &str) -> io::Result<()> {
+    let Some((range, text)) = text_change(old_text, new_text) else {
+      return Ok(());
+    };
+    let uri = file_uri(path);
+    let version = self.versions.entry(uri.clone()).or_default();
+    *version += 1;
+    let version = *version;
+    self.notify(
+      "textDocument/didChange",
+      json!({
+        "textDocument": { "uri": uri, "version": version },
+        "contentChanges": [{
+          "range": { "start": range.start.to_json(), "end": range.end.to_json() },
+          "text": text,
+        }],
+      }),
+    )
+  }
+
+  pub fn did_close(&mut self, path: &Path) -> io::Result<()> {
+    let uri = file_uri(path);
+    self.versions.remove(&uri);
+    self.notify(
+      "textDocument/didClose",
+      json!({ "textDocument": { "uri": uri } }),
+    )
+  }
+
+  /// Asks for the hover info at char `index` of `text`, answered by an `LspEvent::Hover`
+  /// with the returned id
+  pub fn hover(&mut self, path: &Path, text: &str, index: usize) -> io::Result<u64> {
+    self.request(
+      "textDocument/hover",
+      json!({
+        "textDocument": { "uri": file_uri(path) },
+        "position": position_at(text, index).to_json(),
+      }),
+    )
+  }
+
+  /// Handles the messages read since the last poll, answering the server's requests
+  pub fn poll(&mut self) -> io::Result<Vec<LspEvent>> {
+    if let Some(status) = self.child.try_wait()? {
+      return Err(io::Error::other(format!("exited with {}", status)));
+    }
+
+    let messages: Vec<Value> = self.incoming.lock().unwrap().drain(..).collect();
+    let mut events = Vec::new();
+    for message in messages {
+      let id = message.get("id").cloned();
+      let method = message.get("method").and_then(Value::as_str);
+      match (id, method) {
+        (Some(id), Some(method)) => {
+          let result = request_result(method, &message["params"]);
+          self.write(json!({ "jsonrpc": "2.0", "id": id, "result": result }))?;
+        }
+        (Some(id), None) if id.as_u64() == Some(INITIALIZE_ID) && !self.initialized => {
+          if let Some(error) = message.get("error") {
+            let error = error.get("message").and_then(Value::as_str).unwrap_or("");
+            return Err(io::Error::other(format!("initialize failed: {}", error)));
+          }
+          self.initialized = true;
+          self.write(json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }))?;
+          for message in std::mem::take(&mut self.queued) {
+            self.write(message)?;
+          }
+        }
+        // Hover is the only request sent after `initialize`
+        (Some(id), None) => {
+          if let Some(id) = id.as_u64() {
+            let text = message.get("result").and_then(hover_text);
+            events.push(LspEvent::Hover { id, text });
+          }
+        }
+        (None, Some("textDocument/publishDiagnostics")) => {
+          let params = &message["params"];
+          if let Some(uri) = params.get("uri").and_then(Value::as_str) {
+            let items = params
+              .get("diagnostics")
+              .and_then(Value::as_array)
+              .cloned()
+              .unwrap_or_default();
+            events.push(LspEvent::Diagnostics {
+              uri: uri.to_string(),
+              items,
+            });
+          }
+        }
+        _ => {}
+      }
+    }
+    Ok(events)
+  }
+
+  fn notify(&mut self, method: &str, params: Value) -> io::Result<()> {
+    self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+  }
+
+  fn request(&mut self, method: &str, params: Value) -> io::Result<u64> {
+    let id = self.next_id;
+    self.next_id += 1;
+    self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+    Ok(id)
+  }
+
+  fn send(&mut self, message: Value) -> io::Result<()> {
+    if !self.initialized {
+      self.queued.push(message);
+      return Ok(());
+    }
+    self.write(message)
+  }
+
+  /// Asks the server to shut down and then to exit like the spec wants, waiting at most
+  /// `SHUTDOWN_TIMEOUT` for each step
+  fn shut_down(&mut self) {
+    let id = self.next_id;
+    self.next_id += 1;
+    if self
+      .write(json!({ "jsonrpc": "2.0", "id": id, "method": "shutdown" }))
+      .is_err()
+    {
+      return;
+    }
+    let answered = wait_until(SHUTDOWN_TIMEOUT, || {
+      self
+        .incoming
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|message| message.get("id").and_then(Value::as_u64) == Some(id))
+    });
+    if answered
+      && self
+        .write(json!({ "jsonrpc": "2.0", "method": "exit" }))
+        .is_ok()
+    {
+      wait_until(SHUTDOWN_TIMEOUT, || {
+        matches!(self.child.try_wait(), Ok(Some(_)))
+      });
+    }
+  }
+
+  /// Hands `message` to the writer thread, fails once it stopped on a write error
+  fn write(&self, message: Value) -> io::Result<()> {
+    self
+      .outgoing
+      .send(message)
+      .map_err(|_| io::Error::other("the language server stopped reading"))
+  }
+}
+
+impl Drop for LanguageServer {
+  fn drop(&mut self) {
+    if self.initialized {
+      self.shut_down();
+    }
+    // Also ends a writer stuck on a full pipe
+    self.child.kill().ok();
+    self.child.wait().ok();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_utf16_positions() {
+    // "é" is one UTF-16 unit, "😀" two
+    let text = "aé😀b\nxy";
+    assert_eq!(
+      position_at(text, 4),
+      Position {
+        line: 0,
+        character: 5
+      }
+    );
+    assert_eq!(
+      position_at(text, 5),
+      Position {
+        line: 1,
+        character: 0
+      }
+    );
+    assert_eq!(
+      char_index_at(
+        text,
+        Position {
+          line: 0,
+          character: 5
+        }
+      ),
+      4
+    );
+    // Past the end of the line
+    assert_eq!(
+      char_index_at(
+        text,
+        Position {
+          line: 0,
+          character: 40
+        }
+      ),
+      4
+    );
+    assert_eq!(
+      char_index_at(
+        text,
+        Position {
+          line: 9,
+          character: 0
+        }
+      ),
+      7
+    );
+  }
+
+  #[test]
+  fn test_text_change() {
+    assert_eq!(text_change("same", "same"), None);
+    let (range, text) = text_change("let a = 1;\nlet b;\n", "let a = 1;\nlet bc;\n").unwrap();
+    assert_eq!(
+      range,
+      Position {
+        line: 1,
+        character: 5
+      }..Position {
+        line: 1,
+        character: 5
+      }
+    );
+    assert_eq!(text, "c");
+  }
+
+  #[test]
+  fn test_file_uri() {
+    assert_eq!(
+      file_uri(Path::new("/src/my file.rs")),
+      "file:///src/my%20file.rs"
+    );
+  }
+
+  #[test]
+  fn test_diagnostics_from_lsp() {
+    let items = vec![json!({
+      "range": {
+        "start": { "line": 1, "character": 4 },
+        "end": { "line": 1, "character": 5 },
+      },
+      "severity": 2,
+      "source": "rustc",
+      "message": "unused variable: `b`\nhelp: prefix it with an underscore",
+    })];
+    let diagnostics = diagnostics_from_lsp(&items, "let a = 1;\nlet b;\n");
+    assert_eq!(
+      diagnostics,
+      vec![
+        Diagnostic::new(15..16, DiagnosticSeverity::Warning, "unused variable: `b`")
+          .with_source("rustc")
+      ]
+    );
+  }
+
+  #[test]
+  fn test_hover_text() {
+    let markup = json!({ "contents": { "kind": "markdown", "value": "fn main()" } });
+    assert_eq!(hover_text(&markup).as_deref(), Some("fn main()"));
+    let marked = json!({ "contents": ["i32", { "language": "rust", "value": "let a" }] });
+    assert_eq!(hover_text(&marked).as_deref(), Some("i32\n\nlet a"));
+    assert_eq!(hover_text(&json!({ "contents": "" })), None);
+  }
+
+  #[test]
+  fn test_request_result() {
+    let params = json!({ "items": [{ "section": "rust-analyzer" }, { "section": "files" }] });
+    assert_eq!(
+      request_result("workspace/configuration", &params),
+      json!([null, null])
+    );
+    assert_eq!(
+      request_result("window/workDoneProgress/create", &json!({})),
+      Value::Null
+    );
+  }
+
+  #[test]
+  fn test_wait_until() {
+    let mut checks = 0;
+    assert!(wait_until(SHUTDOWN_TIMEOUT, || {
+      checks += 1;
+      checks == 3
+    }));
+    assert!(!wait_until(Duration::from_millis(20), || false));
+  }
+
+  #[test]
+  fn test_read_message() {
+    let mut bytes = Vec::new();
+    write_message(&mut bytes, &json!({ "id": 1 })).unwrap();
+    let mut reader = io::Cursor::new(bytes);
+    assert_eq!(read_message(&mut reader).unwrap(), Some(json!({ "id": 1 })));
+    assert_eq!(read_message(&mut reader).unwrap(), None);
+  }
+}
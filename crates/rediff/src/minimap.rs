@@ -0,0 +1,433 @@
+use crate::display_rows::DisplayRow;
+use crate::line_cache::LineCache;
+use gpui::{
+  App, Bounds, DispatchPhase, Element, ElementId, GlobalElementId, Hitbox, HitboxBehavior, Hsla,
+  InspectorElementId, IntoElement, LayoutId, MouseButton, MouseDownEvent, MouseMoveEvent,
+  MouseUpEvent, Pixels, Style, UniformListScrollHandle, Window, fill, point, px, relative, size,
+};
+use rediff_core::{DiffLine, DiffLineKind};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+const MINIMAP_ROW_HEIGHT: f32 = 2.0; // Rows shrink below this when the whole file doesn't fit
+const MINIMAP_CHAR_WIDTH: f32 = 1.0;
+const MINIMAP_PADDING: f32 = 4.0;
+
+/// Columns of the runs of non-whitespace characters of a line, drawn as blocks in the minimap
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MinimapLine {
+  pub blocks: Vec<Range<usize>>, // Display columns, tabs expanded
+}
+
+impl MinimapLine {
+  pub fn new(text: &str, tab_size: usize) -> Self {
+    let tab_size = tab_size.max(1);
+    let mut blocks: Vec<Range<usize>> = Vec::new();
+    let mut column = 0;
+
+    for c in text.chars() {
+      let width = if c == '\t' {
+        tab_size - column % tab_size
+      } else {
+        1
+      };
+      if !c.is_whitespace() {
+        match blocks.last_mut() {
+          Some(last) if last.end == column => last.end = column + 1,
+          _ => blocks.push(column..column + 1),
+        }
+      }
+      column += width;
+    }
+
+    Self { blocks }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct MinimapColors {
+  pub bg_color: Hsla,
+  pub text_color: Hsla,
+  pub viewport_color: Hsla,
+  pub added_color: Hsla,
+  pub removed_color: Hsla,
+  pub modified_color: Hsla,
+  pub moved_color: Hsla,
+}
+
+/// Geometry shared by painting and mouse handling, in plain pixels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MinimapMetrics {
+  pub row_count: usize,     // Display rows, overscroll included
+  pub line_height: f32,     // Height of a row in the editor
+  pub viewport_height: f32, // Height of the editor viewport
+  pub track_height: f32,
+}
+
+impl MinimapMetrics {
+  /// Height of a row in the minimap, small enough for the whole file to fit
+  pub fn row_height(&self) -> f32 {
+    if self.row_count == 0 {
+      return MINIMAP_ROW_HEIGHT;
+    }
+    (self.track_height / self.row_count as f32).min(MINIMAP_ROW_HEIGHT)
+  }
+
+  pub fn max_scroll(&self) -> f32 {
+    (self.row_count as f32 * self.line_height - self.viewport_height).max(0.0)
+  }
+
+  /// Vertical span of the viewport rectangle for the given scroll position
+  pub fn viewport_span(&self, scroll_top: f32) -> (f32, f32) {
+    if self.line_height <= 0.0 {
+      return (0.0, 0.0);
+    }
+    let scale = self.row_height() / self.line_height;
+    (
+      scroll_top.clamp(0.0, self.max_scroll()) * scale,
+      self.viewport_height * scale,
+    )
+  }
+
+  /// Scroll position that centers the viewport on the given minimap offset
+  pub fn scroll_top_for_y(&self, y: f32) -> f32 {
+    let content_y = y / self.row_height() * self.line_height;
+    (content_y - self.viewport_height / 2.0).clamp(0.0, self.max_scroll())
+  }
+
+  /// Rows to paint with their top offset, at most one per pixel so long files stay cheap
+  pub fn painted_rows(&self) -> Vec<(usize, f32)> {
+    let row_height = self.row_height();
+    if row_height >= 1.0 {
+      return (0..self.row_count)
+        .map(|row| (row, row as f32 * row_height))
+        .collect();
+    }
+
+    let band_count = self.track_height.floor() as usize;
+    (0..band_count)
+      .map(|band| (band * self.row_count / band_count, band as f32))
+      .collect()
+  }
+}
+
+/// Tracks whether the viewport rectangle is being dragged
+#[derive(Default)]
+pub struct MinimapState {
+  pub dragging: bool,
+}
+
+/// Scaled-down view of the whole file with diff colors, drives the shared scroll handle
+pub struct DiffMinimap {
+  rows: Vec<DisplayRow>,
+  row_count: usize,
+  line_cache: Arc<Mutex<LineCache>>,
+  tab_size: usize,
+  line_height: f32,
+  width: f32,
+  scroll_handle: UniformListScrollHandle,
+  state: Arc<Mutex<MinimapState>>,
+  colors: MinimapColors,
+}
+
+impl DiffMinimap {
+  #[allow(clippy::too_many_arguments)]
+  pub fn new(
+    rows: Vec<DisplayRow>,
+    row_count: usize,
+    line_cache: Arc<Mutex<LineCache>>,
+    tab_size: usize,
+    line_height: f32,
+    width: f32,
+    scroll_handle: UniformListScrollHandle,
+    state: Arc<Mutex<MinimapState>>,
+    colors: MinimapColors,
+  ) -> Self {
+    Self {
+      rows,
+      row_count,
+      line_cache,
+      tab_size,
+      line_height,
+      width,
+      scroll_handle,
+      state,
+      colors,
+    }
+  }
+
+  fn metrics(&self, track_height: Pixels) -> MinimapMetrics {
+    let viewport_height = self
+      .scroll_handle
+      .0
+      .borrow()
+      .base_handle
+      .bounds()
+      .size
+      .height;
+    MinimapMetrics {
+      row_count: self.row_count,
+      line_height: self.line_height,
+      viewport_height: f32::from(viewport_height),
+      track_height: f32::from(track_height),
+    }
+  }
+
+  fn line_color(&self, kind: &DiffLineKind) -> Option<Hsla> {
+    match kind {
+      DiffLineKind::Added => Some(self.colors.added_color),
+      DiffLineKind::Removed => Some(self.colors.removed_color),
+      DiffLineKind::Modified => Some(self.colors.modified_color),
+      DiffLineKind::Moved => Some(self.colors.moved_color),
+      DiffLineKind::Unchanged => None,
+    }
+  }
+
+  /// Blocks of a diff line, cached for lines of the buffer
+  fn minimap_line(&self, cache: &mut LineCache, line: &DiffLine) -> MinimapLine {
+    let Some(line_idx) = line.line_number.checked_sub(1) else {
+      // Removed lines aren't in the buffer
+      return MinimapLine::new(&line.content, self.tab_size);
+    };
+    if let Some(minimap_line) = cache.get_minimap(line_idx) {
+      return minimap_line.clone();
+    }
+    let minimap_line = MinimapLine::new(&line.content, self.tab_size);
+    cache.insert_minimap(line_idx, minimap_line.clone());
+    minimap_line
+  }
+}
+
+fn scroll_top(scroll_handle: &UniformListScrollHandle) -> f32 {
+  -f32::from(scroll_handle.0.borrow().base_handle.offset().y)
+}
+
+fn set_scroll_top(scroll_handle: &UniformListScrollHandle, scroll_top: f32) {
+  let base_handle = scroll_handle.0.borrow().base_handle.clone();
+  let offset = base_handle.offset();
+  base_handle.set_offset(point(offset.x, px(-scroll_top)));
+}
+
+impl IntoElement for DiffMinimap {
+  type Element = Self;
+
+  fn into_element(self) -> Self::Element {
+    self
+  }
+}
+
+impl Element for DiffMinimap {
+  type RequestLayoutState = ();
+  type PrepaintState = Hitbox;
+
+  fn id(&self) -> Option<ElementId> {
+    None
+  }
+
+  fn source_location(&self) -> Option<&'static std::panic::Location<'static>> {
+    None
+  }
+
+  fn request_layout(
+    &mut self,
+    _id: Option<&GlobalElementId>,
+    _inspector_id: Option<&InspectorElementId>,
+    window: &mut Window,
+    cx: &mut App,
+  ) -> (LayoutId, Self::RequestLayoutState) {
+    let mut style = Style::default();
+    style.size.width = px(self.width).into();
+    style.size.height = relative(1.0).into();
+    style.flex_shrink = 0.0;
+
+    (window.request_layout(style, vec![], cx), ())
+  }
+
+  fn prepaint(
+    &mut self,
+    _id: Option<&GlobalElementId>,
+    _inspector_id: Option<&InspectorElementId>,
+    bounds: Bounds<Pixels>,
+    _request_layout: &mut Self::RequestLayoutState,
+    window: &mut Window,
+    _cx: &mut App,
+  ) -> Self::PrepaintState {
+    window.insert_hitbox(bounds, HitboxBehavior::Normal)
+  }
+
+  fn paint(
+    &mut self,
+    _id: Option<&GlobalElementId>,
+    _inspector_id: Option<&InspectorElementId>,
+    bounds: Bounds<Pixels>,
+    _request_layout: &mut Self::RequestLayoutState,
+    hitbox: &mut Self::PrepaintState,
+    window: &mut Window,
+    _cx: &mut App,
+  ) {
+    let metrics = self.metrics(bounds.size.height);
+    let row_height = metrics.row_height().max(1.0);
+    let max_columns =
+      ((f32::from(bounds.size.width) - MINIMAP_PADDING) / MINIMAP_CHAR_WIDTH).max(0.0) as usize;
+
+    window.paint_quad(fill(bounds, self.colors.bg_color));
+
+    {
+      let mut cache = self.line_cache.lock().unwrap();
+      for (row, top) in metrics.painted_rows() {
+        let Some(DisplayRow::Line { line, .. }) = self.rows.get(row) else {
+          continue;
+        };
+        let row_top = bounds.origin.y + px(top);
+
+        if let Some(color) = self.line_color(&line.kind) {
+          window.paint_quad(fill(
+            Bounds::new(
+              point(bounds.origin.x, row_top),
+              size(bounds.size.width, px(row_height)),
+            ),
+            color,
+          ));
+        }
+
+        for block in self.minimap_line(&mut cache, line).blocks {
+          if block.start >= max_columns {
+            break;
+          }
+          let end = block.end.min(max_columns);
+          window.paint_quad(fill(
+            Bounds::new(
+              point(
+                bounds.origin.x + px(MINIMAP_PADDING + block.start as f32 * MINIMAP_CHAR_WIDTH),
+                row_top,
+              ),
+              size(
+                px((end - block.start) as f32 * MINIMAP_CHAR_WIDTH),
+                px(row_height),
+              ),
+            ),
+            self.colors.text_color,
+          ));
+        }
+      }
+    }
+
+    let (viewport_top, viewport_height) = metrics.viewport_span(scroll_top(&self.scroll_handle));
+    window.paint_quad(fill(
+      Bounds::new(
+        point(bounds.origin.x, bounds.origin.y + px(viewport_top)),
+        size(bounds.size.width, px(viewport_height)),
+      ),
+      self.colors.viewport_color,
+    ));
+
+    let track_top = bounds.origin.y;
+
+    window.on_mouse_event({
+      let hitbox = hitbox.clone();
+      let state = self.state.clone();
+      let scroll_handle = self.scroll_handle.clone();
+      move |event: &MouseDownEvent, phase, window, cx| {
+        if phase != DispatchPhase::Bubble
+          || event.button != MouseButton::Left
+          || !hitbox.is_hovered(window)
+        {
+          return;
+        }
+
+        let y = f32::from(event.position.y - track_top);
+        set_scroll_top(&scroll_handle, metrics.scroll_top_for_y(y));
+        window.refresh();
+        state.lock().unwrap().dragging = true;
+        cx.stop_propagation();
+      }
+    });
+
+    window.on_mouse_event({
+      let state = self.state.clone();
+      let scroll_handle = self.scroll_handle.clone();
+      move |event: &MouseMoveEvent, phase, window, cx| {
+        if phase != DispatchPhase::Bubble || !state.lock().unwrap().dragging {
+          return;
+        }
+
+        if event.pressed_button != Some(MouseButton::Left) {
+          state.lock().unwrap().dragging = false;
+          return;
+        }
+
+        let y = f32::from(event.position.y - track_top);
+        set_scroll_top(&scroll_handle, metrics.scroll_top_for_y(y));
+        window.refresh();
+        cx.stop_propagation();
+      }
+    });
+
+    window.on_mouse_event({
+      let state = self.state.clone();
+      move |_event: &MouseUpEvent, phase, _window, _cx| {
+        if phase == DispatchPhase::Bubble {
+          state.lock().unwrap().dragging = false;
+        }
+      }
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_minimap_line_blocks() {
+    assert_eq!(
+      MinimapLine::new("  let a = 1;", 4).blocks,
+      vec![2..5, 6..7, 8..9, 10..12]
+    );
+    // Tabs expand to the next tab stop
+    assert_eq!(MinimapLine::new("\tx\ty", 4).blocks, vec![4..5, 8..9]);
+    assert!(MinimapLine::new("   ", 4).blocks.is_empty());
+  }
+
+  #[test]
+  fn test_rows_shrink_to_fit_the_track() {
+    let metrics = MinimapMetrics {
+      row_count: 100,
+      line_height: 20.0,
+      viewport_height: 400.0,
+      track_height: 400.0,
+    };
+    assert_eq!(metrics.row_height(), MINIMAP_ROW_HEIGHT);
+    assert_eq!(metrics.painted_rows().len(), 100);
+
+    let metrics = MinimapMetrics {
+      row_count: 1000,
+      ..metrics
+    };
+    assert_eq!(metrics.row_height(), 0.4);
+    // One row per pixel
+    let rows = metrics.painted_rows();
+    assert_eq!(rows.len(), 400);
+    assert_eq!(rows[1], (2, 1.0));
+  }
+
+  #[test]
+  fn test_viewport_span_and_click_round_trip() {
+    let metrics = MinimapMetrics {
+      row_count: 100,
+      line_height: 20.0,
+      viewport_height: 400.0,
+      track_height: 400.0,
+    };
+    // 20 rows of the editor are visible, 2px each in the minimap
+    assert_eq!(metrics.viewport_span(200.0), (20.0, 40.0));
+
+    // Clicking centers the viewport on the clicked row
+    assert_eq!(metrics.scroll_top_for_y(60.0), 400.0);
+    assert_eq!(metrics.viewport_span(400.0), (40.0, 40.0));
+
+    // Clamped at both ends
+    assert_eq!(metrics.scroll_top_for_y(0.0), 0.0);
+    assert_eq!(metrics.scroll_top_for_y(400.0), metrics.max_scroll());
+  }
+}
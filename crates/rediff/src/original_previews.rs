@@ -0,0 +1,220 @@
+use crate::display_rows::DisplayRow;
+use editor::LineEdit;
+use rediff_core::{DiffLine, DiffLineKind};
+use std::collections::BTreeSet;
+
+/// Row of an inline preview, an original line shown below the line that replaced it
+#[derive(Debug, Clone, PartialEq)]
+pub struct OriginalRow {
+  pub line: usize,             // Buffer line (0-based) the preview belongs to
+  pub content: Option<String>, // None for a line added without replacing any
+}
+
+/// Added and modified lines showing the original lines they replaced right below them, read-only
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OriginalPreviews {
+  expanded: BTreeSet<usize>, // Buffer lines (0-based)
+}
+
+impl OriginalPreviews {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Shows or hides the preview of buffer line `line`, returns whether it shows now
+  pub fn toggle(&mut self, line: usize) -> bool {
+    if self.expanded.remove(&line) {
+      return false;
+    }
+    self.expanded.insert(line);
+    true
+  }
+
+  pub fn is_expanded(&self, line: usize) -> bool {
+    self.expanded.contains(&line)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.expanded.is_empty()
+  }
+
+  pub fn clear(&mut self) {
+    self.expanded.clear();
+  }
+
+  /// Shifts previews below edited lines, previews of deleted lines go away
+  pub fn apply_edits(&mut self, edits: &[LineEdit]) {
+    for edit in edits {
+      let deleted = if edit.line_delta < 0 {
+        edit.line_end + 1 - edit.line_delta.unsigned_abs()..edit.line_end + 1
+      } else {
+        0..0
+      };
+      self.expanded = std::mem::take(&mut self.expanded)
+        .into_iter()
+        .filter(|line| !deleted.contains(line))
+        .filter_map(|line| {
+          if line <= edit.line_end {
+            Some(line)
+          } else {
+            line.checked_add_signed(edit.line_delta)
+          }
+        })
+        .collect();
+    }
+  }
+
+  /// Inserts the preview rows right after the row of each expanded line
+  pub fn insert_rows(&self, rows: Vec<DisplayRow>) -> Vec<DisplayRow> {
+    if self.expanded.is_empty() {
+      return rows;
+    }
+
+    let mut result = Vec::with_capacity(rows.len());
+    for (idx, row) in rows.iter().enumerate() {
+      result.push(row.clone());
+      let Some(line) = row
+        .diff_line()
+        .filter(|line| has_original(line))
+        .and_then(|line| line.line_number.checked_sub(1))
+        .filter(|line| self.expanded.contains(line))
+      else {
+        continue;
+      };
+
+      let originals = original_lines(&rows, idx);
+      if originals.is_empty() {
+        result.push(DisplayRow::Original(OriginalRow {
+          line,
+          content: None,
+        }));
+      }
+      result.extend(originals.into_iter().map(|content| {
+        DisplayRow::Original(OriginalRow {
+          line,
+          content: Some(content),
+        })
+      }));
+    }
+    result
+  }
+}
+
+/// Whether `line` can show a preview: the added half of a modified pair or an added line
+pub fn has_original(line: &DiffLine) -> bool {
+  match line.kind {
+    DiffLineKind::Added => true,
+    DiffLineKind::Modified => line.line_number > 0,
+    DiffLineKind::Unchanged | DiffLineKind::Removed | DiffLineKind::Moved => false,
+  }
+}
+
+/// Original lines the line of `rows[idx]` replaced, newlines trimmed
+///
+/// That's the removed half of a modified pair, or for an added line the removed lines of the
+/// change it's part of
+fn original_lines(rows: &[DisplayRow], idx: usize) -> Vec<String> {
+  let Some(line) = rows[idx].diff_line() else {
+    return Vec::new();
+  };
+  if let Some(pair) = &line.pair {
+    return vec![pair.content.trim_end_matches('\n').to_string()];
+  }
+
+  // The change runs until an unchanged line or a collapsed region, threads are skipped
+  let is_change = |row: &DisplayRow| match row {
+    DisplayRow::Line { line, .. } => line.kind != DiffLineKind::Unchanged,
+    DisplayRow::Collapsed(_) => false,
+    DisplayRow::Thread(_) | DisplayRow::Original(_) => true,
+  };
+  let start = rows[..idx]
+    .iter()
+    .rposition(|row| !is_change(row))
+    .map_or(0, |row| row + 1);
+  let end = rows[idx..]
+    .iter()
+    .position(|row| !is_change(row))
+    .map_or(rows.len(), |row| idx + row);
+
+  rows[start..end]
+    .iter()
+    .filter_map(DisplayRow::diff_line)
+    .filter(|line| line.kind == DiffLineKind::Removed)
+    .map(|line| line.content.trim_end_matches('\n').to_string())
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::display_rows::line_rows;
+  use rediff_core::Differ;
+
+  fn rows(original: &str, modified: &str) -> Vec<DisplayRow> {
+    let differ = Differ::new(original.to_string());
+    line_rows(differ.compute_diff(modified))
+  }
+
+  fn shown(rows: &[DisplayRow]) -> Vec<(usize, Option<&str>)> {
+    rows
+      .iter()
+      .filter_map(|row| match row {
+        DisplayRow::Original(original) => Some((original.line, original.content.as_deref())),
+        _ => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_modified_line_shows_its_pair() {
+    let rows = rows("a\nlet x = 1;\nb\n", "a\nlet x = 2;\nb\n");
+    let mut previews = OriginalPreviews::new();
+    assert!(previews.toggle(1));
+
+    let rows = previews.insert_rows(rows);
+    assert_eq!(shown(&rows), [(1, Some("let x = 1;"))]);
+    // Right below the line
+    let idx = rows
+      .iter()
+      .position(|row| matches!(row, DisplayRow::Original(_)))
+      .unwrap();
+    assert_eq!(rows[idx - 1].diff_line().unwrap().line_number, 2);
+
+    assert!(!previews.toggle(1));
+    assert!(previews.is_empty());
+  }
+
+  #[test]
+  fn test_added_line_shows_removed_lines_of_its_change() {
+    let rows = rows("a\nfirst\nsecond\nb\n", "a\n0123456789\nb\nc\n");
+    let mut previews = OriginalPreviews::new();
+    previews.toggle(1);
+    previews.toggle(3); // Added without replacing anything
+    previews.toggle(0); // Unchanged, nothing to show
+
+    let rows = previews.insert_rows(rows);
+    assert_eq!(
+      shown(&rows),
+      [(1, Some("first")), (1, Some("second")), (3, None)]
+    );
+  }
+
+  #[test]
+  fn test_apply_edits() {
+    let mut previews = OriginalPreviews::new();
+    previews.toggle(2);
+    previews.toggle(5);
+    previews.toggle(8);
+
+    // Lines 4 and 5 deleted
+    previews.apply_edits(&[LineEdit {
+      line_start: 3,
+      line_end: 5,
+      line_delta: -2,
+    }]);
+    assert!(previews.is_expanded(2));
+    assert!(!previews.is_expanded(5));
+    assert!(!previews.is_expanded(3));
+    assert!(previews.is_expanded(6));
+  }
+}
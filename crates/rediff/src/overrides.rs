@@ -0,0 +1,128 @@
+use crate::config::EditorConfig;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+  pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "invalid config: {}", self.message)
+  }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Settings of one editor laid over the global config, in the shape of a TOML config file
+///
+/// Tables merge key by key, so overrides only name the settings they change:
+/// `[diff_options] ignore_case = true` keeps the other diff options of the global config
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigOverrides {
+  table: toml::Table,
+}
+
+impl ConfigOverrides {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn parse(text: &str) -> Result<Self, ConfigError> {
+    let table = text.parse::<toml::Table>().map_err(|e| ConfigError {
+      message: e.to_string(),
+    })?;
+    Ok(Self { table })
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.table.is_empty()
+  }
+
+  /// Overrides a top-level setting, replacing a previous override of it
+  pub fn set(&mut self, key: impl Into<String>, value: impl Into<toml::Value>) {
+    self.table.insert(key.into(), value.into());
+  }
+
+  /// Lays the overrides of `other` over these ones
+  pub fn merge(&mut self, other: &ConfigOverrides) {
+    merge_tables(&mut self.table, &other.table);
+  }
+
+  /// The global config with these overrides applied
+  pub fn apply(&self, global: &EditorConfig) -> Result<EditorConfig, ConfigError> {
+    let error = |message: String| ConfigError { message };
+    let mut table = toml::Table::try_from(global).map_err(|e| error(e.to_string()))?;
+    merge_tables(&mut table, &self.table);
+    let mut config: EditorConfig = table.try_into().map_err(|e| error(e.to_string()))?;
+    // Not part of config files
+    config.keymap = global.keymap.clone();
+    Ok(config)
+  }
+}
+
+fn merge_tables(base: &mut toml::Table, overrides: &toml::Table) {
+  for (key, value) in overrides {
+    match (base.get_mut(key), value) {
+      (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+        merge_tables(base, overrides)
+      }
+      _ => {
+        base.insert(key.clone(), value.clone());
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::LineNumbers;
+
+  #[test]
+  fn test_apply_keeps_unset_settings() {
+    let global = EditorConfig {
+      tab_size: 8,
+      status_bar: true,
+      ..Default::default()
+    };
+    let overrides = ConfigOverrides::parse(
+      "tab_size = 4\nline_numbers = \"relative\"\n[diff_options]\nignore_case = true\n",
+    )
+    .unwrap();
+
+    let config = overrides.apply(&global).unwrap();
+    assert_eq!(config.tab_size, 4);
+    assert_eq!(config.line_numbers, LineNumbers::Relative);
+    assert!(config.status_bar);
+    assert!(config.diff_options.ignore_case);
+    assert_eq!(
+      config.diff_options.similarity_threshold,
+      global.diff_options.similarity_threshold
+    );
+    assert!(!config.keymap.is_empty());
+  }
+
+  #[test]
+  fn test_later_overrides_win() {
+    let mut overrides = ConfigOverrides::new();
+    overrides.set("tab_size", 4);
+    overrides.set("minimap", true);
+    let mut view = ConfigOverrides::new();
+    view.set("tab_size", 2);
+    overrides.merge(&view);
+
+    let config = overrides.apply(&EditorConfig::default()).unwrap();
+    assert_eq!(config.tab_size, 2);
+    assert!(config.minimap);
+  }
+
+  #[test]
+  fn test_invalid_override() {
+    assert!(ConfigOverrides::parse("tab_size = ").is_err());
+
+    let mut overrides = ConfigOverrides::new();
+    overrides.set("tab_size", "wide");
+    assert!(overrides.apply(&EditorConfig::default()).is_err());
+  }
+}
@@ -0,0 +1,129 @@
+use crate::line_cache::LineCacheStats;
+use std::time::{Duration, Instant};
+
+/// Counters of one frame, shown by the profiling overlay
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameStats {
+  pub frame_time: Duration, // From the start of the render to the end of the paint
+  pub lines_shaped: usize,  // Line cache misses, each one shapes a line
+  pub cache_hits: usize,
+  pub diffs: usize, // Diffs computed, views compute their own
+  pub diff_time: Duration,
+}
+
+impl FrameStats {
+  /// Part of the line lookups the cache answered, None when no line was looked up
+  pub fn hit_rate(&self) -> Option<f32> {
+    let lookups = self.cache_hits + self.lines_shaped;
+    (lookups > 0).then(|| self.cache_hits as f32 / lookups as f32)
+  }
+}
+
+/// Times the frames of an editor while profiling is on, the counters stay idle otherwise
+#[derive(Debug, Default)]
+pub struct RenderProfiler {
+  frame: Option<(Instant, LineCacheStats)>, // Start of the frame in progress, with the cache stats then
+  diffs: usize,
+  diff_time: Duration,
+  last_frame: Option<FrameStats>,
+}
+
+impl RenderProfiler {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Starts a frame, a frame that never ended is dropped
+  pub fn begin_frame(&mut self, cache_stats: LineCacheStats) {
+    self.frame = Some((Instant::now(), cache_stats));
+    self.diffs = 0;
+    self.diff_time = Duration::ZERO;
+  }
+
+  /// Counts a diff computed during the frame in progress, outside of frames it's not counted
+  pub fn record_diff(&mut self, elapsed: Duration) {
+    if self.frame.is_some() {
+      self.diffs += 1;
+      self.diff_time += elapsed;
+    }
+  }
+
+  /// Ends the frame in progress once it's painted, `cache_stats` are the stats by then
+  pub fn end_frame(&mut self, cache_stats: LineCacheStats) {
+    let Some((start, start_stats)) = self.frame.take() else {
+      return;
+    };
+    self.last_frame = Some(FrameStats {
+      frame_time: start.elapsed(),
+      // The stats go back to zero when they're reset during the frame
+      lines_shaped: cache_stats.misses.saturating_sub(start_stats.misses),
+      cache_hits: cache_stats.hits.saturating_sub(start_stats.hits),
+      diffs: self.diffs,
+      diff_time: self.diff_time,
+    });
+  }
+
+  /// Last painted frame
+  pub fn last_frame(&self) -> Option<FrameStats> {
+    self.last_frame
+  }
+
+  pub fn is_in_frame(&self) -> bool {
+    self.frame.is_some()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn cache_stats(hits: usize, misses: usize) -> LineCacheStats {
+    LineCacheStats {
+      hits,
+      misses,
+      evictions: 0,
+    }
+  }
+
+  #[test]
+  fn test_frame_stats() {
+    let mut profiler = RenderProfiler::new();
+    // Diffs outside of frames aren't counted
+    profiler.record_diff(Duration::from_millis(5));
+
+    profiler.begin_frame(cache_stats(10, 4));
+    assert!(profiler.is_in_frame());
+    profiler.record_diff(Duration::from_millis(2));
+    profiler.record_diff(Duration::from_millis(3));
+    profiler.end_frame(cache_stats(40, 14));
+    assert!(!profiler.is_in_frame());
+
+    let frame = profiler.last_frame().unwrap();
+    assert_eq!(frame.lines_shaped, 10);
+    assert_eq!(frame.cache_hits, 30);
+    assert_eq!(frame.hit_rate(), Some(0.75));
+    assert_eq!(frame.diffs, 2);
+    assert_eq!(frame.diff_time, Duration::from_millis(5));
+
+    // The next frame starts over
+    profiler.begin_frame(cache_stats(40, 14));
+    profiler.end_frame(cache_stats(40, 14));
+    let frame = profiler.last_frame().unwrap();
+    assert_eq!((frame.lines_shaped, frame.diffs), (0, 0));
+    assert_eq!(frame.hit_rate(), None);
+  }
+
+  #[test]
+  fn test_unfinished_frame() {
+    let mut profiler = RenderProfiler::new();
+    profiler.end_frame(cache_stats(1, 1));
+    assert_eq!(profiler.last_frame(), None);
+
+    // A frame that never ended is dropped, stats reset since it started don't underflow
+    profiler.begin_frame(cache_stats(50, 20));
+    profiler.begin_frame(cache_stats(50, 20));
+    profiler.end_frame(cache_stats(3, 2));
+    let frame = profiler.last_frame().unwrap();
+    assert_eq!((frame.lines_shaped, frame.cache_hits), (0, 0));
+  }
+}
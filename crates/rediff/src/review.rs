@@ -0,0 +1,207 @@
+use crate::loading::stable_hash;
+use rediff_core::{DiffHunk, DiffLine, DiffLineKind};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Where the review of a hunk stands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewState {
+  #[default]
+  Unviewed,
+  Viewed,
+  NeedsWork,
+}
+
+impl ReviewState {
+  /// Whether the hunk was looked at, hunks needing work were reviewed too
+  pub fn is_reviewed(self) -> bool {
+    self != Self::Unviewed
+  }
+}
+
+/// Key of a hunk made from its lines, the review of a hunk follows it when lines are added
+/// above it and is lost once the hunk itself changes
+pub fn hunk_key(diff_lines: &[DiffLine], hunk: &DiffHunk) -> u64 {
+  let bytes = diff_lines[hunk.rows.clone()].iter().flat_map(|line| {
+    let kind = match line.kind {
+      DiffLineKind::Unchanged => b' ',
+      DiffLineKind::Added => b'+',
+      DiffLineKind::Removed => b'-',
+      DiffLineKind::Modified if line.line_number == 0 => b'<',
+      DiffLineKind::Modified => b'>',
+      DiffLineKind::Moved => b'~',
+    };
+    std::iter::once(kind)
+      .chain(line.content.bytes())
+      .chain(std::iter::once(b'\n'))
+  });
+  stable_hash(bytes)
+}
+
+/// File of the review of `path`, the hunk states are kept by key
+#[derive(Debug, Serialize, Deserialize)]
+struct ReviewFile {
+  path: PathBuf,
+  hunks: HashMap<u64, ReviewState>,
+}
+
+/// Review states of the hunks of a file, unviewed hunks aren't stored
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Review {
+  states: HashMap<u64, ReviewState>,
+}
+
+impl Review {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn state(&self, key: u64) -> ReviewState {
+    self.states.get(&key).copied().unwrap_or_default()
+  }
+
+  pub fn set(&mut self, key: u64, state: ReviewState) {
+    match state {
+      ReviewState::Unviewed => self.states.remove(&key),
+      state => self.states.insert(key, state),
+    };
+  }
+
+  /// Sets `state`, or goes back to unviewed when the hunk already is in it, returns the new state
+  pub fn toggle(&mut self, key: u64, state: ReviewState) -> ReviewState {
+    let state = if self.state(key) == state {
+      ReviewState::Unviewed
+    } else {
+      state
+    };
+    self.set(key, state);
+    state
+  }
+
+  /// Reviewed hunks among `keys`, and how many there are
+  pub fn progress(&self, keys: &[u64]) -> (usize, usize) {
+    let reviewed = keys
+      .iter()
+      .filter(|key| self.state(**key).is_reviewed())
+      .count();
+    (reviewed, keys.len())
+  }
+
+  /// Forgets the hunks that are no longer in the diff
+  pub fn retain(&mut self, keys: &[u64]) {
+    self.states.retain(|key, _| keys.contains(key));
+  }
+
+  /// Review file of `file_path` in `dir`
+  pub fn path_for(dir: &Path, file_path: &Path) -> PathBuf {
+    let hash = stable_hash(file_path.to_string_lossy().bytes());
+    dir.join(format!("{:016x}.review", hash))
+  }
+
+  /// Review of `file_path` saved in `dir`, empty when there's none
+  pub fn load(dir: &Path, file_path: &Path) -> io::Result<Self> {
+    let json = match fs::read_to_string(Self::path_for(dir, file_path)) {
+      Ok(json) => json,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::new()),
+      Err(e) => return Err(e),
+    };
+    let file: ReviewFile = serde_json::from_str(&json).map_err(io::Error::other)?;
+    // Another file whose path hashes the same
+    if file.path != file_path {
+      return Ok(Self::new());
+    }
+    Ok(Self { states: file.hunks })
+  }
+
+  /// Writes the review of `file_path` to `dir`, or removes its file once nothing is reviewed
+  pub fn save(&self, dir: &Path, file_path: &Path) -> io::Result<()> {
+    let path = Self::path_for(dir, file_path);
+    if self.states.is_empty() {
+      return match fs::remove_file(&path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+      };
+    }
+
+    fs::create_dir_all(dir)?;
+    let file = ReviewFile {
+      path: file_path.to_path_buf(),
+      hunks: self.states.clone(),
+    };
+    let json = serde_json::to_string(&file).map_err(io::Error::other)?;
+    fs::write(path, json)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rediff_core::{DiffOptions, Differ, diff};
+
+  fn keys(original: &str, current: &str) -> Vec<u64> {
+    let lines = diff(original, current, &DiffOptions::default()).lines;
+    Differ::compute_hunks(&lines)
+      .iter()
+      .map(|hunk| hunk_key(&lines, hunk))
+      .collect()
+  }
+
+  #[test]
+  fn test_hunk_key_follows_the_hunk() {
+    let before = keys("a\nb\nc\nd\n", "a\nB\nc\nd\nE\n");
+    assert_eq!(before.len(), 2);
+
+    // A line added above moves the hunks, their keys stay
+    let after = keys("a\nb\nc\nd\n", "new\na\nB\nc\nd\nE\n");
+    assert_eq!(after.len(), 3);
+    assert_eq!(&after[1..], &before[..]);
+
+    // Editing the hunk makes it another one
+    let edited = keys("a\nb\nc\nd\n", "a\nBB\nc\nd\nE\n");
+    assert_ne!(edited[0], before[0]);
+    assert_eq!(edited[1], before[1]);
+  }
+
+  #[test]
+  fn test_toggle_and_progress() {
+    let mut review = Review::new();
+    assert_eq!(review.toggle(1, ReviewState::Viewed), ReviewState::Viewed);
+    assert_eq!(
+      review.toggle(2, ReviewState::NeedsWork),
+      ReviewState::NeedsWork
+    );
+    assert_eq!(review.progress(&[1, 2, 3]), (2, 3));
+
+    assert_eq!(review.toggle(1, ReviewState::Viewed), ReviewState::Unviewed);
+    assert_eq!(review.toggle(2, ReviewState::Viewed), ReviewState::Viewed);
+    assert_eq!(review.progress(&[1, 2, 3]), (1, 3));
+
+    review.retain(&[1, 3]);
+    assert_eq!(review.state(2), ReviewState::Unviewed);
+  }
+
+  #[test]
+  fn test_save_and_load() {
+    let dir = std::env::temp_dir().join("rediff_review_save");
+    let file_path = Path::new("src/main.rs");
+    let mut review = Review::new();
+    review.set(7, ReviewState::Viewed);
+    review.set(8, ReviewState::NeedsWork);
+    review.save(&dir, file_path).unwrap();
+
+    assert_eq!(Review::load(&dir, file_path).unwrap(), review);
+    assert_eq!(
+      Review::load(&dir, Path::new("src/lib.rs")).unwrap(),
+      Review::new()
+    );
+
+    // Nothing left to keep
+    Review::new().save(&dir, file_path).unwrap();
+    assert!(!Review::path_for(&dir, file_path).exists());
+    fs::remove_dir_all(&dir).ok();
+  }
+}
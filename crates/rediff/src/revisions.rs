@@ -0,0 +1,137 @@
+use std::time::SystemTime;
+
+/// Identifies a revision, given back by `Revisions::add`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RevisionId(usize);
+
+/// Content of the buffer captured at some point, to diff against later
+#[derive(Debug, Clone, PartialEq)]
+pub struct Revision {
+  pub label: String,
+  pub content: String,
+  pub created: SystemTime,
+}
+
+/// What the buffer is diffed against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Baseline {
+  #[default]
+  Original, // The compare content the editor was opened with, or the last compare file loaded
+  Revision(RevisionId),
+  Custom, // Text supplied by the host through `DiffEditor::compare_with_text`
+}
+
+/// Revisions of the open file, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct Revisions {
+  revisions: Vec<(RevisionId, Revision)>,
+  next_id: usize,
+}
+
+impl Revisions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn add(&mut self, label: impl Into<String>, content: String) -> RevisionId {
+    let id = RevisionId(self.next_id);
+    self.next_id += 1;
+    self.revisions.push((
+      id,
+      Revision {
+        label: label.into(),
+        content,
+        created: SystemTime::now(),
+      },
+    ));
+    id
+  }
+
+  pub fn get(&self, id: RevisionId) -> Option<&Revision> {
+    self
+      .revisions
+      .iter()
+      .find(|(revision_id, _)| *revision_id == id)
+      .map(|(_, revision)| revision)
+  }
+
+  pub fn remove(&mut self, id: RevisionId) -> Option<Revision> {
+    let ix = self
+      .revisions
+      .iter()
+      .position(|(revision_id, _)| *revision_id == id)?;
+    Some(self.revisions.remove(ix).1)
+  }
+
+  pub fn clear(&mut self) {
+    self.revisions.clear();
+  }
+
+  pub fn len(&self) -> usize {
+    self.revisions.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.revisions.is_empty()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (RevisionId, &Revision)> {
+    self.revisions.iter().map(|(id, revision)| (*id, revision))
+  }
+
+  /// Label of a baseline, as shown in the baseline picker
+  pub fn baseline_label(&self, baseline: Baseline) -> String {
+    match baseline {
+      Baseline::Original => "Original".to_string(),
+      Baseline::Revision(id) => self
+        .get(id)
+        .map_or_else(|| "Original".to_string(), |revision| revision.label.clone()),
+      Baseline::Custom => "Custom".to_string(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_add_get_remove() {
+    let mut revisions = Revisions::new();
+    let first = revisions.add("Before refactor", "a\n".to_string());
+    let second = revisions.add("Last save", "b\n".to_string());
+
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(revisions.get(first).unwrap().content, "a\n");
+    let labels: Vec<&str> = revisions
+      .iter()
+      .map(|(_, revision)| revision.label.as_str())
+      .collect();
+    assert_eq!(labels, vec!["Before refactor", "Last save"]);
+
+    assert_eq!(revisions.remove(first).unwrap().label, "Before refactor");
+    assert!(revisions.remove(first).is_none());
+    assert!(revisions.get(first).is_none());
+
+    // Ids aren't reused once removed
+    let third = revisions.add("Third", String::new());
+    assert_ne!(third, first);
+    assert_ne!(third, second);
+  }
+
+  #[test]
+  fn test_baseline_label() {
+    let mut revisions = Revisions::new();
+    let id = revisions.add("Last save", String::new());
+    assert_eq!(revisions.baseline_label(Baseline::Original), "Original");
+    assert_eq!(
+      revisions.baseline_label(Baseline::Revision(id)),
+      "Last save"
+    );
+    assert_eq!(revisions.baseline_label(Baseline::Custom), "Custom");
+
+    revisions.clear();
+    assert!(revisions.is_empty());
+    assert_eq!(revisions.baseline_label(Baseline::Revision(id)), "Original");
+  }
+}
@@ -0,0 +1,121 @@
+use crate::diff_editor::{DiffEditor, DiffEditorEvent};
+use gpui::{App, Entity, Subscription};
+use rediff_core::LineMap;
+
+/// Where an editor is scrolled to, in buffer lines so that it holds across layouts and editors
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScrollPosition {
+  pub line: usize, // Buffer line (0-based) at the top of the viewport, or the one after the removed rows there
+  pub fraction: f32, // Part of the top row scrolled past, 0 to 1
+}
+
+impl ScrollPosition {
+  /// Same place in another version of the text, `line_map` maps this text to it
+  ///
+  /// The top line keeps its fraction when it's still there, lines that changed scroll to
+  /// the top of the line replacing them
+  pub fn map(self, line_map: &LineMap) -> Self {
+    let (line, same) = line_map.map_line(self.line);
+    Self {
+      line,
+      fraction: if same { self.fraction } else { 0.0 },
+    }
+  }
+}
+
+/// Keeps two editors scrolled to the same content, like the old and the new file of a side by
+/// side review
+///
+/// Scrolling either one scrolls the other. Lines are matched through the line diff of both
+/// texts, so they stay lined up below added and removed lines. The editors are linked until
+/// the handle is dropped
+pub struct ScrollSync {
+  _subscriptions: [Subscription; 2],
+}
+
+impl ScrollSync {
+  pub fn new(first: &Entity<DiffEditor>, second: &Entity<DiffEditor>, cx: &mut App) -> Self {
+    Self {
+      _subscriptions: [follow(first, second, cx), follow(second, first, cx)],
+    }
+  }
+}
+
+/// Scrolls `follower` along with `leader`
+fn follow(
+  leader: &Entity<DiffEditor>,
+  follower: &Entity<DiffEditor>,
+  cx: &mut App,
+) -> Subscription {
+  let follower = follower.downgrade();
+  cx.subscribe(leader, move |leader, event: &DiffEditorEvent, cx| {
+    if *event != DiffEditorEvent::Scrolled {
+      return;
+    }
+    let Some(follower) = follower.upgrade() else {
+      return;
+    };
+    let (position, text) = {
+      let leader = leader.read(cx);
+      (leader.scroll_position(), leader.text())
+    };
+    follower.update(cx, |follower, cx| {
+      let line_map = LineMap::new(&text, &follower.text());
+      follower.set_scroll_position(position.map(&line_map), cx);
+    });
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_map_position() {
+    let line_map = LineMap::new("a\nb\nc\nd\n", "new\na\nc\nchanged\n");
+    let position = ScrollPosition {
+      line: 2,
+      fraction: 0.5,
+    };
+    // One line added above, one removed
+    assert_eq!(
+      position.map(&line_map),
+      ScrollPosition {
+        line: 2,
+        fraction: 0.5
+      }
+    );
+    assert_eq!(
+      ScrollPosition {
+        line: 0,
+        fraction: 0.25
+      }
+      .map(&line_map),
+      ScrollPosition {
+        line: 1,
+        fraction: 0.25
+      }
+    );
+    // The removed line scrolls to the line after it, a changed one to the line replacing it
+    assert_eq!(
+      ScrollPosition {
+        line: 1,
+        fraction: 0.5
+      }
+      .map(&line_map),
+      ScrollPosition {
+        line: 2,
+        fraction: 0.0
+      }
+    );
+    assert_eq!(
+      ScrollPosition {
+        line: 3,
+        fraction: 0.5
+      }
+      .map(&line_map)
+      .line,
+      3
+    );
+  }
+}
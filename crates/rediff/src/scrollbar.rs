@@ -0,0 +1,491 @@
+use crate::display_rows::DisplayRow;
+use gpui::{
+  App, Bounds, DispatchPhase, Element, ElementId, GlobalElementId, Hitbox, HitboxBehavior, Hsla,
+  InspectorElementId, IntoElement, LayoutId, MouseButton, MouseDownEvent, MouseMoveEvent,
+  MouseUpEvent, Pixels, Style, UniformListScrollHandle, Window, fill, point, px, relative, size,
+};
+use rediff_core::{DiffLine, DiffLineKind};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+const MIN_MARKER_HEIGHT: f32 = 2.0;
+const MIN_THUMB_HEIGHT: f32 = 20.0;
+
+/// A contiguous run of changed diff lines shown as one mark on the scrollbar
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScrollbarMarker {
+  pub range: Range<usize>,
+  pub kind: DiffLineKind,
+}
+
+impl ScrollbarMarker {
+  /// Groups consecutive changed lines of the same kind into markers
+  pub fn from_diff_lines(diff_lines: &[DiffLine]) -> Vec<ScrollbarMarker> {
+    Self::from_kinds(diff_lines.iter().map(|line| Some(&line.kind)))
+  }
+
+  /// Same as `from_diff_lines`, with indices of display rows so markers line up with collapsed views
+  pub fn from_display_rows(rows: &[DisplayRow]) -> Vec<ScrollbarMarker> {
+    Self::from_kinds(
+      rows
+        .iter()
+        .map(|row| row.diff_line().map(|line| &line.kind)),
+    )
+  }
+
+  fn from_kinds<'a>(kinds: impl Iterator<Item = Option<&'a DiffLineKind>>) -> Vec<ScrollbarMarker> {
+    let mut markers: Vec<ScrollbarMarker> = Vec::new();
+
+    for (idx, kind) in kinds.enumerate() {
+      let Some(kind) = kind.filter(|kind| **kind != DiffLineKind::Unchanged) else {
+        continue;
+      };
+
+      match markers.last_mut() {
+        Some(last) if last.range.end == idx && last.kind == *kind => {
+          last.range.end = idx + 1;
+        }
+        _ => markers.push(ScrollbarMarker {
+          range: idx..idx + 1,
+          kind: kind.clone(),
+        }),
+      }
+    }
+
+    markers
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct ScrollbarColors {
+  pub track_color: Hsla,
+  pub thumb_color: Hsla,
+  pub added_color: Hsla,
+  pub removed_color: Hsla,
+  pub modified_color: Hsla,
+  pub moved_color: Hsla,
+}
+
+/// Geometry shared by painting and mouse handling, in plain pixels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScrollbarMetrics {
+  pub content_height: f32,
+  pub viewport_height: f32,
+  pub track_height: f32,
+}
+
+impl ScrollbarMetrics {
+  pub fn max_scroll(&self) -> f32 {
+    (self.content_height - self.viewport_height).max(0.0)
+  }
+
+  pub fn thumb_height(&self) -> f32 {
+    if self.content_height <= 0.0 {
+      return self.track_height;
+    }
+    let ratio = (self.viewport_height / self.content_height).min(1.0);
+    (ratio * self.track_height).max(MIN_THUMB_HEIGHT.min(self.track_height))
+  }
+
+  /// Top of the thumb relative to the track for the given scroll position
+  pub fn thumb_top(&self, scroll_top: f32) -> f32 {
+    let max_scroll = self.max_scroll();
+    if max_scroll <= 0.0 {
+      return 0.0;
+    }
+    let travel = self.track_height - self.thumb_height();
+    (scroll_top.clamp(0.0, max_scroll) / max_scroll) * travel
+  }
+
+  /// Scroll position that places the thumb top at the given track offset
+  pub fn scroll_top_for_thumb(&self, thumb_top: f32) -> f32 {
+    let travel = self.track_height - self.thumb_height();
+    if travel <= 0.0 {
+      return 0.0;
+    }
+    (thumb_top.clamp(0.0, travel) / travel) * self.max_scroll()
+  }
+
+  /// Vertical span of a line range on the track
+  pub fn marker_span(&self, range: &Range<usize>, line_count: usize) -> (f32, f32) {
+    if line_count == 0 {
+      return (0.0, 0.0);
+    }
+    let top = range.start as f32 / line_count as f32 * self.track_height;
+    let height =
+      (range.len() as f32 / line_count as f32 * self.track_height).max(MIN_MARKER_HEIGHT);
+    (top, height)
+  }
+}
+
+/// Tracks an in-progress thumb drag (distance from the thumb top to the grab point)
+#[derive(Default)]
+pub struct ScrollbarState {
+  pub drag_offset: Option<f32>,
+}
+
+/// Vertical overview scrollbar that paints diff markers and drives the shared scroll handle
+pub struct DiffScrollbar {
+  markers: Vec<ScrollbarMarker>,
+  line_count: usize,
+  line_height: f32,
+  width: f32,
+  scroll_handle: UniformListScrollHandle,
+  state: Arc<Mutex<ScrollbarState>>,
+  colors: ScrollbarColors,
+}
+
+impl DiffScrollbar {
+  pub fn new(
+    markers: Vec<ScrollbarMarker>,
+    line_count: usize,
+    line_height: f32,
+    width: f32,
+    scroll_handle: UniformListScrollHandle,
+    state: Arc<Mutex<ScrollbarState>>,
+    colors: ScrollbarColors,
+  ) -> Self {
+    Self {
+      markers,
+      line_count,
+      line_height,
+      width,
+      scroll_handle,
+      state,
+      colors,
+    }
+  }
+
+  fn metrics(&self, track_height: Pixels) -> ScrollbarMetrics {
+    let viewport_height = self
+      .scroll_handle
+      .0
+      .borrow()
+      .base_handle
+      .bounds()
+      .size
+      .height;
+    ScrollbarMetrics {
+      content_height: self.line_count as f32 * self.line_height,
+      viewport_height: f32::from(viewport_height),
+      track_height: f32::from(track_height),
+    }
+  }
+
+  fn marker_color(&self, kind: &DiffLineKind) -> Hsla {
+    match kind {
+      DiffLineKind::Added => self.colors.added_color,
+      DiffLineKind::Removed => self.colors.removed_color,
+      DiffLineKind::Modified => self.colors.modified_color,
+      DiffLineKind::Moved => self.colors.moved_color,
+      DiffLineKind::Unchanged => self.colors.track_color,
+    }
+  }
+}
+
+fn scroll_top(scroll_handle: &UniformListScrollHandle) -> f32 {
+  -f32::from(scroll_handle.0.borrow().base_handle.offset().y)
+}
+
+fn set_scroll_top(scroll_handle: &UniformListScrollHandle, scroll_top: f32) {
+  let base_handle = scroll_handle.0.borrow().base_handle.clone();
+  let offset = base_handle.offset();
+  base_handle.set_offset(point(offset.x, px(-scroll_top)));
+}
+
+impl IntoElement for DiffScrollbar {
+  type Element = Self;
+
+  fn into_element(self) -> Self::Element {
+    self
+  }
+}
+
+impl Element for DiffScrollbar {
+  type RequestLayoutState = ();
+  type PrepaintState = Hitbox;
+
+  fn id(&self) -> Option<ElementId> {
+    None
+  }
+
+  fn source_location(&self) -> Option<&'static std::panic::Location<'static>> {
+    None
+  }
+
+  fn request_layout(
+    &mut self,
+    _id: Option<&GlobalElementId>,
+    _inspector_id: Option<&InspectorElementId>,
+    window: &mut Window,
+    cx: &mut App,
+  ) -> (LayoutId, Self::RequestLayoutState) {
+    let mut style = Style::default();
+    style.size.width = px(self.width).into();
+    style.size.height = relative(1.0).into();
+    style.flex_shrink = 0.0;
+
+    (window.request_layout(style, vec![], cx), ())
+  }
+
+  fn prepaint(
+    &mut self,
+    _id: Option<&GlobalElementId>,
+    _inspector_id: Option<&InspectorElementId>,
+    bounds: Bounds<Pixels>,
+    _request_layout: &mut Self::RequestLayoutState,
+    window: &mut Window,
+    _cx: &mut App,
+  ) -> Self::PrepaintState {
+    window.insert_hitbox(bounds, HitboxBehavior::Normal)
+  }
+
+  fn paint(
+    &mut self,
+    _id: Option<&GlobalElementId>,
+    _inspector_id: Option<&InspectorElementId>,
+    bounds: Bounds<Pixels>,
+    _request_layout: &mut Self::RequestLayoutState,
+    hitbox: &mut Self::PrepaintState,
+    window: &mut Window,
+    _cx: &mut App,
+  ) {
+    let metrics = self.metrics(bounds.size.height);
+
+    window.paint_quad(fill(bounds, self.colors.track_color));
+
+    for marker in &self.markers {
+      let (top, height) = metrics.marker_span(&marker.range, self.line_count);
+      let marker_bounds = Bounds::new(
+        point(bounds.origin.x, bounds.origin.y + px(top)),
+        size(bounds.size.width, px(height)),
+      );
+      window.paint_quad(fill(marker_bounds, self.marker_color(&marker.kind)));
+    }
+
+    if metrics.max_scroll() > 0.0 {
+      let thumb_bounds = Bounds::new(
+        point(
+          bounds.origin.x,
+          bounds.origin.y + px(metrics.thumb_top(scroll_top(&self.scroll_handle))),
+        ),
+        size(bounds.size.width, px(metrics.thumb_height())),
+      );
+      window.paint_quad(fill(thumb_bounds, self.colors.thumb_color));
+    }
+
+    let track_top = bounds.origin.y;
+
+    window.on_mouse_event({
+      let hitbox = hitbox.clone();
+      let state = self.state.clone();
+      let scroll_handle = self.scroll_handle.clone();
+      move |event: &MouseDownEvent, phase, window, cx| {
+        if phase != DispatchPhase::Bubble
+          || event.button != MouseButton::Left
+          || !hitbox.is_hovered(window)
+        {
+          return;
+        }
+
+        let y = f32::from(event.position.y - track_top);
+        let thumb_top = metrics.thumb_top(scroll_top(&scroll_handle));
+        let thumb_height = metrics.thumb_height();
+
+        let drag_offset = if y >= thumb_top && y <= thumb_top + thumb_height {
+          y - thumb_top
+        } else {
+          // Click-to-jump: center the thumb on the clicked position
+          let drag_offset = thumb_height / 2.0;
+          set_scroll_top(
+            &scroll_handle,
+            metrics.scroll_top_for_thumb(y - drag_offset),
+          );
+          window.refresh();
+          drag_offset
+        };
+
+        state.lock().unwrap().drag_offset = Some(drag_offset);
+        cx.stop_propagation();
+      }
+    });
+
+    window.on_mouse_event({
+      let state = self.state.clone();
+      let scroll_handle = self.scroll_handle.clone();
+      move |event: &MouseMoveEvent, phase, window, cx| {
+        if phase != DispatchPhase::Bubble {
+          return;
+        }
+
+        let Some(drag_offset) = state.lock().unwrap().drag_offset else {
+          return;
+        };
+
+        if event.pressed_button != Some(MouseButton::Left) {
+          state.lock().unwrap().drag_offset = None;
+          return;
+        }
+
+        let y = f32::from(event.position.y - track_top);
+        set_scroll_top(
+          &scroll_handle,
+          metrics.scroll_top_for_thumb(y - drag_offset),
+        );
+        window.refresh();
+        cx.stop_propagation();
+      }
+    });
+
+    window.on_mouse_event({
+      let state = self.state.clone();
+      move |_event: &MouseUpEvent, phase, _window, _cx| {
+        if phase == DispatchPhase::Bubble {
+          state.lock().unwrap().drag_offset = None;
+        }
+      }
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::display_rows::CollapsedRegion;
+
+  fn diff_line(kind: DiffLineKind) -> DiffLine {
+    DiffLine {
+      line_number: 1,
+      kind,
+      content: String::new(),
+      char_changes: vec![],
+      is_first_in_group: false,
+      pair: None,
+      counterpart: None,
+    }
+  }
+
+  #[test]
+  fn test_markers_group_consecutive_lines() {
+    let lines = vec![
+      diff_line(DiffLineKind::Unchanged),
+      diff_line(DiffLineKind::Added),
+      diff_line(DiffLineKind::Added),
+      diff_line(DiffLineKind::Removed),
+      diff_line(DiffLineKind::Unchanged),
+      diff_line(DiffLineKind::Modified),
+      diff_line(DiffLineKind::Modified),
+    ];
+
+    let markers = ScrollbarMarker::from_diff_lines(&lines);
+    assert_eq!(
+      markers,
+      vec![
+        ScrollbarMarker {
+          range: 1..3,
+          kind: DiffLineKind::Added
+        },
+        ScrollbarMarker {
+          range: 3..4,
+          kind: DiffLineKind::Removed
+        },
+        ScrollbarMarker {
+          range: 5..7,
+          kind: DiffLineKind::Modified
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_markers_empty_when_unchanged() {
+    let lines = vec![diff_line(DiffLineKind::Unchanged); 3];
+    assert!(ScrollbarMarker::from_diff_lines(&lines).is_empty());
+  }
+
+  #[test]
+  fn test_markers_from_display_rows() {
+    let rows = vec![
+      DisplayRow::Collapsed(CollapsedRegion {
+        rows: 0..10,
+        start_line: 0,
+      }),
+      DisplayRow::Line {
+        row: 10,
+        line: diff_line(DiffLineKind::Added),
+      },
+    ];
+
+    assert_eq!(
+      ScrollbarMarker::from_display_rows(&rows),
+      vec![ScrollbarMarker {
+        range: 1..2,
+        kind: DiffLineKind::Added
+      }]
+    );
+  }
+
+  #[test]
+  fn test_thumb_fills_track_when_content_fits() {
+    let metrics = ScrollbarMetrics {
+      content_height: 200.0,
+      viewport_height: 400.0,
+      track_height: 400.0,
+    };
+    assert_eq!(metrics.max_scroll(), 0.0);
+    assert_eq!(metrics.thumb_height(), 400.0);
+    assert_eq!(metrics.thumb_top(50.0), 0.0);
+    assert_eq!(metrics.scroll_top_for_thumb(100.0), 0.0);
+  }
+
+  #[test]
+  fn test_thumb_position_round_trip() {
+    let metrics = ScrollbarMetrics {
+      content_height: 2000.0,
+      viewport_height: 400.0,
+      track_height: 400.0,
+    };
+    assert_eq!(metrics.thumb_height(), 80.0);
+    assert_eq!(metrics.thumb_top(0.0), 0.0);
+    assert_eq!(metrics.thumb_top(metrics.max_scroll()), 320.0);
+
+    let thumb_top = metrics.thumb_top(800.0);
+    assert_eq!(metrics.scroll_top_for_thumb(thumb_top), 800.0);
+  }
+
+  #[test]
+  fn test_scroll_top_for_thumb_clamps() {
+    let metrics = ScrollbarMetrics {
+      content_height: 2000.0,
+      viewport_height: 400.0,
+      track_height: 400.0,
+    };
+    assert_eq!(metrics.scroll_top_for_thumb(-50.0), 0.0);
+    assert_eq!(metrics.scroll_top_for_thumb(1000.0), metrics.max_scroll());
+  }
+
+  #[test]
+  fn test_thumb_has_minimum_height() {
+    let metrics = ScrollbarMetrics {
+      content_height: 1_000_000.0,
+      viewport_height: 400.0,
+      track_height: 400.0,
+    };
+    assert_eq!(metrics.thumb_height(), MIN_THUMB_HEIGHT);
+  }
+
+  #[test]
+  fn test_marker_span_is_proportional() {
+    let metrics = ScrollbarMetrics {
+      content_height: 2000.0,
+      viewport_height: 400.0,
+      track_height: 400.0,
+    };
+    assert_eq!(metrics.marker_span(&(50..60), 100), (200.0, 40.0));
+    // Single lines in long files still get a visible mark
+    assert_eq!(
+      metrics.marker_span(&(0..1), 10_000),
+      (0.0, MIN_MARKER_HEIGHT)
+    );
+  }
+}
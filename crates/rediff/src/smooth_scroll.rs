@@ -0,0 +1,86 @@
+use crate::config::ScrollEasing;
+use std::time::{Duration, Instant};
+
+/// Vertical scroll moving to a target over time, stepped on every animation frame
+///
+/// Offsets are how far the rows are scrolled up, positive like `Viewport::scroll_top`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScrollAnimation {
+  from: f32,
+  to: f32,
+  start: Instant,
+  duration: Duration,
+  easing: ScrollEasing,
+  last: f32, // Offset set by the last step
+}
+
+impl ScrollAnimation {
+  pub fn new(from: f32, to: f32, start: Instant, duration: Duration, easing: ScrollEasing) -> Self {
+    Self {
+      from,
+      to,
+      start,
+      duration,
+      easing,
+      last: from,
+    }
+  }
+
+  /// Offset at `now`, kept to tell whether something else scrolled before the next step
+  pub fn step(&mut self, now: Instant) -> f32 {
+    let t = if self.duration.is_zero() {
+      1.0
+    } else {
+      now.duration_since(self.start).as_secs_f32() / self.duration.as_secs_f32()
+    };
+    self.last = self.from + (self.to - self.from) * self.easing.apply(t);
+    self.last
+  }
+
+  pub fn is_done(&self, now: Instant) -> bool {
+    now.duration_since(self.start) >= self.duration
+  }
+
+  /// Whether the offset moved since the last step, like when the scrollbar was dragged
+  pub fn is_interrupted(&self, scroll_top: f32) -> bool {
+    (scroll_top - self.last).abs() > 0.5
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_easing() {
+    for easing in [
+      ScrollEasing::Linear,
+      ScrollEasing::EaseOut,
+      ScrollEasing::EaseInOut,
+    ] {
+      assert_eq!(easing.apply(0.0), 0.0);
+      assert_eq!(easing.apply(1.0), 1.0);
+      assert_eq!(easing.apply(2.0), 1.0);
+    }
+    assert_eq!(ScrollEasing::Linear.apply(0.25), 0.25);
+    assert!(ScrollEasing::EaseOut.apply(0.25) > 0.25);
+    assert!(ScrollEasing::EaseInOut.apply(0.25) < 0.25);
+    assert_eq!(ScrollEasing::EaseInOut.apply(0.5), 0.5);
+  }
+
+  #[test]
+  fn test_step() {
+    let start = Instant::now();
+    let duration = Duration::from_millis(200);
+    let mut animation = ScrollAnimation::new(100.0, 300.0, start, duration, ScrollEasing::Linear);
+
+    assert_eq!(animation.step(start), 100.0);
+    assert_eq!(animation.step(start + duration / 2), 200.0);
+    assert!(!animation.is_done(start + duration / 2));
+    assert!(!animation.is_interrupted(200.0));
+    assert!(animation.is_interrupted(250.0));
+
+    assert_eq!(animation.step(start + duration * 2), 300.0);
+    assert!(animation.is_done(start + duration));
+  }
+}
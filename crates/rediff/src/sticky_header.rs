@@ -0,0 +1,126 @@
+use crate::display_rows::DisplayRow;
+use rediff_core::{DiffHunk, DiffLine, DiffLineKind};
+
+/// Header pinned at the top of the viewport, describing the hunk scrolled past
+#[derive(Debug, Clone, PartialEq)]
+pub struct StickyHeader {
+  pub hunk: DiffHunk,
+  pub text: String, // Like "@@ -10,4 +10,6 @@ fn main() {"
+}
+
+/// Diff row shown at the top of the viewport, `top_row` being a display row index
+pub fn top_diff_row(rows: &[DisplayRow], top_row: usize) -> Option<usize> {
+  match rows.get(top_row)? {
+    DisplayRow::Line { row, .. } => Some(*row),
+    DisplayRow::Collapsed(region) => Some(region.rows.start),
+    // Threads and previews belong to the line above them
+    DisplayRow::Thread(_) | DisplayRow::Original(_) => top_diff_row(rows, top_row.checked_sub(1)?),
+  }
+}
+
+/// Header of the last hunk starting at or above `top_diff_row`, None before the first hunk
+pub fn sticky_header(
+  diff_lines: &[DiffLine],
+  hunks: &[DiffHunk],
+  top_diff_row: usize,
+) -> Option<StickyHeader> {
+  let hunk = hunks
+    .iter()
+    .rev()
+    .find(|hunk| hunk.rows.start <= top_diff_row)?;
+
+  let before = &diff_lines[..hunk.rows.start];
+  let inside = &diff_lines[hunk.rows.clone()];
+  let old_start = before.iter().filter(|line| is_old_line(line)).count() + 1;
+  let new_start = before.iter().filter(|line| line.line_number > 0).count() + 1;
+  let old_len = inside.iter().filter(|line| is_old_line(line)).count();
+  let new_len = inside.iter().filter(|line| line.line_number > 0).count();
+
+  let mut text = format!(
+    "@@ -{},{} +{},{} @@",
+    old_start, old_len, new_start, new_len
+  );
+  if let Some(context) = hunk_context(before) {
+    text.push(' ');
+    text.push_str(context);
+  }
+
+  Some(StickyHeader {
+    hunk: hunk.clone(),
+    text,
+  })
+}
+
+/// Removed lines, and the removed half of modified and moved lines, only exist in the original
+fn is_old_line(line: &DiffLine) -> bool {
+  match line.kind {
+    DiffLineKind::Added => false,
+    DiffLineKind::Modified | DiffLineKind::Moved => line.line_number == 0,
+    DiffLineKind::Unchanged | DiffLineKind::Removed => true,
+  }
+}
+
+/// Closest line above the hunk that starts a definition, using git's default heuristic:
+/// the line starts with a letter, `_` or `$`
+fn hunk_context(before: &[DiffLine]) -> Option<&str> {
+  before
+    .iter()
+    .rev()
+    .filter(|line| line.line_number > 0)
+    .map(|line| line.content.trim_end())
+    .find(|content| {
+      content
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::display_rows::CollapsedRegion;
+  use rediff_core::Differ;
+
+  fn diff(original: &str, modified: &str) -> Vec<DiffLine> {
+    Differ::new(original.to_string()).compute_diff(modified)
+  }
+
+  #[test]
+  fn test_no_header_before_first_hunk() {
+    let lines = diff("a\nb\nc\n", "a\nb\nC\n");
+    let hunks = Differ::compute_hunks(&lines);
+    assert!(sticky_header(&lines, &hunks, 1).is_none());
+  }
+
+  #[test]
+  fn test_header_of_hunk_scrolled_past() {
+    let original = "fn main() {\n  a();\n  b();\n}\n";
+    let modified = "fn main() {\n  a();\n  x();\n  y();\n}\n";
+    let lines = diff(original, modified);
+    let hunks = Differ::compute_hunks(&lines);
+
+    let header = sticky_header(&lines, &hunks, lines.len() - 1).unwrap();
+    assert_eq!(header.hunk, hunks[0]);
+    assert_eq!(header.text, "@@ -3,1 +3,2 @@ fn main() {");
+  }
+
+  #[test]
+  fn test_header_without_context() {
+    let lines = diff("  a\n  b\n", "  a\n  c\n");
+    let hunks = Differ::compute_hunks(&lines);
+
+    let header = sticky_header(&lines, &hunks, 1).unwrap();
+    assert_eq!(header.text, "@@ -2,1 +2,1 @@");
+  }
+
+  #[test]
+  fn test_top_diff_row() {
+    let rows = vec![DisplayRow::Collapsed(CollapsedRegion {
+      rows: 0..5,
+      start_line: 0,
+    })];
+    assert_eq!(top_diff_row(&rows, 0), Some(0));
+    assert_eq!(top_diff_row(&rows, 1), None);
+  }
+}
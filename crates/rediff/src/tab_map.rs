@@ -0,0 +1,324 @@
+use gpui::{Pixels, ShapedLine};
+use std::ops::Range;
+
+/// Maps line indices to display indices once tabs are expanded to spaces and inlay hints are
+/// inserted
+///
+/// Tabs expand up to the next multiple of `tab_size` so that columns stay aligned
+///
+/// Line indices are chars, like buffer columns and diff char ranges. Display indices are bytes
+/// of the expanded text, like the indices of the `ShapedLine` it's rendered with
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TabMap {
+  char_offsets: Vec<usize>, // Byte offset of each char of the line, then the line length
+  tabs: Vec<(usize, usize)>, // (index of the tab in the line, number of spaces it expands to)
+  inlays: Vec<(usize, String)>, // (index of the char the text shows before, text), sorted by index
+}
+
+impl TabMap {
+  pub fn new(text: &str, tab_size: usize) -> Self {
+    let tab_size = tab_size.max(1);
+    let mut tabs = Vec::new();
+    let mut column = 0;
+
+    for (index, c) in text.chars().enumerate() {
+      if c == '\t' {
+        let width = tab_size - column % tab_size;
+        tabs.push((index, width));
+        column += width;
+      } else {
+        column += 1;
+      }
+    }
+
+    let char_offsets = text
+      .char_indices()
+      .map(|(offset, _)| offset)
+      .chain([text.len()])
+      .collect();
+
+    Self {
+      char_offsets,
+      tabs,
+      inlays: Vec::new(),
+    }
+  }
+
+  /// Inserts virtual text before the given indices, an index past the end appends it to the line
+  ///
+  /// Tab stops ignore inlays, so that columns stay aligned with and without them
+  pub fn with_inlays(mut self, inlays: &[(usize, String)]) -> Self {
+    self.inlays = inlays.to_vec();
+    self.inlays.sort_by_key(|(index, _)| *index);
+    self
+  }
+
+  pub fn has_tabs(&self) -> bool {
+    !self.tabs.is_empty()
+  }
+
+  /// Width of the line in columns once tabs are expanded, inlays aside
+  pub fn columns(&self) -> usize {
+    let tab_extra: usize = self.tabs.iter().map(|(_, width)| width - 1).sum();
+    self.char_offsets.len() - 1 + tab_extra
+  }
+
+  pub fn has_inlays(&self) -> bool {
+    !self.inlays.is_empty()
+  }
+
+  /// Returns the text with every tab replaced by spaces and the inlays inserted
+  pub fn expand(&self, text: &str) -> String {
+    if !self.has_tabs() && !self.has_inlays() {
+      return text.to_string();
+    }
+
+    let inlay_len: usize = self.inlays.iter().map(|(_, inlay)| inlay.len()).sum();
+    let mut expanded = String::with_capacity(text.len() + self.tabs.len() * 4 + inlay_len);
+    let mut tabs = self.tabs.iter().peekable();
+    let mut inlays = self.inlays.iter().peekable();
+
+    for (index, c) in text.chars().enumerate() {
+      while let Some((_, inlay)) = inlays.next_if(|(inlay_index, _)| *inlay_index <= index) {
+        expanded.push_str(inlay);
+      }
+      match tabs.peek() {
+        Some(&&(tab_index, width)) if tab_index == index => {
+          expanded.extend(std::iter::repeat_n(' ', width));
+          tabs.next();
+        }
+        _ => expanded.push(c),
+      }
+    }
+    for (_, inlay) in inlays {
+      expanded.push_str(inlay);
+    }
+
+    expanded
+  }
+
+  /// Display ranges of the inlays in the expanded text, to paint them apart from the line
+  pub fn inlay_ranges(&self) -> Vec<Range<usize>> {
+    self
+      .inlays
+      .iter()
+      .map(|(index, inlay)| {
+        let start = self.to_display(*index);
+        start..start + inlay.len()
+      })
+      .collect()
+  }
+
+  /// Converts an index in the line to an index in the expanded text
+  ///
+  /// An index with an inlay maps to the start of the inlay, the cursor shows in front of it
+  pub fn to_display(&self, index: usize) -> usize {
+    let tab_extra: usize = self
+      .tabs
+      .iter()
+      .take_while(|(tab_index, _)| *tab_index < index)
+      .map(|(_, width)| width - 1)
+      .sum();
+    let inlay_extra: usize = self
+      .inlays
+      .iter()
+      .take_while(|(inlay_index, _)| *inlay_index < index)
+      .map(|(_, inlay)| inlay.len())
+      .sum();
+    self.byte_offset(index) + tab_extra + inlay_extra
+  }
+
+  /// Converts an index in the expanded text back to the line, snapping to the closest tab edge
+  ///
+  /// Indices inside an inlay land on the index it shows before, inlays can't be stepped into
+  pub fn to_buffer(&self, display_index: usize) -> usize {
+    let mut extra = 0;
+    let mut tabs = self.tabs.iter().peekable();
+    let mut inlays = self.inlays.iter().peekable();
+
+    loop {
+      // An inlay shows before a tab at the same index
+      let next_inlay = inlays.peek().map(|(index, _)| *index);
+      let next_tab = tabs.peek().map(|(index, _)| *index);
+      match (next_inlay, next_tab) {
+        (Some(inlay_index), tab_index) if tab_index.is_none_or(|tab| inlay_index <= tab) => {
+          let (_, inlay) = inlays.next().unwrap();
+          let display_start = self.byte_offset(inlay_index) + extra;
+          if display_index <= display_start {
+            break;
+          }
+          if display_index <= display_start + inlay.len() {
+            return inlay_index;
+          }
+          extra += inlay.len();
+        }
+        (_, Some(tab_index)) => {
+          let (_, width) = *tabs.next().unwrap();
+          let display_start = self.byte_offset(tab_index) + extra;
+          if display_index <= display_start {
+            break;
+          }
+          if display_index < display_start + width {
+            return if display_index - display_start > width / 2 {
+              tab_index + 1
+            } else {
+              tab_index
+            };
+          }
+          extra += width - 1;
+        }
+        (_, None) => break,
+      }
+    }
+
+    self.char_index(display_index - extra)
+  }
+
+  /// Position of the line index `index` in the expanded text shaped as `shaped_line`
+  pub fn x_for_index(&self, shaped_line: &ShapedLine, index: usize) -> Pixels {
+    shaped_line.x_for_index(self.to_display(index))
+  }
+
+  /// Line index of the char under `x` in the expanded text shaped as `shaped_line`
+  pub fn index_for_x(&self, shaped_line: &ShapedLine, x: Pixels) -> Option<usize> {
+    Some(self.to_buffer(shaped_line.index_for_x(x)?))
+  }
+
+  /// Line index of the char boundary closest to `x`, past the end lands on the end of the line
+  pub fn closest_index_for_x(&self, shaped_line: &ShapedLine, x: Pixels) -> usize {
+    self.to_buffer(shaped_line.closest_index_for_x(x))
+  }
+
+  /// Byte offset of a char of the line, indices past the end count one byte per char like
+  /// the cells shown after the line
+  fn byte_offset(&self, index: usize) -> usize {
+    let len = self.char_offsets.len().max(1) - 1;
+    match self.char_offsets.get(index) {
+      Some(offset) => *offset,
+      None => self.char_offsets.last().copied().unwrap_or(0) + index - len,
+    }
+  }
+
+  /// Char of the line at a byte offset, an offset inside a char lands on that char
+  fn char_index(&self, offset: usize) -> usize {
+    let len = self.char_offsets.len().max(1) - 1;
+    let line_len = self.char_offsets.last().copied().unwrap_or(0);
+    if offset >= line_len {
+      return len + offset - line_len;
+    }
+    self
+      .char_offsets
+      .partition_point(|char_offset| *char_offset <= offset)
+      - 1
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_no_tabs() {
+    let map = TabMap::new("hello", 4);
+    assert!(!map.has_tabs());
+    assert_eq!(map.expand("hello"), "hello");
+    assert_eq!(map.to_display(3), 3);
+    assert_eq!(map.to_buffer(3), 3);
+  }
+
+  #[test]
+  fn test_expand_aligns_to_tab_stops() {
+    let text = "\ta\tbc";
+    let map = TabMap::new(text, 4);
+    assert_eq!(map.expand(text), "    a   bc");
+
+    let text = "ab\tc";
+    let map = TabMap::new(text, 4);
+    assert_eq!(map.expand(text), "ab  c");
+  }
+
+  #[test]
+  fn test_columns() {
+    assert_eq!(TabMap::new("", 4).columns(), 0);
+    assert_eq!(TabMap::new("héllo", 4).columns(), 5);
+    assert_eq!(TabMap::new("\ta\tbc", 4).columns(), 10);
+  }
+
+  #[test]
+  fn test_to_display() {
+    let map = TabMap::new("\ta\tb", 2);
+    assert_eq!(map.to_display(0), 0);
+    assert_eq!(map.to_display(1), 2); // "a"
+    assert_eq!(map.to_display(2), 3); // second tab, one column wide
+    assert_eq!(map.to_display(3), 4); // "b"
+  }
+
+  #[test]
+  fn test_to_buffer_roundtrip() {
+    let text = "\tfoo\tbar";
+    let map = TabMap::new(text, 4);
+    for index in 0..=text.len() {
+      assert_eq!(map.to_buffer(map.to_display(index)), index);
+    }
+  }
+
+  #[test]
+  fn test_to_buffer_snaps_inside_tab() {
+    let map = TabMap::new("\tx", 4);
+    assert_eq!(map.to_buffer(1), 0);
+    assert_eq!(map.to_buffer(2), 0);
+    assert_eq!(map.to_buffer(3), 1);
+    assert_eq!(map.to_buffer(4), 1);
+  }
+
+  #[test]
+  fn test_inlays() {
+    let text = "f(a,\tb)";
+    let map = TabMap::new(text, 4).with_inlays(&[(5, "y: ".to_string()), (2, "x: ".to_string())]);
+    assert!(map.has_inlays());
+    assert_eq!(map.expand(text), "f(x: a,    y: b)");
+    assert_eq!(map.inlay_ranges(), vec![2..5, 11..14]);
+
+    // The cursor shows in front of an inlay, the text after it moves right
+    assert_eq!(map.to_display(2), 2);
+    assert_eq!(map.to_display(3), 6);
+    assert_eq!(map.to_display(5), 11);
+    assert_eq!(map.to_display(6), 15);
+
+    // Clicks inside an inlay land on the index it shows before
+    assert_eq!(map.to_buffer(3), 2);
+    assert_eq!(map.to_buffer(5), 2);
+    assert_eq!(map.to_buffer(14), 5);
+    for index in 0..=text.len() {
+      assert_eq!(map.to_buffer(map.to_display(index)), index);
+    }
+  }
+
+  #[test]
+  fn test_multibyte_chars() {
+    let text = "é\t😀x";
+    let map = TabMap::new(text, 4);
+    assert_eq!(map.expand(text), "é   😀x");
+
+    // Line indices are chars, display indices are bytes of the expanded text
+    assert_eq!(map.to_display(1), 2); // tab
+    assert_eq!(map.to_display(2), 5); // "😀"
+    assert_eq!(map.to_display(3), 9); // "x"
+    assert_eq!(map.to_display(4), 10);
+    for index in 0..=text.chars().count() {
+      assert_eq!(map.to_buffer(map.to_display(index)), index);
+    }
+
+    let map = TabMap::new("ü", 4).with_inlays(&[(1, "ö".to_string())]);
+    assert_eq!(map.inlay_ranges(), vec![2..4]);
+    assert_eq!(map.to_buffer(4), 1);
+  }
+
+  #[test]
+  fn test_inlay_at_line_end() {
+    let map = TabMap::new("ab", 4).with_inlays(&[(2, " = 1".to_string())]);
+    assert_eq!(map.expand("ab"), "ab = 1");
+    assert_eq!(map.to_display(2), 2);
+    assert_eq!(map.to_buffer(6), 2);
+  }
+}
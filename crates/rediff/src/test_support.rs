@@ -0,0 +1,182 @@
+//! Headless harness driving a `DiffEditor` in a test window, with simulated keys and mouse
+
+use crate::config::EditorConfig;
+use crate::diff_editor::DiffEditor;
+use crate::display_rows::DisplayRow;
+use crate::keymap::Keymap;
+use gpui::{
+  Entity, Focusable, Modifiers, MouseButton, Pixels, Point, TestAppContext, VisualTestContext,
+  point, px,
+};
+use rediff_core::DiffLineKind;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Editor over the diff of two texts in a window that's never shown
+pub struct EditorTestContext<'a> {
+  pub cx: &'a mut VisualTestContext,
+  pub editor: Entity<DiffEditor>,
+}
+
+impl<'a> EditorTestContext<'a> {
+  /// Editable `modified` diffed against `original`, focused so that keystrokes reach it
+  pub fn new(cx: &'a mut TestAppContext, original: &str, modified: &str) -> Self {
+    Self::with_config(cx, original, modified, EditorConfig::default())
+  }
+
+  pub fn with_config(
+    cx: &'a mut TestAppContext,
+    original: &str,
+    modified: &str,
+    config: EditorConfig,
+  ) -> Self {
+    cx.update(|cx| DiffEditor::register(cx, &Keymap::default()));
+    // The blink timer would keep the executor busy
+    let config = EditorConfig {
+      cursor_blink_interval: None,
+      ..config
+    };
+    let read_only = config.read_only;
+
+    let (editor, cx) = cx.add_window_view(|_window, cx| {
+      let mut editor = DiffEditor::from_text(
+        PathBuf::from("test.txt"),
+        modified,
+        original.to_string(),
+        config,
+        cx,
+      );
+      editor.set_read_only(read_only, cx);
+      editor
+    });
+    editor.update_in(cx, |editor, window, cx| {
+      editor.focus_handle(cx).focus(window);
+    });
+    cx.run_until_parked();
+    Self { cx, editor }
+  }
+
+  /// Keystrokes separated by spaces, like "shift-down f7"
+  pub fn simulate_keystrokes(&mut self, keystrokes: &str) {
+    self.cx.simulate_keystrokes(keystrokes);
+    self.cx.run_until_parked();
+  }
+
+  /// Types lowercase text key by key, the editor takes text from key down events
+  pub fn type_text(&mut self, text: &str) {
+    for c in text.chars() {
+      let key = match c {
+        ' ' => "space".to_string(),
+        c => c.to_string(),
+      };
+      self.cx.simulate_keystrokes(&key);
+    }
+    self.cx.run_until_parked();
+  }
+
+  /// Window position of `text_x` pixels into the text of display row `row`, halfway down it
+  pub fn row_position(&mut self, row: usize, text_x: Pixels) -> Point<Pixels> {
+    self.editor.update(self.cx, |editor, _cx| {
+      let viewport = editor.viewport();
+      viewport.position_of(row, text_x) + point(px(0.0), viewport.line_height / 2.0)
+    })
+  }
+
+  /// Clicks the start of the text of display row `row`
+  pub fn click_row(&mut self, row: usize, modifiers: Modifiers) {
+    let position = self.row_position(row, px(0.0));
+    self.cx.simulate_click(position, modifiers);
+    self.cx.run_until_parked();
+  }
+
+  /// Drags from the start of the text of row `from` to the start of row `to`
+  pub fn drag_rows(&mut self, from: usize, to: usize) {
+    let start = self.row_position(from, px(0.0));
+    let end = self.row_position(to, px(0.0));
+    self
+      .cx
+      .simulate_mouse_down(start, MouseButton::Left, Modifiers::none());
+    self
+      .cx
+      .simulate_mouse_move(end, MouseButton::Left, Modifiers::none());
+    self
+      .cx
+      .simulate_mouse_up(end, MouseButton::Left, Modifiers::none());
+    self.cx.run_until_parked();
+  }
+
+  pub fn text(&mut self) -> String {
+    self
+      .editor
+      .update(self.cx, |editor, _cx| editor.editor().buffer.as_str())
+  }
+
+  /// Line and column of the cursor, 0-based
+  pub fn cursor(&mut self) -> (usize, usize) {
+    self.editor.update(self.cx, |editor, _cx| {
+      let editor = editor.editor();
+      editor.buffer.char_to_line_col(editor.cursor.index)
+    })
+  }
+
+  pub fn selection(&mut self) -> Option<Range<usize>> {
+    self
+      .editor
+      .update(self.cx, |editor, _cx| editor.editor().selection_range())
+  }
+
+  pub fn selected_text(&mut self) -> Option<String> {
+    self.editor.update(self.cx, |editor, _cx| {
+      let editor = editor.editor();
+      let selection = editor.selection_range()?;
+      Some(editor.buffer.slice(selection))
+    })
+  }
+
+  /// Rows drawn by the last render, see `describe_rows`
+  pub fn rows(&mut self) -> String {
+    self.editor.update(self.cx, |editor, _cx| {
+      describe_rows(editor.displayed_rows())
+    })
+  }
+}
+
+/// One line per display row, for golden tests of the row model
+///
+/// Lines show the kind of change like a unified diff, "-" for removed lines and the original
+/// half of modified ones, "+" for added lines and the new half, ">" for moved ones, then the
+/// buffer line number and the content, "~" for previewed original lines
+pub fn describe_rows(rows: &[DisplayRow]) -> String {
+  rows
+    .iter()
+    .map(|row| match row {
+      DisplayRow::Line { line, .. } => {
+        let marker = match line.kind {
+          DiffLineKind::Unchanged => ' ',
+          DiffLineKind::Added => '+',
+          DiffLineKind::Removed => '-',
+          DiffLineKind::Modified if line.line_number == 0 => '-',
+          DiffLineKind::Modified => '+',
+          DiffLineKind::Moved => '>',
+        };
+        let number = match line.line_number {
+          0 => String::new(),
+          number => number.to_string(),
+        };
+        format!(
+          "{}{:>4} {}",
+          marker,
+          number,
+          line.content.trim_end_matches('\n')
+        )
+      }
+      DisplayRow::Collapsed(region) => format!("… {} unchanged lines", region.line_count()),
+      DisplayRow::Thread(_) => "# comment thread".to_string(),
+      DisplayRow::Original(original) => match &original.content {
+        Some(content) => format!("~      {}", content),
+        None => "~      (added)".to_string(),
+      },
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
@@ -0,0 +1,191 @@
+use crate::config::{EditorConfig, EditorTheme};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Syntax of a theme file, picked from its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeFormat {
+  Json,
+  Toml,
+}
+
+impl ThemeFormat {
+  pub fn from_path(path: &Path) -> Option<Self> {
+    match path.extension()?.to_str()?.to_lowercase().as_str() {
+      "json" => Some(Self::Json),
+      "toml" => Some(Self::Toml),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub enum ThemeError {
+  Io(io::Error),
+  UnknownFormat(PathBuf), // Neither a .json nor a .toml file
+  Parse(String),          // Invalid syntax or missing colors
+}
+
+impl fmt::Display for ThemeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Io(e) => write!(f, "can't read theme: {}", e),
+      Self::UnknownFormat(path) => write!(f, "not a JSON or TOML theme: {}", path.display()),
+      Self::Parse(message) => write!(f, "invalid theme: {}", message),
+    }
+  }
+}
+
+impl std::error::Error for ThemeError {}
+
+impl From<io::Error> for ThemeError {
+  fn from(e: io::Error) -> Self {
+    Self::Io(e)
+  }
+}
+
+impl EditorTheme {
+  /// Parses a theme, colors are hex strings like "#1e1e1eff"
+  pub fn parse(text: &str, format: ThemeFormat) -> Result<Self, ThemeError> {
+    match format {
+      ThemeFormat::Json => serde_json::from_str(text).map_err(|e| ThemeError::Parse(e.to_string())),
+      ThemeFormat::Toml => toml::from_str(text).map_err(|e| ThemeError::Parse(e.to_string())),
+    }
+  }
+
+  pub fn from_file(path: &Path) -> Result<Self, ThemeError> {
+    let format =
+      ThemeFormat::from_path(path).ok_or_else(|| ThemeError::UnknownFormat(path.to_path_buf()))?;
+    Self::parse(&std::fs::read_to_string(path)?, format)
+  }
+
+  /// Text of a theme file, a starting point for custom themes
+  pub fn to_text(&self, format: ThemeFormat) -> String {
+    match format {
+      ThemeFormat::Json => serde_json::to_string_pretty(self).unwrap_or_default(),
+      ThemeFormat::Toml => toml::to_string_pretty(self).unwrap_or_default(),
+    }
+  }
+}
+
+/// Themes available by name, starting with the built-in "light" and "dark" ones
+#[derive(Debug, Clone)]
+pub struct ThemeRegistry {
+  themes: BTreeMap<String, EditorTheme>,
+}
+
+impl Default for ThemeRegistry {
+  fn default() -> Self {
+    let mut themes = BTreeMap::new();
+    themes.insert("light".to_string(), EditorConfig::default_theme_light());
+    themes.insert("dark".to_string(), EditorConfig::default_theme_dark());
+    Self { themes }
+  }
+}
+
+impl ThemeRegistry {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds a theme, replacing the one with the same name
+  pub fn insert(&mut self, name: impl Into<String>, theme: EditorTheme) {
+    self.themes.insert(name.into(), theme);
+  }
+
+  pub fn get(&self, name: &str) -> Option<&EditorTheme> {
+    self.themes.get(name)
+  }
+
+  /// Names in alphabetical order
+  pub fn names(&self) -> impl Iterator<Item = &str> {
+    self.themes.keys().map(String::as_str)
+  }
+
+  /// Loads a theme file and registers it under the file name without extension, which is returned
+  pub fn load_file(&mut self, path: &Path) -> Result<String, ThemeError> {
+    let theme = EditorTheme::from_file(path)?;
+    let name = path
+      .file_stem()
+      .map(|stem| stem.to_string_lossy().into_owned())
+      .ok_or_else(|| ThemeError::UnknownFormat(path.to_path_buf()))?;
+    self.insert(name.clone(), theme);
+    Ok(name)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use gpui::red;
+
+  #[test]
+  fn test_theme_round_trip() {
+    let theme = EditorConfig::default_theme_dark();
+    for format in [ThemeFormat::Json, ThemeFormat::Toml] {
+      let parsed = EditorTheme::parse(&theme.to_text(format), format).unwrap();
+      // Colors go through 8-bit channels
+      let close = |a: gpui::Hsla, b: gpui::Hsla| {
+        let (a, b) = (a.to_rgb(), b.to_rgb());
+        [a.r - b.r, a.g - b.g, a.b - b.b, a.a - b.a]
+          .iter()
+          .all(|d| d.abs() < 0.01)
+      };
+      assert!(close(parsed.code.text_color, theme.code.text_color));
+      assert!(close(parsed.git.removed.char_highlight_color, red()));
+      assert!(close(
+        parsed.cursor.selection_color,
+        theme.cursor.selection_color
+      ));
+    }
+
+    assert!(matches!(
+      EditorTheme::parse("{}", ThemeFormat::Json),
+      Err(ThemeError::Parse(_))
+    ));
+  }
+
+  #[test]
+  fn test_registry_load_file() {
+    let path = std::env::temp_dir().join("rediff_solarized.toml");
+    let theme = EditorConfig::default_theme_light();
+    std::fs::write(&path, theme.to_text(ThemeFormat::Toml)).unwrap();
+
+    let mut registry = ThemeRegistry::new();
+    assert_eq!(registry.load_file(&path).unwrap(), "rediff_solarized");
+    assert_eq!(
+      registry.names().collect::<Vec<_>>(),
+      vec!["dark", "light", "rediff_solarized"]
+    );
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(
+      registry.load_file(Path::new("theme.yaml")),
+      Err(ThemeError::UnknownFormat(_))
+    ));
+  }
+
+  #[test]
+  fn test_theme_mode_follows_appearance() {
+    use crate::ThemeMode;
+    use gpui::WindowAppearance;
+
+    assert!(ThemeMode::Auto.is_dark(WindowAppearance::VibrantDark));
+    assert!(!ThemeMode::Auto.is_dark(WindowAppearance::Light));
+    assert!(ThemeMode::Dark.is_dark(WindowAppearance::Light));
+    assert!(!ThemeMode::Light.is_dark(WindowAppearance::Dark));
+  }
+
+  #[test]
+  fn test_config_defaults_missing_fields() {
+    let config: EditorConfig =
+      toml::from_str("tab_size = 4\ncursor_blink_interval = 0\nrender_whitespace = \"trailing\"")
+        .unwrap();
+    assert_eq!(config.tab_size, 4);
+    assert_eq!(config.cursor_blink_interval, None);
+    assert_eq!(config.render_whitespace, crate::RenderWhitespace::Trailing);
+    assert_eq!(config.font_size, EditorConfig::default().font_size);
+  }
+}
@@ -0,0 +1,154 @@
+use gpui::{Bounds, Pixels, Point, UniformListScrollHandle, px};
+use std::ops::Range;
+
+/// Maps window positions to rows and text offsets of the editor pane, and back
+///
+/// Shared by mouse hit-testing, cursor autoscroll and hover tooltips, so that they all
+/// agree on where the pane is and how far it's scrolled
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+  pub bounds: Bounds<Pixels>, // Editor pane in window coordinates
+  pub scroll_top: Pixels,     // How far the rows are scrolled up
+  pub scroll_x: Pixels,
+  pub line_height: Pixels,
+  pub padding: Pixels, // Between the left edge of the pane and the text
+}
+
+impl Viewport {
+  /// Viewport from the last layout of the list tracked by `scroll_handle`
+  ///
+  /// The list renders the gutter columns left of the text, `gutter_width` is taken off its
+  /// bounds so that they're the pane's own
+  pub fn from_scroll_handle(
+    scroll_handle: &UniformListScrollHandle,
+    gutter_width: Pixels,
+    scroll_x: Pixels,
+    line_height: Pixels,
+    padding: Pixels,
+  ) -> Self {
+    let base_handle = scroll_handle.0.borrow().base_handle.clone();
+    let mut bounds = base_handle.bounds();
+    let gutter_width = gutter_width.min(bounds.size.width);
+    bounds.origin.x += gutter_width;
+    bounds.size.width -= gutter_width;
+    Self {
+      bounds,
+      scroll_top: -base_handle.offset().y,
+      scroll_x,
+      line_height,
+      padding,
+    }
+  }
+
+  pub fn contains(&self, position: Point<Pixels>) -> bool {
+    self.bounds.contains(&position)
+  }
+
+  /// Display row under a window position, rows above the pane clamp to the first one
+  pub fn row_at(&self, position: Point<Pixels>) -> usize {
+    if self.line_height <= px(0.0) {
+      return 0;
+    }
+    let content_y = position.y - self.bounds.origin.y + self.scroll_top;
+    (content_y / self.line_height).floor().max(0.0) as usize
+  }
+
+  /// Horizontal offset of a window position from the start of the line text
+  pub fn text_x(&self, x: Pixels) -> Pixels {
+    x - self.bounds.origin.x - self.padding + self.scroll_x
+  }
+
+  /// Window position of a point of the line text in `row`, `text_x` being relative to the text start
+  pub fn position_of(&self, row: usize, text_x: Pixels) -> Point<Pixels> {
+    Point {
+      x: self.bounds.origin.x + self.padding + text_x - self.scroll_x,
+      y: self.bounds.origin.y + self.line_height * row as f32 - self.scroll_top,
+    }
+  }
+
+  /// Width left to the text once the padding on both sides is taken
+  pub fn text_width(&self) -> Pixels {
+    (self.bounds.size.width - self.padding * 2.0).max(px(0.0))
+  }
+
+  /// Rows at least partly visible
+  pub fn visible_rows(&self) -> Range<usize> {
+    if self.line_height <= px(0.0) {
+      return 0..0;
+    }
+    let start = (self.scroll_top / self.line_height).floor().max(0.0) as usize;
+    let end = ((self.scroll_top + self.bounds.size.height) / self.line_height).ceil() as usize;
+    start..end.max(start)
+  }
+
+  /// Scroll position that brings `row` fully into view, None when it already is
+  pub fn scroll_top_to_reveal(&self, row: usize) -> Option<Pixels> {
+    let row_top = self.line_height * row as f32;
+    let row_bottom = row_top + self.line_height;
+
+    if row_top < self.scroll_top {
+      Some(row_top)
+    } else if row_bottom > self.scroll_top + self.bounds.size.height {
+      Some((row_bottom - self.bounds.size.height).max(px(0.0)))
+    } else {
+      None
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use gpui::{point, size};
+
+  // Pane at (200, 30), like after a file panel and a tab bar, scrolled down 5 rows
+  fn viewport() -> Viewport {
+    Viewport {
+      bounds: Bounds::new(point(px(200.0), px(30.0)), size(px(400.0), px(200.0))),
+      scroll_top: px(100.0),
+      scroll_x: px(16.0),
+      line_height: px(20.0),
+      padding: px(8.0),
+    }
+  }
+
+  #[test]
+  fn test_row_at_accounts_for_origin_and_scroll() {
+    let viewport = viewport();
+    assert_eq!(viewport.row_at(point(px(300.0), px(30.0))), 5);
+    assert_eq!(viewport.row_at(point(px(300.0), px(75.0))), 7);
+    // Dragging above the pane
+    assert_eq!(viewport.row_at(point(px(300.0), px(-500.0))), 0);
+  }
+
+  #[test]
+  fn test_text_x_round_trip() {
+    let viewport = viewport();
+    assert_eq!(viewport.text_x(px(208.0)), px(16.0));
+
+    let position = viewport.position_of(7, px(40.0));
+    assert_eq!(position, point(px(232.0), px(70.0)));
+    assert_eq!(viewport.row_at(position), 7);
+    assert_eq!(viewport.text_x(position.x), px(40.0));
+  }
+
+  #[test]
+  fn test_visible_rows() {
+    assert_eq!(viewport().visible_rows(), 5..15);
+    let viewport = Viewport {
+      scroll_top: px(110.0),
+      ..viewport()
+    };
+    assert_eq!(viewport.visible_rows(), 5..16);
+  }
+
+  #[test]
+  fn test_scroll_top_to_reveal() {
+    let viewport = viewport();
+    assert_eq!(viewport.scroll_top_to_reveal(5), None);
+    assert_eq!(viewport.scroll_top_to_reveal(14), None);
+    assert_eq!(viewport.scroll_top_to_reveal(2), Some(px(40.0)));
+    // The row ends up at the bottom edge
+    assert_eq!(viewport.scroll_top_to_reveal(15), Some(px(120.0)));
+  }
+}
@@ -0,0 +1,135 @@
+use crate::loading::modified_time;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+pub const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+pub const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Files of a diff kept in sync with the disk, polled from timers scheduled by the editor
+///
+/// Changes are only reported once the files stopped changing for the debounce delay, so a
+/// file being written by a build isn't read half way through
+#[derive(Debug, Clone)]
+pub struct FileWatcher {
+  files: Vec<WatchedFile>,
+  debounce: Duration,
+  changed_at: Option<Instant>, // Last change not reported yet
+  updated_at: Instant,         // When the diff was last brought up to date
+}
+
+#[derive(Debug, Clone)]
+struct WatchedFile {
+  path: PathBuf,
+  modified: Option<SystemTime>,
+  changed: bool,
+}
+
+impl FileWatcher {
+  pub fn new(paths: impl IntoIterator<Item = PathBuf>, debounce: Duration, now: Instant) -> Self {
+    let files = paths
+      .into_iter()
+      .map(|path| WatchedFile {
+        modified: modified_time(&path),
+        path,
+        changed: false,
+      })
+      .collect();
+    Self {
+      files,
+      debounce,
+      changed_at: None,
+      updated_at: now,
+    }
+  }
+
+  pub fn paths(&self) -> impl Iterator<Item = &Path> {
+    self.files.iter().map(|file| file.path.as_path())
+  }
+
+  /// Files that changed since the last poll that reported any, once they've settled
+  pub fn poll(&mut self, now: Instant) -> Vec<PathBuf> {
+    for file in &mut self.files {
+      let modified = modified_time(&file.path);
+      if modified != file.modified {
+        file.modified = modified;
+        file.changed = true;
+        self.changed_at = Some(now);
+      }
+    }
+
+    match self.changed_at {
+      Some(changed_at) if now.duration_since(changed_at) >= self.debounce => {
+        self.changed_at = None;
+        self.updated_at = now;
+        self
+          .files
+          .iter_mut()
+          .filter(|file| std::mem::take(&mut file.changed))
+          .map(|file| file.path.clone())
+          .collect()
+      }
+      _ => Vec::new(),
+    }
+  }
+
+  /// Like "Updated 5s ago"
+  pub fn updated_label(&self, now: Instant) -> String {
+    let elapsed = now.duration_since(self.updated_at).as_secs();
+    match elapsed {
+      0..5 => "Updated just now".to_string(),
+      5..60 => format!("Updated {}s ago", elapsed),
+      60..3600 => format!("Updated {} min ago", elapsed / 60),
+      _ => format!("Updated {} h ago", elapsed / 3600),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+
+  #[test]
+  fn test_poll_debounces_changes() {
+    let dir = std::env::temp_dir().join("rediff_watch_debounce");
+    fs::create_dir_all(&dir).unwrap();
+    let (old, new) = (dir.join("old.txt"), dir.join("new.txt"));
+    fs::write(&old, "a").unwrap();
+    fs::write(&new, "a").unwrap();
+
+    let start = Instant::now();
+    let mut watcher = FileWatcher::new([old.clone(), new.clone()], WATCH_DEBOUNCE, start);
+    assert!(watcher.poll(start).is_empty());
+
+    // Modification times can be coarse, move them explicitly
+    let later = SystemTime::now() + Duration::from_secs(10);
+    fs::File::options()
+      .write(true)
+      .open(&new)
+      .unwrap()
+      .set_modified(later)
+      .unwrap();
+    assert!(watcher.poll(start).is_empty());
+    assert!(watcher.poll(start + WATCH_DEBOUNCE / 2).is_empty());
+    assert_eq!(watcher.poll(start + WATCH_DEBOUNCE), vec![new.clone()]);
+    // Reported once
+    assert!(watcher.poll(start + WATCH_DEBOUNCE * 2).is_empty());
+
+    fs::remove_dir_all(&dir).ok();
+  }
+
+  #[test]
+  fn test_updated_label() {
+    let start = Instant::now();
+    let watcher = FileWatcher::new([], WATCH_DEBOUNCE, start);
+    assert_eq!(watcher.updated_label(start), "Updated just now");
+    assert_eq!(
+      watcher.updated_label(start + Duration::from_secs(12)),
+      "Updated 12s ago"
+    );
+    assert_eq!(
+      watcher.updated_label(start + Duration::from_secs(150)),
+      "Updated 2 min ago"
+    );
+  }
+}
@@ -0,0 +1,100 @@
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use text::TextBuffer;
+
+const SIZES: [usize; 2] = [10_000, 100_000];
+
+fn buffer(lines: usize) -> TextBuffer {
+  let mut buffer = TextBuffer::new();
+  let text: String = (0..lines)
+    .map(|i| format!("    let value_{i} = compute({i});\n"))
+    .collect();
+  buffer.insert(0, &text);
+  buffer
+}
+
+/// Char indices spread over the buffer, the same on every run
+fn positions(len: usize, count: usize) -> Vec<usize> {
+  let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+  (0..count)
+    .map(|_| {
+      state ^= state << 13;
+      state ^= state >> 7;
+      state ^= state << 17;
+      (state % len as u64) as usize
+    })
+    .collect()
+}
+
+fn bench_edits(c: &mut Criterion) {
+  let mut group = c.benchmark_group("buffer_edits");
+  for lines in SIZES {
+    let base = buffer(lines);
+    let positions = positions(base.len(), 1_000);
+
+    group.bench_function(BenchmarkId::new("insert_char", lines), |b| {
+      b.iter_batched_ref(
+        || base.clone(),
+        |buffer| {
+          for &index in &positions {
+            buffer.insert(index, "x");
+          }
+        },
+        BatchSize::LargeInput,
+      )
+    });
+    group.bench_function(BenchmarkId::new("insert_lines", lines), |b| {
+      b.iter_batched_ref(
+        || base.clone(),
+        |buffer| {
+          for &index in &positions {
+            buffer.insert(index, "first\nsecond\n");
+          }
+        },
+        BatchSize::LargeInput,
+      )
+    });
+    group.bench_function(BenchmarkId::new("delete", lines), |b| {
+      b.iter_batched_ref(
+        || base.clone(),
+        |buffer| {
+          for &index in &positions {
+            buffer.delete(index.min(buffer.len().saturating_sub(8)), 8);
+          }
+        },
+        BatchSize::LargeInput,
+      )
+    });
+  }
+  group.finish();
+}
+
+fn bench_lookups(c: &mut Criterion) {
+  let mut group = c.benchmark_group("buffer_lookups");
+  for lines in SIZES {
+    let buffer = buffer(lines);
+    let positions = positions(buffer.len(), 1_000);
+
+    group.bench_function(BenchmarkId::new("char_to_line_col", lines), |b| {
+      b.iter(|| {
+        for &index in &positions {
+          black_box(buffer.char_to_line_col(index));
+        }
+      })
+    });
+    group.bench_function(BenchmarkId::new("line", lines), |b| {
+      b.iter(|| {
+        for &index in &positions {
+          black_box(buffer.line(index % lines));
+        }
+      })
+    });
+    group.bench_function(BenchmarkId::new("as_str", lines), |b| {
+      b.iter(|| black_box(buffer.as_str()))
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, bench_edits, bench_lookups);
+criterion_main!(benches);
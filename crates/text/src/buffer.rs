@@ -1,31 +1,124 @@
-use ropey::Rope;
+use crate::encoding::{Decoder, Encoding};
+use crate::line_ending::{LineEnding, LineEndingCounts};
+use ropey::{Rope, RopeBuilder};
 use std::fs;
-use std::io;
+use std::io::{self, Read};
+use std::ops::Range;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const LOAD_CHUNK_SIZE: usize = 1 << 20;
+const ENCODING_DETECTION_LEN: u64 = 8 << 10; // Bytes at the start of the file the encoding is detected from
+
+static NEXT_VERSION: AtomicU64 = AtomicU64::new(1); // 0 is left to empty buffers
+
+/// Content of a file read by `FileContent::load`
+#[derive(Debug, Clone)]
+pub enum FileContent {
+  Text(TextBuffer),
+  Binary(Vec<u8>), // Raw bytes, decoding them would only show garbage
+}
+
+impl FileContent {
+  /// Reads a file as text, or as bytes when its first bytes look binary
+  pub fn load(path: &Path, mut on_progress: impl FnMut(LoadProgress)) -> io::Result<Self> {
+    let mut head = Vec::new();
+    fs::File::open(path)?
+      .take(ENCODING_DETECTION_LEN)
+      .read_to_end(&mut head)?;
+    if !Encoding::is_binary(&head) {
+      return TextBuffer::load(path, on_progress).map(Self::Text);
+    }
+
+    let bytes = fs::read(path)?;
+    let total_bytes = bytes.len() as u64;
+    on_progress(LoadProgress {
+      loaded_bytes: total_bytes,
+      total_bytes,
+    });
+    Ok(Self::Binary(bytes))
+  }
+}
+
+/// Bytes read so far by `TextBuffer::load`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadProgress {
+  pub loaded_bytes: u64,
+  pub total_bytes: u64,
+}
+
+impl LoadProgress {
+  /// Between 0 and 1, empty files are done right away
+  pub fn fraction(&self) -> f32 {
+    if self.total_bytes == 0 {
+      return 1.0;
+    }
+    (self.loaded_bytes as f64 / self.total_bytes as f64).min(1.0) as f32
+  }
+}
 
 #[derive(Debug, Clone, Default)]
 pub struct TextBuffer {
   rope: Rope,
+  line_ending: LineEnding,
+  encoding: Encoding,
+  version: u64, // Changes with every edit, unique across buffers
 }
 
 impl TextBuffer {
   pub fn new() -> Self {
-    Self { rope: Rope::new() }
+    Self {
+      rope: Rope::new(),
+      line_ending: LineEnding::default(),
+      encoding: Encoding::default(),
+      version: 0,
+    }
+  }
+
+  /// Identifies the text of the buffer, two buffers with the same version hold the same text
+  pub fn version(&self) -> u64 {
+    self.version
+  }
+
+  /// Encoding detected on load, restored when saving
+  pub fn encoding(&self) -> Encoding {
+    self.encoding
+  }
+
+  pub fn set_encoding(&mut self, encoding: Encoding) {
+    self.encoding = encoding;
+  }
+
+  /// Line ending detected on load, restored when saving
+  pub fn line_ending(&self) -> LineEnding {
+    self.line_ending
+  }
+
+  pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+    self.line_ending = line_ending;
   }
 
   pub fn insert(&mut self, index: usize, content: &str) {
     self.rope.insert(index, content);
+    self.version = next_version();
   }
 
   pub fn delete(&mut self, index: usize, len: usize) {
     let end = (index + len).min(self.rope.len_chars());
     self.rope.remove(index..end);
+    self.version = next_version();
   }
 
   pub fn as_str(&self) -> String {
     self.rope.to_string()
   }
 
+  /// Text of a char range, clamped to the buffer
+  pub fn slice(&self, range: Range<usize>) -> String {
+    let end = range.end.min(self.rope.len_chars());
+    self.rope.slice(range.start.min(end)..end).to_string()
+  }
+
   pub fn len(&self) -> usize {
     self.rope.len_chars()
   }
@@ -34,10 +127,18 @@ impl TextBuffer {
     self.rope.len_chars() == 0
   }
 
+  /// Lines of the buffer, the empty line after a final newline included since the cursor can
+  /// go there
   pub fn line_count(&self) -> usize {
     self.rope.len_lines()
   }
 
+  /// Whether the last line ends with a newline, git marks files without one
+  pub fn ends_with_newline(&self) -> bool {
+    let len = self.rope.len_chars();
+    len > 0 && self.rope.char(len - 1) == '\n'
+  }
+
   pub fn line(&self, line_idx: usize) -> Option<String> {
     if line_idx < self.rope.len_lines() {
       Some(self.rope.line(line_idx).to_string())
@@ -68,19 +169,109 @@ impl TextBuffer {
   }
 
   pub fn from_file(path: &Path) -> io::Result<Self> {
-    let content = fs::read_to_string(path)?;
-    let mut buffer = Self::new();
-    if !content.is_empty() {
-      buffer.insert(0, &content);
+    Self::load(path, |_| {})
+  }
+
+  /// Reads a file into the rope chunk by chunk, reporting progress after each one
+  ///
+  /// Large files take a while, run it off the UI thread
+  pub fn load(path: &Path, mut on_progress: impl FnMut(LoadProgress)) -> io::Result<Self> {
+    let file = fs::File::open(path)?;
+    let total_bytes = file.metadata()?.len();
+    match Self::read_chunked(file, LOAD_CHUNK_SIZE, total_bytes, None, &mut on_progress) {
+      // Invalid UTF-8 after the head the encoding was detected from, read it again byte per char
+      Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+        let file = fs::File::open(path)?;
+        Self::read_chunked(
+          file,
+          LOAD_CHUNK_SIZE,
+          total_bytes,
+          Some(Encoding::Latin1),
+          &mut on_progress,
+        )
+      }
+      result => result,
+    }
+  }
+
+  /// Decodes `reader` with `encoding`, or the one detected from its first bytes
+  fn read_chunked(
+    mut reader: impl Read,
+    chunk_size: usize,
+    total_bytes: u64,
+    encoding: Option<Encoding>,
+    mut on_progress: impl FnMut(LoadProgress),
+  ) -> io::Result<Self> {
+    let mut head = Vec::new();
+    reader
+      .by_ref()
+      .take(ENCODING_DETECTION_LEN)
+      .read_to_end(&mut head)?;
+    let encoding = encoding.unwrap_or_else(|| Encoding::detect(&head));
+
+    let mut decoder = Decoder::new(encoding);
+    let mut builder = RopeBuilder::new();
+    let mut line_endings = LineEndingCounts::default();
+    let mut carried_cr = false; // A '\r' ending a chunk, it could start a CRLF
+    let mut append = |mut text: String, last: bool| {
+      if std::mem::take(&mut carried_cr) {
+        text.insert(0, '\r');
+      }
+      if !last && text.ends_with('\r') {
+        text.pop();
+        carried_cr = true;
+      }
+      line_endings.add(&text);
+      builder.append(&LineEnding::normalize(&text));
+    };
+
+    let mut loaded_bytes = head.len() as u64;
+    append(decoder.decode(&head, false)?, false);
+    on_progress(LoadProgress {
+      loaded_bytes,
+      total_bytes: total_bytes.max(loaded_bytes),
+    });
+
+    let mut chunk = vec![0; chunk_size];
+    loop {
+      let read = match reader.read(&mut chunk) {
+        Ok(0) => break,
+        Ok(read) => read,
+        Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+        Err(e) => return Err(e),
+      };
+      loaded_bytes += read as u64;
+      append(decoder.decode(&chunk[..read], false)?, false);
+
+      on_progress(LoadProgress {
+        loaded_bytes,
+        total_bytes: total_bytes.max(loaded_bytes),
+      });
     }
-    Ok(buffer)
+    append(decoder.decode(&[], true)?, true);
+
+    Ok(Self {
+      rope: builder.finish(),
+      line_ending: line_endings.dominant(),
+      encoding,
+      version: next_version(),
+    })
   }
 
   pub fn save_to_file(&self, path: &Path) -> io::Result<()> {
-    fs::write(path, self.as_str())
+    fs::write(
+      path,
+      self
+        .encoding
+        .encode(&self.line_ending.apply(&self.as_str())),
+    )
   }
 }
 
+fn next_version() -> u64 {
+  NEXT_VERSION.fetch_add(1, Ordering::Relaxed)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -133,6 +324,15 @@ mod tests {
     assert_eq!(buffer.as_str(), "Hel");
   }
 
+  #[test]
+  fn test_slice() {
+    let mut buffer = TextBuffer::new();
+    buffer.insert(0, "héllo\nworld");
+    assert_eq!(buffer.slice(1..8), "éllo\nwo");
+    assert_eq!(buffer.slice(8..100), "rld");
+    assert_eq!(buffer.slice(100..200), "");
+  }
+
   #[test]
   fn test_line_count() {
     let mut buffer = TextBuffer::new();
@@ -148,6 +348,34 @@ mod tests {
     assert_eq!(buffer.line_count(), 3);
   }
 
+  #[test]
+  fn test_version() {
+    let mut buffer = TextBuffer::new();
+    let other = TextBuffer::new();
+    assert_eq!(buffer.version(), other.version());
+
+    buffer.insert(0, "a");
+    let version = buffer.version();
+    assert_ne!(version, other.version());
+    assert_eq!(buffer.clone().version(), version);
+
+    buffer.delete(0, 1);
+    assert_ne!(buffer.version(), version);
+  }
+
+  #[test]
+  fn test_final_newline() {
+    let mut buffer = TextBuffer::new();
+    assert!(!buffer.ends_with_newline());
+
+    buffer.insert(0, "a\nb");
+    assert!(!buffer.ends_with_newline());
+
+    buffer.insert(3, "\n");
+    assert!(buffer.ends_with_newline());
+    assert_eq!(buffer.line_count(), 3);
+  }
+
   #[test]
   fn test_line() {
     let mut buffer = TextBuffer::new();
@@ -317,4 +545,149 @@ mod tests {
 
     std::fs::remove_file(&file_path).ok();
   }
+
+  #[test]
+  fn test_from_file_crlf_is_normalized() {
+    let temp_dir = std::env::temp_dir();
+    let file_path = temp_dir.join("test_from_file_crlf.txt");
+
+    std::fs::write(&file_path, "Line 1\r\nLine 2\r\n").unwrap();
+
+    let buffer = TextBuffer::from_file(&file_path).unwrap();
+    assert_eq!(buffer.as_str(), "Line 1\nLine 2\n");
+    assert_eq!(buffer.line_ending(), LineEnding::Crlf);
+    assert_eq!(buffer.line_count(), 3);
+
+    std::fs::remove_file(&file_path).ok();
+  }
+
+  #[test]
+  fn test_crlf_preserved_on_save() {
+    let temp_dir = std::env::temp_dir();
+    let file_path = temp_dir.join("test_crlf_roundtrip.txt");
+
+    std::fs::write(&file_path, "Line 1\r\nLine 2\r\n").unwrap();
+
+    let mut buffer = TextBuffer::from_file(&file_path).unwrap();
+    buffer.insert(buffer.len(), "Line 3\n");
+    buffer.save_to_file(&file_path).unwrap();
+
+    let new_content = std::fs::read_to_string(&file_path).unwrap();
+    assert_eq!(new_content, "Line 1\r\nLine 2\r\nLine 3\r\n");
+
+    std::fs::remove_file(&file_path).ok();
+  }
+
+  #[test]
+  fn test_mixed_line_endings_saved_with_dominant_style() {
+    let temp_dir = std::env::temp_dir();
+    let file_path = temp_dir.join("test_mixed_eol.txt");
+
+    std::fs::write(&file_path, "a\r\nb\r\nc\nd\re").unwrap();
+
+    let buffer = TextBuffer::from_file(&file_path).unwrap();
+    assert_eq!(buffer.as_str(), "a\nb\nc\nd\ne");
+    assert_eq!(buffer.line_count(), 5);
+    buffer.save_to_file(&file_path).unwrap();
+
+    let new_content = std::fs::read_to_string(&file_path).unwrap();
+    assert_eq!(new_content, "a\r\nb\r\nc\r\nd\r\ne");
+
+    std::fs::remove_file(&file_path).ok();
+  }
+
+  #[test]
+  fn test_read_chunks_split_inside_chars_and_crlf() {
+    // Read past the head used for detection, one byte at a time
+    let head = "a".repeat(ENCODING_DETECTION_LEN as usize);
+    let content = format!("{}é\r\nb€\r\n", head);
+    let mut progress = Vec::new();
+    let buffer = TextBuffer::read_chunked(
+      content.as_bytes(),
+      1,
+      content.len() as u64,
+      None,
+      |loaded| progress.push(loaded.loaded_bytes),
+    )
+    .unwrap();
+
+    assert_eq!(buffer.as_str(), format!("{}é\nb€\n", head));
+    assert_eq!(buffer.line_ending(), LineEnding::Crlf);
+    assert_eq!(buffer.encoding(), Encoding::Utf8);
+    assert_eq!(
+      progress,
+      (head.len() as u64..=content.len() as u64).collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn test_read_chunks_rejects_invalid_utf8() {
+    let result = TextBuffer::read_chunked(&b"ab\xffcd"[..], 2, 5, Some(Encoding::Utf8), |_| {});
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn test_load_progress_fraction() {
+    let progress = LoadProgress {
+      loaded_bytes: 25,
+      total_bytes: 100,
+    };
+    assert_eq!(progress.fraction(), 0.25);
+    assert_eq!(LoadProgress::default().fraction(), 1.0);
+  }
+
+  #[test]
+  fn test_encoding_preserved_on_save() {
+    let temp_dir = std::env::temp_dir();
+    for (name, bytes, encoding) in [
+      (
+        "test_utf16.txt",
+        &b"\xFF\xFEa\x00\r\x00\n\x00\xE9\x00"[..],
+        Encoding::Utf16Le,
+      ),
+      ("test_latin1.txt", &b"caf\xE9\n"[..], Encoding::Latin1),
+    ] {
+      let path = temp_dir.join(name);
+      fs::write(&path, bytes).unwrap();
+
+      let buffer = TextBuffer::from_file(&path).unwrap();
+      assert_eq!(buffer.encoding(), encoding);
+      assert!(!buffer.as_str().contains('\r'));
+      assert!(buffer.as_str().contains('é'));
+
+      buffer.save_to_file(&path).unwrap();
+      assert_eq!(fs::read(&path).unwrap(), bytes);
+      fs::remove_file(&path).ok();
+    }
+  }
+
+  #[test]
+  fn test_invalid_utf8_past_the_head_falls_back_to_latin1() {
+    let path = std::env::temp_dir().join("test_late_latin1.txt");
+    let mut bytes = vec![b'a'; ENCODING_DETECTION_LEN as usize];
+    bytes.extend_from_slice(b"\xE9");
+    fs::write(&path, &bytes).unwrap();
+
+    let buffer = TextBuffer::from_file(&path).unwrap();
+    assert_eq!(buffer.encoding(), Encoding::Latin1);
+    assert!(buffer.as_str().ends_with("aé"));
+    fs::remove_file(&path).ok();
+  }
+
+  #[test]
+  fn test_load_binary_file() {
+    let temp_dir = std::env::temp_dir();
+    let binary_path = temp_dir.join("test_binary.bin");
+    let text_path = temp_dir.join("test_not_binary.txt");
+    fs::write(&binary_path, b"\x89PNG\r\n\x1a\n\x00\x00").unwrap();
+    fs::write(&text_path, "text\n").unwrap();
+
+    let content = FileContent::load(&binary_path, |_| {}).unwrap();
+    assert!(matches!(content, FileContent::Binary(bytes) if bytes.len() == 10));
+    let content = FileContent::load(&text_path, |_| {}).unwrap();
+    assert!(matches!(content, FileContent::Text(buffer) if buffer.as_str() == "text\n"));
+
+    fs::remove_file(&binary_path).ok();
+    fs::remove_file(&text_path).ok();
+  }
 }
@@ -0,0 +1,278 @@
+use std::fmt;
+use std::io;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// Character encoding of a file
+///
+/// Buffers always hold UTF-8, the detected encoding is only applied back on save
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+  #[default]
+  Utf8,
+  Utf8Bom,
+  Utf16Le,
+  Utf16Be,
+  Latin1,
+}
+
+impl Encoding {
+  /// Detects the encoding from the first bytes of a file
+  ///
+  /// A byte order mark wins, then UTF-16 is recognized by the zero bytes of ASCII text,
+  /// then valid UTF-8. Anything else is read as Latin-1, where every byte is a char
+  pub fn detect(head: &[u8]) -> Self {
+    if head.starts_with(UTF8_BOM) {
+      return Self::Utf8Bom;
+    }
+    if head.starts_with(UTF16_LE_BOM) {
+      return Self::Utf16Le;
+    }
+    if head.starts_with(UTF16_BE_BOM) {
+      return Self::Utf16Be;
+    }
+
+    let pairs = head.len() / 2;
+    if pairs > 0 {
+      let zeros_at = |parity: usize| {
+        head
+          .iter()
+          .skip(parity)
+          .step_by(2)
+          .take(pairs)
+          .filter(|b| **b == 0)
+          .count()
+      };
+      let (even_zeros, odd_zeros) = (zeros_at(0), zeros_at(1));
+      // ASCII text in UTF-16 has a zero in every other byte
+      if odd_zeros * 10 >= pairs * 3 && even_zeros * 10 < pairs {
+        return Self::Utf16Le;
+      }
+      if even_zeros * 10 >= pairs * 3 && odd_zeros * 10 < pairs {
+        return Self::Utf16Be;
+      }
+    }
+
+    match std::str::from_utf8(head) {
+      Ok(_) => Self::Utf8,
+      // The head can end inside a char
+      Err(e) if e.error_len().is_none() => Self::Utf8,
+      Err(_) => Self::Latin1,
+    }
+  }
+
+  /// Whether the first bytes of a file look like binary data rather than text
+  ///
+  /// Like git, a zero byte means binary, unless it's part of UTF-16 text
+  pub fn is_binary(head: &[u8]) -> bool {
+    head.contains(&0)
+      && !matches!(
+        Self::detect(head),
+        Self::Utf16Le | Self::Utf16Be | Self::Utf8Bom
+      )
+  }
+
+  /// Short label for display, e.g. in a status bar
+  pub fn label(&self) -> &'static str {
+    match self {
+      Self::Utf8 => "UTF-8",
+      Self::Utf8Bom => "UTF-8 BOM",
+      Self::Utf16Le => "UTF-16 LE",
+      Self::Utf16Be => "UTF-16 BE",
+      Self::Latin1 => "Latin-1",
+    }
+  }
+
+  fn bom(&self) -> &'static [u8] {
+    match self {
+      Self::Utf8 | Self::Latin1 => &[],
+      Self::Utf8Bom => UTF8_BOM,
+      Self::Utf16Le => UTF16_LE_BOM,
+      Self::Utf16Be => UTF16_BE_BOM,
+    }
+  }
+
+  /// Bytes of a text in this encoding, byte order mark included
+  ///
+  /// Chars Latin-1 can't represent are written as '?'
+  pub fn encode(&self, text: &str) -> Vec<u8> {
+    let mut bytes = self.bom().to_vec();
+    match self {
+      Self::Utf8 | Self::Utf8Bom => bytes.extend_from_slice(text.as_bytes()),
+      Self::Utf16Le => bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes)),
+      Self::Utf16Be => bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes)),
+      Self::Latin1 => bytes.extend(text.chars().map(|c| u8::try_from(c).unwrap_or(b'?'))),
+    }
+    bytes
+  }
+}
+
+impl fmt::Display for Encoding {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.label())
+  }
+}
+
+/// Decodes a file read in chunks, keeping the bytes of a char cut by a chunk boundary for the next one
+#[derive(Debug)]
+pub(crate) struct Decoder {
+  encoding: Encoding,
+  pending: Vec<u8>,
+  skip_bom: bool,
+}
+
+impl Decoder {
+  pub fn new(encoding: Encoding) -> Self {
+    Self {
+      encoding,
+      pending: Vec::new(),
+      skip_bom: true,
+    }
+  }
+
+  /// Text of the complete chars read so far, `last` flushes what's left at the end of the file
+  ///
+  /// Invalid UTF-8 is an error, invalid UTF-16 becomes replacement chars
+  pub fn decode(&mut self, bytes: &[u8], last: bool) -> io::Result<String> {
+    self.pending.extend_from_slice(bytes);
+
+    if self.skip_bom {
+      let bom = self.encoding.bom();
+      if self.pending.len() < bom.len() && !last {
+        return Ok(String::new());
+      }
+      if self.pending.starts_with(bom) {
+        self.pending.drain(..bom.len());
+      }
+      self.skip_bom = false;
+    }
+
+    match self.encoding {
+      Encoding::Utf8 | Encoding::Utf8Bom => self.decode_utf8(last),
+      Encoding::Utf16Le => Ok(self.decode_utf16(last, u16::from_le_bytes)),
+      Encoding::Utf16Be => Ok(self.decode_utf16(last, u16::from_be_bytes)),
+      Encoding::Latin1 => Ok(self.pending.drain(..).map(char::from).collect()),
+    }
+  }
+
+  fn decode_utf8(&mut self, last: bool) -> io::Result<String> {
+    let valid_len = match std::str::from_utf8(&self.pending) {
+      Ok(text) => text.len(),
+      Err(e) if e.error_len().is_none() && !last => e.valid_up_to(),
+      Err(_) => return Err(invalid_utf8()),
+    };
+    let rest = self.pending.split_off(valid_len);
+    let bytes = std::mem::replace(&mut self.pending, rest);
+    String::from_utf8(bytes).map_err(|_| invalid_utf8())
+  }
+
+  fn decode_utf16(&mut self, last: bool, from_bytes: fn([u8; 2]) -> u16) -> String {
+    let mut units: Vec<u16> = self
+      .pending
+      .chunks_exact(2)
+      .map(|pair| from_bytes([pair[0], pair[1]]))
+      .collect();
+    let mut kept = self.pending.len() % 2;
+
+    // A high surrogate waits for its pair in the next chunk
+    if !last
+      && units
+        .last()
+        .is_some_and(|unit| (0xD800..0xDC00).contains(unit))
+    {
+      units.pop();
+      kept += 2;
+    }
+    let decoded_len = self.pending.len() - kept;
+    self.pending.drain(..decoded_len);
+
+    let mut text: String = char::decode_utf16(units)
+      .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+      .collect();
+    if last && !self.pending.is_empty() {
+      self.pending.clear();
+      text.push(char::REPLACEMENT_CHARACTER);
+    }
+    text
+  }
+}
+
+pub(crate) fn invalid_utf8() -> io::Error {
+  io::Error::new(
+    io::ErrorKind::InvalidData,
+    "stream did not contain valid UTF-8",
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn decode_bytewise(encoding: Encoding, bytes: &[u8]) -> String {
+    let mut decoder = Decoder::new(encoding);
+    let mut text = String::new();
+    for byte in bytes {
+      text.push_str(&decoder.decode(&[*byte], false).unwrap());
+    }
+    text.push_str(&decoder.decode(&[], true).unwrap());
+    text
+  }
+
+  #[test]
+  fn test_detect_bom() {
+    assert_eq!(Encoding::detect(b"\xEF\xBB\xBFabc"), Encoding::Utf8Bom);
+    assert_eq!(Encoding::detect(b"\xFF\xFEa\x00"), Encoding::Utf16Le);
+    assert_eq!(Encoding::detect(b"\xFE\xFF\x00a"), Encoding::Utf16Be);
+  }
+
+  #[test]
+  fn test_detect_without_bom() {
+    assert_eq!(Encoding::detect("café".as_bytes()), Encoding::Utf8);
+    assert_eq!(Encoding::detect(b""), Encoding::Utf8);
+    // Cut inside "é"
+    assert_eq!(Encoding::detect(&"café".as_bytes()[..4]), Encoding::Utf8);
+    assert_eq!(Encoding::detect(b"caf\xE9 ok"), Encoding::Latin1);
+    assert_eq!(Encoding::detect(b"a\x00b\x00c\x00"), Encoding::Utf16Le);
+    assert_eq!(Encoding::detect(b"\x00a\x00b\x00c"), Encoding::Utf16Be);
+  }
+
+  #[test]
+  fn test_is_binary() {
+    assert!(Encoding::is_binary(b"\x7FELF\x02\x01\x00\x00\x00\x00"));
+    assert!(!Encoding::is_binary(b"plain text\n"));
+    assert!(!Encoding::is_binary(&Encoding::Utf16Le.encode("text")));
+    assert!(!Encoding::is_binary(b"a\x00b\x00"));
+  }
+
+  #[test]
+  fn test_encode_round_trip() {
+    let text = "né 🦀\n";
+    for encoding in [
+      Encoding::Utf8,
+      Encoding::Utf8Bom,
+      Encoding::Utf16Le,
+      Encoding::Utf16Be,
+    ] {
+      let bytes = encoding.encode(text);
+      assert_eq!(Encoding::detect(&bytes), encoding);
+      assert_eq!(decode_bytewise(encoding, &bytes), text);
+    }
+
+    assert_eq!(Encoding::Latin1.encode("né€"), b"n\xE9?");
+    assert_eq!(decode_bytewise(Encoding::Latin1, b"n\xE9"), "né");
+  }
+
+  #[test]
+  fn test_decode_invalid_input() {
+    let mut decoder = Decoder::new(Encoding::Utf8);
+    assert!(decoder.decode(b"a\xFFb", false).is_err());
+
+    // Unpaired surrogate and odd trailing byte
+    assert_eq!(
+      decode_bytewise(Encoding::Utf16Le, b"\x00\xD8a\x00b"),
+      "\u{FFFD}a\u{FFFD}"
+    );
+  }
+}
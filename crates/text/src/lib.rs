@@ -1,2 +1,6 @@
 mod buffer;
-pub use buffer::TextBuffer;
+mod encoding;
+mod line_ending;
+pub use buffer::{FileContent, LoadProgress, TextBuffer};
+pub use encoding::Encoding;
+pub use line_ending::LineEnding;
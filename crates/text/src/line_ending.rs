@@ -0,0 +1,148 @@
+use std::fmt;
+
+/// Line ending style of a file
+///
+/// Buffers always store '\n' internally, the detected style is only applied back on save
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+  #[default]
+  Lf,
+  Crlf,
+  Cr,
+}
+
+impl LineEnding {
+  /// Detects the dominant line ending of a text, defaults to LF when there is no line break
+  ///
+  /// Mixed files resolve to the most frequent style, ties prefer LF then CRLF
+  pub fn detect(text: &str) -> Self {
+    let mut counts = LineEndingCounts::default();
+    counts.add(text);
+    counts.dominant()
+  }
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Lf => "\n",
+      Self::Crlf => "\r\n",
+      Self::Cr => "\r",
+    }
+  }
+
+  /// Short label for display, e.g. in a status bar
+  pub fn label(&self) -> &'static str {
+    match self {
+      Self::Lf => "LF",
+      Self::Crlf => "CRLF",
+      Self::Cr => "CR",
+    }
+  }
+
+  /// Converts every line break (LF, CRLF or CR) to '\n'
+  pub fn normalize(text: &str) -> String {
+    if !text.contains('\r') {
+      return text.to_string();
+    }
+    text.replace("\r\n", "\n").replace('\r', "\n")
+  }
+
+  /// Converts '\n' line breaks of a normalized text to this line ending
+  pub fn apply(&self, text: &str) -> String {
+    match self {
+      Self::Lf => text.to_string(),
+      _ => text.replace('\n', self.as_str()),
+    }
+  }
+}
+
+/// Line breaks of each style, counted over a text read in several chunks
+///
+/// A chunk must not end between the '\r' and '\n' of a CRLF
+#[derive(Debug, Default)]
+pub(crate) struct LineEndingCounts {
+  lf: usize,
+  crlf: usize,
+  cr: usize,
+}
+
+impl LineEndingCounts {
+  pub fn add(&mut self, text: &str) {
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+      match c {
+        '\r' if chars.peek() == Some(&'\n') => {
+          chars.next();
+          self.crlf += 1;
+        }
+        '\r' => self.cr += 1,
+        '\n' => self.lf += 1,
+        _ => {}
+      }
+    }
+  }
+
+  /// Most frequent style, ties prefer LF then CRLF
+  pub fn dominant(&self) -> LineEnding {
+    if self.crlf > self.lf && self.crlf >= self.cr {
+      LineEnding::Crlf
+    } else if self.cr > self.lf && self.cr > self.crlf {
+      LineEnding::Cr
+    } else {
+      LineEnding::Lf
+    }
+  }
+}
+
+impl fmt::Display for LineEnding {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.label())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_detect() {
+    assert_eq!(LineEnding::detect("a\nb\n"), LineEnding::Lf);
+    assert_eq!(LineEnding::detect("a\r\nb\r\n"), LineEnding::Crlf);
+    assert_eq!(LineEnding::detect("a\rb\r"), LineEnding::Cr);
+  }
+
+  #[test]
+  fn test_detect_without_line_break_defaults_to_lf() {
+    assert_eq!(LineEnding::detect(""), LineEnding::Lf);
+    assert_eq!(LineEnding::detect("single line"), LineEnding::Lf);
+  }
+
+  #[test]
+  fn test_detect_mixed_uses_dominant_style() {
+    assert_eq!(LineEnding::detect("a\r\nb\r\nc\n"), LineEnding::Crlf);
+    assert_eq!(LineEnding::detect("a\nb\nc\r\n"), LineEnding::Lf);
+    assert_eq!(LineEnding::detect("a\nb\r\n"), LineEnding::Lf);
+  }
+
+  #[test]
+  fn test_normalize() {
+    assert_eq!(LineEnding::normalize("a\r\nb\rc\nd"), "a\nb\nc\nd");
+    assert_eq!(LineEnding::normalize("a\nb\n"), "a\nb\n");
+    assert_eq!(LineEnding::normalize("\r\n\r\n"), "\n\n");
+  }
+
+  #[test]
+  fn test_apply_roundtrip() {
+    let original = "a\r\nb\r\n";
+    let normalized = LineEnding::normalize(original);
+    assert_eq!(LineEnding::Crlf.apply(&normalized), original);
+    assert_eq!(LineEnding::Cr.apply("a\nb"), "a\rb");
+    assert_eq!(LineEnding::Lf.apply("a\nb"), "a\nb");
+  }
+
+  #[test]
+  fn test_label() {
+    assert_eq!(LineEnding::Crlf.to_string(), "CRLF");
+    assert_eq!(LineEnding::Lf.label(), "LF");
+  }
+}